@@ -6,28 +6,62 @@
  * - CRUD operations (Create, Read, Update, Delete)
  * - Query builder with method chaining
  * - Type-safe query construction
+ * - Parameterized SQL: values are bound, never spliced into the query text
+ * - Raw SQL with typed row mapping
+ * - Transactions with nested savepoints
+ * - Connection pooling for multithreaded use
+ * - Optimistic locking through a version column
+ * - Schema introspection, and blob and datetime values
+ * - An async API (`AsyncDatabase`, `AsyncRepository`) behind the `async` feature
+ * - Soft deletes and named query scopes
  * - Migration support
  * 
  * # Dependencies
- * This requires rusqlite. To run:
+ * Plain `rustc database_orm.rs` builds an in-memory mock backend using the
+ * standard library only. For real SQLite, build as a Cargo project with a
+ * `sqlite` feature:
  * ```bash
- * # Create a Cargo project:
  * cargo new database_orm --bin
  * # Add to Cargo.toml:
  * # [dependencies]
- * # rusqlite = { version = "0.30", features = ["bundled"] }
- * 
- * # Or compile standalone (requires sqlite3-dev):
- * rustc database_orm.rs -L /usr/lib -l sqlite3 -o database_orm
- * ./database_orm
+ * # rusqlite = { version = "0.30", features = ["bundled"], optional = true }
+ * # [features]
+ * # sqlite = ["dep:rusqlite"]
+ * cargo run --features sqlite
+ * cargo test --features sqlite
+ * ```
+ *
+ * `Repository`, `QueryBuilder` and `Model` are the same on either backend;
+ * `Database::open_in_memory()` gives a throwaway database for tests.
+ *
+ * The `derive` feature adds `#[derive(Model)]` from the proc-macro crate in
+ * `database_orm_derive/`:
+ * ```toml
+ * [dependencies]
+ * database_orm_derive = { path = "database_orm_derive", optional = true }
+ * [features]
+ * derive = ["dep:database_orm_derive"]
+ * ```
+ *
+ * The `async` feature adds `AsyncDatabase` and `AsyncRepository` for tokio
+ * code, on either backend:
+ * ```toml
+ * [dependencies]
+ * tokio = { version = "1", features = ["rt", "sync", "macros"], optional = true }
+ * [features]
+ * async = ["dep:tokio"]
  * ```
- * 
- * Note: This demo version uses standard library only for demonstration.
- * For production, use the rusqlite crate.
  */
 
 use std::collections::HashMap;
 use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "derive")]
+use database_orm_derive::Model;
 
 // ============================================================================
 // Error Handling
@@ -39,6 +73,10 @@ pub enum DbError {
     QueryError(String),
     NotFound,
     ValidationError(String),
+    /// No pooled connection came free in time
+    PoolTimeout(Duration),
+    /// Another writer changed the row since it was read
+    Conflict(String),
 }
 
 impl fmt::Display for DbError {
@@ -48,6 +86,10 @@ impl fmt::Display for DbError {
             DbError::QueryError(msg) => write!(f, "Query error: {}", msg),
             DbError::NotFound => write!(f, "Record not found"),
             DbError::ValidationError(msg) => write!(f, "Validation error: {}", msg),
+            DbError::PoolTimeout(waited) => {
+                write!(f, "No pooled connection free after {:?}", waited)
+            }
+            DbError::Conflict(msg) => write!(f, "Conflict: {}", msg),
         }
     }
 }
@@ -65,6 +107,9 @@ pub enum Value {
     Real(f64),
     Text(String),
     Boolean(bool),
+    Blob(Vec<u8>),
+    /// Stored in SQLite as text in its own datetime format, UTC
+    DateTime(SystemTime),
 }
 
 impl fmt::Display for Value {
@@ -75,54 +120,849 @@ impl fmt::Display for Value {
             Value::Real(r) => write!(f, "{}", r),
             Value::Text(s) => write!(f, "'{}'", s.replace("'", "''")),
             Value::Boolean(b) => write!(f, "{}", if *b { 1 } else { 0 }),
+            Value::Blob(bytes) => {
+                write!(f, "X'")?;
+                for byte in bytes {
+                    write!(f, "{:02X}", byte)?;
+                }
+                write!(f, "'")
+            }
+            Value::DateTime(time) => write!(f, "'{}'", format_datetime(*time)),
+        }
+    }
+}
+
+impl Value {
+    pub fn as_blob(&self) -> Option<&[u8]> {
+        match self {
+            Value::Blob(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// A `DateTime`, or what SQLite hands back for one: text in its datetime
+    /// format, or an integer of Unix seconds
+    pub fn as_datetime(&self) -> Option<SystemTime> {
+        match self {
+            Value::DateTime(time) => Some(*time),
+            Value::Text(text) => parse_datetime(text),
+            Value::Integer(secs) => Some(from_unix_millis(secs.checked_mul(1000)?)),
+            _ => None,
+        }
+    }
+}
+
+impl From<Vec<u8>> for Value {
+    fn from(bytes: Vec<u8>) -> Self {
+        Value::Blob(bytes)
+    }
+}
+
+impl From<SystemTime> for Value {
+    fn from(time: SystemTime) -> Self {
+        Value::DateTime(time)
+    }
+}
+
+fn unix_millis(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(after) => after.as_millis() as i64,
+        Err(before) => -(before.duration().as_millis() as i64),
+    }
+}
+
+fn from_unix_millis(millis: i64) -> SystemTime {
+    let offset = Duration::from_millis(millis.unsigned_abs());
+    if millis >= 0 {
+        UNIX_EPOCH + offset
+    } else {
+        UNIX_EPOCH - offset
+    }
+}
+
+/// `YYYY-MM-DD HH:MM:SS.SSS` in UTC, the format SQLite's date functions use.
+/// Precision stops at the millisecond.
+pub fn format_datetime(time: SystemTime) -> String {
+    let millis = unix_millis(time);
+    let (year, month, day) = civil_from_days(millis.div_euclid(86_400_000));
+    let of_day = millis.rem_euclid(86_400_000);
+    let secs = of_day / 1000;
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:03}",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        of_day % 1000
+    )
+}
+
+/// Reads `YYYY-MM-DD[ HH:MM[:SS[.SSS]]]` as UTC, with a space or `T`
+/// before the time and an optional trailing `Z`
+pub fn parse_datetime(text: &str) -> Option<SystemTime> {
+    let (date, time) = match text.find([' ', 'T']) {
+        Some(at) => (&text[..at], text[at + 1..].trim_end_matches('Z')),
+        None => (text, "00:00"),
+    };
+
+    let mut date = date.splitn(3, '-');
+    let year: i64 = date.next()?.parse().ok()?;
+    let month: u32 = date.next()?.parse().ok()?;
+    let day: u32 = date.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    let mut time = time.splitn(3, ':');
+    let hour: i64 = time.next()?.parse().ok()?;
+    let minute: i64 = time.next()?.parse().ok()?;
+    let (second, millis): (i64, i64) = match time.next() {
+        None => (0, 0),
+        Some(seconds) => {
+            let (whole, fraction) = seconds.split_once('.').unwrap_or((seconds, ""));
+            let fraction = format!("{:0<3}", fraction);
+            (whole.parse().ok()?, fraction[..3].parse().ok()?)
+        }
+    };
+    if hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+
+    let secs = days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + second;
+    Some(from_unix_millis(secs * 1000 + millis))
+}
+
+// Conversions between days since 1970-01-01 and proleptic Gregorian dates,
+// after Howard Hinnant's `days_from_civil`/`civil_from_days`
+
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let shifted_month = (i64::from(month) + 9) % 12;
+    let day_of_year = (153 * shifted_month + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 } as u32;
+    (year_of_era + era * 400 + i64::from(month <= 2), month, day)
+}
+
+#[cfg(feature = "sqlite")]
+impl rusqlite::ToSql for Value {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        use rusqlite::types::{ToSqlOutput, ValueRef};
+
+        Ok(ToSqlOutput::Borrowed(match self {
+            Value::Null => ValueRef::Null,
+            Value::Integer(i) => ValueRef::Integer(*i),
+            Value::Real(r) => ValueRef::Real(*r),
+            Value::Text(s) => ValueRef::Text(s.as_bytes()),
+            // SQLite has no boolean type; it stores 0/1
+            Value::Boolean(b) => ValueRef::Integer(*b as i64),
+            Value::Blob(bytes) => ValueRef::Blob(bytes),
+            // Nor a datetime type: text sorts and compares correctly, and
+            // works with its date functions
+            Value::DateTime(time) => {
+                return Ok(ToSqlOutput::Owned(rusqlite::types::Value::Text(format_datetime(
+                    *time,
+                ))))
+            }
+        }))
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl rusqlite::types::FromSql for Value {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        use rusqlite::types::{FromSqlError, ValueRef};
+
+        match value {
+            ValueRef::Null => Ok(Value::Null),
+            ValueRef::Integer(i) => Ok(Value::Integer(i)),
+            ValueRef::Real(r) => Ok(Value::Real(r)),
+            ValueRef::Text(bytes) => String::from_utf8(bytes.to_vec())
+                .map(Value::Text)
+                .map_err(|e| FromSqlError::Other(Box::new(e))),
+            ValueRef::Blob(bytes) => Ok(Value::Blob(bytes.to_vec())),
         }
     }
 }
 
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for DbError {
+    fn from(e: rusqlite::Error) -> Self {
+        DbError::QueryError(e.to_string())
+    }
+}
+
 // ============================================================================
-// Database Connection (Mock)
+// Database Connection
 // ============================================================================
 
+/// A database handle: SQLite through rusqlite with the `sqlite` feature,
+/// otherwise an in-memory mock that only understands enough SQL for the demo
 pub struct Database {
     path: String,
-    tables: HashMap<String, Vec<HashMap<String, Value>>>,
+    /// How many `transaction` calls are open
+    tx_depth: usize,
+    #[cfg(not(feature = "sqlite"))]
+    tables: Tables,
+    /// Columns of each table, as declared by CREATE TABLE
+    #[cfg(not(feature = "sqlite"))]
+    schemas: Schemas,
+    /// Tables and schemas at each open BEGIN/SAVEPOINT, restored on rollback
+    #[cfg(not(feature = "sqlite"))]
+    snapshots: Vec<(Tables, Schemas)>,
+    #[cfg(feature = "sqlite")]
+    conn: rusqlite::Connection,
+}
+
+/// One column of a live table, from `Database::columns`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnInfo {
+    pub name: String,
+    /// The declared type, as written in CREATE TABLE
+    pub sql_type: String,
+    pub nullable: bool,
+    pub primary_key: bool,
+}
+
+impl Database {
+    /// A private database that disappears when dropped
+    pub fn open_in_memory() -> Result<Self> {
+        Self::new(":memory:")
+    }
+
+    /// Run a raw SELECT with `?` placeholders bound to `params` and convert
+    /// each row into `T` through its `Model` implementation.
+    pub fn query_as<T: Model>(&self, sql: &str, params: &[Value]) -> Result<Vec<T>> {
+        self.query(sql, params)?
+            .iter()
+            .map(|row| T::from_row(row))
+            .collect()
+    }
+
+    /// Run a built SELECT
+    pub fn fetch(&self, query: &Query) -> Result<Vec<HashMap<String, Value>>> {
+        self.query(&query.sql, &query.params)
+    }
+
+    /// Runs `f` in a transaction: committed if it returns `Ok`, rolled back
+    /// if it returns `Err` or panics (the panic then carries on). `f` gets
+    /// this same database, so repositories built on it take part. Calls
+    /// inside `f` nest through savepoints: an inner rollback undoes only the
+    /// inner writes.
+    pub fn transaction<T>(&mut self, f: impl FnOnce(&mut Database) -> Result<T>) -> Result<T> {
+        let savepoint = format!("sp_{}", self.tx_depth);
+        let (begin, commit, rollback) = if self.tx_depth == 0 {
+            ("BEGIN".to_string(), "COMMIT".to_string(), "ROLLBACK".to_string())
+        } else {
+            (
+                format!("SAVEPOINT {}", savepoint),
+                format!("RELEASE {}", savepoint),
+                format!("ROLLBACK TO {}", savepoint),
+            )
+        };
+
+        self.execute(&begin, &[])?;
+        self.tx_depth += 1;
+        let outcome = panic::catch_unwind(AssertUnwindSafe(|| f(&mut *self)));
+        self.tx_depth -= 1;
+
+        match outcome {
+            Ok(Ok(value)) => {
+                self.execute(&commit, &[])?;
+                Ok(value)
+            }
+            Ok(Err(e)) => {
+                self.undo(&rollback, &commit);
+                Err(e)
+            }
+            Err(panic) => {
+                self.undo(&rollback, &commit);
+                panic::resume_unwind(panic)
+            }
+        }
+    }
+
+    /// Rolls back; a savepoint is then released too, since ROLLBACK TO
+    /// leaves it open. Failures are reported but lose to the original error.
+    fn undo(&mut self, rollback: &str, release: &str) {
+        let mut result = self.execute(rollback, &[]);
+        if self.tx_depth > 0 && result.is_ok() {
+            result = self.execute(release, &[]);
+        }
+        if let Err(e) = result {
+            println!("⚠️  Rollback failed: {}", e);
+        }
+    }
 }
 
+fn log_sql(label: &str, sql: &str, params: &[Value]) {
+    if params.is_empty() {
+        println!("{} {}", label, sql);
+    } else {
+        println!("{} {}  {:?}", label, sql, params);
+    }
+}
+
+#[cfg(feature = "sqlite")]
 impl Database {
     pub fn new(path: &str) -> Result<Self> {
         println!("📁 Opening database: {}", path);
+        let conn = rusqlite::Connection::open(path)
+            .map_err(|e| DbError::ConnectionError(e.to_string()))?;
         Ok(Database {
             path: path.to_string(),
-            tables: HashMap::new(),
+            tx_depth: 0,
+            conn,
         })
     }
 
-    pub fn execute(&mut self, sql: &str) -> Result<usize> {
-        println!("🔧 Executing: {}", sql);
-        
-        // Simple DDL parsing for demo
-        if sql.trim().to_uppercase().starts_with("CREATE TABLE") {
-            let table_name = self.extract_table_name(sql)?;
-            self.tables.insert(table_name, Vec::new());
-            Ok(0)
+    /// Run SQL with `?` placeholders bound to `params`, returning the
+    /// affected row count
+    pub fn execute(&mut self, sql: &str, params: &[Value]) -> Result<usize> {
+        log_sql("🔧 Executing:", sql, params);
+        let mut stmt = self.conn.prepare_cached(sql)?;
+        Ok(stmt.execute(rusqlite::params_from_iter(params))?)
+    }
+
+    /// Run a SELECT with `?` placeholders bound to `params`
+    pub fn query(&self, sql: &str, params: &[Value]) -> Result<Vec<HashMap<String, Value>>> {
+        log_sql("🔍 Querying:", sql, params);
+
+        let mut stmt = self.conn.prepare_cached(sql)?;
+        let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+        let rows = stmt.query_map(rusqlite::params_from_iter(params), |row| {
+            columns
+                .iter()
+                .enumerate()
+                .map(|(i, name)| Ok((name.clone(), row.get(i)?)))
+                .collect()
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Inserts `row` and returns its rowid
+    pub fn insert(&mut self, table: &str, row: HashMap<String, Value>) -> Result<usize> {
+        println!("➕ Inserting into {}: {:?}", table, row);
+
+        let (columns, values): (Vec<String>, Vec<Value>) = row.into_iter().unzip();
+        let sql = format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            table,
+            columns.join(", "),
+            vec!["?"; columns.len()].join(", ")
+        );
+        self.conn.prepare_cached(&sql)?.execute(rusqlite::params_from_iter(&values))?;
+        Ok(self.conn.last_insert_rowid() as usize)
+    }
+
+    /// Fit to hand out again: answers a query and has no transaction left open
+    fn is_healthy(&self) -> bool {
+        self.conn.is_autocommit() && self.conn.query_row("SELECT 1", [], |_| Ok(())).is_ok()
+    }
+
+    /// Names of the tables in the database, sorted
+    pub fn tables(&self) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT name FROM sqlite_master \
+             WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name",
+        )?;
+        let names = stmt.query_map([], |row| row.get(0))?;
+        Ok(names.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// The columns of `table`, in declaration order
+    pub fn columns(&self, table: &str) -> Result<Vec<ColumnInfo>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT name, type, \"notnull\", pk FROM pragma_table_info(?) ORDER BY cid",
+        )?;
+        let columns = stmt
+            .query_map([table], |row| {
+                Ok(ColumnInfo {
+                    name: row.get(0)?,
+                    sql_type: row.get(1)?,
+                    nullable: !row.get::<_, bool>(2)?,
+                    primary_key: row.get::<_, i64>(3)? > 0,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        if columns.is_empty() {
+            return Err(DbError::QueryError(format!("No such table: {}", table)));
+        }
+        Ok(columns)
+    }
+}
+
+#[cfg(not(feature = "sqlite"))]
+type Row = HashMap<String, Value>;
+
+#[cfg(not(feature = "sqlite"))]
+type Tables = HashMap<String, Vec<Row>>;
+
+#[cfg(not(feature = "sqlite"))]
+type Schemas = HashMap<String, Vec<ColumnInfo>>;
+
+/// Splits SQL into words, operators, `?` and quoted strings, with `(`, `)`
+/// and `,` as tokens of their own
+#[cfg(not(feature = "sqlite"))]
+fn sql_tokens(sql: &str) -> Vec<String> {
+    fn flush(tokens: &mut Vec<String>, current: &mut String) {
+        if !current.is_empty() {
+            tokens.push(std::mem::take(current));
+        }
+    }
+
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut chars = sql.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                // A quoted string, with '' for a quote inside it
+                current.push(c);
+                while let Some(c) = chars.next() {
+                    current.push(c);
+                    if c == '\'' {
+                        match chars.next_if_eq(&'\'') {
+                            Some(quote) => current.push(quote),
+                            None => break,
+                        }
+                    }
+                }
+            }
+            '(' | ')' | ',' => {
+                flush(&mut tokens, &mut current);
+                tokens.push(c.to_string());
+            }
+            ';' => flush(&mut tokens, &mut current),
+            c if c.is_whitespace() => flush(&mut tokens, &mut current),
+            _ => current.push(c),
+        }
+    }
+    flush(&mut tokens, &mut current);
+    tokens
+}
+
+/// A parsed WHERE clause
+#[cfg(not(feature = "sqlite"))]
+enum Predicate {
+    Compare { column: String, op: String, value: Value },
+    In { column: String, values: Vec<Value> },
+    Like { column: String, pattern: String },
+    IsNull { column: String, negated: bool },
+    All(Vec<Predicate>),
+    Any(Vec<Predicate>),
+}
+
+#[cfg(not(feature = "sqlite"))]
+impl Predicate {
+    fn matches(&self, row: &Row) -> bool {
+        let get = |column: &str| row.get(column).unwrap_or(&Value::Null);
+        match self {
+            Predicate::Compare { column, op, value } => {
+                match (op.as_str(), compare_values(get(column), value)) {
+                    (_, None) => false,
+                    ("=", Some(o)) => o.is_eq(),
+                    ("!=" | "<>", Some(o)) => o.is_ne(),
+                    ("<", Some(o)) => o.is_lt(),
+                    ("<=", Some(o)) => o.is_le(),
+                    (">", Some(o)) => o.is_gt(),
+                    (">=", Some(o)) => o.is_ge(),
+                    _ => false,
+                }
+            }
+            Predicate::In { column, values } => values
+                .iter()
+                .any(|v| compare_values(get(column), v).is_some_and(|o| o.is_eq())),
+            Predicate::Like { column, pattern } => {
+                matches!(get(column), Value::Text(text) if like(pattern, text))
+            }
+            Predicate::IsNull { column, negated } => (*get(column) == Value::Null) != *negated,
+            Predicate::All(predicates) => predicates.iter().all(|p| p.matches(row)),
+            Predicate::Any(predicates) => predicates.iter().any(|p| p.matches(row)),
+        }
+    }
+}
+
+/// SQL-style comparison: NULL compares with nothing
+#[cfg(not(feature = "sqlite"))]
+fn compare_values(a: &Value, b: &Value) -> Option<std::cmp::Ordering> {
+    let number = |v: &Value| match v {
+        Value::Integer(i) => Some(*i as f64),
+        Value::Real(r) => Some(*r),
+        Value::Boolean(b) => Some(*b as i64 as f64),
+        _ => None,
+    };
+    match (a, b) {
+        (Value::Integer(x), Value::Integer(y)) => Some(x.cmp(y)),
+        (Value::Text(x), Value::Text(y)) => Some(x.cmp(y)),
+        (Value::Blob(x), Value::Blob(y)) => Some(x.cmp(y)),
+        (Value::DateTime(x), Value::DateTime(y)) => Some(x.cmp(y)),
+        _ => number(a)?.partial_cmp(&number(b)?),
+    }
+}
+
+/// SQL LIKE: `%` is any run of characters, `_` exactly one, and ASCII
+/// letters match either case, as in SQLite
+#[cfg(not(feature = "sqlite"))]
+fn like(pattern: &str, text: &str) -> bool {
+    fn matches(pattern: &[char], text: &[char]) -> bool {
+        match pattern.split_first() {
+            None => text.is_empty(),
+            Some(('%', rest)) => (0..=text.len()).any(|i| matches(rest, &text[i..])),
+            Some(('_', rest)) => !text.is_empty() && matches(rest, &text[1..]),
+            Some((c, rest)) => {
+                text.first().is_some_and(|t| t.eq_ignore_ascii_case(c)) && matches(rest, &text[1..])
+            }
+        }
+    }
+
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    matches(&pattern, &text)
+}
+
+/// Position of a keyword among the tokens, ignoring case
+#[cfg(not(feature = "sqlite"))]
+fn keyword(tokens: &[&str], word: &str) -> Option<usize> {
+    tokens.iter().position(|t| t.eq_ignore_ascii_case(word))
+}
+
+/// A `?` takes the next parameter; anything else must be a number or a
+/// quoted string
+#[cfg(not(feature = "sqlite"))]
+fn operand(token: &str, params: &mut std::slice::Iter<'_, Value>) -> Result<Value> {
+    if token == "?" {
+        return params
+            .next()
+            .cloned()
+            .ok_or_else(|| DbError::QueryError("Not enough parameters".to_string()));
+    }
+    if let Some(text) = token.strip_prefix('\'').and_then(|t| t.strip_suffix('\'')) {
+        return Ok(Value::Text(text.replace("''", "'")));
+    }
+    token
+        .parse::<i64>()
+        .map(Value::Integer)
+        .or_else(|_| token.parse::<f64>().map(Value::Real))
+        .map_err(|_| DbError::QueryError(format!("Mock backend can't read value `{}`", token)))
+}
+
+/// Reads the predicates `QueryBuilder` writes: comparisons, IN, LIKE and
+/// IS [NOT] NULL, combined with AND and OR (AND binding tighter) and
+/// grouped with parentheses
+#[cfg(not(feature = "sqlite"))]
+struct PredicateParser<'a, 'p> {
+    tokens: &'a [&'a str],
+    pos: usize,
+    params: &'a mut std::slice::Iter<'p, Value>,
+}
+
+#[cfg(not(feature = "sqlite"))]
+impl<'a> PredicateParser<'a, '_> {
+    fn parse(
+        tokens: &'a [&'a str],
+        params: &'a mut std::slice::Iter<'_, Value>,
+    ) -> Result<Predicate> {
+        let mut parser = PredicateParser { tokens, pos: 0, params };
+        let predicate = parser.any()?;
+        match parser.peek() {
+            None => Ok(predicate),
+            Some(token) => Err(DbError::QueryError(format!(
+                "Mock backend can't read WHERE clause at `{}`",
+                token
+            ))),
+        }
+    }
+
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Result<&'a str> {
+        let token = self
+            .peek()
+            .ok_or_else(|| DbError::QueryError("WHERE clause ends early".to_string()))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn eat(&mut self, word: &str) -> bool {
+        let found = self.peek().is_some_and(|t| t.eq_ignore_ascii_case(word));
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, word: &str) -> Result<()> {
+        if self.eat(word) {
+            Ok(())
         } else {
-            Ok(1)
+            Err(DbError::QueryError(format!("Expected `{}` in WHERE clause", word)))
         }
     }
 
-    pub fn query(&self, sql: &str) -> Result<Vec<HashMap<String, Value>>> {
-        println!("🔍 Querying: {}", sql);
-        
-        // Extract table name from SELECT query
-        let parts: Vec<&str> = sql.split_whitespace().collect();
-        if let Some(from_idx) = parts.iter().position(|&x| x.to_uppercase() == "FROM") {
-            if let Some(table_name) = parts.get(from_idx + 1) {
-                let table_name = table_name.trim_end_matches(';');
-                return Ok(self.tables.get(table_name).cloned().unwrap_or_default());
+    fn any(&mut self) -> Result<Predicate> {
+        let mut terms = vec![self.all()?];
+        while self.eat("OR") {
+            terms.push(self.all()?);
+        }
+        Ok(if terms.len() == 1 { terms.remove(0) } else { Predicate::Any(terms) })
+    }
+
+    fn all(&mut self) -> Result<Predicate> {
+        let mut terms = vec![self.atom()?];
+        while self.eat("AND") {
+            terms.push(self.atom()?);
+        }
+        Ok(if terms.len() == 1 { terms.remove(0) } else { Predicate::All(terms) })
+    }
+
+    fn atom(&mut self) -> Result<Predicate> {
+        if self.eat("(") {
+            let predicate = self.any()?;
+            self.expect(")")?;
+            return Ok(predicate);
+        }
+
+        let column = self.next()?.to_string();
+        if self.eat("IS") {
+            let negated = self.eat("NOT");
+            self.expect("NULL")?;
+            return Ok(Predicate::IsNull { column, negated });
+        }
+        if self.eat("IN") {
+            self.expect("(")?;
+            let mut values = Vec::new();
+            loop {
+                values.push(operand(self.next()?, self.params)?);
+                if self.eat(")") {
+                    break;
+                }
+                self.expect(",")?;
             }
+            return Ok(Predicate::In { column, values });
         }
-        
-        Ok(Vec::new())
+        if self.eat("LIKE") {
+            return match operand(self.next()?, self.params)? {
+                Value::Text(pattern) => Ok(Predicate::Like { column, pattern }),
+                other => Err(DbError::QueryError(format!("LIKE needs text, got {}", other))),
+            };
+        }
+
+        let op = self.next()?.to_string();
+        if !matches!(op.as_str(), "=" | "!=" | "<>" | "<" | "<=" | ">" | ">=") {
+            return Err(DbError::QueryError(format!("Mock backend can't compare with `{}`", op)));
+        }
+        let value = operand(self.next()?, self.params)?;
+        Ok(Predicate::Compare { column, op, value })
+    }
+}
+
+#[cfg(not(feature = "sqlite"))]
+impl Database {
+    pub fn new(path: &str) -> Result<Self> {
+        println!("📁 Opening database: {}", path);
+        Ok(Database {
+            path: path.to_string(),
+            tx_depth: 0,
+            tables: HashMap::new(),
+            schemas: HashMap::new(),
+            snapshots: Vec::new(),
+        })
+    }
+
+    /// Run SQL with `?` placeholders bound to `params`, returning the
+    /// affected row count
+    pub fn execute(&mut self, sql: &str, params: &[Value]) -> Result<usize> {
+        log_sql("🔧 Executing:", sql, params);
+        // Checks the placeholder count, as a real driver would
+        bind_params(sql, params)?;
+
+        let owned = sql_tokens(sql);
+        let tokens: Vec<&str> = owned.iter().map(String::as_str).collect();
+        let mut params = params.iter();
+        let first = tokens.first().copied().unwrap_or("").to_uppercase();
+
+        match first.as_str() {
+            // Transactions keep a snapshot per level; savepoint names are
+            // ignored since `transaction` always unwinds them in order
+            "BEGIN" | "SAVEPOINT" => {
+                self.snapshots.push((self.tables.clone(), self.schemas.clone()));
+                Ok(0)
+            }
+            "COMMIT" | "RELEASE" => {
+                self.snapshots.pop();
+                Ok(0)
+            }
+            "ROLLBACK" => {
+                let snapshot = if keyword(&tokens, "TO").is_some() {
+                    self.snapshots.last().cloned()
+                } else {
+                    self.snapshots.drain(..).next()
+                };
+                (self.tables, self.schemas) = snapshot
+                    .ok_or_else(|| DbError::QueryError("No transaction to roll back".to_string()))?;
+                Ok(0)
+            }
+            // Simple DDL parsing for demo
+            "CREATE" => {
+                let table_name = self.extract_table_name(sql)?;
+                self.schemas.insert(table_name.clone(), column_definitions(&tokens));
+                self.tables.insert(table_name, Vec::new());
+                Ok(0)
+            }
+            // UPDATE t SET a = ?, b = ? [WHERE ...]
+            "UPDATE" => {
+                let set = keyword(&tokens, "SET")
+                    .ok_or_else(|| DbError::QueryError("UPDATE without SET".to_string()))?;
+                let where_at = keyword(&tokens, "WHERE").unwrap_or(tokens.len());
+
+                let mut assignments = Vec::new();
+                for assignment in tokens[set + 1..where_at].split(|t| *t == ",") {
+                    match assignment {
+                        [column, "=", value] => {
+                            assignments.push((column.to_string(), operand(value, &mut params)?))
+                        }
+                        _ => {
+                            return Err(DbError::QueryError(format!(
+                                "Bad assignment `{}`",
+                                assignment.join(" ")
+                            )))
+                        }
+                    }
+                }
+                let predicate = if where_at < tokens.len() {
+                    PredicateParser::parse(&tokens[where_at + 1..], &mut params)?
+                } else {
+                    Predicate::All(Vec::new())
+                };
+
+                let mut changed = 0;
+                for row in self.table_mut(tokens.get(1).copied())? {
+                    if predicate.matches(row) {
+                        for (column, value) in &assignments {
+                            row.insert(column.clone(), value.clone());
+                        }
+                        changed += 1;
+                    }
+                }
+                Ok(changed)
+            }
+            // DELETE FROM t [WHERE ...]
+            "DELETE" => {
+                let predicate = match keyword(&tokens, "WHERE") {
+                    Some(at) => PredicateParser::parse(&tokens[at + 1..], &mut params)?,
+                    None => Predicate::All(Vec::new()),
+                };
+
+                let rows = self.table_mut(tokens.get(2).copied())?;
+                let before = rows.len();
+                rows.retain(|row| !predicate.matches(row));
+                Ok(before - rows.len())
+            }
+            _ => Err(DbError::QueryError(format!("Mock backend can't run `{}`", sql))),
+        }
+    }
+
+    /// Run a SELECT with `?` placeholders bound to `params`. The mock reads
+    /// `SELECT cols|COUNT(*) AS name FROM t [WHERE ...] [ORDER BY c [DESC]]
+    /// [LIMIT n]`; joins, grouping and other aggregates need SQLite.
+    pub fn query(&self, sql: &str, params: &[Value]) -> Result<Vec<Row>> {
+        log_sql("🔍 Querying:", sql, params);
+        bind_params(sql, params)?;
+
+        let owned = sql_tokens(sql);
+        let tokens: Vec<&str> = owned.iter().map(String::as_str).collect();
+        if keyword(&tokens, "JOIN").is_some() || keyword(&tokens, "GROUP").is_some() {
+            return Err(DbError::QueryError(
+                "The mock backend can't run JOIN or GROUP BY; build with the sqlite feature"
+                    .to_string(),
+            ));
+        }
+        let from = keyword(&tokens, "FROM")
+            .ok_or_else(|| DbError::QueryError("SELECT without FROM".to_string()))?;
+        let table = self
+            .tables
+            .get(tokens.get(from + 1).copied().unwrap_or(""))
+            .ok_or_else(|| DbError::QueryError(format!("No table in `{}`", sql)))?;
+
+        let order_at = keyword(&tokens, "ORDER");
+        let limit_at = keyword(&tokens, "LIMIT");
+        let predicate = match keyword(&tokens, "WHERE") {
+            Some(at) => {
+                let end = order_at.or(limit_at).unwrap_or(tokens.len());
+                PredicateParser::parse(&tokens[at + 1..end], &mut params.iter())?
+            }
+            None => Predicate::All(Vec::new()),
+        };
+
+        let mut rows: Vec<Row> =
+            table.iter().filter(|row| predicate.matches(row)).cloned().collect();
+
+        if let Some(at) = order_at {
+            let column = tokens.get(at + 2).copied().unwrap_or("");
+            let desc = tokens.get(at + 3).is_some_and(|t| t.eq_ignore_ascii_case("DESC"));
+            rows.sort_by(|a, b| {
+                let ordering = compare_values(
+                    a.get(column).unwrap_or(&Value::Null),
+                    b.get(column).unwrap_or(&Value::Null),
+                )
+                .unwrap_or(std::cmp::Ordering::Equal);
+                if desc { ordering.reverse() } else { ordering }
+            });
+        }
+        if let Some(limit) = limit_at.and_then(|at| tokens.get(at + 1)?.parse().ok()) {
+            rows.truncate(limit);
+        }
+
+        let mut fields = Vec::new();
+        for field in tokens[1..from].split(|t| *t == ",") {
+            match field {
+                ["*"] => return Ok(rows),
+                ["COUNT", "(", "*", ")", "AS", alias] => {
+                    let count = Value::Integer(rows.len() as i64);
+                    return Ok(vec![HashMap::from([(alias.to_string(), count)])]);
+                }
+                [column] => fields.push(*column),
+                _ => {
+                    return Err(DbError::QueryError(format!(
+                        "The mock backend can't select `{}`; build with the sqlite feature",
+                        field.join(" ")
+                    )))
+                }
+            }
+        }
+        Ok(rows
+            .into_iter()
+            .map(|mut row| {
+                row.retain(|column, _| fields.contains(&column.as_str()));
+                row
+            })
+            .collect())
+    }
+
+    fn table_mut(&mut self, name: Option<&str>) -> Result<&mut Vec<Row>> {
+        let name = name.unwrap_or("");
+        self.tables
+            .get_mut(name)
+            .ok_or_else(|| DbError::QueryError(format!("Table {} not found", name)))
     }
 
     fn extract_table_name(&self, sql: &str) -> Result<String> {
@@ -135,156 +975,1186 @@ impl Database {
         Err(DbError::QueryError("Could not extract table name".to_string()))
     }
 
-    pub fn insert(&mut self, table: &str, row: HashMap<String, Value>) -> Result<usize> {
+    /// Inserts `row` and returns its id, assigning the next one if the row
+    /// has none, as SQLite does for an `INTEGER PRIMARY KEY`
+    pub fn insert(&mut self, table: &str, mut row: HashMap<String, Value>) -> Result<usize> {
         println!("➕ Inserting into {}: {:?}", table, row);
-        
-        if let Some(table_data) = self.tables.get_mut(table) {
-            table_data.push(row);
-            Ok(table_data.len())
-        } else {
-            Err(DbError::QueryError(format!("Table {} not found", table)))
+
+        let table_data = self.table_mut(Some(table))?;
+        let id = match row.get("id") {
+            Some(Value::Integer(id)) => *id,
+            _ => {
+                let max = table_data
+                    .iter()
+                    .filter_map(|r| match r.get("id") {
+                        Some(Value::Integer(id)) => Some(*id),
+                        _ => None,
+                    })
+                    .max()
+                    .unwrap_or(0);
+                row.insert("id".to_string(), Value::Integer(max + 1));
+                max + 1
+            }
+        };
+        table_data.push(row);
+        Ok(id as usize)
+    }
+
+    /// Fit to hand out again: no transaction left open
+    fn is_healthy(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    /// Names of the tables in the database, sorted
+    pub fn tables(&self) -> Result<Vec<String>> {
+        let mut names: Vec<String> = self.schemas.keys().cloned().collect();
+        names.sort();
+        Ok(names)
+    }
+
+    /// The columns of `table`, in declaration order
+    pub fn columns(&self, table: &str) -> Result<Vec<ColumnInfo>> {
+        self.schemas
+            .get(table)
+            .cloned()
+            .ok_or_else(|| DbError::QueryError(format!("No such table: {}", table)))
+    }
+}
+
+/// Columns declared in a CREATE TABLE; table constraints such as
+/// `FOREIGN KEY (...)` are skipped
+#[cfg(not(feature = "sqlite"))]
+fn column_definitions(tokens: &[&str]) -> Vec<ColumnInfo> {
+    const CONSTRAINTS: [&str; 5] = ["CONSTRAINT", "PRIMARY", "FOREIGN", "UNIQUE", "CHECK"];
+    let is_constraint = |token: &str| CONSTRAINTS.iter().any(|c| token.eq_ignore_ascii_case(c));
+
+    let Some(open) = tokens.iter().position(|t| *t == "(") else {
+        return Vec::new();
+    };
+    let mut definitions = vec![Vec::new()];
+    let mut depth = 0;
+    for &token in &tokens[open + 1..] {
+        match token {
+            ")" if depth == 0 => break,
+            "," if depth == 0 => {
+                definitions.push(Vec::new());
+                continue;
+            }
+            "(" => depth += 1,
+            ")" => depth -= 1,
+            _ => {}
+        }
+        if let Some(definition) = definitions.last_mut() {
+            definition.push(token);
+        }
+    }
+
+    definitions
+        .iter()
+        .filter_map(|definition| {
+            let (&name, rest) = definition.split_first()?;
+            if is_constraint(name) {
+                return None;
+            }
+            let has = |first: &str, second: &str| {
+                rest.windows(2).any(|pair| {
+                    pair[0].eq_ignore_ascii_case(first) && pair[1].eq_ignore_ascii_case(second)
+                })
+            };
+            let sql_type = match rest.first() {
+                Some(&token) if !is_constraint(token) && !token.eq_ignore_ascii_case("NOT") => {
+                    token
+                }
+                _ => "",
+            };
+            Some(ColumnInfo {
+                name: name.to_string(),
+                sql_type: sql_type.to_string(),
+                nullable: !has("NOT", "NULL"),
+                primary_key: has("PRIMARY", "KEY"),
+            })
+        })
+        .collect()
+}
+
+/// Substitute each `?` placeholder with the escaped SQL literal of the next
+/// parameter. Question marks inside quoted strings are left alone. Only for
+/// showing a query in logs; queries themselves go out with bound parameters.
+pub fn bind_params(sql: &str, params: &[Value]) -> Result<String> {
+    let mut bound = String::with_capacity(sql.len());
+    let mut params_iter = params.iter();
+    let mut in_string = false;
+
+    for c in sql.chars() {
+        match c {
+            '\'' => {
+                in_string = !in_string;
+                bound.push(c);
+            }
+            '?' if !in_string => match params_iter.next() {
+                Some(value) => bound.push_str(&value.to_string()),
+                None => {
+                    return Err(DbError::QueryError(format!(
+                        "Not enough parameters: got {}",
+                        params.len()
+                    )))
+                }
+            },
+            _ => bound.push(c),
         }
     }
+
+    if params_iter.next().is_some() {
+        return Err(DbError::QueryError(format!(
+            "Too many parameters: got {}",
+            params.len()
+        )));
+    }
+
+    Ok(bound)
 }
 
 // ============================================================================
-// Query Builder
+// Connection Pool
 // ============================================================================
 
-pub struct QueryBuilder {
-    table: String,
-    select_fields: Vec<String>,
-    where_clauses: Vec<String>,
-    order_by: Option<String>,
-    limit: Option<usize>,
+/// Settings for `Pool::new`
+#[derive(Debug, Clone)]
+pub struct PoolConfig {
+    /// Most connections open at once, idle or checked out
+    pub max_size: usize,
+    /// How long `Pool::get` waits for a free connection
+    pub checkout_timeout: Duration,
+    /// Prepared statements kept per connection (SQLite only)
+    pub statement_cache_capacity: usize,
 }
 
-impl QueryBuilder {
-    pub fn new(table: &str) -> Self {
-        QueryBuilder {
-            table: table.to_string(),
-            select_fields: vec!["*".to_string()],
-            where_clauses: Vec::new(),
-            order_by: None,
-            limit: None,
+impl Default for PoolConfig {
+    fn default() -> Self {
+        PoolConfig {
+            max_size: 8,
+            checkout_timeout: Duration::from_secs(5),
+            statement_cache_capacity: 32,
+        }
+    }
+}
+
+struct PoolState {
+    idle: Vec<Database>,
+    /// Idle plus checked out
+    open: usize,
+}
+
+struct PoolShared {
+    path: String,
+    config: PoolConfig,
+    state: Mutex<PoolState>,
+    returned: Condvar,
+}
+
+impl PoolShared {
+    fn connect(&self) -> Result<Database> {
+        let db = Database::new(&self.path)?;
+        #[cfg(feature = "sqlite")]
+        {
+            db.conn.set_prepared_statement_cache_capacity(self.config.statement_cache_capacity);
+            // Writers on other connections hold the file lock briefly; wait
+            // for it rather than failing with SQLITE_BUSY
+            db.conn.busy_timeout(self.config.checkout_timeout)?;
         }
+        Ok(db)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, PoolState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A fixed-size set of connections to one database, shared between threads.
+/// Cloning is cheap and gives another handle to the same pool.
+///
+/// Each connection is separate: with `":memory:"` (or the mock backend) they
+/// don't see each other's tables, so share data through a database file.
+#[derive(Clone)]
+pub struct Pool {
+    shared: Arc<PoolShared>,
+}
+
+impl Pool {
+    /// Opens one connection up front so a bad path fails here; the rest are
+    /// opened as needed, up to `config.max_size`
+    pub fn new(path: &str, config: PoolConfig) -> Result<Self> {
+        if config.max_size == 0 {
+            return Err(DbError::ConnectionError("Pool max_size must be at least 1".to_string()));
+        }
+        let shared = PoolShared {
+            path: path.to_string(),
+            config,
+            state: Mutex::new(PoolState { idle: Vec::new(), open: 0 }),
+            returned: Condvar::new(),
+        };
+        let first = shared.connect()?;
+        {
+            let mut state = shared.lock();
+            state.idle.push(first);
+            state.open = 1;
+        }
+        Ok(Pool { shared: Arc::new(shared) })
+    }
+
+    /// Checks out a connection, waiting up to `checkout_timeout` for one to
+    /// come back if all are in use. Idle connections are health-checked
+    /// first and replaced if they fail.
+    pub fn get(&self) -> Result<PooledConnection> {
+        let timeout = self.shared.config.checkout_timeout;
+        let deadline = Instant::now() + timeout;
+        let mut state = self.shared.lock();
+        loop {
+            if let Some(db) = state.idle.pop() {
+                if db.is_healthy() {
+                    return Ok(self.lend(db));
+                }
+                println!("♻️  Discarding unhealthy pooled connection");
+                state.open -= 1;
+                continue;
+            }
+
+            if state.open < self.shared.config.max_size {
+                state.open += 1;
+                drop(state);
+                return match self.shared.connect() {
+                    Ok(db) => Ok(self.lend(db)),
+                    Err(e) => {
+                        self.shared.lock().open -= 1;
+                        self.shared.returned.notify_one();
+                        Err(e)
+                    }
+                };
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(DbError::PoolTimeout(timeout));
+            }
+            state = self
+                .shared
+                .returned
+                .wait_timeout(state, deadline - now)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+    }
+
+    fn lend(&self, db: Database) -> PooledConnection {
+        PooledConnection {
+            db: Some(db),
+            pool: Arc::clone(&self.shared),
+        }
+    }
+
+    /// Open connections, idle or checked out
+    pub fn size(&self) -> usize {
+        self.shared.lock().open
+    }
+
+    /// Connections waiting in the pool
+    pub fn idle(&self) -> usize {
+        self.shared.lock().idle.len()
+    }
+}
+
+/// A checked-out connection; derefs to `Database`, so repositories and
+/// transactions take `&mut conn`. Goes back to the pool when dropped.
+pub struct PooledConnection {
+    db: Option<Database>,
+    pool: Arc<PoolShared>,
+}
+
+impl Deref for PooledConnection {
+    type Target = Database;
+
+    fn deref(&self) -> &Database {
+        self.db.as_ref().expect("connection already returned")
+    }
+}
+
+impl DerefMut for PooledConnection {
+    fn deref_mut(&mut self) -> &mut Database {
+        self.db.as_mut().expect("connection already returned")
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        if let Some(db) = self.db.take() {
+            self.pool.lock().idle.push(db);
+            self.pool.returned.notify_one();
+        }
+    }
+}
+
+// ============================================================================
+// Query Builder
+// ============================================================================
+
+/// SQL with `?` placeholders and the values bound to them, in order
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    pub sql: String,
+    pub params: Vec<Value>,
+}
+
+/// Shows the query with its values inlined, for debugging output
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match bind_params(&self.sql, &self.params) {
+            Ok(sql) => write!(f, "{}", sql),
+            Err(_) => write!(f, "{} {:?}", self.sql, self.params),
+        }
+    }
+}
+
+/// Builds SELECT, UPDATE, DELETE and COUNT queries sharing one WHERE
+/// clause; joins, grouping and aggregates apply to SELECT and COUNT only.
+/// Values always become bound parameters; table and column names are written
+/// into the SQL as given, so they must come from code, not from user input.
+pub struct QueryBuilder {
+    table: String,
+    select_fields: Vec<String>,
+    joins: Vec<String>,
+    set_clauses: Vec<(String, Value)>,
+    where_clauses: Vec<String>,
+    params: Vec<Value>,
+    group_by: Vec<String>,
+    having_clauses: Vec<String>,
+    having_params: Vec<Value>,
+    order_by: Option<String>,
+    limit: Option<usize>,
+    /// A soft-delete column and which rows to keep by it
+    soft_delete: Option<(String, Trashed)>,
+}
+
+/// Which soft-deleted rows a query sees
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Trashed {
+    Hidden,
+    Included,
+    Only,
+}
+
+impl QueryBuilder {
+    pub fn new(table: &str) -> Self {
+        QueryBuilder {
+            table: table.to_string(),
+            select_fields: vec!["*".to_string()],
+            joins: Vec::new(),
+            set_clauses: Vec::new(),
+            where_clauses: Vec::new(),
+            params: Vec::new(),
+            group_by: Vec::new(),
+            having_clauses: Vec::new(),
+            having_params: Vec::new(),
+            order_by: None,
+            limit: None,
+            soft_delete: None,
+        }
+    }
+
+    pub fn select(mut self, fields: &[&str]) -> Self {
+        self.select_fields = fields.iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    /// Adds `COUNT(*) AS alias` to the selected columns
+    pub fn select_count(self, alias: &str) -> Self {
+        self.select_aggregate(format!("COUNT(*) AS {}", alias))
+    }
+
+    pub fn select_sum(self, field: &str, alias: &str) -> Self {
+        self.select_aggregate(format!("SUM({}) AS {}", field, alias))
+    }
+
+    pub fn select_avg(self, field: &str, alias: &str) -> Self {
+        self.select_aggregate(format!("AVG({}) AS {}", field, alias))
+    }
+
+    fn select_aggregate(mut self, expr: String) -> Self {
+        if self.select_fields == ["*"] {
+            self.select_fields.clear();
+        }
+        self.select_fields.push(expr);
+        self
+    }
+
+    /// `JOIN table ON on`, where `on` is a condition between columns such
+    /// as `"users.id = posts.user_id"`
+    pub fn join(mut self, table: &str, on: &str) -> Self {
+        self.joins.push(format!("JOIN {} ON {}", table, on));
+        self
+    }
+
+    pub fn left_join(mut self, table: &str, on: &str) -> Self {
+        self.joins.push(format!("LEFT JOIN {} ON {}", table, on));
+        self
+    }
+
+    /// A `column = value` assignment for `build_update`
+    pub fn set(mut self, field: &str, value: Value) -> Self {
+        self.set_clauses.push((field.to_string(), value));
+        self
+    }
+
+    pub fn where_eq(self, field: &str, value: Value) -> Self {
+        self.where_op(field, "=", value)
+    }
+
+    pub fn where_gt(self, field: &str, value: Value) -> Self {
+        self.where_op(field, ">", value)
+    }
+
+    pub fn where_lt(self, field: &str, value: Value) -> Self {
+        self.where_op(field, "<", value)
+    }
+
+    fn where_op(mut self, field: &str, op: &str, value: Value) -> Self {
+        self.where_clauses.push(format!("{} {} ?", field, op));
+        self.params.push(value);
+        self
+    }
+
+    /// `field IN (...)`; an empty list matches nothing
+    pub fn where_in(mut self, field: &str, values: Vec<Value>) -> Self {
+        if values.is_empty() {
+            self.where_clauses.push("0 = 1".to_string());
+        } else {
+            let placeholders = vec!["?"; values.len()].join(", ");
+            self.where_clauses.push(format!("{} IN ({})", field, placeholders));
+            self.params.extend(values);
+        }
+        self
+    }
+
+    /// `field LIKE pattern`, with `%` for any run of characters and `_` for
+    /// exactly one
+    pub fn where_like(self, field: &str, pattern: &str) -> Self {
+        self.where_op(field, "LIKE", Value::Text(pattern.to_string()))
+    }
+
+    pub fn where_null(mut self, field: &str) -> Self {
+        self.where_clauses.push(format!("{} IS NULL", field));
+        self
+    }
+
+    pub fn where_not_null(mut self, field: &str) -> Self {
+        self.where_clauses.push(format!("{} IS NOT NULL", field));
+        self
+    }
+
+    /// A parenthesized group of predicates of which any may hold:
+    /// `.where_any(|q| q.where_eq("a", x).where_null("b"))` adds
+    /// `(a = ? OR b IS NULL)`
+    pub fn where_any(self, group: impl FnOnce(QueryBuilder) -> QueryBuilder) -> Self {
+        self.where_group(" OR ", group)
+    }
+
+    /// A parenthesized group of predicates that must all hold, for nesting
+    /// inside `where_any`
+    pub fn where_all(self, group: impl FnOnce(QueryBuilder) -> QueryBuilder) -> Self {
+        self.where_group(" AND ", group)
+    }
+
+    fn where_group(
+        mut self,
+        separator: &str,
+        group: impl FnOnce(QueryBuilder) -> QueryBuilder,
+    ) -> Self {
+        let group = group(QueryBuilder::new(&self.table));
+        if !group.where_clauses.is_empty() {
+            self.where_clauses
+                .push(format!("({})", group.where_clauses.join(separator)));
+            self.params.extend(group.params);
+        }
+        self
+    }
+
+    pub fn group_by(mut self, fields: &[&str]) -> Self {
+        self.group_by.extend(fields.iter().map(|s| s.to_string()));
+        self
+    }
+
+    /// A HAVING condition on a grouped column, aggregate or alias, such as
+    /// `.having("COUNT(*)", ">", Value::Integer(1))`
+    pub fn having(mut self, expr: &str, op: &str, value: Value) -> Self {
+        self.having_clauses.push(format!("{} {} ?", expr, op));
+        self.having_params.push(value);
+        self
+    }
+
+    /// Leaves out rows whose `column` is set, as `Repository::query` does for
+    /// models with a `soft_delete_column`
+    pub fn soft_deletes(mut self, column: &str) -> Self {
+        self.soft_delete = Some((column.to_string(), Trashed::Hidden));
+        self
+    }
+
+    /// Brings soft-deleted rows back into the results
+    pub fn with_trashed(mut self) -> Self {
+        if let Some((_, trashed)) = &mut self.soft_delete {
+            *trashed = Trashed::Included;
+        }
+        self
+    }
+
+    /// Only soft-deleted rows
+    pub fn only_trashed(mut self) -> Self {
+        if let Some((_, trashed)) = &mut self.soft_delete {
+            *trashed = Trashed::Only;
+        }
+        self
+    }
+
+    /// Applies a reusable filter, typically a named scope on a model:
+    /// `repo.query().scope(User::adults())`
+    pub fn scope(self, scope: impl FnOnce(Self) -> Self) -> Self {
+        scope(self)
+    }
+
+    pub fn order_by(mut self, field: &str, desc: bool) -> Self {
+        self.order_by = Some(format!("{} {}", field, if desc { "DESC" } else { "ASC" }));
+        self
+    }
+
+    pub fn limit(mut self, n: usize) -> Self {
+        self.limit = Some(n);
+        self
+    }
+
+    fn where_sql(&self) -> String {
+        let mut clauses = self.where_clauses.clone();
+        match &self.soft_delete {
+            Some((column, Trashed::Hidden)) => clauses.push(format!("{} IS NULL", column)),
+            Some((column, Trashed::Only)) => clauses.push(format!("{} IS NOT NULL", column)),
+            Some((_, Trashed::Included)) | None => {}
+        }
+
+        if clauses.is_empty() {
+            String::new()
+        } else {
+            format!(" WHERE {}", clauses.join(" AND "))
+        }
+    }
+
+    /// The table and its joins
+    fn table_sql(&self) -> String {
+        let mut from = self.table.clone();
+        for join in &self.joins {
+            from.push(' ');
+            from.push_str(join);
+        }
+        from
+    }
+
+    pub fn build(&self) -> Query {
+        let mut sql = format!("SELECT {} FROM {}", self.select_fields.join(", "), self.table_sql());
+        sql.push_str(&self.where_sql());
+        let mut params = self.params.clone();
+
+        if !self.group_by.is_empty() {
+            sql.push_str(&format!(" GROUP BY {}", self.group_by.join(", ")));
+        }
+
+        if !self.having_clauses.is_empty() {
+            sql.push_str(&format!(" HAVING {}", self.having_clauses.join(" AND ")));
+            params.extend(self.having_params.iter().cloned());
+        }
+
+        if let Some(ref order) = self.order_by {
+            sql.push_str(&format!(" ORDER BY {}", order));
+        }
+
+        if let Some(limit) = self.limit {
+            sql.push_str(&format!(" LIMIT {}", limit));
+        }
+
+        Query { sql, params }
+    }
+
+    /// `UPDATE ... SET` from the `set` calls, limited by the WHERE clause
+    pub fn build_update(&self) -> Query {
+        let assignments: Vec<String> = self
+            .set_clauses
+            .iter()
+            .map(|(field, _)| format!("{} = ?", field))
+            .collect();
+        let mut params: Vec<Value> = self.set_clauses.iter().map(|(_, v)| v.clone()).collect();
+        params.extend(self.params.iter().cloned());
+
+        Query {
+            sql: format!(
+                "UPDATE {} SET {}{}",
+                self.table,
+                assignments.join(", "),
+                self.where_sql()
+            ),
+            params,
+        }
+    }
+
+    pub fn build_delete(&self) -> Query {
+        Query {
+            sql: format!("DELETE FROM {}{}", self.table, self.where_sql()),
+            params: self.params.clone(),
+        }
+    }
+
+    /// `SELECT COUNT(*) AS count`; grouping, ordering and limits are ignored
+    pub fn build_count(&self) -> Query {
+        Query {
+            sql: format!("SELECT COUNT(*) AS count FROM {}{}", self.table_sql(), self.where_sql()),
+            params: self.params.clone(),
+        }
+    }
+
+    /// Builds the SELECT and runs it on `db`
+    #[cfg(feature = "async")]
+    pub async fn fetch(&self, db: &AsyncDatabase) -> Result<Vec<HashMap<String, Value>>> {
+        db.fetch(&self.build()).await
+    }
+}
+
+// ============================================================================
+// Model Trait
+// ============================================================================
+
+pub trait Model: Sized {
+    fn table_name() -> &'static str;
+    fn from_row(row: &HashMap<String, Value>) -> Result<Self>;
+    fn to_row(&self) -> HashMap<String, Value>;
+
+    /// An integer column for optimistic locking: `Repository::update` only
+    /// writes if it still holds the value the model was read with
+    fn version_column() -> Option<&'static str> {
+        None
+    }
+
+    /// A nullable datetime column for soft deletes: `Repository::delete`
+    /// sets it instead of removing the row, and `Repository::query` leaves
+    /// out rows where it is set
+    fn soft_delete_column() -> Option<&'static str> {
+        None
+    }
+    
+    fn create_table(db: &mut Database) -> Result<()> {
+        let sql = Self::create_table_sql();
+        db.execute(&sql, &[])?;
+        Ok(())
+    }
+    
+    fn create_table_sql() -> String;
+}
+
+/// A Rust type that maps onto one column; what `#[derive(Model)]` calls to
+/// read, write and declare each field
+#[cfg(feature = "derive")]
+pub trait ColumnType: Sized {
+    const SQL_TYPE: &'static str;
+    const NULLABLE: bool = false;
+
+    /// `value` is `None` when the row has no such column
+    fn from_value(value: Option<&Value>, column: &str) -> Result<Self>;
+    fn to_value(&self) -> Value;
+
+    fn column_definition(name: &str, primary_key: bool) -> String {
+        if primary_key {
+            format!("{} {} PRIMARY KEY", name, Self::SQL_TYPE)
+        } else if Self::NULLABLE {
+            format!("{} {}", name, Self::SQL_TYPE)
+        } else {
+            format!("{} {} NOT NULL", name, Self::SQL_TYPE)
+        }
+    }
+}
+
+#[cfg(feature = "derive")]
+fn wrong_type(column: &str, value: Option<&Value>) -> DbError {
+    match value {
+        None | Some(Value::Null) => DbError::ValidationError(format!("{} required", column)),
+        Some(other) => DbError::ValidationError(format!("{} can't hold {}", column, other)),
+    }
+}
+
+#[cfg(feature = "derive")]
+impl ColumnType for i64 {
+    const SQL_TYPE: &'static str = "INTEGER";
+
+    fn from_value(value: Option<&Value>, column: &str) -> Result<Self> {
+        match value {
+            Some(Value::Integer(i)) => Ok(*i),
+            other => Err(wrong_type(column, other)),
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        Value::Integer(*self)
+    }
+}
+
+#[cfg(feature = "derive")]
+impl ColumnType for i32 {
+    const SQL_TYPE: &'static str = "INTEGER";
+
+    fn from_value(value: Option<&Value>, column: &str) -> Result<Self> {
+        let i = i64::from_value(value, column)?;
+        i32::try_from(i)
+            .map_err(|_| DbError::ValidationError(format!("{} out of range: {}", column, i)))
+    }
+
+    fn to_value(&self) -> Value {
+        Value::Integer(*self as i64)
+    }
+}
+
+#[cfg(feature = "derive")]
+impl ColumnType for f64 {
+    const SQL_TYPE: &'static str = "REAL";
+
+    fn from_value(value: Option<&Value>, column: &str) -> Result<Self> {
+        match value {
+            Some(Value::Real(r)) => Ok(*r),
+            Some(Value::Integer(i)) => Ok(*i as f64),
+            other => Err(wrong_type(column, other)),
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        Value::Real(*self)
+    }
+}
+
+#[cfg(feature = "derive")]
+impl ColumnType for bool {
+    const SQL_TYPE: &'static str = "INTEGER";
+
+    // SQLite hands booleans back as 0/1
+    fn from_value(value: Option<&Value>, column: &str) -> Result<Self> {
+        match value {
+            Some(Value::Boolean(b)) => Ok(*b),
+            Some(Value::Integer(i)) => Ok(*i != 0),
+            other => Err(wrong_type(column, other)),
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        Value::Boolean(*self)
+    }
+}
+
+#[cfg(feature = "derive")]
+impl ColumnType for String {
+    const SQL_TYPE: &'static str = "TEXT";
+
+    fn from_value(value: Option<&Value>, column: &str) -> Result<Self> {
+        match value {
+            Some(Value::Text(s)) => Ok(s.clone()),
+            other => Err(wrong_type(column, other)),
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        Value::Text(self.clone())
+    }
+}
+
+#[cfg(feature = "derive")]
+impl ColumnType for Vec<u8> {
+    const SQL_TYPE: &'static str = "BLOB";
+
+    fn from_value(value: Option<&Value>, column: &str) -> Result<Self> {
+        match value {
+            Some(Value::Blob(bytes)) => Ok(bytes.clone()),
+            other => Err(wrong_type(column, other)),
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        Value::Blob(self.clone())
+    }
+}
+
+#[cfg(feature = "derive")]
+impl ColumnType for SystemTime {
+    const SQL_TYPE: &'static str = "TEXT";
+
+    fn from_value(value: Option<&Value>, column: &str) -> Result<Self> {
+        value
+            .and_then(Value::as_datetime)
+            .ok_or_else(|| wrong_type(column, value))
+    }
+
+    fn to_value(&self) -> Value {
+        Value::DateTime(*self)
+    }
+}
+
+/// A nullable column
+#[cfg(feature = "derive")]
+impl<T: ColumnType> ColumnType for Option<T> {
+    const SQL_TYPE: &'static str = T::SQL_TYPE;
+    const NULLABLE: bool = true;
+
+    fn from_value(value: Option<&Value>, column: &str) -> Result<Self> {
+        match value {
+            None | Some(Value::Null) => Ok(None),
+            value => T::from_value(value, column).map(Some),
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        self.as_ref().map_or(Value::Null, T::to_value)
+    }
+}
+
+// ============================================================================
+// Repository Pattern
+// ============================================================================
+
+pub struct Repository<'a, T: Model> {
+    db: &'a mut Database,
+    _phantom: std::marker::PhantomData<T>,
+}
+
+impl<'a, T: Model> Repository<'a, T> {
+    pub fn new(db: &'a mut Database) -> Self {
+        Repository {
+            db,
+            _phantom: std::marker::PhantomData,
+        }
+    }
+
+    pub fn create(&mut self, model: &T) -> Result<usize> {
+        let row = model.to_row();
+        self.db.insert(T::table_name(), row)
+    }
+
+    pub fn find_all(&self) -> Result<Vec<T>> {
+        let rows = self.db.fetch(&self.query().build())?;
+        
+        rows.iter()
+            .map(|row| T::from_row(row))
+            .collect()
+    }
+
+    pub fn find_by_id(&self, id: i64) -> Result<T> {
+        let query = self.query().where_eq("id", Value::Integer(id)).limit(1).build();
+        
+        let rows = self.db.fetch(&query)?;
+        
+        if let Some(row) = rows.first() {
+            T::from_row(row)
+        } else {
+            Err(DbError::NotFound)
+        }
+    }
+
+    /// Writes every column of `model` to the row with the same id.
+    ///
+    /// For a model with a `version_column`, the write only happens if the
+    /// stored version still matches `model`'s; the version is then bumped in
+    /// both. If another writer got there first this returns
+    /// `DbError::Conflict`, and the caller should reload and retry.
+    pub fn update(&mut self, model: &mut T) -> Result<()> {
+        let mut row = model.to_row();
+        let id = row
+            .remove("id")
+            .ok_or_else(|| DbError::ValidationError("update needs an id".to_string()))?;
+
+        let mut filter = self.query().where_eq("id", id.clone());
+        let version = match T::version_column() {
+            Some(column) => match row.get(column) {
+                Some(&Value::Integer(current)) => {
+                    filter = filter.where_eq(column, Value::Integer(current));
+                    row.insert(column.to_string(), Value::Integer(current + 1));
+                    Some(current)
+                }
+                _ => {
+                    return Err(DbError::ValidationError(format!(
+                        "{} needs an integer {} to update",
+                        T::table_name(),
+                        column
+                    )))
+                }
+            },
+            None => None,
+        };
+
+        // Sorted so the same model always produces the same SQL
+        let mut columns: Vec<(String, Value)> = row.clone().into_iter().collect();
+        columns.sort_by(|a, b| a.0.cmp(&b.0));
+        let query = columns
+            .into_iter()
+            .fold(filter, |q, (column, value)| q.set(&column, value))
+            .build_update();
+
+        if self.db.execute(&query.sql, &query.params)? > 0 {
+            if version.is_some() {
+                row.insert("id".to_string(), id);
+                *model = T::from_row(&row)?;
+            }
+            return Ok(());
+        }
+        match version {
+            Some(version) if self.count(self.query().where_eq("id", id.clone()))? > 0 => {
+                Err(DbError::Conflict(format!(
+                    "{} {} changed since version {}",
+                    T::table_name(),
+                    id,
+                    version
+                )))
+            }
+            _ => Err(DbError::NotFound),
+        }
+    }
+
+    /// Deletes the row with `id`, or for a soft-deleting model marks it
+    /// deleted; `force_delete` removes it either way
+    pub fn delete(&mut self, id: i64) -> Result<()> {
+        match self.delete_where(self.query().where_eq("id", Value::Integer(id)))? {
+            0 => Err(DbError::NotFound),
+            _ => Ok(()),
+        }
+    }
+
+    /// Deletes the rows matching `builder`'s WHERE clause and returns how
+    /// many went. For a soft-deleting model they are only marked deleted.
+    pub fn delete_where(&mut self, builder: QueryBuilder) -> Result<usize> {
+        let query = match T::soft_delete_column() {
+            Some(column) => builder.set(column, Value::DateTime(SystemTime::now())).build_update(),
+            None => builder.build_delete(),
+        };
+        self.db.execute(&query.sql, &query.params)
+    }
+
+    /// Removes the row with `id` for good, soft-deleted or not
+    pub fn force_delete(&mut self, id: i64) -> Result<()> {
+        let query = self.query().with_trashed().where_eq("id", Value::Integer(id)).build_delete();
+        match self.db.execute(&query.sql, &query.params)? {
+            0 => Err(DbError::NotFound),
+            _ => Ok(()),
+        }
+    }
+
+    /// Undoes a soft delete
+    pub fn restore(&mut self, id: i64) -> Result<()> {
+        let column = T::soft_delete_column().ok_or_else(|| {
+            DbError::ValidationError(format!("{} has no soft deletes", T::table_name()))
+        })?;
+        let query = self
+            .query()
+            .only_trashed()
+            .where_eq("id", Value::Integer(id))
+            .set(column, Value::Null)
+            .build_update();
+        match self.db.execute(&query.sql, &query.params)? {
+            0 => Err(DbError::NotFound),
+            _ => Ok(()),
+        }
+    }
+
+    /// Rows matching `builder`'s WHERE clause
+    pub fn count(&self, builder: QueryBuilder) -> Result<i64> {
+        let rows = self.db.fetch(&builder.build_count())?;
+        match rows.first().and_then(|row| row.get("count")) {
+            Some(Value::Integer(n)) => Ok(*n),
+            _ => Err(DbError::QueryError("COUNT returned no rows".to_string())),
+        }
+    }
+
+    /// A query on this model's table, without soft-deleted rows
+    pub fn query(&self) -> QueryBuilder {
+        model_query::<T>()
+    }
+}
+
+fn model_query<T: Model>() -> QueryBuilder {
+    let query = QueryBuilder::new(T::table_name());
+    match T::soft_delete_column() {
+        Some(column) => query.soft_deletes(column),
+        None => query,
+    }
+}
+
+// ============================================================================
+// Async API
+// ============================================================================
+
+/// Work for the thread behind an `AsyncDatabase`
+#[cfg(feature = "async")]
+type Job = Box<dyn FnOnce(&mut Database) + Send>;
+
+/// A `Database` for async code. The connection lives on a thread of its own
+/// and calls are sent to it, so awaiting a query never blocks the executor;
+/// this is tokio-rusqlite's design, but the thread owns a whole `Database`,
+/// so repositories, transactions and the mock backend all work unchanged.
+///
+/// Cloning gives another handle to the same connection; calls from all
+/// handles run one at a time, in the order they were made.
+#[cfg(feature = "async")]
+#[derive(Clone)]
+pub struct AsyncDatabase {
+    jobs: std::sync::mpsc::Sender<Job>,
+}
+
+#[cfg(feature = "async")]
+impl AsyncDatabase {
+    pub async fn open(path: &str) -> Result<Self> {
+        let path = path.to_string();
+        let (jobs, inbox) = std::sync::mpsc::channel::<Job>();
+        let (opened, ready) = tokio::sync::oneshot::channel();
+        std::thread::Builder::new()
+            .name("orm-connection".to_string())
+            .spawn(move || {
+                let mut db = match Database::new(&path) {
+                    Ok(db) => db,
+                    Err(e) => {
+                        let _ = opened.send(Err(e));
+                        return;
+                    }
+                };
+                let _ = opened.send(Ok(()));
+                // Ends once every handle is dropped
+                for job in inbox {
+                    job(&mut db);
+                }
+            })
+            .map_err(|e| DbError::ConnectionError(e.to_string()))?;
+
+        ready.await.map_err(|_| connection_gone())??;
+        Ok(AsyncDatabase { jobs })
     }
 
-    pub fn select(mut self, fields: &[&str]) -> Self {
-        self.select_fields = fields.iter().map(|s| s.to_string()).collect();
-        self
+    pub async fn open_in_memory() -> Result<Self> {
+        Self::open(":memory:").await
     }
 
-    pub fn where_eq(mut self, field: &str, value: Value) -> Self {
-        self.where_clauses.push(format!("{} = {}", field, value));
-        self
+    /// Runs `f` on the connection's thread and waits for its result. A
+    /// panic in `f` carries on in the caller; the connection stays usable.
+    pub async fn call<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Database) -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let (reply, result) = tokio::sync::oneshot::channel();
+        self.jobs
+            .send(Box::new(move |db| {
+                let _ = reply.send(panic::catch_unwind(AssertUnwindSafe(|| f(db))));
+            }))
+            .map_err(|_| connection_gone())?;
+
+        match result.await.map_err(|_| connection_gone())? {
+            Ok(outcome) => outcome,
+            Err(panic) => panic::resume_unwind(panic),
+        }
     }
 
-    pub fn where_gt(mut self, field: &str, value: Value) -> Self {
-        self.where_clauses.push(format!("{} > {}", field, value));
-        self
+    pub async fn execute(&self, sql: &str, params: Vec<Value>) -> Result<usize> {
+        let sql = sql.to_string();
+        self.call(move |db| db.execute(&sql, &params)).await
     }
 
-    pub fn where_lt(mut self, field: &str, value: Value) -> Self {
-        self.where_clauses.push(format!("{} < {}", field, value));
-        self
+    pub async fn query(
+        &self,
+        sql: &str,
+        params: Vec<Value>,
+    ) -> Result<Vec<HashMap<String, Value>>> {
+        let sql = sql.to_string();
+        self.call(move |db| db.query(&sql, &params)).await
     }
 
-    pub fn order_by(mut self, field: &str, desc: bool) -> Self {
-        self.order_by = Some(format!("{} {}", field, if desc { "DESC" } else { "ASC" }));
-        self
+    pub async fn fetch(&self, query: &Query) -> Result<Vec<HashMap<String, Value>>> {
+        let query = query.clone();
+        self.call(move |db| db.fetch(&query)).await
     }
 
-    pub fn limit(mut self, n: usize) -> Self {
-        self.limit = Some(n);
-        self
+    pub async fn insert(&self, table: &str, row: HashMap<String, Value>) -> Result<usize> {
+        let table = table.to_string();
+        self.call(move |db| db.insert(&table, row)).await
     }
 
-    pub fn build(&self) -> String {
-        let mut sql = format!("SELECT {} FROM {}", self.select_fields.join(", "), self.table);
+    /// `Database::transaction`, run on the connection's thread: `f` itself
+    /// is synchronous, so nothing else can interleave with it
+    pub async fn transaction<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Database) -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        self.call(move |db| db.transaction(f)).await
+    }
+}
 
-        if !self.where_clauses.is_empty() {
-            sql.push_str(&format!(" WHERE {}", self.where_clauses.join(" AND ")));
-        }
+#[cfg(feature = "async")]
+fn connection_gone() -> DbError {
+    DbError::ConnectionError("The connection thread has stopped".to_string())
+}
 
-        if let Some(ref order) = self.order_by {
-            sql.push_str(&format!(" ORDER BY {}", order));
-        }
+/// `Repository` for async code, running each call on an `AsyncDatabase`
+#[cfg(feature = "async")]
+pub struct AsyncRepository<T: Model> {
+    db: AsyncDatabase,
+    _phantom: std::marker::PhantomData<fn() -> T>,
+}
 
-        if let Some(limit) = self.limit {
-            sql.push_str(&format!(" LIMIT {}", limit));
+#[cfg(feature = "async")]
+impl<T: Model + Send + 'static> AsyncRepository<T> {
+    pub fn new(db: &AsyncDatabase) -> Self {
+        AsyncRepository {
+            db: db.clone(),
+            _phantom: std::marker::PhantomData,
         }
+    }
 
-        sql
+    pub async fn create(&self, model: T) -> Result<usize> {
+        self.db.call(move |db| Repository::<T>::new(db).create(&model)).await
     }
-}
 
-// ============================================================================
-// Model Trait
-// ============================================================================
+    pub async fn find_all(&self) -> Result<Vec<T>> {
+        self.db.call(|db| Repository::<T>::new(db).find_all()).await
+    }
 
-pub trait Model: Sized {
-    fn table_name() -> &'static str;
-    fn from_row(row: &HashMap<String, Value>) -> Result<Self>;
-    fn to_row(&self) -> HashMap<String, Value>;
-    
-    fn create_table(db: &mut Database) -> Result<()> {
-        let sql = Self::create_table_sql();
-        db.execute(&sql)?;
-        Ok(())
+    pub async fn find_by_id(&self, id: i64) -> Result<T> {
+        self.db.call(move |db| Repository::<T>::new(db).find_by_id(id)).await
     }
-    
-    fn create_table_sql() -> String;
-}
 
-// ============================================================================
-// Repository Pattern
-// ============================================================================
+    /// `Repository::update`; returns the model as written, with any version
+    /// column bumped
+    pub async fn update(&self, mut model: T) -> Result<T> {
+        self.db
+            .call(move |db| Repository::<T>::new(db).update(&mut model).map(|()| model))
+            .await
+    }
 
-pub struct Repository<'a, T: Model> {
-    db: &'a mut Database,
-    _phantom: std::marker::PhantomData<T>,
-}
+    pub async fn delete(&self, id: i64) -> Result<()> {
+        self.db.call(move |db| Repository::<T>::new(db).delete(id)).await
+    }
 
-impl<'a, T: Model> Repository<'a, T> {
-    pub fn new(db: &'a mut Database) -> Self {
-        Repository {
-            db,
-            _phantom: std::marker::PhantomData,
-        }
+    pub async fn delete_where(&self, builder: QueryBuilder) -> Result<usize> {
+        self.db.call(move |db| Repository::<T>::new(db).delete_where(builder)).await
     }
 
-    pub fn create(&mut self, model: &T) -> Result<usize> {
-        let row = model.to_row();
-        self.db.insert(T::table_name(), row)
+    pub async fn count(&self, builder: QueryBuilder) -> Result<i64> {
+        self.db.call(move |db| Repository::<T>::new(db).count(builder)).await
     }
 
-    pub fn find_all(&self) -> Result<Vec<T>> {
-        let sql = format!("SELECT * FROM {}", T::table_name());
-        let rows = self.db.query(&sql)?;
-        
-        rows.iter()
-            .map(|row| T::from_row(row))
-            .collect()
+    pub async fn force_delete(&self, id: i64) -> Result<()> {
+        self.db.call(move |db| Repository::<T>::new(db).force_delete(id)).await
     }
 
-    pub fn find_by_id(&self, id: i64) -> Result<T> {
-        let sql = QueryBuilder::new(T::table_name())
-            .where_eq("id", Value::Integer(id))
-            .limit(1)
-            .build();
-        
-        let rows = self.db.query(&sql)?;
-        
-        if let Some(row) = rows.first() {
-            T::from_row(row)
-        } else {
-            Err(DbError::NotFound)
-        }
+    pub async fn restore(&self, id: i64) -> Result<()> {
+        self.db.call(move |db| Repository::<T>::new(db).restore(id)).await
     }
 
+    /// A query on this model's table, without soft-deleted rows
     pub fn query(&self) -> QueryBuilder {
-        QueryBuilder::new(T::table_name())
+        model_query::<T>()
     }
 }
 
@@ -351,12 +2221,28 @@ impl Model for User {
     }
 }
 
+/// Named scopes, for `QueryBuilder::scope`
+impl User {
+    pub fn adults() -> impl Fn(QueryBuilder) -> QueryBuilder {
+        |query| query.where_gt("age", Value::Integer(17))
+    }
+
+    pub fn with_email_domain(domain: &str) -> impl Fn(QueryBuilder) -> QueryBuilder {
+        let pattern = format!("%@{}", domain);
+        move |query| query.where_like("email", &pattern)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Post {
     pub id: Option<i64>,
     pub title: String,
     pub content: String,
     pub user_id: i64,
+    /// Bumped by every update, so concurrent edits can't overwrite each other
+    pub version: i64,
+    /// Set when the post is deleted; the row stays until `force_delete`
+    pub deleted_at: Option<SystemTime>,
 }
 
 impl Model for Post {
@@ -382,6 +2268,11 @@ impl Model for Post {
                 Some(Value::Integer(i)) => *i,
                 _ => return Err(DbError::ValidationError("user_id required".to_string())),
             },
+            version: match row.get("version") {
+                Some(Value::Integer(v)) => *v,
+                _ => 0,
+            },
+            deleted_at: row.get("deleted_at").and_then(Value::as_datetime),
         })
     }
 
@@ -394,10 +2285,20 @@ impl Model for Post {
         row.insert("title".to_string(), Value::Text(self.title.clone()));
         row.insert("content".to_string(), Value::Text(self.content.clone()));
         row.insert("user_id".to_string(), Value::Integer(self.user_id));
+        row.insert("version".to_string(), Value::Integer(self.version));
+        row.insert("deleted_at".to_string(), self.deleted_at.map_or(Value::Null, Value::DateTime));
         
         row
     }
 
+    fn version_column() -> Option<&'static str> {
+        Some("version")
+    }
+
+    fn soft_delete_column() -> Option<&'static str> {
+        Some("deleted_at")
+    }
+
     fn create_table_sql() -> String {
         r#"
         CREATE TABLE posts (
@@ -405,12 +2306,28 @@ impl Model for Post {
             title TEXT NOT NULL,
             content TEXT,
             user_id INTEGER NOT NULL,
+            version INTEGER NOT NULL DEFAULT 0,
+            deleted_at TEXT,
             FOREIGN KEY (user_id) REFERENCES users(id)
         )
         "#.to_string()
     }
 }
 
+/// The same kind of model as `User` and `Post`, without the boilerplate
+#[cfg(feature = "derive")]
+#[derive(Debug, Clone, Model)]
+#[orm(table = "comments")]
+pub struct Comment {
+    #[orm(primary_key)]
+    pub id: Option<i64>,
+    pub post_id: i64,
+    #[orm(column = "body")]
+    pub text: String,
+    pub approved: bool,
+    pub rating: Option<f64>,
+}
+
 // ============================================================================
 // Demo Application
 // ============================================================================
@@ -419,12 +2336,23 @@ fn main() -> Result<()> {
     println!("🗃️  Database ORM Demo\n");
 
     // Create database
-    let mut db = Database::new("demo.db")?;
+    let mut db = Database::open_in_memory()?;
 
     // Create tables
     println!("\n📋 Creating tables...");
     User::create_table(&mut db)?;
     Post::create_table(&mut db)?;
+    for table in db.tables()? {
+        let columns: Vec<String> = db
+            .columns(&table)?
+            .iter()
+            .map(|c| {
+                let flag = if c.primary_key { " (pk)" } else if c.nullable { "?" } else { "" };
+                format!("{} {}{}", c.name, c.sql_type, flag)
+            })
+            .collect();
+        println!("  {}: {}", table, columns.join(", "));
+    }
 
     // Create repository
     let mut user_repo = Repository::<User>::new(&mut db);
@@ -467,13 +2395,68 @@ fn main() -> Result<()> {
         .limit(10)
         .build();
     
-    println!("Generated SQL: {}", query);
+    println!("Generated SQL: {}", query.sql);
+    println!("  params: {:?}", query.params);
 
+    // A hostile value stays data: it is bound, not spliced into the SQL
     let query2 = QueryBuilder::new("users")
-        .where_eq("name", Value::Text("Alice".to_string()))
+        .where_eq("name", Value::Text("x' OR '1'='1".to_string()))
         .build();
     
-    println!("Generated SQL: {}", query2);
+    println!("Generated SQL: {}", query2.sql);
+    println!("  params: {:?}", query2.params);
+    println!("  for logs: {}", query2);
+
+    let query3 = QueryBuilder::new("users")
+        .where_not_null("email")
+        .where_any(|q| {
+            q.where_like("name", "A%")
+                .where_in("age", vec![Value::Integer(35), Value::Integer(42)])
+        })
+        .build();
+    println!("Generated SQL: {}", query3.sql);
+    println!("  params: {:?}", query3.params);
+
+    // Update, delete and count
+    println!("\n✏️  Update, delete and count:");
+    let mut carol = user_repo.find_by_id(3)?;
+    carol.email = "carol.white@example.com".to_string();
+    user_repo.update(&mut carol)?;
+    let over_30 = user_repo.count(user_repo.query().where_gt("age", Value::Integer(30)))?;
+    println!("{} users over 30", over_30);
+    let adults = user_repo.query().scope(User::adults());
+    println!("{} adults (named scope)", user_repo.count(adults)?);
+    user_repo.delete(2)?;
+    println!("{} users left after deleting Bob", user_repo.count(user_repo.query())?);
+
+    // Raw SQL escape hatch
+    println!("\n🧰 Raw SQL Examples:");
+    let older: Vec<User> = db.query_as(
+        "SELECT * FROM users WHERE age > ? AND name != ?",
+        &[Value::Integer(30), Value::Text("O'Brien".to_string())],
+    )?;
+    println!("query_as::<User> returned {} typed rows", older.len());
+    db.execute(
+        "UPDATE users SET age = ? WHERE id = ?",
+        &[Value::Integer(29), Value::Integer(1)],
+    )?;
+
+    // Transactions
+    println!("\n🔒 Transactions:");
+    let signup = db.transaction(|tx| {
+        Repository::<User>::new(tx).create(&User {
+            id: None,
+            name: "Dave Brown".to_string(),
+            email: "dave@example.com".to_string(),
+            age: 31,
+        })?;
+        Err::<(), _>(DbError::ValidationError("signup rejected".to_string()))
+    });
+    if let Err(e) = signup {
+        println!("Rolled back: {}", e);
+    }
+    let remaining = Repository::<User>::new(&mut db).count(QueryBuilder::new("users"))?;
+    println!("{} users after the rollback", remaining);
 
     // Create posts
     println!("\n📝 Creating posts...");
@@ -485,12 +2468,16 @@ fn main() -> Result<()> {
             title: "First Post".to_string(),
             content: "Hello, World!".to_string(),
             user_id: 1,
+            version: 0,
+            deleted_at: None,
         },
         Post {
             id: Some(2),
             title: "Rust ORM".to_string(),
             content: "Building an ORM in Rust".to_string(),
             user_id: 1,
+            version: 0,
+            deleted_at: None,
         },
     ];
 
@@ -499,12 +2486,129 @@ fn main() -> Result<()> {
         println!("Created post: {}", post.title);
     }
 
+    // Optimistic locking
+    println!("\n🔐 Optimistic locking:");
+    let mut edit = post_repo.find_by_id(1)?;
+    let mut stale = edit.clone();
+    edit.content = "Hello again!".to_string();
+    post_repo.update(&mut edit)?;
+    println!("First writer saved version {}", edit.version);
+    stale.title = "First Post (edited)".to_string();
+    if let Err(e) = post_repo.update(&mut stale) {
+        println!("Second writer: {}", e);
+    }
+
+    // Soft deletes
+    println!("\n🗑️  Soft deletes:");
+    post_repo.delete(2)?;
+    println!(
+        "{} visible post(s), {} in the trash",
+        post_repo.count(post_repo.query())?,
+        post_repo.count(post_repo.query().only_trashed())?
+    );
+    post_repo.restore(2)?;
+    println!("Restored: {}", post_repo.find_by_id(2)?.title);
+
+    // Joins and aggregates
+    println!("\n📊 Joins and aggregates:");
+    let prolific = QueryBuilder::new("users")
+        .select(&["users.name"])
+        .select_count("posts")
+        .join("posts", "posts.user_id = users.id")
+        .group_by(&["users.name"])
+        .having("COUNT(*)", ">", Value::Integer(1))
+        .build();
+    println!("Generated SQL: {}", prolific.sql);
+    match db.fetch(&prolific) {
+        Ok(rows) => {
+            for row in rows {
+                println!("  {} wrote {} posts", row["name"], row["posts"]);
+            }
+        }
+        Err(e) => println!("  {}", e),
+    }
+
+    #[cfg(feature = "derive")]
+    {
+        println!("\n💬 Derived model:");
+        println!("{}", Comment::create_table_sql());
+        Comment::create_table(&mut db)?;
+        let mut comment_repo = Repository::<Comment>::new(&mut db);
+        comment_repo.create(&Comment {
+            id: None,
+            post_id: 1,
+            text: "Nice post!".to_string(),
+            approved: true,
+            rating: None,
+        })?;
+        println!("Read back: {:?}", comment_repo.find_by_id(1)?);
+    }
+
+    // Connection pool
+    println!("\n🏊 Connection pool:");
+    let pool = Pool::new(
+        ":memory:",
+        PoolConfig {
+            max_size: 2,
+            checkout_timeout: Duration::from_millis(100),
+            ..PoolConfig::default()
+        },
+    )?;
+    // Both workers hold a connection at once, so each gets its own
+    let both_checked_out = Arc::new(std::sync::Barrier::new(2));
+    let workers: Vec<_> = (1..=2)
+        .map(|worker| {
+            let pool = pool.clone();
+            let both_checked_out = Arc::clone(&both_checked_out);
+            std::thread::spawn(move || -> Result<()> {
+                let mut conn = pool.get()?;
+                both_checked_out.wait();
+                conn.transaction(User::create_table)?;
+                println!("Worker {} set up its connection", worker);
+                Ok(())
+            })
+        })
+        .collect();
+    for worker in workers {
+        worker.join().expect("worker panicked")?;
+    }
+    println!("{} connections open, {} idle", pool.size(), pool.idle());
+    let (_a, _b) = (pool.get()?, pool.get()?);
+    if let Err(e) = pool.get() {
+        println!("Third checkout with both in use: {}", e);
+    }
+
+    #[cfg(feature = "async")]
+    {
+        println!("\n⚡ Async API:");
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .map_err(|e| DbError::ConnectionError(e.to_string()))?;
+        runtime.block_on(async {
+            let db = AsyncDatabase::open_in_memory().await?;
+            db.call(User::create_table).await?;
+            let users = AsyncRepository::<User>::new(&db);
+            users
+                .create(User {
+                    id: None,
+                    name: "Erin Green".to_string(),
+                    email: "erin@example.com".to_string(),
+                    age: 27,
+                })
+                .await?;
+            let young = users.query().where_lt("age", Value::Integer(30)).fetch(&db).await?;
+            println!("{} user(s) under 30, fetched without blocking the runtime", young.len());
+            Ok::<_, DbError>(())
+        })?;
+    }
+
     // Summary
     println!("\n✅ Demo completed successfully!");
     println!("   - Created {} users", users.len());
     println!("   - Created {} posts", posts.len());
     println!("   - Demonstrated query builder");
-    println!("\n💡 In production, use rusqlite crate for real SQLite support");
+    #[cfg(not(feature = "sqlite"))]
+    println!("\n💡 Build with the `sqlite` feature to run against real SQLite");
 
     Ok(())
 }
@@ -512,6 +2616,7 @@ fn main() -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::thread;
 
     #[test]
     fn test_query_builder() {
@@ -520,9 +2625,11 @@ mod tests {
             .where_eq("id", Value::Integer(1))
             .build();
         
-        assert!(query.contains("SELECT name, email"));
-        assert!(query.contains("FROM users"));
-        assert!(query.contains("WHERE id = 1"));
+        assert!(query.sql.contains("SELECT name, email"));
+        assert!(query.sql.contains("FROM users"));
+        assert!(query.sql.contains("WHERE id = ?"));
+        assert_eq!(query.params, vec![Value::Integer(1)]);
+        assert!(query.to_string().contains("WHERE id = 1"));
     }
 
     #[test]
@@ -547,4 +2654,832 @@ mod tests {
         let user2 = User::from_row(&row).unwrap();
         assert_eq!(user2.name, "Test");
     }
+
+    #[test]
+    fn test_bind_params() {
+        let sql = bind_params(
+            "SELECT * FROM users WHERE name = ? AND note = 'why?' AND age > ?",
+            &[Value::Text("O'Brien".to_string()), Value::Integer(18)],
+        )
+        .unwrap();
+        assert_eq!(
+            sql,
+            "SELECT * FROM users WHERE name = 'O''Brien' AND note = 'why?' AND age > 18"
+        );
+
+        assert!(bind_params("SELECT * FROM users WHERE id = ?", &[]).is_err());
+        assert!(bind_params("SELECT * FROM users", &[Value::Integer(1)]).is_err());
+    }
+
+    #[test]
+    fn test_query_as_maps_rows() {
+        let mut db = Database::open_in_memory().unwrap();
+        User::create_table(&mut db).unwrap();
+        Repository::<User>::new(&mut db)
+            .create(&User {
+                id: Some(1),
+                name: "Test".to_string(),
+                email: "test@example.com".to_string(),
+                age: 25,
+            })
+            .unwrap();
+
+        let users: Vec<User> = db
+            .query_as("SELECT * FROM users WHERE id = ?", &[Value::Integer(1)])
+            .unwrap();
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0].email, "test@example.com");
+
+        assert_eq!(
+            db.execute("DELETE FROM users WHERE id = ?", &[Value::Integer(1)]).unwrap(),
+            1
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_filters_rows() {
+        let mut db = Database::open_in_memory().unwrap();
+        User::create_table(&mut db).unwrap();
+        let mut repo = Repository::<User>::new(&mut db);
+        for (name, age) in [("Alice", 28), ("Bob", 35), ("Carol", 42)] {
+            repo.create(&User {
+                id: None,
+                name: name.to_string(),
+                email: format!("{}@example.com", name.to_lowercase()),
+                age,
+            })
+            .unwrap();
+        }
+
+        let bob = repo.find_by_id(2).unwrap();
+        assert_eq!(bob.name, "Bob");
+        assert!(matches!(repo.find_by_id(9), Err(DbError::NotFound)));
+
+        let query = repo.query().where_gt("age", Value::Integer(30)).build();
+        assert_eq!(db.fetch(&query).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_update_and_delete_sql() {
+        let update = QueryBuilder::new("users")
+            .set("name", Value::Text("Ann".to_string()))
+            .set("age", Value::Integer(30))
+            .where_eq("id", Value::Integer(7))
+            .build_update();
+        assert_eq!(update.sql, "UPDATE users SET name = ?, age = ? WHERE id = ?");
+        assert_eq!(
+            update.params,
+            vec![Value::Text("Ann".to_string()), Value::Integer(30), Value::Integer(7)]
+        );
+
+        let delete = QueryBuilder::new("users").where_lt("age", Value::Integer(18)).build_delete();
+        assert_eq!(delete.sql, "DELETE FROM users WHERE age < ?");
+        assert_eq!(
+            QueryBuilder::new("users").build_count().sql,
+            "SELECT COUNT(*) AS count FROM users"
+        );
+    }
+
+    #[test]
+    fn test_repository_update_delete_count() {
+        let mut db = Database::open_in_memory().unwrap();
+        User::create_table(&mut db).unwrap();
+        let mut repo = Repository::<User>::new(&mut db);
+        for (name, age) in [("Alice", 28), ("Bob", 35), ("Carol", 42)] {
+            repo.create(&User {
+                id: None,
+                name: name.to_string(),
+                email: format!("{}@example.com", name.to_lowercase()),
+                age,
+            })
+            .unwrap();
+        }
+
+        let mut bob = repo.find_by_id(2).unwrap();
+        bob.age = 36;
+        repo.update(&mut bob).unwrap();
+        assert_eq!(repo.find_by_id(2).unwrap().age, 36);
+        assert_eq!(repo.find_by_id(1).unwrap().age, 28);
+
+        assert_eq!(repo.count(repo.query().where_gt("age", Value::Integer(30))).unwrap(), 2);
+        repo.delete(1).unwrap();
+        assert!(matches!(repo.delete(1), Err(DbError::NotFound)));
+        assert_eq!(
+            repo.delete_where(repo.query().where_gt("age", Value::Integer(40))).unwrap(),
+            1
+        );
+        assert_eq!(repo.count(repo.query()).unwrap(), 1);
+
+        let mut ghost = User { id: Some(99), ..bob };
+        assert!(matches!(repo.update(&mut ghost), Err(DbError::NotFound)));
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn test_derived_model() {
+        assert_eq!(Comment::table_name(), "comments");
+        assert_eq!(
+            Comment::create_table_sql(),
+            "CREATE TABLE comments (id INTEGER PRIMARY KEY, post_id INTEGER NOT NULL, \
+             body TEXT NOT NULL, approved INTEGER NOT NULL, rating REAL)"
+        );
+
+        let comment = Comment {
+            id: None,
+            post_id: 4,
+            text: "hi".to_string(),
+            approved: false,
+            rating: Some(4.5),
+        };
+        let row = comment.to_row();
+        assert!(!row.contains_key("id"));
+        assert_eq!(row.get("body"), Some(&Value::Text("hi".to_string())));
+
+        let mut db = Database::open_in_memory().unwrap();
+        Comment::create_table(&mut db).unwrap();
+        let mut repo = Repository::<Comment>::new(&mut db);
+        repo.create(&comment).unwrap();
+        let stored = repo.find_by_id(1).unwrap();
+        assert_eq!(stored.id, Some(1));
+        assert_eq!(stored.text, "hi");
+        assert_eq!((stored.approved, stored.rating), (false, Some(4.5)));
+
+        let mut row = HashMap::new();
+        row.insert("post_id".to_string(), Value::Integer(1));
+        let err = Comment::from_row(&row).unwrap_err();
+        assert_eq!(err.to_string(), "Validation error: body required");
+    }
+
+    fn user(name: &str) -> User {
+        User {
+            id: None,
+            name: name.to_string(),
+            email: format!("{}@example.com", name.to_lowercase()),
+            age: 30,
+        }
+    }
+
+    fn user_names(db: &mut Database) -> Vec<String> {
+        let mut names: Vec<String> = Repository::<User>::new(db)
+            .find_all()
+            .unwrap()
+            .into_iter()
+            .map(|u| u.name)
+            .collect();
+        names.sort();
+        names
+    }
+
+    fn seed_users(db: &mut Database) {
+        User::create_table(db).unwrap();
+        let mut repo = Repository::<User>::new(db);
+        for (name, age) in [("Alice", 28), ("Bob", 35), ("Carol", 42)] {
+            repo.create(&User { age, ..user(name) }).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_join_group_having_sql() {
+        let query = QueryBuilder::new("users")
+            .select(&["users.name"])
+            .select_count("posts")
+            .select_avg("users.age", "avg_age")
+            .left_join("posts", "posts.user_id = users.id")
+            .where_gt("users.age", Value::Integer(18))
+            .group_by(&["users.name"])
+            .having("COUNT(posts.id)", ">", Value::Integer(1))
+            .order_by("posts", true)
+            .build();
+        assert_eq!(
+            query.sql,
+            "SELECT users.name, COUNT(*) AS posts, AVG(users.age) AS avg_age FROM users \
+             LEFT JOIN posts ON posts.user_id = users.id WHERE users.age > ? \
+             GROUP BY users.name HAVING COUNT(posts.id) > ? ORDER BY posts DESC"
+        );
+        assert_eq!(query.params, vec![Value::Integer(18), Value::Integer(1)]);
+
+        let sum = QueryBuilder::new("users").select_sum("age", "total").build();
+        assert_eq!(sum.sql, "SELECT SUM(age) AS total FROM users");
+    }
+
+    #[test]
+    fn test_where_predicates_sql() {
+        let query = QueryBuilder::new("users")
+            .where_in("id", vec![Value::Integer(1), Value::Integer(2)])
+            .where_like("email", "%@example.com")
+            .where_not_null("name")
+            .where_any(|q| {
+                q.where_lt("age", Value::Integer(30))
+                    .where_all(|q| {
+                        q.where_eq("name", Value::Text("Bob".to_string())).where_null("email")
+                    })
+            })
+            .build();
+        assert_eq!(
+            query.sql,
+            "SELECT * FROM users WHERE id IN (?, ?) AND email LIKE ? AND name IS NOT NULL \
+             AND (age < ? OR (name = ? AND email IS NULL))"
+        );
+        assert_eq!(
+            query.params,
+            vec![
+                Value::Integer(1),
+                Value::Integer(2),
+                Value::Text("%@example.com".to_string()),
+                Value::Integer(30),
+                Value::Text("Bob".to_string()),
+            ]
+        );
+
+        let nothing = QueryBuilder::new("users").where_in("id", Vec::new()).build();
+        assert_eq!(nothing.sql, "SELECT * FROM users WHERE 0 = 1");
+    }
+
+    #[test]
+    fn test_where_predicates_filter_rows() {
+        let mut db = Database::open_in_memory().unwrap();
+        seed_users(&mut db);
+        let names = |db: &Database, query: QueryBuilder| -> Vec<String> {
+            let users: Vec<User> = db.query_as(&query.build().sql, &query.build().params).unwrap();
+            let mut names: Vec<String> = users.into_iter().map(|u| u.name).collect();
+            names.sort();
+            names
+        };
+        let users = || QueryBuilder::new("users");
+
+        let picked = users().where_in(
+            "name",
+            vec![Value::Text("Alice".to_string()), Value::Text("Carol".to_string())],
+        );
+        assert_eq!(names(&db, picked), vec!["Alice", "Carol"]);
+        assert!(names(&db, users().where_in("id", Vec::new())).is_empty());
+        assert_eq!(names(&db, users().where_like("email", "A%")), vec!["Alice"]);
+        assert_eq!(names(&db, users().where_like("name", "_ob")), vec!["Bob"]);
+
+        let young_or_old = users().where_any(|q| {
+            q.where_lt("age", Value::Integer(30)).where_gt("age", Value::Integer(40))
+        });
+        assert_eq!(names(&db, young_or_old), vec!["Alice", "Carol"]);
+        let grouped = users()
+            .where_gt("age", Value::Integer(30))
+            .where_any(|q| {
+                q.where_eq("name", Value::Text("Alice".to_string()))
+                    .where_all(|q| q.where_like("name", "C%").where_not_null("email"))
+            });
+        assert_eq!(names(&db, grouped), vec!["Carol"]);
+
+        Post::create_table(&mut db).unwrap();
+        let mut draft = HashMap::new();
+        draft.insert("title".to_string(), Value::Text("Draft".to_string()));
+        draft.insert("user_id".to_string(), Value::Integer(1));
+        db.insert("posts", draft).unwrap();
+        let mut repo = Repository::<Post>::new(&mut db);
+        repo.create(&Post {
+            id: None,
+            title: "Hello".to_string(),
+            content: "World".to_string(),
+            user_id: 1,
+            version: 0,
+            deleted_at: None,
+        })
+        .unwrap();
+        assert_eq!(repo.count(repo.query().where_null("content")).unwrap(), 1);
+        assert_eq!(repo.count(repo.query().where_not_null("content")).unwrap(), 1);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_joins_and_groups() {
+        let mut db = Database::open_in_memory().unwrap();
+        seed_users(&mut db);
+        Post::create_table(&mut db).unwrap();
+        let mut repo = Repository::<Post>::new(&mut db);
+        for (title, user_id) in [("One", 1), ("Two", 1), ("Three", 2)] {
+            repo.create(&Post {
+                id: None,
+                title: title.to_string(),
+                content: String::new(),
+                user_id,
+                version: 0,
+                deleted_at: None,
+            })
+            .unwrap();
+        }
+
+        let query = QueryBuilder::new("users")
+            .select(&["users.name"])
+            .select_count("posts")
+            .join("posts", "posts.user_id = users.id")
+            .group_by(&["users.name"])
+            .having("COUNT(*)", ">", Value::Integer(1))
+            .build();
+        let rows = db.fetch(&query).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].get("name"), Some(&Value::Text("Alice".to_string())));
+        assert_eq!(rows[0].get("posts"), Some(&Value::Integer(2)));
+
+        // Carol has no posts but still gets a row from the left join
+        let query = QueryBuilder::new("users")
+            .select(&["users.name"])
+            .left_join("posts", "posts.user_id = users.id")
+            .where_null("posts.id")
+            .build();
+        let rows = db.fetch(&query).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].get("name"), Some(&Value::Text("Carol".to_string())));
+
+        let query = QueryBuilder::new("users").select_avg("age", "avg_age").build();
+        assert_eq!(db.fetch(&query).unwrap()[0].get("avg_age"), Some(&Value::Real(35.0)));
+    }
+
+    #[test]
+    fn test_transaction_commits_or_rolls_back() {
+        let mut db = Database::open_in_memory().unwrap();
+        User::create_table(&mut db).unwrap();
+
+        db.transaction(|tx| Repository::<User>::new(tx).create(&user("Alice")))
+            .unwrap();
+
+        let failed = db.transaction(|tx| {
+            Repository::<User>::new(tx).create(&user("Bob"))?;
+            Err::<(), _>(DbError::ValidationError("changed my mind".to_string()))
+        });
+        assert!(failed.is_err());
+
+        let panicked = panic::catch_unwind(AssertUnwindSafe(|| {
+            db.transaction::<()>(|tx| {
+                Repository::<User>::new(tx).create(&user("Carol"))?;
+                panic!("boom");
+            })
+        }));
+        assert!(panicked.is_err());
+
+        assert_eq!(user_names(&mut db), vec!["Alice"]);
+        // Still usable afterwards
+        db.transaction(|tx| Repository::<User>::new(tx).create(&user("Dave")))
+            .unwrap();
+        assert_eq!(user_names(&mut db), vec!["Alice", "Dave"]);
+    }
+
+    #[test]
+    fn test_nested_transactions_use_savepoints() {
+        let mut db = Database::open_in_memory().unwrap();
+        User::create_table(&mut db).unwrap();
+
+        db.transaction(|tx| {
+            Repository::<User>::new(tx).create(&user("Outer"))?;
+            let inner = tx.transaction(|tx| {
+                Repository::<User>::new(tx).create(&user("Dropped"))?;
+                Err::<(), _>(DbError::ValidationError("inner failure".to_string()))
+            });
+            assert!(inner.is_err());
+            tx.transaction(|tx| Repository::<User>::new(tx).create(&user("Kept")))
+        })
+        .unwrap();
+        assert_eq!(user_names(&mut db), vec!["Kept", "Outer"]);
+
+        // An outer rollback takes committed inner work with it
+        let failed = db.transaction(|tx| {
+            tx.transaction(|tx| Repository::<User>::new(tx).create(&user("Inner")))?;
+            Err::<(), _>(DbError::ValidationError("outer failure".to_string()))
+        });
+        assert!(failed.is_err());
+        assert_eq!(user_names(&mut db), vec!["Kept", "Outer"]);
+    }
+
+    fn pool_config(max_size: usize, checkout_timeout: Duration) -> PoolConfig {
+        PoolConfig {
+            max_size,
+            checkout_timeout,
+            ..PoolConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_pool_checkout_and_timeout() {
+        let pool = Pool::new(":memory:", pool_config(2, Duration::from_millis(50))).unwrap();
+        let first = pool.get().unwrap();
+        let _second = pool.get().unwrap();
+        assert_eq!((pool.size(), pool.idle()), (2, 0));
+        assert!(matches!(pool.get(), Err(DbError::PoolTimeout(_))));
+
+        drop(first);
+        assert_eq!(pool.idle(), 1);
+        assert!(pool.get().is_ok());
+        assert_eq!(pool.size(), 2);
+
+        // A waiting thread gets the connection as soon as it comes back
+        let pool = Pool::new(":memory:", pool_config(1, Duration::from_secs(5))).unwrap();
+        let held = pool.get().unwrap();
+        let waiter = {
+            let pool = pool.clone();
+            thread::spawn(move || pool.get().is_ok())
+        };
+        thread::sleep(Duration::from_millis(50));
+        drop(held);
+        assert!(waiter.join().unwrap());
+    }
+
+    #[test]
+    fn test_pool_replaces_unhealthy_connections() {
+        let pool = Pool::new(":memory:", pool_config(1, Duration::from_millis(50))).unwrap();
+        let mut conn = pool.get().unwrap();
+        User::create_table(&mut conn).unwrap();
+        drop(conn);
+
+        // A healthy connection comes back as it was, table and all
+        let mut conn = pool.get().unwrap();
+        assert!(conn.query("SELECT * FROM users", &[]).is_ok());
+        conn.execute("BEGIN", &[]).unwrap();
+        drop(conn);
+
+        // Left mid-transaction, so it is swapped for a fresh (empty) database
+        let conn = pool.get().unwrap();
+        assert!(conn.query("SELECT * FROM users", &[]).is_err());
+        assert_eq!(pool.size(), 1);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_pool_shared_across_threads() {
+        let path = std::env::temp_dir().join(format!("orm_pool_{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let config = pool_config(3, Duration::from_secs(5));
+        let pool = Pool::new(path.to_str().unwrap(), config).unwrap();
+        User::create_table(&mut pool.get().unwrap()).unwrap();
+
+        let workers: Vec<_> = (0..6)
+            .map(|i| {
+                let pool = pool.clone();
+                thread::spawn(move || {
+                    let mut conn = pool.get().unwrap();
+                    let mut repo = Repository::<User>::new(&mut conn);
+                    for j in 0..5 {
+                        repo.create(&user(&format!("User{}_{}", i, j))).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        let mut conn = pool.get().unwrap();
+        let users = Repository::<User>::new(&mut conn).count(QueryBuilder::new("users"));
+        assert_eq!(users.unwrap(), 30);
+        assert!(pool.size() <= 3);
+        drop(conn);
+        drop(pool);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_optimistic_locking() {
+        let mut db = Database::open_in_memory().unwrap();
+        seed_users(&mut db);
+        Post::create_table(&mut db).unwrap();
+        let mut repo = Repository::<Post>::new(&mut db);
+        repo.create(&Post {
+            id: None,
+            title: "Draft".to_string(),
+            content: String::new(),
+            user_id: 1,
+            version: 0,
+            deleted_at: None,
+        })
+        .unwrap();
+
+        let mut mine = repo.find_by_id(1).unwrap();
+        let mut theirs = repo.find_by_id(1).unwrap();
+        mine.title = "Mine".to_string();
+        repo.update(&mut mine).unwrap();
+        assert_eq!(mine.version, 1);
+
+        theirs.title = "Theirs".to_string();
+        let err = repo.update(&mut theirs).unwrap_err();
+        assert!(matches!(err, DbError::Conflict(_)));
+        assert_eq!(err.to_string(), "Conflict: posts 1 changed since version 0");
+        let stored = repo.find_by_id(1).unwrap();
+        assert_eq!((stored.title.as_str(), stored.version), ("Mine", 1));
+
+        // Reload and retry
+        let mut theirs = stored;
+        theirs.title = "Theirs".to_string();
+        repo.update(&mut theirs).unwrap();
+        assert_eq!(repo.find_by_id(1).unwrap().version, 2);
+
+        repo.delete(1).unwrap();
+        assert!(matches!(repo.update(&mut theirs), Err(DbError::NotFound)));
+    }
+
+    #[cfg(feature = "derive")]
+    #[derive(Debug, Model)]
+    #[orm(table = "documents")]
+    struct Document {
+        #[orm(primary_key)]
+        id: Option<i64>,
+        body: String,
+        #[orm(version)]
+        revision: i64,
+        #[orm(soft_delete)]
+        deleted_at: Option<SystemTime>,
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn test_derived_version_column() {
+        assert_eq!(Document::version_column(), Some("revision"));
+        assert_eq!(Document::soft_delete_column(), Some("deleted_at"));
+        assert_eq!(Comment::version_column(), None);
+
+        let mut db = Database::open_in_memory().unwrap();
+        Document::create_table(&mut db).unwrap();
+        let mut repo = Repository::<Document>::new(&mut db);
+        repo.create(&Document {
+            id: None,
+            body: "v0".to_string(),
+            revision: 0,
+            deleted_at: None,
+        })
+        .unwrap();
+
+        let mut stale = repo.find_by_id(1).unwrap();
+        let mut fresh = repo.find_by_id(1).unwrap();
+        fresh.body = "v1".to_string();
+        repo.update(&mut fresh).unwrap();
+        assert_eq!(fresh.revision, 1);
+        assert!(matches!(repo.update(&mut stale), Err(DbError::Conflict(_))));
+
+        repo.delete(1).unwrap();
+        assert_eq!(repo.count(repo.query().with_trashed()).unwrap(), 1);
+        assert!(repo.find_all().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_schema_introspection() {
+        let mut db = Database::open_in_memory().unwrap();
+        User::create_table(&mut db).unwrap();
+        Post::create_table(&mut db).unwrap();
+        assert_eq!(db.tables().unwrap(), vec!["posts", "users"]);
+
+        let columns = db.columns("posts").unwrap();
+        let names: Vec<&str> = columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["id", "title", "content", "user_id", "version", "deleted_at"]);
+        assert_eq!(
+            columns[1],
+            ColumnInfo {
+                name: "title".to_string(),
+                sql_type: "TEXT".to_string(),
+                nullable: false,
+                primary_key: false,
+            }
+        );
+        assert!(columns[0].primary_key);
+        assert!(columns[2].nullable);
+        assert!(db.columns("missing").is_err());
+
+        // A table created in a rolled-back transaction is gone
+        let _ = db.transaction(|tx| {
+            tx.execute("CREATE TABLE scratch (id INTEGER PRIMARY KEY)", &[])?;
+            Err::<(), _>(DbError::ValidationError("undo".to_string()))
+        });
+        assert_eq!(db.tables().unwrap(), vec!["posts", "users"]);
+    }
+
+    #[test]
+    fn test_datetime_format() {
+        let leap_day = UNIX_EPOCH + Duration::from_millis(1_709_210_096_789);
+        assert_eq!(format_datetime(leap_day), "2024-02-29 12:34:56.789");
+        assert_eq!(parse_datetime("2024-02-29 12:34:56.789"), Some(leap_day));
+        assert_eq!(
+            parse_datetime("2024-02-29T12:34:56Z"),
+            Some(UNIX_EPOCH + Duration::from_secs(1_709_210_096))
+        );
+        assert_eq!(parse_datetime("1970-01-01"), Some(UNIX_EPOCH));
+
+        let before_epoch = UNIX_EPOCH - Duration::from_secs(1);
+        assert_eq!(format_datetime(before_epoch), "1969-12-31 23:59:59.000");
+        assert_eq!(parse_datetime("1969-12-31 23:59:59"), Some(before_epoch));
+
+        assert_eq!(parse_datetime("2024-13-01"), None);
+        assert_eq!(parse_datetime("yesterday"), None);
+        assert_eq!(Value::Integer(60).as_datetime(), Some(UNIX_EPOCH + Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn test_blob_and_datetime_values() {
+        let mut db = Database::open_in_memory().unwrap();
+        db.execute(
+            "CREATE TABLE files (id INTEGER PRIMARY KEY, data BLOB NOT NULL, created TEXT)",
+            &[],
+        )
+        .unwrap();
+        let created = UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+        let bytes = vec![0, 159, 146, 150, 255];
+        let mut row = HashMap::new();
+        row.insert("data".to_string(), Value::from(bytes.clone()));
+        row.insert("created".to_string(), Value::from(created));
+        db.insert("files", row).unwrap();
+
+        let later = Value::DateTime(created + Duration::from_secs(1));
+        assert!(db.query("SELECT * FROM files WHERE created > ?", &[later]).unwrap().is_empty());
+        let rows = db.query("SELECT * FROM files", &[]).unwrap();
+        assert_eq!(rows[0]["data"].as_blob(), Some(&bytes[..]));
+        assert_eq!(rows[0]["created"].as_datetime(), Some(created));
+        assert_eq!(Value::Blob(vec![0xAB, 1]).to_string(), "X'AB01'");
+    }
+
+    #[cfg(feature = "derive")]
+    #[derive(Debug, Model)]
+    struct Upload {
+        #[orm(primary_key)]
+        id: Option<i64>,
+        data: Vec<u8>,
+        thumbnail: Option<Vec<u8>>,
+        uploaded_at: SystemTime,
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn test_derived_blob_and_datetime_columns() {
+        assert_eq!(
+            Upload::create_table_sql(),
+            "CREATE TABLE uploads (id INTEGER PRIMARY KEY, data BLOB NOT NULL, thumbnail BLOB, \
+             uploaded_at TEXT NOT NULL)"
+        );
+
+        let mut db = Database::open_in_memory().unwrap();
+        Upload::create_table(&mut db).unwrap();
+        let mut repo = Repository::<Upload>::new(&mut db);
+        let uploaded_at = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        repo.create(&Upload { id: None, data: vec![1, 2, 3], thumbnail: None, uploaded_at })
+            .unwrap();
+        let stored = repo.find_by_id(1).unwrap();
+        assert_eq!(stored.data, vec![1, 2, 3]);
+        assert_eq!((stored.thumbnail, stored.uploaded_at), (None, uploaded_at));
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_async_repository() {
+        let db = AsyncDatabase::open_in_memory().await.unwrap();
+        db.call(User::create_table).await.unwrap();
+        let users = AsyncRepository::<User>::new(&db);
+
+        let creates: Vec<_> = ["Alice", "Bob", "Carol"]
+            .into_iter()
+            .map(|name| {
+                let users = AsyncRepository::<User>::new(&db);
+                tokio::spawn(async move { users.create(user(name)).await })
+            })
+            .collect();
+        for create in creates {
+            create.await.unwrap().unwrap();
+        }
+        assert_eq!(users.count(users.query()).await.unwrap(), 3);
+        assert_eq!(users.query().where_like("name", "%o%").fetch(&db).await.unwrap().len(), 2);
+
+        let all = users.find_all().await.unwrap();
+        let mut alice = all.into_iter().find(|u| u.name == "Alice").unwrap();
+        alice.age = 29;
+        let alice = users.update(alice).await.unwrap();
+        assert_eq!(users.find_by_id(alice.id.unwrap()).await.unwrap().age, 29);
+
+        let failed = db
+            .transaction(|tx| {
+                Repository::<User>::new(tx).create(&user("Dave"))?;
+                Err::<(), _>(DbError::ValidationError("changed my mind".to_string()))
+            })
+            .await;
+        assert!(failed.is_err());
+        assert_eq!(users.count(users.query()).await.unwrap(), 3);
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_async_call_panic_keeps_connection() {
+        let db = AsyncDatabase::open_in_memory().await.unwrap();
+        let handle = db.clone();
+        let panicked = tokio::spawn(async move {
+            handle.call(|_| -> Result<()> { panic!("boom") }).await
+        })
+        .await;
+        assert!(panicked.unwrap_err().is_panic());
+
+        db.call(User::create_table).await.unwrap();
+        assert!(db.query("SELECT * FROM users", Vec::new()).await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_soft_delete_sql() {
+        let posts = || QueryBuilder::new("posts").soft_deletes("deleted_at");
+        assert_eq!(
+            posts().where_eq("user_id", Value::Integer(1)).build().sql,
+            "SELECT * FROM posts WHERE user_id = ? AND deleted_at IS NULL"
+        );
+        assert_eq!(posts().with_trashed().build().sql, "SELECT * FROM posts");
+        assert_eq!(
+            posts().only_trashed().build_count().sql,
+            "SELECT COUNT(*) AS count FROM posts WHERE deleted_at IS NOT NULL"
+        );
+        // Without a soft-delete column the scopes change nothing
+        assert_eq!(QueryBuilder::new("users").only_trashed().build().sql, "SELECT * FROM users");
+    }
+
+    #[test]
+    fn test_soft_deletes() {
+        let mut db = Database::open_in_memory().unwrap();
+        seed_users(&mut db);
+        Post::create_table(&mut db).unwrap();
+        let mut repo = Repository::<Post>::new(&mut db);
+        for title in ["One", "Two", "Three"] {
+            repo.create(&Post {
+                id: None,
+                title: title.to_string(),
+                content: String::new(),
+                user_id: 1,
+                version: 0,
+                deleted_at: None,
+            })
+            .unwrap();
+        }
+
+        repo.delete(1).unwrap();
+        assert!(matches!(repo.find_by_id(1), Err(DbError::NotFound)));
+        assert!(matches!(repo.delete(1), Err(DbError::NotFound)));
+        assert_eq!(repo.find_all().unwrap().len(), 2);
+        assert_eq!(repo.count(repo.query()).unwrap(), 2);
+        assert_eq!(repo.count(repo.query().with_trashed()).unwrap(), 3);
+
+        let trashed = repo.db.fetch(&repo.query().only_trashed().build()).unwrap();
+        assert_eq!(trashed.len(), 1);
+        assert!(Post::from_row(&trashed[0]).unwrap().deleted_at.is_some());
+
+        repo.restore(1).unwrap();
+        assert!(matches!(repo.restore(1), Err(DbError::NotFound)));
+        assert_eq!(repo.find_by_id(1).unwrap().deleted_at, None);
+
+        repo.delete(2).unwrap();
+        repo.force_delete(2).unwrap();
+        assert_eq!(repo.count(repo.query().with_trashed()).unwrap(), 2);
+        let restored = Repository::<User>::new(repo.db).restore(1);
+        assert!(matches!(restored, Err(DbError::ValidationError(_))));
+    }
+
+    #[test]
+    fn test_named_scopes() {
+        let mut db = Database::open_in_memory().unwrap();
+        seed_users(&mut db);
+        let mut repo = Repository::<User>::new(&mut db);
+        repo.create(&User { age: 15, ..user("Dan") }).unwrap();
+        repo.create(&User { email: "eve@corp.test".to_string(), ..user("Eve") }).unwrap();
+
+        assert_eq!(repo.count(repo.query().scope(User::adults())).unwrap(), 4);
+        let query = repo
+            .query()
+            .scope(User::adults())
+            .scope(User::with_email_domain("example.com"));
+        assert_eq!(repo.count(query).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_values_never_reach_sql_text() {
+        let hostile = "x' OR '1'='1";
+        let query = QueryBuilder::new("users")
+            .where_eq("name", Value::Text(hostile.to_string()))
+            .where_lt("age", Value::Integer(40))
+            .build();
+
+        assert_eq!(query.sql, "SELECT * FROM users WHERE name = ? AND age < ?");
+        assert_eq!(
+            query.params,
+            vec![Value::Text(hostile.to_string()), Value::Integer(40)]
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_binds_hostile_values() {
+        let mut db = Database::open_in_memory().unwrap();
+        User::create_table(&mut db).unwrap();
+        Repository::<User>::new(&mut db)
+            .create(&User {
+                id: None,
+                name: "O'Brien".to_string(),
+                email: "ob@example.com".to_string(),
+                age: 50,
+            })
+            .unwrap();
+
+        let query = QueryBuilder::new("users")
+            .where_eq("name", Value::Text("x' OR '1'='1".to_string()))
+            .build();
+        assert!(db.fetch(&query).unwrap().is_empty());
+
+        let query = QueryBuilder::new("users")
+            .where_eq("name", Value::Text("O'Brien".to_string()))
+            .build();
+        assert_eq!(db.fetch(&query).unwrap().len(), 1);
+    }
 }
@@ -0,0 +1,227 @@
+/*!
+ * `#[derive(Model)]` for `database_orm.rs`
+ *
+ * Generates `table_name`, `from_row`, `to_row` and `create_table_sql` from
+ * a struct with named fields:
+ *
+ * ```ignore
+ * #[derive(Model)]
+ * #[orm(table = "comments")]          // default: snake_case name + "s"
+ * struct Comment {
+ *     #[orm(primary_key)]
+ *     id: Option<i64>,
+ *     #[orm(column = "body")]          // default: the field name
+ *     text: String,
+ *     rating: Option<f64>,             // Option means a nullable column
+ *     #[orm(version)]                  // optimistic locking, see below
+ *     version: i64,
+ *     #[orm(soft_delete)]              // soft deletes, see below
+ *     deleted_at: Option<SystemTime>,
+ * }
+ * ```
+ *
+ * `#[orm(version)]` marks an integer field as the model's
+ * `version_column`, which `Repository::update` checks and bumps.
+ * `#[orm(soft_delete)]` marks an `Option<SystemTime>` field as its
+ * `soft_delete_column`, which `Repository::delete` sets instead of removing
+ * the row.
+ *
+ * Field types must implement the ORM's `ColumnType` trait. The generated
+ * code names `Model`, `ColumnType`, `Value` and `Result` through `crate::`,
+ * so they must be reachable from the root of the crate using the derive.
+ */
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, Ident, LitStr, Type};
+
+#[proc_macro_derive(Model, attributes(orm))]
+pub fn derive_model(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+struct Column {
+    field: Ident,
+    ty: Type,
+    name: String,
+    primary_key: bool,
+    version: bool,
+    soft_delete: bool,
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let ident = &input.ident;
+
+    let mut table = format!("{}s", snake_case(&ident.to_string()));
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("orm")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("table") {
+                table = meta.value()?.parse::<LitStr>()?.value();
+                Ok(())
+            } else {
+                Err(meta.error("expected `table = \"...\"`"))
+            }
+        })?;
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(Error::new_spanned(ident, "Model needs a struct with named fields")),
+        },
+        _ => return Err(Error::new_spanned(ident, "Model can only be derived for structs")),
+    };
+
+    let mut columns = Vec::new();
+    for field in fields {
+        let field_ident = field.ident.clone().expect("named field");
+        let mut column = Column {
+            name: field_ident.to_string(),
+            field: field_ident,
+            ty: field.ty.clone(),
+            primary_key: false,
+            version: false,
+            soft_delete: false,
+        };
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("orm")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("primary_key") {
+                    column.primary_key = true;
+                    Ok(())
+                } else if meta.path.is_ident("version") {
+                    column.version = true;
+                    Ok(())
+                } else if meta.path.is_ident("soft_delete") {
+                    column.soft_delete = true;
+                    Ok(())
+                } else if meta.path.is_ident("column") {
+                    column.name = meta.value()?.parse::<LitStr>()?.value();
+                    Ok(())
+                } else {
+                    Err(meta.error(
+                        "expected `primary_key`, `version`, `soft_delete` or `column = \"...\"`",
+                    ))
+                }
+            })?;
+        }
+        if column.primary_key && columns.iter().any(|c: &Column| c.primary_key) {
+            return Err(Error::new_spanned(&field.ident, "only one field can be the primary key"));
+        }
+        if column.version && columns.iter().any(|c: &Column| c.version) {
+            return Err(Error::new_spanned(&field.ident, "only one field can be the version"));
+        }
+        if column.soft_delete && columns.iter().any(|c: &Column| c.soft_delete) {
+            return Err(Error::new_spanned(
+                &field.ident,
+                "only one field can be the soft-delete column",
+            ));
+        }
+        columns.push(column);
+    }
+
+    let from_row = columns.iter().map(|c| {
+        let (field, ty, name) = (&c.field, &c.ty, &c.name);
+        quote! { #field: <#ty as crate::ColumnType>::from_value(row.get(#name), #name)? }
+    });
+
+    let to_row = columns.iter().map(|c| {
+        let (field, name) = (&c.field, &c.name);
+        if c.primary_key {
+            // Left out while unset, so the database assigns it
+            quote! {
+                let value = crate::ColumnType::to_value(&self.#field);
+                if value != crate::Value::Null {
+                    row.insert(#name.to_string(), value);
+                }
+            }
+        } else {
+            quote! { row.insert(#name.to_string(), crate::ColumnType::to_value(&self.#field)); }
+        }
+    });
+
+    let definitions = columns.iter().map(|c| {
+        let (ty, name, primary_key) = (&c.ty, &c.name, c.primary_key);
+        quote! { <#ty as crate::ColumnType>::column_definition(#name, #primary_key) }
+    });
+
+    let version_column = columns.iter().find(|c| c.version).map(|c| {
+        let name = &c.name;
+        quote! {
+            fn version_column() -> ::std::option::Option<&'static str> {
+                ::std::option::Option::Some(#name)
+            }
+        }
+    });
+
+    let soft_delete_column = columns.iter().find(|c| c.soft_delete).map(|c| {
+        let name = &c.name;
+        quote! {
+            fn soft_delete_column() -> ::std::option::Option<&'static str> {
+                ::std::option::Option::Some(#name)
+            }
+        }
+    });
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics crate::Model for #ident #ty_generics #where_clause {
+            fn table_name() -> &'static str {
+                #table
+            }
+
+            fn from_row(
+                row: &::std::collections::HashMap<::std::string::String, crate::Value>,
+            ) -> crate::Result<Self> {
+                Ok(Self {
+                    #(#from_row,)*
+                })
+            }
+
+            fn to_row(&self) -> ::std::collections::HashMap<::std::string::String, crate::Value> {
+                let mut row = ::std::collections::HashMap::new();
+                #(#to_row)*
+                row
+            }
+
+            #version_column
+
+            #soft_delete_column
+
+            fn create_table_sql() -> ::std::string::String {
+                let columns: ::std::vec::Vec<::std::string::String> = vec![#(#definitions),*];
+                format!("CREATE TABLE {} ({})", #table, columns.join(", "))
+            }
+        }
+    })
+}
+
+/// `BlogPost` -> `blog_post`
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snake_case() {
+        assert_eq!(snake_case("User"), "user");
+        assert_eq!(snake_case("BlogPost"), "blog_post");
+    }
+}
@@ -7,7 +7,9 @@
  * - Recursive descent parser
  * - Expression evaluation
  * - Operator precedence handling
- * - Error reporting with position tracking
+ * - Variables bound with `let`, kept between lines in interactive mode
+ * - Exact rational arithmetic with `--exact`, falling back to floats when needed
+ * - Error reporting with a caret under the offending character or token
  * 
  * # Compile and Run
  * ```bash
@@ -17,6 +19,24 @@
  * # Interactive mode:
  * ./lexer_parser "3 + 4 * 2"
  * ./lexer_parser "(5 + 3) * 2 - 4"
+ * ./lexer_parser "let r = 2; 3.14 * r ^ 2"
+ *
+ * # Exact mode: 1/3 * 3 is exactly 1, 0.1 + 0.2 is exactly 3/10
+ * ./lexer_parser --exact "1/3 * 3"
+ * ```
+ *
+ * # Arbitrary Precision
+ * Plain `rustc` builds exact mode on i128 fractions, which fall back to
+ * floats when they overflow. Built as a Cargo project with the
+ * `num-rational` feature, exact mode uses num-rational's `BigRational`
+ * instead, so `10 ^ 40` and `1/3 ^ 100` stay exact:
+ * ```toml
+ * [dependencies]
+ * num-bigint = { version = "0.4", optional = true }
+ * num-rational = { version = "0.4", optional = true }
+ * num-traits = { version = "0.2", optional = true }
+ * [features]
+ * num-rational = ["dep:num-rational", "dep:num-bigint", "dep:num-traits"]
  * ```
  * 
  * # Supported Operations
@@ -27,12 +47,21 @@
  * - Exponentiation: ^
  * - Parentheses: ( )
  * - Unary minus: -x
+ * - Variables: let x = expr; x * 2
  */
 
+use std::collections::HashMap;
 use std::fmt;
 use std::env;
 use std::io::{self, Write};
 
+#[cfg(feature = "num-rational")]
+use num_bigint::BigInt;
+#[cfg(feature = "num-rational")]
+use num_rational::BigRational;
+#[cfg(feature = "num-rational")]
+use num_traits::{Pow, Signed, ToPrimitive, Zero};
+
 // ============================================================================
 // Token Types
 // ============================================================================
@@ -40,6 +69,10 @@ use std::io::{self, Write};
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
     Number(f64),
+    Identifier(String),
+    Let,
+    Equals,
+    Semicolon,
     Plus,
     Minus,
     Star,
@@ -54,6 +87,10 @@ impl fmt::Display for Token {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Token::Number(n) => write!(f, "{}", n),
+            Token::Identifier(name) => write!(f, "{}", name),
+            Token::Let => write!(f, "let"),
+            Token::Equals => write!(f, "="),
+            Token::Semicolon => write!(f, ";"),
             Token::Plus => write!(f, "+"),
             Token::Minus => write!(f, "-"),
             Token::Star => write!(f, "*"),
@@ -66,6 +103,53 @@ impl fmt::Display for Token {
     }
 }
 
+// ============================================================================
+// Errors
+// ============================================================================
+
+/// A lexer or parser error at a byte offset into the source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub offset: usize,
+}
+
+impl ParseError {
+    pub fn new(message: impl Into<String>, offset: usize) -> Self {
+        ParseError {
+            message: message.into(),
+            offset,
+        }
+    }
+
+    /// The source with a caret under the error and the message beside it:
+    ///
+    /// ```text
+    /// 3 + * 4
+    ///     ^ Unexpected token '*'
+    /// ```
+    pub fn render(&self, source: &str) -> String {
+        let column = source
+            .get(..self.offset)
+            .map_or(source.chars().count(), |before| before.chars().count());
+        format!("{}\n{}^ {}", source, " ".repeat(column), self.message)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (at byte {})", self.message, self.offset)
+    }
+}
+
+/// How a token is named in error messages.
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Eof => "end of input".to_string(),
+        token => format!("'{}'", token),
+    }
+}
+
 // ============================================================================
 // Lexer
 // ============================================================================
@@ -73,6 +157,8 @@ impl fmt::Display for Token {
 pub struct Lexer {
     input: Vec<char>,
     position: usize,
+    /// Byte offset of `current_char` in the original string
+    offset: usize,
     current_char: Option<char>,
 }
 
@@ -84,11 +170,15 @@ impl Lexer {
         Lexer {
             input: chars,
             position: 0,
+            offset: 0,
             current_char,
         }
     }
 
     fn advance(&mut self) {
+        if let Some(ch) = self.current_char {
+            self.offset += ch.len_utf8();
+        }
         self.position += 1;
         self.current_char = self.input.get(self.position).copied();
     }
@@ -107,7 +197,8 @@ impl Lexer {
         }
     }
 
-    fn read_number(&mut self) -> Result<f64, String> {
+    fn read_number(&mut self) -> Result<f64, ParseError> {
+        let start = self.offset;
         let mut num_str = String::new();
         let mut has_dot = false;
 
@@ -125,11 +216,36 @@ impl Lexer {
         }
 
         num_str.parse::<f64>()
-            .map_err(|_| format!("Invalid number: {}", num_str))
+            .map_err(|_| ParseError::new(format!("Invalid number: {}", num_str), start))
+    }
+
+    fn read_identifier(&mut self) -> String {
+        let mut name = String::new();
+
+        while let Some(ch) = self.current_char {
+            if ch.is_alphanumeric() || ch == '_' {
+                name.push(ch);
+                self.advance();
+            } else {
+                break;
+            }
+        }
+
+        name
+    }
+
+    pub fn next_token(&mut self) -> Result<Token, ParseError> {
+        self.next_spanned().map(|(token, _)| token)
     }
 
-    pub fn next_token(&mut self) -> Result<Token, String> {
+    /// The next token and the byte offset it starts at.
+    pub fn next_spanned(&mut self) -> Result<(Token, usize), ParseError> {
         self.skip_whitespace();
+        let start = self.offset;
+        self.next_at().map(|token| (token, start))
+    }
+
+    fn next_at(&mut self) -> Result<Token, ParseError> {
 
         match self.current_char {
             None => Ok(Token::Eof),
@@ -138,6 +254,11 @@ impl Lexer {
                     return Ok(Token::Number(self.read_number()?));
                 }
 
+                if ch.is_alphabetic() || ch == '_' {
+                    let name = self.read_identifier();
+                    return Ok(if name == "let" { Token::Let } else { Token::Identifier(name) });
+                }
+
                 let token = match ch {
                     '+' => Token::Plus,
                     '-' => Token::Minus,
@@ -146,7 +267,14 @@ impl Lexer {
                     '^' => Token::Caret,
                     '(' => Token::LeftParen,
                     ')' => Token::RightParen,
-                    _ => return Err(format!("Unexpected character: '{}'", ch)),
+                    '=' => Token::Equals,
+                    ';' => Token::Semicolon,
+                    _ => {
+                        return Err(ParseError::new(
+                            format!("Unexpected character '{}'", ch),
+                            self.offset,
+                        ))
+                    }
                 };
 
                 self.advance();
@@ -155,16 +283,22 @@ impl Lexer {
         }
     }
 
-    pub fn tokenize(&mut self) -> Result<Vec<Token>, String> {
+    pub fn tokenize(&mut self) -> Result<Vec<Token>, ParseError> {
+        let tokens = self.tokenize_spanned()?;
+        Ok(tokens.into_iter().map(|(token, _)| token).collect())
+    }
+
+    /// Like `tokenize`, keeping each token's byte offset for error reporting.
+    pub fn tokenize_spanned(&mut self) -> Result<Vec<(Token, usize)>, ParseError> {
         let mut tokens = Vec::new();
         
         loop {
-            let token = self.next_token()?;
+            let (token, offset) = self.next_spanned()?;
             if token == Token::Eof {
-                tokens.push(token);
+                tokens.push((token, offset));
                 break;
             }
-            tokens.push(token);
+            tokens.push((token, offset));
         }
 
         Ok(tokens)
@@ -178,6 +312,7 @@ impl Lexer {
 #[derive(Debug, Clone, PartialEq)]
 pub enum AstNode {
     Number(f64),
+    Variable(String),
     BinaryOp {
         op: BinaryOperator,
         left: Box<AstNode>,
@@ -189,6 +324,12 @@ pub enum AstNode {
     },
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+    Let { name: String, value: AstNode },
+    Expression(AstNode),
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum BinaryOperator {
     Add,
@@ -207,6 +348,7 @@ impl fmt::Display for AstNode {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             AstNode::Number(n) => write!(f, "{}", n),
+            AstNode::Variable(name) => write!(f, "{}", name),
             AstNode::BinaryOp { op, left, right } => {
                 write!(f, "({} {} {})", left, op, right)
             }
@@ -217,6 +359,15 @@ impl fmt::Display for AstNode {
     }
 }
 
+impl fmt::Display for Statement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Statement::Let { name, value } => write!(f, "(let {} {})", name, value),
+            Statement::Expression(node) => write!(f, "{}", node),
+        }
+    }
+}
+
 impl fmt::Display for BinaryOperator {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -243,13 +394,27 @@ impl fmt::Display for UnaryOperator {
 
 pub struct Parser {
     tokens: Vec<Token>,
+    /// Byte offset of each token; empty when built without offsets
+    offsets: Vec<usize>,
     position: usize,
 }
 
 impl Parser {
+    /// A parser whose errors all point at the start of the input. Use
+    /// `with_offsets` to have them point at the offending token.
     pub fn new(tokens: Vec<Token>) -> Self {
         Parser {
             tokens,
+            offsets: Vec::new(),
+            position: 0,
+        }
+    }
+
+    pub fn with_offsets(tokens: Vec<(Token, usize)>) -> Self {
+        let (tokens, offsets) = tokens.into_iter().unzip();
+        Parser {
+            tokens,
+            offsets,
             position: 0,
         }
     }
@@ -258,28 +423,80 @@ impl Parser {
         self.tokens.get(self.position).unwrap_or(&Token::Eof)
     }
 
+    fn error(&self, message: String) -> ParseError {
+        let offset = self
+            .offsets
+            .get(self.position)
+            .or(self.offsets.last())
+            .copied()
+            .unwrap_or(0);
+        ParseError::new(message, offset)
+    }
+
     fn advance(&mut self) {
         if self.position < self.tokens.len() {
             self.position += 1;
         }
     }
 
-    fn expect(&mut self, expected: Token) -> Result<(), String> {
+    fn expect(&mut self, expected: Token) -> Result<(), ParseError> {
         if self.current_token() == &expected {
             self.advance();
             Ok(())
         } else {
-            Err(format!("Expected {:?}, found {:?}", expected, self.current_token()))
+            Err(self.error(format!(
+                "Expected {}, found {}",
+                describe(&expected),
+                describe(self.current_token())
+            )))
         }
     }
 
     /// Parse entry point
     /// Grammar: expression -> term ((PLUS | MINUS) term)*
-    pub fn parse(&mut self) -> Result<AstNode, String> {
+    pub fn parse(&mut self) -> Result<AstNode, ParseError> {
         self.parse_expression()
     }
 
-    fn parse_expression(&mut self) -> Result<AstNode, String> {
+    /// Grammar: program -> statement (SEMICOLON statement)* SEMICOLON? EOF
+    pub fn parse_program(&mut self) -> Result<Vec<Statement>, ParseError> {
+        let mut statements = Vec::new();
+
+        while self.current_token() != &Token::Eof {
+            statements.push(self.parse_statement()?);
+
+            if self.current_token() == &Token::Semicolon {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+
+        self.expect(Token::Eof)?;
+        Ok(statements)
+    }
+
+    /// Grammar: statement -> LET IDENTIFIER EQUALS expression | expression
+    fn parse_statement(&mut self) -> Result<Statement, ParseError> {
+        if self.current_token() != &Token::Let {
+            return Ok(Statement::Expression(self.parse_expression()?));
+        }
+        self.advance();
+
+        let name = match self.current_token() {
+            Token::Identifier(name) => name.clone(),
+            token => {
+                return Err(self.error(format!("Expected variable name, found {}", describe(token))))
+            }
+        };
+        self.advance();
+        self.expect(Token::Equals)?;
+
+        let value = self.parse_expression()?;
+        Ok(Statement::Let { name, value })
+    }
+
+    fn parse_expression(&mut self) -> Result<AstNode, ParseError> {
         let mut node = self.parse_term()?;
 
         while matches!(self.current_token(), Token::Plus | Token::Minus) {
@@ -302,7 +519,7 @@ impl Parser {
     }
 
     /// Grammar: term -> factor ((STAR | SLASH) factor)*
-    fn parse_term(&mut self) -> Result<AstNode, String> {
+    fn parse_term(&mut self) -> Result<AstNode, ParseError> {
         let mut node = self.parse_power()?;
 
         while matches!(self.current_token(), Token::Star | Token::Slash) {
@@ -326,7 +543,7 @@ impl Parser {
 
     /// Grammar: power -> unary (CARET unary)*
     /// Right-associative: 2^3^4 = 2^(3^4)
-    fn parse_power(&mut self) -> Result<AstNode, String> {
+    fn parse_power(&mut self) -> Result<AstNode, ParseError> {
         let mut node = self.parse_unary()?;
 
         if matches!(self.current_token(), Token::Caret) {
@@ -343,7 +560,7 @@ impl Parser {
     }
 
     /// Grammar: unary -> (PLUS | MINUS) unary | primary
-    fn parse_unary(&mut self) -> Result<AstNode, String> {
+    fn parse_unary(&mut self) -> Result<AstNode, ParseError> {
         match self.current_token() {
             Token::Minus => {
                 self.advance();
@@ -361,59 +578,385 @@ impl Parser {
         }
     }
 
-    /// Grammar: primary -> NUMBER | LPAREN expression RPAREN
-    fn parse_primary(&mut self) -> Result<AstNode, String> {
+    /// Grammar: primary -> NUMBER | IDENTIFIER | LPAREN expression RPAREN
+    fn parse_primary(&mut self) -> Result<AstNode, ParseError> {
         match self.current_token() {
             Token::Number(n) => {
                 let num = *n;
                 self.advance();
                 Ok(AstNode::Number(num))
             }
+            Token::Identifier(name) => {
+                let node = AstNode::Variable(name.clone());
+                self.advance();
+                Ok(node)
+            }
             Token::LeftParen => {
                 self.advance();
                 let node = self.parse_expression()?;
                 self.expect(Token::RightParen)?;
                 Ok(node)
             }
-            token => Err(format!("Unexpected token: {:?}", token)),
+            Token::Eof => Err(self.error("Unexpected end of input".to_string())),
+            token => Err(self.error(format!("Unexpected token {}", describe(token)))),
+        }
+    }
+}
+
+// ============================================================================
+// Numbers
+// ============================================================================
+
+/// A fraction in lowest terms with a positive denominator. Every operation
+/// returns `None` on overflow so the caller can fall back to floats.
+#[cfg(not(feature = "num-rational"))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rational {
+    numer: i128,
+    denom: i128,
+}
+
+#[cfg(not(feature = "num-rational"))]
+fn gcd(a: i128, b: i128) -> i128 {
+    // Unsigned, so i128::MIN has an absolute value
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a as i128
+}
+
+#[cfg(not(feature = "num-rational"))]
+impl Rational {
+    pub fn new(numer: i128, denom: i128) -> Option<Self> {
+        if denom == 0 {
+            return None;
+        }
+        let divisor = gcd(numer, denom);
+        let sign = if denom < 0 { -1 } else { 1 };
+        Some(Rational {
+            numer: (numer / divisor).checked_mul(sign)?,
+            denom: (denom / divisor).checked_mul(sign)?,
+        })
+    }
+
+    pub fn integer(n: i128) -> Self {
+        Rational { numer: n, denom: 1 }
+    }
+
+    /// The decimal `n` is written as, so 0.1 becomes 1/10 rather than the
+    /// binary fraction the float actually holds.
+    pub fn from_f64(n: f64) -> Option<Self> {
+        let (numer, fraction_digits) = decimal_digits(n)?;
+        let denom = 10i128.checked_pow(fraction_digits)?;
+        Rational::new(numer.parse().ok()?, denom)
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.numer as f64 / self.denom as f64
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.denom == 1
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        let numer = self
+            .numer
+            .checked_mul(other.denom)?
+            .checked_add(other.numer.checked_mul(self.denom)?)?;
+        Rational::new(numer, self.denom.checked_mul(other.denom)?)
+    }
+
+    fn checked_neg(&self) -> Option<Self> {
+        Some(Rational { numer: self.numer.checked_neg()?, denom: self.denom })
+    }
+
+    fn checked_sub(&self, other: &Self) -> Option<Self> {
+        self.checked_add(&other.checked_neg()?)
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        Rational::new(
+            self.numer.checked_mul(other.numer)?,
+            self.denom.checked_mul(other.denom)?,
+        )
+    }
+
+    fn checked_div(&self, other: &Self) -> Option<Self> {
+        Rational::new(
+            self.numer.checked_mul(other.denom)?,
+            self.denom.checked_mul(other.numer)?,
+        )
+    }
+
+    /// Integer exponents only; anything else is left to floats.
+    fn checked_pow(&self, exponent: &Self) -> Option<Self> {
+        if !exponent.is_integer() {
+            return None;
+        }
+        let power = u32::try_from(exponent.numer.unsigned_abs()).ok()?;
+        let base = if exponent.numer < 0 {
+            Rational::new(self.denom, self.numer)?
+        } else {
+            *self
+        };
+        Some(Rational {
+            numer: base.numer.checked_pow(power)?,
+            denom: base.denom.checked_pow(power)?,
+        })
+    }
+}
+
+#[cfg(not(feature = "num-rational"))]
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_integer() {
+            write!(f, "{}", self.numer)
+        } else {
+            write!(f, "{}/{}", self.numer, self.denom)
+        }
+    }
+}
+
+/// An arbitrary-precision fraction from num-rational. Nothing overflows, so
+/// results only fall back to floats for fractional powers and for integer
+/// powers above `MAX_EXACT_POWER`, which would take unbounded time.
+#[cfg(feature = "num-rational")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rational(BigRational);
+
+#[cfg(feature = "num-rational")]
+const MAX_EXACT_POWER: u32 = 10_000;
+
+#[cfg(feature = "num-rational")]
+impl Rational {
+    pub fn new(numer: i128, denom: i128) -> Option<Self> {
+        if denom == 0 {
+            return None;
+        }
+        Some(Rational(BigRational::new(numer.into(), denom.into())))
+    }
+
+    pub fn integer(n: i128) -> Self {
+        Rational(BigRational::from_integer(n.into()))
+    }
+
+    /// The decimal `n` is written as, so 0.1 becomes 1/10 rather than the
+    /// binary fraction the float actually holds.
+    pub fn from_f64(n: f64) -> Option<Self> {
+        let (numer, fraction_digits) = decimal_digits(n)?;
+        let numer: BigInt = numer.parse().ok()?;
+        let denom = BigInt::from(10).pow(fraction_digits);
+        Some(Rational(BigRational::new(numer, denom)))
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.0.to_f64().unwrap_or(f64::NAN)
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.0.is_integer()
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(Rational(&self.0 + &other.0))
+    }
+
+    fn checked_neg(&self) -> Option<Self> {
+        Some(Rational(-&self.0))
+    }
+
+    fn checked_sub(&self, other: &Self) -> Option<Self> {
+        Some(Rational(&self.0 - &other.0))
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        Some(Rational(&self.0 * &other.0))
+    }
+
+    fn checked_div(&self, other: &Self) -> Option<Self> {
+        if other.0.is_zero() {
+            return None;
+        }
+        Some(Rational(&self.0 / &other.0))
+    }
+
+    /// Integer exponents only; anything else is left to floats.
+    fn checked_pow(&self, exponent: &Self) -> Option<Self> {
+        if !exponent.is_integer() {
+            return None;
+        }
+        let power = exponent.0.to_integer();
+        let magnitude = power.magnitude().to_u32().filter(|&p| p <= MAX_EXACT_POWER)?;
+        let base = if power.is_negative() {
+            if self.0.is_zero() {
+                return None;
+            }
+            self.0.recip()
+        } else {
+            self.0.clone()
+        };
+        Some(Rational(base.pow(magnitude)))
+    }
+}
+
+#[cfg(feature = "num-rational")]
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The digits of `n` as written in decimal with the point removed, and how
+/// many of them follow the point
+fn decimal_digits(n: f64) -> Option<(String, u32)> {
+    if !n.is_finite() {
+        return None;
+    }
+    let text = n.to_string();
+    let (whole, fraction) = text.split_once('.').unwrap_or((&text, ""));
+    Some((format!("{}{}", whole, fraction), fraction.len() as u32))
+}
+
+/// The result of evaluating an expression. In exact mode numbers stay
+/// `Exact` until an operation has no exact answer (a fractional power) or
+/// overflows, and then continue as `Float`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Exact(Rational),
+    Float(f64),
+}
+
+impl Value {
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Value::Exact(r) => r.to_f64(),
+            Value::Float(n) => *n,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Exact(r) => write!(f, "{}", r),
+            Value::Float(n) => write!(f, "{}", n),
         }
     }
 }
 
+fn apply(op: BinaryOperator, left: Value, right: Value) -> Result<Value, String> {
+    if right.to_f64() == 0.0 && op == BinaryOperator::Divide {
+        return Err("Division by zero".to_string());
+    }
+
+    if let (Value::Exact(a), Value::Exact(b)) = (&left, &right) {
+        let exact = match op {
+            BinaryOperator::Add => a.checked_add(b),
+            BinaryOperator::Subtract => a.checked_sub(b),
+            BinaryOperator::Multiply => a.checked_mul(b),
+            BinaryOperator::Divide => a.checked_div(b),
+            BinaryOperator::Power => a.checked_pow(b),
+        };
+        if let Some(result) = exact {
+            return Ok(Value::Exact(result));
+        }
+    }
+
+    let (a, b) = (left.to_f64(), right.to_f64());
+    Ok(Value::Float(match op {
+        BinaryOperator::Add => a + b,
+        BinaryOperator::Subtract => a - b,
+        BinaryOperator::Multiply => a * b,
+        BinaryOperator::Divide => a / b,
+        BinaryOperator::Power => a.powf(b),
+    }))
+}
+
 // ============================================================================
 // Evaluator
 // ============================================================================
 
+/// Variables bound with `let`, and whether numbers are exact. Interactive
+/// mode keeps one for the whole session so later lines can use earlier
+/// results.
+#[derive(Debug, Default)]
+pub struct Environment {
+    variables: HashMap<String, Value>,
+    exact: bool,
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number literals become fractions, so `1/3 * 3` is exactly 1.
+    pub fn exact() -> Self {
+        Environment { exact: true, ..Self::default() }
+    }
+
+    pub fn get(&self, name: &str) -> Option<Value> {
+        self.variables.get(name).cloned()
+    }
+
+    pub fn set(&mut self, name: String, value: Value) {
+        self.variables.insert(name, value);
+    }
+}
+
+/// Evaluates an expression that uses no variables, in floating point.
 pub fn evaluate(node: &AstNode) -> Result<f64, String> {
+    evaluate_in(node, &Environment::new()).map(|value| value.to_f64())
+}
+
+pub fn evaluate_in(node: &AstNode, env: &Environment) -> Result<Value, String> {
     match node {
-        AstNode::Number(n) => Ok(*n),
+        AstNode::Number(n) => Ok(match Rational::from_f64(*n) {
+            Some(r) if env.exact => Value::Exact(r),
+            _ => Value::Float(*n),
+        }),
+        AstNode::Variable(name) => env
+            .get(name)
+            .ok_or_else(|| format!("Undefined variable: {}", name)),
         AstNode::BinaryOp { op, left, right } => {
-            let left_val = evaluate(left)?;
-            let right_val = evaluate(right)?;
-
-            match op {
-                BinaryOperator::Add => Ok(left_val + right_val),
-                BinaryOperator::Subtract => Ok(left_val - right_val),
-                BinaryOperator::Multiply => Ok(left_val * right_val),
-                BinaryOperator::Divide => {
-                    if right_val == 0.0 {
-                        Err("Division by zero".to_string())
-                    } else {
-                        Ok(left_val / right_val)
-                    }
-                }
-                BinaryOperator::Power => Ok(left_val.powf(right_val)),
-            }
+            let left_val = evaluate_in(left, env)?;
+            let right_val = evaluate_in(right, env)?;
+            apply(*op, left_val, right_val)
         }
         AstNode::UnaryOp { op, operand } => {
-            let val = evaluate(operand)?;
-            match op {
-                UnaryOperator::Negate => Ok(-val),
+            let val = evaluate_in(operand, env)?;
+            match (op, val) {
+                (UnaryOperator::Negate, Value::Exact(r)) => Ok(r
+                    .checked_neg()
+                    .map_or(Value::Float(-r.to_f64()), Value::Exact)),
+                (UnaryOperator::Negate, Value::Float(n)) => Ok(Value::Float(-n)),
             }
         }
     }
 }
 
+/// Runs statements in order and returns the value of the last one; a `let`
+/// evaluates to the value it binds.
+pub fn execute(program: &[Statement], env: &mut Environment) -> Result<Option<Value>, String> {
+    let mut last = None;
+
+    for statement in program {
+        let value = match statement {
+            Statement::Let { name, value } => {
+                let value = evaluate_in(value, env)?;
+                env.set(name.clone(), value.clone());
+                value
+            }
+            Statement::Expression(node) => evaluate_in(node, env)?,
+        };
+        last = Some(value);
+    }
+
+    Ok(last)
+}
+
 // ============================================================================
 // Pretty Printer
 // ============================================================================
@@ -425,6 +968,9 @@ pub fn print_ast(node: &AstNode, indent: usize) {
         AstNode::Number(n) => {
             println!("{}Number({})", prefix, n);
         }
+        AstNode::Variable(name) => {
+            println!("{}Variable({})", prefix, name);
+        }
         AstNode::BinaryOp { op, left, right } => {
             println!("{}BinaryOp({:?})", prefix, op);
             print_ast(left, indent + 1);
@@ -437,25 +983,35 @@ pub fn print_ast(node: &AstNode, indent: usize) {
     }
 }
 
+pub fn print_statement(statement: &Statement) {
+    match statement {
+        Statement::Let { name, value } => {
+            println!("Let({})", name);
+            print_ast(value, 1);
+        }
+        Statement::Expression(node) => print_ast(node, 0),
+    }
+}
+
 // ============================================================================
 // CLI Interface
 // ============================================================================
 
-fn process_expression(expr: &str) {
+fn process_expression(expr: &str, env: &mut Environment) {
     println!("\n📝 Expression: {}", expr);
     
     // Lexing
     let mut lexer = Lexer::new(expr);
-    let tokens = match lexer.tokenize() {
+    let tokens = match lexer.tokenize_spanned() {
         Ok(t) => t,
         Err(e) => {
-            println!("❌ Lexer error: {}", e);
+            println!("❌ Lexer error:\n{}", e.render(expr));
             return;
         }
     };
     
     print!("🔤 Tokens: ");
-    for (i, token) in tokens.iter().enumerate() {
+    for (i, (token, _)) in tokens.iter().enumerate() {
         if i > 0 && token != &Token::Eof {
             print!(", ");
         }
@@ -466,38 +1022,60 @@ fn process_expression(expr: &str) {
     println!();
     
     // Parsing
-    let mut parser = Parser::new(tokens);
-    let ast = match parser.parse() {
-        Ok(a) => a,
+    let mut parser = Parser::with_offsets(tokens);
+    let program = match parser.parse_program() {
+        Ok(p) => p,
         Err(e) => {
-            println!("❌ Parser error: {}", e);
+            println!("❌ Parser error:\n{}", e.render(expr));
             return;
         }
     };
     
     println!("\n🌳 Abstract Syntax Tree:");
-    print_ast(&ast, 0);
+    for statement in &program {
+        print_statement(statement);
+    }
     
-    println!("\n📐 S-Expression: {}", ast);
+    for statement in &program {
+        println!("\n📐 S-Expression: {}", statement);
+    }
     
     // Evaluation
-    match evaluate(&ast) {
-        Ok(result) => {
+    match execute(&program, env) {
+        Ok(Some(Value::Exact(r))) if !r.is_integer() => {
+            println!("\n✅ Result: {} (≈ {})", r, r.to_f64());
+        }
+        Ok(Some(result)) => {
             println!("\n✅ Result: {}", result);
         }
+        Ok(None) => {}
         Err(e) => {
             println!("\n❌ Evaluation error: {}", e);
         }
     }
 }
 
-fn interactive_mode() {
+fn new_environment(exact: bool) -> Environment {
+    if exact {
+        Environment::exact()
+    } else {
+        Environment::new()
+    }
+}
+
+fn interactive_mode(exact: bool) {
     println!("🔢 Expression Parser & Evaluator");
     println!("=================================\n");
     println!("Enter arithmetic expressions to evaluate.");
     println!("Supported operators: +, -, *, /, ^ (power), ( )");
+    println!("Bind variables with 'let x = 2 * 3' and use them on later lines.");
+    if exact {
+        println!("Exact mode: results are fractions unless a float is unavoidable.");
+    }
     println!("Type 'quit' or 'exit' to quit.\n");
 
+    let mut env = new_environment(exact);
+
     loop {
         print!("> ");
         io::stdout().flush().unwrap();
@@ -515,11 +1093,11 @@ fn interactive_mode() {
             break;
         }
 
-        process_expression(input);
+        process_expression(input, &mut env);
     }
 }
 
-fn run_tests() {
+fn run_tests(exact: bool) {
     println!("🧪 Running Test Cases\n");
     println!("{:=^60}", "");
     
@@ -539,30 +1117,39 @@ fn run_tests() {
         "100 / 10 / 2",
         "3.14 * 2",
         "-(4 + 5)",
+        "let r = 2; 3.14 * r ^ 2",
+        "(1 + 2",
+        "3 + * 4",
+        "1 / 3 * 3",
+        "0.1 + 0.2",
+        "2 ^ 0.5",
     ];
 
     for expr in test_cases {
-        process_expression(expr);
+        process_expression(expr, &mut new_environment(exact));
         println!();
     }
 }
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+    let exact = args.iter().skip(1).any(|arg| arg == "--exact");
+    args.retain(|arg| arg != "--exact");
 
     if args.len() == 1 {
-        interactive_mode();
+        interactive_mode(exact);
     } else if args.len() == 2 {
         if args[1] == "test" {
-            run_tests();
+            run_tests(exact);
         } else {
-            process_expression(&args[1]);
+            process_expression(&args[1], &mut new_environment(exact));
         }
     } else {
         println!("Usage:");
         println!("  {}              # Interactive mode", args[0]);
         println!("  {} <expression>  # Evaluate expression", args[0]);
         println!("  {} test         # Run test cases", args[0]);
+        println!("  Add --exact to any of these for rational arithmetic");
     }
 }
 
@@ -627,4 +1214,61 @@ mod tests {
         let result = evaluate(&ast).unwrap();
         assert_eq!(result, -2.0);
     }
+
+    #[test]
+    fn test_let_bindings() {
+        let mut lexer = Lexer::new("let x = 4; let y = x * 2; y - x");
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse_program().unwrap();
+        let mut env = Environment::new();
+        assert_eq!(execute(&program, &mut env).unwrap(), Some(Value::Float(4.0)));
+        assert_eq!(env.get("y"), Some(Value::Float(8.0)));
+    }
+
+    #[test]
+    fn test_environment_persists() {
+        let mut env = Environment::new();
+        for (line, expected) in [("let total = 10", 10.0), ("total / 4", 2.5)] {
+            let tokens = Lexer::new(line).tokenize().unwrap();
+            let program = Parser::new(tokens).parse_program().unwrap();
+            assert_eq!(execute(&program, &mut env).unwrap(), Some(Value::Float(expected)));
+        }
+
+        let tokens = Lexer::new("missing + 1").tokenize().unwrap();
+        let program = Parser::new(tokens).parse_program().unwrap();
+        assert!(execute(&program, &mut env).is_err());
+    }
+
+    #[test]
+    fn test_error_offsets() {
+        let source = "2 * (3 + × 4)";
+        let tokens = Lexer::new(source).tokenize_spanned();
+        let error = tokens.unwrap_err();
+        assert_eq!(error.offset, 9);
+        assert_eq!(error.render(source), "2 * (3 + × 4)\n         ^ Unexpected character '×'");
+
+        let tokens = Lexer::new("1 + (2 * 3").tokenize_spanned().unwrap();
+        let error = Parser::with_offsets(tokens).parse_program().unwrap_err();
+        assert_eq!(error.message, "Expected ')', found end of input");
+        assert_eq!(error.offset, 10);
+    }
+
+    fn eval_exact(source: &str) -> Value {
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        let program = Parser::new(tokens).parse_program().unwrap();
+        execute(&program, &mut Environment::exact()).unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_exact_mode() {
+        assert_eq!(eval_exact("1/3 * 3"), Value::Exact(Rational::integer(1)));
+        assert_eq!(eval_exact("0.1 + 0.2"), Value::Exact(Rational::new(3, 10).unwrap()));
+        assert_eq!(eval_exact("(2/3) ^ -2"), Value::Exact(Rational::new(9, 4).unwrap()));
+        assert_eq!(eval_exact("4 ^ 0.5"), Value::Float(2.0));
+        #[cfg(not(feature = "num-rational"))]
+        assert!(matches!(eval_exact("10 ^ 40"), Value::Float(_)));
+        #[cfg(feature = "num-rational")]
+        assert_eq!(eval_exact("10 ^ 40").to_string(), format!("1{}", "0".repeat(40)));
+    }
 }
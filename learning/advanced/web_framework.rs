@@ -5,6 +5,7 @@
  * - HTTP server with request parsing
  * - Route matching and handlers
  * - Middleware support
+ * - CORS with per-route configuration and automatic preflight handling
  * - JSON response helpers
  * - Query parameter parsing
  * 
@@ -21,6 +22,8 @@
  * curl http://localhost:8080/json
  * curl http://localhost:8080/echo?msg=Hello
  * curl -X POST http://localhost:8080/data -d "test data"
+ * curl -i -X OPTIONS http://localhost:8080/api/items \
+ *      -H "Origin: http://localhost:3000" -H "Access-Control-Request-Method: POST"
  * ```
  */
 
@@ -40,6 +43,7 @@ pub enum Method {
     PUT,
     DELETE,
     PATCH,
+    OPTIONS,
 }
 
 impl Method {
@@ -50,9 +54,21 @@ impl Method {
             "PUT" => Some(Method::PUT),
             "DELETE" => Some(Method::DELETE),
             "PATCH" => Some(Method::PATCH),
+            "OPTIONS" => Some(Method::OPTIONS),
             _ => None,
         }
     }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Method::GET => "GET",
+            Method::POST => "POST",
+            Method::PUT => "PUT",
+            Method::DELETE => "DELETE",
+            Method::PATCH => "PATCH",
+            Method::OPTIONS => "OPTIONS",
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -193,6 +209,12 @@ impl Response {
         resp
     }
 
+    pub fn no_content() -> Self {
+        let mut resp = Self::new(204, "No Content");
+        resp.headers.remove("Content-Type");
+        resp
+    }
+
     pub fn not_found() -> Self {
         let mut resp = Self::new(404, "Not Found");
         resp.body = "404 Not Found".to_string();
@@ -244,30 +266,160 @@ impl Route {
             return None;
         }
 
-        let pattern_parts: Vec<&str> = self.pattern.split('/').collect();
-        let path_parts: Vec<&str> = path.split('/').collect();
+        match_pattern(&self.pattern, path)
+    }
+}
+
+/// Match a path against a pattern like `/users/:id`, returning captured params
+fn match_pattern(pattern: &str, path: &str) -> Option<HashMap<String, String>> {
+    let pattern_parts: Vec<&str> = pattern.split('/').collect();
+    let path_parts: Vec<&str> = path.split('/').collect();
+
+    if pattern_parts.len() != path_parts.len() {
+        return None;
+    }
+
+    let mut params = HashMap::new();
 
-        if pattern_parts.len() != path_parts.len() {
+    for (pattern, path) in pattern_parts.iter().zip(path_parts.iter()) {
+        if pattern.starts_with(':') {
+            params.insert(pattern[1..].to_string(), path.to_string());
+        } else if pattern != path {
             return None;
         }
+    }
 
-        let mut params = HashMap::new();
+    Some(params)
+}
 
-        for (pattern, path) in pattern_parts.iter().zip(path_parts.iter()) {
-            if pattern.starts_with(':') {
-                params.insert(pattern[1..].to_string(), path.to_string());
-            } else if pattern != path {
-                return None;
-            }
+// ============================================================================
+// CORS
+// ============================================================================
+
+/// Cross-Origin Resource Sharing policy for a set of routes
+#[derive(Debug, Clone)]
+pub struct CorsConfig {
+    allowed_origins: Option<Vec<String>>, // None = any origin
+    allowed_methods: Vec<Method>,
+    allowed_headers: Vec<String>,
+    allow_credentials: bool,
+    max_age: Option<u32>,
+}
+
+impl CorsConfig {
+    /// Permissive defaults: any origin, GET/POST, no credentials
+    pub fn new() -> Self {
+        CorsConfig {
+            allowed_origins: None,
+            allowed_methods: vec![Method::GET, Method::POST],
+            allowed_headers: Vec::new(),
+            allow_credentials: false,
+            max_age: None,
+        }
+    }
+
+    pub fn allow_origin(mut self, origin: &str) -> Self {
+        self.allowed_origins
+            .get_or_insert_with(Vec::new)
+            .push(origin.to_string());
+        self
+    }
+
+    pub fn allow_methods(mut self, methods: &[Method]) -> Self {
+        self.allowed_methods = methods.to_vec();
+        self
+    }
+
+    pub fn allow_headers(mut self, headers: &[&str]) -> Self {
+        self.allowed_headers = headers.iter().map(|h| h.to_lowercase()).collect();
+        self
+    }
+
+    pub fn allow_credentials(mut self, allow: bool) -> Self {
+        self.allow_credentials = allow;
+        self
+    }
+
+    /// How long (seconds) browsers may cache a preflight response
+    pub fn max_age(mut self, seconds: u32) -> Self {
+        self.max_age = Some(seconds);
+        self
+    }
+
+    fn origin_allowed(&self, origin: &str) -> bool {
+        match &self.allowed_origins {
+            None => true,
+            Some(origins) => origins.iter().any(|o| o == origin),
+        }
+    }
+
+    /// Value for Access-Control-Allow-Origin. Credentialed responses must
+    /// echo the origin, since browsers reject `*` together with credentials.
+    fn allow_origin_value(&self, origin: &str) -> String {
+        if self.allowed_origins.is_none() && !self.allow_credentials {
+            "*".to_string()
+        } else {
+            origin.to_string()
+        }
+    }
+
+    /// Add CORS headers to a response for an actual (non-preflight) request
+    fn apply(&self, origin: &str, response: Response) -> Response {
+        if !self.origin_allowed(origin) {
+            return response;
+        }
+
+        let mut response = response
+            .header("Access-Control-Allow-Origin", &self.allow_origin_value(origin))
+            .header("Vary", "Origin");
+        if self.allow_credentials {
+            response = response.header("Access-Control-Allow-Credentials", "true");
+        }
+        response
+    }
+
+    /// Answer an OPTIONS preflight for the given origin and requested method
+    fn preflight(&self, origin: &str, requested_method: &str, requested_headers: Option<&String>) -> Response {
+        let method_allowed = Method::from_str(requested_method)
+            .map(|m| self.allowed_methods.contains(&m))
+            .unwrap_or(false);
+        let headers_allowed = requested_headers
+            .map(|list| {
+                list.split(',')
+                    .map(|h| h.trim().to_lowercase())
+                    .filter(|h| !h.is_empty())
+                    .all(|h| self.allowed_headers.contains(&h))
+            })
+            .unwrap_or(true);
+
+        // Without CORS headers the browser blocks the real request
+        if !self.origin_allowed(origin) || !method_allowed || !headers_allowed {
+            return Response::no_content().header("Vary", "Origin");
         }
 
-        Some(params)
+        let methods: Vec<&str> = self.allowed_methods.iter().map(|m| m.as_str()).collect();
+        let mut response = self
+            .apply(origin, Response::no_content())
+            .header("Access-Control-Allow-Methods", &methods.join(", "));
+        if !self.allowed_headers.is_empty() {
+            response = response.header("Access-Control-Allow-Headers", &self.allowed_headers.join(", "));
+        }
+        if let Some(max_age) = self.max_age {
+            response = response.header("Access-Control-Max-Age", &max_age.to_string());
+        }
+        response
     }
 }
 
+struct CorsRule {
+    pattern: String, // Route pattern, or "*" for every route
+    config: Arc<CorsConfig>,
+}
+
 pub struct Router {
     routes: Vec<Route>,
     middlewares: Vec<Middleware>,
+    cors_rules: Vec<CorsRule>,
 }
 
 impl Router {
@@ -275,6 +427,7 @@ impl Router {
         Router {
             routes: Vec::new(),
             middlewares: Vec::new(),
+            cors_rules: Vec::new(),
         }
     }
 
@@ -307,7 +460,42 @@ impl Router {
         self.middlewares.push(Arc::new(middleware));
     }
 
+    /// Enable CORS for routes matching `pattern` (`"*"` applies to every
+    /// route). Route-specific rules take precedence over `"*"`.
+    pub fn cors(&mut self, pattern: &str, config: CorsConfig) {
+        self.cors_rules.push(CorsRule {
+            pattern: pattern.to_string(),
+            config: Arc::new(config),
+        });
+    }
+
+    fn cors_for(&self, path: &str) -> Option<Arc<CorsConfig>> {
+        self.cors_rules
+            .iter()
+            .find(|rule| rule.pattern != "*" && match_pattern(&rule.pattern, path).is_some())
+            .or_else(|| self.cors_rules.iter().find(|rule| rule.pattern == "*"))
+            .map(|rule| rule.config.clone())
+    }
+
     fn handle(&self, mut request: Request) -> Response {
+        let origin = request.headers.get("origin").cloned();
+        let cors = origin.as_ref().and_then(|_| self.cors_for(&request.path));
+
+        // Answer preflights for any path that has at least one route
+        if request.method == Method::OPTIONS {
+            if let (Some(origin), Some(cors)) = (&origin, &cors) {
+                let route_exists = self.routes
+                    .iter()
+                    .any(|route| match_pattern(&route.pattern, &request.path).is_some());
+                if let Some(requested) = request.headers.get("access-control-request-method") {
+                    if route_exists {
+                        let requested_headers = request.headers.get("access-control-request-headers");
+                        return cors.preflight(origin, requested, requested_headers);
+                    }
+                }
+            }
+        }
+
         // Find matching route
         for route in &self.routes {
             if let Some(params) = route.matches(&request.method, &request.path) {
@@ -315,7 +503,11 @@ impl Router {
                 
                 // Apply middlewares
                 let handler = route.handler.clone();
-                return self.apply_middlewares(request, handler, 0);
+                let response = self.apply_middlewares(request, handler, 0);
+                return match (&origin, &cors) {
+                    (Some(origin), Some(cors)) => cors.apply(origin, response),
+                    _ => response,
+                };
             }
         }
 
@@ -333,7 +525,7 @@ impl Router {
             let handler = handler.clone();
             move |req| {
                 // Create a mini router for recursive middleware application
-                let mut next_index = index + 1;
+                let next_index = index + 1;
                 if next_index >= router_middlewares.len() {
                     handler(req)
                 } else {
@@ -442,6 +634,26 @@ fn main() {
         Response::ok(&format!("Received {} bytes: {}", req.body.len(), req.body))
     });
 
+    // CORS: a locked-down policy for the API, permissive for everything else
+    router.cors(
+        "/api/items",
+        CorsConfig::new()
+            .allow_origin("http://localhost:3000")
+            .allow_methods(&[Method::GET, Method::POST])
+            .allow_headers(&["Content-Type", "Authorization"])
+            .allow_credentials(true)
+            .max_age(600),
+    );
+    router.cors("*", CorsConfig::new());
+
+    router.get("/api/items", |_req| {
+        Response::json(r#"[{"id": 1, "name": "widget"}]"#)
+    });
+
+    router.post("/api/items", |req| {
+        Response::json(&format!(r#"{{"created": true, "bytes": {}}}"#, req.body.len()))
+    });
+
     router.get("/headers", |req| {
         let mut body = String::from("Request Headers:\n");
         for (key, value) in &req.headers {
@@ -501,4 +713,84 @@ mod tests {
         assert_eq!(query.get("foo"), Some(&"bar".to_string()));
         assert_eq!(query.get("baz"), Some(&"qux".to_string()));
     }
+
+    fn cors_request(method: Method, path: &str, headers: &[(&str, &str)]) -> Request {
+        Request {
+            method,
+            path: path.to_string(),
+            query: HashMap::new(),
+            headers: headers.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            body: String::new(),
+            params: HashMap::new(),
+        }
+    }
+
+    fn cors_router() -> Router {
+        let mut router = Router::new();
+        router.get("/api/items", |_| Response::ok("items"));
+        router.post("/api/items", |_| Response::ok("created"));
+        router.get("/public", |_| Response::ok("public"));
+        router.cors(
+            "/api/items",
+            CorsConfig::new()
+                .allow_origin("http://app.test")
+                .allow_methods(&[Method::GET, Method::POST])
+                .allow_headers(&["Content-Type"])
+                .allow_credentials(true)
+                .max_age(600),
+        );
+        router.cors("*", CorsConfig::new());
+        router
+    }
+
+    #[test]
+    fn test_cors_preflight() {
+        let router = cors_router();
+        let resp = router.handle(cors_request(Method::OPTIONS, "/api/items", &[
+            ("origin", "http://app.test"),
+            ("access-control-request-method", "POST"),
+            ("access-control-request-headers", "content-type"),
+        ]));
+        assert_eq!(resp.status, 204);
+        assert_eq!(resp.headers.get("Access-Control-Allow-Origin"), Some(&"http://app.test".to_string()));
+        assert_eq!(resp.headers.get("Access-Control-Allow-Methods"), Some(&"GET, POST".to_string()));
+        assert_eq!(resp.headers.get("Access-Control-Allow-Credentials"), Some(&"true".to_string()));
+        assert_eq!(resp.headers.get("Access-Control-Max-Age"), Some(&"600".to_string()));
+
+        // Disallowed origin, method, or header gets no CORS headers
+        for headers in [
+            [("origin", "http://evil.test"), ("access-control-request-method", "POST")],
+            [("origin", "http://app.test"), ("access-control-request-method", "DELETE")],
+            [("origin", "http://app.test"), ("access-control-request-headers", "x-secret")],
+        ].iter() {
+            let mut headers = headers.to_vec();
+            if !headers.iter().any(|(k, _)| *k == "access-control-request-method") {
+                headers.push(("access-control-request-method", "GET"));
+            }
+            let resp = router.handle(cors_request(Method::OPTIONS, "/api/items", &headers));
+            assert!(resp.headers.get("Access-Control-Allow-Origin").is_none());
+        }
+
+        // No route, no preflight
+        let resp = router.handle(cors_request(Method::OPTIONS, "/missing", &[
+            ("origin", "http://app.test"),
+            ("access-control-request-method", "GET"),
+        ]));
+        assert_eq!(resp.status, 404);
+    }
+
+    #[test]
+    fn test_cors_actual_request_headers() {
+        let router = cors_router();
+
+        let resp = router.handle(cors_request(Method::GET, "/public", &[("origin", "http://any.test")]));
+        assert_eq!(resp.headers.get("Access-Control-Allow-Origin"), Some(&"*".to_string()));
+
+        let resp = router.handle(cors_request(Method::GET, "/api/items", &[("origin", "http://evil.test")]));
+        assert_eq!(resp.status, 200);
+        assert!(resp.headers.get("Access-Control-Allow-Origin").is_none());
+
+        let resp = router.handle(cors_request(Method::GET, "/public", &[]));
+        assert!(resp.headers.get("Access-Control-Allow-Origin").is_none());
+    }
 }
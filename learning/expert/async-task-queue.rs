@@ -1,9 +1,11 @@
 // Production Async Task Queue with Priority, Worker Pool, Retry Logic, and Persistence
 // Implements a robust job queue system with tokio runtime
+// Run with --simulate to stress the queue and check its ordering and retry invariants
 
 use std::collections::{BinaryHeap, HashMap};
 use std::cmp::Ordering;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, SystemTime};
 use tokio::sync::{mpsc, RwLock, Semaphore};
 use tokio::time::{sleep, interval};
@@ -57,7 +59,7 @@ impl Job {
 #[derive(Debug, Clone)]
 struct PriorityJob {
     job: Job,
-    enqueued_at: SystemTime,
+    seq: u64, // Position in the queue's event order; breaks priority ties FIFO
 }
 
 impl PartialEq for PriorityJob {
@@ -77,7 +79,7 @@ impl PartialOrd for PriorityJob {
 impl Ord for PriorityJob {
     fn cmp(&self, other: &Self) -> Ordering {
         match self.job.priority.cmp(&other.job.priority) {
-            Ordering::Equal => other.enqueued_at.cmp(&self.enqueued_at),
+            Ordering::Equal => other.seq.cmp(&self.seq),
             other => other,
         }
     }
@@ -85,6 +87,7 @@ impl Ord for PriorityJob {
 
 // ========== JOB PROCESSOR ==========
 type JobProcessor = Arc<dyn Fn(Job) -> JobResult + Send + Sync>;
+type LatencyFn = Arc<dyn Fn(&Job) -> Duration + Send + Sync>;
 
 enum JobResult {
     Success,
@@ -92,6 +95,86 @@ enum JobResult {
     Retry,
 }
 
+// ========== QUEUE CONFIGURATION ==========
+#[derive(Clone)]
+struct QueueConfig {
+    num_workers: usize,
+    latency: LatencyFn,      // Simulated work time per attempt
+    retry_delay: Duration,   // Wait before a retried job is re-enqueued
+    log_jobs: bool,          // Print per-job progress and periodic stats
+    record_events: bool,     // Keep an EventLog for invariant checking
+}
+
+impl QueueConfig {
+    fn new(num_workers: usize) -> Self {
+        QueueConfig {
+            num_workers,
+            latency: Arc::new(|_| Duration::from_millis(100)),
+            retry_delay: Duration::from_millis(500),
+            log_jobs: true,
+            record_events: false,
+        }
+    }
+}
+
+// ========== EVENT LOG ==========
+#[derive(Debug, Clone, PartialEq)]
+enum EventKind {
+    Enqueued,
+    Dispatched,
+    Finished(JobStatus),
+}
+
+#[derive(Debug, Clone)]
+struct QueueEvent {
+    seq: u64,
+    job_id: JobId,
+    priority: Priority,
+    kind: EventKind,
+}
+
+// Totally ordered record of queue activity. Enqueue and dispatch events take
+// their sequence number while holding the queue lock, so the log reflects the
+// exact heap contents at every dispatch.
+struct EventLog {
+    clock: AtomicU64,
+    events: Option<Mutex<Vec<QueueEvent>>>,
+}
+
+impl EventLog {
+    fn new(enabled: bool) -> Self {
+        EventLog {
+            clock: AtomicU64::new(0),
+            events: if enabled { Some(Mutex::new(Vec::new())) } else { None },
+        }
+    }
+
+    fn tick(&self) -> u64 {
+        self.clock.fetch_add(1, AtomicOrdering::SeqCst)
+    }
+
+    fn record(&self, seq: u64, job: &Job, kind: EventKind) {
+        if let Some(events) = &self.events {
+            events.lock().unwrap().push(QueueEvent {
+                seq,
+                job_id: job.id,
+                priority: job.priority,
+                kind,
+            });
+        }
+    }
+
+    fn snapshot(&self) -> Vec<QueueEvent> {
+        let mut events = self
+            .events
+            .as_ref()
+            .map(|events| events.lock().unwrap().clone())
+            .unwrap_or_default();
+        events.sort_by_key(|event| event.seq);
+        events
+    }
+}
+
 // ========== PERSISTENCE LAYER ==========
 struct PersistenceLayer {
     jobs: Arc<RwLock<HashMap<JobId, Job>>>,
@@ -161,61 +244,74 @@ struct JobStats {
 }
 
 // ========== WORKER ==========
+#[derive(Clone)]
 struct Worker {
     id: usize,
     processor: JobProcessor,
     persistence: Arc<PersistenceLayer>,
+    events: Arc<EventLog>,
+    latency: LatencyFn,
+    log_jobs: bool,
 }
 
 impl Worker {
-    fn new(id: usize, processor: JobProcessor, persistence: Arc<PersistenceLayer>) -> Self {
+    fn new(id: usize, processor: JobProcessor, persistence: Arc<PersistenceLayer>, events: Arc<EventLog>, config: &QueueConfig) -> Self {
         Worker {
             id,
             processor,
             persistence,
+            events,
+            latency: config.latency.clone(),
+            log_jobs: config.log_jobs,
         }
     }
 
     async fn process(&self, mut job: Job) -> Job {
-        println!("[Worker {}] Processing job {:?} (priority: {:?})", 
-                 self.id, job.id, job.priority);
+        if self.log_jobs {
+            println!("[Worker {}] Processing job {:?} (priority: {:?})", 
+                     self.id, job.id, job.priority);
+        }
 
         job.status = JobStatus::Running;
         self.persistence.update_status(job.id, JobStatus::Running).await;
 
-        sleep(Duration::from_millis(100)).await;
+        sleep((self.latency)(&job)).await;
 
         let result = (self.processor)(job.clone());
 
         match result {
             JobResult::Success => {
-                println!("[Worker {}] Job {:?} completed successfully", self.id, job.id);
+                if self.log_jobs {
+                    println!("[Worker {}] Job {:?} completed successfully", self.id, job.id);
+                }
                 job.status = JobStatus::Completed;
-                self.persistence.update_status(job.id, JobStatus::Completed).await;
             }
             JobResult::Failure(reason) => {
-                println!("[Worker {}] Job {:?} failed: {}", self.id, job.id, reason);
-                job.status = JobStatus::Failed(reason.clone());
-                self.persistence.update_status(job.id, JobStatus::Failed(reason)).await;
+                if self.log_jobs {
+                    println!("[Worker {}] Job {:?} failed: {}", self.id, job.id, reason);
+                }
+                job.status = JobStatus::Failed(reason);
             }
             JobResult::Retry => {
                 if job.retry_count < job.max_retries {
-                    println!("[Worker {}] Job {:?} will retry ({}/{})", 
-                             self.id, job.id, job.retry_count + 1, job.max_retries);
+                    if self.log_jobs {
+                        println!("[Worker {}] Job {:?} will retry ({}/{})", 
+                                 self.id, job.id, job.retry_count + 1, job.max_retries);
+                    }
                     job.retry_count += 1;
                     job.status = JobStatus::Retrying;
-                    self.persistence.update_status(job.id, JobStatus::Retrying).await;
                 } else {
-                    println!("[Worker {}] Job {:?} exhausted retries", self.id, job.id);
+                    if self.log_jobs {
+                        println!("[Worker {}] Job {:?} exhausted retries", self.id, job.id);
+                    }
                     job.status = JobStatus::Failed("Max retries exceeded".to_string());
-                    self.persistence.update_status(
-                        job.id, 
-                        JobStatus::Failed("Max retries exceeded".to_string())
-                    ).await;
                 }
             }
         }
 
+        self.events.record(self.events.tick(), &job, EventKind::Finished(job.status.clone()));
+        // Save the whole job so the persisted retry_count stays accurate
+        self.persistence.save_job(&job).await;
         job
     }
 }
@@ -231,17 +327,25 @@ struct TaskQueue {
     retry_tx: mpsc::UnboundedSender<Job>,
     retry_rx: Arc<RwLock<mpsc::UnboundedReceiver<Job>>>,
     semaphore: Arc<Semaphore>,
+    events: Arc<EventLog>,
+    config: QueueConfig,
 }
 
 impl TaskQueue {
     fn new(num_workers: usize, processor: JobProcessor) -> Self {
+        Self::with_config(QueueConfig::new(num_workers), processor)
+    }
+
+    fn with_config(config: QueueConfig, processor: JobProcessor) -> Self {
+        let num_workers = config.num_workers;
         let persistence = Arc::new(PersistenceLayer::new());
+        let events = Arc::new(EventLog::new(config.record_events));
         let (job_tx, job_rx) = mpsc::unbounded_channel();
         let (retry_tx, retry_rx) = mpsc::unbounded_channel();
 
         let mut workers = Vec::new();
         for i in 0..num_workers {
-            workers.push(Worker::new(i, processor.clone(), persistence.clone()));
+            workers.push(Worker::new(i, processor.clone(), persistence.clone(), events.clone(), &config));
         }
 
         TaskQueue {
@@ -254,6 +358,8 @@ impl TaskQueue {
             retry_tx,
             retry_rx: Arc::new(RwLock::new(retry_rx)),
             semaphore: Arc::new(Semaphore::new(num_workers)),
+            events,
+            config,
         }
     }
 
@@ -269,19 +375,21 @@ impl TaskQueue {
         
         self.persistence.save_job(&job).await;
 
-        let priority_job = PriorityJob {
-            job: job.clone(),
-            enqueued_at: SystemTime::now(),
-        };
-
         {
             let mut queue = self.queue.write().await;
-            queue.push(priority_job);
+            let seq = self.events.tick();
+            self.events.record(seq, &job, EventKind::Enqueued);
+            queue.push(PriorityJob {
+                job: job.clone(),
+                seq,
+            });
         }
 
         self.job_tx.send(job).unwrap();
 
-        println!("Enqueued job {:?} with priority {:?}", job_id, priority);
+        if self.config.log_jobs {
+            println!("Enqueued job {:?} with priority {:?}", job_id, priority);
+        }
         job_id
     }
 
@@ -290,9 +398,13 @@ impl TaskQueue {
         let job_rx = self.job_rx.clone();
         let retry_rx = self.retry_rx.clone();
         let retry_tx = self.retry_tx.clone();
+        let job_tx = self.job_tx.clone();
         let semaphore = self.semaphore.clone();
         let workers = self.workers.clone();
         let persistence = self.persistence.clone();
+        let events = self.events.clone();
+        let retry_delay = self.config.retry_delay;
+        let log_jobs = self.config.log_jobs;
 
         tokio::spawn(async move {
             let mut job_rx = job_rx.write().await;
@@ -305,20 +417,22 @@ impl TaskQueue {
                         
                         let job_opt = {
                             let mut q = queue.write().await;
-                            q.pop().map(|pj| pj.job)
+                            q.pop().map(|pj| {
+                                events.record(events.tick(), &pj.job, EventKind::Dispatched);
+                                pj.job
+                            })
                         };
 
                         if let Some(job) = job_opt {
                             let worker_idx = job.id.0 as usize % workers.len();
                             let worker = workers[worker_idx].clone();
                             let retry_tx = retry_tx.clone();
-                            let persistence = persistence.clone();
 
                             tokio::spawn(async move {
                                 let result = worker.process(job).await;
 
                                 if result.status == JobStatus::Retrying {
-                                    sleep(Duration::from_millis(500)).await;
+                                    sleep(retry_delay).await;
                                     retry_tx.send(result).unwrap();
                                 }
 
@@ -330,27 +444,31 @@ impl TaskQueue {
                     }
 
                     Some(retry_job) = retry_rx.recv() => {
-                        println!("Re-enqueueing job {:?} for retry", retry_job.id);
-                        
-                        let priority_job = PriorityJob {
-                            job: retry_job.clone(),
-                            enqueued_at: SystemTime::now(),
-                        };
+                        if log_jobs {
+                            println!("Re-enqueueing job {:?} for retry", retry_job.id);
+                        }
 
                         {
                             let mut q = queue.write().await;
-                            q.push(priority_job);
+                            let seq = events.tick();
+                            events.record(seq, &retry_job, EventKind::Enqueued);
+                            q.push(PriorityJob {
+                                job: retry_job.clone(),
+                                seq,
+                            });
                         }
 
-                        job_rx.try_recv().ok();
-                        drop(job_rx);
-                        
-                        job_rx = TaskQueue::recreate_rx();
+                        // Wake the dispatch branch for the re-queued job
+                        job_tx.send(retry_job).unwrap();
                     }
                 }
             }
         });
 
+        if !log_jobs {
+            return;
+        }
+
         tokio::spawn(async move {
             let mut stats_interval = interval(Duration::from_secs(5));
             loop {
@@ -364,10 +482,6 @@ impl TaskQueue {
         });
     }
 
-    fn recreate_rx() -> tokio::sync::RwLockWriteGuard<'static, mpsc::UnboundedReceiver<Job>> {
-        unimplemented!("This is a simplified example")
-    }
-
     async fn wait_for_completion(&self, timeout: Duration) {
         let start = SystemTime::now();
         loop {
@@ -388,11 +502,268 @@ impl TaskQueue {
     async fn get_stats(&self) -> JobStats {
         self.persistence.get_stats().await
     }
+
+    fn events(&self) -> Vec<QueueEvent> {
+        self.events.snapshot()
+    }
+}
+
+// ========== SIMULATION TEST-KIT ==========
+// Stress harness: thousands of jobs with mixed priorities, flaky and fatal
+// failures, and randomized worker latency, checked against the queue's
+// ordering, at-least-once, and retry-count guarantees.
+mod simulation {
+    use super::*;
+
+    // Deterministic SplitMix64 so a failing run can be replayed from its seed
+    struct SimRng(u64);
+
+    impl SimRng {
+        fn next_u64(&mut self) -> u64 {
+            self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            mix(self.0)
+        }
+
+        fn below(&mut self, n: u64) -> u64 {
+            self.next_u64() % n
+        }
+    }
+
+    fn mix(mut z: u64) -> u64 {
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    pub struct SimulationConfig {
+        pub jobs: usize,
+        pub workers: usize,
+        pub max_retries: u32,
+        pub max_latency_ms: u64,
+        pub seed: u64,
+        pub timeout: Duration,
+    }
+
+    impl Default for SimulationConfig {
+        fn default() -> Self {
+            SimulationConfig {
+                jobs: 2000,
+                workers: 8,
+                max_retries: 3,
+                max_latency_ms: 3,
+                seed: 42,
+                timeout: Duration::from_secs(60),
+            }
+        }
+    }
+
+    // Scripted behaviour of one job, carried in its payload as "sim:<flaky>:<fatal>"
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct JobPlan {
+        pub flaky_attempts: u32, // Attempts that ask for a retry before succeeding
+        pub fatal: bool,         // Fails permanently on the first attempt
+    }
+
+    impl JobPlan {
+        fn payload(&self) -> String {
+            format!("sim:{}:{}", self.flaky_attempts, self.fatal as u8)
+        }
+
+        pub fn parse(payload: &str) -> Option<JobPlan> {
+            let mut parts = payload.strip_prefix("sim:")?.split(':');
+            let flaky_attempts = parts.next()?.parse().ok()?;
+            let fatal = parts.next()? == "1";
+            Some(JobPlan { flaky_attempts, fatal })
+        }
+
+        fn expected_attempts(&self, max_retries: u32) -> u32 {
+            if self.fatal {
+                1
+            } else {
+                self.flaky_attempts.min(max_retries) + 1
+            }
+        }
+
+        fn expect_success(&self, max_retries: u32) -> bool {
+            !self.fatal && self.flaky_attempts <= max_retries
+        }
+    }
+
+    fn processor() -> JobProcessor {
+        Arc::new(|job: Job| match JobPlan::parse(&job.payload) {
+            Some(plan) if plan.fatal => JobResult::Failure("Scripted failure".to_string()),
+            Some(plan) if job.retry_count < plan.flaky_attempts => JobResult::Retry,
+            Some(_) => JobResult::Success,
+            None => JobResult::Failure(format!("Unknown payload {}", job.payload)),
+        })
+    }
+
+    #[derive(Debug)]
+    pub struct SimulationReport {
+        pub jobs: usize,
+        pub attempts: usize,
+        pub completed: usize,
+        pub failed: usize,
+        pub elapsed: Duration,
+        pub violations: Vec<String>,
+    }
+
+    impl SimulationReport {
+        pub fn print(&self) {
+            println!("Jobs: {}, attempts: {}, completed: {}, failed: {}, elapsed: {:?}",
+                     self.jobs, self.attempts, self.completed, self.failed, self.elapsed);
+            if self.violations.is_empty() {
+                println!("All invariants held");
+            } else {
+                println!("{} invariant violations:", self.violations.len());
+                for violation in self.violations.iter().take(20) {
+                    println!("  - {}", violation);
+                }
+            }
+        }
+    }
+
+    pub async fn run(config: SimulationConfig) -> SimulationReport {
+        let mut rng = SimRng(config.seed);
+        let seed = config.seed;
+        let max_latency_ms = config.max_latency_ms.max(1);
+
+        let mut queue_config = QueueConfig::new(config.workers);
+        queue_config.latency = Arc::new(move |job: &Job| {
+            let jitter = mix(seed ^ job.id.0.wrapping_mul(31) ^ u64::from(job.retry_count) << 48);
+            Duration::from_micros(jitter % (max_latency_ms * 1000))
+        });
+        queue_config.retry_delay = Duration::from_millis(1);
+        queue_config.log_jobs = false;
+        queue_config.record_events = true;
+
+        let queue = TaskQueue::with_config(queue_config, processor());
+        let started = SystemTime::now();
+        queue.start().await;
+
+        for i in 0..config.jobs {
+            let priority = match rng.below(4) {
+                0 => Priority::Low,
+                1 => Priority::Normal,
+                2 => Priority::High,
+                _ => Priority::Critical,
+            };
+            let plan = JobPlan {
+                flaky_attempts: if rng.below(4) == 0 { rng.below(u64::from(config.max_retries) + 2) as u32 } else { 0 },
+                fatal: rng.below(50) == 0,
+            };
+            queue.enqueue(priority, plan.payload(), config.max_retries).await;
+
+            // Enqueue in bursts so dispatch interleaves with new arrivals
+            if i % 64 == 63 {
+                sleep(Duration::from_millis(rng.below(3))).await;
+            }
+        }
+
+        queue.wait_for_completion(config.timeout).await;
+
+        let events = queue.events();
+        let jobs = queue.persistence.get_all_jobs().await;
+        let violations = check_invariants(&events, &jobs, config.max_retries);
+
+        SimulationReport {
+            jobs: jobs.len(),
+            attempts: events.iter().filter(|e| e.kind == EventKind::Dispatched).count(),
+            completed: jobs.iter().filter(|j| j.status == JobStatus::Completed).count(),
+            failed: jobs.iter().filter(|j| matches!(j.status, JobStatus::Failed(_))).count(),
+            elapsed: started.elapsed().unwrap_or_default(),
+            violations,
+        }
+    }
+
+    pub fn check_invariants(events: &[QueueEvent], jobs: &[Job], max_retries: u32) -> Vec<String> {
+        let mut violations = Vec::new();
+
+        // Ordering: every dispatch takes the highest-priority waiting job,
+        // oldest first among equals
+        let mut waiting: HashMap<JobId, (Priority, u64)> = HashMap::new();
+        for event in events {
+            match event.kind {
+                EventKind::Enqueued => {
+                    if waiting.insert(event.job_id, (event.priority, event.seq)).is_some() {
+                        violations.push(format!("{:?} enqueued twice while waiting", event.job_id));
+                    }
+                }
+                EventKind::Dispatched => {
+                    let best = waiting
+                        .iter()
+                        .max_by(|a, b| (a.1 .0, std::cmp::Reverse(a.1 .1)).cmp(&(b.1 .0, std::cmp::Reverse(b.1 .1))))
+                        .map(|(id, entry)| (*id, *entry));
+                    match best {
+                        None => violations.push(format!("{:?} dispatched without being queued", event.job_id)),
+                        Some((id, (priority, _))) if id != event.job_id => violations.push(format!(
+                            "seq {}: dispatched {:?} ({:?}) while {:?} ({:?}) waited ahead of it",
+                            event.seq, event.job_id, event.priority, id, priority
+                        )),
+                        Some(_) => {}
+                    }
+                    waiting.remove(&event.job_id);
+                }
+                EventKind::Finished(_) => {}
+            }
+        }
+        for id in waiting.keys() {
+            violations.push(format!("{:?} was never dispatched", id));
+        }
+
+        // At-least-once execution and retry accounting per job
+        for job in jobs {
+            let job_events: Vec<&QueueEvent> = events.iter().filter(|e| e.job_id == job.id).collect();
+            let attempts = job_events.iter().filter(|e| e.kind == EventKind::Dispatched).count() as u32;
+            let terminal = job_events
+                .iter()
+                .filter(|e| matches!(e.kind, EventKind::Finished(JobStatus::Completed) | EventKind::Finished(JobStatus::Failed(_))))
+                .count();
+
+            if attempts == 0 {
+                violations.push(format!("{:?} never executed", job.id));
+            }
+            if terminal != 1 {
+                violations.push(format!("{:?} reached {} terminal states", job.id, terminal));
+            }
+            if attempts > max_retries + 1 {
+                violations.push(format!("{:?} ran {} times with max_retries {}", job.id, attempts, max_retries));
+            }
+            if attempts > 0 && job.retry_count != attempts - 1 {
+                violations.push(format!("{:?} ran {} times but retry_count is {}", job.id, attempts, job.retry_count));
+            }
+
+            if let Some(plan) = JobPlan::parse(&job.payload) {
+                if attempts != plan.expected_attempts(max_retries) {
+                    violations.push(format!("{:?} ran {} times, plan {:?} expects {}",
+                                            job.id, attempts, plan, plan.expected_attempts(max_retries)));
+                }
+                let succeeded = job.status == JobStatus::Completed;
+                if succeeded != plan.expect_success(max_retries) {
+                    violations.push(format!("{:?} ended {:?}, plan {:?}", job.id, job.status, plan));
+                }
+            }
+        }
+
+        violations
+    }
 }
 
 // ========== MAIN ==========
 #[tokio::main]
 async fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(pos) = args.iter().position(|arg| arg == "--simulate") {
+        let mut config = simulation::SimulationConfig::default();
+        if let Some(jobs) = args.get(pos + 1).and_then(|n| n.parse().ok()) {
+            config.jobs = jobs;
+        }
+        println!("=== Task Queue Simulation ({} jobs, {} workers) ===\n", config.jobs, config.workers);
+        let report = simulation::run(config).await;
+        report.print();
+        std::process::exit(if report.violations.is_empty() { 0 } else { 1 });
+    }
+
     println!("=== Production Async Task Queue ===\n");
 
     let processor: JobProcessor = Arc::new(|job: Job| {
@@ -466,3 +837,55 @@ async fn main() {
     println!("  • Real-time statistics and monitoring");
     println!("  • Graceful error handling and recovery");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use simulation::{check_invariants, SimulationConfig};
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_simulation_invariants_hold() {
+        let report = simulation::run(SimulationConfig::default()).await;
+        assert_eq!(report.jobs, 2000);
+        assert!(report.attempts > report.jobs, "expected some retries");
+        assert!(report.violations.is_empty(), "{:#?}", report.violations);
+    }
+
+    #[tokio::test]
+    async fn test_single_worker_drains_backlog_by_priority() {
+        let mut config = QueueConfig::new(1);
+        config.latency = Arc::new(|_| Duration::from_millis(0));
+        config.log_jobs = false;
+        config.record_events = true;
+        let queue = TaskQueue::with_config(config, Arc::new(|_| JobResult::Success));
+
+        let priorities = [Priority::Low, Priority::Critical, Priority::Normal, Priority::High, Priority::Critical];
+        for (i, priority) in priorities.iter().enumerate() {
+            queue.enqueue(*priority, format!("job {}", i), 0).await;
+        }
+        queue.start().await;
+        queue.wait_for_completion(Duration::from_secs(5)).await;
+
+        let order: Vec<u64> = queue
+            .events()
+            .iter()
+            .filter(|e| e.kind == EventKind::Dispatched)
+            .map(|e| e.job_id.0)
+            .collect();
+        assert_eq!(order, vec![1, 4, 3, 2, 0]);
+    }
+
+    #[test]
+    fn test_checker_reports_priority_inversion() {
+        let event = |seq, id, priority, kind| QueueEvent { seq, job_id: JobId(id), priority, kind };
+        let events = vec![
+            event(0, 0, Priority::Low, EventKind::Enqueued),
+            event(1, 1, Priority::High, EventKind::Enqueued),
+            event(2, 0, Priority::Low, EventKind::Dispatched),
+            event(3, 1, Priority::High, EventKind::Dispatched),
+        ];
+        let violations = check_invariants(&events, &[], 0);
+        assert_eq!(violations.len(), 1);
+        assert!(violations[0].contains("waited ahead"));
+    }
+}
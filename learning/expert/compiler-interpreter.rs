@@ -1,14 +1,33 @@
 // Complete Interpreter with Lexer, Parser, AST, Symbol Tables, and REPL
 // Implements a simple expression language with variables, functions, and control flow
-
-use std::collections::HashMap;
+// String literals support \n, \t, \r, \\, \", and \$ escapes plus ${expr} interpolation
+// Strings concatenate with +, compare with == != < > <= >=, and have len(s) / substr(s, start[, count])
+// Errors carry the line and column they occurred at and print the source line with a caret
+// Conditions are real booleans (true/false, && || !); comparisons chain, so a < b <= c works
+// Loops: while, for (init; cond; update), and for (i in start..end), with break and continue
+// Maps: {key: value} literals with get/set/remove/has built-ins, iterated by for (k in map)
+// Calls nest at most --max-depth deep; `return f(...)` inside f reuses the frame instead
+// Usage: compiler-interpreter [--tokens] [--ast] [--max-depth N] [script] (no script runs the demo and REPL)
+// compiler_interpreter/ builds this file as a library; `wasm-pack build -- --features wasm` there exports eval
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
 use std::fmt;
+use std::fs;
 use std::io::{self, Write};
+use std::process;
+use std::rc::Rc;
+use std::thread;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::wasm_bindgen;
 
 // ========== TOKEN DEFINITIONS ==========
 #[derive(Debug, Clone, PartialEq)]
 enum Token {
     Number(f64),
+    Str(Vec<StrPart>),
     Identifier(String),
     Plus,
     Minus,
@@ -23,38 +42,142 @@ enum Token {
     NotEqual,
     LessThan,
     GreaterThan,
+    LessEqual,
+    GreaterEqual,
+    And,
+    Or,
+    Bang,
+    True,
+    False,
     If,
     Else,
     While,
+    For,
+    In,
+    Break,
+    Continue,
+    DotDot,
     Fn,
     Return,
     Comma,
+    Colon,
     Semicolon,
     Eof,
 }
 
+// A piece of a string literal: literal text, or the source of a ${...} expression
+// together with where that source starts
+#[derive(Debug, Clone, PartialEq)]
+enum StrPart {
+    Text(String),
+    Code(String, Span),
+}
+
+// ========== SOURCE LOCATIONS ==========
+// 1-based line and column of a token or AST node in the source text
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Span {
+    line: usize,
+    column: usize,
+}
+
+impl Span {
+    fn start() -> Self {
+        Span { line: 1, column: 1 }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct LangError {
+    message: String,
+    span: Span,
+}
+
+impl LangError {
+    fn new(message: impl Into<String>, span: Span) -> Self {
+        LangError {
+            message: message.into(),
+            span,
+        }
+    }
+
+    // The message, then the offending source line with a caret under the column
+    fn render(&self, source: &str) -> String {
+        let Span { line, column } = self.span;
+        let text = source.lines().nth(line - 1).unwrap_or("");
+        // Keep tabs so the caret lines up with the text above it
+        let padding: String = text
+            .chars()
+            .take(column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let gutter = " ".repeat(line.to_string().len());
+
+        format!(
+            "error: {}\n{}--> line {}, column {}\n{} |\n{} | {}\n{} | {}^",
+            self.message, gutter, line, column, gutter, line, text, gutter, padding
+        )
+    }
+}
+
+impl fmt::Display for LangError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} at line {}, column {}",
+            self.message, self.span.line, self.span.column
+        )
+    }
+}
+
 // ========== LEXER ==========
 struct Lexer<'a> {
     input: &'a str,
     position: usize,
     current_char: Option<char>,
+    line: usize,
+    column: usize,
 }
 
 impl<'a> Lexer<'a> {
-    fn new(input: &'a str) -> Self {
-        let current_char = input.chars().next();
+    // `start` is where the input begins in the source: line 1, column 1, or
+    // for a ${...} interpolation its position inside the enclosing literal
+    fn new(input: &'a str, start: Span) -> Self {
         Lexer {
             input,
             position: 0,
-            current_char,
+            current_char: input.chars().next(),
+            line: start.line,
+            column: start.column,
+        }
+    }
+
+    fn span(&self) -> Span {
+        Span {
+            line: self.line,
+            column: self.column,
         }
     }
 
+    fn error(&self, message: impl Into<String>) -> LangError {
+        LangError::new(message, self.span())
+    }
+
     fn advance(&mut self) {
+        if self.current_char == Some('\n') {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
         self.position += 1;
         self.current_char = self.input.chars().nth(self.position);
     }
 
+    fn peek(&self) -> Option<char> {
+        self.input.chars().nth(self.position + 1)
+    }
+
     fn skip_whitespace(&mut self) {
         while let Some(ch) = self.current_char {
             if ch.is_whitespace() {
@@ -65,87 +188,231 @@ impl<'a> Lexer<'a> {
         }
     }
 
-    fn read_number(&mut self) -> f64 {
-        let start = self.position;
+    fn read_number(&mut self) -> Result<f64, LangError> {
+        let span = self.span();
+        let mut text = String::new();
         while let Some(ch) = self.current_char {
-            if ch.is_numeric() || ch == '.' {
+            // Stop before `..` so 0..10 lexes as a range
+            if ch.is_numeric() || (ch == '.' && self.peek() != Some('.')) {
+                text.push(ch);
                 self.advance();
             } else {
                 break;
             }
         }
-        self.input[start..self.position].parse().unwrap()
+        text.parse()
+            .map_err(|_| LangError::new(format!("Invalid number: {}", text), span))
     }
 
     fn read_identifier(&mut self) -> String {
-        let start = self.position;
+        let mut ident = String::new();
         while let Some(ch) = self.current_char {
             if ch.is_alphanumeric() || ch == '_' {
+                ident.push(ch);
                 self.advance();
             } else {
                 break;
             }
         }
-        self.input[start..self.position].to_string()
+        ident
     }
 
-    fn next_token(&mut self) -> Token {
-        self.skip_whitespace();
+    fn read_escape(&mut self, string_start: Span) -> Result<char, LangError> {
+        let span = self.span();
+        self.advance(); // Skip the backslash
+        let escaped = match self.current_char {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('\\') => '\\',
+            Some('"') => '"',
+            Some('$') => '$',
+            Some(other) => {
+                return Err(LangError::new(format!("Unknown escape sequence: \\{}", other), span))
+            }
+            None => return Err(LangError::new("Unterminated string literal", string_start)),
+        };
+        self.advance();
+        Ok(escaped)
+    }
 
-        match self.current_char {
-            None => Token::Eof,
-            Some(ch) => {
-                if ch.is_numeric() {
-                    return Token::Number(self.read_number());
-                }
-                if ch.is_alphabetic() {
-                    let ident = self.read_identifier();
-                    return match ident.as_str() {
-                        "if" => Token::If,
-                        "else" => Token::Else,
-                        "while" => Token::While,
-                        "fn" => Token::Fn,
-                        "return" => Token::Return,
-                        _ => Token::Identifier(ident),
-                    };
-                }
+    // Collect the raw source of a ${...} expression, up to its matching brace
+    fn read_interpolation(&mut self) -> Result<(String, Span), LangError> {
+        let start = self.span();
+        self.advance(); // Skip '$'
+        self.advance(); // Skip '{'
+        let code_start = self.span();
+        let mut code = String::new();
+        let mut depth = 0;
+        let mut in_string = false;
 
-                let token = match ch {
-                    '+' => Token::Plus,
-                    '-' => Token::Minus,
-                    '*' => Token::Star,
-                    '/' => Token::Slash,
-                    '(' => Token::LParen,
-                    ')' => Token::RParen,
-                    '{' => Token::LBrace,
-                    '}' => Token::RBrace,
-                    ',' => Token::Comma,
-                    ';' => Token::Semicolon,
-                    '=' => {
+        loop {
+            let ch = match self.current_char {
+                Some(ch) => ch,
+                None => {
+                    return Err(LangError::new(
+                        "Unterminated interpolation in string literal",
+                        start,
+                    ))
+                }
+            };
+            match ch {
+                '"' => in_string = !in_string,
+                '\\' if in_string => {
+                    code.push(ch);
+                    self.advance();
+                    if let Some(next) = self.current_char {
+                        code.push(next);
                         self.advance();
-                        if self.current_char == Some('=') {
-                            Token::Equal
-                        } else {
-                            self.position -= 1;
-                            self.current_char = Some('=');
-                            Token::Assign
-                        }
                     }
-                    '!' => {
+                    continue;
+                }
+                '{' if !in_string => depth += 1,
+                '}' if !in_string => {
+                    if depth == 0 {
                         self.advance();
-                        if self.current_char == Some('=') {
-                            Token::NotEqual
-                        } else {
-                            panic!("Unexpected character: !")
-                        }
+                        return Ok((code, code_start));
                     }
-                    '<' => Token::LessThan,
-                    '>' => Token::GreaterThan,
-                    _ => panic!("Unexpected character: {}", ch),
-                };
+                    depth -= 1;
+                }
+                _ => {}
+            }
+            code.push(ch);
+            self.advance();
+        }
+    }
+
+    fn read_string(&mut self) -> Result<Vec<StrPart>, LangError> {
+        let start = self.span();
+        self.advance(); // Skip opening quote
+        let mut parts = Vec::new();
+        let mut text = String::new();
+
+        loop {
+            match self.current_char {
+                None => return Err(LangError::new("Unterminated string literal", start)),
+                Some('"') => {
+                    self.advance();
+                    break;
+                }
+                Some('\\') => text.push(self.read_escape(start)?),
+                Some('$') if self.peek() == Some('{') => {
+                    if !text.is_empty() {
+                        parts.push(StrPart::Text(std::mem::take(&mut text)));
+                    }
+                    let (code, span) = self.read_interpolation()?;
+                    parts.push(StrPart::Code(code, span));
+                }
+                Some(ch) => {
+                    text.push(ch);
+                    self.advance();
+                }
+            }
+        }
+
+        if !text.is_empty() || parts.is_empty() {
+            parts.push(StrPart::Text(text));
+        }
+        Ok(parts)
+    }
+
+    fn next_token(&mut self) -> Result<(Token, Span), LangError> {
+        self.skip_whitespace();
+        let span = self.span();
+
+        let ch = match self.current_char {
+            None => return Ok((Token::Eof, span)),
+            Some(ch) => ch,
+        };
+        if ch.is_numeric() {
+            return Ok((Token::Number(self.read_number()?), span));
+        }
+        if ch == '"' {
+            return Ok((Token::Str(self.read_string()?), span));
+        }
+        if ch.is_alphabetic() {
+            let ident = self.read_identifier();
+            let token = match ident.as_str() {
+                "if" => Token::If,
+                "else" => Token::Else,
+                "while" => Token::While,
+                "for" => Token::For,
+                "in" => Token::In,
+                "break" => Token::Break,
+                "continue" => Token::Continue,
+                "fn" => Token::Fn,
+                "return" => Token::Return,
+                "true" => Token::True,
+                "false" => Token::False,
+                _ => Token::Identifier(ident),
+            };
+            return Ok((token, span));
+        }
+
+        let token = match ch {
+            '+' => Token::Plus,
+            '-' => Token::Minus,
+            '*' => Token::Star,
+            '/' => Token::Slash,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '{' => Token::LBrace,
+            '}' => Token::RBrace,
+            ',' => Token::Comma,
+            ':' => Token::Colon,
+            ';' => Token::Semicolon,
+            '.' if self.peek() == Some('.') => {
+                self.advance();
+                Token::DotDot
+            }
+            '=' if self.peek() == Some('=') => {
+                self.advance();
+                Token::Equal
+            }
+            '=' => Token::Assign,
+            '!' if self.peek() == Some('=') => {
                 self.advance();
-                token
+                Token::NotEqual
             }
+            '!' => Token::Bang,
+            '<' if self.peek() == Some('=') => {
+                self.advance();
+                Token::LessEqual
+            }
+            '>' if self.peek() == Some('=') => {
+                self.advance();
+                Token::GreaterEqual
+            }
+            '<' => Token::LessThan,
+            '>' => Token::GreaterThan,
+            '&' if self.peek() == Some('&') => {
+                self.advance();
+                Token::And
+            }
+            '|' if self.peek() == Some('|') => {
+                self.advance();
+                Token::Or
+            }
+            _ => return Err(self.error(format!("Unexpected character: {}", ch))),
+        };
+        self.advance();
+        Ok((token, span))
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<(Token, Span)>, LangError> {
+    tokenize_at(input, Span::start())
+}
+
+fn tokenize_at(input: &str, start: Span) -> Result<Vec<(Token, Span)>, LangError> {
+    let mut lexer = Lexer::new(input, start);
+    let mut tokens = Vec::new();
+    loop {
+        let (token, span) = lexer.next_token()?;
+        let done = token == Token::Eof;
+        tokens.push((token, span));
+        if done {
+            return Ok(tokens);
         }
     }
 }
@@ -154,15 +421,47 @@ impl<'a> Lexer<'a> {
 #[derive(Debug, Clone)]
 enum Expr {
     Number(f64),
-    Variable(String),
+    Str(String),
+    Bool(bool),
+    // {key: value, ...}; `span` is the opening brace, for reporting bad keys
+    Map {
+        entries: Vec<(Expr, Expr)>,
+        span: Span,
+    },
+    // String with ${...} parts; each piece is evaluated and spliced together
+    Interpolated(Vec<Expr>),
+    Variable {
+        name: String,
+        span: Span,
+    },
+    // The span is that of the operator
     BinaryOp {
         op: BinOp,
         left: Box<Expr>,
         right: Box<Expr>,
+        span: Span,
+    },
+    // a < b <= c: each operand is evaluated at most once, stopping at the
+    // first comparison that fails
+    Chain {
+        operands: Vec<Expr>,
+        ops: Vec<(BinOp, Span)>,
+    },
+    // && and ||, which only evaluate the right side when needed
+    Logical {
+        op: LogicalOp,
+        left: Box<Expr>,
+        right: Box<Expr>,
+        span: Span,
+    },
+    Not {
+        operand: Box<Expr>,
+        span: Span,
     },
     Call {
         name: String,
         args: Vec<Expr>,
+        span: Span,
     },
 }
 
@@ -176,6 +475,14 @@ enum BinOp {
     NotEqual,
     LessThan,
     GreaterThan,
+    LessEqual,
+    GreaterEqual,
+}
+
+#[derive(Debug, Clone)]
+enum LogicalOp {
+    And,
+    Or,
 }
 
 #[derive(Debug, Clone)]
@@ -184,15 +491,43 @@ enum Stmt {
         name: String,
         value: Expr,
     },
+    // `span` is where the condition starts, for reporting non-boolean conditions
     If {
         condition: Expr,
+        span: Span,
         then_branch: Vec<Stmt>,
         else_branch: Option<Vec<Stmt>>,
     },
     While {
         condition: Expr,
+        span: Span,
         body: Vec<Stmt>,
     },
+    // for (init; condition; update) { body }, where each part may be empty
+    For {
+        init: Option<Box<Stmt>>,
+        condition: Option<Expr>,
+        span: Span,
+        update: Option<Box<Stmt>>,
+        body: Vec<Stmt>,
+    },
+    // for (var in start..end) { body }, counting up with end excluded
+    ForRange {
+        var: String,
+        start: Expr,
+        end: Expr,
+        span: Span,
+        body: Vec<Stmt>,
+    },
+    // for (var in map) { body }, visiting the keys in sorted order
+    ForIn {
+        var: String,
+        iterable: Expr,
+        span: Span,
+        body: Vec<Stmt>,
+    },
+    Break,
+    Continue,
     Function {
         name: String,
         params: Vec<String>,
@@ -204,36 +539,56 @@ enum Stmt {
 
 // ========== PARSER ==========
 struct Parser {
-    tokens: Vec<Token>,
+    tokens: Vec<(Token, Span)>,
     position: usize,
+    // How many loops enclose the current statement, so a stray break or
+    // continue is rejected before anything runs
+    loop_depth: usize,
 }
 
 impl Parser {
-    fn new(tokens: Vec<Token>) -> Self {
+    fn new(tokens: Vec<(Token, Span)>) -> Self {
         Parser {
             tokens,
             position: 0,
+            loop_depth: 0,
         }
     }
 
     fn current(&self) -> &Token {
-        self.tokens.get(self.position).unwrap_or(&Token::Eof)
+        self.tokens
+            .get(self.position)
+            .map(|(token, _)| token)
+            .unwrap_or(&Token::Eof)
+    }
+
+    // Span of the current token, or of the end of input once past it
+    fn span(&self) -> Span {
+        self.tokens
+            .get(self.position)
+            .or(self.tokens.last())
+            .map(|(_, span)| *span)
+            .unwrap_or(Span::start())
+    }
+
+    fn error(&self, message: impl Into<String>) -> LangError {
+        LangError::new(message, self.span())
     }
 
     fn advance(&mut self) {
         self.position += 1;
     }
 
-    fn expect(&mut self, token: Token) -> Result<(), String> {
+    fn expect(&mut self, token: Token) -> Result<(), LangError> {
         if self.current() == &token {
             self.advance();
             Ok(())
         } else {
-            Err(format!("Expected {:?}, got {:?}", token, self.current()))
+            Err(self.error(format!("Expected {:?}, got {:?}", token, self.current())))
         }
     }
 
-    fn parse_program(&mut self) -> Result<Vec<Stmt>, String> {
+    fn parse_program(&mut self) -> Result<Vec<Stmt>, LangError> {
         let mut statements = Vec::new();
         while self.current() != &Token::Eof {
             statements.push(self.parse_statement()?);
@@ -241,10 +596,24 @@ impl Parser {
         Ok(statements)
     }
 
-    fn parse_statement(&mut self) -> Result<Stmt, String> {
+    fn parse_statement(&mut self) -> Result<Stmt, LangError> {
         match self.current() {
             Token::If => self.parse_if(),
             Token::While => self.parse_while(),
+            Token::For => self.parse_for(),
+            Token::Break | Token::Continue => {
+                let (stmt, keyword) = if self.current() == &Token::Break {
+                    (Stmt::Break, "break")
+                } else {
+                    (Stmt::Continue, "continue")
+                };
+                if self.loop_depth == 0 {
+                    return Err(self.error(format!("`{}` outside of a loop", keyword)));
+                }
+                self.advance();
+                self.expect(Token::Semicolon)?;
+                Ok(stmt)
+            }
             Token::Fn => self.parse_function(),
             Token::Return => {
                 self.advance();
@@ -280,9 +649,10 @@ impl Parser {
         }
     }
 
-    fn parse_if(&mut self) -> Result<Stmt, String> {
+    fn parse_if(&mut self) -> Result<Stmt, LangError> {
         self.expect(Token::If)?;
         self.expect(Token::LParen)?;
+        let span = self.span();
         let condition = self.parse_expression()?;
         self.expect(Token::RParen)?;
         self.expect(Token::LBrace)?;
@@ -308,33 +678,128 @@ impl Parser {
 
         Ok(Stmt::If {
             condition,
+            span,
             then_branch,
             else_branch,
         })
     }
 
-    fn parse_while(&mut self) -> Result<Stmt, String> {
+    fn parse_while(&mut self) -> Result<Stmt, LangError> {
         self.expect(Token::While)?;
         self.expect(Token::LParen)?;
+        let span = self.span();
         let condition = self.parse_expression()?;
         self.expect(Token::RParen)?;
-        self.expect(Token::LBrace)?;
+        let body = self.parse_loop_body()?;
 
+        Ok(Stmt::While {
+            condition,
+            span,
+            body,
+        })
+    }
+
+    fn parse_for(&mut self) -> Result<Stmt, LangError> {
+        self.expect(Token::For)?;
+        self.expect(Token::LParen)?;
+        let span = self.span();
+
+        if let (Token::Identifier(var), Some((Token::In, _))) =
+            (self.current().clone(), self.tokens.get(self.position + 1))
+        {
+            self.advance();
+            self.advance();
+            let start = self.parse_expression()?;
+            if self.current() == &Token::RParen {
+                self.advance();
+                let body = self.parse_loop_body()?;
+                return Ok(Stmt::ForIn {
+                    var,
+                    iterable: start,
+                    span,
+                    body,
+                });
+            }
+            self.expect(Token::DotDot)?;
+            let end = self.parse_expression()?;
+            self.expect(Token::RParen)?;
+            let body = self.parse_loop_body()?;
+            return Ok(Stmt::ForRange {
+                var,
+                start,
+                end,
+                span,
+                body,
+            });
+        }
+
+        let init = if self.current() == &Token::Semicolon {
+            None
+        } else {
+            Some(Box::new(self.parse_assignment()?))
+        };
+        self.expect(Token::Semicolon)?;
+        let condition_span = self.span();
+        let condition = if self.current() == &Token::Semicolon {
+            None
+        } else {
+            Some(self.parse_expression()?)
+        };
+        self.expect(Token::Semicolon)?;
+        let update = if self.current() == &Token::RParen {
+            None
+        } else {
+            Some(Box::new(self.parse_assignment()?))
+        };
+        self.expect(Token::RParen)?;
+        let body = self.parse_loop_body()?;
+
+        Ok(Stmt::For {
+            init,
+            condition,
+            span: condition_span,
+            update,
+            body,
+        })
+    }
+
+    // `name = value` without the trailing semicolon, as in a for loop header
+    fn parse_assignment(&mut self) -> Result<Stmt, LangError> {
+        let name = if let Token::Identifier(n) = self.current().clone() {
+            n
+        } else {
+            return Err(self.error("Expected an assignment"));
+        };
+        self.advance();
+        self.expect(Token::Assign)?;
+        let value = self.parse_expression()?;
+        Ok(Stmt::Assign { name, value })
+    }
+
+    fn parse_loop_body(&mut self) -> Result<Vec<Stmt>, LangError> {
+        self.expect(Token::LBrace)?;
+        self.loop_depth += 1;
         let mut body = Vec::new();
         while self.current() != &Token::RBrace {
-            body.push(self.parse_statement()?);
+            match self.parse_statement() {
+                Ok(stmt) => body.push(stmt),
+                Err(e) => {
+                    self.loop_depth -= 1;
+                    return Err(e);
+                }
+            }
         }
+        self.loop_depth -= 1;
         self.expect(Token::RBrace)?;
-
-        Ok(Stmt::While { condition, body })
+        Ok(body)
     }
 
-    fn parse_function(&mut self) -> Result<Stmt, String> {
+    fn parse_function(&mut self) -> Result<Stmt, LangError> {
         self.expect(Token::Fn)?;
         let name = if let Token::Identifier(n) = self.current().clone() {
             n
         } else {
-            return Err("Expected function name".to_string());
+            return Err(self.error("Expected function name"));
         };
         self.advance();
         self.expect(Token::LParen)?;
@@ -348,52 +813,105 @@ impl Parser {
                     self.advance();
                 }
             } else {
-                return Err("Expected parameter name".to_string());
+                return Err(self.error("Expected parameter name"));
             }
         }
         self.expect(Token::RParen)?;
         self.expect(Token::LBrace)?;
 
+        // break and continue cannot reach a loop outside the function
+        let loop_depth = std::mem::replace(&mut self.loop_depth, 0);
         let mut body = Vec::new();
         while self.current() != &Token::RBrace {
-            body.push(self.parse_statement()?);
+            match self.parse_statement() {
+                Ok(stmt) => body.push(stmt),
+                Err(e) => {
+                    self.loop_depth = loop_depth;
+                    return Err(e);
+                }
+            }
         }
+        self.loop_depth = loop_depth;
         self.expect(Token::RBrace)?;
 
         Ok(Stmt::Function { name, params, body })
     }
 
-    fn parse_expression(&mut self) -> Result<Expr, String> {
-        self.parse_comparison()
+    fn parse_expression(&mut self) -> Result<Expr, LangError> {
+        self.parse_or()
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, LangError> {
+        let mut left = self.parse_and()?;
+
+        while self.current() == &Token::Or {
+            let span = self.span();
+            self.advance();
+            let right = self.parse_and()?;
+            left = Expr::Logical {
+                op: LogicalOp::Or,
+                left: Box::new(left),
+                right: Box::new(right),
+                span,
+            };
+        }
+
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, LangError> {
+        let mut left = self.parse_comparison()?;
+
+        while self.current() == &Token::And {
+            let span = self.span();
+            self.advance();
+            let right = self.parse_comparison()?;
+            left = Expr::Logical {
+                op: LogicalOp::And,
+                left: Box::new(left),
+                right: Box::new(right),
+                span,
+            };
+        }
+
+        Ok(left)
     }
 
-    fn parse_comparison(&mut self) -> Result<Expr, String> {
-        let mut left = self.parse_term()?;
+    fn parse_comparison(&mut self) -> Result<Expr, LangError> {
+        let mut operands = vec![self.parse_term()?];
+        let mut ops = Vec::new();
 
-        while matches!(
-            self.current(),
-            Token::Equal | Token::NotEqual | Token::LessThan | Token::GreaterThan
-        ) {
+        loop {
             let op = match self.current() {
                 Token::Equal => BinOp::Equal,
                 Token::NotEqual => BinOp::NotEqual,
                 Token::LessThan => BinOp::LessThan,
                 Token::GreaterThan => BinOp::GreaterThan,
-                _ => unreachable!(),
+                Token::LessEqual => BinOp::LessEqual,
+                Token::GreaterEqual => BinOp::GreaterEqual,
+                _ => break,
             };
+            ops.push((op, self.span()));
             self.advance();
-            let right = self.parse_term()?;
-            left = Expr::BinaryOp {
+            operands.push(self.parse_term()?);
+        }
+
+        if ops.len() > 1 {
+            return Ok(Expr::Chain { operands, ops });
+        }
+        let left = operands.remove(0);
+        Ok(match (ops.pop(), operands.pop()) {
+            (Some((op, span)), Some(right)) => Expr::BinaryOp {
                 op,
                 left: Box::new(left),
                 right: Box::new(right),
-            };
-        }
-
-        Ok(left)
+                span,
+            },
+            _ => left,
+        })
     }
 
-    fn parse_term(&mut self) -> Result<Expr, String> {
+    fn parse_term(&mut self) -> Result<Expr, LangError> {
         let mut left = self.parse_factor()?;
 
         while matches!(self.current(), Token::Plus | Token::Minus) {
@@ -402,20 +920,22 @@ impl Parser {
                 Token::Minus => BinOp::Sub,
                 _ => unreachable!(),
             };
+            let span = self.span();
             self.advance();
             let right = self.parse_factor()?;
             left = Expr::BinaryOp {
                 op,
                 left: Box::new(left),
                 right: Box::new(right),
+                span,
             };
         }
 
         Ok(left)
     }
 
-    fn parse_factor(&mut self) -> Result<Expr, String> {
-        let mut left = self.parse_primary()?;
+    fn parse_factor(&mut self) -> Result<Expr, LangError> {
+        let mut left = self.parse_unary()?;
 
         while matches!(self.current(), Token::Star | Token::Slash) {
             let op = match self.current() {
@@ -423,24 +943,49 @@ impl Parser {
                 Token::Slash => BinOp::Div,
                 _ => unreachable!(),
             };
+            let span = self.span();
             self.advance();
-            let right = self.parse_primary()?;
+            let right = self.parse_unary()?;
             left = Expr::BinaryOp {
                 op,
                 left: Box::new(left),
                 right: Box::new(right),
+                span,
             };
         }
 
         Ok(left)
     }
 
-    fn parse_primary(&mut self) -> Result<Expr, String> {
+    fn parse_unary(&mut self) -> Result<Expr, LangError> {
+        if self.current() == &Token::Bang {
+            let span = self.span();
+            self.advance();
+            let operand = self.parse_unary()?;
+            return Ok(Expr::Not {
+                operand: Box::new(operand),
+                span,
+            });
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, LangError> {
+        let span = self.span();
         match self.current().clone() {
             Token::Number(n) => {
                 self.advance();
                 Ok(Expr::Number(n))
             }
+            Token::Str(parts) => {
+                self.advance();
+                self.parse_string(parts)
+            }
+            Token::True | Token::False => {
+                let value = self.current() == &Token::True;
+                self.advance();
+                Ok(Expr::Bool(value))
+            }
             Token::Identifier(name) => {
                 self.advance();
                 if self.current() == &Token::LParen {
@@ -453,9 +998,9 @@ impl Parser {
                         }
                     }
                     self.expect(Token::RParen)?;
-                    Ok(Expr::Call { name, args })
+                    Ok(Expr::Call { name, args, span })
                 } else {
-                    Ok(Expr::Variable(name))
+                    Ok(Expr::Variable { name, span })
                 }
             }
             Token::LParen => {
@@ -464,8 +1009,60 @@ impl Parser {
                 self.expect(Token::RParen)?;
                 Ok(expr)
             }
-            _ => Err(format!("Unexpected token: {:?}", self.current())),
+            Token::LBrace => self.parse_map(),
+            _ => Err(self.error(format!("Unexpected token: {:?}", self.current()))),
+        }
+    }
+
+    // A bare identifier key is its own name as a string, so {a: 1} has key "a"
+    fn parse_map(&mut self) -> Result<Expr, LangError> {
+        let span = self.span();
+        self.expect(Token::LBrace)?;
+
+        let mut entries = Vec::new();
+        while self.current() != &Token::RBrace {
+            let key = match (self.current().clone(), self.tokens.get(self.position + 1)) {
+                (Token::Identifier(name), Some((Token::Colon, _))) => {
+                    self.advance();
+                    Expr::Str(name)
+                }
+                _ => self.parse_expression()?,
+            };
+            self.expect(Token::Colon)?;
+            let value = self.parse_expression()?;
+            entries.push((key, value));
+
+            if self.current() == &Token::Comma {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+        self.expect(Token::RBrace)?;
+
+        Ok(Expr::Map { entries, span })
+    }
+
+    fn parse_string(&mut self, parts: Vec<StrPart>) -> Result<Expr, LangError> {
+        if let [StrPart::Text(text)] = parts.as_slice() {
+            return Ok(Expr::Str(text.clone()));
+        }
+
+        let mut pieces = Vec::new();
+        for part in parts {
+            match part {
+                StrPart::Text(text) => pieces.push(Expr::Str(text)),
+                StrPart::Code(code, span) => {
+                    let mut parser = Parser::new(tokenize_at(&code, span)?);
+                    let expr = parser.parse_expression()?;
+                    if parser.current() != &Token::Eof {
+                        return Err(parser.error(format!("Invalid interpolation: ${{{}}}", code)));
+                    }
+                    pieces.push(expr);
+                }
+            }
         }
+        Ok(Expr::Interpolated(pieces))
     }
 }
 
@@ -473,6 +1070,11 @@ impl Parser {
 #[derive(Debug, Clone)]
 enum Value {
     Number(f64),
+    Str(String),
+    Bool(bool),
+    // Shared, like objects in most scripting languages: set() through one
+    // variable is visible through every other that holds the same map
+    Map(Rc<RefCell<BTreeMap<String, Value>>>),
     Function { params: Vec<String>, body: Vec<Stmt> },
 }
 
@@ -480,15 +1082,66 @@ impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Value::Number(n) => write!(f, "{}", n),
+            Value::Str(s) => write!(f, "{}", s),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Map(map) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in map.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    match value {
+                        Value::Str(s) => write!(f, "{:?}: {:?}", key, s)?,
+                        _ => write!(f, "{:?}: {}", key, value)?,
+                    }
+                }
+                write!(f, "}}")
+            }
             Value::Function { .. } => write!(f, "<function>"),
         }
     }
 }
 
+impl Value {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Value::Number(_) => "number",
+            Value::Str(_) => "string",
+            Value::Bool(_) => "boolean",
+            Value::Map(_) => "map",
+            Value::Function { .. } => "function",
+        }
+    }
+
+    // Numbers and booleans are converted, so 1 and "1" are the same key
+    fn to_key(&self) -> Result<String, String> {
+        match self {
+            Value::Str(s) => Ok(s.clone()),
+            Value::Number(_) | Value::Bool(_) => Ok(self.to_string()),
+            other => Err(format!("A {} cannot be a map key", other.type_name())),
+        }
+    }
+}
+
+// How a statement finished: normally, or by unwinding to the enclosing loop
+// (break/continue) or function (return)
+enum Flow {
+    Normal,
+    Break,
+    Continue,
+    Return(Value),
+    // `return f(...)` from inside f: rerun the body with these arguments
+    TailCall(Vec<Value>),
+}
+
+const DEFAULT_MAX_CALL_DEPTH: usize = 1000;
+
 struct Interpreter<'a> {
     globals: HashMap<String, Value>,
     locals: Vec<HashMap<String, Value>>,
-    return_value: Option<Value>,
+    // Name and arity of each active call, parallel to `locals`
+    calls: Vec<(String, usize)>,
+    max_call_depth: usize,
     _lifetime: std::marker::PhantomData<&'a ()>,
 }
 
@@ -497,21 +1150,29 @@ impl<'a> Interpreter<'a> {
         Interpreter {
             globals: HashMap::new(),
             locals: Vec::new(),
-            return_value: None,
+            calls: Vec::new(),
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
             _lifetime: std::marker::PhantomData,
         }
     }
 
-    fn get_variable(&self, name: &str) -> Result<Value, String> {
+    fn with_max_call_depth(mut self, depth: usize) -> Self {
+        self.max_call_depth = depth;
+        self
+    }
+
+    fn lookup(&self, name: &str) -> Option<Value> {
         for scope in self.locals.iter().rev() {
             if let Some(value) = scope.get(name) {
-                return Ok(value.clone());
+                return Some(value.clone());
             }
         }
-        self.globals
-            .get(name)
-            .cloned()
-            .ok_or_else(|| format!("Undefined variable: {}", name))
+        self.globals.get(name).cloned()
+    }
+
+    fn get_variable(&self, name: &str, span: Span) -> Result<Value, LangError> {
+        self.lookup(name)
+            .ok_or_else(|| LangError::new(format!("Undefined variable: {}", name), span))
     }
 
     fn set_variable(&mut self, name: String, value: Value) {
@@ -522,165 +1183,318 @@ impl<'a> Interpreter<'a> {
         }
     }
 
-    fn eval_expr(&mut self, expr: &Expr) -> Result<Value, String> {
+    fn eval_expr(&mut self, expr: &Expr) -> Result<Value, LangError> {
         match expr {
             Expr::Number(n) => Ok(Value::Number(*n)),
-            Expr::Variable(name) => self.get_variable(name),
-            Expr::BinaryOp { op, left, right } => {
+            Expr::Str(s) => Ok(Value::Str(s.clone())),
+            Expr::Bool(b) => Ok(Value::Bool(*b)),
+            Expr::Map { entries, span } => {
+                let mut map = BTreeMap::new();
+                for (key, value) in entries {
+                    let key = self
+                        .eval_expr(key)?
+                        .to_key()
+                        .map_err(|e| LangError::new(e, *span))?;
+                    map.insert(key, self.eval_expr(value)?);
+                }
+                Ok(Value::Map(Rc::new(RefCell::new(map))))
+            }
+            Expr::Interpolated(pieces) => {
+                let mut result = String::new();
+                for piece in pieces {
+                    result.push_str(&self.eval_expr(piece)?.to_string());
+                }
+                Ok(Value::Str(result))
+            }
+            Expr::Variable { name, span } => self.get_variable(name, *span),
+            Expr::BinaryOp {
+                op,
+                left,
+                right,
+                span,
+            } => {
                 let left_val = self.eval_expr(left)?;
                 let right_val = self.eval_expr(right)?;
 
-                match (left_val, right_val) {
-                    (Value::Number(l), Value::Number(r)) => {
-                        let result = match op {
-                            BinOp::Add => l + r,
-                            BinOp::Sub => l - r,
-                            BinOp::Mul => l * r,
-                            BinOp::Div => l / r,
-                            BinOp::Equal => {
-                                if (l - r).abs() < f64::EPSILON {
-                                    1.0
-                                } else {
-                                    0.0
-                                }
-                            }
-                            BinOp::NotEqual => {
-                                if (l - r).abs() >= f64::EPSILON {
-                                    1.0
-                                } else {
-                                    0.0
-                                }
-                            }
-                            BinOp::LessThan => {
-                                if l < r {
-                                    1.0
-                                } else {
-                                    0.0
-                                }
-                            }
-                            BinOp::GreaterThan => {
-                                if l > r {
-                                    1.0
-                                } else {
-                                    0.0
-                                }
-                            }
-                        };
-                        Ok(Value::Number(result))
+                binary_op(op, left_val, right_val).map_err(|e| LangError::new(e, *span))
+            }
+            Expr::Chain { operands, ops } => {
+                let mut left = self.eval_expr(&operands[0])?;
+                for ((op, span), operand) in ops.iter().zip(&operands[1..]) {
+                    let right = self.eval_expr(operand)?;
+                    let holds = binary_op(op, left, right.clone())
+                        .map_err(|e| LangError::new(e, *span))?;
+                    if !matches!(holds, Value::Bool(true)) {
+                        return Ok(Value::Bool(false));
                     }
-                    _ => Err("Type error in binary operation".to_string()),
+                    left = right;
                 }
+                Ok(Value::Bool(true))
             }
-            Expr::Call { name, args } => {
-                let func = self.get_variable(name)?;
+            Expr::Logical {
+                op,
+                left,
+                right,
+                span,
+            } => {
+                let left = self.eval_condition(left, *span)?;
+                match (op, left) {
+                    (LogicalOp::And, false) => Ok(Value::Bool(false)),
+                    (LogicalOp::Or, true) => Ok(Value::Bool(true)),
+                    _ => Ok(Value::Bool(self.eval_condition(right, *span)?)),
+                }
+            }
+            Expr::Not { operand, span } => Ok(Value::Bool(!self.eval_condition(operand, *span)?)),
+            Expr::Call { name, args, span } if is_builtin(name) && self.lookup(name).is_none() => {
+                let mut arg_values = Vec::new();
+                for arg in args {
+                    arg_values.push(self.eval_expr(arg)?);
+                }
+                call_builtin(name, &arg_values).map_err(|e| LangError::new(e, *span))
+            }
+            Expr::Call { name, args, span } => {
+                let func = self.get_variable(name, *span)?;
                 if let Value::Function { params, body } = func {
-                    if args.len() != params.len() {
-                        return Err(format!(
-                            "Wrong number of arguments: expected {}, got {}",
-                            params.len(),
-                            args.len()
-                        ));
-                    }
+                    let arg_values = self.eval_args(params.len(), args, *span)?;
 
-                    let mut arg_values = Vec::new();
-                    for arg in args {
-                        arg_values.push(self.eval_expr(arg)?);
+                    if self.calls.len() >= self.max_call_depth {
+                        return Err(LangError::new(
+                            format!("Maximum call depth of {} exceeded", self.max_call_depth),
+                            *span,
+                        ));
                     }
 
                     self.locals.push(HashMap::new());
-                    for (param, value) in params.iter().zip(arg_values) {
-                        self.set_variable(param.clone(), value);
-                    }
-
-                    for stmt in &body {
-                        self.eval_stmt(stmt)?;
-                        if self.return_value.is_some() {
-                            break;
-                        }
-                    }
-
-                    let result = self.return_value.take().unwrap_or(Value::Number(0.0));
+                    self.calls.push((name.clone(), params.len()));
+                    // Pop the frame even on error, so the REPL is not left inside it
+                    let outcome = self.call_function(&params, &body, arg_values);
+                    self.calls.pop();
                     self.locals.pop();
-                    Ok(result)
+                    outcome
                 } else {
-                    Err(format!("{} is not a function", name))
+                    Err(LangError::new(format!("{} is not a function", name), *span))
                 }
             }
         }
     }
 
-    fn eval_stmt(&mut self, stmt: &Stmt) -> Result<(), String> {
+    fn eval_args(&mut self, arity: usize, args: &[Expr], span: Span) -> Result<Vec<Value>, LangError> {
+        if args.len() != arity {
+            return Err(LangError::new(
+                format!(
+                    "Wrong number of arguments: expected {}, got {}",
+                    arity,
+                    args.len()
+                ),
+                span,
+            ));
+        }
+
+        let mut values = Vec::new();
+        for arg in args {
+            values.push(self.eval_expr(arg)?);
+        }
+        Ok(values)
+    }
+
+    // Runs a function body in the frame pushed for it. Self-recursive tail
+    // calls loop here with fresh arguments instead of nesting deeper
+    fn call_function(
+        &mut self,
+        params: &[String],
+        body: &[Stmt],
+        mut args: Vec<Value>,
+    ) -> Result<Value, LangError> {
+        loop {
+            let frame = self.locals.last_mut().expect("call frame");
+            frame.clear();
+            for (param, value) in params.iter().zip(args) {
+                frame.insert(param.clone(), value);
+            }
+
+            match self.exec_block(body)? {
+                Flow::Return(value) => return Ok(value),
+                Flow::TailCall(next) => args = next,
+                _ => return Ok(Value::Number(0.0)),
+            }
+        }
+    }
+
+    // Arity of the running function if `name` still refers to it, i.e. it is
+    // the innermost call and no local in its frame shadows the name
+    fn tail_call_arity(&self, name: &str) -> Option<usize> {
+        let (current, arity) = self.calls.last()?;
+        let frame = self.locals.last()?;
+        (current == name && !frame.contains_key(name)).then_some(*arity)
+    }
+
+    // Conditions must be booleans; there is no numeric truthiness
+    fn eval_condition(&mut self, expr: &Expr, span: Span) -> Result<bool, LangError> {
+        match self.eval_expr(expr)? {
+            Value::Bool(b) => Ok(b),
+            other => Err(LangError::new(
+                format!("Expected a boolean, got {}", other.type_name()),
+                span,
+            )),
+        }
+    }
+
+    fn eval_number(&mut self, expr: &Expr, span: Span) -> Result<f64, LangError> {
+        match self.eval_expr(expr)? {
+            Value::Number(n) => Ok(n),
+            other => Err(LangError::new(
+                format!("Expected a number, got {}", other.type_name()),
+                span,
+            )),
+        }
+    }
+
+    // Runs statements until one of them breaks, continues, or returns
+    fn exec_block(&mut self, stmts: &[Stmt]) -> Result<Flow, LangError> {
+        for stmt in stmts {
+            match self.eval_stmt(stmt)? {
+                Flow::Normal => {}
+                flow => return Ok(flow),
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    fn eval_stmt(&mut self, stmt: &Stmt) -> Result<Flow, LangError> {
         match stmt {
             Stmt::Assign { name, value } => {
                 let val = self.eval_expr(value)?;
                 self.set_variable(name.clone(), val);
-                Ok(())
+                Ok(Flow::Normal)
             }
             Stmt::If {
                 condition,
+                span,
                 then_branch,
                 else_branch,
             } => {
-                let cond = self.eval_expr(condition)?;
-                if let Value::Number(n) = cond {
-                    if n != 0.0 {
-                        for stmt in then_branch {
-                            self.eval_stmt(stmt)?;
-                            if self.return_value.is_some() {
-                                break;
-                            }
-                        }
-                    } else if let Some(else_stmts) = else_branch {
-                        for stmt in else_stmts {
-                            self.eval_stmt(stmt)?;
-                            if self.return_value.is_some() {
-                                break;
-                            }
-                        }
+                let branch = if self.eval_condition(condition, *span)? {
+                    then_branch
+                } else if let Some(else_stmts) = else_branch {
+                    else_stmts
+                } else {
+                    return Ok(Flow::Normal);
+                };
+                self.exec_block(branch)
+            }
+            Stmt::While {
+                condition,
+                span,
+                body,
+            } => {
+                while self.eval_condition(condition, *span)? {
+                    match self.exec_block(body)? {
+                        Flow::Break => break,
+                        flow @ (Flow::Return(_) | Flow::TailCall(_)) => return Ok(flow),
+                        Flow::Normal | Flow::Continue => {}
                     }
                 }
-                Ok(())
+                Ok(Flow::Normal)
             }
-            Stmt::While { condition, body } => {
+            Stmt::For {
+                init,
+                condition,
+                span,
+                update,
+                body,
+            } => {
+                if let Some(init) = init {
+                    self.eval_stmt(init)?;
+                }
                 loop {
-                    let cond = self.eval_expr(condition)?;
-                    if let Value::Number(n) = cond {
-                        if n == 0.0 {
+                    if let Some(condition) = condition {
+                        if !self.eval_condition(condition, *span)? {
                             break;
                         }
-                        for stmt in body {
-                            self.eval_stmt(stmt)?;
-                            if self.return_value.is_some() {
-                                return Ok(());
-                            }
-                        }
-                    } else {
-                        break;
+                    }
+                    match self.exec_block(body)? {
+                        Flow::Break => break,
+                        flow @ (Flow::Return(_) | Flow::TailCall(_)) => return Ok(flow),
+                        Flow::Normal | Flow::Continue => {}
+                    }
+                    if let Some(update) = update {
+                        self.eval_stmt(update)?;
                     }
                 }
-                Ok(())
+                Ok(Flow::Normal)
             }
+            Stmt::ForRange {
+                var,
+                start,
+                end,
+                span,
+                body,
+            } => {
+                let mut i = self.eval_number(start, *span)?;
+                let end = self.eval_number(end, *span)?;
+                while i < end {
+                    self.set_variable(var.clone(), Value::Number(i));
+                    match self.exec_block(body)? {
+                        Flow::Break => break,
+                        flow @ (Flow::Return(_) | Flow::TailCall(_)) => return Ok(flow),
+                        Flow::Normal | Flow::Continue => {}
+                    }
+                    i += 1.0;
+                }
+                Ok(Flow::Normal)
+            }
+            Stmt::ForIn {
+                var,
+                iterable,
+                span,
+                body,
+            } => {
+                // Iterate over a snapshot of the keys, so the body may modify the map
+                let keys: Vec<String> = match self.eval_expr(iterable)? {
+                    Value::Map(map) => map.borrow().keys().cloned().collect(),
+                    other => {
+                        return Err(LangError::new(
+                            format!("Cannot iterate over a {}", other.type_name()),
+                            *span,
+                        ))
+                    }
+                };
+                for key in keys {
+                    self.set_variable(var.clone(), Value::Str(key));
+                    match self.exec_block(body)? {
+                        Flow::Break => break,
+                        flow @ (Flow::Return(_) | Flow::TailCall(_)) => return Ok(flow),
+                        Flow::Normal | Flow::Continue => {}
+                    }
+                }
+                Ok(Flow::Normal)
+            }
+            Stmt::Break => Ok(Flow::Break),
+            Stmt::Continue => Ok(Flow::Continue),
             Stmt::Function { name, params, body } => {
                 let func = Value::Function {
                     params: params.clone(),
                     body: body.clone(),
                 };
                 self.set_variable(name.clone(), func);
-                Ok(())
+                Ok(Flow::Normal)
             }
             Stmt::Return(expr) => {
-                let value = self.eval_expr(expr)?;
-                self.return_value = Some(value);
-                Ok(())
+                if let Expr::Call { name, args, span } = expr {
+                    if let Some(arity) = self.tail_call_arity(name) {
+                        return Ok(Flow::TailCall(self.eval_args(arity, args, *span)?));
+                    }
+                }
+                Ok(Flow::Return(self.eval_expr(expr)?))
             }
             Stmt::Expr(expr) => {
                 self.eval_expr(expr)?;
-                Ok(())
+                Ok(Flow::Normal)
             }
         }
     }
 
-    fn execute(&mut self, program: &[Stmt]) -> Result<Option<Value>, String> {
+    fn execute(&mut self, program: &[Stmt]) -> Result<Option<Value>, LangError> {
         let mut last_value = None;
         for stmt in program {
             match stmt {
@@ -688,7 +1502,10 @@ impl<'a> Interpreter<'a> {
                     last_value = Some(self.eval_expr(expr)?);
                 }
                 _ => {
-                    self.eval_stmt(stmt)?;
+                    // A return outside any function ends the program with its value
+                    if let Flow::Return(value) = self.eval_stmt(stmt)? {
+                        return Ok(Some(value));
+                    }
                 }
             }
         }
@@ -696,9 +1513,184 @@ impl<'a> Interpreter<'a> {
     }
 }
 
+fn binary_op(op: &BinOp, left: Value, right: Value) -> Result<Value, String> {
+    match (left, right) {
+        (Value::Number(l), Value::Number(r)) => Ok(match op {
+            BinOp::Add => Value::Number(l + r),
+            BinOp::Sub => Value::Number(l - r),
+            BinOp::Mul => Value::Number(l * r),
+            BinOp::Div => Value::Number(l / r),
+            BinOp::Equal => Value::Bool((l - r).abs() < f64::EPSILON),
+            BinOp::NotEqual => Value::Bool((l - r).abs() >= f64::EPSILON),
+            BinOp::LessThan => Value::Bool(l < r),
+            BinOp::GreaterThan => Value::Bool(l > r),
+            BinOp::LessEqual => Value::Bool(l <= r),
+            BinOp::GreaterEqual => Value::Bool(l >= r),
+        }),
+        (Value::Str(l), Value::Str(r)) => match op {
+            BinOp::Add => Ok(Value::Str(l + &r)),
+            BinOp::Equal => Ok(Value::Bool(l == r)),
+            BinOp::NotEqual => Ok(Value::Bool(l != r)),
+            BinOp::LessThan => Ok(Value::Bool(l < r)),
+            BinOp::GreaterThan => Ok(Value::Bool(l > r)),
+            BinOp::LessEqual => Ok(Value::Bool(l <= r)),
+            BinOp::GreaterEqual => Ok(Value::Bool(l >= r)),
+            _ => Err(format!("Cannot apply {:?} to strings", op)),
+        },
+        (Value::Bool(l), Value::Bool(r)) => match op {
+            BinOp::Equal => Ok(Value::Bool(l == r)),
+            BinOp::NotEqual => Ok(Value::Bool(l != r)),
+            _ => Err(format!("Cannot apply {:?} to booleans", op)),
+        },
+        // Maps compare by identity: two literals with the same entries differ
+        (Value::Map(l), Value::Map(r)) => match op {
+            BinOp::Equal => Ok(Value::Bool(Rc::ptr_eq(&l, &r))),
+            BinOp::NotEqual => Ok(Value::Bool(!Rc::ptr_eq(&l, &r))),
+            _ => Err(format!("Cannot apply {:?} to maps", op)),
+        },
+        // Concatenating a string with a number or boolean formats it
+        (Value::Str(l), r @ (Value::Number(_) | Value::Bool(_))) if matches!(op, BinOp::Add) => {
+            Ok(Value::Str(format!("{}{}", l, r)))
+        }
+        (l @ (Value::Number(_) | Value::Bool(_)), Value::Str(r)) if matches!(op, BinOp::Add) => {
+            Ok(Value::Str(format!("{}{}", l, r)))
+        }
+        // Values of different types are never equal
+        (_, _) if matches!(op, BinOp::Equal) => Ok(Value::Bool(false)),
+        (_, _) if matches!(op, BinOp::NotEqual) => Ok(Value::Bool(true)),
+        (l, r) => Err(format!(
+            "Cannot apply {:?} to {} and {}",
+            op,
+            l.type_name(),
+            r.type_name()
+        )),
+    }
+}
+
+// ========== BUILT-INS ==========
+// Built-ins can be shadowed by a user-defined function of the same name
+fn is_builtin(name: &str) -> bool {
+    matches!(name, "len" | "substr" | "get" | "set" | "remove" | "has")
+}
+
+fn call_builtin(name: &str, args: &[Value]) -> Result<Value, String> {
+    match (name, args) {
+        ("len", [Value::Str(s)]) => Ok(Value::Number(s.chars().count() as f64)),
+        ("len", [Value::Map(map)]) => Ok(Value::Number(map.borrow().len() as f64)),
+        // get(map, key) fails on a missing key; get(map, key, default) does not
+        ("get", [Value::Map(map), key, default @ ..]) if default.len() <= 1 => {
+            let key = key.to_key()?;
+            match (map.borrow().get(&key), default) {
+                (Some(value), _) => Ok(value.clone()),
+                (None, [default]) => Ok(default.clone()),
+                (None, _) => Err(format!("Key not found: {}", key)),
+            }
+        }
+        // set and remove change the map in place and return the value. A map
+        // may not end up inside itself, so maps never form reference cycles
+        ("set", [Value::Map(map), key, value]) => {
+            if contains_map(value, map) {
+                return Err("Cannot store a map inside itself".to_string());
+            }
+            map.borrow_mut().insert(key.to_key()?, value.clone());
+            Ok(value.clone())
+        }
+        ("remove", [Value::Map(map), key]) => {
+            let key = key.to_key()?;
+            map.borrow_mut()
+                .remove(&key)
+                .ok_or_else(|| format!("Key not found: {}", key))
+        }
+        ("has", [Value::Map(map), key]) => {
+            Ok(Value::Bool(map.borrow().contains_key(&key.to_key()?)))
+        }
+        // substr(s, start) or substr(s, start, count); indices count characters
+        ("substr", [Value::Str(s), Value::Number(start), rest @ ..]) if rest.len() <= 1 => {
+            let start = as_index(*start)?;
+            let count = match rest {
+                [Value::Number(count)] => as_index(*count)?,
+                [] => usize::MAX,
+                _ => return Err("substr expects a number of characters".to_string()),
+            };
+            Ok(Value::Str(s.chars().skip(start).take(count).collect()))
+        }
+        ("len", _) => Err("len expects a single string or map".to_string()),
+        ("substr", _) => Err("substr expects (string, start[, count])".to_string()),
+        ("get", _) => Err("get expects (map, key[, default])".to_string()),
+        ("set", _) => Err("set expects (map, key, value)".to_string()),
+        _ => Err(format!("{} expects (map, key)", name)),
+    }
+}
+
+// Whether `value` is `target` or holds it at any depth
+fn contains_map(value: &Value, target: &Rc<RefCell<BTreeMap<String, Value>>>) -> bool {
+    match value {
+        Value::Map(map) => {
+            Rc::ptr_eq(map, target) || map.borrow().values().any(|v| contains_map(v, target))
+        }
+        _ => false,
+    }
+}
+
+fn as_index(n: f64) -> Result<usize, String> {
+    if n < 0.0 || n.fract() != 0.0 {
+        return Err(format!("Invalid index: {}", n));
+    }
+    Ok(n as usize)
+}
+
+// ========== EMBEDDING ==========
+// Runs a whole program and returns what the REPL would print: the value of the
+// last expression, or the error rendered against the source. Strings in and out
+// only, so the `wasm` feature can export it to a browser playground as is.
+// Calls nest at most EVAL_MAX_CALL_DEPTH deep. Natively eval runs on its own
+// thread sized for that depth, like main; wasm has no threads, so there it
+// runs on the caller's stack, which the limit keeps clear of in a release build.
+const EVAL_MAX_CALL_DEPTH: usize = 200;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn eval(source: &str) -> String {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let owned = source.to_string();
+        let stack_size = STACK_PER_CALL * EVAL_MAX_CALL_DEPTH + BASE_STACK;
+        let worker = thread::Builder::new()
+            .stack_size(stack_size)
+            .spawn(move || eval_here(&owned));
+        match worker.map(|handle| handle.join()) {
+            Ok(Ok(output)) => output,
+            Ok(Err(_)) => "Runtime error: interpreter panicked".to_string(),
+            Err(e) => format!("error: cannot start interpreter thread: {}", e),
+        }
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        eval_here(source)
+    }
+}
+
+fn eval_here(source: &str) -> String {
+    let result = tokenize(source)
+        .and_then(|tokens| Parser::new(tokens).parse_program())
+        .and_then(|program| {
+            Interpreter::new()
+                .with_max_call_depth(EVAL_MAX_CALL_DEPTH)
+                .execute(&program)
+        });
+    match result {
+        Ok(Some(value)) => value.to_string(),
+        Ok(None) => String::new(),
+        Err(e) => e.render(source),
+    }
+}
+
 // ========== REPL ==========
-fn repl() {
-    let mut interpreter = Interpreter::new();
+fn repl(max_call_depth: usize) {
+    let mut interpreter = Interpreter::new().with_max_call_depth(max_call_depth);
+    // Every line entered so far; each input is numbered as the next line, so
+    // errors inside functions defined earlier still show their source
+    let mut history = String::new();
+    let mut line = 0;
     println!("Welcome to the Interpreter REPL!");
     println!("Type expressions or statements. Use Ctrl+C to exit.\n");
 
@@ -707,8 +1699,9 @@ fn repl() {
         io::stdout().flush().unwrap();
 
         let mut input = String::new();
-        if io::stdin().read_line(&mut input).is_err() {
-            break;
+        match io::stdin().read_line(&mut input) {
+            Ok(0) | Err(_) => break, // EOF
+            Ok(_) => {}
         }
 
         let input = input.trim();
@@ -716,47 +1709,160 @@ fn repl() {
             continue;
         }
 
-        let mut lexer = Lexer::new(input);
-        let mut tokens = Vec::new();
-        loop {
-            let token = lexer.next_token();
-            if token == Token::Eof {
-                tokens.push(token);
-                break;
+        line += 1;
+        history.push_str(input);
+        history.push('\n');
+
+        let start = Span { line, column: 1 };
+        let result = tokenize_at(input, start)
+            .and_then(|tokens| Parser::new(tokens).parse_program())
+            .and_then(|program| interpreter.execute(&program));
+        match result {
+            Ok(Some(value)) => println!("{}", value),
+            Ok(None) => {}
+            Err(e) => println!("{}", e.render(&history)),
+        }
+    }
+}
+
+// ========== SCRIPTS ==========
+const STACK_PER_CALL: usize = 64 * 1024;
+const BASE_STACK: usize = 8 * 1024 * 1024;
+
+const USAGE: &str = "usage: compiler-interpreter [--tokens] [--ast] [--max-depth N] [script]";
+
+struct Options {
+    dump_tokens: bool,
+    dump_ast: bool,
+    max_call_depth: usize,
+    script: Option<String>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        dump_tokens: false,
+        dump_ast: false,
+        max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+        script: None,
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--tokens" => options.dump_tokens = true,
+            "--ast" => options.dump_ast = true,
+            "--max-depth" => {
+                options.max_call_depth = args
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .ok_or("--max-depth needs a number")?;
             }
-            tokens.push(token);
+            flag if flag.starts_with('-') => return Err(format!("unknown option: {}", flag)),
+            _ if options.script.is_some() => return Err("only one script can be given".into()),
+            _ => options.script = Some(arg),
         }
+    }
+    if options.script.is_none() && (options.dump_tokens || options.dump_ast) {
+        return Err("--tokens and --ast need a script".into());
+    }
+    Ok(options)
+}
 
-        let mut parser = Parser::new(tokens);
-        match parser.parse_program() {
-            Ok(program) => match interpreter.execute(&program) {
-                Ok(Some(value)) => println!("{}", value),
-                Ok(None) => {}
-                Err(e) => println!("Runtime error: {}", e),
-            },
-            Err(e) => println!("Parse error: {}", e),
+// Blank out a `#!` line rather than removing it, so line numbers in errors
+// still match the file
+fn strip_shebang(source: &str) -> &str {
+    if source.starts_with("#!") {
+        &source[source.find('\n').unwrap_or(source.len())..]
+    } else {
+        source
+    }
+}
+
+// Runs a script and prints the value of its last expression. With --tokens or
+// --ast the script is only dumped, not executed. Returns the exit code.
+fn run_file(path: &str, options: &Options) -> i32 {
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!("error: cannot read {}: {}", path, e);
+            return 1;
+        }
+    };
+
+    let result = tokenize(strip_shebang(&source)).and_then(|tokens| {
+        if options.dump_tokens {
+            for (token, span) in &tokens {
+                println!("{}:{}\t{:?}", span.line, span.column, token);
+            }
+        }
+        let program = Parser::new(tokens).parse_program()?;
+        if options.dump_ast {
+            println!("{:#?}", program);
+        }
+        if options.dump_tokens || options.dump_ast {
+            return Ok(None);
+        }
+        Interpreter::new()
+            .with_max_call_depth(options.max_call_depth)
+            .execute(&program)
+    });
+
+    match result {
+        Ok(value) => {
+            if let Some(value) = value {
+                println!("{}", value);
+            }
+            0
+        }
+        Err(e) => {
+            eprintln!("{}", e.render(&source));
+            1
         }
     }
 }
 
 // ========== MAIN ==========
 fn main() {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("error: {}\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+
+    // Each interpreted call nests several Rust frames, so size the stack to
+    // hit the call depth limit long before the stack itself runs out
+    let stack_size = STACK_PER_CALL
+        .saturating_mul(options.max_call_depth)
+        .saturating_add(BASE_STACK);
+    let interpreter = thread::Builder::new()
+        .stack_size(stack_size)
+        .spawn(move || match &options.script {
+            Some(path) => run_file(path, &options),
+            None => {
+                demo();
+                println!("\n=== Starting REPL ===");
+                repl(options.max_call_depth);
+                0
+            }
+        });
+
+    let code = match interpreter {
+        Ok(handle) => handle.join().unwrap_or(101),
+        Err(e) => {
+            eprintln!("error: cannot start interpreter thread: {}", e);
+            1
+        }
+    };
+    process::exit(code);
+}
+
+fn demo() {
     println!("=== Compiler/Interpreter Demo ===\n");
 
     // Example 1: Basic arithmetic
     println!("Example 1: Basic Arithmetic");
     let code1 = "2 + 3 * 4;";
-    let mut lexer = Lexer::new(code1);
-    let mut tokens = Vec::new();
-    loop {
-        let token = lexer.next_token();
-        if token == Token::Eof {
-            tokens.push(token);
-            break;
-        }
-        tokens.push(token);
-    }
-    let mut parser = Parser::new(tokens);
+    let mut parser = Parser::new(tokenize(code1).unwrap());
     let program = parser.parse_program().unwrap();
     let mut interpreter = Interpreter::new();
     if let Ok(Some(result)) = interpreter.execute(&program) {
@@ -766,17 +1872,7 @@ fn main() {
     // Example 2: Variables
     println!("Example 2: Variables");
     let code2 = "x = 10; y = 20; x + y;";
-    let mut lexer = Lexer::new(code2);
-    let mut tokens = Vec::new();
-    loop {
-        let token = lexer.next_token();
-        if token == Token::Eof {
-            tokens.push(token);
-            break;
-        }
-        tokens.push(token);
-    }
-    let mut parser = Parser::new(tokens);
+    let mut parser = Parser::new(tokenize(code2).unwrap());
     let program = parser.parse_program().unwrap();
     let mut interpreter = Interpreter::new();
     if let Ok(Some(result)) = interpreter.execute(&program) {
@@ -791,17 +1887,7 @@ fn main() {
         }
         add(5, 7);
     "#;
-    let mut lexer = Lexer::new(code3);
-    let mut tokens = Vec::new();
-    loop {
-        let token = lexer.next_token();
-        if token == Token::Eof {
-            tokens.push(token);
-            break;
-        }
-        tokens.push(token);
-    }
-    let mut parser = Parser::new(tokens);
+    let mut parser = Parser::new(tokenize(code3).unwrap());
     let program = parser.parse_program().unwrap();
     let mut interpreter = Interpreter::new();
     if let Ok(Some(result)) = interpreter.execute(&program) {
@@ -819,17 +1905,7 @@ fn main() {
         }
         result;
     "#;
-    let mut lexer = Lexer::new(code4);
-    let mut tokens = Vec::new();
-    loop {
-        let token = lexer.next_token();
-        if token == Token::Eof {
-            tokens.push(token);
-            break;
-        }
-        tokens.push(token);
-    }
-    let mut parser = Parser::new(tokens);
+    let mut parser = Parser::new(tokenize(code4).unwrap());
     let program = parser.parse_program().unwrap();
     let mut interpreter = Interpreter::new();
     if let Ok(Some(result)) = interpreter.execute(&program) {
@@ -850,17 +1926,7 @@ fn main() {
         }
         factorial(5);
     "#;
-    let mut lexer = Lexer::new(code5);
-    let mut tokens = Vec::new();
-    loop {
-        let token = lexer.next_token();
-        if token == Token::Eof {
-            tokens.push(token);
-            break;
-        }
-        tokens.push(token);
-    }
-    let mut parser = Parser::new(tokens);
+    let mut parser = Parser::new(tokenize(code5).unwrap());
     let program = parser.parse_program().unwrap();
     let mut interpreter = Interpreter::new();
     if let Ok(Some(result)) = interpreter.execute(&program) {
@@ -888,23 +1954,145 @@ fn main() {
         }
         fib(10);
     "#;
-    let mut lexer = Lexer::new(code6);
-    let mut tokens = Vec::new();
-    loop {
-        let token = lexer.next_token();
-        if token == Token::Eof {
-            tokens.push(token);
-            break;
-        }
-        tokens.push(token);
-    }
-    let mut parser = Parser::new(tokens);
+    let mut parser = Parser::new(tokenize(code6).unwrap());
     let program = parser.parse_program().unwrap();
     let mut interpreter = Interpreter::new();
     if let Ok(Some(result)) = interpreter.execute(&program) {
         println!("fib(10) = {}\n", result);
     }
 
-    println!("\n=== Starting REPL ===");
-    repl();
+    // Example 7: Strings
+    println!("Example 7: String Interpolation and Escapes");
+    let code7 = r#"
+        name = "world";
+        n = 6;
+        "Hello, ${name}!\n\tfib(${n}) = ${fib(n)}, escaped: \${n} \\ \"done\"";
+    "#;
+    let program = Parser::new(tokenize(code7).unwrap()).parse_program().unwrap();
+    let mut interpreter = Interpreter::new();
+    interpreter.execute(&Parser::new(tokenize(code6).unwrap()).parse_program().unwrap()).unwrap();
+    if let Ok(Some(result)) = interpreter.execute(&program) {
+        println!("{}\n", result);
+    }
+
+    // Example 8: String operations
+    println!("Example 8: String Operations");
+    let code8 = r#"
+        greeting = "Hello, " + "world";
+        fn initials(first, last) {
+            return substr(first, 0, 1) + substr(last, 0, 1);
+        }
+        if ("apple" < "banana") {
+            order = "sorted";
+        } else {
+            order = "unsorted";
+        }
+        greeting + " has " + len(greeting) + " chars, initials " + initials("Ada", "Lovelace") + ", " + order;
+    "#;
+    let program = Parser::new(tokenize(code8).unwrap()).parse_program().unwrap();
+    let mut interpreter = Interpreter::new();
+    if let Ok(Some(result)) = interpreter.execute(&program) {
+        println!("{}\n", result);
+    }
+
+    // Example 9: Error reporting
+    println!("Example 9: Errors with Source Locations");
+    let code9 = "total = 0;\nfn average(a, b) {\n    return (a + b) / count;\n}\naverage(3, 4);";
+    println!("{}\n", eval(code9));
+
+    // Example 10: Booleans
+    println!("Example 10: Booleans and Chained Comparisons");
+    let code10 = r#"
+        fn in_range(x, lo, hi) {
+            return lo <= x <= hi;
+        }
+        fn describe(n) {
+            if (in_range(n, 1, 9) && !(n == 5) || n >= 100) {
+                return "ok";
+            }
+            return "skip";
+        }
+        "" + describe(3) + " " + describe(5) + " " + describe(250) + " " + (3 > 2 > 1);
+    "#;
+    let program = Parser::new(tokenize(code10).unwrap()).parse_program().unwrap();
+    let mut interpreter = Interpreter::new();
+    if let Ok(Some(result)) = interpreter.execute(&program) {
+        println!("{}\n", result);
+    }
+    if let Err(e) = tokenize("if (1) { 2; }")
+        .and_then(|tokens| Parser::new(tokens).parse_program())
+        .and_then(|program| Interpreter::new().execute(&program))
+    {
+        println!("{}\n", e);
+    }
+
+    // Example 11: Loop control
+    println!("Example 11: For Loops, Break, and Continue");
+    let code11 = r#"
+        fn first_square_over(limit) {
+            for (i in 1..100) {
+                if (i * i > limit) {
+                    return i * i;
+                }
+            }
+            return 0;
+        }
+        total = 0;
+        for (i = 0; i < 10; i = i + 1) {
+            if (i == 3) { continue; }
+            if (i == 7) { break; }
+            total = total + i;
+        }
+        "total = ${total}, first square over 50 = ${first_square_over(50)}";
+    "#;
+    let program = Parser::new(tokenize(code11).unwrap()).parse_program().unwrap();
+    let mut interpreter = Interpreter::new();
+    if let Ok(Some(result)) = interpreter.execute(&program) {
+        println!("{}\n", result);
+    }
+
+    // Example 12: Tail calls and the call depth limit
+    println!("Example 12: Tail Calls and Call Depth");
+    let code12 = r#"
+        fn count_down(n, steps) {
+            if (n == 0) {
+                return steps;
+            }
+            return count_down(n - 1, steps + 1);
+        }
+        fn depth(n) {
+            return 1 + depth(n + 1);
+        }
+        "count_down(100000) took ${count_down(100000, 0)} steps";
+    "#;
+    let program = Parser::new(tokenize(code12).unwrap()).parse_program().unwrap();
+    let mut interpreter = Interpreter::new();
+    if let Ok(Some(result)) = interpreter.execute(&program) {
+        println!("{}", result);
+    }
+    let overflow = Parser::new(tokenize("depth(0);").unwrap()).parse_program().unwrap();
+    if let Err(e) = interpreter.execute(&overflow) {
+        println!("{}\n", e);
+    }
+
+    // Example 13: Maps
+    println!("Example 13: Maps");
+    let code13 = r#"
+        inventory = {apples: 3, pears: 0};
+        set(inventory, "plums", 7);
+        remove(inventory, "pears");
+        fn total(m) {
+            sum = 0;
+            for (item in m) {
+                sum = sum + get(m, item);
+            }
+            return sum;
+        }
+        "${inventory} holds ${total(inventory)} items, kiwis: ${get(inventory, "kiwis", 0)}";
+    "#;
+    let program = Parser::new(tokenize(code13).unwrap()).parse_program().unwrap();
+    let mut interpreter = Interpreter::new();
+    if let Ok(Some(result)) = interpreter.execute(&program) {
+        println!("{}\n", result);
+    }
 }
@@ -0,0 +1,65 @@
+//! Library build of `../compiler-interpreter.rs` for embedding the interpreter,
+//! e.g. in a browser playground. The lexer, parser and interpreter stay in the
+//! single-file example; this crate only compiles it as a module and re-exports
+//! `eval`, which the `wasm` feature exports to JavaScript through wasm-bindgen.
+//!
+//! ```bash
+//! wasm-pack build --target web -- --features wasm
+//! ```
+
+// The example's CLI (main, REPL, demo) is unused from the library
+#[allow(dead_code)]
+#[path = "../../compiler-interpreter.rs"]
+mod interpreter;
+
+pub use interpreter::eval;
+
+#[cfg(test)]
+mod tests {
+    use super::eval;
+
+    #[test]
+    fn test_eval_returns_last_value() {
+        assert_eq!(eval("x = 2; y = 3; x * y;"), "6");
+        assert_eq!(eval("name = \"web\"; \"hi ${name}\";"), "hi web");
+    }
+
+    #[test]
+    fn test_eval_renders_errors() {
+        let out = eval("1 +;");
+        assert!(out.contains("1 +;"), "error should quote the source: {}", out);
+        assert_eq!(eval("x = 1;"), "");
+    }
+
+    #[test]
+    fn test_eval_depth_limit_fits_a_small_stack() {
+        let deep = |n: usize| {
+            format!("fn g(n) {{ if (n == 0) {{ return 0; }} return 1 + g(n - 1); }} g({});", n)
+        };
+        // Even a 1 MB caller, like a browser tab, gets the error instead of a crash
+        let run = |source: String| {
+            std::thread::Builder::new()
+                .stack_size(1024 * 1024)
+                .spawn(move || eval(&source))
+                .unwrap()
+                .join()
+                .unwrap()
+        };
+
+        assert_eq!(run(deep(100)), "100");
+        let out = run(deep(900));
+        assert!(out.contains("Maximum call depth of 200 exceeded"), "{}", out);
+    }
+
+    #[test]
+    fn test_eval_rejects_map_cycles() {
+        let out = eval("m = {}; set(m, \"self\", m);");
+        assert!(out.contains("Cannot store a map inside itself"), "{}", out);
+
+        let out = eval("m = {}; inner = {}; set(m, \"a\", inner); set(inner, \"back\", {wrap: m});");
+        assert!(out.contains("Cannot store a map inside itself"), "{}", out);
+
+        // The same map may still appear under several keys
+        assert_eq!(eval("m = {}; s = {}; set(m, \"a\", s); set(m, \"b\", s); m;"), "{\"a\": {}, \"b\": {}}");
+    }
+}
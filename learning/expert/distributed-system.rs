@@ -1,14 +1,25 @@
 // Distributed System with Raft Consensus Algorithm
-// Implements leader election, log replication, and fault tolerance
+// Implements leader election, log replication, snapshotting, membership
+// changes, and fault tolerance, with a replicated key-value store on top
+// Runs as an in-process simulation by default, or as one node per process
+// over TCP with --node (see USAGE)
 
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
-use tokio::sync::mpsc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
 use tokio::time::{interval, sleep};
 
 // ========== RAFT MESSAGE TYPES ==========
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 enum RaftMessage {
     RequestVote {
         term: u64,
@@ -27,24 +38,101 @@ enum RaftMessage {
         prev_log_term: u64,
         entries: Vec<LogEntry>,
         leader_commit: usize,
+        // Heartbeat round, echoed back so the leader can confirm it still
+        // leads before serving a read
+        round: u64,
     },
     AppendEntriesResponse {
         term: u64,
         success: bool,
         match_index: usize,
+        round: u64,
     },
+    InstallSnapshot {
+        term: u64,
+        leader_id: u64,
+        last_included_index: usize,
+        last_included_term: u64,
+        members: Vec<u64>,
+        data: String,
+    },
+    InstallSnapshotResponse {
+        term: u64,
+        match_index: usize,
+    },
+    // `sequence` numbers each client's requests so retries apply only once
     ClientRequest {
+        client_id: u64,
+        sequence: u64,
+        command: String,
+    },
+    // `leader` is who the responding node believes leads, so a client that
+    // asked a follower knows where to go instead
+    ClientResponse {
+        client_id: u64,
+        sequence: u64,
+        result: Result<String, String>,
+        leader: Option<u64>,
+    },
+    ConfigChange {
+        change: MembershipChange,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+enum MembershipChange {
+    AddNode(u64),
+    RemoveNode(u64),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum Command {
+    // Applied to the key-value store
+    Client {
+        client_id: u64,
+        sequence: u64,
         command: String,
     },
+    // New cluster configuration: the IDs of all voting members. Takes effect
+    // on each node as soon as it is in the log, committed or not.
+    Config(Vec<u64>),
+    // Appended by a new leader so that it commits an entry from its own term
+    Noop,
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Command::Client { client_id, sequence, command } => {
+                write!(f, "{} (client {} #{})", command, client_id, sequence)
+            }
+            Command::Config(members) => write!(f, "CONFIG {:?}", members),
+            Command::Noop => write!(f, "NOOP"),
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct LogEntry {
     term: u64,
     index: usize,
-    command: String,
+    command: Command,
+}
+
+// Compacted prefix of the log: everything up to and including
+// `last_included_index`, replaced by the state machine it produced
+#[derive(Debug, Clone, Default)]
+struct Snapshot {
+    last_included_index: usize,
+    last_included_term: u64,
+    // Configuration as of `last_included_index`
+    members: Vec<u64>,
+    data: String,
 }
 
+// Take a snapshot once this many applied entries sit in the log
+const SNAPSHOT_THRESHOLD: usize = 8;
+
 #[derive(Debug, Clone, PartialEq)]
 enum NodeState {
     Follower,
@@ -52,93 +140,447 @@ enum NodeState {
     Leader,
 }
 
+// A GET waiting until the leader knows it is still the leader and has
+// applied everything committed when the read arrived
+struct PendingRead {
+    client_id: u64,
+    sequence: u64,
+    key: String,
+    read_index: usize,
+    round: u64,
+}
+
+// ========== EVENTS ==========
+// What a node reports about elections and replication as it happens
+#[derive(Debug, Clone, PartialEq)]
+enum RaftEvent {
+    TermChanged { node: u64, term: u64 },
+    // The election timeout fired without word from a leader
+    HeartbeatMissed { node: u64, term: u64 },
+    ElectionStarted { node: u64, term: u64 },
+    ElectionWon { node: u64, term: u64, votes: usize },
+    // The commit index advanced to `index`
+    EntriesCommitted { node: u64, index: usize },
+}
+
+impl fmt::Display for RaftEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RaftEvent::TermChanged { node, term } => write!(f, "node {} moved to term {}", node, term),
+            RaftEvent::HeartbeatMissed { node, term } => {
+                write!(f, "node {} heard from no leader in term {}", node, term)
+            }
+            RaftEvent::ElectionStarted { node, term } => {
+                write!(f, "node {} started an election for term {}", node, term)
+            }
+            RaftEvent::ElectionWon { node, term, votes } => {
+                write!(f, "node {} won term {} with {} votes", node, term, votes)
+            }
+            RaftEvent::EntriesCommitted { node, index } => {
+                write!(f, "node {} committed through index {}", node, index)
+            }
+        }
+    }
+}
+
+// Receives every node's events, from whichever task the node runs on
+trait EventSubscriber: Send + Sync {
+    fn on_event(&self, event: &RaftEvent);
+}
+
+// Prints each event as it arrives
+struct ConsoleReporter;
+
+impl EventSubscriber for ConsoleReporter {
+    fn on_event(&self, event: &RaftEvent) {
+        println!("[Event] {}", event);
+    }
+}
+
+// Keeps every event so scenarios can check the history afterwards
+#[derive(Default)]
+struct EventCollector {
+    events: Mutex<Vec<RaftEvent>>,
+}
+
+impl EventSubscriber for EventCollector {
+    fn on_event(&self, event: &RaftEvent) {
+        self.events.lock().unwrap().push(event.clone());
+    }
+}
+
+impl EventCollector {
+    fn events(&self) -> Vec<RaftEvent> {
+        self.events.lock().unwrap().clone()
+    }
+
+    fn count(&self, matches: impl Fn(&RaftEvent) -> bool) -> usize {
+        self.events.lock().unwrap().iter().filter(|e| matches(e)).count()
+    }
+
+    // Winner of each term, or an error if some term was won twice
+    fn leaders_by_term(&self) -> Result<BTreeMap<u64, u64>, String> {
+        let mut leaders = BTreeMap::new();
+        for event in self.events() {
+            if let RaftEvent::ElectionWon { node, term, .. } = event {
+                if let Some(other) = leaders.insert(term, node) {
+                    return Err(format!("nodes {} and {} both won term {}", other, node, term));
+                }
+            }
+        }
+        Ok(leaders)
+    }
+
+    // Properties of the history itself, for a cluster of `cluster_size`:
+    // every winner had a majority, and no node's term or commit index ever
+    // went backwards
+    fn check_history(&self, cluster_size: usize) -> Result<(), String> {
+        self.leaders_by_term()?;
+        let mut terms: HashMap<u64, u64> = HashMap::new();
+        let mut commits: HashMap<u64, usize> = HashMap::new();
+        for event in self.events() {
+            match event {
+                RaftEvent::ElectionWon { node, term, votes } if votes <= cluster_size / 2 => {
+                    return Err(format!("node {} won term {} with only {} of {} votes", node, term, votes, cluster_size));
+                }
+                RaftEvent::TermChanged { node, term } => {
+                    if let Some(previous) = terms.insert(node, term).filter(|&previous| previous > term) {
+                        return Err(format!("node {} went back from term {} to {}", node, previous, term));
+                    }
+                }
+                RaftEvent::EntriesCommitted { node, index } => {
+                    if let Some(previous) = commits.insert(node, index).filter(|&previous| previous > index) {
+                        return Err(format!("node {} uncommitted from index {} to {}", node, previous, index));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn print_metrics(&self) {
+        let mut committed: BTreeMap<u64, usize> = BTreeMap::new();
+        for event in self.events() {
+            if let RaftEvent::EntriesCommitted { node, index } = event {
+                committed.insert(node, index);
+            }
+        }
+
+        println!("  Term changes:      {}", self.count(|e| matches!(e, RaftEvent::TermChanged { .. })));
+        println!("  Heartbeats missed: {}", self.count(|e| matches!(e, RaftEvent::HeartbeatMissed { .. })));
+        println!("  Elections started: {}", self.count(|e| matches!(e, RaftEvent::ElectionStarted { .. })));
+        println!("  Elections won:     {}", self.count(|e| matches!(e, RaftEvent::ElectionWon { .. })));
+        println!("  Leaders by term:   {:?}", self.leaders_by_term());
+        println!("  Committed through: {:?}", committed);
+    }
+}
+
+// ========== CLOCKS ==========
+// Time since the clock started. Nodes only ever compare two readings, so a
+// simulation can hand them a clock it moves forward itself.
+trait Clock: Send + Sync {
+    fn now(&self) -> Duration;
+}
+
+struct SystemClock(Instant);
+
+impl SystemClock {
+    fn new() -> Self {
+        SystemClock(Instant::now())
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.0.elapsed()
+    }
+}
+
+#[derive(Default)]
+struct VirtualClock(Mutex<Duration>);
+
+impl VirtualClock {
+    fn advance_to(&self, time: Duration) {
+        *self.0.lock().unwrap() = time;
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now(&self) -> Duration {
+        *self.0.lock().unwrap()
+    }
+}
+
+// Xorshift step; `state` must not be zero
+fn xorshift(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+// Uniform in [0, 1)
+fn random_unit(state: &mut u64) -> f64 {
+    (xorshift(state) >> 11) as f64 / (1u64 << 53) as f64
+}
+
 // ========== RAFT NODE ==========
+// Each election timeout is drawn afresh from [MIN, MIN + SPREAD)
+const ELECTION_TIMEOUT_MIN: Duration = Duration::from_millis(150);
+const ELECTION_TIMEOUT_SPREAD: Duration = Duration::from_millis(150);
 struct RaftNode {
     id: u64,
     state: NodeState,
     current_term: u64,
     voted_for: Option<u64>,
+    // Leader of the current term, once we've heard from it
+    leader_id: Option<u64>,
+    // Entries after the snapshot; entry `i` lives at `log[i - snapshot.last_included_index - 1]`
     log: Vec<LogEntry>,
+    snapshot: Snapshot,
     commit_index: usize,
     last_applied: usize,
+
+    kv: KvStore,
     
     // Leader-specific state
     next_index: HashMap<u64, usize>,
     match_index: HashMap<u64, usize>,
+    // Index of the no-op this leader appended on election
+    term_start_index: usize,
+    // Heartbeat round last sent, and the latest each peer has answered
+    round: u64,
+    acked_round: HashMap<u64, u64>,
+    pending_reads: Vec<PendingRead>,
+    // Responses to clients, sent by the node's task
+    outbox: Vec<(u64, RaftMessage)>,
+    subscriber: Arc<dyn EventSubscriber>,
     
-    // Timing
-    last_heartbeat: Instant,
+    // Timing: `last_heartbeat` is a reading of `clock`, and `rng` picks
+    // election timeouts
+    clock: Arc<dyn Clock>,
+    rng: u64,
+    last_heartbeat: Duration,
     election_timeout: Duration,
     heartbeat_interval: Duration,
+    // Print what the node is doing
+    verbose: bool,
     
     // Voting
     votes_received: usize,
     
-    // Peers
+    // Current configuration: all voting members, and the ones other than us
+    members: Vec<u64>,
     peers: Vec<u64>,
 }
 
 impl RaftNode {
-    fn new(id: u64, peers: Vec<u64>) -> Self {
-        let election_timeout = Duration::from_millis(150 + (id * 50));
+    // `members` is the initial configuration; a node joining a running
+    // cluster starts with none and waits to be added
+    fn new(id: u64, members: Vec<u64>, subscriber: Arc<dyn EventSubscriber>) -> Self {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.subsec_nanos());
+        let peers = members.iter().copied().filter(|&m| m != id).collect();
         
         RaftNode {
             id,
             state: NodeState::Follower,
             current_term: 0,
             voted_for: None,
+            leader_id: None,
             log: Vec::new(),
+            snapshot: Snapshot {
+                members: members.clone(),
+                ..Snapshot::default()
+            },
             commit_index: 0,
             last_applied: 0,
+            kv: KvStore::default(),
             next_index: HashMap::new(),
             match_index: HashMap::new(),
-            last_heartbeat: Instant::now(),
-            election_timeout,
+            term_start_index: 0,
+            round: 0,
+            acked_round: HashMap::new(),
+            pending_reads: Vec::new(),
+            outbox: Vec::new(),
+            subscriber,
+            clock: Arc::new(SystemClock::new()),
+            rng: (u64::from(nanos) << 8 | id) | 1,
+            last_heartbeat: Duration::ZERO,
+            election_timeout: ELECTION_TIMEOUT_MIN,
             heartbeat_interval: Duration::from_millis(50),
+            verbose: true,
             votes_received: 0,
+            members,
             peers,
         }
     }
 
+    // Run on `clock`, with election timeouts drawn from `seed`
+    fn with_clock(mut self, clock: Arc<dyn Clock>, seed: u64) -> Self {
+        self.clock = clock;
+        self.rng = seed | 1;
+        self.reset_election_timer();
+        self
+    }
+
+    fn quorum(&self) -> usize {
+        self.members.len() / 2 + 1
+    }
+
+    // Only voting members time out and campaign
+    fn is_voter(&self) -> bool {
+        self.members.contains(&self.id)
+    }
+
+    // Configuration in effect after `index`: the last config entry up to
+    // there, or the snapshot's
+    fn config_at(&self, index: usize) -> Vec<u64> {
+        let base = self.snapshot.last_included_index;
+        self.log[..index.saturating_sub(base).min(self.log.len())]
+            .iter()
+            .rev()
+            .find_map(|e| match &e.command {
+                Command::Config(members) => Some(members.clone()),
+                Command::Client { .. } | Command::Noop => None,
+            })
+            .unwrap_or_else(|| self.snapshot.members.clone())
+    }
+
+    // Index of the newest configuration entry still in the log
+    fn last_config_index(&self) -> Option<usize> {
+        self.log
+            .iter()
+            .rev()
+            .find(|e| matches!(e.command, Command::Config(_)))
+            .map(|e| e.index)
+    }
+
+    // Adopt the latest configuration in the log; called after every change to it
+    fn refresh_config(&mut self) {
+        let members = self.config_at(self.last_log_index());
+        if members == self.members {
+            return;
+        }
+        self.log(format_args!("Configuration is now {:?}", members));
+
+        self.peers = members.iter().copied().filter(|&m| m != self.id).collect();
+        self.members = members;
+        let next_idx = self.last_log_index() + 1;
+        for peer in &self.peers {
+            self.next_index.entry(*peer).or_insert(next_idx);
+            self.match_index.entry(*peer).or_insert(0);
+        }
+    }
+
+    fn last_log_index(&self) -> usize {
+        self.snapshot.last_included_index + self.log.len()
+    }
+
+    fn last_log_term(&self) -> u64 {
+        self.log.last().map_or(self.snapshot.last_included_term, |e| e.term)
+    }
+
+    // Term of the entry at `index`, if it is still known (0 for the empty prefix)
+    fn term_at(&self, index: usize) -> Option<u64> {
+        let base = self.snapshot.last_included_index;
+        if index == base {
+            Some(self.snapshot.last_included_term)
+        } else if index > base {
+            self.log.get(index - base - 1).map(|e| e.term)
+        } else {
+            None
+        }
+    }
+
+    fn entry(&self, index: usize) -> &LogEntry {
+        &self.log[index - self.snapshot.last_included_index - 1]
+    }
+
     fn reset_election_timer(&mut self) {
-        self.last_heartbeat = Instant::now();
+        self.last_heartbeat = self.clock.now();
+        self.election_timeout = ELECTION_TIMEOUT_MIN + ELECTION_TIMEOUT_SPREAD.mul_f64(random_unit(&mut self.rng));
     }
 
     fn is_election_timeout(&self) -> bool {
-        self.last_heartbeat.elapsed() > self.election_timeout
+        self.clock.now().saturating_sub(self.last_heartbeat) > self.election_timeout
+    }
+
+    fn log(&self, message: fmt::Arguments) {
+        if self.verbose {
+            println!("[Node {}] {}", self.id, message);
+        }
+    }
+
+    fn emit(&self, event: RaftEvent) {
+        self.subscriber.on_event(&event);
     }
 
     fn start_election(&mut self) {
         self.state = NodeState::Candidate;
         self.current_term += 1;
+        self.emit(RaftEvent::TermChanged { node: self.id, term: self.current_term });
+        self.emit(RaftEvent::ElectionStarted { node: self.id, term: self.current_term });
         self.voted_for = Some(self.id);
+        self.leader_id = None;
         self.votes_received = 1;
         self.reset_election_timer();
         
-        println!(
-            "[Node {}] Starting election for term {}",
-            self.id, self.current_term
-        );
+        self.log(format_args!(
+            "Starting election for term {}",
+            self.current_term
+        ));
+        // A single-node cluster needs no one else's vote
+        if self.votes_received >= self.quorum() {
+            self.become_leader();
+        }
     }
 
     fn become_leader(&mut self) {
-        println!("[Node {}] Became leader for term {}", self.id, self.current_term);
+        self.log(format_args!("Became leader for term {}", self.current_term));
         self.state = NodeState::Leader;
+        self.leader_id = Some(self.id);
+        self.emit(RaftEvent::ElectionWon {
+            node: self.id,
+            term: self.current_term,
+            votes: self.votes_received,
+        });
         
-        let next_idx = self.log.len();
+        let next_idx = self.last_log_index() + 1;
         for peer in &self.peers {
             self.next_index.insert(*peer, next_idx);
             self.match_index.insert(*peer, 0);
         }
+        self.acked_round.clear();
+
+        // Until an entry from this term commits, we can't know which earlier
+        // entries are committed, so reads wait for this one
+        self.term_start_index = next_idx;
+        self.log.push(LogEntry {
+            term: self.current_term,
+            index: next_idx,
+            command: Command::Noop,
+        });
+        self.update_commit_index();
     }
 
     fn become_follower(&mut self, term: u64) {
         if term > self.current_term {
             self.current_term = term;
             self.voted_for = None;
+            self.leader_id = None;
+            self.emit(RaftEvent::TermChanged { node: self.id, term });
+        }
+        if self.leader_id == Some(self.id) {
+            self.leader_id = None;
         }
         self.state = NodeState::Follower;
         self.reset_election_timer();
+        // Reads waiting for our leadership to be confirmed never will be;
+        // the clients time out and retry elsewhere
+        self.pending_reads.clear();
     }
 
     fn handle_request_vote(
@@ -155,22 +597,18 @@ impl RaftNode {
         }
 
         if term >= self.current_term {
-            let log_ok = if self.log.is_empty() {
-                last_log_index == 0
-            } else {
-                let my_last_log = &self.log[self.log.len() - 1];
-                last_log_term > my_last_log.term
-                    || (last_log_term == my_last_log.term && last_log_index >= my_last_log.index)
-            };
+            let my_last_term = self.last_log_term();
+            let log_ok = last_log_term > my_last_term
+                || (last_log_term == my_last_term && last_log_index >= self.last_log_index());
 
             if (self.voted_for.is_none() || self.voted_for == Some(candidate_id)) && log_ok {
                 vote_granted = true;
                 self.voted_for = Some(candidate_id);
                 self.reset_election_timer();
-                println!(
-                    "[Node {}] Granted vote to {} for term {}",
-                    self.id, candidate_id, term
-                );
+                self.log(format_args!(
+                    "Granted vote to {} for term {}",
+                    candidate_id, term
+                ));
             }
         }
 
@@ -188,14 +626,14 @@ impl RaftNode {
 
         if self.state == NodeState::Candidate && term == self.current_term && vote_granted {
             self.votes_received += 1;
-            let majority = (self.peers.len() + 1) / 2 + 1;
             
-            if self.votes_received >= majority {
+            if self.votes_received >= self.quorum() {
                 self.become_leader();
             }
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn handle_append_entries(
         &mut self,
         term: u64,
@@ -204,6 +642,7 @@ impl RaftNode {
         prev_log_term: u64,
         entries: Vec<LogEntry>,
         leader_commit: usize,
+        round: u64,
     ) -> RaftMessage {
         if term > self.current_term {
             self.become_follower(term);
@@ -216,51 +655,108 @@ impl RaftNode {
                 term: self.current_term,
                 success: false,
                 match_index: 0,
+                round,
             };
         }
 
         if self.state == NodeState::Candidate {
             self.become_follower(term);
         }
+        self.leader_id = Some(leader_id);
 
-        let log_ok = if prev_log_index == 0 {
-            true
-        } else if prev_log_index <= self.log.len() {
-            self.log[prev_log_index - 1].term == prev_log_term
-        } else {
-            false
-        };
+        // Everything up to the snapshot is committed, so it matches any leader
+        let log_ok = prev_log_index <= self.snapshot.last_included_index
+            || self.term_at(prev_log_index) == Some(prev_log_term);
 
         if !log_ok {
             return RaftMessage::AppendEntriesResponse {
                 term: self.current_term,
                 success: false,
-                match_index: self.log.len(),
+                match_index: self.last_log_index(),
+                round,
             };
         }
 
         let mut index = prev_log_index;
         for entry in entries {
             index += 1;
-            if index <= self.log.len() {
-                if self.log[index - 1].term != entry.term {
-                    self.log.truncate(index - 1);
+            if index <= self.snapshot.last_included_index {
+                continue;
+            }
+            match self.term_at(index) {
+                Some(term) if term == entry.term => {}
+                Some(_) => {
+                    self.log.truncate(index - self.snapshot.last_included_index - 1);
                     self.log.push(entry);
                 }
-            } else {
-                self.log.push(entry);
+                None => self.log.push(entry),
             }
         }
+        self.refresh_config();
 
         if leader_commit > self.commit_index {
-            self.commit_index = leader_commit.min(self.log.len());
+            self.commit_index = leader_commit.min(self.last_log_index());
             self.apply_committed_entries();
         }
 
         RaftMessage::AppendEntriesResponse {
             term: self.current_term,
             success: true,
-            match_index: self.log.len(),
+            match_index: index.max(self.snapshot.last_included_index),
+            round,
+        }
+    }
+
+    fn handle_install_snapshot(
+        &mut self,
+        term: u64,
+        leader_id: u64,
+        last_included_index: usize,
+        last_included_term: u64,
+        members: Vec<u64>,
+        data: String,
+    ) -> RaftMessage {
+        if term > self.current_term || self.state == NodeState::Candidate {
+            self.become_follower(term);
+        }
+        if term < self.current_term {
+            return RaftMessage::InstallSnapshotResponse {
+                term: self.current_term,
+                match_index: 0,
+            };
+        }
+        self.reset_election_timer();
+        self.leader_id = Some(leader_id);
+
+        // A stale or duplicate snapshot covers nothing we haven't applied
+        if last_included_index > self.commit_index {
+            // Keep any entries that follow the snapshot if our log agrees with it
+            if self.term_at(last_included_index) == Some(last_included_term) {
+                self.log.drain(..last_included_index - self.snapshot.last_included_index);
+            } else {
+                self.log.clear();
+            }
+
+            self.kv = KvStore::decode(&data);
+            self.snapshot = Snapshot {
+                last_included_index,
+                last_included_term,
+                members,
+                data,
+            };
+            self.commit_index = last_included_index;
+            self.last_applied = last_included_index;
+            self.refresh_config();
+
+            self.log(format_args!(
+                "Installed snapshot from leader {} through index {} (term {})",
+                leader_id, last_included_index, last_included_term
+            ));
+        }
+
+        RaftMessage::InstallSnapshotResponse {
+            term: self.current_term,
+            match_index: last_included_index,
         }
     }
 
@@ -270,6 +766,7 @@ impl RaftNode {
         term: u64,
         success: bool,
         match_index: usize,
+        round: u64,
     ) {
         if term > self.current_term {
             self.become_follower(term);
@@ -280,16 +777,38 @@ impl RaftNode {
             return;
         }
 
+        // Any reply from our term, success or not, shows the peer still
+        // accepts us as leader
+        let acked = self.acked_round.entry(peer_id).or_insert(0);
+        *acked = (*acked).max(round);
+        self.serve_reads();
+
         if success {
+            self.record_match(peer_id, match_index);
+        } else {
+            // Back up one entry, or straight past the end of a short follower log
+            let next = self.next_index.get(&peer_id).copied().unwrap_or(1);
+            self.next_index.insert(peer_id, (next - 1).min(match_index + 1).max(1));
+        }
+    }
+
+    fn handle_install_snapshot_response(&mut self, peer_id: u64, term: u64, match_index: usize) {
+        if term > self.current_term {
+            self.become_follower(term);
+            return;
+        }
+
+        if self.state == NodeState::Leader && term == self.current_term {
+            self.record_match(peer_id, match_index);
+        }
+    }
+
+    fn record_match(&mut self, peer_id: u64, match_index: usize) {
+        // Responses can arrive out of order; never move a peer backwards
+        if match_index >= self.match_index.get(&peer_id).copied().unwrap_or(0) {
             self.next_index.insert(peer_id, match_index + 1);
             self.match_index.insert(peer_id, match_index);
-            
             self.update_commit_index();
-        } else {
-            let next = self.next_index.get(&peer_id).copied().unwrap_or(1);
-            if next > 1 {
-                self.next_index.insert(peer_id, next - 1);
-            }
         }
     }
 
@@ -298,86 +817,214 @@ impl RaftNode {
             return;
         }
 
-        for n in (self.commit_index + 1)..=self.log.len() {
-            if self.log[n - 1].term == self.current_term {
-                let mut count = 1;
+        for n in (self.commit_index + 1)..=self.last_log_index() {
+            if self.entry(n).term == self.current_term {
+                // A leader that is removing itself doesn't count its own log
+                let mut count = usize::from(self.is_voter());
                 for peer in &self.peers {
                     if self.match_index.get(peer).copied().unwrap_or(0) >= n {
                         count += 1;
                     }
                 }
                 
-                let majority = (self.peers.len() + 1) / 2 + 1;
-                if count >= majority {
+                if count >= self.quorum() {
                     self.commit_index = n;
                     self.apply_committed_entries();
                 }
             }
         }
+
+        // Once the configuration that removes us is committed, step down
+        if !self.is_voter() && self.last_config_index().is_none_or(|i| i <= self.commit_index) {
+            self.log(format_args!("Removed from the cluster, stepping down"));
+            self.become_follower(self.current_term);
+        }
     }
 
     fn apply_committed_entries(&mut self) {
+        if self.commit_index > self.last_applied {
+            self.emit(RaftEvent::EntriesCommitted { node: self.id, index: self.commit_index });
+        }
         while self.last_applied < self.commit_index {
             self.last_applied += 1;
-            let entry = &self.log[self.last_applied - 1];
-            println!(
-                "[Node {}] Applied log entry {}: {}",
-                self.id, entry.index, entry.command
-            );
+            let entry = self.entry(self.last_applied).clone();
+            self.log(format_args!(
+                "Applied log entry {}: {}",
+                entry.index, entry.command
+            ));
+            if let Command::Client { client_id, sequence, command } = entry.command {
+                let result = self.kv.apply(client_id, sequence, &command);
+                if self.state == NodeState::Leader {
+                    self.respond(client_id, sequence, result);
+                }
+            }
+        }
+        self.serve_reads();
+
+        if self.last_applied - self.snapshot.last_included_index >= SNAPSHOT_THRESHOLD {
+            self.take_snapshot();
+        }
+    }
+
+    // Replace the applied prefix of the log with a snapshot of the state machine
+    fn take_snapshot(&mut self) {
+        let last_included_index = self.last_applied;
+        let last_included_term = self.entry(last_included_index).term;
+        let members = self.config_at(last_included_index);
+        self.log.drain(..last_included_index - self.snapshot.last_included_index);
+        self.snapshot = Snapshot {
+            last_included_index,
+            last_included_term,
+            members,
+            data: self.kv.encode(),
+        };
+
+        self.log(format_args!(
+            "Took snapshot through index {}, {} entries left in log",
+            last_included_index,
+            self.log.len()
+        ));
+    }
+
+    fn handle_client_request(&mut self, client_id: u64, sequence: u64, command: String) {
+        // The response carries our leader hint
+        if self.state != NodeState::Leader {
+            self.respond(client_id, sequence, Err("not the leader".to_string()));
+            return;
+        }
+
+        match KvCommand::parse(&command) {
+            Err(e) => self.respond(client_id, sequence, Err(e)),
+            // Reads don't go through the log; see `serve_reads`
+            Ok(KvCommand::Get(key)) => {
+                self.pending_reads.push(PendingRead {
+                    client_id,
+                    sequence,
+                    key,
+                    read_index: self.commit_index.max(self.term_start_index),
+                    round: self.round + 1,
+                });
+                self.serve_reads();
+            }
+            // A retry of a write that was already applied gets the same answer
+            Ok(_) if self.kv.is_applied(client_id, sequence) => {
+                let result = self.kv.apply(client_id, sequence, &command);
+                self.respond(client_id, sequence, result);
+            }
+            Ok(_) => {
+                let entry = LogEntry {
+                    term: self.current_term,
+                    index: self.last_log_index() + 1,
+                    command: Command::Client { client_id, sequence, command },
+                };
+
+                self.log(format_args!(
+                    "Received client command: {} (index: {})",
+                    entry.command, entry.index
+                ));
+
+                self.log.push(entry);
+                self.update_commit_index();
+            }
         }
     }
 
-    fn handle_client_request(&mut self, command: String) -> Result<(), String> {
+    // ReadIndex: answer a read once a quorum has acknowledged a heartbeat
+    // sent after it arrived, proving no newer leader could have committed
+    // writes we haven't seen, and once the state machine has caught up
+    fn serve_reads(&mut self) {
+        if self.state != NodeState::Leader {
+            return;
+        }
+
+        for read in std::mem::take(&mut self.pending_reads) {
+            let acks = usize::from(self.is_voter())
+                + self
+                    .peers
+                    .iter()
+                    .filter(|peer| self.acked_round.get(peer).is_some_and(|&r| r >= read.round))
+                    .count();
+
+            if acks >= self.quorum() && self.last_applied >= read.read_index {
+                let result = self.kv.get(&read.key);
+                self.respond(read.client_id, read.sequence, result);
+            } else {
+                self.pending_reads.push(read);
+            }
+        }
+    }
+
+    fn respond(&mut self, client_id: u64, sequence: u64, result: Result<String, String>) {
+        let leader = self.leader_id;
+        self.outbox.push((
+            CLIENT_ID,
+            RaftMessage::ClientResponse { client_id, sequence, result, leader },
+        ));
+    }
+
+    // Single-server membership change: the new configuration goes into the
+    // log like any command, and only one may be uncommitted at a time
+    fn handle_config_change(&mut self, change: MembershipChange) -> Result<(), String> {
         if self.state != NodeState::Leader {
             return Err("Not the leader".to_string());
         }
+        if self.last_config_index().is_some_and(|i| i > self.commit_index) {
+            return Err("A configuration change is already in progress".to_string());
+        }
+
+        let mut members = self.members.clone();
+        match change {
+            MembershipChange::AddNode(id) if !members.contains(&id) => members.push(id),
+            MembershipChange::RemoveNode(id) if members.contains(&id) && members.len() > 1 => {
+                members.retain(|&m| m != id)
+            }
+            _ => return Err(format!("{:?} does not change {:?}", change, members)),
+        }
+        members.sort();
 
         let entry = LogEntry {
             term: self.current_term,
-            index: self.log.len() + 1,
-            command,
+            index: self.last_log_index() + 1,
+            command: Command::Config(members),
         };
-        
-        println!(
-            "[Node {}] Received client command: {} (index: {})",
-            self.id, entry.command, entry.index
-        );
-        
+        self.log(format_args!(
+            "Received {:?} (index: {})",
+            change, entry.index
+        ));
         self.log.push(entry);
+        self.refresh_config();
         Ok(())
     }
 
     fn create_request_vote(&self) -> RaftMessage {
-        let (last_log_index, last_log_term) = if self.log.is_empty() {
-            (0, 0)
-        } else {
-            let last = &self.log[self.log.len() - 1];
-            (last.index, last.term)
-        };
-
         RaftMessage::RequestVote {
             term: self.current_term,
             candidate_id: self.id,
-            last_log_index,
-            last_log_term,
+            last_log_index: self.last_log_index(),
+            last_log_term: self.last_log_term(),
         }
     }
 
+    // AppendEntries for the peer's next index, or InstallSnapshot if the
+    // entries it needs have already been compacted away
     fn create_append_entries(&self, peer_id: u64) -> RaftMessage {
-        let next_idx = self.next_index.get(&peer_id).copied().unwrap_or(1);
-        
-        let (prev_log_index, prev_log_term) = if next_idx > 1 && !self.log.is_empty() {
-            let prev = &self.log[next_idx - 2];
-            (prev.index, prev.term)
-        } else {
-            (0, 0)
-        };
+        let next_idx = self.next_index.get(&peer_id).copied().unwrap_or(1).max(1);
+        let base = self.snapshot.last_included_index;
 
-        let entries = if next_idx <= self.log.len() {
-            self.log[next_idx - 1..].to_vec()
-        } else {
-            Vec::new()
-        };
+        if next_idx <= base {
+            return RaftMessage::InstallSnapshot {
+                term: self.current_term,
+                leader_id: self.id,
+                last_included_index: base,
+                last_included_term: self.snapshot.last_included_term,
+                members: self.snapshot.members.clone(),
+                data: self.snapshot.data.clone(),
+            };
+        }
+
+        let prev_log_index = next_idx - 1;
+        let prev_log_term = self.term_at(prev_log_index).unwrap_or(0);
+        let entries = self.log[(next_idx - base - 1).min(self.log.len())..].to_vec();
 
         RaftMessage::AppendEntries {
             term: self.current_term,
@@ -386,134 +1033,1361 @@ impl RaftNode {
             prev_log_term,
             entries,
             leader_commit: self.commit_index,
+            round: self.round,
         }
     }
 }
 
-// ========== CLUSTER SIMULATION ==========
-type NodeHandle = Arc<Mutex<RaftNode>>;
-
-struct Cluster {
-    nodes: HashMap<u64, NodeHandle>,
-    channels: HashMap<u64, mpsc::UnboundedSender<(u64, RaftMessage)>>,
+// ========== STATE MACHINE ==========
+#[derive(Debug, Clone, PartialEq)]
+enum KvCommand {
+    Set(String, String),
+    Get(String),
+    Del(String),
 }
 
-impl Cluster {
-    fn new(node_count: usize) -> Self {
-        let mut nodes = HashMap::new();
-        let mut channels = HashMap::new();
-
-        let peer_ids: Vec<u64> = (0..node_count as u64).collect();
+impl KvCommand {
+    // `SET key value`, `GET key`, or `DEL key`; the value may contain spaces
+    fn parse(command: &str) -> Result<Self, String> {
+        let mut parts = command.trim().splitn(3, ' ');
+        let op = parts.next().unwrap_or("").to_ascii_uppercase();
+        let key = parts.next().filter(|k| !k.is_empty());
+        let rest = parts.next();
 
-        for id in 0..node_count as u64 {
-            let peers: Vec<u64> = peer_ids.iter().filter(|&&p| p != id).copied().collect();
-            let node = Arc::new(Mutex::new(RaftNode::new(id, peers)));
-            nodes.insert(id, node);
-            
-            let (tx, _rx) = mpsc::unbounded_channel();
-            channels.insert(id, tx);
+        match (op.as_str(), key, rest) {
+            ("SET", Some(key), Some(value)) => Ok(KvCommand::Set(key.to_string(), value.to_string())),
+            ("GET", Some(key), None) => Ok(KvCommand::Get(key.to_string())),
+            ("DEL", Some(key), None) => Ok(KvCommand::Del(key.to_string())),
+            _ => Err(format!("invalid command: {}", command)),
         }
-
-        Cluster { nodes, channels }
     }
+}
 
-    async fn run_node(&self, node_id: u64) {
-        let node_handle = self.nodes.get(&node_id).unwrap().clone();
-        let (tx, mut rx) = mpsc::unbounded_channel::<(u64, RaftMessage)>();
-        
-        let channels = self.channels.clone();
-        
-        tokio::spawn(async move {
-            let mut heartbeat_timer = interval(Duration::from_millis(50));
-            
+// The replicated state machine: the data, plus the last result returned to
+// each client so a retried write is answered instead of applied again
+#[derive(Debug, Clone, Default, PartialEq)]
+struct KvStore {
+    data: BTreeMap<String, String>,
+    sessions: BTreeMap<u64, (u64, Result<String, String>)>,
+}
+
+impl KvStore {
+    fn apply(&mut self, client_id: u64, sequence: u64, command: &str) -> Result<String, String> {
+        if let Some((last, result)) = self.sessions.get(&client_id) {
+            if sequence == *last {
+                return result.clone();
+            }
+            if sequence < *last {
+                return Err(format!("request {} is older than {}", sequence, last));
+            }
+        }
+
+        let result = match KvCommand::parse(command) {
+            Ok(KvCommand::Set(key, value)) => {
+                self.data.insert(key, value);
+                Ok("OK".to_string())
+            }
+            Ok(KvCommand::Get(key)) => self.get(&key),
+            Ok(KvCommand::Del(key)) => Ok(if self.data.remove(&key).is_some() { "1" } else { "0" }.to_string()),
+            Err(e) => Err(e),
+        };
+        self.sessions.insert(client_id, (sequence, result.clone()));
+        result
+    }
+
+    fn get(&self, key: &str) -> Result<String, String> {
+        self.data.get(key).cloned().ok_or_else(|| format!("no such key: {}", key))
+    }
+
+    fn is_applied(&self, client_id: u64, sequence: u64) -> bool {
+        self.sessions.get(&client_id).is_some_and(|&(last, _)| sequence <= last)
+    }
+
+    // Snapshot encoding: tab-separated lines, `K key value` for data and
+    // `S client sequence ok|err text` for sessions. Text fields are escaped
+    // so tabs, newlines and backslashes in them can't split a record.
+    fn encode(&self) -> String {
+        let mut out = String::new();
+        for (key, value) in &self.data {
+            out.push_str(&format!("K\t{}\t{}\n", escape_field(key), escape_field(value)));
+        }
+        for (client_id, (sequence, result)) in &self.sessions {
+            let (tag, text) = match result {
+                Ok(text) => ("ok", text),
+                Err(text) => ("err", text),
+            };
+            out.push_str(&format!("S\t{}\t{}\t{}\t{}\n", client_id, sequence, tag, escape_field(text)));
+        }
+        out
+    }
+
+    fn decode(data: &str) -> Self {
+        let mut store = KvStore::default();
+        for line in data.lines() {
+            let fields: Vec<&str> = line.split('\t').collect();
+            match fields.as_slice() {
+                ["K", key, value] => {
+                    store.data.insert(unescape_field(key), unescape_field(value));
+                }
+                ["S", client_id, sequence, tag, text] => {
+                    let (Ok(client_id), Ok(sequence)) = (client_id.parse(), sequence.parse()) else {
+                        continue;
+                    };
+                    let text = unescape_field(text);
+                    let result = if *tag == "ok" { Ok(text) } else { Err(text) };
+                    store.sessions.insert(client_id, (sequence, result));
+                }
+                _ => {}
+            }
+        }
+        store
+    }
+}
+
+fn escape_field(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn unescape_field(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+// ========== TRANSPORT ==========
+// A message and the ID of its sender
+type Envelope = (u64, RaftMessage);
+
+// How a node's messages reach other nodes. Delivery is best effort: Raft
+// already copes with lost, duplicated, and reordered messages.
+trait Transport: Send + Sync {
+    fn send(&self, from: u64, to: u64, msg: RaftMessage);
+}
+
+// ========== SIMULATED NETWORK ==========
+// Address of the clients outside the cluster: requests come from it and
+// responses go to it. Faults never apply to it.
+const CLIENT_ID: u64 = u64::MAX;
+
+// Misbehavior of one direction of a link. Rates are probabilities per
+// message; a random extra delay of up to `jitter` reorders messages.
+#[derive(Debug, Clone, Copy, Default)]
+struct LinkFault {
+    drop_rate: f64,
+    duplicate_rate: f64,
+    delay: Duration,
+    jitter: Duration,
+}
+
+impl LinkFault {
+    fn disconnected() -> Self {
+        LinkFault {
+            drop_rate: 1.0,
+            ..LinkFault::default()
+        }
+    }
+}
+
+// Delivers messages between nodes, applying whatever faults are configured
+// for each (from, to) pair
+struct Network {
+    // Every node's inbox, keyed by node ID
+    channels: HashMap<u64, mpsc::UnboundedSender<Envelope>>,
+    faults: Mutex<HashMap<(u64, u64), LinkFault>>,
+    rng: Mutex<u64>,
+}
+
+impl Network {
+    fn new(channels: HashMap<u64, mpsc::UnboundedSender<Envelope>>) -> Self {
+        Network {
+            channels,
+            faults: Mutex::new(HashMap::new()),
+            rng: Mutex::new(0x9e37_79b9_7f4a_7c15),
+        }
+    }
+
+    fn random(&self) -> f64 {
+        random_unit(&mut self.rng.lock().unwrap())
+    }
+
+    fn set_fault(&self, from: u64, to: u64, fault: LinkFault) {
+        self.faults.lock().unwrap().insert((from, to), fault);
+    }
+
+    fn node_ids(&self) -> impl Iterator<Item = u64> + '_ {
+        self.channels.keys().copied().filter(|&id| id != CLIENT_ID)
+    }
+
+    // Apply `fault` to every link between distinct nodes
+    fn set_fault_everywhere(&self, fault: LinkFault) {
+        for from in self.node_ids() {
+            for to in self.node_ids() {
+                if from != to {
+                    self.set_fault(from, to, fault);
+                }
+            }
+        }
+    }
+
+    // Cut every link between the groups; links within a group are untouched
+    fn partition(&self, groups: &[&[u64]]) {
+        for (i, a) in groups.iter().enumerate() {
+            for b in &groups[i + 1..] {
+                for &x in a.iter() {
+                    for &y in b.iter() {
+                        self.set_fault(x, y, LinkFault::disconnected());
+                        self.set_fault(y, x, LinkFault::disconnected());
+                    }
+                }
+            }
+        }
+    }
+
+    fn isolate(&self, node: u64) {
+        let others: Vec<u64> = self.node_ids().filter(|&id| id != node).collect();
+        self.partition(&[&[node], &others]);
+    }
+
+    fn heal(&self) {
+        self.faults.lock().unwrap().clear();
+    }
+}
+
+impl Transport for Network {
+    fn send(&self, from: u64, to: u64, msg: RaftMessage) {
+        let Some(sender) = self.channels.get(&to) else {
+            return;
+        };
+        let fault = self.faults.lock().unwrap().get(&(from, to)).copied();
+        let Some(fault) = fault else {
+            let _ = sender.send((from, msg));
+            return;
+        };
+
+        if self.random() < fault.drop_rate {
+            return;
+        }
+        let copies = if self.random() < fault.duplicate_rate { 2 } else { 1 };
+        for _ in 0..copies {
+            let delay = fault.delay + fault.jitter.mul_f64(self.random());
+            if delay.is_zero() {
+                let _ = sender.send((from, msg.clone()));
+            } else {
+                let sender = sender.clone();
+                let msg = msg.clone();
+                tokio::spawn(async move {
+                    sleep(delay).await;
+                    let _ = sender.send((from, msg));
+                });
+            }
+        }
+    }
+}
+
+// ========== NODE EVENT LOOP ==========
+// A copy of a node's state, for checks and reporting
+struct NodeStatus {
+    id: u64,
+    state: NodeState,
+    current_term: u64,
+    members: Vec<u64>,
+    snapshot_index: usize,
+    log: Vec<LogEntry>,
+    commit_index: usize,
+    last_applied: usize,
+    kv: KvStore,
+}
+
+impl NodeStatus {
+    fn entry(&self, index: usize) -> &LogEntry {
+        &self.log[index - self.snapshot_index - 1]
+    }
+}
+
+// Raft's safety properties over a set of node states:
+// - election safety: at most one leader per term
+// - log matching / state machine safety: committed entries that two
+//   nodes both still hold are identical
+// - applied state: nodes that applied the same prefix hold the same data
+fn check_statuses(nodes: &[NodeStatus]) -> Result<(), String> {
+
+    let mut leaders: HashMap<u64, u64> = HashMap::new();
+    for node in nodes {
+        if node.state == NodeState::Leader {
+            if let Some(other) = leaders.insert(node.current_term, node.id) {
+                return Err(format!("nodes {} and {} both lead term {}", other, node.id, node.current_term));
+            }
+        }
+    }
+
+    for (i, a) in nodes.iter().enumerate() {
+        for b in &nodes[i + 1..] {
+            let (a_id, b_id) = (a.id, b.id);
+            let first = a.snapshot_index.max(b.snapshot_index) + 1;
+            for index in first..=a.commit_index.min(b.commit_index) {
+                let (x, y) = (a.entry(index), b.entry(index));
+                if x.term != y.term || x.command.to_string() != y.command.to_string() {
+                    return Err(format!(
+                        "committed entry {} differs: node {} has {} (term {}), node {} has {} (term {})",
+                        index, a_id, x.command, x.term, b_id, y.command, y.term
+                    ));
+                }
+            }
+            if a.last_applied == b.last_applied && a.kv != b.kv {
+                return Err(format!("nodes {} and {} applied {} entries but differ", a_id, b_id, a.last_applied));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+impl RaftNode {
+    fn status(&self) -> NodeStatus {
+        NodeStatus {
+            id: self.id,
+            state: self.state.clone(),
+            current_term: self.current_term,
+            members: self.members.clone(),
+            snapshot_index: self.snapshot.last_included_index,
+            log: self.log.clone(),
+            commit_index: self.commit_index,
+            last_applied: self.last_applied,
+            kv: self.kv.clone(),
+        }
+    }
+}
+
+impl RaftNode {
+    // Timer work: a leader's heartbeats, or an election once a follower's
+    // timeout has run out. Returns the messages to send.
+    fn tick(&mut self) -> Vec<(u64, RaftMessage)> {
+        match self.state {
+            NodeState::Leader => {
+                self.round += 1;
+                self.peers.iter().map(|&peer| (peer, self.create_append_entries(peer))).collect()
+            }
+            NodeState::Follower | NodeState::Candidate => {
+                if !self.is_voter() || !self.is_election_timeout() {
+                    return Vec::new();
+                }
+                self.emit(RaftEvent::HeartbeatMissed { node: self.id, term: self.current_term });
+                self.start_election();
+                let msg = self.create_request_vote();
+                self.peers.iter().map(|&peer| (peer, msg.clone())).collect()
+            }
+        }
+    }
+
+    // Handle one message, returning the reply to its sender if there is one.
+    // Anything for clients goes to `outbox`.
+    fn handle_message(&mut self, from_id: u64, msg: RaftMessage) -> Option<RaftMessage> {
+        match msg {
+            RaftMessage::RequestVote { term, candidate_id, last_log_index, last_log_term } => {
+                Some(self.handle_request_vote(term, candidate_id, last_log_index, last_log_term))
+            }
+            RaftMessage::RequestVoteResponse { term, vote_granted } => {
+                self.handle_vote_response(term, vote_granted);
+                None
+            }
+            RaftMessage::AppendEntries { term, leader_id, prev_log_index, prev_log_term, entries, leader_commit, round } => {
+                Some(self.handle_append_entries(term, leader_id, prev_log_index, prev_log_term, entries, leader_commit, round))
+            }
+            RaftMessage::AppendEntriesResponse { term, success, match_index, round } => {
+                self.handle_append_entries_response(from_id, term, success, match_index, round);
+                None
+            }
+            RaftMessage::InstallSnapshot { term, leader_id, last_included_index, last_included_term, members, data } => {
+                Some(self.handle_install_snapshot(term, leader_id, last_included_index, last_included_term, members, data))
+            }
+            RaftMessage::InstallSnapshotResponse { term, match_index } => {
+                self.handle_install_snapshot_response(from_id, term, match_index);
+                None
+            }
+            RaftMessage::ClientRequest { client_id, sequence, command } => {
+                self.handle_client_request(client_id, sequence, command);
+                None
+            }
+            RaftMessage::ClientResponse { .. } => None,
+            RaftMessage::ConfigChange { change } => {
+                if let Err(e) = self.handle_config_change(change) {
+                    self.log(format_args!("Rejected {:?}: {}", change, e));
+                }
+                None
+            }
+        }
+    }
+}
+
+// Requests to a running node from outside the protocol
+enum Control {
+    Inspect(oneshot::Sender<NodeStatus>),
+}
+
+// Drive a node: heartbeats and elections on a timer, and a response to
+// every message from `rx`, all sent through `transport`. The task owns the
+// node outright; everyone else reaches it through the returned channel, so
+// there is no lock to hold across an await or to block the runtime on.
+fn spawn_node(
+    mut node: RaftNode,
+    mut rx: mpsc::UnboundedReceiver<Envelope>,
+    transport: Arc<dyn Transport>,
+) -> (mpsc::UnboundedSender<Control>, JoinHandle<()>) {
+    let (control_tx, mut control_rx) = mpsc::unbounded_channel();
+    let node_id = node.id;
+
+    let task = tokio::spawn(async move {
+        let mut heartbeat_timer = interval(node.heartbeat_interval);
+        
+        loop {
+            tokio::select! {
+                _ = heartbeat_timer.tick() => {
+                    for (to, msg) in node.tick() {
+                        transport.send(node_id, to, msg);
+                    }
+                }
+                
+                Some((from_id, msg)) = rx.recv() => {
+                    if let Some(resp) = node.handle_message(from_id, msg) {
+                        transport.send(node_id, from_id, resp);
+                    }
+                    for (to, msg) in node.outbox.drain(..) {
+                        transport.send(node_id, to, msg);
+                    }
+                }
+
+                Some(control) = control_rx.recv() => match control {
+                    Control::Inspect(reply) => {
+                        let _ = reply.send(node.status());
+                    }
+                },
+            }
+        }
+    });
+    (control_tx, task)
+}
+
+// ========== CLUSTER SIMULATION ==========
+
+struct Cluster {
+    // Every event from every node
+    events: Arc<EventCollector>,
+    network: Arc<Network>,
+    // Nodes not started yet, with the receiving ends of their inboxes
+    idle: Mutex<HashMap<u64, (RaftNode, mpsc::UnboundedReceiver<Envelope>)>>,
+    controls: Mutex<HashMap<u64, mpsc::UnboundedSender<Control>>>,
+    // Responses addressed to CLIENT_ID
+    responses: tokio::sync::Mutex<mpsc::UnboundedReceiver<Envelope>>,
+    // Client ID and sequence numbers for fire-and-forget requests
+    next_sequence: AtomicU64,
+    tasks: Mutex<Vec<JoinHandle<()>>>,
+}
+
+// Stop the node tasks along with the cluster
+impl Drop for Cluster {
+    fn drop(&mut self) {
+        for task in self.tasks.lock().unwrap().drain(..) {
+            task.abort();
+        }
+    }
+}
+
+impl Cluster {
+    // `node_count` nodes, of which the first `initial_members` form the
+    // starting configuration; the rest wait to be added
+    fn new(node_count: usize, initial_members: usize) -> Self {
+        let mut channels = HashMap::new();
+        let mut idle = HashMap::new();
+        let events = Arc::new(EventCollector::default());
+
+        let member_ids: Vec<u64> = (0..initial_members as u64).collect();
+
+        for id in 0..node_count as u64 {
+            let members = if member_ids.contains(&id) { member_ids.clone() } else { Vec::new() };
+            let (tx, rx) = mpsc::unbounded_channel();
+            channels.insert(id, tx);
+            let node = RaftNode::new(id, members, events.clone());
+            idle.insert(id, (node, rx));
+        }
+        let (tx, responses) = mpsc::unbounded_channel();
+        channels.insert(CLIENT_ID, tx);
+
+        Cluster {
+            events,
+            network: Arc::new(Network::new(channels)),
+            idle: Mutex::new(idle),
+            controls: Mutex::new(HashMap::new()),
+            responses: tokio::sync::Mutex::new(responses),
+            next_sequence: AtomicU64::new(1),
+            tasks: Mutex::new(Vec::new()),
+        }
+    }
+
+    async fn run_node(&self, node_id: u64) {
+        let (mut node, rx) = self
+            .idle
+            .lock()
+            .unwrap()
+            .remove(&node_id)
+            .expect("node is already running");
+        // Start the election clock now, not when the cluster was created
+        node.reset_election_timer();
+        
+        let (control, task) = spawn_node(node, rx, self.network.clone());
+        self.controls.lock().unwrap().insert(node_id, control);
+        self.tasks.lock().unwrap().push(task);
+    }
+
+    // Status of every running node, in ID order. Each node reports in turn,
+    // so this is not one instant across the cluster.
+    async fn statuses(&self) -> Vec<NodeStatus> {
+        let controls: Vec<mpsc::UnboundedSender<Control>> =
+            self.controls.lock().unwrap().values().cloned().collect();
+
+        let mut statuses = Vec::new();
+        for control in controls {
+            let (reply, status) = oneshot::channel();
+            if control.send(Control::Inspect(reply)).is_ok() {
+                if let Ok(status) = status.await {
+                    statuses.push(status);
+                }
+            }
+        }
+        statuses.sort_by_key(|status| status.id);
+        statuses
+    }
+
+    // The leader of the newest term; a partitioned old leader may still
+    // believe it leads an older one
+    async fn get_leader(&self) -> Option<u64> {
+        self.statuses()
+            .await
+            .iter()
+            .filter(|node| node.state == NodeState::Leader)
+            .map(|node| (node.current_term, node.id))
+            .max()
+            .map(|(_, id)| id)
+    }
+
+    async fn wait_for_leader(&self, timeout: Duration) -> Option<u64> {
+        let start = Instant::now();
+        while start.elapsed() < timeout {
+            if let Some(leader) = self.get_leader().await {
+                return Some(leader);
+            }
+            sleep(Duration::from_millis(20)).await;
+        }
+        None
+    }
+
+    // See `check_statuses`. Each property holds for any mix of past states,
+    // so it doesn't matter that the nodes report at slightly different times.
+    async fn check_safety(&self) -> Result<(), String> {
+        check_statuses(&self.statuses().await)
+    }
+
+    // Wait for the cluster to settle, then require every node to have
+    // applied the same entries and the safety checks to pass
+    async fn assert_converged(&self, expected: &[(&str, &str)]) {
+        sleep(Duration::from_millis(800)).await;
+        if let Err(e) = self.check_safety().await {
+            panic!("safety violated: {}", e);
+        }
+        // Election safety over the whole run, not just the current state
+        if let Err(e) = self.events.leaders_by_term() {
+            panic!("safety violated: {}", e);
+        }
+
+        let nodes = self.statuses().await;
+        let applied: Vec<usize> = nodes.iter().map(|n| n.last_applied).collect();
+        assert!(applied.windows(2).all(|w| w[0] == w[1]), "nodes did not converge: {:?}", applied);
+        let leader = self.get_leader().await.expect("no leader after healing");
+        let node = nodes.iter().find(|n| n.id == leader).unwrap();
+        for (name, value) in expected {
+            assert_eq!(node.kv.data.get(*name).map(String::as_str), Some(*value), "{} after healing", name);
+        }
+        println!("✓ Safety invariants hold; all nodes applied {} entries", applied[0]);
+    }
+
+    async fn print_summary(&self) {
+        for node in self.statuses().await {
+            println!(
+                "  Node {} ({:?}, members {:?}): snapshot through {}, {} log entries, state {:?}",
+                node.id,
+                node.state,
+                node.members,
+                node.snapshot_index,
+                node.log.len(),
+                node.kv.data
+            );
+        }
+    }
+
+    fn send_config_change(&self, leader_id: u64, change: MembershipChange) {
+        self.network.send(CLIENT_ID, leader_id, RaftMessage::ConfigChange { change });
+    }
+
+    // Fire and forget, as client 0
+    fn send_client_request(&self, leader_id: u64, command: String) {
+        let sequence = self.next_sequence.fetch_add(1, Ordering::Relaxed);
+        self.network.send(
+            CLIENT_ID,
+            leader_id,
+            RaftMessage::ClientRequest { client_id: 0, sequence, command },
+        );
+    }
+
+    // Send a request to one node and wait for its result. `None` if it doesn't
+    // answer in time; retrying with the same `sequence` is safe.
+    async fn request(
+        &self,
+        node_id: u64,
+        client_id: u64,
+        sequence: u64,
+        command: &str,
+    ) -> Option<Result<String, String>> {
+        self.call(node_id, client_id, sequence, command).await.map(|reply| reply.result)
+    }
+}
+
+impl ClientTransport for Cluster {
+    async fn call(&self, node_id: u64, client_id: u64, sequence: u64, command: &str) -> Option<ClientReply> {
+        let mut responses = self.responses.lock().await;
+        self.network.send(
+            CLIENT_ID,
+            node_id,
+            RaftMessage::ClientRequest { client_id, sequence, command: command.to_string() },
+        );
+
+        let deadline = tokio::time::Instant::now() + Duration::from_millis(500);
+        loop {
+            // Responses to earlier, abandoned requests are skipped
+            match tokio::time::timeout_at(deadline, responses.recv()).await {
+                Ok(Some((_, RaftMessage::ClientResponse { client_id: c, sequence: s, result, leader })))
+                    if c == client_id && s == sequence =>
+                {
+                    return Some(ClientReply { result, leader })
+                }
+                Ok(Some(_)) => continue,
+                Ok(None) | Err(_) => return None,
+            }
+        }
+    }
+}
+
+// ========== CLIENT ==========
+// A node's answer to a request, and who it thinks the leader is
+struct ClientReply {
+    result: Result<String, String>,
+    leader: Option<u64>,
+}
+
+// Delivers one request to one node and waits for its answer; `None` if none
+// arrives in time
+trait ClientTransport {
+    async fn call(&self, node_id: u64, client_id: u64, sequence: u64, command: &str) -> Option<ClientReply>;
+}
+
+const CLIENT_ATTEMPTS: usize = 20;
+
+// Sends each command to the leader, following the hints followers give and
+// trying the nodes in turn when nobody knows. A command keeps its sequence
+// number across retries, so a write whose answer was lost applies only once.
+struct RaftClient<'a, T> {
+    transport: &'a T,
+    client_id: u64,
+    next_sequence: u64,
+    nodes: Vec<u64>,
+    leader: Option<u64>,
+    next_node: usize,
+}
+
+impl<'a, T: ClientTransport> RaftClient<'a, T> {
+    // `nodes` is the order to try when the leader is unknown
+    fn new(transport: &'a T, client_id: u64, nodes: Vec<u64>) -> Self {
+        RaftClient {
+            transport,
+            client_id,
+            next_sequence: 1,
+            nodes,
+            leader: None,
+            next_node: 0,
+        }
+    }
+
+    async fn execute(&mut self, command: &str) -> Result<String, String> {
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+
+        for _ in 0..CLIENT_ATTEMPTS {
+            let node = self.leader.unwrap_or_else(|| {
+                self.next_node += 1;
+                self.nodes[(self.next_node - 1) % self.nodes.len()]
+            });
+
+            match self.transport.call(node, self.client_id, sequence, command).await {
+                Some(reply) if reply.leader == Some(node) => {
+                    self.leader = Some(node);
+                    return reply.result;
+                }
+                Some(reply) => {
+                    match reply.leader {
+                        Some(leader) => println!("[Client {}] Node {} redirected to leader {}", self.client_id, node, leader),
+                        // Probably mid-election; give it a moment
+                        None => sleep(Duration::from_millis(100)).await,
+                    }
+                    self.leader = reply.leader;
+                }
+                None => self.leader = None,
+            }
+        }
+        Err(format!("no leader answered after {} attempts", CLIENT_ATTEMPTS))
+    }
+}
+
+// ========== FAULT SCENARIOS ==========
+async fn start_cluster(size: usize) -> (Cluster, u64) {
+    let cluster = Cluster::new(size, size);
+    for id in 0..size as u64 {
+        cluster.run_node(id).await;
+    }
+    let leader = cluster
+        .wait_for_leader(Duration::from_secs(3))
+        .await
+        .expect("no leader elected");
+    (cluster, leader)
+}
+
+// The leader is cut off from everyone. The rest elect a new leader and keep
+// committing; the old leader's write never commits and is overwritten once
+// the partition heals.
+async fn scenario_leader_isolation() {
+    println!("\n--- Scenario: leader isolation ---");
+    let (cluster, old_leader) = start_cluster(5).await;
+    cluster.send_client_request(old_leader, "SET a 1".to_string());
+    sleep(Duration::from_millis(200)).await;
+
+    println!("Isolating leader {}", old_leader);
+    cluster.network.isolate(old_leader);
+    cluster.send_client_request(old_leader, "SET a lost".to_string());
+    sleep(Duration::from_millis(600)).await;
+
+    let new_leader = cluster.get_leader().await.filter(|&l| l != old_leader).expect("majority elected no new leader");
+    println!("Majority elected leader {}", new_leader);
+    let missed = cluster.events.count(|e| matches!(e, RaftEvent::HeartbeatMissed { node, .. } if *node != old_leader));
+    assert!(missed > 0, "a new leader was elected without any follower timing out");
+    // The old leader still thinks it leads, but can't get a quorum to
+    // confirm it, so it must not answer reads
+    assert_eq!(cluster.request(old_leader, 1, 1, "GET a").await, None, "isolated leader served a read");
+    assert_eq!(cluster.request(new_leader, 1, 2, "GET a").await, Some(Ok("1".to_string())));
+    println!("Only the new leader answers reads");
+    cluster.send_client_request(new_leader, "SET b 2".to_string());
+    sleep(Duration::from_millis(200)).await;
+
+    println!("Healing the partition");
+    cluster.network.heal();
+    cluster.assert_converged(&[("a", "1"), ("b", "2")]).await;
+}
+
+// A two-node minority keeps the old leader, which accepts writes it can never
+// commit, while the majority elects its own leader. After healing only the
+// majority's writes survive.
+async fn scenario_split_brain() {
+    println!("\n--- Scenario: split brain ---");
+    let (cluster, old_leader) = start_cluster(5).await;
+    let ids: Vec<u64> = (0..5).collect();
+    let minority = [old_leader, ids.iter().copied().find(|&id| id != old_leader).unwrap()];
+    let majority: Vec<u64> = ids.iter().copied().filter(|id| !minority.contains(id)).collect();
+
+    println!("Partitioning {:?} | {:?}", minority, majority);
+    cluster.network.partition(&[&minority, &majority]);
+    sleep(Duration::from_millis(600)).await;
+
+    let new_leader = cluster.get_leader().await.filter(|&l| l != old_leader).expect("majority elected no new leader");
+    println!("Two leaders: {} (old, minority) and {} (new, majority)", old_leader, new_leader);
+    cluster.send_client_request(old_leader, "SET side minority".to_string());
+    cluster.send_client_request(new_leader, "SET side majority".to_string());
+    sleep(Duration::from_millis(300)).await;
+    if let Err(e) = cluster.check_safety().await {
+        panic!("safety violated during partition: {}", e);
+    }
+
+    println!("Healing the partition");
+    cluster.network.heal();
+    cluster.assert_converged(&[("side", "majority")]).await;
+}
+
+// Every link drops, duplicates, and reorders messages while writes stream in
+async fn scenario_lossy_network() {
+    println!("\n--- Scenario: lossy network ---");
+    let (cluster, leader) = start_cluster(5).await;
+    cluster.network.set_fault_everywhere(LinkFault {
+        drop_rate: 0.2,
+        duplicate_rate: 0.2,
+        delay: Duration::from_millis(5),
+        jitter: Duration::from_millis(40),
+    });
+
+    for i in 0..10 {
+        cluster.send_client_request(leader, format!("SET n {}", i));
+        sleep(Duration::from_millis(30)).await;
+    }
+    sleep(Duration::from_millis(500)).await;
+
+    println!("Restoring the network");
+    cluster.network.heal();
+    // The leader can change under loss; writes sent to a deposed leader are
+    // dropped, so only check that every node agrees
+    cluster.assert_converged(&[]).await;
+}
+
+async fn run_fault_scenarios() {
+    scenario_leader_isolation().await;
+    scenario_split_brain().await;
+    scenario_lossy_network().await;
+    println!("\n✓ All fault scenarios passed");
+}
+
+// ========== DETERMINISTIC SIMULATION ==========
+// A whole cluster on one thread in virtual time. Every random choice, from
+// election timeouts to message loss, comes from the seed, so a seed always
+// replays the same history.
+const SIM_NODES: u64 = 5;
+const SIM_DURATION: Duration = Duration::from_secs(5);
+const SIM_DROP_RATE: f64 = 0.1;
+const SIM_MAX_LATENCY: Duration = Duration::from_millis(20);
+
+enum SimEvent {
+    Tick(u64),
+    Deliver(u64, u64, RaftMessage),
+    // A client write to whoever leads at the time
+    Write(u64),
+    Isolate(u64),
+    Heal,
+}
+
+struct Simulation {
+    clock: Arc<VirtualClock>,
+    events: Arc<EventCollector>,
+    nodes: Vec<RaftNode>,
+    // Pending events by time, then by order of scheduling
+    queue: BTreeMap<(Duration, u64), SimEvent>,
+    scheduled: u64,
+    rng: u64,
+    isolated: Option<u64>,
+}
+
+impl Simulation {
+    fn new(seed: u64, verbose: bool) -> Self {
+        let clock = Arc::new(VirtualClock::default());
+        let events = Arc::new(EventCollector::default());
+        let members: Vec<u64> = (0..SIM_NODES).collect();
+        let mut rng = seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1;
+
+        let nodes = members
+            .iter()
+            .map(|&id| {
+                let mut node = RaftNode::new(id, members.clone(), events.clone())
+                    .with_clock(clock.clone(), xorshift(&mut rng));
+                node.verbose = verbose;
+                node
+            })
+            .collect();
+
+        let mut sim = Simulation {
+            clock,
+            events,
+            nodes,
+            queue: BTreeMap::new(),
+            scheduled: 0,
+            rng,
+            isolated: None,
+        };
+        for id in members {
+            let offset = Duration::from_millis(50).mul_f64(random_unit(&mut sim.rng));
+            sim.schedule(offset, SimEvent::Tick(id));
+        }
+        for i in 0..SIM_DURATION.as_millis() as u64 / 250 {
+            sim.schedule(Duration::from_millis(250 * i), SimEvent::Write(i));
+        }
+        for i in 1..SIM_DURATION.as_secs() {
+            let node = xorshift(&mut sim.rng) % SIM_NODES;
+            sim.schedule(Duration::from_secs(i), SimEvent::Isolate(node));
+            sim.schedule(Duration::from_secs(i) + Duration::from_millis(500), SimEvent::Heal);
+        }
+        sim
+    }
+
+    fn schedule(&mut self, at: Duration, event: SimEvent) {
+        self.queue.insert((at, self.scheduled), event);
+        self.scheduled += 1;
+    }
+
+    fn send(&mut self, from: u64, to: u64, msg: RaftMessage) {
+        let cut = self.isolated.is_some_and(|id| id == from || id == to);
+        if to == CLIENT_ID || cut || random_unit(&mut self.rng) < SIM_DROP_RATE {
+            return;
+        }
+        let at = self.clock.now() + SIM_MAX_LATENCY.mul_f64(random_unit(&mut self.rng));
+        self.schedule(at, SimEvent::Deliver(from, to, msg));
+    }
+
+    // The leader of the newest term, as the simulation sees it
+    fn leader(&self) -> Option<u64> {
+        self.nodes
+            .iter()
+            .filter(|n| n.state == NodeState::Leader)
+            .max_by_key(|n| n.current_term)
+            .map(|n| n.id)
+    }
+
+    fn run(&mut self) {
+        while let Some(((time, _), event)) = self.queue.pop_first() {
+            if time > SIM_DURATION {
+                break;
+            }
+            self.clock.advance_to(time);
+
+            let id = match event {
+                SimEvent::Tick(id) => {
+                    let node = &mut self.nodes[id as usize];
+                    let next = time + node.heartbeat_interval;
+                    for (to, msg) in node.tick() {
+                        self.send(id, to, msg);
+                    }
+                    self.schedule(next, SimEvent::Tick(id));
+                    id
+                }
+                SimEvent::Deliver(from, to, msg) => {
+                    if let Some(resp) = self.nodes[to as usize].handle_message(from, msg) {
+                        self.send(to, from, resp);
+                    }
+                    to
+                }
+                SimEvent::Write(i) => {
+                    let Some(leader) = self.leader() else {
+                        continue;
+                    };
+                    let request = RaftMessage::ClientRequest {
+                        client_id: 0,
+                        sequence: i + 1,
+                        command: format!("SET k{} {}", i % 4, i),
+                    };
+                    self.nodes[leader as usize].handle_message(CLIENT_ID, request);
+                    leader
+                }
+                SimEvent::Isolate(id) => {
+                    self.isolated = Some(id);
+                    continue;
+                }
+                SimEvent::Heal => {
+                    self.isolated = None;
+                    continue;
+                }
+            };
+
+            for (to, msg) in std::mem::take(&mut self.nodes[id as usize].outbox) {
+                self.send(id, to, msg);
+            }
+        }
+    }
+
+    fn check(&self) -> Result<(), String> {
+        self.events.check_history(self.nodes.len())?;
+        let statuses: Vec<NodeStatus> = self.nodes.iter().map(RaftNode::status).collect();
+        check_statuses(&statuses)
+    }
+}
+
+// Simulate `count` seeds from `first_seed`, checking the safety properties
+// after each and that a replay of the first reproduces its history exactly
+fn run_simulations(count: u64, first_seed: u64) -> Result<(), String> {
+    // A single seed is usually a failure being investigated, so narrate it
+    let verbose = count == 1;
+    let mut elections = 0;
+    let mut max_term = 0;
+
+    for seed in first_seed..first_seed + count {
+        let mut sim = Simulation::new(seed, verbose);
+        sim.run();
+        sim.check().map_err(|e| {
+            format!("seed {}: {}\nReplay with --simulate 1 {}", seed, e, seed)
+        })?;
+
+        if verbose {
+            println!("\nEvent metrics:");
+            sim.events.print_metrics();
+            println!();
+        }
+        let leaders = sim.events.leaders_by_term()?;
+        elections += leaders.len();
+        max_term = max_term.max(leaders.keys().copied().max().unwrap_or(0));
+
+        if seed == first_seed {
+            let mut replay = Simulation::new(seed, false);
+            replay.run();
+            if replay.events.events() != sim.events.events() {
+                return Err(format!("seed {} produced a different history when replayed", seed));
+            }
+        }
+    }
+
+    println!(
+        "✓ {} seeds from {}: {} leaders elected, highest term {}, no safety violations",
+        count, first_seed, elections, max_term
+    );
+    println!("✓ Seed {} replays to an identical event history", first_seed);
+    Ok(())
+}
+
+// ========== TCP TRANSPORT ==========
+// Frames on the wire: a big-endian u32 length, then the JSON-encoded envelope
+const MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+async fn write_frame<W: AsyncWrite + Unpin>(writer: &mut W, envelope: &Envelope) -> io::Result<()> {
+    let body = serde_json::to_vec(envelope).map_err(io::Error::other)?;
+    writer.write_u32(body.len() as u32).await?;
+    writer.write_all(&body).await?;
+    writer.flush().await
+}
+
+async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R) -> io::Result<Envelope> {
+    let len = reader.read_u32().await? as usize;
+    if len > MAX_FRAME_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("frame of {} bytes exceeds the {} byte limit", len, MAX_FRAME_LEN),
+        ));
+    }
+    let mut body = vec![0; len];
+    reader.read_exact(&mut body).await?;
+    serde_json::from_slice(&body).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+// One outgoing connection per peer, opened on first use and reopened after
+// an error. Responses come back over the peer's own connection to us.
+// Clients get their responses on the connection they sent the request on.
+struct TcpTransport {
+    peers: HashMap<u64, SocketAddr>,
+    links: Mutex<HashMap<u64, mpsc::UnboundedSender<Envelope>>>,
+    clients: Mutex<HashMap<u64, mpsc::UnboundedSender<Envelope>>>,
+}
+
+impl TcpTransport {
+    fn new(peers: HashMap<u64, SocketAddr>) -> Self {
+        TcpTransport {
+            peers,
+            links: Mutex::new(HashMap::new()),
+            clients: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn connect(addr: SocketAddr) -> mpsc::UnboundedSender<Envelope> {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Envelope>();
+        tokio::spawn(async move {
             loop {
-                tokio::select! {
-                    _ = heartbeat_timer.tick() => {
-                        let mut node = node_handle.lock().unwrap();
-                        
-                        match node.state {
-                            NodeState::Leader => {
-                                for peer in node.peers.clone() {
-                                    let msg = node.create_append_entries(peer);
-                                    if let Some(sender) = channels.get(&peer) {
-                                        let _ = sender.send((node_id, msg));
-                                    }
-                                }
-                            }
-                            NodeState::Follower | NodeState::Candidate => {
-                                if node.is_election_timeout() {
-                                    node.start_election();
-                                    let msg = node.create_request_vote();
-                                    for peer in node.peers.clone() {
-                                        if let Some(sender) = channels.get(&peer) {
-                                            let _ = sender.send((node_id, msg.clone()));
-                                        }
-                                    }
-                                }
+                match TcpStream::connect(addr).await {
+                    Ok(mut stream) => {
+                        while let Some(envelope) = rx.recv().await {
+                            if write_frame(&mut stream, &envelope).await.is_err() {
+                                break;
                             }
                         }
-                    }
-                    
-                    Some((from_id, msg)) = rx.recv() => {
-                        let mut node = node_handle.lock().unwrap();
-                        
-                        let response = match msg {
-                            RaftMessage::RequestVote { term, candidate_id, last_log_index, last_log_term } => {
-                                Some(node.handle_request_vote(term, candidate_id, last_log_index, last_log_term))
-                            }
-                            RaftMessage::RequestVoteResponse { term, vote_granted } => {
-                                node.handle_vote_response(term, vote_granted);
-                                None
-                            }
-                            RaftMessage::AppendEntries { term, leader_id, prev_log_index, prev_log_term, entries, leader_commit } => {
-                                Some(node.handle_append_entries(term, leader_id, prev_log_index, prev_log_term, entries, leader_commit))
-                            }
-                            RaftMessage::AppendEntriesResponse { term, success, match_index } => {
-                                node.handle_append_entries_response(from_id, term, success, match_index);
-                                None
-                            }
-                            RaftMessage::ClientRequest { command } => {
-                                let _ = node.handle_client_request(command);
-                                None
-                            }
-                        };
-                        
-                        if let Some(resp) = response {
-                            if let Some(sender) = channels.get(&from_id) {
-                                let _ = sender.send((node_id, resp));
-                            }
+                        if rx.is_closed() {
+                            return;
                         }
                     }
+                    // Don't let heartbeats pile up for a peer that is down
+                    Err(_) => while rx.try_recv().is_ok() {},
                 }
+                sleep(Duration::from_millis(100)).await;
             }
         });
+        tx
     }
 
-    fn get_leader(&self) -> Option<u64> {
-        for (id, node_handle) in &self.nodes {
-            let node = node_handle.lock().unwrap();
-            if node.state == NodeState::Leader {
-                return Some(*id);
+    // Accept connections and feed every frame that arrives into `inbox`
+    async fn serve(self: Arc<Self>, listener: TcpListener, inbox: mpsc::UnboundedSender<Envelope>) {
+        loop {
+            let Ok((stream, _)) = listener.accept().await else {
+                continue;
+            };
+            let (mut reader, mut writer) = stream.into_split();
+            let (reply_tx, mut reply_rx) = mpsc::unbounded_channel::<Envelope>();
+            tokio::spawn(async move {
+                while let Some(envelope) = reply_rx.recv().await {
+                    if write_frame(&mut writer, &envelope).await.is_err() {
+                        break;
+                    }
+                }
+            });
+
+            let transport = Arc::clone(&self);
+            let inbox = inbox.clone();
+            tokio::spawn(async move {
+                while let Ok(envelope) = read_frame(&mut reader).await {
+                    if let (CLIENT_ID, RaftMessage::ClientRequest { client_id, .. }) = &envelope {
+                        transport.clients.lock().unwrap().insert(*client_id, reply_tx.clone());
+                    }
+                    if inbox.send(envelope).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    }
+}
+
+impl Transport for TcpTransport {
+    fn send(&self, from: u64, to: u64, msg: RaftMessage) {
+        if to == CLIENT_ID {
+            if let RaftMessage::ClientResponse { client_id, .. } = &msg {
+                if let Some(reply) = self.clients.lock().unwrap().get(client_id) {
+                    let _ = reply.send((from, msg));
+                }
             }
+            return;
         }
-        None
+
+        let Some(&addr) = self.peers.get(&to) else {
+            return;
+        };
+        let mut links = self.links.lock().unwrap();
+        let link = links.entry(to).or_insert_with(|| Self::connect(addr));
+        let _ = link.send((from, msg));
     }
+}
 
-    fn send_client_request(&self, leader_id: u64, command: String) {
-        if let Some(node_handle) = self.nodes.get(&leader_id) {
-            let mut node = node_handle.lock().unwrap();
-            let _ = node.handle_client_request(command);
+// Run node `id` in this process until it is killed. The configuration is
+// this node plus `peers`, which must be the same on every node.
+async fn run_tcp_node(id: u64, bind: SocketAddr, peers: HashMap<u64, SocketAddr>) -> io::Result<()> {
+    let mut members: Vec<u64> = peers.keys().copied().chain([id]).collect();
+    members.sort();
+
+    let listener = TcpListener::bind(bind).await?;
+    println!("[Node {}] Listening on {}, cluster {:?}", id, listener.local_addr()?, members);
+
+    let transport = Arc::new(TcpTransport::new(peers));
+    let (inbox, rx) = mpsc::unbounded_channel();
+    let node = RaftNode::new(id, members, Arc::new(ConsoleReporter));
+    // Nothing inspects a standalone node, so its control channel goes unused
+    let _control = spawn_node(node, rx, transport.clone());
+    transport.serve(listener, inbox).await;
+    Ok(())
+}
+
+// A fresh connection per request; any failure just counts as no answer
+struct TcpClient {
+    addresses: HashMap<u64, SocketAddr>,
+}
+
+impl TcpClient {
+    async fn try_call(&self, node_id: u64, client_id: u64, sequence: u64, command: &str) -> io::Result<ClientReply> {
+        let addr = self.addresses.get(&node_id).ok_or(io::ErrorKind::NotFound)?;
+        let mut stream = TcpStream::connect(addr).await?;
+        let request = RaftMessage::ClientRequest { client_id, sequence, command: command.to_string() };
+        write_frame(&mut stream, &(CLIENT_ID, request)).await?;
+
+        loop {
+            if let (_, RaftMessage::ClientResponse { client_id: c, sequence: s, result, leader }) =
+                read_frame(&mut stream).await?
+            {
+                if c == client_id && s == sequence {
+                    return Ok(ClientReply { result, leader });
+                }
+            }
+        }
+    }
+}
+
+impl ClientTransport for TcpClient {
+    async fn call(&self, node_id: u64, client_id: u64, sequence: u64, command: &str) -> Option<ClientReply> {
+        let call = self.try_call(node_id, client_id, sequence, command);
+        tokio::time::timeout(Duration::from_secs(1), call).await.ok()?.ok()
+    }
+}
+
+// Sessions outlive processes, so a reused pid would collide with an old
+// session and have its writes dropped as duplicates. Mix in the clock and
+// RandomState's per-process keys for an id no earlier client has used.
+fn fresh_client_id() -> u64 {
+    use std::hash::{BuildHasher, Hash, Hasher};
+
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    std::time::SystemTime::now().hash(&mut hasher);
+    std::process::id().hash(&mut hasher);
+    hasher.finish()
+}
+
+// Run one command against the cluster at `addresses` and print the result
+async fn run_tcp_client(addresses: HashMap<u64, SocketAddr>, command: String) -> io::Result<()> {
+    let mut nodes: Vec<u64> = addresses.keys().copied().collect();
+    nodes.sort();
+    let transport = TcpClient { addresses };
+    let mut client = RaftClient::new(&transport, fresh_client_id(), nodes);
+
+    let value = client.execute(&command).await.map_err(io::Error::other)?;
+    println!("{}", value);
+    Ok(())
+}
+
+// ========== COMMAND LINE ==========
+const USAGE: &str = "\
+usage: distributed-system              in-process demo
+       distributed-system --faults     partition and message-loss scenarios
+       distributed-system --simulate [SEEDS] [FIRST_SEED]
+                                       deterministic simulation of many seeds
+       distributed-system --node ID --bind ADDR [--peers ID=ADDR,...]
+       distributed-system --client ID=ADDR,... COMMAND...";
+
+enum Mode {
+    Demo,
+    Faults,
+    Simulate {
+        seeds: u64,
+        first_seed: u64,
+    },
+    Node {
+        id: u64,
+        bind: SocketAddr,
+        peers: HashMap<u64, SocketAddr>,
+    },
+    Client {
+        addresses: HashMap<u64, SocketAddr>,
+        command: String,
+    },
+}
+
+fn parse_addr(s: &str) -> Result<SocketAddr, String> {
+    s.parse().map_err(|e| format!("bad address {}: {}", s, e))
+}
+
+// `ID=ADDR,ID=ADDR,...`
+fn parse_nodes(list: &str) -> Result<HashMap<u64, SocketAddr>, String> {
+    let mut nodes = HashMap::new();
+    for node in list.split(',').filter(|n| !n.is_empty()) {
+        let (id, addr) = node.split_once('=').ok_or_else(|| format!("bad node {}, expected ID=ADDR", node))?;
+        let id = id.parse().map_err(|_| format!("bad node ID {}", id))?;
+        nodes.insert(id, parse_addr(addr)?);
+    }
+    Ok(nodes)
+}
+
+fn parse_args(args: &[String]) -> Result<Mode, String> {
+    match args.first().map(String::as_str) {
+        None => Ok(Mode::Demo),
+        Some("--faults") => Ok(Mode::Faults),
+        Some("--simulate") => {
+            let number = |i: usize, default: u64| {
+                args.get(i).map_or(Ok(default), |n| n.parse().map_err(|_| format!("bad number {}", n)))
+            };
+            Ok(Mode::Simulate {
+                seeds: number(1, 200)?.max(1),
+                first_seed: number(2, 0)?,
+            })
         }
+        Some("--client") => {
+            let addresses = parse_nodes(args.get(1).ok_or("--client needs the cluster's addresses")?)?;
+            if addresses.is_empty() || args.len() < 3 {
+                return Err("--client needs addresses and a command".to_string());
+            }
+            Ok(Mode::Client {
+                addresses,
+                command: args[2..].join(" "),
+            })
+        }
+        Some("--node") => {
+            let mut id = None;
+            let mut bind = None;
+            let mut peers = HashMap::new();
+            let mut rest = args.iter();
+            while let Some(flag) = rest.next() {
+                let value = rest.next().ok_or_else(|| format!("{} needs a value", flag))?;
+                match flag.as_str() {
+                    "--node" => id = Some(value.parse().map_err(|_| format!("bad node ID {}", value))?),
+                    "--bind" => bind = Some(parse_addr(value)?),
+                    "--peers" => peers.extend(parse_nodes(value)?),
+                    _ => return Err(format!("unknown option {}", flag)),
+                }
+            }
+            let id: u64 = id.ok_or("--node needs an ID")?;
+            if id == CLIENT_ID || peers.contains_key(&id) {
+                return Err(format!("node ID {} is reserved or listed as its own peer", id));
+            }
+            Ok(Mode::Node {
+                id,
+                bind: bind.ok_or("--node needs --bind")?,
+                peers,
+            })
+        }
+        Some(other) => Err(format!("unknown option {}", other)),
     }
 }
 
 // ========== MAIN ==========
 #[tokio::main]
 async fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mode = match parse_args(&args) {
+        Ok(mode) => mode,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let result = match mode {
+        Mode::Demo => {
+            run_demo().await;
+            Ok(())
+        }
+        Mode::Faults => {
+            println!("=== Distributed System with Raft Consensus ===");
+            run_fault_scenarios().await;
+            Ok(())
+        }
+        Mode::Simulate { seeds, first_seed } => {
+            run_simulations(seeds, first_seed).map_err(io::Error::other)
+        }
+        Mode::Node { id, bind, peers } => run_tcp_node(id, bind, peers).await,
+        Mode::Client { addresses, command } => run_tcp_client(addresses, command).await,
+    };
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+// ========== DEMO ==========
+async fn run_demo() {
     println!("=== Distributed System with Raft Consensus ===\n");
 
-    println!("Creating a 5-node Raft cluster...");
-    let cluster = Arc::new(Cluster::new(5));
+    // Nodes 3 and 4 run from the start but only join the cluster later
+    println!("Creating a Raft cluster of nodes 0-2, with 3 and 4 standing by...");
+    let cluster = Arc::new(Cluster::new(5, 3));
 
     println!("Starting all nodes...\n");
     for id in 0..5 {
@@ -523,18 +2397,54 @@ async fn main() {
     println!("Waiting for leader election...");
     sleep(Duration::from_secs(2)).await;
 
-    if let Some(leader_id) = cluster.get_leader() {
+    if let Some(leader_id) = cluster.get_leader().await {
         println!("\n✓ Leader elected: Node {}\n", leader_id);
 
         println!("Sending client requests to leader...");
-        cluster.send_client_request(leader_id, "SET x = 10".to_string());
-        sleep(Duration::from_millis(200)).await;
+        let commands = ["SET x 10", "SET y 20", "GET x", "DEL y", "DEL y", "GET y", "ADD x y"];
+        // The second `DEL y` retries the first with the same sequence number;
+        // it gets the original answer rather than being applied again
+        for (sequence, command) in [1, 2, 3, 4, 4, 5, 6].into_iter().zip(commands) {
+            let result = cluster.request(leader_id, 1, sequence, command).await;
+            println!("  #{} {} -> {:?}", sequence, command, result);
+        }
 
-        cluster.send_client_request(leader_id, "SET y = 20".to_string());
-        sleep(Duration::from_millis(200)).await;
+        // The client library starts at a follower and is redirected
+        println!("\nUsing the client library, starting at a follower...");
+        let follower = (0..3).find(|&id| id != leader_id).unwrap();
+        let mut client = RaftClient::new(&*cluster, 2, vec![follower, leader_id]);
+        println!("  SET z 30 -> {:?}", client.execute("SET z 30").await);
+        println!("  GET z -> {:?}", client.execute("GET z").await);
 
-        cluster.send_client_request(leader_id, "ADD x y".to_string());
-        sleep(Duration::from_millis(200)).await;
+        println!("\nSending more requests to trigger log compaction...");
+        for i in 0..SNAPSHOT_THRESHOLD {
+            cluster.send_client_request(leader_id, format!("SET k{} {}", i, i * i));
+        }
+        sleep(Duration::from_millis(300)).await;
+
+        // Node 3 has missed the compacted entries and catches up from the snapshot
+        println!("\nAdding nodes 3 and 4...");
+        cluster.send_config_change(leader_id, MembershipChange::AddNode(3));
+        // Rejected: the first change hasn't committed yet
+        cluster.send_config_change(leader_id, MembershipChange::AddNode(4));
+        sleep(Duration::from_millis(300)).await;
+        cluster.send_config_change(leader_id, MembershipChange::AddNode(4));
+        sleep(Duration::from_millis(300)).await;
+
+        println!("\nRemoving the leader, node {}...", leader_id);
+        cluster.send_config_change(leader_id, MembershipChange::RemoveNode(leader_id));
+        sleep(Duration::from_secs(1)).await;
+        if let Some(new_leader) = cluster.get_leader().await {
+            println!("New leader: Node {}", new_leader);
+        }
+        // The client's cached leader has stepped down; it finds the new one
+        println!("  GET z -> {:?}", client.execute("GET z").await);
+
+        println!("\nCluster state:");
+        cluster.print_summary().await;
+
+        println!("\nEvent metrics:");
+        cluster.events.print_metrics();
 
         println!("\n✓ Raft consensus demonstration complete!");
         println!("\nKey features demonstrated:");
@@ -543,9 +2453,88 @@ async fn main() {
         println!("  • Heartbeat mechanism to maintain leadership");
         println!("  • Term-based conflict resolution");
         println!("  • Majority-based commit consensus");
+        println!("  • Snapshots and InstallSnapshot for lagging followers");
+        println!("  • Adding and removing nodes one at a time through the log");
+        println!("  • Key-value store with deduplicated client retries");
+        println!("  • Linearizable reads via ReadIndex");
+        println!("  • Client library following leader hints from followers");
+        println!("  • Election and replication events for metrics and checks");
+        println!("\nRun with --faults for partition and message-loss scenarios,");
+        println!("--simulate to check safety over many seeded deterministic runs,");
+        println!("or --node to run each node as its own process over TCP.");
     } else {
         println!("\n✗ No leader elected (this is expected in some scenarios)");
     }
 
     sleep(Duration::from_secs(1)).await;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEEDS: std::ops::Range<u64> = 0..1000;
+
+    fn simulate(seed: u64) -> Simulation {
+        let mut sim = Simulation::new(seed, false);
+        sim.run();
+        sim
+    }
+
+    #[test]
+    fn test_safety_holds_across_seeds() {
+        for seed in SEEDS {
+            let sim = simulate(seed);
+            if let Err(e) = sim.check() {
+                panic!("seed {}: {}\nReplay with --simulate 1 {}", seed, e, seed);
+            }
+            // Isolating one node of five at a time never stops progress
+            let leaders = sim.events.leaders_by_term().unwrap();
+            assert!(!leaders.is_empty(), "seed {} elected no leader", seed);
+            assert!(
+                sim.events.count(|e| matches!(e, RaftEvent::EntriesCommitted { .. })) > 0,
+                "seed {} committed nothing",
+                seed
+            );
+        }
+    }
+
+    #[test]
+    fn test_seed_replays_identical_history() {
+        for seed in 0..20 {
+            assert_eq!(simulate(seed).events.events(), simulate(seed).events.events(), "seed {}", seed);
+        }
+        assert_ne!(simulate(1).events.events(), simulate(2).events.events());
+    }
+
+    #[test]
+    fn test_collector_flags_unsafe_histories() {
+        let collector = EventCollector::default();
+        collector.on_event(&RaftEvent::ElectionWon { node: 1, term: 3, votes: 3 });
+        collector.on_event(&RaftEvent::EntriesCommitted { node: 1, index: 4 });
+        assert!(collector.check_history(5).is_ok());
+
+        collector.on_event(&RaftEvent::EntriesCommitted { node: 1, index: 2 });
+        assert!(collector.check_history(5).unwrap_err().contains("uncommitted"));
+
+        let collector = EventCollector::default();
+        collector.on_event(&RaftEvent::ElectionWon { node: 1, term: 3, votes: 3 });
+        collector.on_event(&RaftEvent::ElectionWon { node: 2, term: 3, votes: 3 });
+        assert_eq!(collector.leaders_by_term().unwrap_err(), "nodes 1 and 2 both won term 3");
+
+        let collector = EventCollector::default();
+        collector.on_event(&RaftEvent::ElectionWon { node: 1, term: 3, votes: 2 });
+        assert!(collector.check_history(5).unwrap_err().contains("only 2 of 5 votes"));
+    }
+
+    #[test]
+    fn test_snapshot_round_trips_awkward_text() {
+        let mut store = KvStore::default();
+        store.data.insert("tab\tkey".to_string(), "line\nbreak\\t and \\".to_string());
+        store.data.insert("plain".to_string(), String::new());
+        store.sessions.insert(7, (3, Ok("a\tb\r\n".to_string())));
+        store.sessions.insert(9, (1, Err("bad \\n\n".to_string())));
+
+        assert_eq!(KvStore::decode(&store.encode()), store);
+    }
+}
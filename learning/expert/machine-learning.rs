@@ -1,6 +1,15 @@
 // Machine Learning Library with Neural Networks and Backpropagation
 // Implements linear regression, logistic regression, and multi-layer perceptrons
-
+// Trained with shuffled mini-batches and pluggable optimizers (SGD+momentum, RMSProp, Adam)
+// Validation tracking, early stopping, and confusion-matrix metrics for classifiers
+// Weights use seeded Xavier/He initialization so runs are reproducible
+// CSV datasets load into matrices with standard/min-max feature scaling
+// k-fold cross-validation and hyperparameter grid search for model selection
+// `machine-learning serve MODEL_FILE [ADDR]` serves a saved network's predictions over HTTP
+// Training callbacks drive learning-rate schedules, CSV history logs, and best-model checkpoints
+// Loss curves and decision boundaries can be exported as CSV (gnuplot-ready) or SVG
+
+use std::collections::HashMap;
 use std::f64::consts::E;
 
 // ========== MATRIX OPERATIONS ==========
@@ -37,12 +46,15 @@ impl Matrix {
         }
     }
 
-    fn random(rows: usize, cols: usize, scale: f64) -> Self {
-        let mut data = Vec::with_capacity(rows * cols);
-        for i in 0..rows * cols {
-            let pseudo_random = (i as f64 * 12.9898).sin() * 43758.5453;
-            data.push((pseudo_random.fract() - 0.5) * 2.0 * scale);
-        }
+    // Entries drawn uniformly from [-limit, limit)
+    fn random_uniform(rows: usize, cols: usize, limit: f64, rng: &mut Rng) -> Self {
+        let data = (0..rows * cols).map(|_| rng.uniform(-limit, limit)).collect();
+        Matrix { rows, cols, data }
+    }
+
+    // Entries drawn from N(0, std_dev^2)
+    fn random_normal(rows: usize, cols: usize, std_dev: f64, rng: &mut Rng) -> Self {
+        let data = (0..rows * cols).map(|_| rng.normal() * std_dev).collect();
         Matrix { rows, cols, data }
     }
 
@@ -72,6 +84,25 @@ impl Matrix {
         }
     }
 
+    // Add a 1 x cols row vector to every row (bias broadcasting)
+    fn add_row(&self, row: &Matrix) -> Matrix {
+        assert_eq!(row.rows, 1);
+        assert_eq!(self.cols, row.cols);
+
+        let data: Vec<f64> = self
+            .data
+            .iter()
+            .enumerate()
+            .map(|(i, a)| a + row.data[i % self.cols])
+            .collect();
+
+        Matrix {
+            rows: self.rows,
+            cols: self.cols,
+            data,
+        }
+    }
+
     fn sub(&self, other: &Matrix) -> Matrix {
         assert_eq!(self.rows, other.rows);
         assert_eq!(self.cols, other.cols);
@@ -161,6 +192,30 @@ impl Matrix {
         self.data.iter().sum()
     }
 
+    // New matrix made of the given rows, in the given order
+    fn select_rows(&self, indices: &[usize]) -> Matrix {
+        let mut data = Vec::with_capacity(indices.len() * self.cols);
+        for &row in indices {
+            data.extend_from_slice(&self.data[row * self.cols..(row + 1) * self.cols]);
+        }
+        Matrix {
+            rows: indices.len(),
+            cols: self.cols,
+            data,
+        }
+    }
+
+    // Sum each column into a 1 x cols row vector
+    fn sum_rows(&self) -> Matrix {
+        let mut result = Matrix::zeros(1, self.cols);
+        for i in 0..self.rows {
+            for j in 0..self.cols {
+                result.data[j] += self.get(i, j);
+            }
+        }
+        result
+    }
+
     fn mean(&self) -> f64 {
         self.sum() / (self.rows * self.cols) as f64
     }
@@ -197,50 +252,541 @@ fn tanh_derivative(x: f64) -> f64 {
     1.0 - t * t
 }
 
+// Named activations, so a trained network can be written out and read back
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Activation {
+    Sigmoid,
+    Tanh,
+    Relu,
+    Identity,
+}
+
+impl Activation {
+    fn apply(self, x: f64) -> f64 {
+        match self {
+            Activation::Sigmoid => sigmoid(x),
+            Activation::Tanh => tanh(x),
+            Activation::Relu => relu(x),
+            Activation::Identity => x,
+        }
+    }
+
+    fn derivative(self, x: f64) -> f64 {
+        match self {
+            Activation::Sigmoid => sigmoid_derivative(x),
+            Activation::Tanh => tanh_derivative(x),
+            Activation::Relu => relu_derivative(x),
+            Activation::Identity => 1.0,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Activation::Sigmoid => "sigmoid",
+            Activation::Tanh => "tanh",
+            Activation::Relu => "relu",
+            Activation::Identity => "identity",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Activation> {
+        match name {
+            "sigmoid" => Some(Activation::Sigmoid),
+            "tanh" => Some(Activation::Tanh),
+            "relu" => Some(Activation::Relu),
+            "identity" => Some(Activation::Identity),
+            _ => None,
+        }
+    }
+}
+
+// ========== RANDOM NUMBERS ==========
+// xorshift64* generator; small, fast, and deterministic for a given seed
+#[derive(Clone)]
+struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // A zero state would make xorshift emit zeros forever
+        Rng {
+            state: seed ^ 0x9E37_79B9_7F4A_7C15,
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    // Uniform in [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn uniform(&mut self, low: f64, high: f64) -> f64 {
+        low + (high - low) * self.next_f64()
+    }
+
+    // Standard normal via the Box-Muller transform
+    fn normal(&mut self) -> f64 {
+        // 1 - u keeps the log argument in (0, 1]
+        let u1 = 1.0 - self.next_f64();
+        let u2 = self.next_f64();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+
+    // Uniform in [0, n)
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    // Fisher-Yates
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+// ========== WEIGHT INITIALIZATION ==========
+#[derive(Debug, Clone, Copy)]
+enum Init {
+    // Glorot uniform, limit sqrt(6 / (fan_in + fan_out)); suits sigmoid/tanh/linear
+    Xavier,
+    // Kaiming normal, std sqrt(2 / fan_in); suits ReLU
+    He,
+}
+
+impl Init {
+    fn weights(self, fan_in: usize, fan_out: usize, rng: &mut Rng) -> Matrix {
+        match self {
+            Init::Xavier => {
+                let limit = (6.0 / (fan_in + fan_out) as f64).sqrt();
+                Matrix::random_uniform(fan_in, fan_out, limit, rng)
+            }
+            Init::He => {
+                let std_dev = (2.0 / fan_in as f64).sqrt();
+                Matrix::random_normal(fan_in, fan_out, std_dev, rng)
+            }
+        }
+    }
+}
+
+// ========== OPTIMIZERS ==========
+// Parameters are identified by a slot number chosen by the model, so an
+// optimizer can keep per-parameter state (velocities, moment estimates).
+// Send + Sync lets a trained model be shared across server threads.
+trait Optimizer: Send + Sync {
+    // Returns the updated parameter after one step along `grad`
+    fn step(&mut self, slot: usize, param: &Matrix, grad: &Matrix) -> Matrix;
+    fn name(&self) -> &'static str;
+    fn learning_rate(&self) -> f64;
+    fn set_learning_rate(&mut self, learning_rate: f64);
+}
+
+// Zero-initialised state for `slot`, shaped like `param`
+fn slot_state<'a>(states: &'a mut HashMap<usize, Matrix>, slot: usize, param: &Matrix) -> &'a mut Matrix {
+    states
+        .entry(slot)
+        .or_insert_with(|| Matrix::zeros(param.rows, param.cols))
+}
+
+// Stochastic gradient descent with optional classical momentum
+struct Sgd {
+    learning_rate: f64,
+    momentum: f64,
+    velocities: HashMap<usize, Matrix>,
+}
+
+impl Sgd {
+    fn new(learning_rate: f64) -> Self {
+        Sgd {
+            learning_rate,
+            momentum: 0.0,
+            velocities: HashMap::new(),
+        }
+    }
+
+    // Fraction of the previous step carried into the next, typically 0.9
+    fn momentum(mut self, momentum: f64) -> Self {
+        self.momentum = momentum;
+        self
+    }
+}
+
+impl Optimizer for Sgd {
+    fn step(&mut self, slot: usize, param: &Matrix, grad: &Matrix) -> Matrix {
+        let velocity = slot_state(&mut self.velocities, slot, param);
+        *velocity = velocity.scale(self.momentum).sub(&grad.scale(self.learning_rate));
+        param.add(velocity)
+    }
+
+    fn name(&self) -> &'static str {
+        if self.momentum > 0.0 {
+            "SGD+momentum"
+        } else {
+            "SGD"
+        }
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.learning_rate = learning_rate;
+    }
+}
+
+// Divides each step by a running RMS of recent gradients
+struct RmsProp {
+    learning_rate: f64,
+    decay: f64,
+    epsilon: f64,
+    mean_squares: HashMap<usize, Matrix>,
+}
+
+impl RmsProp {
+    fn new(learning_rate: f64) -> Self {
+        RmsProp {
+            learning_rate,
+            decay: 0.9,
+            epsilon: 1e-8,
+            mean_squares: HashMap::new(),
+        }
+    }
+}
+
+impl Optimizer for RmsProp {
+    fn step(&mut self, slot: usize, param: &Matrix, grad: &Matrix) -> Matrix {
+        let mean_square = slot_state(&mut self.mean_squares, slot, param);
+        *mean_square = mean_square
+            .scale(self.decay)
+            .add(&grad.hadamard(grad).scale(1.0 - self.decay));
+
+        let (lr, eps) = (self.learning_rate, self.epsilon);
+        let data = param
+            .data
+            .iter()
+            .zip(&grad.data)
+            .zip(&mean_square.data)
+            .map(|((p, g), ms)| p - lr * g / (ms.sqrt() + eps))
+            .collect();
+        Matrix::from_vec(param.rows, param.cols, data)
+    }
+
+    fn name(&self) -> &'static str {
+        "RMSProp"
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.learning_rate = learning_rate;
+    }
+}
+
+// Momentum on the gradient plus RMSProp-style scaling, with bias correction
+struct Adam {
+    learning_rate: f64,
+    beta1: f64,
+    beta2: f64,
+    epsilon: f64,
+    first_moments: HashMap<usize, Matrix>,
+    second_moments: HashMap<usize, Matrix>,
+    steps: HashMap<usize, i32>,
+}
+
+impl Adam {
+    fn new(learning_rate: f64) -> Self {
+        Adam {
+            learning_rate,
+            beta1: 0.9,
+            beta2: 0.999,
+            epsilon: 1e-8,
+            first_moments: HashMap::new(),
+            second_moments: HashMap::new(),
+            steps: HashMap::new(),
+        }
+    }
+}
+
+impl Optimizer for Adam {
+    fn step(&mut self, slot: usize, param: &Matrix, grad: &Matrix) -> Matrix {
+        let t = self.steps.entry(slot).or_insert(0);
+        *t += 1;
+        let t = *t;
+
+        let m = slot_state(&mut self.first_moments, slot, param);
+        *m = m.scale(self.beta1).add(&grad.scale(1.0 - self.beta1));
+        let m = m.scale(1.0 / (1.0 - self.beta1.powi(t)));
+
+        let v = slot_state(&mut self.second_moments, slot, param);
+        *v = v.scale(self.beta2).add(&grad.hadamard(grad).scale(1.0 - self.beta2));
+        let v = v.scale(1.0 / (1.0 - self.beta2.powi(t)));
+
+        let (lr, eps) = (self.learning_rate, self.epsilon);
+        let data = param
+            .data
+            .iter()
+            .zip(&m.data)
+            .zip(&v.data)
+            .map(|((p, m), v)| p - lr * m / (v.sqrt() + eps))
+            .collect();
+        Matrix::from_vec(param.rows, param.cols, data)
+    }
+
+    fn name(&self) -> &'static str {
+        "Adam"
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.learning_rate = learning_rate;
+    }
+}
+
+// ========== MODEL TRAINING ==========
+trait Model {
+    fn predict(&self, x: &Matrix) -> Matrix;
+    // One optimizer step on a batch; returns the batch loss before the update
+    fn train_batch(&mut self, x: &Matrix, y: &Matrix) -> f64;
+    fn optimizer_mut(&mut self) -> &mut dyn Optimizer;
+}
+
+// What a callback sees at the end of each epoch
+struct EpochLogs {
+    epoch: usize,
+    train_loss: f64,
+    validation_loss: Option<f64>,
+    // Rate used during the epoch that just finished
+    learning_rate: f64,
+}
+
+// Hooks into Trainer::fit_with_callbacks; both methods default to no-ops
+trait Callback<M: Model> {
+    fn on_batch_end(&mut self, _epoch: usize, _batch: usize, _loss: f64, _model: &mut M) {}
+    fn on_epoch_end(&mut self, _logs: &EpochLogs, _model: &mut M) {}
+}
+
+fn mse_loss(predictions: &Matrix, y: &Matrix) -> f64 {
+    let errors = predictions.sub(y);
+    errors.hadamard(&errors).sum() / (2.0 * y.rows as f64)
+}
+
+// Per-epoch losses recorded by Trainer::fit
+struct History {
+    train_loss: Vec<f64>,
+    // Empty unless the trainer was given a validation set
+    validation_loss: Vec<f64>,
+    best_epoch: Option<usize>,
+    stopped_early: bool,
+}
+
+impl History {
+    fn best_validation_loss(&self) -> Option<f64> {
+        self.best_epoch.map(|epoch| self.validation_loss[epoch])
+    }
+}
+
+// Runs epochs of shuffled mini-batches over any Model
+#[derive(Clone)]
+struct Trainer {
+    epochs: usize,
+    batch_size: Option<usize>,
+    log_every: usize,
+    rng: Rng,
+    validation: Option<(Matrix, Matrix)>,
+    patience: Option<usize>,
+    min_delta: f64,
+}
+
+impl Trainer {
+    fn new(epochs: usize) -> Self {
+        Trainer {
+            epochs,
+            batch_size: None,
+            log_every: 100,
+            rng: Rng::new(42),
+            validation: None,
+            patience: None,
+            min_delta: 0.0,
+        }
+    }
+
+    // Rows per optimizer step; without it every step sees the full dataset
+    fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = Some(batch_size.max(1));
+        self
+    }
+
+    // Print progress every `epochs` epochs; 0 disables progress output
+    fn log_every(mut self, epochs: usize) -> Self {
+        self.log_every = epochs;
+        self
+    }
+
+    fn should_log(&self, epoch: usize) -> bool {
+        self.log_every > 0 && epoch.is_multiple_of(self.log_every)
+    }
+
+    // Seed for the per-epoch shuffle order
+    fn seed(mut self, seed: u64) -> Self {
+        self.rng = Rng::new(seed);
+        self
+    }
+
+    // Held-out rows scored at the end of every epoch
+    fn validation(mut self, x: &Matrix, y: &Matrix) -> Self {
+        assert_eq!(x.rows, y.rows);
+        self.validation = Some((x.clone(), y.clone()));
+        self
+    }
+
+    // Stop once validation loss has not improved by more than `min_delta`
+    // for `patience` consecutive epochs. Needs a validation set.
+    fn early_stopping(mut self, patience: usize, min_delta: f64) -> Self {
+        self.patience = Some(patience);
+        self.min_delta = min_delta;
+        self
+    }
+
+    fn fit<M: Model>(&mut self, model: &mut M, x: &Matrix, y: &Matrix) -> History {
+        self.fit_with_callbacks(model, x, y, &mut [])
+    }
+
+    // Like fit, notifying each callback after every batch and epoch
+    fn fit_with_callbacks<M: Model>(
+        &mut self,
+        model: &mut M,
+        x: &Matrix,
+        y: &Matrix,
+        callbacks: &mut [&mut dyn Callback<M>],
+    ) -> History {
+        assert_eq!(x.rows, y.rows);
+        let batch_size = self.batch_size.unwrap_or(x.rows).min(x.rows);
+        let mut order: Vec<usize> = (0..x.rows).collect();
+        let mut history = History {
+            train_loss: Vec::with_capacity(self.epochs),
+            validation_loss: Vec::new(),
+            best_epoch: None,
+            stopped_early: false,
+        };
+
+        for epoch in 0..self.epochs {
+            self.rng.shuffle(&mut order);
+
+            let mut total = 0.0;
+            for (batch_index, batch) in order.chunks(batch_size).enumerate() {
+                let loss = model.train_batch(&x.select_rows(batch), &y.select_rows(batch));
+                total += loss * batch.len() as f64;
+                for callback in callbacks.iter_mut() {
+                    callback.on_batch_end(epoch, batch_index, loss, model);
+                }
+            }
+            let loss = total / x.rows as f64;
+            history.train_loss.push(loss);
+
+            let validation_loss = self
+                .validation
+                .as_ref()
+                .map(|(x_val, y_val)| mse_loss(&model.predict(x_val), y_val));
+            if let Some(val_loss) = validation_loss {
+                history.validation_loss.push(val_loss);
+                let improved = history
+                    .best_validation_loss()
+                    .is_none_or(|best| val_loss < best - self.min_delta);
+                if improved {
+                    history.best_epoch = Some(epoch);
+                }
+            }
+
+            if self.should_log(epoch) {
+                match validation_loss {
+                    Some(val_loss) => {
+                        println!("Epoch {}: train loss = {:.6}, val loss = {:.6}", epoch, loss, val_loss)
+                    }
+                    None => println!("Epoch {}: train loss = {:.6}", epoch, loss),
+                }
+            }
+
+            let logs = EpochLogs {
+                epoch,
+                train_loss: loss,
+                validation_loss,
+                learning_rate: model.optimizer_mut().learning_rate(),
+            };
+            for callback in callbacks.iter_mut() {
+                callback.on_epoch_end(&logs, model);
+            }
+
+            let since_best = history.best_epoch.map_or(0, |best| epoch - best);
+            if self.patience.is_some_and(|patience| since_best >= patience) {
+                if self.log_every > 0 {
+                    println!(
+                        "Early stopping at epoch {}: best val loss {:.6} at epoch {}",
+                        epoch,
+                        history.best_validation_loss().unwrap_or(f64::NAN),
+                        history.best_epoch.unwrap_or(0)
+                    );
+                }
+                history.stopped_early = true;
+                break;
+            }
+        }
+
+        history
+    }
+}
+
 // ========== LINEAR REGRESSION ==========
 struct LinearRegression {
     weights: Matrix,
     bias: f64,
-    learning_rate: f64,
+    optimizer: Box<dyn Optimizer>,
 }
 
 impl LinearRegression {
-    fn new(features: usize, learning_rate: f64) -> Self {
+    fn new(features: usize, optimizer: impl Optimizer + 'static, seed: u64) -> Self {
         LinearRegression {
-            weights: Matrix::random(features, 1, 0.1),
+            weights: Init::Xavier.weights(features, 1, &mut Rng::new(seed)),
             bias: 0.0,
-            learning_rate,
+            optimizer: Box::new(optimizer),
         }
     }
+}
 
+impl Model for LinearRegression {
     fn predict(&self, x: &Matrix) -> Matrix {
         x.multiply(&self.weights).map(|v| v + self.bias)
     }
 
-    fn train(&mut self, x: &Matrix, y: &Matrix, epochs: usize) -> Vec<f64> {
+    fn train_batch(&mut self, x: &Matrix, y: &Matrix) -> f64 {
         let m = x.rows as f64;
-        let mut losses = Vec::new();
-
-        for epoch in 0..epochs {
-            let predictions = self.predict(x);
-            let errors = predictions.sub(y);
+        let predictions = self.predict(x);
+        let errors = predictions.sub(y);
 
-            let loss = errors.hadamard(&errors).sum() / (2.0 * m);
-            losses.push(loss);
+        let gradient = x.transpose().multiply(&errors).scale(1.0 / m);
+        let bias_gradient = Matrix::from_vec(1, 1, vec![errors.sum() / m]);
 
-            if epoch % 100 == 0 {
-                println!("Epoch {}: Loss = {:.6}", epoch, loss);
-            }
-
-            let x_transpose = x.transpose();
-            let gradient = x_transpose.multiply(&errors).scale(1.0 / m);
-            let bias_gradient = errors.sum() / m;
+        self.weights = self.optimizer.step(0, &self.weights, &gradient);
+        let bias = Matrix::from_vec(1, 1, vec![self.bias]);
+        self.bias = self.optimizer.step(1, &bias, &bias_gradient).get(0, 0);
 
-            self.weights = self.weights.sub(&gradient.scale(self.learning_rate));
-            self.bias -= self.learning_rate * bias_gradient;
-        }
+        mse_loss(&predictions, y)
+    }
 
-        losses
+    fn optimizer_mut(&mut self) -> &mut dyn Optimizer {
+        self.optimizer.as_mut()
     }
 }
 
@@ -248,51 +794,58 @@ impl LinearRegression {
 struct LogisticRegression {
     weights: Matrix,
     bias: f64,
-    learning_rate: f64,
+    optimizer: Box<dyn Optimizer>,
+    l2: f64,
 }
 
 impl LogisticRegression {
-    fn new(features: usize, learning_rate: f64) -> Self {
+    fn new(features: usize, optimizer: impl Optimizer + 'static, seed: u64) -> Self {
         LogisticRegression {
-            weights: Matrix::random(features, 1, 0.1),
+            weights: Init::Xavier.weights(features, 1, &mut Rng::new(seed)),
             bias: 0.0,
-            learning_rate,
+            optimizer: Box::new(optimizer),
+            l2: 0.0,
         }
     }
 
+    // L2 penalty (weight decay) on the weights; the bias is not penalised
+    fn l2(mut self, strength: f64) -> Self {
+        self.l2 = strength;
+        self
+    }
+
+    fn classify(&self, x: &Matrix) -> Matrix {
+        self.predict(x).map(|p| if p >= 0.5 { 1.0 } else { 0.0 })
+    }
+}
+
+impl Model for LogisticRegression {
     fn predict(&self, x: &Matrix) -> Matrix {
         x.multiply(&self.weights)
             .map(|v| sigmoid(v + self.bias))
     }
 
-    fn train(&mut self, x: &Matrix, y: &Matrix, epochs: usize) -> Vec<f64> {
+    fn train_batch(&mut self, x: &Matrix, y: &Matrix) -> f64 {
         let m = x.rows as f64;
-        let mut losses = Vec::new();
-
-        for epoch in 0..epochs {
-            let predictions = self.predict(x);
-            let errors = predictions.sub(y);
-
-            let loss = errors.hadamard(&errors).sum() / (2.0 * m);
-            losses.push(loss);
+        let predictions = self.predict(x);
+        let errors = predictions.sub(y);
 
-            if epoch % 100 == 0 {
-                println!("Epoch {}: Loss = {:.6}", epoch, loss);
-            }
-
-            let x_transpose = x.transpose();
-            let gradient = x_transpose.multiply(&errors).scale(1.0 / m);
-            let bias_gradient = errors.sum() / m;
+        let gradient = x
+            .transpose()
+            .multiply(&errors)
+            .scale(1.0 / m)
+            .add(&self.weights.scale(self.l2));
+        let bias_gradient = Matrix::from_vec(1, 1, vec![errors.sum() / m]);
 
-            self.weights = self.weights.sub(&gradient.scale(self.learning_rate));
-            self.bias -= self.learning_rate * bias_gradient;
-        }
+        self.weights = self.optimizer.step(0, &self.weights, &gradient);
+        let bias = Matrix::from_vec(1, 1, vec![self.bias]);
+        self.bias = self.optimizer.step(1, &bias, &bias_gradient).get(0, 0);
 
-        losses
+        mse_loss(&predictions, y)
     }
 
-    fn classify(&self, x: &Matrix) -> Matrix {
-        self.predict(x).map(|p| if p >= 0.5 { 1.0 } else { 0.0 })
+    fn optimizer_mut(&mut self) -> &mut dyn Optimizer {
+        self.optimizer.as_mut()
     }
 }
 
@@ -300,58 +853,53 @@ impl LogisticRegression {
 struct Layer {
     weights: Matrix,
     biases: Matrix,
-    activation: fn(f64) -> f64,
-    activation_derivative: fn(f64) -> f64,
+    activation: Activation,
 }
 
 impl Layer {
-    fn new(
-        input_size: usize,
-        output_size: usize,
-        activation: fn(f64) -> f64,
-        activation_derivative: fn(f64) -> f64,
-    ) -> Self {
+    fn new(input_size: usize, output_size: usize, activation: Activation, init: Init, rng: &mut Rng) -> Self {
         Layer {
-            weights: Matrix::random(input_size, output_size, 0.5),
+            weights: init.weights(input_size, output_size, rng),
             biases: Matrix::zeros(1, output_size),
             activation,
-            activation_derivative,
         }
     }
 
     fn forward(&self, input: &Matrix) -> (Matrix, Matrix) {
-        let z = input.multiply(&self.weights).add(&self.biases);
-        let a = z.map(self.activation);
+        let z = input.multiply(&self.weights).add_row(&self.biases);
+        let a = z.map(|v| self.activation.apply(v));
         (z, a)
     }
 }
 
 struct NeuralNetwork {
     layers: Vec<Layer>,
-    learning_rate: f64,
+    optimizer: Box<dyn Optimizer>,
+    // Draws initial weights for each added layer, so the same seed and
+    // architecture always produce the same network
+    rng: Rng,
+    l2: f64,
 }
 
 impl NeuralNetwork {
-    fn new(learning_rate: f64) -> Self {
+    fn new(optimizer: impl Optimizer + 'static, seed: u64) -> Self {
         NeuralNetwork {
             layers: Vec::new(),
-            learning_rate,
+            optimizer: Box::new(optimizer),
+            rng: Rng::new(seed),
+            l2: 0.0,
         }
     }
 
-    fn add_layer(
-        &mut self,
-        input_size: usize,
-        output_size: usize,
-        activation: fn(f64) -> f64,
-        activation_derivative: fn(f64) -> f64,
-    ) {
-        self.layers.push(Layer::new(
-            input_size,
-            output_size,
-            activation,
-            activation_derivative,
-        ));
+    // L2 penalty (weight decay) on every layer's weights; biases are not penalised
+    fn l2(mut self, strength: f64) -> Self {
+        self.l2 = strength;
+        self
+    }
+
+    fn add_layer(&mut self, input_size: usize, output_size: usize, activation: Activation, init: Init) {
+        self.layers
+            .push(Layer::new(input_size, output_size, activation, init, &mut self.rng));
     }
 
     fn forward(&self, input: &Matrix) -> Vec<(Matrix, Matrix)> {
@@ -380,84 +928,1488 @@ impl NeuralNetwork {
         let mut delta = last_activation.sub(y);
 
         for i in (0..num_layers).rev() {
-            let (z, a) = &layer_outputs[i];
-            
-            let activation_grad = z.map(self.layers[i].activation_derivative);
+            let z = &layer_outputs[i].0;
+
+            let activation = self.layers[i].activation;
+            let activation_grad = z.map(|v| activation.derivative(v));
             delta = delta.hadamard(&activation_grad);
 
             let prev_activation = if i == 0 {
-                x.clone()
+                x
             } else {
-                layer_outputs[i - 1].1.clone()
+                &layer_outputs[i - 1].1
             };
 
-            let weight_gradient = prev_activation.transpose().multiply(&delta).scale(1.0 / m);
-            let bias_gradient = Matrix::from_vec(
-                1,
-                delta.cols,
-                (0..delta.cols)
-                    .map(|j| {
-                        (0..delta.rows).map(|i| delta.get(i, j)).sum::<f64>() / m
-                    })
-                    .collect(),
-            );
-
-            self.layers[i].weights = self.layers[i]
-                .weights
-                .sub(&weight_gradient.scale(self.learning_rate));
-            self.layers[i].biases = self.layers[i]
-                .biases
-                .sub(&bias_gradient.scale(self.learning_rate));
-
-            if i > 0 {
-                delta = delta.multiply(&self.layers[i].weights.transpose());
-            }
-        }
-    }
-
-    fn train(&mut self, x: &Matrix, y: &Matrix, epochs: usize) -> Vec<f64> {
-        let mut losses = Vec::new();
+            let weight_gradient = prev_activation
+                .transpose()
+                .multiply(&delta)
+                .scale(1.0 / m)
+                .add(&self.layers[i].weights.scale(self.l2));
+            let bias_gradient = delta.sum_rows().scale(1.0 / m);
 
-        for epoch in 0..epochs {
-            let layer_outputs = self.forward(x);
-            let predictions = &layer_outputs[self.layers.len() - 1].1;
+            // Propagate through the weights used in the forward pass, before updating them
+            let next_delta = if i > 0 {
+                Some(delta.multiply(&self.layers[i].weights.transpose()))
+            } else {
+                None
+            };
 
-            let loss = predictions
-                .sub(y)
-                .hadamard(&predictions.sub(y))
-                .sum()
-                / (2.0 * x.rows as f64);
-            losses.push(loss);
+            let layer = &mut self.layers[i];
+            layer.weights = self.optimizer.step(2 * i, &layer.weights, &weight_gradient);
+            layer.biases = self.optimizer.step(2 * i + 1, &layer.biases, &bias_gradient);
 
-            if epoch % 100 == 0 {
-                println!("Epoch {}: Loss = {:.6}", epoch, loss);
+            if let Some(next_delta) = next_delta {
+                delta = next_delta;
             }
-
-            self.backward(x, y, &layer_outputs);
         }
-
-        losses
     }
+}
 
+impl Model for NeuralNetwork {
     fn predict(&self, x: &Matrix) -> Matrix {
         let layer_outputs = self.forward(x);
         layer_outputs[self.layers.len() - 1].1.clone()
     }
-}
 
-// ========== MAIN ==========
-fn main() {
-    println!("=== Machine Learning Library Demo ===\n");
+    fn train_batch(&mut self, x: &Matrix, y: &Matrix) -> f64 {
+        let layer_outputs = self.forward(x);
+        let loss = mse_loss(&layer_outputs[self.layers.len() - 1].1, y);
+        self.backward(x, y, &layer_outputs);
+        loss
+    }
 
-    // Example 1: Linear Regression
-    println!("=== Example 1: Linear Regression ===");
+    fn optimizer_mut(&mut self) -> &mut dyn Optimizer {
+        self.optimizer.as_mut()
+    }
+}
+
+// ========== MODEL PERSISTENCE ==========
+// Plain-text network format, one record per line:
+//   network <layer count>
+//   layer <inputs> <outputs> <activation>
+//   <weights, row-major, comma-separated>
+//   <biases, comma-separated>
+// An optional trailing "scaler" line is followed by Scaler::to_text output.
+impl NeuralNetwork {
+    fn to_text(&self) -> String {
+        let join = |values: &[f64]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(",");
+        let mut text = format!("network {}\n", self.layers.len());
+        for layer in &self.layers {
+            text.push_str(&format!(
+                "layer {} {} {}\n{}\n{}\n",
+                layer.weights.rows,
+                layer.weights.cols,
+                layer.activation.name(),
+                join(&layer.weights.data),
+                join(&layer.biases.data)
+            ));
+        }
+        text
+    }
+
+    // `optimizer` is only used if training continues after loading
+    fn from_text(text: &str, optimizer: impl Optimizer + 'static) -> Result<NeuralNetwork, String> {
+        let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line.trim()));
+        let mut next = |what: &str| lines.next().ok_or_else(|| format!("unexpected end of file, expected {}", what));
+
+        let (n, header) = next("network header")?;
+        let layer_count: usize = header
+            .strip_prefix("network ")
+            .and_then(|count| count.parse().ok())
+            .ok_or_else(|| format!("line {}: expected 'network <layers>'", n))?;
+
+        let mut network = NeuralNetwork::new(optimizer, 0);
+        for _ in 0..layer_count {
+            let (n, spec) = next("layer header")?;
+            let fields: Vec<&str> = spec.split_whitespace().collect();
+            let (inputs, outputs, activation) = match fields.as_slice() {
+                ["layer", inputs, outputs, activation] => (
+                    inputs.parse::<usize>().ok(),
+                    outputs.parse::<usize>().ok(),
+                    Activation::from_name(activation),
+                ),
+                _ => (None, None, None),
+            };
+            let (Some(inputs), Some(outputs), Some(activation)) = (inputs, outputs, activation) else {
+                return Err(format!("line {}: expected 'layer <inputs> <outputs> <activation>'", n));
+            };
+
+            let mut values = |count: usize| -> Result<Vec<f64>, String> {
+                let (n, line) = next("parameter values")?;
+                let values: Vec<f64> = line
+                    .split(',')
+                    .map(|v| v.trim().parse().map_err(|_| format!("line {}: '{}' is not a number", n, v)))
+                    .collect::<Result<_, _>>()?;
+                if values.len() != count {
+                    return Err(format!("line {}: expected {} values, found {}", n, count, values.len()));
+                }
+                Ok(values)
+            };
+            let weights = Matrix::from_vec(inputs, outputs, values(inputs * outputs)?);
+            let biases = Matrix::from_vec(1, outputs, values(outputs)?);
+
+            if let Some(previous) = network.layers.last() {
+                if previous.weights.cols != inputs {
+                    return Err(format!(
+                        "line {}: layer takes {} inputs but the previous layer has {} outputs",
+                        n, inputs, previous.weights.cols
+                    ));
+                }
+            }
+            network.layers.push(Layer {
+                weights,
+                biases,
+                activation,
+            });
+        }
+
+        if network.layers.is_empty() {
+            return Err("network has no layers".to_string());
+        }
+        Ok(network)
+    }
+
+    fn input_size(&self) -> usize {
+        self.layers.first().map_or(0, |layer| layer.weights.rows)
+    }
+}
+
+// A trained network plus the scaler its inputs were standardized with
+struct SavedModel {
+    network: NeuralNetwork,
+    scaler: Option<dataset::Scaler>,
+}
+
+impl SavedModel {
+    fn save(&self, path: &str) -> std::io::Result<()> {
+        let mut text = self.network.to_text();
+        if let Some(scaler) = &self.scaler {
+            text.push_str("scaler\n");
+            text.push_str(&scaler.to_text());
+        }
+        std::fs::write(path, text)
+    }
+
+    fn load(path: &str) -> Result<SavedModel, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        let (network_text, scaler_text) = match text.split_once("\nscaler\n") {
+            Some((network, scaler)) => (network, Some(scaler)),
+            None => (text.as_str(), None),
+        };
+
+        let network = NeuralNetwork::from_text(network_text, Sgd::new(0.0))?;
+        let scaler = scaler_text
+            .map(|text| dataset::Scaler::from_text(text).map_err(|e| format!("scaler: {}", e)))
+            .transpose()?;
+        if let Some(scaler) = &scaler {
+            if scaler.offsets.len() != network.input_size() {
+                return Err(format!(
+                    "scaler has {} columns but the network takes {} inputs",
+                    scaler.offsets.len(),
+                    network.input_size()
+                ));
+            }
+        }
+        Ok(SavedModel { network, scaler })
+    }
+
+    // Scales raw feature rows (if a scaler was saved) and runs the network
+    fn predict(&self, x: &Matrix) -> Matrix {
+        match &self.scaler {
+            Some(scaler) => self.network.predict(&scaler.transform(x)),
+            None => self.network.predict(x),
+        }
+    }
+}
+
+// ========== TRAINING CALLBACKS ==========
+#[derive(Debug, Clone, Copy)]
+enum Schedule {
+    // Multiply the rate by `factor` every `every` epochs
+    StepDecay { every: usize, factor: f64 },
+    // Multiply the rate by `gamma` after each epoch
+    Exponential { gamma: f64 },
+    // Half a cosine from the initial rate down to `min_rate` over `epochs`
+    Cosine { epochs: usize, min_rate: f64 },
+}
+
+impl Schedule {
+    fn rate(self, initial: f64, epoch: usize) -> f64 {
+        match self {
+            Schedule::StepDecay { every, factor } => initial * factor.powi((epoch / every.max(1)) as i32),
+            Schedule::Exponential { gamma } => initial * gamma.powi(epoch as i32),
+            Schedule::Cosine { epochs, min_rate } => {
+                let progress = (epoch as f64 / epochs.max(1) as f64).min(1.0);
+                min_rate + 0.5 * (initial - min_rate) * (1.0 + (std::f64::consts::PI * progress).cos())
+            }
+        }
+    }
+}
+
+// Sets the optimizer's rate for the next epoch; the rate the optimizer was
+// built with is the schedule's starting point
+struct LearningRateScheduler {
+    schedule: Schedule,
+    initial: Option<f64>,
+}
+
+impl LearningRateScheduler {
+    fn new(schedule: Schedule) -> Self {
+        LearningRateScheduler { schedule, initial: None }
+    }
+}
+
+impl<M: Model> Callback<M> for LearningRateScheduler {
+    fn on_epoch_end(&mut self, logs: &EpochLogs, model: &mut M) {
+        let optimizer = model.optimizer_mut();
+        let initial = *self.initial.get_or_insert(logs.learning_rate);
+        optimizer.set_learning_rate(self.schedule.rate(initial, logs.epoch + 1));
+    }
+}
+
+// Appends one CSV row per epoch, with batch-loss spread from on_batch_end
+struct CsvLogger<W: std::io::Write> {
+    out: W,
+    batches: usize,
+    min_batch_loss: f64,
+    max_batch_loss: f64,
+    error: Option<std::io::Error>,
+}
+
+impl<W: std::io::Write> CsvLogger<W> {
+    fn new(mut out: W) -> std::io::Result<Self> {
+        writeln!(
+            out,
+            "epoch,train_loss,validation_loss,learning_rate,batches,min_batch_loss,max_batch_loss"
+        )?;
+        Ok(CsvLogger {
+            out,
+            batches: 0,
+            min_batch_loss: f64::INFINITY,
+            max_batch_loss: f64::NEG_INFINITY,
+            error: None,
+        })
+    }
+
+    // The first write error, if any; logging stops after it
+    fn finish(mut self) -> std::io::Result<()> {
+        match self.error.take() {
+            Some(e) => Err(e),
+            None => self.out.flush(),
+        }
+    }
+}
+
+impl<M: Model, W: std::io::Write> Callback<M> for CsvLogger<W> {
+    fn on_batch_end(&mut self, _epoch: usize, _batch: usize, loss: f64, _model: &mut M) {
+        self.batches += 1;
+        self.min_batch_loss = self.min_batch_loss.min(loss);
+        self.max_batch_loss = self.max_batch_loss.max(loss);
+    }
+
+    fn on_epoch_end(&mut self, logs: &EpochLogs, _model: &mut M) {
+        if self.error.is_none() {
+            let validation = logs.validation_loss.map(|v| v.to_string()).unwrap_or_default();
+            if let Err(e) = writeln!(
+                self.out,
+                "{},{},{},{},{},{},{}",
+                logs.epoch,
+                logs.train_loss,
+                validation,
+                logs.learning_rate,
+                self.batches,
+                self.min_batch_loss,
+                self.max_batch_loss
+            ) {
+                self.error = Some(e);
+            }
+        }
+        self.batches = 0;
+        self.min_batch_loss = f64::INFINITY;
+        self.max_batch_loss = f64::NEG_INFINITY;
+    }
+}
+
+// Keeps the network with the lowest validation loss seen so far, in memory
+// and (optionally) on disk in the SavedModel format
+struct ModelCheckpoint {
+    path: Option<String>,
+    best_loss: f64,
+    best_epoch: Option<usize>,
+    best: Option<String>,
+}
+
+impl ModelCheckpoint {
+    fn new(path: Option<String>) -> Self {
+        ModelCheckpoint {
+            path,
+            best_loss: f64::INFINITY,
+            best_epoch: None,
+            best: None,
+        }
+    }
+
+    // The best network seen, ready for inference
+    fn restore(&self) -> Option<NeuralNetwork> {
+        self.best
+            .as_ref()
+            .and_then(|text| NeuralNetwork::from_text(text, Sgd::new(0.0)).ok())
+    }
+}
+
+impl Callback<NeuralNetwork> for ModelCheckpoint {
+    fn on_epoch_end(&mut self, logs: &EpochLogs, model: &mut NeuralNetwork) {
+        let Some(loss) = logs.validation_loss else {
+            return;
+        };
+        if loss >= self.best_loss {
+            return;
+        }
+        self.best_loss = loss;
+        self.best_epoch = Some(logs.epoch);
+        let text = model.to_text();
+        if let Some(path) = &self.path {
+            if let Err(e) = std::fs::write(path, &text) {
+                eprintln!("Checkpoint to {} failed: {}", path, e);
+            }
+        }
+        self.best = Some(text);
+    }
+}
+
+// ========== EVALUATION ==========
+// Shuffles rows and holds out `validation_fraction` of them.
+// Returns (x_train, y_train, x_val, y_val).
+fn train_validation_split(
+    x: &Matrix,
+    y: &Matrix,
+    validation_fraction: f64,
+    seed: u64,
+) -> (Matrix, Matrix, Matrix, Matrix) {
+    assert_eq!(x.rows, y.rows);
+    assert!((0.0..1.0).contains(&validation_fraction));
+
+    let mut order: Vec<usize> = (0..x.rows).collect();
+    Rng::new(seed).shuffle(&mut order);
+    let validation_rows = ((x.rows as f64 * validation_fraction).round() as usize).min(x.rows - 1);
+    let (val, train) = order.split_at(validation_rows);
+
+    (x.select_rows(train), y.select_rows(train), x.select_rows(val), y.select_rows(val))
+}
+
+// Binary confusion matrix; predictions >= threshold count as the positive class
+#[derive(Debug, Clone, Copy, Default)]
+struct ConfusionMatrix {
+    true_positives: usize,
+    false_positives: usize,
+    true_negatives: usize,
+    false_negatives: usize,
+}
+
+impl ConfusionMatrix {
+    fn from_predictions(predictions: &Matrix, actual: &Matrix, threshold: f64) -> Self {
+        assert_eq!(predictions.rows, actual.rows);
+        let mut matrix = ConfusionMatrix::default();
+        for i in 0..predictions.rows {
+            let predicted = predictions.get(i, 0) >= threshold;
+            let expected = actual.get(i, 0) >= 0.5;
+            match (predicted, expected) {
+                (true, true) => matrix.true_positives += 1,
+                (true, false) => matrix.false_positives += 1,
+                (false, false) => matrix.true_negatives += 1,
+                (false, true) => matrix.false_negatives += 1,
+            }
+        }
+        matrix
+    }
+
+    fn total(&self) -> usize {
+        self.true_positives + self.false_positives + self.true_negatives + self.false_negatives
+    }
+
+    fn accuracy(&self) -> f64 {
+        ratio(self.true_positives + self.true_negatives, self.total())
+    }
+
+    fn precision(&self) -> f64 {
+        ratio(self.true_positives, self.true_positives + self.false_positives)
+    }
+
+    fn recall(&self) -> f64 {
+        ratio(self.true_positives, self.true_positives + self.false_negatives)
+    }
+
+    fn f1(&self) -> f64 {
+        let (p, r) = (self.precision(), self.recall());
+        if p + r == 0.0 {
+            0.0
+        } else {
+            2.0 * p * r / (p + r)
+        }
+    }
+
+    fn print(&self) {
+        println!("                 predicted 0  predicted 1");
+        println!("    actual 0  {:>14} {:>12}", self.true_negatives, self.false_positives);
+        println!("    actual 1  {:>14} {:>12}", self.false_negatives, self.true_positives);
+        println!(
+            "  accuracy {:.3}  precision {:.3}  recall {:.3}  F1 {:.3}",
+            self.accuracy(),
+            self.precision(),
+            self.recall(),
+            self.f1()
+        );
+    }
+}
+
+// 0/0 is reported as 0 so metrics stay printable for degenerate splits
+fn ratio(numerator: usize, denominator: usize) -> f64 {
+    if denominator == 0 {
+        0.0
+    } else {
+        numerator as f64 / denominator as f64
+    }
+}
+
+// ========== DATASETS ==========
+mod dataset {
+    use super::{train_validation_split, Matrix};
+    use std::fmt;
+    use std::fs;
+
+    #[derive(Debug)]
+    pub enum DatasetError {
+        Io(String),
+        Parse { line: usize, column: usize, value: String },
+        RaggedRow { line: usize, expected: usize, found: usize },
+        UnknownColumn(String),
+        Empty,
+    }
+
+    impl fmt::Display for DatasetError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                DatasetError::Io(msg) => write!(f, "IO error: {}", msg),
+                DatasetError::Parse { line, column, value } => {
+                    write!(f, "line {}, column {}: '{}' is not a number", line, column + 1, value)
+                }
+                DatasetError::RaggedRow { line, expected, found } => {
+                    write!(f, "line {}: expected {} fields, found {}", line, expected, found)
+                }
+                DatasetError::UnknownColumn(name) => write!(f, "no column named '{}'", name),
+                DatasetError::Empty => write!(f, "no data rows"),
+            }
+        }
+    }
+
+    // Which column holds the target; every other column becomes a feature
+    pub enum LabelColumn {
+        Last,
+        Index(usize),
+        Name(String),
+    }
+
+    pub struct CsvOptions {
+        pub has_header: bool,
+        pub delimiter: char,
+        pub label: LabelColumn,
+    }
+
+    impl Default for CsvOptions {
+        fn default() -> Self {
+            CsvOptions {
+                has_header: true,
+                delimiter: ',',
+                label: LabelColumn::Last,
+            }
+        }
+    }
+
+    // Numeric feature matrix with a single label column
+    #[derive(Debug, Clone)]
+    pub struct Dataset {
+        pub feature_names: Vec<String>,
+        pub label_name: String,
+        pub features: Matrix,
+        pub labels: Matrix,
+    }
+
+    impl Dataset {
+        pub fn load(path: &str, options: &CsvOptions) -> Result<Dataset, DatasetError> {
+            let text = fs::read_to_string(path).map_err(|e| DatasetError::Io(format!("{}: {}", path, e)))?;
+            Self::from_csv_str(&text, options)
+        }
+
+        pub fn from_csv_str(text: &str, options: &CsvOptions) -> Result<Dataset, DatasetError> {
+            // Line numbers are 1-based and count blank lines, to match an editor
+            let mut lines = text
+                .lines()
+                .enumerate()
+                .map(|(i, line)| (i + 1, line.trim()))
+                .filter(|(_, line)| !line.is_empty())
+                .map(|(n, line)| (n, line.split(options.delimiter).map(str::trim).collect::<Vec<_>>()));
+
+            let first = lines.next().ok_or(DatasetError::Empty)?;
+            let width = first.1.len();
+            let (headers, mut rows): (Vec<String>, Vec<_>) = if options.has_header {
+                (first.1.iter().map(|h| h.to_string()).collect(), Vec::new())
+            } else {
+                ((0..width).map(|i| format!("column_{}", i + 1)).collect(), vec![first])
+            };
+            rows.extend(lines);
+            if rows.is_empty() {
+                return Err(DatasetError::Empty);
+            }
+
+            let label_index = match &options.label {
+                LabelColumn::Last => width - 1,
+                LabelColumn::Index(i) if *i < width => *i,
+                LabelColumn::Index(i) => return Err(DatasetError::UnknownColumn(format!("#{}", i))),
+                LabelColumn::Name(name) => headers
+                    .iter()
+                    .position(|h| h == name)
+                    .ok_or_else(|| DatasetError::UnknownColumn(name.clone()))?,
+            };
+
+            let mut features = Vec::with_capacity(rows.len() * (width - 1));
+            let mut labels = Vec::with_capacity(rows.len());
+            for (line, fields) in &rows {
+                if fields.len() != width {
+                    return Err(DatasetError::RaggedRow {
+                        line: *line,
+                        expected: width,
+                        found: fields.len(),
+                    });
+                }
+                for (column, field) in fields.iter().enumerate() {
+                    let value: f64 = field.parse().map_err(|_| DatasetError::Parse {
+                        line: *line,
+                        column,
+                        value: field.to_string(),
+                    })?;
+                    if column == label_index {
+                        labels.push(value);
+                    } else {
+                        features.push(value);
+                    }
+                }
+            }
+
+            let mut feature_names = headers;
+            let label_name = feature_names.remove(label_index);
+            Ok(Dataset {
+                feature_names,
+                label_name,
+                features: Matrix::from_vec(rows.len(), width - 1, features),
+                labels: Matrix::from_vec(rows.len(), 1, labels),
+            })
+        }
+
+        pub fn len(&self) -> usize {
+            self.features.rows
+        }
+
+        // Shuffled (train, test) split holding out `test_fraction` of the rows
+        pub fn train_test_split(&self, test_fraction: f64, seed: u64) -> (Dataset, Dataset) {
+            let (x_train, y_train, x_test, y_test) =
+                train_validation_split(&self.features, &self.labels, test_fraction, seed);
+            let part = |features, labels| Dataset {
+                feature_names: self.feature_names.clone(),
+                label_name: self.label_name.clone(),
+                features,
+                labels,
+            };
+            (part(x_train, y_train), part(x_test, y_test))
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Scaling {
+        // Zero mean, unit variance per column
+        Standard,
+        // Each column mapped onto [0, 1]
+        MinMax,
+    }
+
+    // Per-column affine transform, value' = (value - offset) / scale.
+    // Fit it on training data only, then reuse it for test data and at
+    // inference time so every input sees the same transform.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Scaler {
+        pub scaling: Scaling,
+        pub offsets: Vec<f64>,
+        pub scales: Vec<f64>,
+    }
+
+    impl Scaler {
+        pub fn fit(x: &Matrix, scaling: Scaling) -> Scaler {
+            let mut offsets = Vec::with_capacity(x.cols);
+            let mut scales = Vec::with_capacity(x.cols);
+            for j in 0..x.cols {
+                let column: Vec<f64> = (0..x.rows).map(|i| x.get(i, j)).collect();
+                let (offset, scale) = match scaling {
+                    Scaling::Standard => {
+                        let mean = column.iter().sum::<f64>() / column.len() as f64;
+                        let variance =
+                            column.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / column.len() as f64;
+                        (mean, variance.sqrt())
+                    }
+                    Scaling::MinMax => {
+                        let min = column.iter().cloned().fold(f64::INFINITY, f64::min);
+                        let max = column.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+                        (min, max - min)
+                    }
+                };
+                offsets.push(offset);
+                // Constant columns would divide by zero; leave them centred instead
+                scales.push(if scale > 0.0 { scale } else { 1.0 });
+            }
+            Scaler { scaling, offsets, scales }
+        }
+
+        pub fn transform(&self, x: &Matrix) -> Matrix {
+            assert_eq!(x.cols, self.offsets.len());
+            let mut result = x.clone();
+            for i in 0..x.rows {
+                for j in 0..x.cols {
+                    result.set(i, j, (x.get(i, j) - self.offsets[j]) / self.scales[j]);
+                }
+            }
+            result
+        }
+
+        // Three lines: scaling kind, then offsets and scales as comma-separated values
+        pub fn to_text(&self) -> String {
+            let join = |values: &[f64]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(",");
+            let kind = match self.scaling {
+                Scaling::Standard => "standard",
+                Scaling::MinMax => "minmax",
+            };
+            format!("{}\n{}\n{}\n", kind, join(&self.offsets), join(&self.scales))
+        }
+
+        pub fn from_text(text: &str) -> Result<Scaler, DatasetError> {
+            let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line.trim()));
+            let (_, kind) = lines.next().ok_or(DatasetError::Empty)?;
+            let scaling = match kind {
+                "standard" => Scaling::Standard,
+                "minmax" => Scaling::MinMax,
+                other => {
+                    return Err(DatasetError::Parse {
+                        line: 1,
+                        column: 0,
+                        value: other.to_string(),
+                    })
+                }
+            };
+            let mut parse_line = || -> Result<Vec<f64>, DatasetError> {
+                let (line, values) = lines.next().ok_or(DatasetError::Empty)?;
+                values
+                    .split(',')
+                    .enumerate()
+                    .map(|(column, v)| {
+                        v.parse().map_err(|_| DatasetError::Parse {
+                            line,
+                            column,
+                            value: v.to_string(),
+                        })
+                    })
+                    .collect()
+            };
+            let offsets = parse_line()?;
+            let scales = parse_line()?;
+            if offsets.len() != scales.len() {
+                return Err(DatasetError::RaggedRow {
+                    line: 3,
+                    expected: offsets.len(),
+                    found: scales.len(),
+                });
+            }
+            Ok(Scaler { scaling, offsets, scales })
+        }
+
+        pub fn save(&self, path: &str) -> Result<(), DatasetError> {
+            fs::write(path, self.to_text()).map_err(|e| DatasetError::Io(format!("{}: {}", path, e)))
+        }
+
+        pub fn load(path: &str) -> Result<Scaler, DatasetError> {
+            let text = fs::read_to_string(path).map_err(|e| DatasetError::Io(format!("{}: {}", path, e)))?;
+            Self::from_text(&text)
+        }
+    }
+}
+
+// ========== MODEL SELECTION ==========
+mod model_selection {
+    use super::dataset::{Scaler, Scaling};
+    use super::{mse_loss, ConfusionMatrix, Matrix, Model, Rng, Trainer};
+
+    // Mean and (population) standard deviation of one metric across folds
+    #[derive(Debug, Clone, Copy)]
+    pub struct Summary {
+        pub mean: f64,
+        pub std_dev: f64,
+    }
+
+    impl Summary {
+        fn of(values: &[f64]) -> Summary {
+            let mean = values.iter().sum::<f64>() / values.len() as f64;
+            let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64;
+            Summary {
+                mean,
+                std_dev: variance.sqrt(),
+            }
+        }
+    }
+
+    // Validation scores of each fold. Accuracy/F1 treat predictions >= 0.5 as
+    // class 1 and are only meaningful for 0/1 labels.
+    #[derive(Debug, Clone)]
+    pub struct CvReport {
+        pub losses: Vec<f64>,
+        pub accuracies: Vec<f64>,
+        pub f1_scores: Vec<f64>,
+    }
+
+    impl CvReport {
+        pub fn loss(&self) -> Summary {
+            Summary::of(&self.losses)
+        }
+
+        pub fn accuracy(&self) -> Summary {
+            Summary::of(&self.accuracies)
+        }
+
+        pub fn f1(&self) -> Summary {
+            Summary::of(&self.f1_scores)
+        }
+    }
+
+    // One combination of hyperparameters tried by grid search
+    #[derive(Debug, Clone, Copy)]
+    pub struct Hyperparameters {
+        pub learning_rate: f64,
+        pub hidden_size: usize,
+        pub l2: f64,
+    }
+
+    pub struct Grid {
+        pub learning_rates: Vec<f64>,
+        pub hidden_sizes: Vec<usize>,
+        pub l2_strengths: Vec<f64>,
+    }
+
+    impl Grid {
+        fn points(&self) -> Vec<Hyperparameters> {
+            let mut points = Vec::new();
+            for &learning_rate in &self.learning_rates {
+                for &hidden_size in &self.hidden_sizes {
+                    for &l2 in &self.l2_strengths {
+                        points.push(Hyperparameters {
+                            learning_rate,
+                            hidden_size,
+                            l2,
+                        });
+                    }
+                }
+            }
+            points
+        }
+    }
+
+    pub struct CrossValidation {
+        k: usize,
+        seed: u64,
+        scaling: Option<Scaling>,
+        trainer: Trainer,
+    }
+
+    impl CrossValidation {
+        // `trainer` is cloned for every fold; its logging is switched off
+        pub fn new(k: usize, trainer: Trainer) -> Self {
+            assert!(k >= 2, "cross-validation needs at least two folds");
+            CrossValidation {
+                k,
+                seed: 0,
+                scaling: None,
+                trainer: trainer.log_every(0),
+            }
+        }
+
+        // Seed for the assignment of rows to folds
+        pub fn seed(mut self, seed: u64) -> Self {
+            self.seed = seed;
+            self
+        }
+
+        // Fit a scaler on each fold's training rows and apply it to both
+        // sides, so validation rows never leak into the scaling parameters
+        pub fn scaling(mut self, scaling: Scaling) -> Self {
+            self.scaling = Some(scaling);
+            self
+        }
+
+        // (train rows, validation rows) for each fold; every row is
+        // validated exactly once
+        fn folds(&self, rows: usize) -> Vec<(Vec<usize>, Vec<usize>)> {
+            assert!(rows >= self.k, "fewer rows than folds");
+            let mut order: Vec<usize> = (0..rows).collect();
+            Rng::new(self.seed).shuffle(&mut order);
+
+            (0..self.k)
+                .map(|fold| {
+                    let start = fold * rows / self.k;
+                    let end = (fold + 1) * rows / self.k;
+                    let validation = order[start..end].to_vec();
+                    let train = order[..start].iter().chain(&order[end..]).cloned().collect();
+                    (train, validation)
+                })
+                .collect()
+        }
+
+        // Trains a fresh model from `model_builder` on each fold and scores it
+        // on the held-out rows
+        pub fn cross_validate<M: Model>(
+            &self,
+            mut model_builder: impl FnMut() -> M,
+            x: &Matrix,
+            y: &Matrix,
+        ) -> CvReport {
+            let mut report = CvReport {
+                losses: Vec::with_capacity(self.k),
+                accuracies: Vec::with_capacity(self.k),
+                f1_scores: Vec::with_capacity(self.k),
+            };
+
+            for (train, validation) in self.folds(x.rows) {
+                let (mut x_train, y_train) = (x.select_rows(&train), y.select_rows(&train));
+                let (mut x_val, y_val) = (x.select_rows(&validation), y.select_rows(&validation));
+                if let Some(scaling) = self.scaling {
+                    let scaler = Scaler::fit(&x_train, scaling);
+                    x_train = scaler.transform(&x_train);
+                    x_val = scaler.transform(&x_val);
+                }
+
+                let mut model = model_builder();
+                self.trainer.clone().fit(&mut model, &x_train, &y_train);
+
+                let predictions = model.predict(&x_val);
+                let confusion = ConfusionMatrix::from_predictions(&predictions, &y_val, 0.5);
+                report.losses.push(mse_loss(&predictions, &y_val));
+                report.accuracies.push(confusion.accuracy());
+                report.f1_scores.push(confusion.f1());
+            }
+
+            report
+        }
+
+        // Cross-validates every point of `grid`; results are sorted by mean
+        // validation loss, best first
+        pub fn grid_search<M: Model>(
+            &self,
+            grid: &Grid,
+            model_builder: impl Fn(&Hyperparameters) -> M,
+            x: &Matrix,
+            y: &Matrix,
+        ) -> Vec<(Hyperparameters, CvReport)> {
+            let mut results: Vec<_> = grid
+                .points()
+                .into_iter()
+                .map(|params| {
+                    let report = self.cross_validate(|| model_builder(&params), x, y);
+                    (params, report)
+                })
+                .collect();
+            results.sort_by(|a, b| a.1.loss().mean.total_cmp(&b.1.loss().mean));
+            results
+        }
+    }
+}
+
+// ========== INFERENCE SERVER ==========
+// Serves a SavedModel over HTTP/1.1, one thread per connection in the style
+// of web_framework.rs:
+//   POST /predict   {"features": [1.0, 2.0]} or {"features": [[1.0, 2.0], [3.0, 4.0]]}
+//   GET  /health
+//   GET  /metrics   request counts and latency percentiles
+mod server {
+    use super::{Matrix, SavedModel};
+    use std::collections::{HashMap, VecDeque};
+    use std::io::{self, BufRead, BufReader, Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Instant;
+
+    // Latency percentiles are computed over this many recent requests
+    const LATENCY_WINDOW: usize = 1024;
+    const MAX_BODY_BYTES: usize = 1 << 20;
+
+    struct Request {
+        method: String,
+        path: String,
+        body: String,
+    }
+
+    struct Response {
+        status: u16,
+        status_text: &'static str,
+        body: String,
+    }
+
+    impl Response {
+        fn json(body: String) -> Self {
+            Response {
+                status: 200,
+                status_text: "OK",
+                body,
+            }
+        }
+
+        fn error(status: u16, status_text: &'static str, message: &str) -> Self {
+            Response {
+                status,
+                status_text,
+                body: format!(r#"{{"error": "{}"}}"#, message.replace('\\', "\\\\").replace('"', "\\\"")),
+            }
+        }
+
+        fn to_bytes(&self) -> Vec<u8> {
+            format!(
+                "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                self.status,
+                self.status_text,
+                self.body.len(),
+                self.body
+            )
+            .into_bytes()
+        }
+    }
+
+    #[derive(Default)]
+    struct Metrics {
+        requests: u64,
+        errors: u64,
+        predictions: u64,
+        // Handling time of recent /predict calls, in microseconds
+        latencies_us: VecDeque<u64>,
+    }
+
+    impl Metrics {
+        fn record(&mut self, ok: bool, predictions: usize, latency_us: u64) {
+            self.requests += 1;
+            if !ok {
+                self.errors += 1;
+            }
+            self.predictions += predictions as u64;
+            if self.latencies_us.len() == LATENCY_WINDOW {
+                self.latencies_us.pop_front();
+            }
+            self.latencies_us.push_back(latency_us);
+        }
+
+        fn to_json(&self) -> String {
+            let mut sorted: Vec<u64> = self.latencies_us.iter().cloned().collect();
+            sorted.sort_unstable();
+            let percentile = |p: f64| {
+                if sorted.is_empty() {
+                    0
+                } else {
+                    sorted[((sorted.len() - 1) as f64 * p).round() as usize]
+                }
+            };
+            let mean = if sorted.is_empty() {
+                0.0
+            } else {
+                sorted.iter().sum::<u64>() as f64 / sorted.len() as f64
+            };
+            format!(
+                r#"{{"requests": {}, "errors": {}, "predictions": {}, "latency_us": {{"window": {}, "mean": {:.1}, "p50": {}, "p95": {}, "p99": {}, "max": {}}}}}"#,
+                self.requests,
+                self.errors,
+                self.predictions,
+                sorted.len(),
+                mean,
+                percentile(0.5),
+                percentile(0.95),
+                percentile(0.99),
+                sorted.last().cloned().unwrap_or(0)
+            )
+        }
+    }
+
+    struct State {
+        model: SavedModel,
+        metrics: Mutex<Metrics>,
+    }
+
+    pub fn serve(model: SavedModel, addr: &str) -> io::Result<()> {
+        let listener = TcpListener::bind(addr)?;
+        println!("Serving {}-input model on http://{}", model.network.input_size(), addr);
+        println!("  curl -X POST http://{}/predict -d '{{\"features\": [...]}}'", addr);
+
+        let state = Arc::new(State {
+            model,
+            metrics: Mutex::new(Metrics::default()),
+        });
+        for stream in listener.incoming() {
+            match stream {
+                Ok(mut stream) => {
+                    let state = state.clone();
+                    thread::spawn(move || handle_connection(&mut stream, &state));
+                }
+                Err(e) => eprintln!("Connection error: {}", e),
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_connection(stream: &mut TcpStream, state: &State) {
+        let response = match read_request(stream) {
+            Ok(request) => route(&request, state),
+            Err(e) => Response::error(400, "Bad Request", &e),
+        };
+        if let Err(e) = stream.write_all(&response.to_bytes()) {
+            eprintln!("Failed to send response: {}", e);
+        }
+    }
+
+    fn route(request: &Request, state: &State) -> Response {
+        match (request.method.as_str(), request.path.as_str()) {
+            ("POST", "/predict") => {
+                let started = Instant::now();
+                let result = predict(&request.body, &state.model);
+                let latency_us = started.elapsed().as_micros() as u64;
+
+                let mut metrics = state.metrics.lock().unwrap();
+                match result {
+                    Ok(predictions) => {
+                        metrics.record(true, predictions.len(), latency_us);
+                        let values: Vec<String> = predictions.iter().map(|p| p.to_string()).collect();
+                        Response::json(format!(
+                            r#"{{"predictions": [{}], "latency_us": {}}}"#,
+                            values.join(", "),
+                            latency_us
+                        ))
+                    }
+                    Err(e) => {
+                        metrics.record(false, 0, latency_us);
+                        Response::error(422, "Unprocessable Entity", &e)
+                    }
+                }
+            }
+            ("GET", "/health") => Response::json(r#"{"status": "ok"}"#.to_string()),
+            ("GET", "/metrics") => Response::json(state.metrics.lock().unwrap().to_json()),
+            (_, "/predict") | (_, "/health") | (_, "/metrics") => {
+                Response::error(405, "Method Not Allowed", "method not allowed")
+            }
+            _ => Response::error(404, "Not Found", "not found"),
+        }
+    }
+
+    // One prediction per feature row (first output of the network)
+    fn predict(body: &str, model: &SavedModel) -> Result<Vec<f64>, String> {
+        let rows = parse_features(body)?;
+        let width = model.network.input_size();
+        if let Some(row) = rows.iter().find(|row| row.len() != width) {
+            return Err(format!("expected {} features per row, got {}", width, row.len()));
+        }
+        let x = Matrix::from_vec(rows.len(), width, rows.concat());
+        let output = model.predict(&x);
+        Ok((0..output.rows).map(|i| output.get(i, 0)).collect())
+    }
+
+    // Extracts the "features" array from a JSON object: either a flat array
+    // of numbers (one row) or an array of such arrays (a batch)
+    fn parse_features(body: &str) -> Result<Vec<Vec<f64>>, String> {
+        let key = body.find("\"features\"").ok_or("missing \"features\" field")?;
+        let value = body[key + "\"features\"".len()..]
+            .trim_start()
+            .strip_prefix(':')
+            .ok_or("expected ':' after \"features\"")?
+            .trim_start();
+        if !value.starts_with('[') {
+            return Err("\"features\" must be an array".to_string());
+        }
+
+        let mut rows = Vec::new();
+        let mut current = String::new();
+        let mut depth = 0;
+        let mut nested = false;
+        for c in value.chars() {
+            match c {
+                '[' => {
+                    depth += 1;
+                    if depth > 2 {
+                        return Err("features nest at most two arrays deep".to_string());
+                    }
+                    if depth == 2 {
+                        if !current.trim().is_empty() {
+                            return Err("cannot mix numbers and arrays in \"features\"".to_string());
+                        }
+                        nested = true;
+                    }
+                }
+                ']' => {
+                    if depth == 2 || !nested {
+                        rows.push(parse_row(&current)?);
+                        current.clear();
+                    }
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                c if depth == 1 && nested => {
+                    if c != ',' && !c.is_whitespace() {
+                        return Err("cannot mix numbers and arrays in \"features\"".to_string());
+                    }
+                }
+                c => current.push(c),
+            }
+        }
+        if depth != 0 {
+            return Err("unterminated \"features\" array".to_string());
+        }
+        if rows.is_empty() {
+            return Err("\"features\" is empty".to_string());
+        }
+        Ok(rows)
+    }
+
+    fn parse_row(text: &str) -> Result<Vec<f64>, String> {
+        if text.trim().is_empty() {
+            return Err("empty feature row".to_string());
+        }
+        text.split(',')
+            .map(|v| {
+                let v = v.trim();
+                v.parse::<f64>()
+                    .ok()
+                    .filter(|x| x.is_finite())
+                    .ok_or_else(|| format!("'{}' is not a number", v))
+            })
+            .collect()
+    }
+
+    fn read_request(stream: &mut TcpStream) -> Result<Request, String> {
+        let mut reader = BufReader::new(stream.try_clone().map_err(|e| e.to_string())?);
+
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).map_err(|e| e.to_string())?;
+        let mut parts = request_line.split_whitespace();
+        let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+            return Err("invalid request line".to_string());
+        };
+        let path = target.split('?').next().unwrap_or(target).to_string();
+
+        let mut headers = HashMap::new();
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).map_err(|e| e.to_string())? == 0 || line.trim().is_empty() {
+                break;
+            }
+            if let Some((key, value)) = line.split_once(':') {
+                headers.insert(key.trim().to_lowercase(), value.trim().to_string());
+            }
+        }
+
+        let length = match headers.get("content-length") {
+            Some(value) => value.parse::<usize>().map_err(|_| "invalid Content-Length".to_string())?,
+            None => 0,
+        };
+        if length > MAX_BODY_BYTES {
+            return Err(format!("body larger than {} bytes", MAX_BODY_BYTES));
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).map_err(|e| e.to_string())?;
+
+        Ok(Request {
+            method: method.to_uppercase(),
+            path,
+            body: String::from_utf8_lossy(&body).into_owned(),
+        })
+    }
+}
+
+// ========== PLOT EXPORT ==========
+mod export {
+    use std::fmt::Write as _;
+    use std::io::{self, Write};
+
+    const COLORS: [&str; 4] = ["#1f77b4", "#ff7f0e", "#2ca02c", "#d62728"];
+    const WIDTH: f64 = 640.0;
+    const HEIGHT: f64 = 400.0;
+    const MARGIN: f64 = 50.0;
+
+    // Rectangular region of the input space to sample for decision boundaries
+    pub struct Bounds {
+        pub x_min: f64,
+        pub x_max: f64,
+        pub y_min: f64,
+        pub y_max: f64,
+    }
+
+    impl Bounds {
+        // Smallest box containing the points, padded by `padding` on every side
+        pub fn around(points: &[(f64, f64, f64)], padding: f64) -> Self {
+            let mut bounds = Bounds {
+                x_min: f64::INFINITY,
+                x_max: f64::NEG_INFINITY,
+                y_min: f64::INFINITY,
+                y_max: f64::NEG_INFINITY,
+            };
+            for &(x, y, _) in points {
+                bounds.x_min = bounds.x_min.min(x - padding);
+                bounds.x_max = bounds.x_max.max(x + padding);
+                bounds.y_min = bounds.y_min.min(y - padding);
+                bounds.y_max = bounds.y_max.max(y + padding);
+            }
+            bounds
+        }
+
+        fn grid(&self, resolution: usize) -> Vec<Vec<(f64, f64)>> {
+            let step = |min: f64, max: f64, i: usize| {
+                min + (max - min) * i as f64 / (resolution - 1).max(1) as f64
+            };
+            (0..resolution)
+                .map(|row| {
+                    let y = step(self.y_min, self.y_max, row);
+                    (0..resolution)
+                        .map(|col| (step(self.x_min, self.x_max, col), y))
+                        .collect()
+                })
+                .collect()
+        }
+    }
+
+    // Named loss series, one value per epoch, e.g. [("train", &losses)].
+    // Columns: epoch,<name>,... with '#' header lines gnuplot skips.
+    // Plot with: set datafile separator ","; plot "loss.csv" using 1:2 with lines
+    pub fn loss_curves_csv<W: Write>(out: &mut W, series: &[(&str, &[f64])]) -> io::Result<()> {
+        let names: Vec<&str> = series.iter().map(|(name, _)| *name).collect();
+        writeln!(out, "# epoch,{}", names.join(","))?;
+
+        let epochs = series.iter().map(|(_, values)| values.len()).max().unwrap_or(0);
+        for epoch in 0..epochs {
+            let row: Vec<String> = series
+                .iter()
+                .map(|(_, values)| values.get(epoch).map(|v| v.to_string()).unwrap_or_default())
+                .collect();
+            writeln!(out, "{},{}", epoch, row.join(","))?;
+        }
+        Ok(())
+    }
+
+    pub fn loss_curves_svg(title: &str, series: &[(&str, &[f64])]) -> String {
+        let epochs = series.iter().map(|(_, values)| values.len()).max().unwrap_or(0);
+        let max_loss = series
+            .iter()
+            .flat_map(|(_, values)| values.iter().cloned())
+            .filter(|v| v.is_finite())
+            .fold(0.0, f64::max);
+        let y_max = if max_loss > 0.0 { max_loss } else { 1.0 };
+
+        let plot_w = WIDTH - 2.0 * MARGIN;
+        let plot_h = HEIGHT - 2.0 * MARGIN;
+        let to_x = |epoch: usize| MARGIN + plot_w * epoch as f64 / (epochs.max(2) - 1) as f64;
+        let to_y = |loss: f64| HEIGHT - MARGIN - plot_h * loss.min(y_max) / y_max;
+
+        let mut svg = svg_header(title);
+        axes(&mut svg, &format!("epoch (0-{})", epochs.saturating_sub(1)), &format!("loss (max {:.4})", y_max));
+
+        for (i, (name, values)) in series.iter().enumerate() {
+            let color = COLORS[i % COLORS.len()];
+            let points: Vec<String> = values
+                .iter()
+                .enumerate()
+                .filter(|(_, v)| v.is_finite())
+                .map(|(epoch, &v)| format!("{:.1},{:.1}", to_x(epoch), to_y(v)))
+                .collect();
+            let _ = writeln!(
+                svg,
+                r#"  <polyline fill="none" stroke="{}" stroke-width="2" points="{}"/>"#,
+                color,
+                points.join(" ")
+            );
+            let legend_y = MARGIN + 16.0 * i as f64;
+            let _ = writeln!(
+                svg,
+                r#"  <text x="{:.0}" y="{:.0}" fill="{}" font-size="12" text-anchor="end">{}</text>"#,
+                WIDTH - MARGIN,
+                legend_y,
+                color,
+                escape(name)
+            );
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    // Grid of model outputs as "x,y,prediction" rows, one blank line between
+    // scanlines so gnuplot can draw it with `splot ... with pm3d`.
+    pub fn decision_boundary_csv<W: Write, F: Fn(f64, f64) -> f64>(
+        out: &mut W,
+        predict: F,
+        bounds: &Bounds,
+        resolution: usize,
+    ) -> io::Result<()> {
+        writeln!(out, "# x,y,prediction")?;
+        for row in bounds.grid(resolution) {
+            for (x, y) in row {
+                writeln!(out, "{},{},{}", x, y, predict(x, y))?;
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+
+    // Heatmap of predictions in [0, 1] with the labelled points drawn on top.
+    // Points are (x, y, label) with label 0.0 or 1.0.
+    pub fn decision_boundary_svg<F: Fn(f64, f64) -> f64>(
+        title: &str,
+        predict: F,
+        bounds: &Bounds,
+        resolution: usize,
+        points: &[(f64, f64, f64)],
+    ) -> String {
+        let plot_w = WIDTH - 2.0 * MARGIN;
+        let plot_h = HEIGHT - 2.0 * MARGIN;
+        let to_x = |x: f64| MARGIN + plot_w * (x - bounds.x_min) / (bounds.x_max - bounds.x_min);
+        let to_y = |y: f64| HEIGHT - MARGIN - plot_h * (y - bounds.y_min) / (bounds.y_max - bounds.y_min);
+        let cell_w = plot_w / resolution as f64;
+        let cell_h = plot_h / resolution as f64;
+
+        let mut svg = svg_header(title);
+        for (row, cells) in bounds.grid(resolution).into_iter().enumerate() {
+            for (col, (x, y)) in cells.into_iter().enumerate() {
+                let p = predict(x, y).clamp(0.0, 1.0);
+                // Blend from blue (class 0) to orange (class 1)
+                let r = (31.0 + (255.0 - 31.0) * p) as u8;
+                let g = (119.0 + (127.0 - 119.0) * p) as u8;
+                let b = (180.0 + (14.0 - 180.0) * p) as u8;
+                let _ = writeln!(
+                    svg,
+                    r#"  <rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="rgb({},{},{})" fill-opacity="0.6"/>"#,
+                    MARGIN + cell_w * col as f64,
+                    HEIGHT - MARGIN - cell_h * (row + 1) as f64,
+                    cell_w + 0.5,
+                    cell_h + 0.5,
+                    r,
+                    g,
+                    b
+                );
+            }
+        }
+        axes(&mut svg, "x1", "x2");
+
+        for &(x, y, label) in points {
+            let color = if label >= 0.5 { COLORS[1] } else { COLORS[0] };
+            let _ = writeln!(
+                svg,
+                r#"  <circle cx="{:.1}" cy="{:.1}" r="6" fill="{}" stroke="black"/>"#,
+                to_x(x),
+                to_y(y),
+                color
+            );
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    fn svg_header(title: &str) -> String {
+        format!(
+            concat!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+                "\n",
+                r#"  <rect width="100%" height="100%" fill="white"/>"#,
+                "\n",
+                r#"  <text x="{cx}" y="24" font-size="16" text-anchor="middle">{title}</text>"#,
+                "\n"
+            ),
+            w = WIDTH,
+            h = HEIGHT,
+            cx = WIDTH / 2.0,
+            title = escape(title)
+        )
+    }
+
+    fn axes(svg: &mut String, x_label: &str, y_label: &str) {
+        let _ = writeln!(
+            svg,
+            r#"  <path d="M{m},{top} V{bottom} H{right}" fill="none" stroke="black"/>"#,
+            m = MARGIN,
+            top = MARGIN,
+            bottom = HEIGHT - MARGIN,
+            right = WIDTH - MARGIN
+        );
+        let _ = writeln!(
+            svg,
+            r#"  <text x="{:.0}" y="{:.0}" font-size="12" text-anchor="middle">{}</text>"#,
+            WIDTH / 2.0,
+            HEIGHT - MARGIN / 2.0,
+            escape(x_label)
+        );
+        let _ = writeln!(
+            svg,
+            r#"  <text x="14" y="{:.0}" font-size="12" text-anchor="middle" transform="rotate(-90 14 {:.0})">{}</text>"#,
+            HEIGHT / 2.0,
+            HEIGHT / 2.0,
+            escape(y_label)
+        );
+    }
+
+    fn escape(text: &str) -> String {
+        text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+    }
+}
+
+// ========== MAIN ==========
+// Seeds weight initialization for every demo model; change it to see how
+// much results depend on the starting point
+const SEED: u64 = 2024;
+
+// Default data for the CSV example: three features on very different scales
+// and a 0/1 label in the last column
+const EXAM_RESULTS_CSV: &str = include_str!("fixtures/exam_results.csv");
+
+fn main() {
+    // Usage: machine-learning serve MODEL_FILE [ADDR]
+    if std::env::args().nth(1).as_deref() == Some("serve") {
+        let Some(path) = std::env::args().nth(2) else {
+            eprintln!("usage: machine-learning serve MODEL_FILE [ADDR]");
+            std::process::exit(2);
+        };
+        let addr = std::env::args().nth(3).unwrap_or_else(|| "127.0.0.1:8090".to_string());
+        let model = SavedModel::load(&path).unwrap_or_else(|e| {
+            eprintln!("Failed to load model: {}", e);
+            std::process::exit(1);
+        });
+        if let Err(e) = server::serve(model, &addr) {
+            eprintln!("Server error: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    println!("=== Machine Learning Library Demo ===\n");
+    println!("Seed: {}\n", SEED);
+
+    // Usage: machine-learning [OUT_DIR] [DATA_CSV] [LABEL_COLUMN_NAME_OR_INDEX]
+    let out_dir = std::env::args().nth(1).unwrap_or_else(|| "ml-plots".to_string());
+
+    // Example 1: Linear Regression
+    println!("=== Example 1: Linear Regression ===");
     println!("Training a model to fit y = 2x + 3\n");
 
     let x_train = Matrix::from_vec(5, 1, vec![1.0, 2.0, 3.0, 4.0, 5.0]);
     let y_train = Matrix::from_vec(5, 1, vec![5.0, 7.0, 9.0, 11.0, 13.0]);
 
-    let mut linear_model = LinearRegression::new(1, 0.01);
-    linear_model.train(&x_train, &y_train, 1000);
+    let mut linear_model = LinearRegression::new(1, Sgd::new(0.005).momentum(0.9), SEED);
+    println!("Optimizer: {}, batch size 2", linear_model.optimizer.name());
+    let linear_history = Trainer::new(1000).batch_size(2).fit(&mut linear_model, &x_train, &y_train);
 
     let x_test = Matrix::from_vec(3, 1, vec![6.0, 7.0, 8.0]);
     let predictions = linear_model.predict(&x_test);
@@ -486,8 +2438,9 @@ fn main() {
     );
     let y_class = Matrix::from_vec(6, 1, vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0]);
 
-    let mut logistic_model = LogisticRegression::new(2, 0.1);
-    logistic_model.train(&x_class, &y_class, 1000);
+    let mut logistic_model = LogisticRegression::new(2, Adam::new(0.05), SEED);
+    println!("Optimizer: {}, batch size 3", logistic_model.optimizer.name());
+    let logistic_history = Trainer::new(1000).batch_size(3).fit(&mut logistic_model, &x_class, &y_class);
 
     let classifications = logistic_model.classify(&x_class);
     println!("\nClassifications:");
@@ -500,6 +2453,8 @@ fn main() {
             y_class.get(i, 0) as i32
         );
     }
+    println!("\nConfusion matrix (training set):");
+    ConfusionMatrix::from_predictions(&logistic_model.predict(&x_class), &y_class, 0.5).print();
 
     // Example 3: Neural Network (XOR problem)
     println!("\n\n=== Example 3: Neural Network (XOR Problem) ===");
@@ -508,11 +2463,12 @@ fn main() {
     let x_xor = Matrix::from_vec(4, 2, vec![0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 1.0, 1.0]);
     let y_xor = Matrix::from_vec(4, 1, vec![0.0, 1.0, 1.0, 0.0]);
 
-    let mut nn = NeuralNetwork::new(0.5);
-    nn.add_layer(2, 4, tanh, tanh_derivative);
-    nn.add_layer(4, 1, sigmoid, sigmoid_derivative);
+    let mut nn = NeuralNetwork::new(Adam::new(0.05), SEED);
+    nn.add_layer(2, 4, Activation::Tanh, Init::Xavier);
+    nn.add_layer(4, 1, Activation::Sigmoid, Init::Xavier);
 
-    nn.train(&x_xor, &y_xor, 2000);
+    println!("Optimizer: {}, full batch", nn.optimizer.name());
+    let xor_history = Trainer::new(2000).fit(&mut nn, &x_xor, &y_xor);
 
     let nn_predictions = nn.predict(&x_xor);
     println!("\nXOR Predictions:");
@@ -536,12 +2492,13 @@ fn main() {
     let x_square = Matrix::from_vec(10, 1, vec![0.0, 0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9]);
     let y_square = x_square.map(|x| x * x);
 
-    let mut nn_reg = NeuralNetwork::new(0.3);
-    nn_reg.add_layer(1, 8, relu, relu_derivative);
-    nn_reg.add_layer(8, 8, relu, relu_derivative);
-    nn_reg.add_layer(8, 1, |x| x, |_| 1.0);
+    let mut nn_reg = NeuralNetwork::new(RmsProp::new(0.005), SEED);
+    nn_reg.add_layer(1, 8, Activation::Relu, Init::He);
+    nn_reg.add_layer(8, 8, Activation::Relu, Init::He);
+    nn_reg.add_layer(8, 1, Activation::Identity, Init::Xavier);
 
-    nn_reg.train(&x_square, &y_square, 1000);
+    println!("Optimizer: {}, batch size 4", nn_reg.optimizer.name());
+    let reg_history = Trainer::new(1000).batch_size(4).seed(7).fit(&mut nn_reg, &x_square, &y_square);
 
     let reg_predictions = nn_reg.predict(&x_square);
     println!("\nRegression Predictions:");
@@ -554,12 +2511,362 @@ fn main() {
         );
     }
 
+    // Example 5: Validation split and early stopping
+    println!("\n\n=== Example 5: Validation and Early Stopping ===");
+    println!("Noisy linearly separable points, 25% held out, patience 25\n");
+
+    let (x_blobs, y_blobs) = noisy_blobs(200, 0.15, 3);
+    let (x_fit, y_fit, x_val, y_val) = train_validation_split(&x_blobs, &y_blobs, 0.25, 11);
+    println!("{} training rows, {} validation rows", x_fit.rows, x_val.rows);
+
+    let mut blob_model = LogisticRegression::new(2, Adam::new(0.05), SEED);
+    let blob_history = Trainer::new(2000)
+        .batch_size(16)
+        .validation(&x_val, &y_val)
+        .early_stopping(25, 1e-5)
+        .fit(&mut blob_model, &x_fit, &y_fit);
+    println!(
+        "Trained {} epochs (stopped early: {})",
+        blob_history.train_loss.len(),
+        blob_history.stopped_early
+    );
+
+    println!("\nConfusion matrix (validation set):");
+    ConfusionMatrix::from_predictions(&blob_model.predict(&x_val), &y_val, 0.5).print();
+
+    // Example 6: CSV dataset with feature scaling
+    println!("\n\n=== Example 6: CSV Dataset with Feature Scaling ===");
+    let data_path = std::env::args().nth(2);
+    let mut csv_options = dataset::CsvOptions::default();
+    if let Some(label) = std::env::args().nth(3) {
+        csv_options.label = match label.parse() {
+            Ok(index) => dataset::LabelColumn::Index(index),
+            Err(_) => dataset::LabelColumn::Name(label),
+        };
+    }
+    let loaded = match &data_path {
+        Some(path) => dataset::Dataset::load(path, &csv_options),
+        None => dataset::Dataset::from_csv_str(EXAM_RESULTS_CSV, &csv_options),
+    };
+    match loaded {
+        Ok(data) => {
+            scaled_dataset_demo(&data, &out_dir);
+            model_selection_demo(&data, &out_dir);
+        }
+        Err(e) => println!("Failed to load {}: {}", data_path.as_deref().unwrap_or("fixture"), e),
+    }
+
+    // Example 8: Learning-rate schedules and callbacks, on Example 5's split
+    schedules_demo(&x_fit, &y_fit, &x_val, &y_val, &out_dir);
+
+    // Export plots
+    println!("\n\n=== Exporting Plots to {}/ ===", out_dir);
+    if let Err(e) = export_plots(
+        &out_dir,
+        &[
+            ("linear", &linear_history),
+            ("logistic", &logistic_history),
+            ("xor", &xor_history),
+            ("square", &reg_history),
+            ("blobs", &blob_history),
+        ],
+        &logistic_model,
+        &x_class,
+        &y_class,
+        &nn,
+        &x_xor,
+        &y_xor,
+    ) {
+        println!("  Failed to export plots: {}", e);
+    }
+
     println!("\n✓ Machine Learning demonstrations complete!");
     println!("\nKey features demonstrated:");
     println!("  • Custom matrix operations with proper bounds checking");
     println!("  • Linear regression with gradient descent");
+    println!("  • Shuffled mini-batches with SGD+momentum, RMSProp, and Adam optimizers");
     println!("  • Logistic regression for binary classification");
     println!("  • Multi-layer neural network with backpropagation");
     println!("  • Multiple activation functions (sigmoid, tanh, ReLU)");
+    println!("  • Seeded Xavier/He weight initialization for reproducible runs");
     println!("  • XOR problem solved with hidden layers");
+    println!("  • Train/validation split with early stopping on validation loss");
+    println!("  • Confusion matrix with accuracy, precision, recall, and F1");
+    println!("  • CSV datasets with standard/min-max scaling and saved scaler parameters");
+    println!("  • k-fold cross-validation and grid search with L2 regularization");
+    println!("  • Saved networks served over HTTP with latency metrics (`serve` mode)");
+    println!("  • Step/exponential/cosine learning-rate schedules via training callbacks");
+    println!("  • CSV training history and best-model checkpoints by validation loss");
+    println!("  • Loss curves and decision boundaries exported as CSV/SVG");
+}
+
+// Trains logistic regression on raw and on standardized features and compares
+// test-set metrics. The scaler is fit on the training split only and saved to
+// `out_dir` so the same transform can be applied at inference time.
+fn scaled_dataset_demo(data: &dataset::Dataset, out_dir: &str) {
+    println!(
+        "{} rows, features [{}], label '{}'",
+        data.len(),
+        data.feature_names.join(", "),
+        data.label_name
+    );
+    let (train, test) = data.train_test_split(0.2, SEED);
+    println!("{} training rows, {} test rows\n", train.len(), test.len());
+
+    let scaler = dataset::Scaler::fit(&train.features, dataset::Scaling::Standard);
+    for (j, name) in train.feature_names.iter().enumerate() {
+        println!("  {:<16} mean {:>8.3}  std {:>8.3}", name, scaler.offsets[j], scaler.scales[j]);
+    }
+
+    let scaler_path = format!("{}/scaler.txt", out_dir);
+    let saved = std::fs::create_dir_all(out_dir)
+        .map_err(|e| dataset::DatasetError::Io(e.to_string()))
+        .and_then(|_| scaler.save(&scaler_path))
+        .and_then(|_| dataset::Scaler::load(&scaler_path));
+    let scaler = match saved {
+        Ok(reloaded) => {
+            println!("  Saved scaler parameters to {}", scaler_path);
+            reloaded
+        }
+        Err(e) => {
+            println!("  Could not save scaler ({}); using it in memory", e);
+            scaler
+        }
+    };
+
+    let features = train.features.cols;
+    for (title, x_train, x_test) in [
+        ("raw features", train.features.clone(), test.features.clone()),
+        ("standardized features", scaler.transform(&train.features), scaler.transform(&test.features)),
+    ] {
+        println!("\nLogistic regression on {}:", title);
+        let mut model = LogisticRegression::new(features, Sgd::new(0.1), SEED);
+        Trainer::new(300).batch_size(8).fit(&mut model, &x_train, &train.labels);
+        ConfusionMatrix::from_predictions(&model.predict(&x_test), &test.labels, 0.5).print();
+    }
+}
+
+// Trains the same network on Example 5's data under three learning-rate
+// schedules, logging history to CSV and checkpointing the best epoch
+fn schedules_demo(x_fit: &Matrix, y_fit: &Matrix, x_val: &Matrix, y_val: &Matrix, out_dir: &str) {
+    println!("\n\n=== Example 8: Learning-Rate Schedules and Callbacks ===");
+    let epochs = 150;
+    let schedules = [
+        ("step", Schedule::StepDecay { every: 50, factor: 0.5 }),
+        ("exponential", Schedule::Exponential { gamma: 0.98 }),
+        ("cosine", Schedule::Cosine { epochs, min_rate: 0.001 }),
+    ];
+    if let Err(e) = std::fs::create_dir_all(out_dir) {
+        println!("Cannot create {}: {}", out_dir, e);
+        return;
+    }
+
+    for (name, schedule) in schedules {
+        let mut nn = NeuralNetwork::new(Adam::new(0.05), SEED);
+        nn.add_layer(2, 8, Activation::Tanh, Init::Xavier);
+        nn.add_layer(8, 1, Activation::Sigmoid, Init::Xavier);
+
+        let history_path = format!("{}/history_{}.csv", out_dir, name);
+        let checkpoint_path = format!("{}/best_{}.txt", out_dir, name);
+        let logger = std::fs::File::create(&history_path).and_then(|f| CsvLogger::new(std::io::BufWriter::new(f)));
+        let mut logger = match logger {
+            Ok(logger) => logger,
+            Err(e) => {
+                println!("Cannot write {}: {}", history_path, e);
+                continue;
+            }
+        };
+        let mut scheduler = LearningRateScheduler::new(schedule);
+        let mut checkpoint = ModelCheckpoint::new(Some(checkpoint_path.clone()));
+
+        let history = Trainer::new(epochs)
+            .batch_size(16)
+            .validation(x_val, y_val)
+            .log_every(0)
+            .fit_with_callbacks(&mut nn, x_fit, y_fit, &mut [&mut scheduler, &mut logger, &mut checkpoint]);
+        if let Err(e) = logger.finish() {
+            println!("Writing {} failed: {}", history_path, e);
+        }
+
+        let last = history.train_loss.len() - 1;
+        print!(
+            "{:<12} final lr {:.5}  last val loss {:.4}",
+            name,
+            nn.optimizer_mut().learning_rate(),
+            history.validation_loss[last]
+        );
+        match (checkpoint.restore(), checkpoint.best_epoch) {
+            (Some(best), Some(epoch)) => {
+                let accuracy = ConfusionMatrix::from_predictions(&best.predict(x_val), y_val, 0.5).accuracy();
+                println!("  best {:.4} at epoch {} (accuracy {:.3})", checkpoint.best_loss, epoch, accuracy);
+            }
+            _ => println!("  no checkpoint"),
+        }
+    }
+    println!("\nHistories in {0}/history_*.csv, best networks in {0}/best_*.txt", out_dir);
+}
+
+fn build_network(features: usize, params: &model_selection::Hyperparameters) -> NeuralNetwork {
+    let mut nn = NeuralNetwork::new(Adam::new(params.learning_rate), SEED).l2(params.l2);
+    nn.add_layer(features, params.hidden_size, Activation::Tanh, Init::Xavier);
+    nn.add_layer(params.hidden_size, 1, Activation::Sigmoid, Init::Xavier);
+    nn
+}
+
+// 5-fold cross-validation of a logistic baseline, then a grid search over
+// one-hidden-layer networks. The winner is retrained on every row and saved
+// to `out_dir` for `machine-learning serve`.
+fn model_selection_demo(data: &dataset::Dataset, out_dir: &str) {
+    use model_selection::{CrossValidation, Grid, Summary};
+
+    println!("\n\n=== Example 7: Cross-Validation and Grid Search ===");
+    let cv = CrossValidation::new(5, Trainer::new(200).batch_size(8))
+        .seed(SEED)
+        .scaling(dataset::Scaling::Standard);
+    let features = data.features.cols;
+    let show = |s: Summary| format!("{:.3} ± {:.3}", s.mean, s.std_dev);
+
+    let baseline = cv.cross_validate(
+        || LogisticRegression::new(features, Sgd::new(0.1), SEED).l2(0.01),
+        &data.features,
+        &data.labels,
+    );
+    println!(
+        "Logistic regression (l2 0.01), 5 folds: loss {}  accuracy {}  F1 {}",
+        show(baseline.loss()),
+        show(baseline.accuracy()),
+        show(baseline.f1())
+    );
+
+    let grid = Grid {
+        learning_rates: vec![0.01, 0.05],
+        hidden_sizes: vec![2, 8],
+        l2_strengths: vec![0.0, 0.01],
+    };
+    let results = cv.grid_search(&grid, |params| build_network(features, params), &data.features, &data.labels);
+
+    println!("\nGrid search over one-hidden-layer networks (best first):");
+    println!("  {:>6} {:>6} {:>6}  {:<15}  {:<15}", "lr", "hidden", "l2", "val loss", "accuracy");
+    for (params, report) in &results {
+        println!(
+            "  {:>6} {:>6} {:>6}  {:<15}  {:<15}",
+            params.learning_rate,
+            params.hidden_size,
+            params.l2,
+            show(report.loss()),
+            show(report.accuracy())
+        );
+    }
+
+    let best = results[0].0;
+    let scaler = dataset::Scaler::fit(&data.features, dataset::Scaling::Standard);
+    let mut network = build_network(features, &best);
+    Trainer::new(200)
+        .batch_size(8)
+        .log_every(0)
+        .fit(&mut network, &scaler.transform(&data.features), &data.labels);
+
+    let model_path = format!("{}/model.txt", out_dir);
+    let model = SavedModel {
+        network,
+        scaler: Some(scaler),
+    };
+    let saved = std::fs::create_dir_all(out_dir).and_then(|_| model.save(&model_path));
+    match saved.map_err(|e| e.to_string()).and_then(|_| SavedModel::load(&model_path)) {
+        Ok(reloaded) => {
+            let row = data.features.select_rows(&[0]);
+            println!(
+                "\nBest network retrained on all rows and saved to {} (row 1: {:.4} before save, {:.4} after reload)",
+                model_path,
+                model.predict(&row).get(0, 0),
+                reloaded.predict(&row).get(0, 0)
+            );
+            println!("  Serve it with: machine-learning serve {}", model_path);
+        }
+        Err(e) => println!("\nFailed to save model: {}", e),
+    }
+}
+
+// Two classes in the unit square split by x1 + x2 = 1, with each point's
+// label flipped with probability `noise`
+fn noisy_blobs(n: usize, noise: f64, seed: u64) -> (Matrix, Matrix) {
+    let mut rng = Rng::new(seed);
+    let mut features = Vec::with_capacity(n * 2);
+    let mut labels = Vec::with_capacity(n);
+    for _ in 0..n {
+        let (a, b) = (rng.next_f64(), rng.next_f64());
+        let mut label = if a + b > 1.0 { 1.0 } else { 0.0 };
+        if rng.next_f64() < noise {
+            label = 1.0 - label;
+        }
+        features.extend([a, b]);
+        labels.push(label);
+    }
+    (Matrix::from_vec(n, 2, features), Matrix::from_vec(n, 1, labels))
+}
+
+fn labelled_points(x: &Matrix, y: &Matrix) -> Vec<(f64, f64, f64)> {
+    (0..x.rows).map(|i| (x.get(i, 0), x.get(i, 1), y.get(i, 0))).collect()
+}
+
+#[allow(clippy::too_many_arguments)]
+fn export_plots(
+    dir: &str,
+    histories: &[(&str, &History)],
+    logistic_model: &LogisticRegression,
+    x_class: &Matrix,
+    y_class: &Matrix,
+    nn: &NeuralNetwork,
+    x_xor: &Matrix,
+    y_xor: &Matrix,
+) -> std::io::Result<()> {
+    use std::fs::{self, File};
+    use std::io::BufWriter;
+
+    fs::create_dir_all(dir)?;
+    let path = |name: &str| format!("{}/{}", dir, name);
+
+    for (name, history) in histories {
+        let mut series = vec![("train", history.train_loss.as_slice())];
+        if !history.validation_loss.is_empty() {
+            series.push(("validation", history.validation_loss.as_slice()));
+        }
+        export::loss_curves_csv(&mut BufWriter::new(File::create(path(&format!("{}_loss.csv", name)))?), &series)?;
+        fs::write(
+            path(&format!("{}_loss.svg", name)),
+            export::loss_curves_svg(&format!("{} loss", name), &series),
+        )?;
+    }
+
+    let logistic_predict = |a: f64, b: f64| logistic_model.predict(&Matrix::from_vec(1, 2, vec![a, b])).get(0, 0);
+    let class_points = labelled_points(x_class, y_class);
+    let class_bounds = export::Bounds::around(&class_points, 1.0);
+    export::decision_boundary_csv(
+        &mut BufWriter::new(File::create(path("logistic_boundary.csv"))?),
+        logistic_predict,
+        &class_bounds,
+        50,
+    )?;
+    fs::write(
+        path("logistic_boundary.svg"),
+        export::decision_boundary_svg("Logistic regression", logistic_predict, &class_bounds, 50, &class_points),
+    )?;
+
+    let xor_predict = |a: f64, b: f64| nn.predict(&Matrix::from_vec(1, 2, vec![a, b])).get(0, 0);
+    let xor_points = labelled_points(x_xor, y_xor);
+    let xor_bounds = export::Bounds::around(&xor_points, 0.25);
+    export::decision_boundary_csv(
+        &mut BufWriter::new(File::create(path("xor_boundary.csv"))?),
+        xor_predict,
+        &xor_bounds,
+        50,
+    )?;
+    fs::write(
+        path("xor_boundary.svg"),
+        export::decision_boundary_svg("XOR network", xor_predict, &xor_bounds, 50, &xor_points),
+    )?;
+
+    println!("  Wrote loss curves (*_loss.csv/svg) and decision boundaries (*_boundary.csv/svg)");
+    println!("  gnuplot: set datafile separator \",\"; plot \"{}\" using 1:2 with lines", path("xor_loss.csv"));
+    Ok(())
 }
@@ -1,13 +1,50 @@
 // WebSocket Protocol Implementation (RFC 6455) with Chat Demo
 // Implements full WebSocket handshake, frame parsing, and bidirectional communication
-
-use std::collections::HashMap;
+// Built with the `tls` feature (tokio-rustls, rustls-pemfile), `--cert PATH
+// --key PATH` also serves wss:// on 127.0.0.1:8443
+
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
-use tokio::sync::{mpsc, RwLock};
+use tokio::sync::{mpsc, Notify, RwLock};
 
 // ========== WEBSOCKET FRAME ==========
+/// Largest single frame payload accepted from a peer
+const MAX_FRAME_SIZE: usize = 64 * 1024;
+/// Largest message, summed over its fragments
+const MAX_MESSAGE_SIZE: usize = 1024 * 1024;
+
+// Close codes from RFC 6455 section 7.4.1
+const CLOSE_GOING_AWAY: u16 = 1001;
+const CLOSE_PROTOCOL_ERROR: u16 = 1002;
+const CLOSE_INVALID_PAYLOAD: u16 = 1007;
+const CLOSE_TOO_BIG: u16 = 1009;
+
+/// A peer misbehaved; the connection is failed with `code`
+#[derive(Debug, PartialEq)]
+struct ProtocolError {
+    code: u16,
+    reason: &'static str,
+}
+
+impl ProtocolError {
+    fn new(code: u16, reason: &'static str) -> Self {
+        ProtocolError { code, reason }
+    }
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (close code {})", self.reason, self.code)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum OpCode {
     Continuation = 0x0,
@@ -19,6 +56,10 @@ enum OpCode {
 }
 
 impl OpCode {
+    fn is_control(self) -> bool {
+        matches!(self, OpCode::Close | OpCode::Ping | OpCode::Pong)
+    }
+
     fn from_u8(byte: u8) -> Option<Self> {
         match byte {
             0x0 => Some(OpCode::Continuation),
@@ -32,7 +73,7 @@ impl OpCode {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct WebSocketFrame {
     fin: bool,
     opcode: OpCode,
@@ -50,17 +91,23 @@ impl WebSocketFrame {
         }
     }
 
-    fn parse(data: &[u8]) -> Result<(Self, usize), String> {
+    /// Parses the frame at the start of `data` and how many bytes it took,
+    /// or `None` if the rest of it hasn't arrived yet. Payloads over
+    /// `max_payload` are rejected from the header alone, before buffering.
+    fn parse(data: &[u8], max_payload: usize) -> Result<Option<(Self, usize)>, ProtocolError> {
         if data.len() < 2 {
-            return Err("Frame too short".to_string());
+            return Ok(None);
         }
 
         let byte1 = data[0];
         let byte2 = data[1];
 
         let fin = (byte1 & 0x80) != 0;
+        if byte1 & 0x70 != 0 {
+            return Err(ProtocolError::new(CLOSE_PROTOCOL_ERROR, "reserved bits set"));
+        }
         let opcode = OpCode::from_u8(byte1 & 0x0F)
-            .ok_or_else(|| "Invalid opcode".to_string())?;
+            .ok_or_else(|| ProtocolError::new(CLOSE_PROTOCOL_ERROR, "invalid opcode"))?;
         let mask = (byte2 & 0x80) != 0;
         let mut payload_len = (byte2 & 0x7F) as usize;
 
@@ -68,13 +115,13 @@ impl WebSocketFrame {
 
         if payload_len == 126 {
             if data.len() < pos + 2 {
-                return Err("Frame too short for extended payload".to_string());
+                return Ok(None);
             }
             payload_len = u16::from_be_bytes([data[pos], data[pos + 1]]) as usize;
             pos += 2;
         } else if payload_len == 127 {
             if data.len() < pos + 8 {
-                return Err("Frame too short for extended payload".to_string());
+                return Ok(None);
             }
             payload_len = u64::from_be_bytes([
                 data[pos],
@@ -89,9 +136,19 @@ impl WebSocketFrame {
             pos += 8;
         }
 
+        if opcode.is_control() && (!fin || payload_len > 125) {
+            return Err(ProtocolError::new(
+                CLOSE_PROTOCOL_ERROR,
+                "control frames must be unfragmented and at most 125 bytes",
+            ));
+        }
+        if payload_len > max_payload {
+            return Err(ProtocolError::new(CLOSE_TOO_BIG, "frame too large"));
+        }
+
         let masking_key = if mask {
             if data.len() < pos + 4 {
-                return Err("Frame too short for masking key".to_string());
+                return Ok(None);
             }
             let key = [data[pos], data[pos + 1], data[pos + 2], data[pos + 3]];
             pos += 4;
@@ -101,7 +158,7 @@ impl WebSocketFrame {
         };
 
         if data.len() < pos + payload_len {
-            return Err("Frame too short for payload".to_string());
+            return Ok(None);
         }
 
         let mut payload = data[pos..pos + payload_len].to_vec();
@@ -113,7 +170,7 @@ impl WebSocketFrame {
             }
         }
 
-        Ok((
+        Ok(Some((
             WebSocketFrame {
                 fin,
                 opcode,
@@ -121,7 +178,7 @@ impl WebSocketFrame {
                 payload,
             },
             pos,
-        ))
+        )))
     }
 
     fn serialize(&self) -> Vec<u8> {
@@ -132,25 +189,44 @@ impl WebSocketFrame {
         frame.push(byte1);
 
         let payload_len = self.payload.len();
-        
+        let mask_bit = if self.mask { 0x80 } else { 0x00 };
+
         if payload_len < 126 {
-            frame.push(payload_len as u8);
+            frame.push(mask_bit | payload_len as u8);
         } else if payload_len < 65536 {
-            frame.push(126);
+            frame.push(mask_bit | 126);
             frame.extend_from_slice(&(payload_len as u16).to_be_bytes());
         } else {
-            frame.push(127);
+            frame.push(mask_bit | 127);
             frame.extend_from_slice(&(payload_len as u64).to_be_bytes());
         }
 
-        frame.extend_from_slice(&self.payload);
+        if self.mask {
+            // A fresh key per frame, as RFC 6455 section 5.3 requires
+            let bytes = random_u64().to_be_bytes();
+            let key = [bytes[0], bytes[1], bytes[2], bytes[3]];
+            frame.extend_from_slice(&key);
+            frame.extend(self.payload.iter().enumerate().map(|(i, byte)| byte ^ key[i % 4]));
+        } else {
+            frame.extend_from_slice(&self.payload);
+        }
         frame
     }
 
+    /// Client-to-server frames must be masked
+    fn masked(mut self) -> Self {
+        self.mask = true;
+        self
+    }
+
     fn text(text: &str) -> Self {
         Self::new(OpCode::Text, text.as_bytes().to_vec())
     }
 
+    fn ping(data: Vec<u8>) -> Self {
+        Self::new(OpCode::Ping, data)
+    }
+
     fn pong(data: Vec<u8>) -> Self {
         Self::new(OpCode::Pong, data)
     }
@@ -158,21 +234,193 @@ impl WebSocketFrame {
     fn close() -> Self {
         Self::new(OpCode::Close, Vec::new())
     }
+
+    fn close_with_status(code: u16, reason: &str) -> Self {
+        let mut payload = code.to_be_bytes().to_vec();
+        payload.extend_from_slice(reason.as_bytes());
+        Self::new(OpCode::Close, payload)
+    }
+}
+
+/// 64 unpredictable bits from std's per-process random hasher keys; enough
+/// for masking keys and handshake nonces, which only need to be unguessable
+/// by intermediaries
+fn random_u64() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+// ========== MESSAGES ==========
+/// What the chat layer sends and receives: a whole message, however many
+/// frames it took on the wire
+#[derive(Debug, Clone, PartialEq)]
+enum Message {
+    Text(String),
+    Binary(Vec<u8>),
+    Ping(Vec<u8>),
+    Pong(Vec<u8>),
+    /// Status code and reason, if the peer gave one
+    Close(Option<(u16, String)>),
+}
+
+impl Message {
+    /// Decodes a control frame, checking the close payload as section
+    /// 5.5.1 requires
+    fn from_control(frame: WebSocketFrame) -> Result<Self, ProtocolError> {
+        match frame.opcode {
+            OpCode::Ping => Ok(Message::Ping(frame.payload)),
+            OpCode::Pong => Ok(Message::Pong(frame.payload)),
+            _ => {
+                let payload = frame.payload;
+                if payload.is_empty() {
+                    return Ok(Message::Close(None));
+                }
+                if payload.len() < 2 {
+                    return Err(ProtocolError::new(CLOSE_PROTOCOL_ERROR, "truncated close code"));
+                }
+                let code = u16::from_be_bytes([payload[0], payload[1]]);
+                if !matches!(code, 1000..=1003 | 1007..=1011 | 3000..=4999) {
+                    return Err(ProtocolError::new(CLOSE_PROTOCOL_ERROR, "invalid close code"));
+                }
+                let reason = String::from_utf8(payload[2..].to_vec()).map_err(|_| {
+                    ProtocolError::new(CLOSE_INVALID_PAYLOAD, "close reason is not valid UTF-8")
+                })?;
+                Ok(Message::Close(Some((code, reason))))
+            }
+        }
+    }
+}
+
+impl From<Message> for WebSocketFrame {
+    fn from(message: Message) -> Self {
+        match message {
+            Message::Text(text) => WebSocketFrame::text(&text),
+            Message::Binary(data) => WebSocketFrame::new(OpCode::Binary, data),
+            Message::Ping(data) => WebSocketFrame::ping(data),
+            Message::Pong(data) => WebSocketFrame::pong(data),
+            Message::Close(None) => WebSocketFrame::close(),
+            Message::Close(Some((code, reason))) => {
+                WebSocketFrame::close_with_status(code, &reason)
+            }
+        }
+    }
+}
+
+/// Turns frames into messages: reassembles fragments, enforces
+/// `MAX_MESSAGE_SIZE`, and validates text as UTF-8
+#[derive(Default)]
+struct MessageBuffer {
+    opcode: Option<OpCode>,
+    payload: Vec<u8>,
+}
+
+impl MessageBuffer {
+    /// Adds a frame; returns the message once its final fragment has
+    /// arrived. Control frames may come between fragments and are returned
+    /// straight away.
+    fn push(&mut self, frame: WebSocketFrame) -> Result<Option<Message>, ProtocolError> {
+        if frame.opcode.is_control() {
+            return Message::from_control(frame).map(Some);
+        }
+
+        match (frame.opcode, self.opcode) {
+            (OpCode::Continuation, None) => {
+                return Err(ProtocolError::new(
+                    CLOSE_PROTOCOL_ERROR,
+                    "continuation without a message",
+                ));
+            }
+            (OpCode::Continuation, Some(_)) => {}
+            (_, Some(_)) => {
+                return Err(ProtocolError::new(
+                    CLOSE_PROTOCOL_ERROR,
+                    "new message before the last one finished",
+                ));
+            }
+            (opcode, None) => self.opcode = Some(opcode),
+        }
+
+        if self.payload.len() + frame.payload.len() > MAX_MESSAGE_SIZE {
+            return Err(ProtocolError::new(CLOSE_TOO_BIG, "message too large"));
+        }
+        self.payload.extend_from_slice(&frame.payload);
+
+        if !frame.fin {
+            return Ok(None);
+        }
+        let opcode = self.opcode.take().unwrap_or(frame.opcode);
+        let payload = std::mem::take(&mut self.payload);
+        if opcode == OpCode::Binary {
+            return Ok(Some(Message::Binary(payload)));
+        }
+        String::from_utf8(payload)
+            .map(|text| Some(Message::Text(text)))
+            .map_err(|_| ProtocolError::new(CLOSE_INVALID_PAYLOAD, "text is not valid UTF-8"))
+    }
 }
 
 // ========== WEBSOCKET HANDSHAKE ==========
+/// base64(SHA-1(key + GUID)), as RFC 6455 section 4.2.2 requires; clients
+/// reject any other value
 fn generate_accept_key(key: &str) -> String {
-    use std::collections::hash_map::DefaultHasher;
-    use std::hash::{Hash, Hasher};
-
     let magic = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
     let combined = format!("{}{}", key, magic);
-    
-    let mut hasher = DefaultHasher::new();
-    combined.hash(&mut hasher);
-    let hash = hasher.finish();
-    
-    base64_encode(&hash.to_be_bytes())
+
+    base64_encode(&sha1(combined.as_bytes()))
+}
+
+/// SHA-1 (FIPS 180-4). Long broken for signatures, but the handshake only
+/// uses it to prove the server understood the request.
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut state: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+
+    // Pad with a 1 bit, zeros, and the bit length to a multiple of 64 bytes
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+
+    for block in message.chunks_exact(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = state;
+        for (i, word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+
+        for (h, value) in state.iter_mut().zip([a, b, c, d, e]) {
+            *h = h.wrapping_add(value);
+        }
+    }
+
+    let mut digest = [0u8; 20];
+    for (bytes, h) in digest.chunks_exact_mut(4).zip(state) {
+        bytes.copy_from_slice(&h.to_be_bytes());
+    }
+    digest
 }
 
 fn base64_encode(data: &[u8]) -> String {
@@ -207,7 +455,10 @@ fn base64_encode(data: &[u8]) -> String {
     result
 }
 
-async fn perform_handshake(stream: &mut TcpStream) -> Result<(), String> {
+async fn perform_handshake<S>(stream: &mut S) -> Result<(), String>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
     let mut buffer = vec![0u8; 4096];
     let n = stream
         .read(&mut buffer)
@@ -249,25 +500,41 @@ type ClientId = u64;
 
 struct Client {
     id: ClientId,
-    tx: mpsc::UnboundedSender<WebSocketFrame>,
+    tx: mpsc::UnboundedSender<Message>,
     name: String,
+    /// `None` while in the lobby
+    room: Option<String>,
+    last_pong: Instant,
+    shutdown: Arc<Notify>,
 }
 
 // ========== CHAT SERVER ==========
+const PING_INTERVAL: Duration = Duration::from_secs(15);
+const PONG_TIMEOUT: Duration = Duration::from_secs(45);
+
 struct ChatServer {
     clients: Arc<RwLock<HashMap<ClientId, Client>>>,
+    /// Members of each room; empty rooms are dropped
+    rooms: Arc<RwLock<HashMap<String, HashSet<ClientId>>>>,
     next_client_id: Arc<RwLock<ClientId>>,
+    keepalive_started: AtomicBool,
 }
 
 impl ChatServer {
     fn new() -> Self {
         ChatServer {
             clients: Arc::new(RwLock::new(HashMap::new())),
+            rooms: Arc::new(RwLock::new(HashMap::new())),
             next_client_id: Arc::new(RwLock::new(0)),
+            keepalive_started: AtomicBool::new(false),
         }
     }
 
-    async fn register_client(&self, tx: mpsc::UnboundedSender<WebSocketFrame>) -> ClientId {
+    async fn register_client(
+        &self,
+        tx: mpsc::UnboundedSender<Message>,
+        shutdown: Arc<Notify>,
+    ) -> ClientId {
         let client_id = {
             let mut next_id = self.next_client_id.write().await;
             let id = *next_id;
@@ -279,6 +546,9 @@ impl ChatServer {
             id: client_id,
             tx,
             name: format!("User{}", client_id),
+            room: None,
+            last_pong: Instant::now(),
+            shutdown,
         };
 
         {
@@ -294,9 +564,12 @@ impl ChatServer {
     }
 
     async fn unregister_client(&self, client_id: ClientId) {
-        {
+        let client = {
             let mut clients = self.clients.write().await;
-            clients.remove(&client_id);
+            clients.remove(&client_id)
+        };
+        if let Some(room) = client.and_then(|c| c.room) {
+            self.remove_from_room(client_id, &room).await;
         }
 
         println!("[Server] Client {} disconnected", client_id);
@@ -305,20 +578,160 @@ impl ChatServer {
     }
 
     async fn broadcast(&self, message: &str) {
-        let frame = WebSocketFrame::text(message);
+        let message = Message::Text(message.to_string());
         let clients = self.clients.read().await;
 
         for client in clients.values() {
-            let _ = client.tx.send(frame.clone());
+            let _ = client.tx.send(message.clone());
+        }
+    }
+
+    async fn broadcast_to_room(&self, room: &str, message: &str) {
+        let members: Vec<ClientId> = {
+            let rooms = self.rooms.read().await;
+            rooms.get(room).map(|m| m.iter().copied().collect()).unwrap_or_default()
+        };
+
+        let message = Message::Text(message.to_string());
+        let clients = self.clients.read().await;
+        for client in members.iter().filter_map(|id| clients.get(id)) {
+            let _ = client.tx.send(message.clone());
+        }
+    }
+
+    /// Everyone not in a room
+    async fn broadcast_to_lobby(&self, message: &str) {
+        let message = Message::Text(message.to_string());
+        let clients = self.clients.read().await;
+
+        for client in clients.values().filter(|c| c.room.is_none()) {
+            let _ = client.tx.send(message.clone());
+        }
+    }
+
+    async fn join_room(&self, client_id: ClientId, room: &str) {
+        let (name, old_room) = {
+            let mut clients = self.clients.write().await;
+            let Some(client) = clients.get_mut(&client_id) else {
+                return;
+            };
+            if client.room.as_deref() == Some(room) {
+                drop(clients);
+                self.send_to_client(client_id, &format!("You are already in #{}", room))
+                    .await;
+                return;
+            }
+            (client.name.clone(), client.room.replace(room.to_string()))
+        };
+
+        if let Some(old_room) = old_room {
+            self.remove_from_room(client_id, &old_room).await;
+            self.broadcast_to_room(&old_room, &format!("{} left #{}", name, old_room))
+                .await;
+        }
+        {
+            let mut rooms = self.rooms.write().await;
+            rooms.entry(room.to_string()).or_default().insert(client_id);
+        }
+        self.broadcast_to_room(room, &format!("{} joined #{}", name, room))
+            .await;
+    }
+
+    async fn leave_room(&self, client_id: ClientId) {
+        let (name, room) = {
+            let mut clients = self.clients.write().await;
+            match clients.get_mut(&client_id) {
+                Some(client) => (client.name.clone(), client.room.take()),
+                None => return,
+            }
+        };
+
+        let Some(room) = room else {
+            self.send_to_client(client_id, "You are not in a room").await;
+            return;
+        };
+        self.remove_from_room(client_id, &room).await;
+        self.broadcast_to_room(&room, &format!("{} left #{}", name, room))
+            .await;
+        self.send_to_client(client_id, &format!("You left #{} for the lobby", room))
+            .await;
+    }
+
+    /// Passes a binary message on to everyone else in the sender's room,
+    /// or the lobby
+    async fn relay_binary(&self, client_id: ClientId, data: Vec<u8>) {
+        println!("[Server] Client {}: {} bytes of binary", client_id, data.len());
+
+        let clients = self.clients.read().await;
+        let Some(room) = clients.get(&client_id).map(|c| c.room.clone()) else {
+            return;
+        };
+        let message = Message::Binary(data);
+        for client in clients.values().filter(|c| c.id != client_id && c.room == room) {
+            let _ = client.tx.send(message.clone());
+        }
+    }
+
+    async fn remove_from_room(&self, client_id: ClientId, room: &str) {
+        let mut rooms = self.rooms.write().await;
+        if let Some(members) = rooms.get_mut(room) {
+            members.remove(&client_id);
+            if members.is_empty() {
+                rooms.remove(room);
+            }
         }
     }
 
     async fn send_to_client(&self, client_id: ClientId, message: &str) {
-        let frame = WebSocketFrame::text(message);
+        self.send(client_id, Message::Text(message.to_string())).await;
+    }
+
+    async fn send(&self, client_id: ClientId, message: Message) {
         let clients = self.clients.read().await;
 
         if let Some(client) = clients.get(&client_id) {
-            let _ = client.tx.send(frame);
+            let _ = client.tx.send(message);
+        }
+    }
+
+    async fn record_pong(&self, client_id: ClientId) {
+        let mut clients = self.clients.write().await;
+        if let Some(client) = clients.get_mut(&client_id) {
+            client.last_pong = Instant::now();
+        }
+    }
+
+    /// One keepalive task covers every listener the server runs
+    fn start_keepalive(self: &Arc<Self>) {
+        if !self.keepalive_started.swap(true, Ordering::SeqCst) {
+            tokio::spawn(self.clone().keepalive());
+        }
+    }
+
+    /// Pings every client each `PING_INTERVAL` and evicts the ones that
+    /// have not sent a pong for `PONG_TIMEOUT`. The first round goes out one
+    /// interval after start, not immediately, so fresh connections see their
+    /// own traffic before any ping
+    async fn keepalive(self: Arc<Self>) {
+        let start = tokio::time::Instant::now() + PING_INTERVAL;
+        let mut ticker = tokio::time::interval_at(start, PING_INTERVAL);
+        let mut sequence: u64 = 0;
+
+        loop {
+            ticker.tick().await;
+            sequence += 1;
+
+            let clients = self.clients.read().await;
+            for client in clients.values() {
+                if client.last_pong.elapsed() > PONG_TIMEOUT {
+                    println!("[Server] Client {} missed its pongs, disconnecting", client.id);
+                    let status = (CLOSE_GOING_AWAY, "ping timeout".to_string());
+                    let _ = client.tx.send(Message::Close(Some(status)));
+                    client.shutdown.notify_one();
+                } else {
+                    let _ = client.tx.send(Message::Ping(sequence.to_be_bytes().to_vec()));
+                }
+            }
         }
     }
 
@@ -347,25 +760,82 @@ impl ChatServer {
             
             self.send_to_client(client_id, &format!("Online users:\n{}", user_list.join("\n")))
                 .await;
+        } else if let Some(room) = message.strip_prefix("/join ") {
+            let room = room.trim().trim_start_matches('#');
+            if room.is_empty() || room.contains(char::is_whitespace) {
+                self.send_to_client(client_id, "Usage: /join <room>").await;
+            } else {
+                self.join_room(client_id, room).await;
+            }
+        } else if message == "/leave" {
+            self.leave_room(client_id).await;
+        } else if message == "/rooms" {
+            let mut room_list: Vec<String> = {
+                let rooms = self.rooms.read().await;
+                rooms
+                    .iter()
+                    .map(|(room, members)| format!("#{} ({})", room, members.len()))
+                    .collect()
+            };
+            room_list.sort();
+
+            let reply = if room_list.is_empty() {
+                "No rooms yet; /join <room> creates one".to_string()
+            } else {
+                format!("Rooms:\n{}", room_list.join("\n"))
+            };
+            self.send_to_client(client_id, &reply).await;
         } else {
-            let sender_name = {
+            let (sender_name, room) = {
                 let clients = self.clients.read().await;
                 clients
                     .get(&client_id)
-                    .map(|c| c.name.clone())
-                    .unwrap_or_else(|| format!("User{}", client_id))
+                    .map(|c| (c.name.clone(), c.room.clone()))
+                    .unwrap_or_else(|| (format!("User{}", client_id), None))
             };
 
-            self.broadcast(&format!("{}: {}", sender_name, message))
-                .await;
+            let line = format!("{}: {}", sender_name, message);
+            match room {
+                Some(room) => self.broadcast_to_room(&room, &line).await,
+                None => self.broadcast_to_lobby(&line).await,
+            }
         }
     }
 
-    async fn handle_client(
-        self: Arc<Self>,
-        mut stream: TcpStream,
-        addr: std::net::SocketAddr,
-    ) {
+    /// Acts on one frame from a client. `Ok(false)` means the client closed
+    /// the connection; an error means it broke the protocol.
+    async fn handle_frame(
+        &self,
+        client_id: ClientId,
+        frame: WebSocketFrame,
+        message: &mut MessageBuffer,
+    ) -> Result<bool, ProtocolError> {
+        if !frame.mask {
+            return Err(ProtocolError::new(CLOSE_PROTOCOL_ERROR, "client frames must be masked"));
+        }
+
+        match message.push(frame)? {
+            Some(Message::Text(text)) => self.handle_message(client_id, &text).await,
+            Some(Message::Binary(data)) => self.relay_binary(client_id, data).await,
+            Some(Message::Close(status)) => {
+                println!("[Server] Client {} sent close frame", client_id);
+                // Echo the status code back, completing the closing handshake
+                let status = status.map(|(code, _)| (code, String::new()));
+                self.send(client_id, Message::Close(status)).await;
+                return Ok(false);
+            }
+            Some(Message::Ping(data)) => self.send(client_id, Message::Pong(data)).await,
+            Some(Message::Pong(_)) => self.record_pong(client_id).await,
+            None => {}
+        }
+        Ok(true)
+    }
+
+    /// Serves one connection, plain TCP or TLS
+    async fn handle_client<S>(self: Arc<Self>, mut stream: S, addr: std::net::SocketAddr)
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
         println!("[Server] New connection from {}", addr);
 
         if let Err(e) = perform_handshake(&mut stream).await {
@@ -373,15 +843,16 @@ impl ChatServer {
             return;
         }
 
-        let (mut reader, mut writer) = stream.into_split();
-        let (tx, mut rx) = mpsc::unbounded_channel::<WebSocketFrame>();
+        let (mut reader, mut writer) = tokio::io::split(stream);
+        let (tx, mut rx) = mpsc::unbounded_channel::<Message>();
+        let shutdown = Arc::new(Notify::new());
 
-        let client_id = self.register_client(tx).await;
+        let client_id = self.register_client(tx, shutdown.clone()).await;
 
         let server_clone = self.clone();
         tokio::spawn(async move {
-            while let Some(frame) = rx.recv().await {
-                let data = frame.serialize();
+            while let Some(message) = rx.recv().await {
+                let data = WebSocketFrame::from(message).serialize();
                 if writer.write_all(&data).await.is_err() {
                     break;
                 }
@@ -391,42 +862,38 @@ impl ChatServer {
         let server_clone = self.clone();
         tokio::spawn(async move {
             let mut buffer = vec![0u8; 8192];
+            // Bytes of a frame that has only partly arrived
+            let mut pending = Vec::new();
+            let mut message = MessageBuffer::default();
+
+            'connection: loop {
+                let read = tokio::select! {
+                    read = reader.read(&mut buffer) => read,
+                    _ = shutdown.notified() => break,
+                };
 
-            loop {
-                match reader.read(&mut buffer).await {
+                match read {
                     Ok(0) => break,
                     Ok(n) => {
-                        let mut offset = 0;
-                        while offset < n {
-                            match WebSocketFrame::parse(&buffer[offset..n]) {
-                                Ok((frame, consumed)) => {
-                                    offset += consumed;
-
-                                    match frame.opcode {
-                                        OpCode::Text => {
-                                            if let Ok(text) = String::from_utf8(frame.payload) {
-                                                server_clone.handle_message(client_id, &text).await;
-                                            }
-                                        }
-                                        OpCode::Close => {
-                                            println!("[Server] Client {} sent close frame", client_id);
-                                            break;
-                                        }
-                                        OpCode::Ping => {
-                                            let pong = WebSocketFrame::pong(frame.payload);
-                                            server_clone
-                                                .send_to_client(
-                                                    client_id,
-                                                    &String::from_utf8_lossy(&pong.payload),
-                                                )
-                                                .await;
-                                        }
-                                        _ => {}
-                                    }
+                        pending.extend_from_slice(&buffer[..n]);
+                        loop {
+                            let handled = match WebSocketFrame::parse(&pending, MAX_FRAME_SIZE) {
+                                Ok(Some((frame, consumed))) => {
+                                    pending.drain(..consumed);
+                                    server_clone.handle_frame(client_id, frame, &mut message).await
                                 }
+                                Ok(None) => break,
+                                Err(e) => Err(e),
+                            };
+
+                            match handled {
+                                Ok(true) => {}
+                                Ok(false) => break 'connection,
                                 Err(e) => {
-                                    eprintln!("[Server] Frame parse error: {}", e);
-                                    break;
+                                    eprintln!("[Server] Client {}: {}", client_id, e);
+                                    let close = Message::Close(Some((e.code, e.reason.into())));
+                                    server_clone.send(client_id, close).await;
+                                    break 'connection;
                                 }
                             }
                         }
@@ -447,11 +914,24 @@ impl ChatServer {
             .await
             .map_err(|e| format!("Failed to bind: {}", e))?;
 
+        self.serve(listener).await
+    }
+
+    async fn serve(self: Arc<Self>, listener: TcpListener) -> Result<(), String> {
+        let addr = listener
+            .local_addr()
+            .map_err(|e| format!("Failed to read local address: {}", e))?;
+
+        self.start_keepalive();
+
         println!("[Server] WebSocket server listening on {}", addr);
         println!("[Server] Connect using: ws://{}", addr);
         println!("[Server] Available commands:");
         println!("  /name <newname> - Change your username");
         println!("  /users - List online users");
+        println!("  /join <room> - Chat in a room instead of the lobby");
+        println!("  /leave - Go back to the lobby");
+        println!("  /rooms - List rooms");
         println!();
 
         loop {
@@ -468,6 +948,288 @@ impl ChatServer {
             }
         }
     }
+
+    /// Like `serve`, but every connection starts with a TLS handshake
+    #[cfg(feature = "tls")]
+    async fn serve_tls(
+        self: Arc<Self>,
+        listener: TcpListener,
+        acceptor: tokio_rustls::TlsAcceptor,
+    ) -> Result<(), String> {
+        let addr = listener
+            .local_addr()
+            .map_err(|e| format!("Failed to read local address: {}", e))?;
+
+        self.start_keepalive();
+
+        println!("[Server] Secure WebSocket server listening on {}", addr);
+        println!("[Server] Connect using: wss://{}", addr);
+
+        loop {
+            match listener.accept().await {
+                Ok((stream, addr)) => {
+                    let server = self.clone();
+                    let acceptor = acceptor.clone();
+                    tokio::spawn(async move {
+                        match acceptor.accept(stream).await {
+                            Ok(stream) => server.handle_client(stream, addr).await,
+                            Err(e) => eprintln!("[Server] TLS handshake with {} failed: {}", addr, e),
+                        }
+                    });
+                }
+                Err(e) => {
+                    eprintln!("[Server] Accept error: {}", e);
+                }
+            }
+        }
+    }
+}
+
+// ========== TLS ==========
+#[cfg(feature = "tls")]
+const TLS_ADDR: &str = "127.0.0.1:8443";
+
+/// A TLS acceptor from PEM files: the certificate chain, leaf first, and
+/// its private key (PKCS#8, PKCS#1, or SEC1)
+#[cfg(feature = "tls")]
+fn load_tls_acceptor(cert_path: &str, key_path: &str) -> Result<tokio_rustls::TlsAcceptor, String> {
+    use std::fs::File;
+    use std::io::BufReader;
+    use tokio_rustls::rustls::ServerConfig;
+
+    let open = |path: &str| {
+        File::open(path)
+            .map(BufReader::new)
+            .map_err(|e| format!("Cannot open {}: {}", path, e))
+    };
+
+    let certs = rustls_pemfile::certs(&mut open(cert_path)?)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Bad certificate in {}: {}", cert_path, e))?;
+    if certs.is_empty() {
+        return Err(format!("No certificates in {}", cert_path));
+    }
+    let key = rustls_pemfile::private_key(&mut open(key_path)?)
+        .map_err(|e| format!("Bad private key in {}: {}", key_path, e))?
+        .ok_or_else(|| format!("No private key in {}", key_path))?;
+
+    let config = ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| format!("Invalid certificate/key pair: {}", e))?;
+    Ok(tokio_rustls::TlsAcceptor::from(Arc::new(config)))
+}
+
+/// Serves wss:// on `TLS_ADDR` alongside the plain listener
+#[cfg(feature = "tls")]
+async fn start_tls(server: Arc<ChatServer>, cert_path: &str, key_path: &str) -> Result<(), String> {
+    let acceptor = load_tls_acceptor(cert_path, key_path)?;
+    let listener = TcpListener::bind(TLS_ADDR)
+        .await
+        .map_err(|e| format!("Failed to bind {}: {}", TLS_ADDR, e))?;
+
+    tokio::spawn(async move {
+        if let Err(e) = server.serve_tls(listener, acceptor).await {
+            eprintln!("TLS server error: {}", e);
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(feature = "tls"))]
+async fn start_tls(_server: Arc<ChatServer>, _cert_path: &str, _key_path: &str) -> Result<(), String> {
+    Err("wss:// needs a build with the tls feature".to_string())
+}
+
+/// `--cert PATH --key PATH`, if given
+fn tls_paths() -> Result<Option<(String, String)>, String> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let value = |flag: &str| {
+        args.iter()
+            .position(|arg| arg == flag)
+            .and_then(|i| args.get(i + 1))
+            .cloned()
+    };
+
+    match (value("--cert"), value("--key")) {
+        (Some(cert), Some(key)) => Ok(Some((cert, key))),
+        (None, None) => Ok(None),
+        _ => Err("--cert and --key must be given together".to_string()),
+    }
+}
+
+// ========== WEBSOCKET CLIENT ==========
+/// The client side of RFC 6455: sends the opening handshake with a random
+/// key, checks the server's accept value, and masks everything it sends
+struct WebSocketClient {
+    stream: TcpStream,
+    pending: Vec<u8>,
+    messages: MessageBuffer,
+}
+
+impl WebSocketClient {
+    /// Connects to `ws://host:port/path` (or plain `host:port`)
+    async fn connect(url: &str) -> Result<Self, String> {
+        let rest = url.strip_prefix("ws://").unwrap_or(url);
+        let (host, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, "/"),
+        };
+
+        let mut stream = TcpStream::connect(host)
+            .await
+            .map_err(|e| format!("Connect error: {}", e))?;
+
+        let nonce = [random_u64().to_be_bytes(), random_u64().to_be_bytes()].concat();
+        let key = base64_encode(&nonce);
+        let request = format!(
+            "GET {} HTTP/1.1\r\n\
+             Host: {}\r\n\
+             Upgrade: websocket\r\n\
+             Connection: Upgrade\r\n\
+             Sec-WebSocket-Key: {}\r\n\
+             Sec-WebSocket-Version: 13\r\n\
+             \r\n",
+            path, host, key
+        );
+        stream
+            .write_all(request.as_bytes())
+            .await
+            .map_err(|e| format!("Write error: {}", e))?;
+
+        // Read up to the end of the response head; anything after it is frames
+        let mut response = Vec::new();
+        let mut buffer = [0u8; 1024];
+        let head_end = loop {
+            if let Some(i) = response.windows(4).position(|w| w == b"\r\n\r\n") {
+                break i + 4;
+            }
+            let n = stream
+                .read(&mut buffer)
+                .await
+                .map_err(|e| format!("Read error: {}", e))?;
+            if n == 0 {
+                return Err("Server closed the connection during the handshake".to_string());
+            }
+            response.extend_from_slice(&buffer[..n]);
+        };
+
+        let head = String::from_utf8_lossy(&response[..head_end]).into_owned();
+        let status = head.lines().next().unwrap_or("");
+        if !status.starts_with("HTTP/1.1 101") {
+            return Err(format!("Handshake rejected: {}", status));
+        }
+        let accept = head.lines().find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.eq_ignore_ascii_case("Sec-WebSocket-Accept").then(|| value.trim().to_string())
+        });
+        if accept.as_deref() != Some(generate_accept_key(&key).as_str()) {
+            return Err("Handshake failed: wrong Sec-WebSocket-Accept".to_string());
+        }
+
+        Ok(WebSocketClient {
+            stream,
+            pending: response[head_end..].to_vec(),
+            messages: MessageBuffer::default(),
+        })
+    }
+
+    async fn send_frame(&mut self, frame: WebSocketFrame) -> Result<(), String> {
+        self.stream
+            .write_all(&frame.masked().serialize())
+            .await
+            .map_err(|e| format!("Write error: {}", e))
+    }
+
+    async fn send(&mut self, message: Message) -> Result<(), String> {
+        self.send_frame(message.into()).await
+    }
+
+    async fn send_text(&mut self, text: &str) -> Result<(), String> {
+        self.send(Message::Text(text.to_string())).await
+    }
+
+    /// The next frame from the server, whatever its type
+    async fn receive_frame(&mut self) -> Result<WebSocketFrame, String> {
+        let mut buffer = [0u8; 4096];
+        loop {
+            let parsed = WebSocketFrame::parse(&self.pending, MAX_MESSAGE_SIZE)
+                .map_err(|e| e.to_string())?;
+            if let Some((frame, consumed)) = parsed {
+                self.pending.drain(..consumed);
+                return Ok(frame);
+            }
+            let n = self
+                .stream
+                .read(&mut buffer)
+                .await
+                .map_err(|e| format!("Read error: {}", e))?;
+            if n == 0 {
+                return Err("Connection closed".to_string());
+            }
+            self.pending.extend_from_slice(&buffer[..n]);
+        }
+    }
+
+    /// The next complete message from the server
+    async fn receive(&mut self) -> Result<Message, String> {
+        loop {
+            let frame = self.receive_frame().await?;
+            if let Some(message) = self.messages.push(frame).map_err(|e| e.to_string())? {
+                return Ok(message);
+            }
+        }
+    }
+
+    /// The next text message, answering pings and skipping binary messages
+    /// along the way. `None` once the server closes the connection.
+    async fn receive_text(&mut self) -> Result<Option<String>, String> {
+        loop {
+            match self.receive().await? {
+                Message::Text(text) => return Ok(Some(text)),
+                Message::Ping(data) => self.send(Message::Pong(data)).await?,
+                Message::Close(_) => return Ok(None),
+                Message::Binary(_) | Message::Pong(_) => {}
+            }
+        }
+    }
+
+    async fn close(mut self) -> Result<(), String> {
+        self.send(Message::Close(None)).await
+    }
+}
+
+/// A chat participant that answers `!echo <text>` and `!time`, and leaves
+/// on `!kick`
+async fn run_bot(url: String) -> Result<(), String> {
+    let mut client = WebSocketClient::connect(&url).await?;
+    client.send_text("/name ChatBot").await?;
+    client
+        .send_text("Hi! I answer !echo <text>, !time and !kick")
+        .await?;
+
+    let started = Instant::now();
+    while let Some(message) = client.receive_text().await? {
+        // Chat lines arrive as "<sender>: <text>"; skip our own
+        let Some((sender, text)) = message.split_once(": ") else {
+            continue;
+        };
+        if sender == "ChatBot" {
+            continue;
+        }
+
+        if let Some(echo) = text.strip_prefix("!echo ") {
+            client.send_text(&format!("{} said: {}", sender, echo)).await?;
+        } else if text == "!time" {
+            client
+                .send_text(&format!("Up for {}s", started.elapsed().as_secs()))
+                .await?;
+        } else if text == "!kick" {
+            client.send_text("Bye!").await?;
+            return client.close().await;
+        }
+    }
+    Ok(())
 }
 
 // ========== MAIN ==========
@@ -479,6 +1241,21 @@ async fn main() {
 
     println!("Starting WebSocket chat server...\n");
 
+    let tls = match tls_paths() {
+        Ok(Some((cert, key))) => match start_tls(server.clone(), &cert, &key).await {
+            Ok(()) => true,
+            Err(e) => {
+                eprintln!("TLS error: {}", e);
+                return;
+            }
+        },
+        Ok(None) => false,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+
     let server_task = {
         let server = server.clone();
         tokio::spawn(async move {
@@ -490,6 +1267,12 @@ async fn main() {
 
     tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
 
+    tokio::spawn(async {
+        if let Err(e) = run_bot("ws://127.0.0.1:8080/".to_string()).await {
+            eprintln!("[Bot] {}", e);
+        }
+    });
+
     println!("\n✓ WebSocket server is running!");
     println!("\nTo test the chat server:");
     println!("  1. Open your browser's developer console");
@@ -497,15 +1280,251 @@ async fn main() {
     println!("  3. Run: ws.onmessage = (e) => console.log('Received:', e.data)");
     println!("  4. Run: ws.send('Hello from browser!')");
     println!("  5. Open multiple browser tabs to test multi-user chat");
+    println!("  6. Send '!echo hello', '!time' or '!kick' to talk to ChatBot");
+    if tls {
+        println!("  Pages served over HTTPS can use: new WebSocket('wss://localhost:8443')");
+    }
     println!("\nKey features demonstrated:");
     println!("  • Full WebSocket handshake (HTTP Upgrade)");
     println!("  • RFC 6455 compliant frame parsing");
     println!("  • Masking/unmasking of frames");
-    println!("  • Text and control frames (ping/pong/close)");
-    println!("  • Multi-client broadcast messaging");
+    println!("  • Text, binary and control frames (ping/pong/close)");
+    println!("  • Size limits, UTF-8 validation and fragment reassembly");
+    println!("  • Server pings with eviction of unresponsive clients");
+    println!("  • Multi-client broadcast messaging with rooms");
+    println!("  • Client-side handshake and masking (the ChatBot)");
     println!("  • Bidirectional async communication");
     println!("  • Connection lifecycle management");
     println!("\nPress Ctrl+C to stop the server...\n");
 
     server_task.await.unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn test_sha1_fips_vectors() {
+        assert_eq!(hex(&sha1(b"")), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+        assert_eq!(hex(&sha1(b"abc")), "a9993e364706816aba3e25717850c26c9cd0d89d");
+        assert_eq!(
+            hex(&sha1(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq")),
+            "84983e441c3bd26ebaae4aa1f95129e5e54670f1"
+        );
+        assert_eq!(
+            hex(&sha1(&vec![b'a'; 1_000_000])),
+            "34aa973cd4c4daa4f61eeb2bdbad27316534016f"
+        );
+    }
+
+    #[test]
+    fn test_base64_rfc4648_vectors() {
+        let vectors = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (input, expected) in vectors {
+            assert_eq!(base64_encode(input.as_bytes()), expected);
+        }
+    }
+
+    #[test]
+    fn test_masked_frame_round_trip() {
+        let bytes = WebSocketFrame::text("hello").masked().serialize();
+        assert_eq!(bytes[1] & 0x80, 0x80);
+        assert_ne!(&bytes[6..], b"hello");
+
+        let (frame, consumed) = WebSocketFrame::parse(&bytes, MAX_FRAME_SIZE).unwrap().unwrap();
+        assert_eq!(consumed, bytes.len());
+        assert_eq!(frame.payload, b"hello");
+        assert!(WebSocketFrame::parse(&bytes[..bytes.len() - 1], MAX_FRAME_SIZE)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_oversized_frame_rejected_from_header() {
+        // Only the header of a 16 MiB frame; nothing else needs to arrive
+        let mut header = vec![0x82, 0xFF];
+        header.extend_from_slice(&(16u64 << 20).to_be_bytes());
+        let err = WebSocketFrame::parse(&header, MAX_FRAME_SIZE).unwrap_err();
+        assert_eq!(err.code, CLOSE_TOO_BIG);
+
+        let long_ping = WebSocketFrame::ping(vec![0; 126]).serialize();
+        let err = WebSocketFrame::parse(&long_ping, MAX_FRAME_SIZE).unwrap_err();
+        assert_eq!(err.code, CLOSE_PROTOCOL_ERROR);
+    }
+
+    #[test]
+    fn test_message_buffer_reassembles_fragments() {
+        let fragment = |opcode, fin, payload: &[u8]| WebSocketFrame {
+            fin,
+            opcode,
+            mask: true,
+            payload: payload.to_vec(),
+        };
+
+        let mut message = MessageBuffer::default();
+        assert_eq!(message.push(fragment(OpCode::Text, false, b"hel")).unwrap(), None);
+        // A ping may arrive between fragments
+        assert_eq!(
+            message.push(fragment(OpCode::Ping, true, b"1")).unwrap(),
+            Some(Message::Ping(b"1".to_vec()))
+        );
+        assert_eq!(
+            message.push(fragment(OpCode::Continuation, true, b"lo")).unwrap(),
+            Some(Message::Text("hello".to_string()))
+        );
+        assert_eq!(
+            message.push(fragment(OpCode::Binary, true, &[0xFF])).unwrap(),
+            Some(Message::Binary(vec![0xFF]))
+        );
+
+        let err = message.push(fragment(OpCode::Continuation, true, b"x")).unwrap_err();
+        assert_eq!(err.code, CLOSE_PROTOCOL_ERROR);
+    }
+
+    /// Reads messages until the server's close and returns its status code
+    async fn close_code(client: &mut WebSocketClient) -> u16 {
+        loop {
+            if let Message::Close(status) = client.receive().await.unwrap() {
+                return status.unwrap().0;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_server_fails_bad_clients() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/", listener.local_addr().unwrap());
+        tokio::spawn(Arc::new(ChatServer::new()).serve(listener));
+
+        let mut client = WebSocketClient::connect(&url).await.unwrap();
+        client
+            .send_frame(WebSocketFrame::new(OpCode::Text, vec![b'h', 0xFF, b'i']))
+            .await
+            .unwrap();
+        assert_eq!(close_code(&mut client).await, CLOSE_INVALID_PAYLOAD);
+
+        let mut client = WebSocketClient::connect(&url).await.unwrap();
+        let unmasked = WebSocketFrame::text("hello").serialize();
+        client.stream.write_all(&unmasked).await.unwrap();
+        assert_eq!(close_code(&mut client).await, CLOSE_PROTOCOL_ERROR);
+    }
+
+    #[tokio::test]
+    async fn test_clients_chat_through_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/", listener.local_addr().unwrap());
+        tokio::spawn(Arc::new(ChatServer::new()).serve(listener));
+
+        let mut alice = WebSocketClient::connect(&url).await.unwrap();
+        assert_eq!(alice.receive_text().await.unwrap().unwrap(), "User0 joined the chat");
+        let mut bob = WebSocketClient::connect(&url).await.unwrap();
+        assert_eq!(alice.receive_text().await.unwrap().unwrap(), "User1 joined the chat");
+        assert_eq!(bob.receive_text().await.unwrap().unwrap(), "User1 joined the chat");
+
+        bob.send_text("hello alice").await.unwrap();
+        assert_eq!(alice.receive_text().await.unwrap().unwrap(), "User1: hello alice");
+
+        bob.close().await.unwrap();
+        assert_eq!(alice.receive_text().await.unwrap().unwrap(), "User1 left the chat");
+    }
+
+    #[tokio::test]
+    async fn test_bot_answers_echo() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/", listener.local_addr().unwrap());
+        tokio::spawn(Arc::new(ChatServer::new()).serve(listener));
+
+        let mut user = WebSocketClient::connect(&url).await.unwrap();
+        tokio::spawn(run_bot(url));
+        loop {
+            let message = user.receive_text().await.unwrap().unwrap();
+            if message.starts_with("ChatBot: Hi!") {
+                break;
+            }
+        }
+
+        user.send_text("!echo ping").await.unwrap();
+        loop {
+            let message = user.receive_text().await.unwrap().unwrap();
+            if message.starts_with("ChatBot:") {
+                assert_eq!(message, "ChatBot: User0 said: ping");
+                break;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_binary_messages_relayed() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/", listener.local_addr().unwrap());
+        tokio::spawn(Arc::new(ChatServer::new()).serve(listener));
+
+        let mut alice = WebSocketClient::connect(&url).await.unwrap();
+        assert_eq!(alice.receive_text().await.unwrap().unwrap(), "User0 joined the chat");
+        let mut bob = WebSocketClient::connect(&url).await.unwrap();
+        assert_eq!(bob.receive_text().await.unwrap().unwrap(), "User1 joined the chat");
+
+        let data: Vec<u8> = (0..=255).collect();
+        alice.send(Message::Binary(data.clone())).await.unwrap();
+        assert_eq!(bob.receive().await.unwrap(), Message::Binary(data));
+    }
+
+    #[tokio::test]
+    async fn test_rooms_scope_chat() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/", listener.local_addr().unwrap());
+        tokio::spawn(Arc::new(ChatServer::new()).serve(listener));
+
+        let mut alice = WebSocketClient::connect(&url).await.unwrap();
+        assert_eq!(alice.receive_text().await.unwrap().unwrap(), "User0 joined the chat");
+        alice.send_text("/join rust").await.unwrap();
+        assert_eq!(alice.receive_text().await.unwrap().unwrap(), "User0 joined #rust");
+
+        let mut bob = WebSocketClient::connect(&url).await.unwrap();
+        assert_eq!(bob.receive_text().await.unwrap().unwrap(), "User1 joined the chat");
+        let mut carol = WebSocketClient::connect(&url).await.unwrap();
+        assert_eq!(carol.receive_text().await.unwrap().unwrap(), "User2 joined the chat");
+        carol.send_text("/join #rust").await.unwrap();
+        assert_eq!(carol.receive_text().await.unwrap().unwrap(), "User2 joined #rust");
+
+        // Bob's lobby line reaches him but not the room
+        bob.send_text("lobby line").await.unwrap();
+        assert_eq!(bob.receive_text().await.unwrap().unwrap(), "User2 joined the chat");
+        assert_eq!(bob.receive_text().await.unwrap().unwrap(), "User1: lobby line");
+        carol.send_text("room line").await.unwrap();
+
+        for expected in [
+            "User1 joined the chat",
+            "User2 joined the chat",
+            "User2 joined #rust",
+            "User2: room line",
+        ] {
+            assert_eq!(alice.receive_text().await.unwrap().unwrap(), expected);
+        }
+
+        carol.send_text("/leave").await.unwrap();
+        assert_eq!(alice.receive_text().await.unwrap().unwrap(), "User2 left #rust");
+    }
+
+    #[test]
+    fn test_accept_key_rfc6455_example() {
+        // RFC 6455 section 1.3
+        assert_eq!(
+            generate_accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+    }
+}
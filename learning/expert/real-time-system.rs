@@ -1,14 +1,32 @@
 // Real-Time Stream Processing System with Windowing, Backpressure, and Event Time
 // Implements complex event processing with async streams and futures
-
-use std::collections::{BTreeMap, VecDeque};
+// Streams can be keyed (key_by) so every entity gets its own windows
+// Window aggregates are pluggable (Aggregator: fold/merge/emit accumulators)
+// Pipelines compose operators: source.map(..).filter(..).window(..).sink(..)
+// Window state and source offsets are checkpointed to disk for crash recovery
+// Results go to sinks (stdout, JSON-lines files, WebSocket) with retries
+// Complex event processing matches patterns like "A then B within 5s without C"
+// Metrics cover throughput, lag and buffers, with an HTTP endpoint for scraping
+// Recorded logs can be replayed from any offset for deterministic windowing
+
+use std::any::Any;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::task::{Context, Poll};
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
-use tokio::sync::{mpsc, RwLock};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, mpsc, RwLock};
 use tokio::time::{interval, sleep};
-use futures::stream::{Stream, StreamExt};
+use futures::future;
+use futures::stream::{self, Stream, StreamExt};
 
 // ========== EVENT DEFINITIONS ==========
 #[derive(Debug, Clone)]
@@ -41,25 +59,80 @@ impl Event {
 struct EventSource {
     next_id: u64,
     tx: mpsc::Sender<Event>,
+    stats: Arc<BackpressureStats>,
 }
 
 impl EventSource {
     fn new(buffer_size: usize) -> (Self, mpsc::Receiver<Event>) {
+        Self::resume_from(0, buffer_size)
+    }
+
+    // Continue numbering at `offset`, e.g. the one returned by a restore
+    fn resume_from(offset: u64, buffer_size: usize) -> (Self, mpsc::Receiver<Event>) {
         let (tx, rx) = mpsc::channel(buffer_size);
         (
-            EventSource { next_id: 0, tx },
+            EventSource {
+                next_id: offset,
+                tx,
+                stats: Arc::new(BackpressureStats {
+                    capacity: buffer_size,
+                    ..Default::default()
+                }),
+            },
             rx,
         )
     }
 
-    async fn emit(&mut self, event_type: String, value: f64) -> Result<(), String> {
+    fn stats(&self) -> Arc<BackpressureStats> {
+        self.stats.clone()
+    }
+
+    fn next_event(&mut self, event_type: String, value: f64) -> Event {
         let event = Event::new(self.next_id, event_type, value);
         self.next_id += 1;
+        event
+    }
+
+    // Waits while the buffer is full, so a slow consumer slows the producer
+    // down instead of losing events
+    async fn emit(&mut self, event_type: String, value: f64) -> Result<(), String> {
+        let event = self.next_event(event_type, value);
 
+        let event = match self.tx.try_send(event) {
+            Ok(()) => {
+                self.stats.sent.fetch_add(1, Ordering::Relaxed);
+                return Ok(());
+            }
+            Err(mpsc::error::TrySendError::Full(event)) => {
+                self.stats.paused.fetch_add(1, Ordering::Relaxed);
+                event
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => return Err("Failed to send event".to_string()),
+        };
         self.tx
             .send(event)
             .await
-            .map_err(|_| "Failed to send event".to_string())
+            .map_err(|_| "Failed to send event".to_string())?;
+        self.stats.sent.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    // Never waits: for producers that cannot block (e.g. sampling a sensor),
+    // an event that finds the buffer full is dropped and counted
+    fn try_emit(&mut self, event_type: String, value: f64) -> Result<(), String> {
+        let event = self.next_event(event_type, value);
+
+        match self.tx.try_send(event) {
+            Ok(()) => {
+                self.stats.sent.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            }
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => Err("Failed to send event".to_string()),
+        }
     }
 
     async fn emit_batch(&mut self, events: Vec<(String, f64)>) -> Result<(), String> {
@@ -70,26 +143,219 @@ impl EventSource {
     }
 }
 
+// ========== REPLAYABLE SOURCE ==========
+// Reads timestamped events from a log file, or from a directory holding one
+// file per partition, as `<timestamp_ms>,<event_type>,<value>` lines ('#'
+// starts a comment). An event's offset is its position among the event lines
+// of its partition and doubles as its id, so a replay from a stored offset
+// sees exactly the same events again.
+
+#[derive(Debug)]
+enum SourceError {
+    Io(String),
+    Parse { file: String, line: usize, reason: String },
+    UnknownPartition(String),
+}
+
+impl fmt::Display for SourceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SourceError::Io(msg) => write!(f, "IO error: {}", msg),
+            SourceError::Parse { file, line, reason } => write!(f, "{}:{}: {}", file, line, reason),
+            SourceError::UnknownPartition(name) => write!(f, "no partition named '{}'", name),
+        }
+    }
+}
+
+impl From<io::Error> for SourceError {
+    fn from(e: io::Error) -> Self {
+        SourceError::Io(e.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Pacing {
+    // Emit as fast as the consumer takes events
+    Unpaced,
+    // Wait out the gaps between event times, sped up by `speed`
+    EventTime { speed: f64 },
+}
+
+struct Partition {
+    name: String,
+    events: Vec<Event>,
+    next: usize,
+}
+
+impl Partition {
+    fn load(path: &Path) -> Result<Self, SourceError> {
+        let name = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default()
+            .to_string();
+        let text = std::fs::read_to_string(path)?;
+
+        let mut events = Vec::new();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let parse_error = |reason: &str| SourceError::Parse {
+                file: path.display().to_string(),
+                line: n + 1,
+                reason: reason.to_string(),
+            };
+            let mut fields = line.splitn(3, ',');
+            let timestamp = fields
+                .next()
+                .and_then(|f| f.trim().parse().ok())
+                .ok_or_else(|| parse_error("expected a timestamp in ms"))?;
+            let event_type = fields.next().ok_or_else(|| parse_error("missing event type"))?;
+            let value = fields
+                .next()
+                .and_then(|f| f.trim().parse().ok())
+                .ok_or_else(|| parse_error("expected a value"))?;
+            events.push(Event {
+                timestamp,
+                ..Event::new(events.len() as u64, event_type.trim().to_string(), value)
+            });
+        }
+        Ok(Partition { name, events, next: 0 })
+    }
+}
+
+// Next offset to read per partition, updated as events are emitted
+type ReplayOffsets = Arc<std::sync::Mutex<BTreeMap<String, u64>>>;
+
+struct ReplaySource {
+    partitions: Vec<Partition>,
+    pacing: Pacing,
+}
+
+impl ReplaySource {
+    // A file is a single partition; a directory is one partition per file,
+    // in file name order
+    fn open(path: &Path) -> Result<Self, SourceError> {
+        let partitions = if path.is_dir() {
+            let mut files: Vec<PathBuf> = std::fs::read_dir(path)?
+                .map(|entry| entry.map(|e| e.path()))
+                .collect::<Result<_, _>>()?;
+            files.retain(|file| file.is_file());
+            files.sort();
+            files.iter().map(|file| Partition::load(file)).collect::<Result<_, _>>()?
+        } else {
+            vec![Partition::load(path)?]
+        };
+        Ok(ReplaySource {
+            partitions,
+            pacing: Pacing::Unpaced,
+        })
+    }
+
+    fn pacing(mut self, pacing: Pacing) -> Self {
+        self.pacing = pacing;
+        self
+    }
+
+    // Resume a partition at `offset`, e.g. one recorded in a checkpoint
+    fn seek(mut self, partition: &str, offset: u64) -> Result<Self, SourceError> {
+        let found = self
+            .partitions
+            .iter_mut()
+            .find(|p| p.name == partition)
+            .ok_or_else(|| SourceError::UnknownPartition(partition.to_string()))?;
+        found.next = (offset as usize).min(found.events.len());
+        Ok(self)
+    }
+
+    fn offsets(&self) -> BTreeMap<String, u64> {
+        self.partitions
+            .iter()
+            .map(|p| (p.name.clone(), p.next as u64))
+            .collect()
+    }
+
+    // Interleave the partitions in event-time order (ties go to the earlier
+    // partition), so every replay yields the same sequence
+    fn next_event(&mut self) -> Option<(String, Event)> {
+        let partition = self
+            .partitions
+            .iter_mut()
+            .filter(|p| p.next < p.events.len())
+            .min_by_key(|p| p.events[p.next].timestamp)?;
+        let event = partition.events[partition.next].clone();
+        partition.next += 1;
+        Some((partition.name.clone(), event))
+    }
+
+    fn into_stream(self) -> (EventStream, ReplayOffsets) {
+        let offsets: ReplayOffsets = Arc::new(std::sync::Mutex::new(self.offsets()));
+        let tracker = offsets.clone();
+
+        let stream = stream::unfold((self, None::<u64>), move |(mut source, previous)| {
+            let tracker = tracker.clone();
+            async move {
+                let (partition, event) = source.next_event()?;
+                if let (Pacing::EventTime { speed }, Some(previous)) = (source.pacing, previous) {
+                    let gap = event.timestamp.saturating_sub(previous) as f64 / speed.max(f64::EPSILON);
+                    sleep(Duration::from_millis(gap as u64)).await;
+                }
+                tracker.lock().unwrap().insert(partition, event.id + 1);
+                let timestamp = event.timestamp;
+                Some((event, (source, Some(timestamp))))
+            }
+        });
+        (Box::pin(stream), offsets)
+    }
+}
+
 // ========== BACKPRESSURE STREAM ==========
+// Flow control comes from the bounded channel: the stream only takes an
+// event when the consumer polls for one, and a full channel makes
+// `EventSource::emit` wait until the consumer catches up
+
+// Shared between a source and its stream; plain atomics, so reading them
+// never contends with the pipeline
+#[derive(Debug, Default)]
+struct BackpressureStats {
+    capacity: usize,
+    sent: AtomicU64,
+    delivered: AtomicU64,
+    // emit() calls that had to wait for buffer space
+    paused: AtomicU64,
+    // try_emit() calls rejected because the buffer was full
+    dropped: AtomicU64,
+}
+
+impl BackpressureStats {
+    // Events in the channel, waiting for the consumer
+    fn buffered(&self) -> u64 {
+        self.sent.load(Ordering::Relaxed).saturating_sub(self.delivered())
+    }
+
+    fn delivered(&self) -> u64 {
+        self.delivered.load(Ordering::Relaxed)
+    }
+
+    fn paused(&self) -> u64 {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
 struct BackpressureStream {
     rx: mpsc::Receiver<Event>,
-    buffer: VecDeque<Event>,
-    max_buffer: usize,
-    dropped_count: Arc<RwLock<u64>>,
+    stats: Arc<BackpressureStats>,
 }
 
 impl BackpressureStream {
-    fn new(rx: mpsc::Receiver<Event>, max_buffer: usize) -> Self {
-        BackpressureStream {
-            rx,
-            buffer: VecDeque::new(),
-            max_buffer,
-            dropped_count: Arc::new(RwLock::new(0)),
-        }
-    }
-
-    async fn get_dropped_count(&self) -> u64 {
-        *self.dropped_count.read().await
+    fn new(rx: mpsc::Receiver<Event>, stats: Arc<BackpressureStats>) -> Self {
+        BackpressureStream { rx, stats }
     }
 }
 
@@ -97,28 +363,441 @@ impl Stream for BackpressureStream {
     type Item = Event;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        if let Some(event) = self.buffer.pop_front() {
-            return Poll::Ready(Some(event));
+        let polled = self.rx.poll_recv(cx);
+        if let Poll::Ready(Some(_)) = polled {
+            self.stats.delivered.fetch_add(1, Ordering::Relaxed);
         }
+        polled
+    }
+}
 
-        match self.rx.poll_recv(cx) {
-            Poll::Ready(Some(event)) => {
-                if self.buffer.len() >= self.max_buffer {
-                    let dropped = self.dropped_count.clone();
-                    tokio::spawn(async move {
-                        let mut count = dropped.write().await;
-                        *count += 1;
-                    });
-                    cx.waker().wake_by_ref();
-                    Poll::Pending
-                } else {
-                    Poll::Ready(Some(event))
-                }
+// ========== AGGREGATION FUNCTIONS ==========
+// A window aggregate built from a per-window accumulator: `fold` adds one
+// event, `merge` combines two partial accumulators (e.g. sliding-window
+// panes), and `emit` reads out the final value
+trait Aggregator: Send + Sync + 'static {
+    type Acc: Send + 'static;
+
+    fn name(&self) -> String;
+    fn init(&self) -> Self::Acc;
+    fn fold(&self, acc: &mut Self::Acc, event: &Event);
+    fn merge(&self, acc: &mut Self::Acc, other: &Self::Acc);
+    fn emit(&self, acc: &Self::Acc) -> f64;
+}
+
+// Object-safe view of an Aggregator, so one processor can hold aggregators
+// with different accumulator types
+type AccState = Box<dyn Any + Send>;
+
+trait DynAggregator: Send + Sync {
+    fn name(&self) -> String;
+    fn init(&self) -> AccState;
+    fn fold(&self, acc: &mut AccState, event: &Event);
+    fn merge(&self, acc: &mut AccState, other: &AccState);
+    fn emit(&self, acc: &AccState) -> f64;
+}
+
+impl<A: Aggregator> DynAggregator for A {
+    fn name(&self) -> String {
+        Aggregator::name(self)
+    }
+
+    fn init(&self) -> AccState {
+        Box::new(Aggregator::init(self))
+    }
+
+    fn fold(&self, acc: &mut AccState, event: &Event) {
+        Aggregator::fold(self, downcast_mut::<A>(acc), event)
+    }
+
+    fn merge(&self, acc: &mut AccState, other: &AccState) {
+        let other = other.downcast_ref::<A::Acc>().expect("accumulator from another aggregator");
+        Aggregator::merge(self, downcast_mut::<A>(acc), other)
+    }
+
+    fn emit(&self, acc: &AccState) -> f64 {
+        Aggregator::emit(self, acc.downcast_ref::<A::Acc>().expect("accumulator from another aggregator"))
+    }
+}
+
+fn downcast_mut<A: Aggregator>(acc: &mut AccState) -> &mut A::Acc {
+    acc.downcast_mut::<A::Acc>().expect("accumulator from another aggregator")
+}
+
+struct Sum;
+
+impl Aggregator for Sum {
+    type Acc = f64;
+
+    fn name(&self) -> String {
+        "sum".to_string()
+    }
+    fn init(&self) -> f64 {
+        0.0
+    }
+    fn fold(&self, acc: &mut f64, event: &Event) {
+        *acc += event.value;
+    }
+    fn merge(&self, acc: &mut f64, other: &f64) {
+        *acc += other;
+    }
+    fn emit(&self, acc: &f64) -> f64 {
+        *acc
+    }
+}
+
+struct Mean;
+
+impl Aggregator for Mean {
+    // (sum, count)
+    type Acc = (f64, u64);
+
+    fn name(&self) -> String {
+        "avg".to_string()
+    }
+    fn init(&self) -> (f64, u64) {
+        (0.0, 0)
+    }
+    fn fold(&self, acc: &mut (f64, u64), event: &Event) {
+        acc.0 += event.value;
+        acc.1 += 1;
+    }
+    fn merge(&self, acc: &mut (f64, u64), other: &(f64, u64)) {
+        acc.0 += other.0;
+        acc.1 += other.1;
+    }
+    fn emit(&self, acc: &(f64, u64)) -> f64 {
+        if acc.1 == 0 {
+            0.0
+        } else {
+            acc.0 / acc.1 as f64
+        }
+    }
+}
+
+struct Min;
+
+impl Aggregator for Min {
+    type Acc = f64;
+
+    fn name(&self) -> String {
+        "min".to_string()
+    }
+    fn init(&self) -> f64 {
+        f64::INFINITY
+    }
+    fn fold(&self, acc: &mut f64, event: &Event) {
+        *acc = acc.min(event.value);
+    }
+    fn merge(&self, acc: &mut f64, other: &f64) {
+        *acc = acc.min(*other);
+    }
+    fn emit(&self, acc: &f64) -> f64 {
+        *acc
+    }
+}
+
+struct Max;
+
+impl Aggregator for Max {
+    type Acc = f64;
+
+    fn name(&self) -> String {
+        "max".to_string()
+    }
+    fn init(&self) -> f64 {
+        f64::NEG_INFINITY
+    }
+    fn fold(&self, acc: &mut f64, event: &Event) {
+        *acc = acc.max(event.value);
+    }
+    fn merge(&self, acc: &mut f64, other: &f64) {
+        *acc = acc.max(*other);
+    }
+    fn emit(&self, acc: &f64) -> f64 {
+        *acc
+    }
+}
+
+// Log-bucketed quantile sketch (DDSketch-style): every value lands in a
+// bucket whose width is a fixed fraction of its magnitude, so estimates stay
+// within `relative_accuracy` of the true quantile and sketches merge by
+// adding bucket counts
+#[derive(Debug, Clone)]
+struct QuantileSketch {
+    gamma: f64,
+    positive: BTreeMap<i32, u64>,
+    negative: BTreeMap<i32, u64>,
+    zeros: u64,
+    count: u64,
+}
+
+impl QuantileSketch {
+    const MIN_MAGNITUDE: f64 = 1e-9;
+
+    fn new(relative_accuracy: f64) -> Self {
+        QuantileSketch {
+            gamma: (1.0 + relative_accuracy) / (1.0 - relative_accuracy),
+            positive: BTreeMap::new(),
+            negative: BTreeMap::new(),
+            zeros: 0,
+            count: 0,
+        }
+    }
+
+    fn bucket(&self, magnitude: f64) -> i32 {
+        (magnitude.ln() / self.gamma.ln()).ceil() as i32
+    }
+
+    // Midpoint of a bucket, in relative terms
+    fn bucket_value(&self, index: i32) -> f64 {
+        2.0 * self.gamma.powi(index) / (self.gamma + 1.0)
+    }
+
+    fn insert(&mut self, value: f64) {
+        if value > Self::MIN_MAGNITUDE {
+            *self.positive.entry(self.bucket(value)).or_insert(0) += 1;
+        } else if value < -Self::MIN_MAGNITUDE {
+            *self.negative.entry(self.bucket(-value)).or_insert(0) += 1;
+        } else {
+            self.zeros += 1;
+        }
+        self.count += 1;
+    }
+
+    fn merge(&mut self, other: &QuantileSketch) {
+        for (&index, &n) in &other.positive {
+            *self.positive.entry(index).or_insert(0) += n;
+        }
+        for (&index, &n) in &other.negative {
+            *self.negative.entry(index).or_insert(0) += n;
+        }
+        self.zeros += other.zeros;
+        self.count += other.count;
+    }
+
+    // `q` in [0, 1]; NaN for an empty sketch
+    fn quantile(&self, q: f64) -> f64 {
+        if self.count == 0 {
+            return f64::NAN;
+        }
+        let rank = (q.clamp(0.0, 1.0) * (self.count - 1) as f64).round() as u64;
+        let mut seen = 0;
+        // Ascending order: large negatives first, then zeros, then positives
+        for (&index, &n) in self.negative.iter().rev() {
+            seen += n;
+            if seen > rank {
+                return -self.bucket_value(index);
+            }
+        }
+        seen += self.zeros;
+        if seen > rank {
+            return 0.0;
+        }
+        for (&index, &n) in &self.positive {
+            seen += n;
+            if seen > rank {
+                return self.bucket_value(index);
             }
-            Poll::Ready(None) => Poll::Ready(None),
-            Poll::Pending => Poll::Pending,
+        }
+        self.positive.keys().next_back().map_or(0.0, |&index| self.bucket_value(index))
+    }
+}
+
+struct Percentile {
+    quantile: f64,
+    relative_accuracy: f64,
+}
+
+impl Percentile {
+    // e.g. Percentile::new(0.99) for p99, accurate to within 1%
+    fn new(quantile: f64) -> Self {
+        Percentile {
+            quantile,
+            relative_accuracy: 0.01,
+        }
+    }
+}
+
+impl Aggregator for Percentile {
+    type Acc = QuantileSketch;
+
+    fn name(&self) -> String {
+        format!("p{}", self.quantile * 100.0)
+    }
+    fn init(&self) -> QuantileSketch {
+        QuantileSketch::new(self.relative_accuracy)
+    }
+    fn fold(&self, acc: &mut QuantileSketch, event: &Event) {
+        acc.insert(event.value);
+    }
+    fn merge(&self, acc: &mut QuantileSketch, other: &QuantileSketch) {
+        acc.merge(other);
+    }
+    fn emit(&self, acc: &QuantileSketch) -> f64 {
+        acc.quantile(self.quantile)
+    }
+}
+
+// HyperLogLog cardinality estimator with 2^precision registers; standard
+// error is about 1.04 / sqrt(2^precision)
+#[derive(Debug, Clone)]
+struct HyperLogLog {
+    precision: u32,
+    registers: Vec<u8>,
+}
+
+impl HyperLogLog {
+    fn new(precision: u32) -> Self {
+        assert!((4..=16).contains(&precision));
+        HyperLogLog {
+            precision,
+            registers: vec![0; 1 << precision],
+        }
+    }
+
+    fn insert<T: Hash>(&mut self, item: &T) {
+        let mut hasher = DefaultHasher::new();
+        item.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        let index = (hash >> (64 - self.precision)) as usize;
+        // Guard bit keeps the rank bounded when the remaining bits are zero
+        let rest = (hash << self.precision) | (1 << (self.precision - 1));
+        let rank = rest.leading_zeros() as u8 + 1;
+        self.registers[index] = self.registers[index].max(rank);
+    }
+
+    fn merge(&mut self, other: &HyperLogLog) {
+        assert_eq!(self.precision, other.precision);
+        for (mine, theirs) in self.registers.iter_mut().zip(&other.registers) {
+            *mine = (*mine).max(*theirs);
+        }
+    }
+
+    fn estimate(&self) -> f64 {
+        let m = self.registers.len() as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let harmonic: f64 = self.registers.iter().map(|&r| 2f64.powi(-(r as i32))).sum();
+        let raw = alpha * m * m / harmonic;
+
+        // Linear counting is more accurate while many registers are empty
+        let empty = self.registers.iter().filter(|&&r| r == 0).count();
+        if raw <= 2.5 * m && empty > 0 {
+            m * (m / empty as f64).ln()
+        } else {
+            raw
+        }
+    }
+}
+
+// Approximate number of distinct values of `key_fn` per window
+struct DistinctCount {
+    name: String,
+    key_fn: Box<dyn Fn(&Event) -> String + Send + Sync>,
+    precision: u32,
+}
+
+impl DistinctCount {
+    fn new<F>(name: &str, key_fn: F) -> Self
+    where
+        F: Fn(&Event) -> String + Send + Sync + 'static,
+    {
+        DistinctCount {
+            name: name.to_string(),
+            key_fn: Box::new(key_fn),
+            precision: 12,
+        }
+    }
+}
+
+impl Aggregator for DistinctCount {
+    type Acc = HyperLogLog;
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+    fn init(&self) -> HyperLogLog {
+        HyperLogLog::new(self.precision)
+    }
+    fn fold(&self, acc: &mut HyperLogLog, event: &Event) {
+        acc.insert(&(self.key_fn)(event));
+    }
+    fn merge(&self, acc: &mut HyperLogLog, other: &HyperLogLog) {
+        acc.merge(other);
+    }
+    fn emit(&self, acc: &HyperLogLog) -> f64 {
+        acc.estimate().round()
+    }
+}
+
+// The aggregators a windowed stream computes for every window
+#[derive(Clone)]
+struct Aggregations {
+    aggregators: Vec<Arc<dyn DynAggregator>>,
+}
+
+// Partial aggregate for a window (or a pane of one)
+struct WindowAccumulator {
+    event_count: usize,
+    states: Vec<AccState>,
+}
+
+impl Aggregations {
+    // sum, avg, min and max of the event values
+    fn defaults() -> Self {
+        Aggregations {
+            aggregators: vec![Arc::new(Sum), Arc::new(Mean), Arc::new(Min), Arc::new(Max)],
+        }
+    }
+
+    fn push(&mut self, aggregator: impl Aggregator) {
+        self.aggregators.push(Arc::new(aggregator));
+    }
+
+    fn init(&self) -> WindowAccumulator {
+        WindowAccumulator {
+            event_count: 0,
+            states: self.aggregators.iter().map(|a| a.init()).collect(),
+        }
+    }
+
+    fn fold(&self, acc: &mut WindowAccumulator, event: &Event) {
+        acc.event_count += 1;
+        for (aggregator, state) in self.aggregators.iter().zip(&mut acc.states) {
+            aggregator.fold(state, event);
+        }
+    }
+
+    fn merge(&self, acc: &mut WindowAccumulator, other: &WindowAccumulator) {
+        acc.event_count += other.event_count;
+        for ((aggregator, state), other) in self.aggregators.iter().zip(&mut acc.states).zip(&other.states) {
+            aggregator.merge(state, other);
         }
     }
+
+    fn emit(&self, acc: &WindowAccumulator, window_start: u64, window_end: u64) -> WindowResult {
+        WindowResult {
+            key: None,
+            window_start,
+            window_end,
+            event_count: acc.event_count,
+            values: self
+                .aggregators
+                .iter()
+                .zip(&acc.states)
+                .map(|(aggregator, state)| (aggregator.name(), aggregator.emit(state)))
+                .collect(),
+        }
+    }
+
+    fn aggregate(&self, events: &[Event], window_start: u64, window_end: u64) -> WindowResult {
+        let mut acc = self.init();
+        for event in events {
+            self.fold(&mut acc, event);
+        }
+        self.emit(&acc, window_start, window_end)
+    }
 }
 
 // ========== WINDOWING OPERATIONS ==========
@@ -131,26 +810,27 @@ enum WindowType {
 
 #[derive(Debug, Clone)]
 struct WindowResult {
+    // Set when the stream is keyed; None for a global window
+    key: Option<String>,
     window_start: u64,
     window_end: u64,
     event_count: usize,
-    sum: f64,
-    avg: f64,
-    min: f64,
-    max: f64,
+    // (aggregator name, value) in registration order
+    values: Vec<(String, f64)>,
 }
 
-struct WindowedStream {
+type KeyFn = Arc<dyn Fn(&Event) -> String + Send + Sync>;
+
+// Buffered events and window progress for one key (or the whole stream)
+struct WindowState {
     events: BTreeMap<u64, Vec<Event>>,
-    window_type: WindowType,
     last_window_end: u64,
 }
 
-impl WindowedStream {
-    fn new(window_type: WindowType) -> Self {
-        WindowedStream {
+impl WindowState {
+    fn new() -> Self {
+        WindowState {
             events: BTreeMap::new(),
-            window_type,
             last_window_end: 0,
         }
     }
@@ -162,220 +842,1509 @@ impl WindowedStream {
             .push(event);
     }
 
-    fn compute_windows(&mut self, current_time: u64) -> Vec<WindowResult> {
+    fn compute_windows(
+        &mut self,
+        window_type: &WindowType,
+        aggregations: &Aggregations,
+        current_time: u64,
+    ) -> Vec<WindowResult> {
         let mut results = Vec::new();
 
-        match self.window_type {
-            WindowType::Tumbling(duration) => {
-                let window_size = duration.as_millis() as u64;
+        match *window_type {
+            WindowType::Tumbling(duration) => {
+                let window_size = (duration.as_millis() as u64).max(1);
+
+                // Windows are aligned to multiples of the size; only closed
+                // windows are emitted and evicted, open ones keep buffering
+                let mut closed: BTreeMap<u64, Vec<Event>> = BTreeMap::new();
+                self.events.retain(|&ts, events| {
+                    let window_start = ts - ts % window_size;
+                    if window_start + window_size <= current_time {
+                        closed.entry(window_start).or_default().append(events);
+                        false
+                    } else {
+                        true
+                    }
+                });
+
+                for (window_start, window_events) in closed {
+                    results.push(aggregations.aggregate(
+                        &window_events,
+                        window_start,
+                        window_start + window_size,
+                    ));
+                }
+            }
+
+            WindowType::Sliding { size, slide } => {
+                let window_size = size.as_millis() as u64;
+                let slide_size = slide.as_millis() as u64;
+
+                // A fresh key starts at its first event, not at the epoch
+                if self.last_window_end == 0 {
+                    if let Some(&first) = self.events.keys().next() {
+                        self.last_window_end = first - first % slide_size.max(1);
+                    }
+                }
+
+                // When the size is a whole number of slides, each slide-sized
+                // pane is folded once and windows merge their panes
+                let use_panes = slide_size > 0 && window_size.is_multiple_of(slide_size);
+                let mut panes: BTreeMap<u64, WindowAccumulator> = BTreeMap::new();
+                let fold_range = |start: u64, end: u64| {
+                    let mut acc = aggregations.init();
+                    for event in self.events.range(start..end).flat_map(|(_, events)| events.iter()) {
+                        aggregations.fold(&mut acc, event);
+                    }
+                    acc
+                };
+
+                let mut window_start = self.last_window_end;
+                while window_start + window_size <= current_time {
+                    let window_end = window_start + window_size;
+
+                    let window = if use_panes {
+                        let mut window = aggregations.init();
+                        for pane_start in (window_start..window_end).step_by(slide_size as usize) {
+                            let pane = panes
+                                .entry(pane_start)
+                                .or_insert_with(|| fold_range(pane_start, pane_start + slide_size));
+                            aggregations.merge(&mut window, pane);
+                        }
+                        window
+                    } else {
+                        fold_range(window_start, window_end)
+                    };
+
+                    if window.event_count > 0 {
+                        results.push(aggregations.emit(&window, window_start, window_end));
+                    }
+
+                    window_start += slide_size;
+                    self.last_window_end = window_start;
+                }
+
+                let cutoff = current_time.saturating_sub(window_size);
+                self.events.retain(|&ts, _| ts >= cutoff);
+            }
+
+            WindowType::Session { gap } => {
+                let gap_ms = gap.as_millis() as u64;
+                let mut session_start = 0u64;
+                let mut session_events = Vec::new();
+                let mut last_event_time = 0u64;
+
+                let all_events: Vec<Event> = self
+                    .events
+                    .values()
+                    .flat_map(|events| events.iter())
+                    .cloned()
+                    .collect();
+
+                for event in all_events {
+                    if session_events.is_empty() {
+                        session_start = event.timestamp;
+                        session_events.push(event.clone());
+                        last_event_time = event.timestamp;
+                    } else if event.timestamp - last_event_time <= gap_ms {
+                        session_events.push(event.clone());
+                        last_event_time = event.timestamp;
+                    } else {
+                        if current_time - last_event_time > gap_ms {
+                            results.push(aggregations.aggregate(
+                                &session_events,
+                                session_start,
+                                last_event_time,
+                            ));
+                        }
+                        session_start = event.timestamp;
+                        session_events = vec![event.clone()];
+                        last_event_time = event.timestamp;
+                    }
+                }
+
+                if !session_events.is_empty() && current_time - last_event_time > gap_ms {
+                    results.push(aggregations.aggregate(
+                        &session_events,
+                        session_start,
+                        last_event_time,
+                    ));
+                }
+
+                self.events.retain(|&ts, _| current_time - ts <= gap_ms * 2);
+            }
+        }
+
+        results
+    }
+}
+
+struct WindowedStream {
+    window_type: WindowType,
+    key_fn: Option<KeyFn>,
+    aggregations: Aggregations,
+    // Events consumed so far: the input position this state reflects
+    offset: u64,
+    // One independent state per key; a single None entry when unkeyed
+    partitions: HashMap<Option<String>, WindowState>,
+}
+
+impl WindowedStream {
+    fn new(window_type: WindowType) -> Self {
+        WindowedStream {
+            window_type,
+            key_fn: None,
+            aggregations: Aggregations::defaults(),
+            offset: 0,
+            partitions: HashMap::new(),
+        }
+    }
+
+    // Partition the stream so every key gets its own windows
+    fn key_by<F>(mut self, key_fn: F) -> Self
+    where
+        F: Fn(&Event) -> String + Send + Sync + 'static,
+    {
+        self.key_fn = Some(Arc::new(key_fn));
+        self
+    }
+
+    // Compute an extra aggregate for every window, after sum/avg/min/max
+    fn aggregate(mut self, aggregator: impl Aggregator) -> Self {
+        self.aggregations.push(aggregator);
+        self
+    }
+
+    fn add_event(&mut self, event: Event) {
+        self.offset += 1;
+        let key = self.key_fn.as_ref().map(|key_fn| key_fn(&event));
+        self.partitions
+            .entry(key)
+            .or_insert_with(WindowState::new)
+            .add_event(event);
+    }
+
+    fn compute_windows(&mut self, current_time: u64) -> Vec<WindowResult> {
+        let mut results = Vec::new();
+        for (key, state) in self.partitions.iter_mut() {
+            for mut result in state.compute_windows(&self.window_type, &self.aggregations, current_time) {
+                result.key = key.clone();
+                results.push(result);
+            }
+        }
+        // Keys that have gone quiet hold no state until they emit again.
+        // Sliding windows also track progress, so their partitions are kept.
+        let keep_idle = matches!(self.window_type, WindowType::Sliding { .. });
+        self.partitions
+            .retain(|_, state| keep_idle || !state.events.is_empty());
+        results.sort_by(|a, b| (a.window_start, &a.key).cmp(&(b.window_start, &b.key)));
+        results
+    }
+}
+
+// ========== STREAM PROCESSORS ==========
+struct StreamProcessor {
+    name: String,
+    windowed_stream: Arc<RwLock<WindowedStream>>,
+    metrics: Arc<ProcessorMetrics>,
+}
+
+impl StreamProcessor {
+    fn new(name: String, window_type: WindowType) -> Self {
+        StreamProcessor {
+            name,
+            windowed_stream: Arc::new(RwLock::new(WindowedStream::new(window_type))),
+            metrics: Arc::new(ProcessorMetrics::new()),
+        }
+    }
+
+    // Reconfigure the windowed stream; only valid before the processor is run
+    fn configure(self, f: impl FnOnce(WindowedStream) -> WindowedStream) -> Self {
+        let stream = Arc::try_unwrap(self.windowed_stream)
+            .ok()
+            .expect("processor must be configured before it is shared")
+            .into_inner();
+        StreamProcessor {
+            name: self.name,
+            windowed_stream: Arc::new(RwLock::new(f(stream))),
+            metrics: self.metrics,
+        }
+    }
+
+    // Window per key instead of globally
+    fn key_by<F>(self, key_fn: F) -> Self
+    where
+        F: Fn(&Event) -> String + Send + Sync + 'static,
+    {
+        self.configure(|stream| stream.key_by(key_fn))
+    }
+
+    // Register an extra per-window aggregate
+    fn aggregate(self, aggregator: impl Aggregator) -> Self {
+        self.configure(|stream| stream.aggregate(aggregator))
+    }
+
+    async fn process_event(&self, event: Event) {
+        self.metrics.record_event(&event);
+        let mut stream = self.windowed_stream.write().await;
+        stream.add_event(event);
+    }
+
+    async fn compute_windows(&self) -> Vec<WindowResult> {
+        let current_time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+
+        self.compute_windows_at(current_time).await
+    }
+
+    async fn compute_windows_at(&self, current_time: u64) -> Vec<WindowResult> {
+        let mut stream = self.windowed_stream.write().await;
+        stream.compute_windows(current_time)
+    }
+
+    async fn run(&self, mut input_stream: EventStream, mut sink: impl Sink + 'static) {
+        let name = self.name.clone();
+        println!("[{}] Stream processor started", name);
+
+        let processor = self.clone();
+        tokio::spawn(async move {
+            while let Some(event) = input_stream.next().await {
+                processor.process_event(event).await;
+            }
+            println!("[{}] Stream ended", name);
+        });
+
+        let processor = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = interval(Duration::from_secs(2));
+            let mut pending = VecDeque::new();
+            loop {
+                ticker.tick().await;
+                let results = processor.compute_windows().await;
+                processor.metrics.windows.fetch_add(results.len() as u64, Ordering::Relaxed);
+                pending.extend(results);
+                deliver(&mut sink, &processor.name, &mut pending).await;
+            }
+        });
+    }
+}
+
+fn print_window(processor: &str, result: &WindowResult) {
+    let key = result.key.as_deref().map(|k| format!(" {}", k)).unwrap_or_default();
+    let values: Vec<String> = result
+        .values
+        .iter()
+        .map(|(name, value)| format!("{}={:.2}", name, value))
+        .collect();
+    println!(
+        "[{}]{} Window [{} - {}]: count={}, {}",
+        processor,
+        key,
+        result.window_start,
+        result.window_end,
+        result.event_count,
+        values.join(", ")
+    );
+}
+
+impl Clone for StreamProcessor {
+    fn clone(&self) -> Self {
+        StreamProcessor {
+            name: self.name.clone(),
+            windowed_stream: self.windowed_stream.clone(),
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+// ========== PIPELINE DSL ==========
+type EventStream = Pin<Box<dyn Stream<Item = Event> + Send>>;
+
+// Composable operators over an event stream, finished by a windowed processor:
+// Pipeline::from_receiver(rx).filter(..).map(..).window(..).sink(..)
+struct Pipeline {
+    stream: EventStream,
+}
+
+impl Pipeline {
+    fn from_stream<S>(stream: S) -> Self
+    where
+        S: Stream<Item = Event> + Send + 'static,
+    {
+        Pipeline {
+            stream: Box::pin(stream),
+        }
+    }
+
+    fn from_receiver(rx: mpsc::Receiver<Event>) -> Self {
+        Pipeline::from_stream(stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|event| (event, rx))
+        }))
+    }
+
+    fn map<F>(self, f: F) -> Self
+    where
+        F: FnMut(Event) -> Event + Send + 'static,
+    {
+        Pipeline::from_stream(self.stream.map(f))
+    }
+
+    fn filter<F>(self, mut predicate: F) -> Self
+    where
+        F: FnMut(&Event) -> bool + Send + 'static,
+    {
+        Pipeline::from_stream(self.stream.filter(move |event| future::ready(predicate(event))))
+    }
+
+    fn flat_map<F, I>(self, mut f: F) -> Self
+    where
+        F: FnMut(Event) -> I + Send + 'static,
+        I: IntoIterator<Item = Event>,
+        I::IntoIter: Send + 'static,
+    {
+        Pipeline::from_stream(self.stream.flat_map(move |event| stream::iter(f(event))))
+    }
+
+    // Interleave two streams in arrival order
+    fn merge(self, other: Pipeline) -> Self {
+        Pipeline::from_stream(stream::select(self.stream, other.stream))
+    }
+
+    // Pair up events from both streams that share a key and whose event
+    // times are at most `within` apart; `combine` receives (left, right)
+    fn join<K, C>(self, other: Pipeline, within: Duration, key_fn: K, combine: C) -> Self
+    where
+        K: Fn(&Event) -> String + Send + 'static,
+        C: Fn(&Event, &Event) -> Event + Send + 'static,
+    {
+        let left = self.stream.map(JoinSide::Left);
+        let right = other.stream.map(JoinSide::Right);
+        let mut state = JoinState::new(within.as_millis() as u64);
+
+        Pipeline::from_stream(stream::select(left, right).flat_map(move |side| {
+            stream::iter(state.push(side, &key_fn, &combine))
+        }))
+    }
+
+    fn window(self, name: &str, window_type: WindowType) -> WindowedPipeline {
+        WindowedPipeline {
+            stream: self.stream,
+            processor: StreamProcessor::new(name.to_string(), window_type),
+        }
+    }
+}
+
+enum JoinSide {
+    Left(Event),
+    Right(Event),
+}
+
+// Per-key buffers for each side of a join, evicted once the newest event
+// time has moved more than one join window past them
+struct JoinState {
+    within_ms: u64,
+    watermark: u64,
+    left: HashMap<String, Vec<Event>>,
+    right: HashMap<String, Vec<Event>>,
+}
+
+impl JoinState {
+    fn new(within_ms: u64) -> Self {
+        JoinState {
+            within_ms,
+            watermark: 0,
+            left: HashMap::new(),
+            right: HashMap::new(),
+        }
+    }
+
+    fn push<K, C>(&mut self, side: JoinSide, key_fn: &K, combine: &C) -> Vec<Event>
+    where
+        K: Fn(&Event) -> String,
+        C: Fn(&Event, &Event) -> Event,
+    {
+        let within_ms = self.within_ms;
+        let (event, is_left) = match side {
+            JoinSide::Left(event) => (event, true),
+            JoinSide::Right(event) => (event, false),
+        };
+        let key = key_fn(&event);
+        self.watermark = self.watermark.max(event.timestamp);
+
+        let (own, others) = if is_left {
+            (&mut self.left, &self.right)
+        } else {
+            (&mut self.right, &self.left)
+        };
+        let joined = others
+            .get(&key)
+            .into_iter()
+            .flatten()
+            .filter(|other| other.timestamp.abs_diff(event.timestamp) <= within_ms)
+            .map(|other| {
+                if is_left {
+                    combine(&event, other)
+                } else {
+                    combine(other, &event)
+                }
+            })
+            .collect();
+        own.entry(key).or_default().push(event);
+
+        let horizon = self.watermark.saturating_sub(within_ms);
+        for buffers in [&mut self.left, &mut self.right] {
+            buffers.retain(|_, events| {
+                events.retain(|event| event.timestamp >= horizon);
+                !events.is_empty()
+            });
+        }
+        joined
+    }
+}
+
+// A pipeline whose events feed a windowed stream processor
+struct WindowedPipeline {
+    stream: EventStream,
+    processor: StreamProcessor,
+}
+
+impl WindowedPipeline {
+    fn key_by<F>(self, key_fn: F) -> Self
+    where
+        F: Fn(&Event) -> String + Send + Sync + 'static,
+    {
+        WindowedPipeline {
+            stream: self.stream,
+            processor: self.processor.key_by(key_fn),
+        }
+    }
+
+    fn aggregate(self, aggregator: impl Aggregator) -> Self {
+        WindowedPipeline {
+            stream: self.stream,
+            processor: self.processor.aggregate(aggregator),
+        }
+    }
+
+    fn monitor(self, registry: &MetricsRegistry) -> Self {
+        registry.register(&self.processor, None);
+        self
+    }
+
+    // Start processing, handing every closed window to `sink`
+    async fn sink(self, sink: impl Sink + 'static) {
+        self.processor.run(self.stream, sink).await
+    }
+
+}
+
+// ========== COMPLEX EVENT PROCESSING ==========
+// Declarative sequence patterns such as "A followed by B within 5s without C".
+// Every event that matches the first step opens a run (a partial match);
+// together the open runs are the NFA's active states. A run advances to the
+// next step on the first matching event, dies on a forbidden one, and times
+// out once `within` of event time has passed since its first event.
+
+type EventPredicate = Arc<dyn Fn(&Event) -> bool + Send + Sync>;
+
+#[derive(Clone)]
+struct Step {
+    name: String,
+    predicate: EventPredicate,
+}
+
+#[derive(Clone)]
+struct Pattern {
+    name: String,
+    steps: Vec<Step>,
+    // forbidden[i] holds events that may not occur after step i has matched
+    // and before step i + 1; a run whose last step has a forbidden event
+    // only completes when its time runs out
+    forbidden: Vec<Vec<EventPredicate>>,
+    within: Duration,
+}
+
+impl Pattern {
+    fn begin<F>(name: &str, step: &str, predicate: F) -> Self
+    where
+        F: Fn(&Event) -> bool + Send + Sync + 'static,
+    {
+        Pattern {
+            name: name.to_string(),
+            steps: vec![Step {
+                name: step.to_string(),
+                predicate: Arc::new(predicate),
+            }],
+            forbidden: vec![Vec::new()],
+            within: Duration::from_secs(60),
+        }
+    }
+
+    fn followed_by<F>(mut self, step: &str, predicate: F) -> Self
+    where
+        F: Fn(&Event) -> bool + Send + Sync + 'static,
+    {
+        self.steps.push(Step {
+            name: step.to_string(),
+            predicate: Arc::new(predicate),
+        });
+        self.forbidden.push(Vec::new());
+        self
+    }
+
+    // Forbid an event between the previous step and the next one
+    fn not_followed_by<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&Event) -> bool + Send + Sync + 'static,
+    {
+        self.forbidden
+            .last_mut()
+            .expect("pattern has a first step")
+            .push(Arc::new(predicate));
+        self
+    }
+
+    fn within(mut self, within: Duration) -> Self {
+        self.within = within;
+        self
+    }
+
+    fn ends_with_negation(&self) -> bool {
+        !self.forbidden[self.steps.len() - 1].is_empty()
+    }
+}
+
+// A completed pattern: each step's name with the event that matched it
+#[derive(Debug, Clone)]
+struct PatternMatch {
+    pattern: String,
+    key: Option<String>,
+    events: Vec<(String, Event)>,
+}
+
+impl PatternMatch {
+    // Composite event typed "<pattern>[:<key>]", valued by the match's
+    // duration in seconds and stamped with its last event's time
+    fn to_event(&self) -> Event {
+        let (_, first) = &self.events[0];
+        let (_, last) = &self.events[self.events.len() - 1];
+        let event_type = match &self.key {
+            Some(key) => format!("{}:{}", self.pattern, key),
+            None => self.pattern.clone(),
+        };
+        Event {
+            timestamp: last.timestamp,
+            ..Event::new(first.id, event_type, (last.timestamp - first.timestamp) as f64 / 1000.0)
+        }
+    }
+}
+
+struct Run {
+    key: Option<String>,
+    // Index of the next step to match; steps.len() once all have matched
+    next: usize,
+    events: Vec<Event>,
+}
+
+struct PatternMatcher {
+    pattern: Pattern,
+    key_fn: Option<KeyFn>,
+    runs: Vec<Run>,
+    timed_out: u64,
+}
+
+impl PatternMatcher {
+    fn new(pattern: Pattern) -> Self {
+        PatternMatcher {
+            pattern,
+            key_fn: None,
+            runs: Vec::new(),
+            timed_out: 0,
+        }
+    }
+
+    // Match the pattern independently per key
+    fn key_by<F>(mut self, key_fn: F) -> Self
+    where
+        F: Fn(&Event) -> String + Send + Sync + 'static,
+    {
+        self.key_fn = Some(Arc::new(key_fn));
+        self
+    }
+
+    fn complete(&self, run: Run) -> PatternMatch {
+        let steps = self.pattern.steps.iter().map(|step| step.name.clone());
+        PatternMatch {
+            pattern: self.pattern.name.clone(),
+            key: run.key,
+            events: steps.zip(run.events).collect(),
+        }
+    }
+
+    // Close runs whose time has run out at `now` (event time, ms). Runs that
+    // were only waiting out a trailing negation complete; the rest time out.
+    fn expire(&mut self, now: u64) -> Vec<PatternMatch> {
+        let within = self.pattern.within.as_millis() as u64;
+        let steps = self.pattern.steps.len();
+        let (expired, open): (Vec<Run>, Vec<Run>) = std::mem::take(&mut self.runs)
+            .into_iter()
+            .partition(|run| now.saturating_sub(run.events[0].timestamp) > within);
+        self.runs = open;
+
+        let mut matches = Vec::new();
+        for run in expired {
+            if run.next == steps {
+                matches.push(self.complete(run));
+            } else {
+                self.timed_out += 1;
+            }
+        }
+        matches
+    }
+
+    fn process(&mut self, event: &Event) -> Vec<PatternMatch> {
+        let mut matches = self.expire(event.timestamp);
+        let key = self.key_fn.as_ref().map(|key_fn| key_fn(event));
+        let steps = &self.pattern.steps;
+        let forbidden = &self.pattern.forbidden;
+
+        let mut runs = Vec::with_capacity(self.runs.len() + 1);
+        for mut run in std::mem::take(&mut self.runs) {
+            if run.key != key {
+                runs.push(run);
+                continue;
+            }
+            if forbidden[run.next - 1].iter().any(|predicate| predicate(event)) {
+                continue;
+            }
+            if run.next < steps.len() && (steps[run.next].predicate)(event) {
+                run.next += 1;
+                run.events.push(event.clone());
+            }
+            runs.push(run);
+        }
+        if (steps[0].predicate)(event) {
+            runs.push(Run {
+                key,
+                next: 1,
+                events: vec![event.clone()],
+            });
+        }
+
+        // Without a trailing negation a run is done as soon as every step matched
+        let wait = self.pattern.ends_with_negation();
+        for run in runs {
+            if run.next == steps.len() && !wait {
+                matches.push(self.complete(run));
+            } else {
+                self.runs.push(run);
+            }
+        }
+        matches
+    }
+}
+
+impl Pipeline {
+    // Replace the stream with the composite events of `matcher`'s pattern;
+    // time only advances with incoming events
+    fn detect(self, mut matcher: PatternMatcher) -> Self {
+        Pipeline::from_stream(self.stream.flat_map(move |event| {
+            let matches: Vec<Event> = matcher.process(&event).iter().map(PatternMatch::to_event).collect();
+            stream::iter(matches)
+        }))
+    }
+}
+
+// ========== SINKS ==========
+// Where closed windows go. A failed write is retried with exponential
+// backoff; if the sink is still failing, the result stays queued ahead of
+// newer ones for the next tick. Nothing is dropped, but a result may be
+// written twice (at-least-once).
+
+#[derive(Debug)]
+enum SinkError {
+    Io(String),
+}
+
+impl fmt::Display for SinkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SinkError::Io(msg) => write!(f, "IO error: {}", msg),
+        }
+    }
+}
+
+impl From<io::Error> for SinkError {
+    fn from(e: io::Error) -> Self {
+        SinkError::Io(e.to_string())
+    }
+}
+
+trait Sink: Send {
+    fn write(&mut self, processor: &str, result: &WindowResult) -> Result<(), SinkError>;
+
+    fn flush(&mut self) -> Result<(), SinkError> {
+        Ok(())
+    }
+}
+
+// Any closure over (processor name, result) is an infallible sink
+impl<F> Sink for F
+where
+    F: FnMut(&str, &WindowResult) + Send,
+{
+    fn write(&mut self, processor: &str, result: &WindowResult) -> Result<(), SinkError> {
+        self(processor, result);
+        Ok(())
+    }
+}
+
+// Fan out to several sinks; a retry after a partial failure re-sends to all
+impl Sink for Vec<Box<dyn Sink>> {
+    fn write(&mut self, processor: &str, result: &WindowResult) -> Result<(), SinkError> {
+        self.iter_mut().try_for_each(|sink| sink.write(processor, result))
+    }
+
+    fn flush(&mut self) -> Result<(), SinkError> {
+        self.iter_mut().try_for_each(|sink| sink.flush())
+    }
+}
+
+const SINK_ATTEMPTS: u32 = 3;
+const SINK_BACKOFF: Duration = Duration::from_millis(100);
+
+// Write queued results in order, stopping at the first one that keeps failing
+async fn deliver(sink: &mut dyn Sink, processor: &str, pending: &mut VecDeque<WindowResult>) {
+    while let Some(result) = pending.front() {
+        let mut attempt = 1;
+        while let Err(e) = sink.write(processor, result) {
+            if attempt == SINK_ATTEMPTS {
+                eprintln!("[{}] Sink unavailable ({}), {} results queued", processor, e, pending.len());
+                return;
+            }
+            sleep(SINK_BACKOFF * 2u32.pow(attempt - 1)).await;
+            attempt += 1;
+        }
+        pending.pop_front();
+    }
+    if let Err(e) = sink.flush() {
+        eprintln!("[{}] Sink flush failed: {}", processor, e);
+    }
+}
+
+struct StdoutSink;
+
+impl Sink for StdoutSink {
+    fn write(&mut self, processor: &str, result: &WindowResult) -> Result<(), SinkError> {
+        print_window(processor, result);
+        Ok(())
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+impl WindowResult {
+    // One JSON object; non-finite values (e.g. a percentile of nothing) are null
+    fn to_json(&self, processor: &str) -> String {
+        let values: Vec<String> = self
+            .values
+            .iter()
+            .map(|(name, value)| {
+                let value = if value.is_finite() { value.to_string() } else { "null".to_string() };
+                format!("{}:{}", json_string(name), value)
+            })
+            .collect();
+        format!(
+            "{{\"processor\":{},\"key\":{},\"window_start\":{},\"window_end\":{},\"event_count\":{},\"values\":{{{}}}}}",
+            json_string(processor),
+            self.key.as_deref().map_or("null".to_string(), json_string),
+            self.window_start,
+            self.window_end,
+            self.event_count,
+            values.join(",")
+        )
+    }
+}
+
+// Appends one JSON object per line to `<prefix>.<n>.jsonl`, starting a new
+// file once the current one reaches `max_bytes` and keeping the newest `keep`
+struct JsonLinesSink {
+    dir: PathBuf,
+    prefix: String,
+    max_bytes: u64,
+    keep: usize,
+    sequence: u64,
+    file: Option<(std::fs::File, u64)>,
+}
+
+impl JsonLinesSink {
+    fn new(dir: impl Into<PathBuf>, prefix: &str, max_bytes: u64, keep: usize) -> Self {
+        JsonLinesSink {
+            dir: dir.into(),
+            prefix: prefix.to_string(),
+            max_bytes,
+            keep: keep.max(1),
+            sequence: 0,
+            file: None,
+        }
+    }
+
+    fn path(&self, sequence: u64) -> PathBuf {
+        self.dir.join(format!("{}.{}.jsonl", self.prefix, sequence))
+    }
+
+    // Highest sequence already on disk, so a restart never overwrites output
+    fn last_sequence(&self) -> Result<u64, SinkError> {
+        let mut last = 0;
+        for entry in std::fs::read_dir(&self.dir)? {
+            let name = entry?.file_name();
+            let sequence = name
+                .to_str()
+                .and_then(|n| n.strip_prefix(&self.prefix)?.strip_prefix('.')?.strip_suffix(".jsonl")?.parse().ok());
+            last = last.max(sequence.unwrap_or(0));
+        }
+        Ok(last)
+    }
+
+    fn rotate(&mut self) -> Result<(), SinkError> {
+        std::fs::create_dir_all(&self.dir)?;
+        if self.sequence == 0 {
+            self.sequence = self.last_sequence()?;
+        }
+        self.sequence += 1;
+        let file = std::fs::File::create(self.path(self.sequence))?;
+        self.file = Some((file, 0));
+
+        if let Some(expired) = self.sequence.checked_sub(self.keep as u64) {
+            match std::fs::remove_file(self.path(expired)) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Sink for JsonLinesSink {
+    fn write(&mut self, processor: &str, result: &WindowResult) -> Result<(), SinkError> {
+        let line = result.to_json(processor) + "\n";
+        let full = self
+            .file
+            .as_ref()
+            .is_none_or(|(_, written)| *written > 0 && written + line.len() as u64 > self.max_bytes);
+        if full {
+            self.rotate()?;
+        }
+
+        let (file, written) = self.file.as_mut().expect("rotate opens a file");
+        if let Err(e) = file.write_all(line.as_bytes()) {
+            // The file may now end in a partial line; continue in a fresh one
+            self.file = None;
+            return Err(e.into());
+        }
+        *written += line.len() as u64;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), SinkError> {
+        if let Some((file, _)) = self.file.as_mut() {
+            file.sync_data()?;
+        }
+        Ok(())
+    }
+}
+
+// Pushes every result as a JSON text frame to all connected WebSocket
+// clients. Clients that fall too far behind skip ahead rather than stall
+// the processor.
+struct WebSocketSink {
+    tx: broadcast::Sender<String>,
+}
+
+impl WebSocketSink {
+    async fn bind(addr: &str) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        let (tx, _) = broadcast::channel(256);
+
+        let clients = tx.clone();
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                let updates = clients.subscribe();
+                tokio::spawn(async move {
+                    let _ = serve_websocket(socket, updates).await;
+                });
+            }
+        });
+        Ok(WebSocketSink { tx })
+    }
+}
+
+impl Sink for WebSocketSink {
+    fn write(&mut self, processor: &str, result: &WindowResult) -> Result<(), SinkError> {
+        // An error only means nobody is connected, which is not a failure
+        let _ = self.tx.send(result.to_json(processor));
+        Ok(())
+    }
+}
+
+async fn serve_websocket(socket: TcpStream, mut updates: broadcast::Receiver<String>) -> io::Result<()> {
+    let mut reader = BufReader::new(socket);
+    let mut key = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(());
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("sec-websocket-key") {
+                key = Some(value.trim().to_string());
+            }
+        }
+    }
+
+    let mut socket = reader.into_inner();
+    let Some(key) = key else {
+        socket.write_all(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n").await?;
+        return Ok(());
+    };
+    let handshake = format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        websocket_accept(&key)
+    );
+    socket.write_all(handshake.as_bytes()).await?;
+
+    loop {
+        match updates.recv().await {
+            Ok(message) => socket.write_all(&websocket_text_frame(&message)).await?,
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => return Ok(()),
+        }
+    }
+}
+
+// Unmasked, unfragmented server-to-client text frame (RFC 6455 §5.2)
+fn websocket_text_frame(message: &str) -> Vec<u8> {
+    let payload = message.as_bytes();
+    let mut frame = vec![0x81];
+    match payload.len() {
+        n if n < 126 => frame.push(n as u8),
+        n if n <= u16::MAX as usize => {
+            frame.push(126);
+            frame.extend_from_slice(&(n as u16).to_be_bytes());
+        }
+        n => {
+            frame.push(127);
+            frame.extend_from_slice(&(n as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    frame
+}
+
+// base64(SHA-1(key + GUID)), as required by the opening handshake
+fn websocket_accept(key: &str) -> String {
+    const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+    base64(&sha1(format!("{}{}", key, GUID).as_bytes()))
+}
+
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for block in message.chunks(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, &word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (state, value) in h.iter_mut().zip([a, b, c, d, e]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+
+    let mut digest = [0u8; 20];
+    for (chunk, word) in digest.chunks_mut(4).zip(h) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let n = (chunk[0] as u32) << 16
+            | (*chunk.get(1).unwrap_or(&0) as u32) << 8
+            | *chunk.get(2).unwrap_or(&0) as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+// ========== METRICS ==========
+// Counters a processor updates as it runs. Lag is processing time minus
+// event time, sampled as each event is processed.
+struct ProcessorMetrics {
+    events: AtomicU64,
+    windows: AtomicU64,
+    last_lag_ms: AtomicU64,
+    lag: std::sync::Mutex<QuantileSketch>,
+}
 
-                let windows_to_process: Vec<u64> = self
-                    .events
-                    .keys()
-                    .filter(|&&ts| ts + window_size <= current_time)
-                    .copied()
-                    .collect();
+impl ProcessorMetrics {
+    fn new() -> Self {
+        ProcessorMetrics {
+            events: AtomicU64::new(0),
+            windows: AtomicU64::new(0),
+            last_lag_ms: AtomicU64::new(0),
+            lag: std::sync::Mutex::new(QuantileSketch::new(0.01)),
+        }
+    }
 
-                for &window_start in &windows_to_process {
-                    let window_end = window_start + window_size;
+    fn record_event(&self, event: &Event) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        let lag = now.saturating_sub(event.timestamp);
 
-                    let window_events: Vec<Event> = self
-                        .events
-                        .range(window_start..window_end)
-                        .flat_map(|(_, events)| events.iter())
-                        .cloned()
-                        .collect();
+        self.events.fetch_add(1, Ordering::Relaxed);
+        self.last_lag_ms.store(lag, Ordering::Relaxed);
+        self.lag.lock().unwrap().insert(lag as f64);
+    }
+}
 
-                    if !window_events.is_empty() {
-                        results.push(Self::aggregate_events(&window_events, window_start, window_end));
-                    }
-                }
+#[derive(Debug, Clone)]
+struct MetricsSnapshot {
+    processor: String,
+    events: u64,
+    windows: u64,
+    lag_last_ms: u64,
+    lag_p50_ms: f64,
+    lag_p99_ms: f64,
+    // Present when the processor's source was registered with it
+    source: Option<SourceSnapshot>,
+}
 
-                self.events.retain(|&ts, _| ts >= current_time);
-            }
+#[derive(Debug, Clone)]
+struct SourceSnapshot {
+    buffered: u64,
+    capacity: usize,
+    paused: u64,
+    dropped: u64,
+}
 
-            WindowType::Sliding { size, slide } => {
-                let window_size = size.as_millis() as u64;
-                let slide_size = slide.as_millis() as u64;
+type MonitoredProcessor = (String, Arc<ProcessorMetrics>, Option<Arc<BackpressureStats>>);
 
-                let mut window_start = self.last_window_end;
-                while window_start + window_size <= current_time {
-                    let window_end = window_start + window_size;
+// Every monitored processor, reported periodically and optionally served
+// over HTTP in the Prometheus text format
+#[derive(Clone, Default)]
+struct MetricsRegistry {
+    processors: Arc<std::sync::Mutex<Vec<MonitoredProcessor>>>,
+}
 
-                    let window_events: Vec<Event> = self
-                        .events
-                        .range(window_start..window_end)
-                        .flat_map(|(_, events)| events.iter())
-                        .cloned()
-                        .collect();
+impl MetricsRegistry {
+    fn register(&self, processor: &StreamProcessor, source: Option<Arc<BackpressureStats>>) {
+        self.processors
+            .lock()
+            .unwrap()
+            .push((processor.name.clone(), processor.metrics.clone(), source));
+    }
 
-                    if !window_events.is_empty() {
-                        results.push(Self::aggregate_events(&window_events, window_start, window_end));
-                    }
+    fn snapshot(&self) -> Vec<MetricsSnapshot> {
+        let processors = self.processors.lock().unwrap();
+        processors
+            .iter()
+            .map(|(name, metrics, source)| {
+                let lag = metrics.lag.lock().unwrap();
+                MetricsSnapshot {
+                    processor: name.clone(),
+                    events: metrics.events.load(Ordering::Relaxed),
+                    windows: metrics.windows.load(Ordering::Relaxed),
+                    lag_last_ms: metrics.last_lag_ms.load(Ordering::Relaxed),
+                    lag_p50_ms: lag.quantile(0.5),
+                    lag_p99_ms: lag.quantile(0.99),
+                    source: source.as_ref().map(|stats| SourceSnapshot {
+                        buffered: stats.buffered(),
+                        capacity: stats.capacity,
+                        paused: stats.paused(),
+                        dropped: stats.dropped(),
+                    }),
+                }
+            })
+            .collect()
+    }
 
-                    window_start += slide_size;
-                    self.last_window_end = window_start;
+    // Print one logfmt line per processor every `period`, with throughput
+    // measured over that period
+    fn report_every(&self, period: Duration) {
+        let registry = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = interval(period);
+            ticker.tick().await;
+            let mut previous: HashMap<String, u64> = HashMap::new();
+            loop {
+                ticker.tick().await;
+                for snapshot in registry.snapshot() {
+                    let before = previous.insert(snapshot.processor.clone(), snapshot.events).unwrap_or(0);
+                    let rate = (snapshot.events - before) as f64 / period.as_secs_f64();
+                    let mut line = format!(
+                        "[Metrics] processor={} events={} events_per_sec={:.1} windows={} lag_ms={} lag_p50_ms={:.0} lag_p99_ms={:.0}",
+                        snapshot.processor,
+                        snapshot.events,
+                        rate,
+                        snapshot.windows,
+                        snapshot.lag_last_ms,
+                        snapshot.lag_p50_ms,
+                        snapshot.lag_p99_ms
+                    );
+                    if let Some(source) = &snapshot.source {
+                        line.push_str(&format!(
+                            " buffered={}/{} paused={} dropped={}",
+                            source.buffered, source.capacity, source.paused, source.dropped
+                        ));
+                    }
+                    println!("{}", line);
                 }
+            }
+        });
+    }
 
-                let cutoff = current_time.saturating_sub(window_size);
-                self.events.retain(|&ts, _| ts >= cutoff);
+    fn render_prometheus(&self) -> String {
+        let snapshots = self.snapshot();
+        let mut out = String::new();
+        let mut metric = |name: &str, kind: &str, values: Vec<(String, String)>| {
+            out.push_str(&format!("# TYPE {} {}\n", name, kind));
+            for (labels, value) in values {
+                out.push_str(&format!("{}{{{}}} {}\n", name, labels, value));
             }
+        };
+        let label = |s: &MetricsSnapshot| format!("processor={}", json_string(&s.processor));
+
+        metric(
+            "rts_events_total",
+            "counter",
+            snapshots.iter().map(|s| (label(s), s.events.to_string())).collect(),
+        );
+        metric(
+            "rts_windows_total",
+            "counter",
+            snapshots.iter().map(|s| (label(s), s.windows.to_string())).collect(),
+        );
+        metric(
+            "rts_lag_ms",
+            "gauge",
+            snapshots.iter().map(|s| (label(s), s.lag_last_ms.to_string())).collect(),
+        );
+        metric(
+            "rts_lag_quantile_ms",
+            "gauge",
+            snapshots
+                .iter()
+                .filter(|s| s.events > 0)
+                .flat_map(|s| {
+                    [
+                        (format!("{},quantile=\"0.5\"", label(s)), format!("{:.0}", s.lag_p50_ms)),
+                        (format!("{},quantile=\"0.99\"", label(s)), format!("{:.0}", s.lag_p99_ms)),
+                    ]
+                })
+                .collect(),
+        );
+        let sources: Vec<(String, &SourceSnapshot)> = snapshots
+            .iter()
+            .filter_map(|s| s.source.as_ref().map(|source| (label(s), source)))
+            .collect();
+        metric(
+            "rts_buffer_occupancy",
+            "gauge",
+            sources.iter().map(|(l, s)| (l.clone(), s.buffered.to_string())).collect(),
+        );
+        metric(
+            "rts_buffer_capacity",
+            "gauge",
+            sources.iter().map(|(l, s)| (l.clone(), s.capacity.to_string())).collect(),
+        );
+        metric(
+            "rts_source_paused_total",
+            "counter",
+            sources.iter().map(|(l, s)| (l.clone(), s.paused.to_string())).collect(),
+        );
+        metric(
+            "rts_source_dropped_total",
+            "counter",
+            sources.iter().map(|(l, s)| (l.clone(), s.dropped.to_string())).collect(),
+        );
+        out
+    }
 
-            WindowType::Session { gap } => {
-                let gap_ms = gap.as_millis() as u64;
-                let mut session_start = 0u64;
-                let mut session_events = Vec::new();
-                let mut last_event_time = 0u64;
+    // GET /metrics for scrapers; anything else is a 404
+    async fn serve(&self, addr: &str) -> io::Result<()> {
+        let listener = TcpListener::bind(addr).await?;
+        let registry = self.clone();
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                let registry = registry.clone();
+                tokio::spawn(async move {
+                    let _ = serve_metrics(socket, registry).await;
+                });
+            }
+        });
+        Ok(())
+    }
+}
 
-                let all_events: Vec<Event> = self
-                    .events
-                    .values()
-                    .flat_map(|events| events.iter())
-                    .cloned()
-                    .collect();
+async fn serve_metrics(socket: TcpStream, registry: MetricsRegistry) -> io::Result<()> {
+    let mut reader = BufReader::new(socket);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).await? == 0 || header.trim_end().is_empty() {
+            break;
+        }
+    }
 
-                for event in all_events {
-                    if session_events.is_empty() {
-                        session_start = event.timestamp;
-                        session_events.push(event.clone());
-                        last_event_time = event.timestamp;
-                    } else if event.timestamp - last_event_time <= gap_ms {
-                        session_events.push(event.clone());
-                        last_event_time = event.timestamp;
-                    } else {
-                        if current_time - last_event_time > gap_ms {
-                            results.push(Self::aggregate_events(
-                                &session_events,
-                                session_start,
-                                last_event_time,
-                            ));
-                        }
-                        session_start = event.timestamp;
-                        session_events = vec![event.clone()];
-                        last_event_time = event.timestamp;
-                    }
-                }
+    let path = request_line.split_whitespace().nth(1).unwrap_or("");
+    let (status, body) = if path == "/metrics" {
+        ("200 OK", registry.render_prometheus())
+    } else {
+        ("404 Not Found", "not found\n".to_string())
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    reader.into_inner().write_all(response.as_bytes()).await
+}
 
-                if !session_events.is_empty() && current_time - last_event_time > gap_ms {
-                    results.push(Self::aggregate_events(
-                        &session_events,
-                        session_start,
-                        last_event_time,
-                    ));
-                }
+// ========== CHECKPOINTING ==========
+// A checkpoint holds every partition's buffered events plus the input offset
+// they reflect. Both are read under the processor's lock, so they always
+// agree: restoring it and resuming the source at that offset means no event
+// is lost or counted twice by the aggregations.
 
-                self.events.retain(|&ts, _| current_time - ts <= gap_ms * 2);
+#[derive(Debug)]
+enum CheckpointError {
+    Io(String),
+    Corrupt { line: usize, reason: String },
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CheckpointError::Io(msg) => write!(f, "IO error: {}", msg),
+            CheckpointError::Corrupt { line, reason } => {
+                write!(f, "corrupt checkpoint at line {}: {}", line, reason)
             }
         }
+    }
+}
 
-        results
+impl From<io::Error> for CheckpointError {
+    fn from(e: io::Error) -> Self {
+        CheckpointError::Io(e.to_string())
     }
+}
 
-    fn aggregate_events(events: &[Event], window_start: u64, window_end: u64) -> WindowResult {
-        let event_count = events.len();
-        let sum: f64 = events.iter().map(|e| e.value).sum();
-        let avg = sum / event_count as f64;
-        let min = events
-            .iter()
-            .map(|e| e.value)
-            .min_by(|a, b| a.partial_cmp(b).unwrap())
-            .unwrap_or(0.0);
-        let max = events
-            .iter()
-            .map(|e| e.value)
-            .max_by(|a, b| a.partial_cmp(b).unwrap())
-            .unwrap_or(0.0);
+const CHECKPOINT_HEADER: &str = "checkpoint v1";
 
-        WindowResult {
-            window_start,
-            window_end,
-            event_count,
-            sum,
-            avg,
-            min,
-            max,
+impl WindowedStream {
+    // Line format:
+    //   checkpoint v1
+    //   offset <events consumed>
+    //   partition[ <key>]          (no key for an unkeyed stream)
+    //   last_window_end <ms>
+    //   event <id> <timestamp> <value> <event_type>
+    fn snapshot(&self) -> String {
+        let mut out = format!("{}\noffset {}\n", CHECKPOINT_HEADER, self.offset);
+        let mut keys: Vec<&Option<String>> = self.partitions.keys().collect();
+        keys.sort();
+        for key in keys {
+            let state = &self.partitions[key];
+            match key {
+                Some(key) => out.push_str(&format!("partition {}\n", key)),
+                None => out.push_str("partition\n"),
+            }
+            out.push_str(&format!("last_window_end {}\n", state.last_window_end));
+            for event in state.events.values().flatten() {
+                out.push_str(&format!(
+                    "event {} {} {} {}\n",
+                    event.id, event.timestamp, event.value, event.event_type
+                ));
+            }
         }
+        out
     }
-}
 
-// ========== STREAM PROCESSORS ==========
-struct StreamProcessor {
-    name: String,
-    windowed_stream: Arc<RwLock<WindowedStream>>,
+    // Replace all window state with a snapshot; leaves the stream untouched
+    // if the snapshot does not parse
+    fn restore(&mut self, text: &str) -> Result<(), CheckpointError> {
+        let corrupt = |line: usize, reason: &str| CheckpointError::Corrupt {
+            line: line + 1,
+            reason: reason.to_string(),
+        };
+        let number = |line: usize, field: Option<&str>| -> Result<u64, CheckpointError> {
+            field
+                .and_then(|f| f.parse().ok())
+                .ok_or_else(|| corrupt(line, "expected an integer"))
+        };
+
+        let mut lines = text.lines().enumerate();
+        if lines.next().map(|(_, l)| l) != Some(CHECKPOINT_HEADER) {
+            return Err(corrupt(0, "missing header"));
+        }
+        let offset = match lines.next() {
+            Some((n, line)) => number(n, line.strip_prefix("offset "))?,
+            None => return Err(corrupt(1, "missing offset")),
+        };
+
+        let mut partitions: HashMap<Option<String>, WindowState> = HashMap::new();
+        let mut current: Option<&mut WindowState> = None;
+        for (n, line) in lines {
+            let (tag, rest) = line.split_once(' ').unwrap_or((line, ""));
+            match tag {
+                "partition" => {
+                    let key = line.strip_prefix("partition ").map(str::to_string);
+                    current = Some(partitions.entry(key).or_insert_with(WindowState::new));
+                }
+                "last_window_end" => {
+                    let state = current.as_mut().ok_or_else(|| corrupt(n, "no partition"))?;
+                    state.last_window_end = number(n, Some(rest))?;
+                }
+                "event" => {
+                    let state = current.as_mut().ok_or_else(|| corrupt(n, "no partition"))?;
+                    let mut fields = rest.splitn(4, ' ');
+                    let id = number(n, fields.next())?;
+                    let timestamp = number(n, fields.next())?;
+                    let value = fields
+                        .next()
+                        .and_then(|f| f.parse().ok())
+                        .ok_or_else(|| corrupt(n, "expected a value"))?;
+                    let event_type = fields.next().ok_or_else(|| corrupt(n, "missing event type"))?;
+                    state.add_event(Event {
+                        timestamp,
+                        ..Event::new(id, event_type.to_string(), value)
+                    });
+                }
+                "" => {}
+                _ => return Err(corrupt(n, &format!("unknown record '{}'", tag))),
+            }
+        }
+
+        self.offset = offset;
+        self.partitions = partitions;
+        Ok(())
+    }
 }
 
 impl StreamProcessor {
-    fn new(name: String, window_type: WindowType) -> Self {
-        StreamProcessor {
-            name,
-            windowed_stream: Arc::new(RwLock::new(WindowedStream::new(window_type))),
-        }
+    fn checkpoint_path(&self, dir: &Path) -> PathBuf {
+        dir.join(format!("{}.ckpt", self.name))
     }
 
-    async fn process_event(&self, event: Event) {
-        let mut stream = self.windowed_stream.write().await;
-        stream.add_event(event);
+    // Write a checkpoint and return the offset it covers. The file is synced
+    // and then renamed into place, so a crash never leaves a partial one.
+    async fn checkpoint(&self, dir: &Path) -> Result<u64, CheckpointError> {
+        let (snapshot, offset) = {
+            let stream = self.windowed_stream.read().await;
+            (stream.snapshot(), stream.offset)
+        };
+
+        tokio::fs::create_dir_all(dir).await?;
+        let path = self.checkpoint_path(dir);
+        let tmp = path.with_extension("ckpt.tmp");
+        let mut file = tokio::fs::File::create(&tmp).await?;
+        file.write_all(snapshot.as_bytes()).await?;
+        file.sync_all().await?;
+        tokio::fs::rename(&tmp, &path).await?;
+        Ok(offset)
     }
 
-    async fn compute_windows(&self) -> Vec<WindowResult> {
-        let current_time = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as u64;
-
+    // Load the last checkpoint, if any, and return the offset the source
+    // should resume from (0 when starting fresh)
+    async fn restore(&self, dir: &Path) -> Result<u64, CheckpointError> {
+        let text = match tokio::fs::read_to_string(self.checkpoint_path(dir)).await {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
         let mut stream = self.windowed_stream.write().await;
-        stream.compute_windows(current_time)
+        stream.restore(&text)?;
+        Ok(stream.offset)
     }
 
-    async fn run(
-        &self,
-        mut input_stream: Pin<Box<dyn Stream<Item = Event> + Send>>,
-    ) {
-        let name = self.name.clone();
-        println!("[{}] Stream processor started", name);
-
-        let processor = self.clone();
-        tokio::spawn(async move {
-            while let Some(event) = input_stream.next().await {
-                processor.process_event(event).await;
-            }
-            println!("[{}] Stream ended", name);
-        });
-
+    fn checkpoint_every(&self, dir: PathBuf, period: Duration) {
         let processor = self.clone();
         tokio::spawn(async move {
-            let mut ticker = interval(Duration::from_secs(2));
+            let mut ticker = interval(period);
+            ticker.tick().await;
             loop {
                 ticker.tick().await;
-                let results = processor.compute_windows().await;
-
-                for result in results {
-                    println!(
-                        "[{}] Window [{} - {}]: count={}, sum={:.2}, avg={:.2}, min={:.2}, max={:.2}",
-                        processor.name,
-                        result.window_start,
-                        result.window_end,
-                        result.event_count,
-                        result.sum,
-                        result.avg,
-                        result.min,
-                        result.max
-                    );
+                if let Err(e) = processor.checkpoint(&dir).await {
+                    eprintln!("[{}] Checkpoint failed: {}", processor.name, e);
                 }
             }
         });
     }
 }
 
-impl Clone for StreamProcessor {
-    fn clone(&self) -> Self {
-        StreamProcessor {
-            name: self.name.clone(),
-            windowed_stream: self.windowed_stream.clone(),
-        }
-    }
-}
-
 // ========== RATE LIMITER ==========
 struct RateLimiter {
     permits_per_second: u64,
@@ -413,20 +2382,374 @@ impl RateLimiter {
 }
 
 // ========== MAIN ==========
+
+// A user-defined aggregate: how many readings in the window exceeded a limit
+struct AboveThreshold(f64);
+
+impl Aggregator for AboveThreshold {
+    type Acc = u64;
+
+    fn name(&self) -> String {
+        format!("above_{}", self.0)
+    }
+    fn init(&self) -> u64 {
+        0
+    }
+    fn fold(&self, acc: &mut u64, event: &Event) {
+        if event.value > self.0 {
+            *acc += 1;
+        }
+    }
+    fn merge(&self, acc: &mut u64, other: &u64) {
+        *acc += other;
+    }
+    fn emit(&self, acc: &u64) -> f64 {
+        *acc as f64
+    }
+}
+
+// Rooms report temperature from two feeds and humidity as one combined
+// reading; the pipeline joins them per room into a comfort index
+async fn run_join_pipeline(metrics: &MetricsRegistry) {
+    let (mut north, north_rx) = EventSource::new(100);
+    let (mut south, south_rx) = EventSource::new(100);
+    let (mut humidity, humidity_rx) = EventSource::new(100);
+
+    let room = |event: &Event| event.event_type.split(':').nth(1).unwrap_or_default().to_string();
+
+    let temperatures = Pipeline::from_receiver(north_rx)
+        .merge(Pipeline::from_receiver(south_rx))
+        .filter(|event| (-40.0..=60.0).contains(&event.value));
+
+    // "humidity:a,b" carries one value per room
+    let humidities = Pipeline::from_receiver(humidity_rx).flat_map(|event| {
+        let values: Vec<f64> = event.event_type["humidity:".len()..]
+            .split(',')
+            .filter_map(|v| v.parse().ok())
+            .collect();
+        values
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| Event {
+                event_type: format!("humidity:room-{}", i),
+                value,
+                ..event.clone()
+            })
+            .collect::<Vec<_>>()
+    });
+
+    temperatures
+        .join(humidities, Duration::from_millis(500), room, move |temp, hum| Event {
+            event_type: format!("comfort:{}", room(temp)),
+            value: temp.value - 0.05 * hum.value,
+            // A joined event exists once both inputs have arrived
+            timestamp: temp.timestamp.max(hum.timestamp),
+            ..temp.clone()
+        })
+        .map(|mut event| {
+            event.value = (event.value * 10.0).round() / 10.0;
+            event
+        })
+        .window("Join-5s", WindowType::Tumbling(Duration::from_secs(5)))
+        .key_by(|event| event.event_type.clone())
+        .aggregate(Percentile::new(0.5))
+        .monitor(metrics)
+        .sink(|name: &str, result: &WindowResult| {
+            println!(
+                "[{}] {} Window [{} - {}]: {} joined readings, {}",
+                name,
+                result.key.as_deref().unwrap_or("-"),
+                result.window_start,
+                result.window_end,
+                result.event_count,
+                result
+                    .values
+                    .iter()
+                    .find(|(name, _)| name == "p50")
+                    .map(|(_, median)| format!("median comfort={:.1}", median))
+                    .unwrap_or_default()
+            );
+        })
+        .await;
+
+    tokio::spawn(async move {
+        for i in 0..40 {
+            let _ = north.emit("temperature:room-0".to_string(), 20.0 + (i % 5) as f64).await;
+            let _ = south.emit("temperature:room-1".to_string(), 24.0 - (i % 3) as f64).await;
+            if i % 2 == 0 {
+                let readings = format!("humidity:{},{}", 40 + i % 7, 55 + i % 4);
+                let _ = humidity.emit(readings, 0.0).await;
+            }
+            sleep(Duration::from_millis(250)).await;
+        }
+    });
+}
+
+// Checkpoint part-way through, "crash", then recover into a fresh processor
+// and replay from the checkpointed offset; the totals must match a run that
+// never crashed
+async fn run_recovery_demo(dir: &Path) -> Result<(), CheckpointError> {
+    let new_processor = || {
+        StreamProcessor::new("Recoverable-1s".to_string(), WindowType::Tumbling(Duration::from_secs(1)))
+            .key_by(|event| event.event_type.clone())
+    };
+    // The replayable input: event i always sits at offset i
+    let base = 1_700_000_000_000;
+    let events: Vec<Event> = (0..60)
+        .map(|i| Event {
+            timestamp: base + i * 100,
+            ..Event::new(i, format!("sensor-{}", i % 3), i as f64)
+        })
+        .collect();
+
+    let totals = |results: Vec<WindowResult>| {
+        let count: usize = results.iter().map(|r| r.event_count).sum();
+        let sum: f64 = results
+            .iter()
+            .flat_map(|r| r.values.iter())
+            .filter(|(name, _)| name == "sum")
+            .map(|(_, v)| v)
+            .sum();
+        (count, sum)
+    };
+
+    let uninterrupted = new_processor();
+    for event in &events {
+        uninterrupted.process_event(event.clone()).await;
+    }
+
+    let crashed = new_processor();
+    let mut checkpointed = 0;
+    for event in &events[..45] {
+        crashed.process_event(event.clone()).await;
+        if event.id == 29 {
+            checkpointed = crashed.checkpoint(dir).await?;
+        }
+    }
+    // Events 30..45 were processed but never checkpointed
+    drop(crashed);
+
+    let recovered = new_processor();
+    let offset = recovered.restore(dir).await?;
+    for event in &events[offset as usize..] {
+        recovered.process_event(event.clone()).await;
+    }
+
+    let (expected_count, expected_sum) = totals(uninterrupted.compute_windows_at(u64::MAX).await);
+    let (count, sum) = totals(recovered.compute_windows_at(u64::MAX).await);
+    println!(
+        "[Recovery] checkpoint at offset {}, crashed after 45 events, replayed {}..{}",
+        checkpointed,
+        offset,
+        events.len()
+    );
+    println!(
+        "[Recovery] uninterrupted: count={}, sum={:.1}; recovered: count={}, sum={:.1} ({})",
+        expected_count,
+        expected_sum,
+        count,
+        sum,
+        if (count, sum) == (expected_count, expected_sum) { "exactly once" } else { "MISMATCH" }
+    );
+    Ok(())
+}
+
+// A producer faster than its consumer: emit() pauses until there is room,
+// try_emit() drops instead, and every event is either delivered or counted
+async fn run_backpressure_demo() {
+    let (mut source, rx) = EventSource::new(8);
+    let stats = source.stats();
+    let mut stream = BackpressureStream::new(rx, source.stats());
+
+    let consumer = tokio::spawn(async move {
+        while stream.next().await.is_some() {
+            sleep(Duration::from_millis(5)).await;
+        }
+    });
+
+    for i in 0..40 {
+        let _ = source.emit("reliable".to_string(), i as f64).await;
+    }
+    for i in 0..40 {
+        let _ = source.try_emit("best-effort".to_string(), i as f64);
+        sleep(Duration::from_millis(2)).await;
+    }
+    drop(source);
+    let _ = consumer.await;
+
+    println!(
+        "[Backpressure] sent=80 delivered={} dropped={} (producer paused {} times)",
+        stats.delivered(),
+        stats.dropped(),
+        stats.paused()
+    );
+}
+
+// Two patterns over a scripted login audit log, matched per user: a
+// brute-force signature, and alerts nobody acknowledged within 5s
+async fn run_cep_demo() {
+    let base = 1_700_000_000_000;
+    let script = [
+        (0, "alice", "login_failed"),
+        (1_000, "bob", "login_failed"),
+        (1_500, "alice", "login_failed"),
+        (2_000, "alice", "login_ok"),
+        (2_500, "bob", "password_reset"),
+        (3_000, "bob", "login_failed"),
+        (4_000, "bob", "login_ok"),
+        (5_000, "carol", "alert"),
+        (6_000, "dave", "alert"),
+        (7_000, "dave", "ack"),
+        (14_000, "erin", "login_ok"),
+    ];
+    let events: Vec<Event> = script
+        .iter()
+        .enumerate()
+        .map(|(i, &(at, user, action))| Event {
+            timestamp: base + at,
+            ..Event::new(i as u64, format!("{}:{}", user, action), 0.0)
+        })
+        .collect();
+
+    let user = |event: &Event| event.event_type.split(':').next().unwrap_or_default().to_string();
+    let action = |name: &'static str| move |event: &Event| event.event_type.ends_with(name);
+
+    let brute_force = Pattern::begin("brute-force", "failure", action(":login_failed"))
+        .not_followed_by(action(":password_reset"))
+        .followed_by("failure again", action(":login_failed"))
+        .not_followed_by(action(":password_reset"))
+        .followed_by("success", action(":login_ok"))
+        .within(Duration::from_secs(10));
+
+    let mut matcher = PatternMatcher::new(brute_force).key_by(user);
+    for event in &events {
+        for found in matcher.process(event) {
+            let steps: Vec<String> = found
+                .events
+                .iter()
+                .map(|(step, event)| format!("{} @{}ms", step, event.timestamp - base))
+                .collect();
+            println!("[CEP] {} {}: {}", found.pattern, found.key.unwrap_or_default(), steps.join(" → "));
+        }
+    }
+    println!("[CEP] brute-force: {} partial matches timed out", matcher.timed_out);
+
+    let unacknowledged = Pattern::begin("unacknowledged", "alert", action(":alert"))
+        .not_followed_by(action(":ack"))
+        .within(Duration::from_secs(5));
+
+    let escalations: Vec<Event> = Pipeline::from_stream(stream::iter(events))
+        .detect(PatternMatcher::new(unacknowledged).key_by(user))
+        .stream
+        .collect()
+        .await;
+    for escalation in escalations {
+        println!("[CEP] {} escalated at {}ms", escalation.event_type, escalation.timestamp - base);
+    }
+}
+
+// Feed a replay through a fresh keyed processor and close every window
+async fn replay_windows(source: ReplaySource) -> (Vec<WindowResult>, BTreeMap<String, u64>) {
+    let processor = StreamProcessor::new("Replay-1s".to_string(), WindowType::Tumbling(Duration::from_secs(1)))
+        .key_by(|event| event.event_type.clone());
+    let (mut events, offsets) = source.into_stream();
+    while let Some(event) = events.next().await {
+        processor.process_event(event).await;
+    }
+    let windows = processor.compute_windows_at(u64::MAX).await;
+    let offsets = offsets.lock().unwrap().clone();
+    (windows, offsets)
+}
+
+// Write a two-partition log, then replay it in full twice, once more from
+// stored offsets, and finally paced by event time
+async fn run_replay_demo(dir: &Path) -> Result<(), SourceError> {
+    std::fs::create_dir_all(dir)?;
+    let base = 1_700_000_000_000u64;
+    for partition in 0..2u64 {
+        let mut log = String::from("# timestamp_ms,event_type,value\n");
+        for i in 0..30u64 {
+            let timestamp = base + i * 200 + partition * 50;
+            log.push_str(&format!("{},sensor-{},{}\n", timestamp, partition, i * 7 % 40));
+        }
+        std::fs::write(dir.join(format!("partition-{}.log", partition)), log)?;
+    }
+
+    let as_json = |windows: &[WindowResult]| -> Vec<String> {
+        windows.iter().map(|w| w.to_json("Replay-1s")).collect()
+    };
+    let event_count = |windows: &[WindowResult]| -> usize { windows.iter().map(|w| w.event_count).sum() };
+
+    let (first, offsets) = replay_windows(ReplaySource::open(dir)?).await;
+    let (second, _) = replay_windows(ReplaySource::open(dir)?).await;
+    println!(
+        "[Replay] {} events into {} windows, final offsets {:?}; second replay {}",
+        event_count(&first),
+        first.len(),
+        offsets,
+        if as_json(&first) == as_json(&second) { "identical" } else { "DIFFERENT" }
+    );
+
+    let resumed = ReplaySource::open(dir)?
+        .seek("partition-0", 20)?
+        .seek("partition-1", 25)?;
+    let (tail, _) = replay_windows(resumed).await;
+    println!("[Replay] from offsets partition-0=20, partition-1=25: {} events", event_count(&tail));
+
+    let started = Instant::now();
+    let paced = ReplaySource::open(dir)?.pacing(Pacing::EventTime { speed: 4.0 });
+    let (windows, _) = replay_windows(paced).await;
+    println!(
+        "[Replay] paced at 4x: {} windows in {:.1}s for {:.1}s of event time",
+        windows.len(),
+        started.elapsed().as_secs_f64(),
+        (29 * 200 + 50) as f64 / 1000.0
+    );
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() {
     println!("=== Real-Time Stream Processing System ===\n");
 
     let (mut source, rx) = EventSource::new(1000);
 
+    let checkpoint_dir = std::env::temp_dir().join("rts-checkpoints");
+    println!("Simulating a crash and recovery from checkpoint...\n");
+    if let Err(e) = run_recovery_demo(&checkpoint_dir).await {
+        println!("[Recovery] failed: {}", e);
+    }
+    println!();
+
+    println!("Feeding a slow consumer through an 8-event buffer...\n");
+    run_backpressure_demo().await;
+    println!();
+
+    println!("Replaying a partitioned event log...\n");
+    if let Err(e) = run_replay_demo(&std::env::temp_dir().join("rts-replay")).await {
+        println!("[Replay] failed: {}", e);
+    }
+    println!();
+
+    println!("Matching event patterns (CEP)...\n");
+    run_cep_demo().await;
+    println!();
+
     println!("Creating stream processors with different window types...\n");
 
-    let backpressure_stream = BackpressureStream::new(rx, 500);
+    let source_stats = source.stats();
+    let backpressure_stream = BackpressureStream::new(rx, source.stats());
 
+    // Independent windows per sensor, with a latency-style percentile,
+    // distinct event ids and a custom threshold count on top of the defaults
     let tumbling_processor = StreamProcessor::new(
         "Tumbling-5s".to_string(),
         WindowType::Tumbling(Duration::from_secs(5)),
-    );
+    )
+    .key_by(|event| event.event_type.clone())
+    .aggregate(Percentile::new(0.95))
+    .aggregate(DistinctCount::new("distinct_ids", |event| event.id.to_string()))
+    .aggregate(AboveThreshold(50.0));
 
     let sliding_processor = StreamProcessor::new(
         "Sliding-10s/2s".to_string(),
@@ -445,8 +2768,35 @@ async fn main() {
 
     println!("Starting stream processors...\n");
 
+    // Console, rotating JSON-lines files and any connected dashboards
+    let output_dir = std::env::temp_dir().join("rts-output");
+    let mut sinks: Vec<Box<dyn Sink>> = vec![
+        Box::new(StdoutSink),
+        Box::new(JsonLinesSink::new(&output_dir, "tumbling", 4096, 3)),
+    ];
+    match WebSocketSink::bind("127.0.0.1:9002").await {
+        Ok(sink) => {
+            println!("Dashboard feed: ws://127.0.0.1:9002");
+            sinks.push(Box::new(sink));
+        }
+        Err(e) => println!("Dashboard feed disabled: {}", e),
+    }
+    println!("Window results also written to {}\n", output_dir.display());
+
+    let metrics = MetricsRegistry::default();
+    metrics.register(&tumbling_processor, Some(source_stats.clone()));
+    metrics.report_every(Duration::from_secs(5));
+    match metrics.serve("127.0.0.1:9003").await {
+        Ok(()) => println!("Metrics endpoint: http://127.0.0.1:9003/metrics\n"),
+        Err(e) => println!("Metrics endpoint disabled: {}\n", e),
+    }
+
     let stream1 = Box::pin(backpressure_stream);
-    tumbling_processor.run(stream1).await;
+    tumbling_processor.run(stream1, sinks).await;
+    tumbling_processor.checkpoint_every(checkpoint_dir.clone(), Duration::from_secs(5));
+
+    println!("Starting join pipeline (temperature ⋈ humidity per room)...\n");
+    run_join_pipeline(&metrics).await;
 
     sleep(Duration::from_millis(100)).await;
 
@@ -462,7 +2812,8 @@ async fn main() {
             let mut src = emitter.write().await;
             
             let value = (i as f64 * 1.5) % 100.0;
-            let _ = src.emit("metric".to_string(), value).await;
+            let sensor = format!("sensor-{}", i % 3);
+            let _ = src.emit(sensor, value).await;
 
             drop(src);
 
@@ -478,14 +2829,179 @@ async fn main() {
 
     sleep(Duration::from_secs(15)).await;
 
+    println!(
+        "\n[Source] delivered={}, paused={}, dropped={}",
+        source_stats.delivered(),
+        source_stats.paused(),
+        source_stats.dropped()
+    );
     println!("\n✓ Stream processing demonstration complete!");
     println!("\nKey features demonstrated:");
     println!("  • Async event stream processing with futures");
     println!("  • Tumbling windows (fixed non-overlapping intervals)");
     println!("  • Sliding windows (overlapping time windows)");
     println!("  • Session windows (gap-based activity sessions)");
-    println!("  • Backpressure handling with bounded buffers");
+    println!("  • Keyed streams with independent per-key windows (key_by)");
+    println!("  • Backpressure propagated to producers through bounded buffers");
     println!("  • Event-time vs processing-time semantics");
     println!("  • Windowed aggregations (sum, avg, min, max, count)");
+    println!("  • Pluggable aggregators: percentile sketches, HyperLogLog, custom");
+    println!("  • Pipeline DSL: map, filter, flat_map, merge and windowed joins");
+    println!("  • Checkpointed window state and offsets for exactly-once recovery");
+    println!("  • Sinks (stdout, rotating JSON lines, WebSocket) with at-least-once retry");
+    println!("  • CEP: sequence patterns with negation and timeouts (NFA runs)");
+    println!("  • Metrics: throughput, event-time lag, buffer occupancy, drops");
+    println!("  • Replayable partitioned log source with offsets and event-time pacing");
     println!("  • Rate limiting for stream control");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The fixture partitions, copied to a fresh directory so ReplaySource
+    // reads them from disk regardless of where the tests run
+    fn replay_fixture(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rts-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("sensor-a.log"), include_str!("fixtures/replay/sensor-a.log")).unwrap();
+        std::fs::write(dir.join("sensor-b.log"), include_str!("fixtures/replay/sensor-b.log")).unwrap();
+        dir
+    }
+
+    // (key, window start, event count, sum) per window
+    fn summary(windows: &[WindowResult]) -> Vec<(String, u64, usize, f64)> {
+        windows
+            .iter()
+            .map(|w| (w.key.clone().unwrap_or_default(), w.window_start, w.event_count, w.values[0].1))
+            .collect()
+    }
+
+    fn event_at(timestamp: u64, event_type: &str, value: f64) -> Event {
+        Event {
+            timestamp,
+            ..Event::new(timestamp, event_type.to_string(), value)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay_windows_and_offsets() {
+        let dir = replay_fixture("replay");
+        let (windows, offsets) = replay_windows(ReplaySource::open(&dir).unwrap()).await;
+
+        assert_eq!(
+            summary(&windows),
+            vec![
+                ("humidity".to_string(), 1000, 2, 120.0),
+                ("temp".to_string(), 1000, 2, 42.0),
+                ("humidity".to_string(), 2000, 1, 60.0),
+                ("temp".to_string(), 2000, 1, 30.0),
+                ("temp".to_string(), 3000, 1, 10.0),
+            ]
+        );
+        let expected: BTreeMap<String, u64> = [("sensor-a".to_string(), 4), ("sensor-b".to_string(), 3)].into();
+        assert_eq!(offsets, expected);
+
+        // A second replay sees exactly the same windows
+        let (again, _) = replay_windows(ReplaySource::open(&dir).unwrap()).await;
+        assert_eq!(summary(&again), summary(&windows));
+
+        // Resuming from stored offsets skips what was already consumed
+        let resumed = ReplaySource::open(&dir).unwrap().seek("sensor-a", 2).unwrap();
+        assert_eq!(resumed.offsets()["sensor-a"], 2);
+        let (tail, offsets) = replay_windows(resumed).await;
+        let temp: Vec<_> = summary(&tail).into_iter().filter(|w| w.0 == "temp").collect();
+        assert_eq!(temp, vec![("temp".to_string(), 2000, 1, 30.0), ("temp".to_string(), 3000, 1, 10.0)]);
+        assert_eq!(offsets, expected);
+
+        assert!(matches!(
+            ReplaySource::open(&dir).unwrap().seek("sensor-c", 0),
+            Err(SourceError::UnknownPartition(_))
+        ));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_snapshot_restore_round_trip() {
+        let keyed = || {
+            WindowedStream::new(WindowType::Tumbling(Duration::from_secs(1)))
+                .key_by(|event: &Event| event.event_type.clone())
+        };
+        let mut original = keyed();
+        for (timestamp, event_type, value) in [(1000, "temp", 20.0), (1500, "humidity", 55.5), (2200, "temp", 21.0)] {
+            original.add_event(event_at(timestamp, event_type, value));
+        }
+        let snapshot = original.snapshot();
+
+        let mut restored = keyed();
+        restored.restore(&snapshot).unwrap();
+        assert_eq!(restored.offset, 3);
+        assert_eq!(restored.snapshot(), snapshot);
+
+        // Both produce the same windows, and the restored one keeps counting
+        // from the checkpointed offset
+        assert_eq!(summary(&restored.compute_windows(u64::MAX)), summary(&original.compute_windows(u64::MAX)));
+        restored.add_event(event_at(4000, "temp", 1.0));
+        assert_eq!(restored.offset, 4);
+
+        // A corrupt snapshot leaves the state alone
+        let before = restored.snapshot();
+        let err = restored.restore("checkpoint v1\noffset 9\nevent 1 2 x temp\n").unwrap_err();
+        assert!(matches!(err, CheckpointError::Corrupt { line: 3, .. }));
+        assert_eq!(restored.snapshot(), before);
+    }
+
+    fn is(event_type: &'static str) -> impl Fn(&Event) -> bool + Send + Sync + 'static {
+        move |event: &Event| event.event_type == event_type
+    }
+
+    #[test]
+    fn test_pattern_negation_between_steps() {
+        let a_then_b = || {
+            Pattern::begin("a-then-b", "a", is("A"))
+                .not_followed_by(is("C"))
+                .followed_by("b", is("B"))
+                .within(Duration::from_secs(10))
+        };
+
+        let mut matcher = PatternMatcher::new(a_then_b());
+        assert!(matcher.process(&event_at(0, "A", 0.0)).is_empty());
+        let matches = matcher.process(&event_at(1_000, "B", 0.0));
+        assert_eq!(matches.len(), 1);
+        let steps: Vec<(&str, u64)> = matches[0].events.iter().map(|(s, e)| (s.as_str(), e.timestamp)).collect();
+        assert_eq!(steps, vec![("a", 0), ("b", 1_000)]);
+
+        // A C between them kills the run
+        let mut matcher = PatternMatcher::new(a_then_b());
+        for (timestamp, event_type) in [(0, "A"), (500, "C"), (1_000, "B")] {
+            assert!(matcher.process(&event_at(timestamp, event_type, 0.0)).is_empty());
+        }
+
+        // A run that never sees its B times out
+        let mut matcher = PatternMatcher::new(a_then_b());
+        matcher.process(&event_at(0, "A", 0.0));
+        assert!(matcher.process(&event_at(11_000, "B", 0.0)).is_empty());
+        assert_eq!(matcher.timed_out, 1);
+    }
+
+    #[test]
+    fn test_trailing_negation_completes_on_timeout() {
+        let unanswered = || {
+            Pattern::begin("unanswered", "alert", is("alert"))
+                .not_followed_by(is("ack"))
+                .within(Duration::from_secs(5))
+        };
+
+        // Nothing matches until the window has passed without an ack
+        let mut matcher = PatternMatcher::new(unanswered());
+        assert!(matcher.process(&event_at(0, "alert", 0.0)).is_empty());
+        assert!(matcher.process(&event_at(4_000, "noise", 0.0)).is_empty());
+        assert_eq!(matcher.process(&event_at(6_000, "noise", 0.0)).len(), 1);
+
+        let mut matcher = PatternMatcher::new(unanswered());
+        matcher.process(&event_at(0, "alert", 0.0));
+        matcher.process(&event_at(1_000, "ack", 0.0));
+        assert!(matcher.process(&event_at(6_000, "noise", 0.0)).is_empty());
+        assert_eq!(matcher.timed_out, 0);
+    }
+}
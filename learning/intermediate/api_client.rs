@@ -3,18 +3,29 @@
 // COMPILE & RUN:
 //   rustc api_client.rs && ./api_client
 //
-// For production use with real HTTP requests, add to Cargo.toml:
+// The standalone build talks plain HTTP/1.1 over std's TcpStream. For HTTPS
+// (`reqwest`) and typed JSON responses (`serde`), build as a Cargo bin:
 //   [dependencies]
-//   reqwest = { version = "0.11", features = ["blocking", "json"] }
-//   serde = { version = "1.0", features = ["derive"] }
-//   serde_json = "1.0"
+//   reqwest = { version = "0.11", features = ["blocking", "json"], optional = true }
+//   serde = { version = "1.0", features = ["derive"], optional = true }
+//   serde_json = { version = "1.0", optional = true }
+//
+//   [features]
+//   reqwest = ["dep:reqwest"]
+//   serde = ["dep:serde", "dep:serde_json"]
 //
 // This program demonstrates a REST API client with all HTTP methods
 
 use std::collections::HashMap;
 use std::error::Error;
 use std::fmt;
-use std::time::{Duration, Instant};
+use std::io::{Read, Write};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 // ============================================================================
 // ERROR HANDLING
@@ -26,6 +37,8 @@ enum ApiError {
     ParseError(String),
     ValidationError(String),
     HttpError(u16, String),
+    /// The host's circuit breaker is open; the request was not sent
+    CircuitOpen(String),
 }
 
 impl fmt::Display for ApiError {
@@ -35,6 +48,7 @@ impl fmt::Display for ApiError {
             ApiError::ParseError(msg) => write!(f, "Parse error: {}", msg),
             ApiError::ValidationError(msg) => write!(f, "Validation error: {}", msg),
             ApiError::HttpError(code, msg) => write!(f, "HTTP {} error: {}", code, msg),
+            ApiError::CircuitOpen(host) => write!(f, "Circuit open for {}; request not sent", host),
         }
     }
 }
@@ -70,6 +84,13 @@ impl fmt::Display for HttpMethod {
     }
 }
 
+impl HttpMethod {
+    /// Whether sending the request twice has the same effect as sending it once
+    fn is_idempotent(&self) -> bool {
+        !matches!(self, HttpMethod::POST | HttpMethod::PATCH)
+    }
+}
+
 #[derive(Debug, Clone)]
 struct HttpRequest {
     method: HttpMethod,
@@ -139,27 +160,1287 @@ impl HttpResponse {
         self.status_code >= 400 && self.status_code < 500
     }
 
-    fn is_server_error(&self) -> bool {
-        self.status_code >= 500
+    fn is_server_error(&self) -> bool {
+        self.status_code >= 500
+    }
+
+    /// Deserializes the body into `T`. Errors name the target type, say
+    /// what kind of failure it was, and quote the body around the position.
+    #[cfg(feature = "serde")]
+    fn json<T: serde::de::DeserializeOwned>(&self) -> Result<T, ApiError> {
+        serde_json::from_str(&self.body).map_err(|e| {
+            let kind = match e.classify() {
+                serde_json::error::Category::Data => "unexpected shape",
+                serde_json::error::Category::Syntax => "invalid JSON",
+                serde_json::error::Category::Eof => "truncated JSON",
+                serde_json::error::Category::Io => "I/O failure",
+            };
+            let mut message = format!("{} for {}: {}", kind, std::any::type_name::<T>(), e);
+            if let Some(line) = self.body.lines().nth(e.line().saturating_sub(1)) {
+                let chars: Vec<char> = line.chars().collect();
+                let at = e.column().min(chars.len());
+                let from = at.saturating_sub(30);
+                let to = (at + 10).min(chars.len());
+                let excerpt: String = chars[from..to].iter().collect();
+                message.push_str(&format!(" (near `{}`)", excerpt));
+            }
+            ApiError::ParseError(message)
+        })
+    }
+}
+
+// ============================================================================
+// TRANSPORT
+// ============================================================================
+
+/// Sends a fully prepared request (absolute URL, all headers merged) and
+/// returns whatever the server answered
+trait Transport {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, ApiError>;
+
+    /// Connection pool counters, for transports that pool
+    fn pool_stats(&self) -> Option<PoolStats> {
+        None
+    }
+}
+
+/// Canned responses for demonstration; never touches the network
+struct MockTransport;
+
+impl MockTransport {
+    const ITEM_COUNT: usize = 7;
+    const PAGE_SIZE: usize = 3;
+
+    /// Numbered items, either by `offset` and `limit` query parameters or
+    /// by `page` with a `Link` header to the next one
+    fn items_page(url: &str) -> (String, HashMap<String, String>) {
+        let param = |name: &str| {
+            let query = url.split_once('?')?.1;
+            query.split('&').find_map(|pair| {
+                let (key, value) = pair.split_once('=')?;
+                if key == name { value.parse::<usize>().ok() } else { None }
+            })
+        };
+
+        let mut headers = HashMap::new();
+        let (first, count) = match param("offset") {
+            Some(offset) => (offset, param("limit").unwrap_or(Self::PAGE_SIZE)),
+            None => {
+                let page = param("page").unwrap_or(1).max(1);
+                if page * Self::PAGE_SIZE < Self::ITEM_COUNT {
+                    let base = url.split('?').next().unwrap_or(url);
+                    headers.insert(
+                        "link".to_string(),
+                        format!("<{}?page={}>; rel=\"next\"", base, page + 1),
+                    );
+                }
+                ((page - 1) * Self::PAGE_SIZE, Self::PAGE_SIZE)
+            }
+        };
+
+        let items: Vec<String> = (first + 1..=Self::ITEM_COUNT)
+            .take(count)
+            .map(|item| item.to_string())
+            .collect();
+        (format!("[{}]", items.join(", ")), headers)
+    }
+}
+
+impl Transport for MockTransport {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, ApiError> {
+        println!("→ {} {}", request.method, request.url);
+        
+        let start = Instant::now();
+        
+        // Simulate network delay
+        std::thread::sleep(Duration::from_millis(100));
+
+        let expired = request
+            .headers
+            .get("Authorization")
+            .is_some_and(|auth| auth == "Bearer expired-token");
+
+        let mut headers = HashMap::new();
+        headers.insert("content-type".to_string(), "application/json".to_string());
+        headers.insert("server".to_string(), "MockServer/1.0".to_string());
+
+        if request.method == HttpMethod::GET && request.url.contains("/items") && !expired {
+            let (body, page_headers) = Self::items_page(&request.url);
+            headers.extend(page_headers);
+            return Ok(HttpResponse {
+                status_code: 200,
+                status_text: "OK".to_string(),
+                headers,
+                body,
+                elapsed: start.elapsed(),
+            });
+        }
+
+        let (status_code, status_text, body) = match request.method {
+            _ if expired => {
+                (401, "Unauthorized", r#"{"error": "Token expired"}"#)
+            }
+            HttpMethod::GET => {
+                if request.url.contains("/users/1") {
+                    (200, "OK", r#"{"id": 1, "name": "Alice", "email": "alice@example.com"}"#)
+                } else if request.url.contains("/users") {
+                    (200, "OK", r#"[{"id": 1, "name": "Alice"}, {"id": 2, "name": "Bob"}]"#)
+                } else if request.url.contains("/notfound") {
+                    (404, "Not Found", r#"{"error": "Resource not found"}"#)
+                } else {
+                    (200, "OK", r#"{"status": "success"}"#)
+                }
+            }
+            HttpMethod::POST => {
+                let body = request.body.as_deref().unwrap_or("");
+                if request.url.contains("/oauth/token") && body.contains("grant_type=refresh_token") {
+                    (200, "OK", r#"{"access_token": "fresh-token", "token_type": "Bearer", "expires_in": 3600, "refresh_token": "refresh-2"}"#)
+                } else if request.url.contains("/oauth/token") {
+                    // Issues a token the API then rejects, as if revoked since
+                    (200, "OK", r#"{"access_token": "expired-token", "token_type": "Bearer", "expires_in": 3600, "refresh_token": "refresh-1"}"#)
+                } else {
+                    (201, "Created", r#"{"id": 3, "name": "Charlie", "created": true}"#)
+                }
+            }
+            HttpMethod::PUT => {
+                (200, "OK", r#"{"id": 1, "name": "Alice Updated", "updated": true}"#)
+            }
+            HttpMethod::PATCH => {
+                (200, "OK", r#"{"id": 1, "name": "Alice Patched", "updated": true}"#)
+            }
+            HttpMethod::DELETE => {
+                (204, "No Content", "")
+            }
+            HttpMethod::HEAD => {
+                (200, "OK", "")
+            }
+            HttpMethod::OPTIONS => {
+                (200, "OK", "")
+            }
+        };
+
+        let elapsed = start.elapsed();
+
+        Ok(HttpResponse {
+            status_code,
+            status_text: status_text.to_string(),
+            headers,
+            body: body.to_string(),
+            elapsed,
+        })
+    }
+}
+
+/// Plain HTTP/1.1 over std's TcpStream with keep-alive. Connections are
+/// pooled per host: after a response, up to `max_idle_per_host` of them wait
+/// for the next request, and any left idle past `idle_timeout` are closed
+/// rather than reused. Only `http://` URLs; HTTPS needs the reqwest feature.
+struct StdHttpTransport {
+    max_idle_per_host: usize,
+    idle_timeout: Duration,
+    idle: Mutex<IdleConnections>,
+    stats: Mutex<PoolStats>,
+}
+
+/// Idle connections per (host, port), each with when it went idle
+type IdleConnections = HashMap<(String, u16), Vec<(TcpStream, Instant)>>;
+
+/// A snapshot of `StdHttpTransport`'s pool
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct PoolStats {
+    /// TCP connections made
+    opened: u64,
+    /// Requests sent on a pooled connection
+    reused: u64,
+    /// Idle connections closed for outliving the idle timeout
+    expired: u64,
+    /// Reusable connections closed because their host's pool was full
+    discarded: u64,
+    /// Connections waiting in the pool right now
+    idle: usize,
+}
+
+impl StdHttpTransport {
+    fn new() -> Self {
+        StdHttpTransport {
+            max_idle_per_host: 4,
+            idle_timeout: Duration::from_secs(30),
+            idle: Mutex::new(HashMap::new()),
+            stats: Mutex::new(PoolStats::default()),
+        }
+    }
+
+    fn max_idle_per_host(mut self, max_idle_per_host: usize) -> Self {
+        self.max_idle_per_host = max_idle_per_host;
+        self
+    }
+
+    fn idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Splits `http://host[:port]/path` into host, port, and path
+    fn parse_url(url: &str) -> Result<(String, u16, String), ApiError> {
+        let rest = match url.strip_prefix("http://") {
+            Some(rest) => rest,
+            None if url.starts_with("https://") => {
+                return Err(ApiError::NetworkError(
+                    "HTTPS needs the reqwest feature".to_string(),
+                ))
+            }
+            None => return Err(ApiError::ValidationError(format!("Not an http:// URL: {}", url))),
+        };
+
+        let (authority, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => {
+                let port = port
+                    .parse()
+                    .map_err(|_| ApiError::ValidationError(format!("Bad port in {}", url)))?;
+                (host, port)
+            }
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(ApiError::ValidationError(format!("No host in {}", url)));
+        }
+        Ok((host.to_string(), port, path.to_string()))
+    }
+
+    fn connect(&self, host: &str, port: u16, timeout: Duration) -> Result<TcpStream, ApiError> {
+        let network = |e: std::io::Error| ApiError::NetworkError(format!("{}:{}: {}", host, port, e));
+        let addr = (host, port)
+            .to_socket_addrs()
+            .map_err(network)?
+            .next()
+            .ok_or_else(|| ApiError::NetworkError(format!("{} did not resolve", host)))?;
+
+        let stream = TcpStream::connect_timeout(&addr, timeout).map_err(network)?;
+        stream.set_nodelay(true).map_err(network)?;
+        self.stats.lock().unwrap().opened += 1;
+        Ok(stream)
+    }
+
+    /// The most recently used idle connection to the host, if any is still
+    /// within the idle timeout. The flag says whether it came from the pool.
+    fn checkout(
+        &self,
+        host: &str,
+        port: u16,
+        timeout: Duration,
+    ) -> Result<(TcpStream, bool), ApiError> {
+        let pooled = {
+            let mut idle = self.idle.lock().unwrap();
+            let mut stats = self.stats.lock().unwrap();
+            let connections = idle.entry((host.to_string(), port)).or_default();
+            let before = connections.len();
+            connections.retain(|(_, since)| since.elapsed() < self.idle_timeout);
+            stats.expired += (before - connections.len()) as u64;
+            let pooled = connections.pop().map(|(stream, _)| stream);
+            if pooled.is_some() {
+                stats.reused += 1;
+            }
+            pooled
+        };
+
+        match pooled {
+            Some(stream) => Ok((stream, true)),
+            None => Ok((self.connect(host, port, timeout)?, false)),
+        }
+    }
+
+    fn checkin(&self, host: String, port: u16, stream: TcpStream) {
+        let mut idle = self.idle.lock().unwrap();
+        let connections = idle.entry((host, port)).or_default();
+        if connections.len() < self.max_idle_per_host {
+            connections.push((stream, Instant::now()));
+        } else {
+            self.stats.lock().unwrap().discarded += 1;
+        }
+    }
+
+    /// Sends one request and reads its response. The flag says whether the
+    /// connection can carry another request afterwards.
+    fn exchange(
+        stream: &mut TcpStream,
+        message: &[u8],
+        timeout: Duration,
+        head_only: bool,
+    ) -> Result<(HttpResponse, bool), ApiError> {
+        let network = |e: std::io::Error| ApiError::NetworkError(e.to_string());
+        stream.set_read_timeout(Some(timeout)).map_err(network)?;
+        stream.set_write_timeout(Some(timeout)).map_err(network)?;
+        stream.write_all(message).map_err(network)?;
+
+        let (raw, framed) = read_response(stream, head_only)?;
+        if raw.is_empty() {
+            return Err(ApiError::NetworkError(
+                "Connection closed before a response arrived".to_string(),
+            ));
+        }
+        let response = parse_http_response(&raw, head_only)?;
+        let close = response
+            .headers
+            .get("connection")
+            .is_some_and(|value| value.eq_ignore_ascii_case("close"));
+        Ok((response, framed && !close))
+    }
+}
+
+impl Transport for StdHttpTransport {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, ApiError> {
+        let (host, port, path) = Self::parse_url(&request.url)?;
+        let start = Instant::now();
+        let head_only = request.method == HttpMethod::HEAD;
+
+        let body = request.body.as_deref().unwrap_or("");
+        let mut message = format!("{} {} HTTP/1.1\r\nHost: {}\r\n", request.method, path, host);
+        for (key, value) in &request.headers {
+            message.push_str(&format!("{}: {}\r\n", key, value));
+        }
+        if request.body.is_some() {
+            message.push_str(&format!("Content-Length: {}\r\n", body.len()));
+        }
+        message.push_str("Connection: keep-alive\r\n\r\n");
+        message.push_str(body);
+
+        let (mut stream, pooled) = self.checkout(&host, port, request.timeout)?;
+        let mut result = Self::exchange(&mut stream, message.as_bytes(), request.timeout, head_only);
+
+        // The server may have closed a pooled connection while it sat idle,
+        // which only shows once we use it. Try once more on a new one.
+        if pooled && matches!(result, Err(ApiError::NetworkError(_))) {
+            stream = self.connect(&host, port, request.timeout)?;
+            result = Self::exchange(&mut stream, message.as_bytes(), request.timeout, head_only);
+        }
+
+        let (mut response, reusable) = result?;
+        if reusable {
+            self.checkin(host, port, stream);
+        }
+        response.elapsed = start.elapsed();
+        Ok(response)
+    }
+
+    fn pool_stats(&self) -> Option<PoolStats> {
+        let idle = self.idle.lock().unwrap().values().map(Vec::len).sum();
+        Some(PoolStats {
+            idle,
+            ..*self.stats.lock().unwrap()
+        })
+    }
+}
+
+/// Status line and headers of a response (keys lowercased)
+struct ResponseHead {
+    status_code: u16,
+    status_text: String,
+    headers: HashMap<String, String>,
+    body_start: usize,
+}
+
+/// How a response body is delimited
+enum BodyFraming {
+    Empty,
+    Length(usize),
+    Chunked,
+    UntilClose,
+}
+
+/// Parses the status line and headers, or `None` if they haven't all arrived
+fn parse_response_head(raw: &[u8]) -> Result<Option<ResponseHead>, ApiError> {
+    let parse_error = |msg: &str| ApiError::ParseError(msg.to_string());
+    let Some(split) = raw.windows(4).position(|w| w == b"\r\n\r\n") else {
+        return Ok(None);
+    };
+    let head = String::from_utf8_lossy(&raw[..split]);
+
+    let mut lines = head.split("\r\n");
+    let status_line = lines.next().unwrap_or("");
+    let mut parts = status_line.splitn(3, ' ');
+    let version = parts.next().unwrap_or("");
+    if !version.starts_with("HTTP/") {
+        return Err(parse_error(&format!("Bad status line: {}", status_line)));
+    }
+    let status_code = parts
+        .next()
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| parse_error(&format!("Bad status code: {}", status_line)))?;
+    let status_text = parts.next().unwrap_or("").to_string();
+
+    let mut headers = HashMap::new();
+    for line in lines {
+        if let Some((key, value)) = line.split_once(':') {
+            headers.insert(key.trim().to_ascii_lowercase(), value.trim().to_string());
+        }
+    }
+
+    Ok(Some(ResponseHead {
+        status_code,
+        status_text,
+        headers,
+        body_start: split + 4,
+    }))
+}
+
+impl ResponseHead {
+    fn framing(&self, head_only: bool) -> Result<BodyFraming, ApiError> {
+        let chunked = self
+            .headers
+            .get("transfer-encoding")
+            .is_some_and(|te| te.eq_ignore_ascii_case("chunked"));
+
+        if head_only || self.status_code == 204 || self.status_code == 304 {
+            Ok(BodyFraming::Empty)
+        } else if chunked {
+            Ok(BodyFraming::Chunked)
+        } else if let Some(length) = self.headers.get("content-length") {
+            let length = length
+                .parse()
+                .map_err(|_| ApiError::ParseError("Bad Content-Length".to_string()))?;
+            Ok(BodyFraming::Length(length))
+        } else {
+            Ok(BodyFraming::UntilClose)
+        }
+    }
+}
+
+/// Where the response at the start of `raw` ends, once all of it has
+/// arrived. A body that runs until the connection closes never ends early.
+fn response_end(raw: &[u8], head_only: bool) -> Result<Option<usize>, ApiError> {
+    let Some(head) = parse_response_head(raw)? else {
+        return Ok(None);
+    };
+    let body = &raw[head.body_start..];
+    Ok(match head.framing(head_only)? {
+        BodyFraming::Empty => Some(head.body_start),
+        BodyFraming::Length(length) => (body.len() >= length).then_some(head.body_start + length),
+        BodyFraming::Chunked => {
+            let mut rest = body;
+            decode_chunked(&mut rest).ok().map(|_| raw.len() - rest.len())
+        }
+        BodyFraming::UntilClose => None,
+    })
+}
+
+/// Reads one response off the stream. The flag is false when the response
+/// only ended because the connection did, or unexpected bytes followed it.
+fn read_response(stream: &mut TcpStream, head_only: bool) -> Result<(Vec<u8>, bool), ApiError> {
+    let mut raw = Vec::new();
+    let mut buf = [0u8; 8192];
+    loop {
+        if let Some(end) = response_end(&raw, head_only)? {
+            let exact = end == raw.len();
+            raw.truncate(end);
+            return Ok((raw, exact));
+        }
+        let n = stream
+            .read(&mut buf)
+            .map_err(|e| ApiError::NetworkError(e.to_string()))?;
+        if n == 0 {
+            return Ok((raw, false));
+        }
+        raw.extend_from_slice(&buf[..n]);
+    }
+}
+
+/// Parses a complete HTTP/1.1 response: status line, headers (keys
+/// lowercased), and a body framed by Content-Length, chunked encoding, or
+/// the end of the connection
+fn parse_http_response(raw: &[u8], head_only: bool) -> Result<HttpResponse, ApiError> {
+    let parse_error = |msg: &str| ApiError::ParseError(msg.to_string());
+    let head = parse_response_head(raw)?
+        .ok_or_else(|| parse_error("Response ended before the headers did"))?;
+    let mut rest = &raw[head.body_start..];
+
+    let body = match head.framing(head_only)? {
+        BodyFraming::Empty => Vec::new(),
+        BodyFraming::Chunked => decode_chunked(&mut rest)?,
+        BodyFraming::Length(length) => rest
+            .get(..length)
+            .ok_or_else(|| parse_error("Body shorter than Content-Length"))?
+            .to_vec(),
+        BodyFraming::UntilClose => rest.to_vec(),
+    };
+
+    Ok(HttpResponse {
+        status_code: head.status_code,
+        status_text: head.status_text,
+        headers: head.headers,
+        body: String::from_utf8_lossy(&body).into_owned(),
+        elapsed: Duration::ZERO,
+    })
+}
+
+/// Decodes a chunked body, advancing `rest` past it
+fn decode_chunked(rest: &mut &[u8]) -> Result<Vec<u8>, ApiError> {
+    let parse_error = |msg: &str| ApiError::ParseError(msg.to_string());
+    let mut body = Vec::new();
+    loop {
+        let line_end = rest
+            .windows(2)
+            .position(|w| w == b"\r\n")
+            .ok_or_else(|| parse_error("Unterminated chunk size"))?;
+        let size_line = String::from_utf8_lossy(&rest[..line_end]);
+        let size_hex = size_line.split(';').next().unwrap_or("").trim();
+        let size = usize::from_str_radix(size_hex, 16)
+            .map_err(|_| parse_error(&format!("Bad chunk size: {}", size_hex)))?;
+        *rest = &rest[line_end + 2..];
+
+        if size == 0 {
+            // Trailers, if any, end with a blank line
+            loop {
+                let end = rest
+                    .windows(2)
+                    .position(|w| w == b"\r\n")
+                    .ok_or_else(|| parse_error("Unterminated trailers"))?;
+                *rest = &rest[end + 2..];
+                if end == 0 {
+                    return Ok(body);
+                }
+            }
+        }
+        let chunk = rest.get(..size).ok_or_else(|| parse_error("Truncated chunk"))?;
+        body.extend_from_slice(chunk);
+        *rest = rest.get(size + 2..).ok_or_else(|| parse_error("Truncated chunk"))?;
+    }
+}
+
+/// reqwest's blocking client, for HTTPS and everything else std doesn't do
+#[cfg(feature = "reqwest")]
+struct ReqwestTransport {
+    client: reqwest::blocking::Client,
+}
+
+#[cfg(feature = "reqwest")]
+impl ReqwestTransport {
+    fn new() -> Self {
+        ReqwestTransport {
+            client: reqwest::blocking::Client::new(),
+        }
+    }
+}
+
+#[cfg(feature = "reqwest")]
+impl Transport for ReqwestTransport {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, ApiError> {
+        let method = reqwest::Method::from_bytes(request.method.to_string().as_bytes())
+            .map_err(|e| ApiError::ValidationError(e.to_string()))?;
+        let mut builder = self
+            .client
+            .request(method, request.url.as_str())
+            .timeout(request.timeout);
+        for (key, value) in &request.headers {
+            builder = builder.header(key.as_str(), value.as_str());
+        }
+        if let Some(body) = &request.body {
+            builder = builder.body(body.clone());
+        }
+
+        let start = Instant::now();
+        let response = builder
+            .send()
+            .map_err(|e| ApiError::NetworkError(e.to_string()))?;
+        let status = response.status();
+        let headers = response
+            .headers()
+            .iter()
+            .map(|(key, value)| {
+                (key.as_str().to_string(), value.to_str().unwrap_or("").to_string())
+            })
+            .collect();
+        let body = response
+            .text()
+            .map_err(|e| ApiError::NetworkError(e.to_string()))?;
+
+        Ok(HttpResponse {
+            status_code: status.as_u16(),
+            status_text: status.canonical_reason().unwrap_or("").to_string(),
+            headers,
+            body,
+            elapsed: start.elapsed(),
+        })
+    }
+}
+
+// ============================================================================
+// MIDDLEWARE
+// ============================================================================
+
+/// Wraps request execution. A middleware may change the request, answer it
+/// without calling `next`, call `next` more than once, or change the response.
+trait Middleware {
+    fn handle(&self, request: HttpRequest, next: Next<'_>) -> Result<HttpResponse, ApiError>;
+}
+
+/// The rest of the chain: the remaining middleware, then the transport
+#[derive(Clone, Copy)]
+struct Next<'a> {
+    middleware: &'a [Box<dyn Middleware>],
+    transport: &'a dyn Transport,
+}
+
+impl<'a> Next<'a> {
+    fn run(self, request: HttpRequest) -> Result<HttpResponse, ApiError> {
+        match self.middleware.split_first() {
+            Some((first, rest)) => first.handle(
+                request,
+                Next {
+                    middleware: rest,
+                    transport: self.transport,
+                },
+            ),
+            None => self.transport.send(&request),
+        }
+    }
+}
+
+/// Plain closures work as middleware
+impl<F> Middleware for F
+where
+    F: Fn(HttpRequest, Next<'_>) -> Result<HttpResponse, ApiError>,
+{
+    fn handle(&self, request: HttpRequest, next: Next<'_>) -> Result<HttpResponse, ApiError> {
+        self(request, next)
+    }
+}
+
+/// Prints every request and its outcome
+struct Logger;
+
+impl Middleware for Logger {
+    fn handle(&self, request: HttpRequest, next: Next<'_>) -> Result<HttpResponse, ApiError> {
+        let label = format!("{} {}", request.method, request.url);
+        let start = Instant::now();
+        let result = next.run(request);
+        match &result {
+            Ok(response) => println!(
+                "  [log] {} -> {} in {:.0?}",
+                label,
+                response.status_code,
+                start.elapsed()
+            ),
+            Err(e) => println!("  [log] {} -> {}", label, e),
+        }
+        result
+    }
+}
+
+/// Adds an `X-Signature` header over method, URL, and body. DefaultHasher
+/// stands in for the HMAC a real API would require.
+struct RequestSigner {
+    key_id: String,
+    secret: String,
+}
+
+impl RequestSigner {
+    fn new(key_id: &str, secret: &str) -> Self {
+        RequestSigner {
+            key_id: key_id.to_string(),
+            secret: secret.to_string(),
+        }
+    }
+}
+
+impl Middleware for RequestSigner {
+    fn handle(&self, request: HttpRequest, next: Next<'_>) -> Result<HttpResponse, ApiError> {
+        let mut hasher = DefaultHasher::new();
+        self.secret.hash(&mut hasher);
+        request.method.to_string().hash(&mut hasher);
+        request.url.hash(&mut hasher);
+        request.body.hash(&mut hasher);
+        let signature = format!("{}:{:016x}", self.key_id, hasher.finish());
+        next.run(request.header("X-Signature", &signature))
+    }
+}
+
+/// Serves repeated successful GETs from memory until they are `ttl` old.
+/// Cached responses carry `x-cache: HIT`.
+struct ResponseCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (Instant, HttpResponse)>>,
+}
+
+impl ResponseCache {
+    fn new(ttl: Duration) -> Self {
+        ResponseCache {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl Middleware for ResponseCache {
+    fn handle(&self, request: HttpRequest, next: Next<'_>) -> Result<HttpResponse, ApiError> {
+        if request.method != HttpMethod::GET {
+            return next.run(request);
+        }
+
+        let url = request.url.clone();
+        if let Some((stored, response)) = self.entries.lock().unwrap().get(&url) {
+            if stored.elapsed() < self.ttl {
+                let mut response = response.clone();
+                response.headers.insert("x-cache".to_string(), "HIT".to_string());
+                response.elapsed = Duration::ZERO;
+                return Ok(response);
+            }
+        }
+
+        let response = next.run(request)?;
+        if response.is_success() {
+            self.entries
+                .lock()
+                .unwrap()
+                .insert(url, (Instant::now(), response.clone()));
+        }
+        Ok(response)
+    }
+}
+
+// ============================================================================
+// OAUTH2
+// ============================================================================
+
+/// OAuth2 client-credentials and refresh-token grants (RFC 6749 §4.4, §6)
+#[cfg(feature = "serde")]
+mod oauth2 {
+    use super::*;
+
+    /// Tokens this close to expiry are renewed before use
+    const EXPIRY_MARGIN: Duration = Duration::from_secs(30);
+
+    #[derive(Debug, Clone)]
+    pub struct OAuth2Config {
+        pub token_url: String,
+        pub client_id: String,
+        pub client_secret: String,
+        pub scope: Option<String>,
+    }
+
+    impl OAuth2Config {
+        pub fn new(token_url: &str, client_id: &str, client_secret: &str) -> Self {
+            OAuth2Config {
+                token_url: token_url.to_string(),
+                client_id: client_id.to_string(),
+                client_secret: client_secret.to_string(),
+                scope: None,
+            }
+        }
+
+        pub fn scope(mut self, scope: &str) -> Self {
+            self.scope = Some(scope.to_string());
+            self
+        }
+    }
+
+    #[derive(serde::Deserialize)]
+    struct TokenResponse {
+        access_token: String,
+        expires_in: Option<u64>,
+        refresh_token: Option<String>,
+    }
+
+    struct Token {
+        access_token: String,
+        refresh_token: Option<String>,
+        expires_at: Option<Instant>,
+    }
+
+    impl Token {
+        fn expires_soon(&self) -> bool {
+            self.expires_at
+                .is_some_and(|at| at.saturating_duration_since(Instant::now()) < EXPIRY_MARGIN)
+        }
+    }
+
+    /// Middleware that attaches a bearer token, caching it until it nears
+    /// expiry. A 401 renews the token and retries the request once.
+    /// Token requests go down the rest of the chain like any other request.
+    pub struct OAuth2 {
+        config: OAuth2Config,
+        token: Mutex<Option<Token>>,
+    }
+
+    impl OAuth2 {
+        pub fn new(config: OAuth2Config) -> Self {
+            OAuth2 {
+                config,
+                token: Mutex::new(None),
+            }
+        }
+
+        /// The cached access token, or a new one if there is none, it is
+        /// about to expire, or it is the one the server just `rejected`.
+        /// The lock is held while fetching so concurrent callers share one
+        /// token request.
+        fn access_token(
+            &self,
+            next: Next<'_>,
+            rejected: Option<&str>,
+        ) -> Result<String, ApiError> {
+            let mut cached = self.token.lock().unwrap();
+            if let Some(token) = cached.as_ref() {
+                if !token.expires_soon() && rejected != Some(token.access_token.as_str()) {
+                    return Ok(token.access_token.clone());
+                }
+            }
+
+            let previous_refresh = cached.as_ref().and_then(|token| token.refresh_token.clone());
+            let token = match &previous_refresh {
+                // A refresh token can be revoked too; start over if it is
+                Some(refresh_token) => self
+                    .refresh_token_grant(next, refresh_token)
+                    .or_else(|_| self.client_credentials_grant(next))?,
+                None => self.client_credentials_grant(next)?,
+            };
+
+            let access_token = token.access_token.clone();
+            *cached = Some(Token {
+                // Servers may keep the old refresh token without resending it
+                refresh_token: token.refresh_token.or(previous_refresh),
+                ..token
+            });
+            Ok(access_token)
+        }
+
+        fn client_credentials_grant(&self, next: Next<'_>) -> Result<Token, ApiError> {
+            println!("  [oauth2] client_credentials grant");
+            let mut params = vec![("grant_type", "client_credentials")];
+            if let Some(scope) = &self.config.scope {
+                params.push(("scope", scope));
+            }
+            self.request_token(next, params)
+        }
+
+        fn refresh_token_grant(
+            &self,
+            next: Next<'_>,
+            refresh_token: &str,
+        ) -> Result<Token, ApiError> {
+            println!("  [oauth2] refresh_token grant");
+            self.request_token(
+                next,
+                vec![("grant_type", "refresh_token"), ("refresh_token", refresh_token)],
+            )
+        }
+
+        /// Posts a form to the token endpoint, authenticating the client
+        /// with its credentials in the body
+        fn request_token<'a>(
+            &'a self,
+            next: Next<'_>,
+            mut params: Vec<(&'a str, &'a str)>,
+        ) -> Result<Token, ApiError> {
+            params.push(("client_id", &self.config.client_id));
+            params.push(("client_secret", &self.config.client_secret));
+            let request = HttpRequest::new(HttpMethod::POST, &self.config.token_url)
+                .header("Content-Type", "application/x-www-form-urlencoded")
+                .body(form_encode(&params));
+
+            let response = next.run(request)?;
+            if !response.is_success() {
+                return Err(ApiError::HttpError(response.status_code, response.body));
+            }
+            let token: TokenResponse = response.json()?;
+            Ok(Token {
+                access_token: token.access_token,
+                refresh_token: token.refresh_token,
+                expires_at: token.expires_in.map(|secs| Instant::now() + Duration::from_secs(secs)),
+            })
+        }
+    }
+
+    impl Middleware for OAuth2 {
+        fn handle(&self, request: HttpRequest, next: Next<'_>) -> Result<HttpResponse, ApiError> {
+            let token = self.access_token(next, None)?;
+            let authorized = request.clone().header("Authorization", &format!("Bearer {}", token));
+            let response = next.run(authorized)?;
+            if response.status_code != 401 {
+                return Ok(response);
+            }
+
+            // Rejected before it expired: revoked, or the clocks disagree
+            let token = self.access_token(next, Some(&token))?;
+            next.run(request.header("Authorization", &format!("Bearer {}", token)))
+        }
+    }
+
+    /// `application/x-www-form-urlencoded`
+    fn form_encode(params: &[(&str, &str)]) -> String {
+        let encode = |value: &str| {
+            value
+                .bytes()
+                .map(|byte| match byte {
+                    b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                        (byte as char).to_string()
+                    }
+                    b' ' => "+".to_string(),
+                    _ => format!("%{:02X}", byte),
+                })
+                .collect::<String>()
+        };
+        params
+            .iter()
+            .map(|(key, value)| format!("{}={}", encode(key), encode(value)))
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+}
+
+// ============================================================================
+// RETRIES
+// ============================================================================
+
+/// When and how long to wait before trying a request again.
+///
+/// Statuses in `retry_on` and network errors are retried for idempotent
+/// methods. 429 and 503 mean the server did not act on the request, so those
+/// are retried for every method, waiting as long as `Retry-After` asks (up
+/// to `max_retry_after`; longer waits return the response instead).
+#[derive(Debug, Clone)]
+struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    max_retry_after: Duration,
+    jitter: bool,
+    retry_on: Vec<u16>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+            max_retry_after: Duration::from_secs(30),
+            jitter: true,
+            retry_on: vec![429, 500, 502, 503, 504],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A single attempt, no retries
+    fn none() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            ..RetryPolicy::default()
+        }
+    }
+
+    fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    fn base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// The wait before retrying after a response, or `None` to return it
+    fn delay_for_response(
+        &self,
+        method: HttpMethod,
+        response: &HttpResponse,
+        attempt: u32,
+    ) -> Option<Duration> {
+        let refused = response.status_code == 429 || response.status_code == 503;
+        if !self.retry_on.contains(&response.status_code) || !(refused || method.is_idempotent()) {
+            return None;
+        }
+
+        // Only the delta-seconds form of Retry-After; HTTP dates fall back to backoff
+        let retry_after = response
+            .headers
+            .get("retry-after")
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .map(Duration::from_secs);
+        match retry_after {
+            Some(wait) if refused && wait > self.max_retry_after => None,
+            Some(wait) if refused => Some(wait),
+            _ => Some(self.backoff(attempt)),
+        }
+    }
+
+    /// The wait before retrying after a transport error, or `None` to give up
+    fn delay_for_error(&self, method: HttpMethod, error: &ApiError, attempt: u32) -> Option<Duration> {
+        match error {
+            ApiError::NetworkError(_) if method.is_idempotent() => Some(self.backoff(attempt)),
+            _ => None,
+        }
+    }
+
+    /// `base_delay * 2^(attempt - 1)`, capped at `max_delay`. With jitter the
+    /// wait is drawn from the upper half of that, so clients that failed
+    /// together don't retry together.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exponential = self
+            .base_delay
+            .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+            .min(self.max_delay);
+        if !self.jitter {
+            return exponential;
+        }
+        // Sub-second clock noise is random enough to spread clients apart
+        let noise = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or(0);
+        let fraction = 0.5 + (noise % 1000) as f64 / 2000.0;
+        exponential.mul_f64(fraction)
+    }
+}
+
+/// Counters behind `ApiClient::retry_stats`
+#[derive(Default)]
+struct RetryMetrics {
+    requests: AtomicU64,
+    retries: AtomicU64,
+    exhausted: AtomicU64,
+}
+
+/// A snapshot of the retry counters
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct RetryStats {
+    /// Calls to `execute`
+    requests: u64,
+    /// Attempts beyond the first
+    retries: u64,
+    /// Requests that were still retryable when attempts ran out
+    exhausted: u64,
+}
+
+// ============================================================================
+// CIRCUIT BREAKER
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CircuitState {
+    /// Requests flow normally
+    Closed,
+    /// Requests fail fast with `ApiError::CircuitOpen` until the cooldown ends
+    Open,
+    /// One trial request is allowed through; its outcome closes or reopens
+    HalfOpen,
+}
+
+struct HostCircuit {
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Instant,
+    trial_in_flight: bool,
+}
+
+/// Tracks each host separately. After `failure_threshold` consecutive
+/// failures (network errors or 5xx responses) the host's circuit opens;
+/// after `cooldown` it half-opens for a single trial request.
+struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    hosts: Mutex<HashMap<String, HostCircuit>>,
+}
+
+impl CircuitBreaker {
+    fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        CircuitBreaker {
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// `scheme://host[:port]` of a URL
+    fn host_of(url: &str) -> &str {
+        let authority_start = url.find("://").map_or(0, |i| i + 3);
+        let end = url[authority_start..]
+            .find(['/', '?'])
+            .map_or(url.len(), |i| authority_start + i);
+        &url[..end]
+    }
+
+    fn state(&self, host: &str) -> CircuitState {
+        let hosts = self.hosts.lock().unwrap();
+        match hosts.get(host) {
+            Some(circuit) if self.cooled_down(circuit) => CircuitState::HalfOpen,
+            Some(circuit) => circuit.state,
+            None => CircuitState::Closed,
+        }
+    }
+
+    fn cooled_down(&self, circuit: &HostCircuit) -> bool {
+        circuit.state == CircuitState::Open && circuit.opened_at.elapsed() >= self.cooldown
+    }
+
+    /// Runs `send` unless the host's circuit refuses, and records the outcome
+    fn call(
+        &self,
+        host: &str,
+        send: impl FnOnce() -> Result<HttpResponse, ApiError>,
+    ) -> Result<HttpResponse, ApiError> {
+        self.admit(host)?;
+        let result = send();
+        let failed = match &result {
+            Ok(response) => response.is_server_error(),
+            Err(e) => matches!(e, ApiError::NetworkError(_)),
+        };
+        self.record(host, failed);
+        result
+    }
+
+    fn admit(&self, host: &str) -> Result<(), ApiError> {
+        let mut hosts = self.hosts.lock().unwrap();
+        let Some(circuit) = hosts.get_mut(host) else {
+            return Ok(());
+        };
+        match circuit.state {
+            CircuitState::Closed => Ok(()),
+            CircuitState::Open if self.cooled_down(circuit) => {
+                circuit.state = CircuitState::HalfOpen;
+                circuit.trial_in_flight = true;
+                println!("  ⚡ {} half-open, sending a trial request", host);
+                Ok(())
+            }
+            CircuitState::HalfOpen if !circuit.trial_in_flight => {
+                circuit.trial_in_flight = true;
+                Ok(())
+            }
+            CircuitState::Open | CircuitState::HalfOpen => {
+                Err(ApiError::CircuitOpen(host.to_string()))
+            }
+        }
+    }
+
+    fn record(&self, host: &str, failed: bool) {
+        let mut hosts = self.hosts.lock().unwrap();
+        let circuit = hosts.entry(host.to_string()).or_insert(HostCircuit {
+            state: CircuitState::Closed,
+            consecutive_failures: 0,
+            opened_at: Instant::now(),
+            trial_in_flight: false,
+        });
+        circuit.trial_in_flight = false;
+
+        if !failed {
+            if circuit.state != CircuitState::Closed {
+                println!("  ⚡ {} closed", host);
+            }
+            circuit.state = CircuitState::Closed;
+            circuit.consecutive_failures = 0;
+            return;
+        }
+
+        circuit.consecutive_failures += 1;
+        let reopen = circuit.state == CircuitState::HalfOpen;
+        if reopen || circuit.consecutive_failures >= self.failure_threshold {
+            if circuit.state != CircuitState::Open {
+                println!(
+                    "  ⚡ {} open after {} consecutive failure(s)",
+                    host, circuit.consecutive_failures
+                );
+            }
+            circuit.state = CircuitState::Open;
+            circuit.opened_at = Instant::now();
+        }
+    }
+}
+
+// ============================================================================
+// API CLIENT
+// ============================================================================
+
+struct ApiClient {
+    base_url: String,
+    default_headers: HashMap<String, String>,
+    timeout: Duration,
+    transport: Box<dyn Transport>,
+    middleware: Vec<Box<dyn Middleware>>,
+    retry: RetryPolicy,
+    retry_metrics: RetryMetrics,
+    circuit_breaker: Option<CircuitBreaker>,
+}
+
+impl ApiClient {
+    /// A client on the mock transport; see `with_transport` for real requests
+    fn new(base_url: &str) -> Self {
+        ApiClient {
+            base_url: base_url.to_string(),
+            default_headers: HashMap::new(),
+            timeout: Duration::from_secs(30),
+            transport: Box::new(MockTransport),
+            middleware: Vec::new(),
+            retry: RetryPolicy::none(),
+            retry_metrics: RetryMetrics::default(),
+            circuit_breaker: None,
+        }
+    }
+
+    fn with_transport(mut self, transport: impl Transport + 'static) -> Self {
+        self.transport = Box::new(transport);
+        self
+    }
+
+    /// Appends to the middleware chain; the first added runs outermost
+    fn with_middleware(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.middleware.push(Box::new(middleware));
+        self
+    }
+
+    /// Authenticates every request with an OAuth2 access token, added to the
+    /// middleware chain at this point. A relative token URL is resolved
+    /// against the base URL.
+    #[cfg(feature = "serde")]
+    fn with_oauth2(self, mut config: oauth2::OAuth2Config) -> Self {
+        if !config.token_url.starts_with("http") {
+            config.token_url = format!("{}{}", self.base_url, config.token_url);
+        }
+        self.with_middleware(oauth2::OAuth2::new(config))
+    }
+
+    /// Retries wrap the whole middleware chain, so every attempt passes
+    /// through it again
+    fn with_retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// Every attempt, retries included, passes through the breaker
+    fn with_circuit_breaker(mut self, breaker: CircuitBreaker) -> Self {
+        self.circuit_breaker = Some(breaker);
+        self
+    }
+
+    /// The breaker's state for the host `url` points at, if there is a breaker
+    fn circuit_state(&self, url: &str) -> Option<CircuitState> {
+        let breaker = self.circuit_breaker.as_ref()?;
+        let url = if url.starts_with("http") {
+            url.to_string()
+        } else {
+            format!("{}{}", self.base_url, url)
+        };
+        Some(breaker.state(CircuitBreaker::host_of(&url)))
     }
-}
-
-// ============================================================================
-// API CLIENT
-// ============================================================================
 
-struct ApiClient {
-    base_url: String,
-    default_headers: HashMap<String, String>,
-    timeout: Duration,
-}
+    /// The transport's connection pool counters, if it pools
+    fn pool_stats(&self) -> Option<PoolStats> {
+        self.transport.pool_stats()
+    }
 
-impl ApiClient {
-    fn new(base_url: &str) -> Self {
-        ApiClient {
-            base_url: base_url.to_string(),
-            default_headers: HashMap::new(),
-            timeout: Duration::from_secs(30),
+    fn retry_stats(&self) -> RetryStats {
+        RetryStats {
+            requests: self.retry_metrics.requests.load(Ordering::Relaxed),
+            retries: self.retry_metrics.retries.load(Ordering::Relaxed),
+            exhausted: self.retry_metrics.exhausted.load(Ordering::Relaxed),
         }
     }
 
@@ -194,64 +1475,43 @@ impl ApiClient {
         };
         request.url = full_url;
 
-        // Execute request (mock implementation)
-        self.execute_mock(request)
-    }
-
-    /// Mock HTTP execution for demonstration
-    fn execute_mock(&self, request: HttpRequest) -> Result<HttpResponse, ApiError> {
-        println!("→ {} {}", request.method, request.url);
-        
-        let start = Instant::now();
-        
-        // Simulate network delay
-        std::thread::sleep(Duration::from_millis(100));
+        // The client's timeout caps every request's
+        request.timeout = request.timeout.min(self.timeout);
 
-        let (status_code, status_text, body) = match request.method {
-            HttpMethod::GET => {
-                if request.url.contains("/users/1") {
-                    (200, "OK", r#"{"id": 1, "name": "Alice", "email": "alice@example.com"}"#)
-                } else if request.url.contains("/users") {
-                    (200, "OK", r#"[{"id": 1, "name": "Alice"}, {"id": 2, "name": "Bob"}]"#)
-                } else if request.url.contains("/notfound") {
-                    (404, "Not Found", r#"{"error": "Resource not found"}"#)
-                } else {
-                    (200, "OK", r#"{"status": "success"}"#)
+        let chain = Next {
+            middleware: &self.middleware,
+            transport: self.transport.as_ref(),
+        };
+        let metrics = &self.retry_metrics;
+        metrics.requests.fetch_add(1, Ordering::Relaxed);
+
+        let host = CircuitBreaker::host_of(&request.url).to_string();
+
+        let mut attempt = 1;
+        loop {
+            let result = match &self.circuit_breaker {
+                Some(breaker) => breaker.call(&host, || chain.run(request.clone())),
+                None => chain.run(request.clone()),
+            };
+            let delay = match &result {
+                Ok(response) => self.retry.delay_for_response(request.method, response, attempt),
+                Err(e) => self.retry.delay_for_error(request.method, e, attempt),
+            };
+            let Some(delay) = delay else {
+                return result;
+            };
+            if attempt >= self.retry.max_attempts {
+                if self.retry.max_attempts > 1 {
+                    metrics.exhausted.fetch_add(1, Ordering::Relaxed);
                 }
+                return result;
             }
-            HttpMethod::POST => {
-                (201, "Created", r#"{"id": 3, "name": "Charlie", "created": true}"#)
-            }
-            HttpMethod::PUT => {
-                (200, "OK", r#"{"id": 1, "name": "Alice Updated", "updated": true}"#)
-            }
-            HttpMethod::PATCH => {
-                (200, "OK", r#"{"id": 1, "name": "Alice Patched", "updated": true}"#)
-            }
-            HttpMethod::DELETE => {
-                (204, "No Content", "")
-            }
-            HttpMethod::HEAD => {
-                (200, "OK", "")
-            }
-            HttpMethod::OPTIONS => {
-                (200, "OK", "")
-            }
-        };
-
-        let elapsed = start.elapsed();
-        
-        let mut headers = HashMap::new();
-        headers.insert("content-type".to_string(), "application/json".to_string());
-        headers.insert("server".to_string(), "MockServer/1.0".to_string());
 
-        Ok(HttpResponse {
-            status_code,
-            status_text: status_text.to_string(),
-            headers,
-            body: body.to_string(),
-            elapsed,
-        })
+            println!("  ↻ retry {} of {} in {:.0?}", attempt, self.retry.max_attempts - 1, delay);
+            std::thread::sleep(delay);
+            metrics.retries.fetch_add(1, Ordering::Relaxed);
+            attempt += 1;
+        }
     }
 
     // ========================================================================
@@ -270,6 +1530,32 @@ impl ApiClient {
         self.execute(request)
     }
 
+    /// GET request deserialized into `T`; non-2xx statuses become `HttpError`
+    #[cfg(feature = "serde")]
+    fn get_json<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T, ApiError> {
+        Self::typed(self.get(path)?)
+    }
+
+    /// POST `body` as JSON and deserialize the reply into `T`
+    #[cfg(feature = "serde")]
+    fn post_json<B, T>(&self, path: &str, body: &B) -> Result<T, ApiError>
+    where
+        B: serde::Serialize,
+        T: serde::de::DeserializeOwned,
+    {
+        let body = serde_json::to_string(body)
+            .map_err(|e| ApiError::ValidationError(format!("Could not serialize body: {}", e)))?;
+        Self::typed(self.post(path, &body)?)
+    }
+
+    #[cfg(feature = "serde")]
+    fn typed<T: serde::de::DeserializeOwned>(response: HttpResponse) -> Result<T, ApiError> {
+        if !response.is_success() {
+            return Err(ApiError::HttpError(response.status_code, response.body));
+        }
+        response.json()
+    }
+
     /// PUT request
     fn put(&self, path: &str, body: &str) -> Result<HttpResponse, ApiError> {
         let request = HttpRequest::new(HttpMethod::PUT, path).json_body(body);
@@ -299,6 +1585,118 @@ impl ApiClient {
         let request = HttpRequest::new(HttpMethod::OPTIONS, path);
         self.execute(request)
     }
+
+    /// Pages of a collection whose every page is a JSON array of `T`
+    #[cfg(feature = "serde")]
+    fn paginate<T: serde::de::DeserializeOwned>(&self, path: &str) -> Paginator<'_, T> {
+        self.paginate_with(path, |response| response.json::<Vec<T>>())
+    }
+
+    /// Pages of a collection, with `extract` pulling the items out of each
+    /// page's response
+    fn paginate_with<'a, T>(
+        &'a self,
+        path: &str,
+        extract: impl Fn(&HttpResponse) -> Result<Vec<T>, ApiError> + 'a,
+    ) -> Paginator<'a, T> {
+        Paginator {
+            client: self,
+            path: path.to_string(),
+            next: Some(path.to_string()),
+            scheme: PageScheme::LinkHeader,
+            extract: Box::new(extract),
+        }
+    }
+}
+
+// ============================================================================
+// PAGINATION
+// ============================================================================
+
+/// How a paginated collection points at its next page
+#[derive(Debug, Clone, Copy)]
+enum PageScheme {
+    /// Follow `Link: <url>; rel="next"` until a page has none
+    LinkHeader,
+    /// Add `offset` and `limit` query parameters, stopping after a short page
+    OffsetLimit { offset: usize, limit: usize },
+}
+
+type PageExtractor<'a, T> = Box<dyn Fn(&HttpResponse) -> Result<Vec<T>, ApiError> + 'a>;
+
+/// Fetches one page per `next()`, lazily, until the collection runs out.
+/// An error (including a non-2xx page) is yielded once and ends iteration.
+struct Paginator<'a, T> {
+    client: &'a ApiClient,
+    path: String,
+    next: Option<String>,
+    scheme: PageScheme,
+    extract: PageExtractor<'a, T>,
+}
+
+impl<'a, T> Paginator<'a, T> {
+    /// Switches to offset/limit paging, starting at offset 0
+    fn offset_limit(mut self, limit: usize) -> Self {
+        self.scheme = PageScheme::OffsetLimit {
+            offset: 0,
+            limit: limit.max(1),
+        };
+        self.next = Some(self.page_path(0, limit.max(1)));
+        self
+    }
+
+    fn page_path(&self, offset: usize, limit: usize) -> String {
+        let separator = if self.path.contains('?') { '&' } else { '?' };
+        format!("{}{}offset={}&limit={}", self.path, separator, offset, limit)
+    }
+
+    /// The `rel="next"` target of a `Link` header
+    fn next_link(link: &str) -> Option<String> {
+        link.split(',').find_map(|entry| {
+            let mut parts = entry.split(';');
+            let target = parts.next()?.trim().strip_prefix('<')?.strip_suffix('>')?;
+            let is_next = parts.any(|param| {
+                let Some((key, value)) = param.split_once('=') else {
+                    return false;
+                };
+                key.trim().eq_ignore_ascii_case("rel")
+                    && value.trim().trim_matches('"').split_whitespace().any(|rel| rel == "next")
+            });
+            is_next.then(|| target.to_string())
+        })
+    }
+}
+
+impl<'a, T> Iterator for Paginator<'a, T> {
+    type Item = Result<Vec<T>, ApiError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let path = self.next.take()?;
+        let page = self.client.get(&path).and_then(|response| {
+            if !response.is_success() {
+                return Err(ApiError::HttpError(response.status_code, response.body));
+            }
+            let items = (self.extract)(&response)?;
+            Ok((response, items))
+        });
+        let (response, items) = match page {
+            Ok(page) => page,
+            Err(e) => return Some(Err(e)),
+        };
+
+        self.next = match &mut self.scheme {
+            PageScheme::LinkHeader => response
+                .headers
+                .get("link")
+                .and_then(|link| Self::next_link(link)),
+            PageScheme::OffsetLimit { offset, limit } => {
+                let (offset, limit) = (*offset + items.len(), *limit);
+                self.scheme = PageScheme::OffsetLimit { offset, limit };
+                (items.len() == limit).then(|| self.page_path(offset, limit))
+            }
+        };
+        Some(Ok(items))
+    }
 }
 
 // ============================================================================
@@ -356,29 +1754,6 @@ impl ResponseHandler {
             println!("  Body: {}", response.body);
         }
     }
-
-    fn extract_json_field(json: &str, field: &str) -> Option<String> {
-        // Simple JSON field extraction (for demo purposes)
-        let search = format!("\"{}\":", field);
-        if let Some(start) = json.find(&search) {
-            let value_start = start + search.len();
-            let remaining = &json[value_start..].trim_start();
-            
-            if remaining.starts_with('"') {
-                // String value
-                if let Some(end) = remaining[1..].find('"') {
-                    return Some(remaining[1..end + 1].to_string());
-                }
-            } else {
-                // Number or boolean
-                let end = remaining
-                    .find(|c: char| c == ',' || c == '}' || c == ']')
-                    .unwrap_or(remaining.len());
-                return Some(remaining[..end].trim().to_string());
-            }
-        }
-        None
-    }
 }
 
 // ============================================================================
@@ -494,28 +1869,68 @@ fn demo_error_handling() {
     }
 }
 
+#[cfg(feature = "serde")]
+#[derive(Debug, serde::Deserialize)]
+struct User {
+    id: u64,
+    name: String,
+    email: String,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Debug, serde::Deserialize)]
+struct UserSummary {
+    id: u64,
+    name: String,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct NewUser {
+    name: String,
+}
+
+#[cfg(feature = "serde")]
 fn demo_response_parsing() {
     println!("\n=== Response Parsing Demo ===\n");
 
     let client = ApiClient::new("https://api.example.com");
 
-    match client.get("/users/1") {
-        Ok(response) => {
-            ResponseHandler::print_response(&response);
-            
-            println!("\n  Extracted fields:");
-            if let Some(id) = ResponseHandler::extract_json_field(&response.body, "id") {
-                println!("    id: {}", id);
-            }
-            if let Some(name) = ResponseHandler::extract_json_field(&response.body, "name") {
-                println!("    name: {}", name);
-            }
-            if let Some(email) = ResponseHandler::extract_json_field(&response.body, "email") {
-                println!("    email: {}", email);
+    match client.get_json::<User>("/users/1") {
+        Ok(user) => println!("  User #{}: {} <{}>", user.id, user.name, user.email),
+        Err(e) => println!("Error: {}", e),
+    }
+
+    match client.get_json::<Vec<UserSummary>>("/users") {
+        Ok(users) => {
+            for user in users {
+                println!("  Listed #{}: {}", user.id, user.name);
             }
         }
         Err(e) => println!("Error: {}", e),
     }
+
+    let new_user = NewUser { name: "Charlie".to_string() };
+    match client.post_json::<_, UserSummary>("/users", &new_user) {
+        Ok(user) => println!("  Created #{}: {}", user.id, user.name),
+        Err(e) => println!("Error: {}", e),
+    }
+
+    println!("\n  A health check is not a user:");
+    if let Err(e) = client.get_json::<User>("/health") {
+        println!("  Error: {}", e);
+    }
+
+    println!("\n  Error statuses are not deserialized:");
+    if let Err(e) = client.get_json::<User>("/notfound") {
+        println!("  Error: {}", e);
+    }
+}
+
+#[cfg(not(feature = "serde"))]
+fn demo_response_parsing() {
+    println!("\n=== Response Parsing Demo ===\n");
+    println!("Typed responses need the serde feature (see the top of this file).");
 }
 
 fn demo_request_builder() {
@@ -536,6 +1951,363 @@ fn demo_request_builder() {
     }
 }
 
+fn demo_middleware() {
+    println!("\n=== Middleware Demo ===\n");
+
+    // Refreshes the bearer token once when the server rejects it
+    let token = Mutex::new("expired-token".to_string());
+    let refresh = move |request: HttpRequest, next: Next<'_>| {
+        let current = token.lock().unwrap().clone();
+        let authorized = request.clone().header("Authorization", &format!("Bearer {}", current));
+        let response = next.run(authorized)?;
+        if response.status_code != 401 {
+            return Ok(response);
+        }
+        println!("  [auth] token rejected, refreshing");
+        let fresh = "fresh-token".to_string();
+        *token.lock().unwrap() = fresh.clone();
+        next.run(request.header("Authorization", &format!("Bearer {}", fresh)))
+    };
+
+    let client = ApiClient::new("https://api.example.com")
+        .with_middleware(Logger)
+        .with_middleware(ResponseCache::new(Duration::from_secs(60)))
+        .with_middleware(refresh)
+        .with_middleware(RequestSigner::new("demo-key", "s3cret"));
+
+    println!("First GET (token refresh, then cached):");
+    match client.get("/users/1") {
+        Ok(response) => ResponseHandler::print_response(&response),
+        Err(e) => println!("Error: {}", e),
+    }
+
+    println!("\nSecond GET (served from cache):");
+    match client.get("/users/1") {
+        Ok(response) => {
+            ResponseHandler::print_response(&response);
+            if let Some(cache) = response.headers.get("x-cache") {
+                println!("  x-cache: {}", cache);
+            }
+        }
+        Err(e) => println!("Error: {}", e),
+    }
+}
+
+fn demo_retries() {
+    println!("\n=== Retry Demo ===\n");
+
+    // Stands in for an overloaded server: rate-limits the first attempt,
+    // is unavailable for the second, then recovers
+    let failures = AtomicU64::new(0);
+    let overloaded = move |request: HttpRequest, next: Next<'_>| {
+        let attempt = failures.fetch_add(1, Ordering::Relaxed);
+        let (status_code, status_text, retry_after) = match attempt {
+            0 => (429, "Too Many Requests", Some("1")),
+            1 => (503, "Service Unavailable", None),
+            _ => return next.run(request),
+        };
+        let mut headers = HashMap::new();
+        if let Some(seconds) = retry_after {
+            headers.insert("retry-after".to_string(), seconds.to_string());
+        }
+        Ok(HttpResponse {
+            status_code,
+            status_text: status_text.to_string(),
+            headers,
+            body: String::new(),
+            elapsed: Duration::ZERO,
+        })
+    };
+
+    let client = ApiClient::new("https://api.example.com")
+        .with_middleware(Logger)
+        .with_middleware(overloaded)
+        .with_retry(
+            RetryPolicy::default()
+                .max_attempts(4)
+                .base_delay(Duration::from_millis(100)),
+        );
+
+    println!("GET against a struggling server:");
+    match client.get("/users/1") {
+        Ok(response) => ResponseHandler::print_response(&response),
+        Err(e) => println!("Error: {}", e),
+    }
+
+    println!("\nGET to a host that is down:");
+    let down = ApiClient::new("http://127.0.0.1:9")
+        .with_transport(StdHttpTransport::new())
+        .with_retry(RetryPolicy::default().jitter(false));
+    if let Err(e) = down.get("/users") {
+        println!("Error: {}", e);
+    }
+
+    let stats = client.retry_stats();
+    println!(
+        "\nRetry stats: {} request(s), {} retries, {} exhausted",
+        stats.requests, stats.retries, stats.exhausted
+    );
+    let stats = down.retry_stats();
+    println!(
+        "Down host:   {} request(s), {} retries, {} exhausted",
+        stats.requests, stats.retries, stats.exhausted
+    );
+}
+
+fn demo_pagination() {
+    println!("\n=== Pagination Demo ===\n");
+
+    let client = ApiClient::new("https://api.example.com");
+
+    // Any extractor works; this one reads a flat array of numbers by hand
+    let numbers = |response: &HttpResponse| {
+        response
+            .body
+            .trim()
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .split(',')
+            .filter(|item| !item.trim().is_empty())
+            .map(|item| {
+                item.trim()
+                    .parse::<u32>()
+                    .map_err(|e| ApiError::ParseError(format!("{}: {}", item.trim(), e)))
+            })
+            .collect::<Result<Vec<u32>, ApiError>>()
+    };
+
+    println!("Following Link headers:");
+    for page in client.paginate_with("/items", numbers) {
+        match page {
+            Ok(items) => println!("  page: {:?}", items),
+            Err(e) => println!("Error: {}", e),
+        }
+    }
+
+    println!("\nOffset/limit, 4 at a time:");
+    let all: Result<Vec<Vec<u32>>, ApiError> = client
+        .paginate_with("/items", numbers)
+        .offset_limit(4)
+        .collect();
+    match all {
+        Ok(pages) => println!("  {} pages, items {:?}", pages.len(), pages.concat()),
+        Err(e) => println!("Error: {}", e),
+    }
+
+    #[cfg(feature = "serde")]
+    {
+        println!("\nDeserialized with serde, first page only:");
+        let first = client.paginate::<u64>("/items").next();
+        if let Some(Ok(items)) = first {
+            println!("  page: {:?}", items);
+        }
+    }
+}
+
+fn demo_oauth2() {
+    println!("\n=== OAuth2 Demo ===\n");
+
+    #[cfg(feature = "serde")]
+    {
+        let config = oauth2::OAuth2Config::new("/oauth/token", "demo-client", "s3cret value")
+            .scope("users:read");
+        let client = ApiClient::new("https://api.example.com").with_oauth2(config);
+
+        println!("First GET (fetches a token, which turns out to be revoked):");
+        match client.get("/users/1") {
+            Ok(response) => ResponseHandler::print_response(&response),
+            Err(e) => println!("Error: {}", e),
+        }
+
+        println!("\nSecond GET (cached token):");
+        match client.get("/users/1") {
+            Ok(response) => ResponseHandler::print_response(&response),
+            Err(e) => println!("Error: {}", e),
+        }
+    }
+
+    #[cfg(not(feature = "serde"))]
+    println!("OAuth2 needs the serde feature (see the top of this file).");
+}
+
+fn demo_circuit_breaker() {
+    println!("\n=== Circuit Breaker Demo ===\n");
+
+    // A dependency that is down for its first three requests, then recovers
+    let outage = AtomicU64::new(3);
+    let flaky = move |request: HttpRequest, next: Next<'_>| {
+        let down = outage.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1));
+        if down.is_ok() {
+            return Err(ApiError::NetworkError("connection reset".to_string()));
+        }
+        next.run(request)
+    };
+
+    let cooldown = Duration::from_millis(300);
+    let client = ApiClient::new("https://api.example.com")
+        .with_middleware(flaky)
+        .with_circuit_breaker(CircuitBreaker::new(3, cooldown));
+
+    for i in 1..=5 {
+        match client.get("/users/1") {
+            Ok(response) => println!("  #{}: {} {}", i, response.status_code, response.status_text),
+            Err(e) => println!("  #{}: {}", i, e),
+        }
+    }
+    println!("  State: {:?}", client.circuit_state("/users/1"));
+
+    // Other hosts are unaffected
+    match client.get("https://status.example.com/health") {
+        Ok(response) => println!("  Other host: {} {}", response.status_code, response.status_text),
+        Err(e) => println!("  Other host: {}", e),
+    }
+
+    println!("\nAfter the cooldown:");
+    std::thread::sleep(cooldown);
+    match client.get("/users/1") {
+        Ok(response) => println!("  #6: {} {}", response.status_code, response.status_text),
+        Err(e) => println!("  #6: {}", e),
+    }
+    println!("  State: {:?}", client.circuit_state("/users/1"));
+}
+
+fn demo_live_server() {
+    println!("\n=== Live HTTP Demo ===\n");
+
+    let addr = match spawn_echo_server() {
+        Ok(addr) => addr,
+        Err(e) => {
+            println!("Could not start the local server: {}", e);
+            return;
+        }
+    };
+    println!("Echo server listening on {}", addr);
+
+    // Same client API as the mock demos, against a real socket
+    let client = ApiClient::new(&format!("http://{}", addr))
+        .with_transport(
+            StdHttpTransport::new()
+                .max_idle_per_host(2)
+                .idle_timeout(Duration::from_secs(10)),
+        )
+        .with_timeout(Duration::from_secs(5));
+
+    println!("\nGET over TCP:");
+    match client.get("/users/1") {
+        Ok(response) => ResponseHandler::print_response(&response),
+        Err(e) => println!("Error: {}", e),
+    }
+
+    println!("\nPOST over TCP:");
+    match client.post("/users", r#"{"name": "Erin"}"#) {
+        Ok(response) => ResponseHandler::print_response(&response),
+        Err(e) => println!("Error: {}", e),
+    }
+
+    println!("\nFive more GETs on the same connection:");
+    for id in 2..7 {
+        if let Err(e) = client.get(&format!("/users/{}", id)) {
+            println!("Error: {}", e);
+        }
+    }
+    if let Some(stats) = client.pool_stats() {
+        println!(
+            "  Pool: {} opened, {} reused, {} expired, {} discarded, {} idle",
+            stats.opened, stats.reused, stats.expired, stats.discarded, stats.idle
+        );
+    }
+
+    #[cfg(not(feature = "reqwest"))]
+    {
+        println!("\nHTTPS without the reqwest feature:");
+        match client.get("https://api.example.com/users") {
+            Ok(response) => ResponseHandler::print_response(&response),
+            Err(e) => println!("Error: {}", e),
+        }
+    }
+
+    #[cfg(feature = "reqwest")]
+    {
+        let client = ApiClient::new(&format!("http://{}", addr))
+            .with_transport(ReqwestTransport::new());
+        println!("\nGET through reqwest:");
+        match client.get("/users/1") {
+            Ok(response) => ResponseHandler::print_response(&response),
+            Err(e) => println!("Error: {}", e),
+        }
+    }
+}
+
+/// A tiny keep-alive HTTP server on background threads that answers every
+/// request with a JSON description of it
+fn spawn_echo_server() -> std::io::Result<std::net::SocketAddr> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let addr = listener.local_addr()?;
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            std::thread::spawn(move || {
+                let _ = echo(stream);
+            });
+        }
+    });
+    Ok(addr)
+}
+
+/// Answers requests on one connection until the client hangs up
+fn echo(mut stream: TcpStream) -> std::io::Result<()> {
+    let mut raw = Vec::new();
+    let mut buf = [0u8; 1024];
+    loop {
+        // Read the head, then as much body as Content-Length promises
+        let body_start = loop {
+            if let Some(i) = raw.windows(4).position(|w| w == b"\r\n\r\n") {
+                break i + 4;
+            }
+            let n = stream.read(&mut buf)?;
+            if n == 0 {
+                return Ok(());
+            }
+            raw.extend_from_slice(&buf[..n]);
+        };
+        let head = String::from_utf8_lossy(&raw[..body_start]).into_owned();
+        let length = head
+            .lines()
+            .find_map(|line| {
+                let (key, value) = line.split_once(':')?;
+                key.eq_ignore_ascii_case("content-length").then(|| value.trim().parse().ok())?
+            })
+            .unwrap_or(0);
+        while raw.len() < body_start + length {
+            let n = stream.read(&mut buf)?;
+            if n == 0 {
+                return Ok(());
+            }
+            raw.extend_from_slice(&buf[..n]);
+        }
+        let request: Vec<u8> = raw.drain(..body_start + length).collect();
+
+        let request_line = head.lines().next().unwrap_or("");
+        let mut parts = request_line.split(' ');
+        let method = parts.next().unwrap_or("");
+        let path = parts.next().unwrap_or("");
+        let body = String::from_utf8_lossy(&request[body_start..]);
+        let json = format!(
+            r#"{{"method": "{}", "path": "{}", "body": {}}}"#,
+            method,
+            path,
+            if body.is_empty() { "null".to_string() } else { body.into_owned() }
+        );
+
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            json.len(),
+            json
+        );
+        stream.write_all(response.as_bytes())?;
+    }
+}
+
 fn main() {
     demo_basic_requests();
     demo_authentication();
@@ -543,8 +2315,15 @@ fn main() {
     demo_error_handling();
     demo_response_parsing();
     demo_request_builder();
+    demo_middleware();
+    demo_retries();
+    demo_pagination();
+    demo_oauth2();
+    demo_circuit_breaker();
+    demo_live_server();
 
     println!("\n=== Demo Complete ===");
-    println!("\nNote: This is a mock implementation for demonstration.");
-    println!("For production use, integrate with reqwest crate for real HTTP requests.");
+    println!("\nNote: Most demos run on the mock transport. StdHttpTransport speaks");
+    println!("plain HTTP with pooled keep-alive connections; build with the reqwest");
+    println!("feature for ReqwestTransport and HTTPS.");
 }
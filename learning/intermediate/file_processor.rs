@@ -3,14 +3,42 @@
 // COMPILE & RUN:
 //   rustc file_processor.rs && ./file_processor
 //
-// This program demonstrates CSV file processing, statistical analysis,
-// and report generation
+// The standalone build runs a demo over sample data. The command line
+// (`cli`), parallel multi-file processing (`parallel`), regex validation
+// rules (`regex`), and XLSX export (`xlsx`) need a Cargo bin:
+//   [dependencies]
+//   clap = { version = "4", features = ["derive"], optional = true }
+//   glob = { version = "0.3", optional = true }
+//   rayon = { version = "1", optional = true }
+//   regex = { version = "1", optional = true }
+//   rust_xlsxwriter = { version = "0.80", optional = true }
+//
+//   [features]
+//   cli = ["dep:clap", "dep:glob"]
+//   parallel = ["dep:rayon"]
+//   regex = ["dep:regex"]
+//   xlsx = ["dep:rust_xlsxwriter"]
+//
+//   Then run, e.g.:
+//     process stats 'data/*.csv' --column Salary
+//     process report in.csv --format html -o out.html
+//     process export in.csv --format markdown --delimiter ';'
+//
+// This program demonstrates CSV file processing with column type inference,
+// statistical analysis, and report generation
+
+// The pipeline and sample-data helpers are only exercised by the demo
+#![cfg_attr(feature = "cli", allow(dead_code))]
 
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::error::Error;
 use std::fmt;
 use std::fs::File;
 use std::io::{BufRead, BufReader, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 // ============================================================================
 // ERROR HANDLING
@@ -36,74 +64,596 @@ impl fmt::Display for ProcessorError {
 impl Error for ProcessorError {}
 
 // ============================================================================
-// CSV STRUCTURES
+// COLUMN TYPES
 // ============================================================================
 
-/// Represents a CSV row
-#[derive(Debug, Clone)]
-struct CsvRow {
-    fields: Vec<String>,
+/// Type inferred for a column: the narrowest one every non-empty value fits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColumnType {
+    Integer,
+    Float,
+    Date,
+    Boolean,
+    String,
+}
+
+impl ColumnType {
+    /// The narrowest type a single value parses as
+    fn of(value: &str) -> ColumnType {
+        if parse_integer(value).is_some() {
+            ColumnType::Integer
+        } else if parse_float(value).is_some() {
+            ColumnType::Float
+        } else if Date::parse(value).is_some() {
+            ColumnType::Date
+        } else if parse_bool(value).is_some() {
+            ColumnType::Boolean
+        } else {
+            ColumnType::String
+        }
+    }
+
+    /// A type that holds values of both; integers widen to floats,
+    /// any other mix falls back to strings
+    fn unify(self, other: ColumnType) -> ColumnType {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (ColumnType::Integer, ColumnType::Float) | (ColumnType::Float, ColumnType::Integer) => {
+                ColumnType::Float
+            }
+            _ => ColumnType::String,
+        }
+    }
+
+    /// Infer a column's type from its values, ignoring empty cells
+    fn infer<'a>(values: impl IntoIterator<Item = &'a str>) -> ColumnType {
+        values
+            .into_iter()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(ColumnType::of)
+            .reduce(ColumnType::unify)
+            .unwrap_or(ColumnType::String)
+    }
+}
+
+impl fmt::Display for ColumnType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ColumnType::Integer => "integer",
+            ColumnType::Float => "float",
+            ColumnType::Date => "date",
+            ColumnType::Boolean => "boolean",
+            ColumnType::String => "string",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Leading zeros mark codes such as "007" or ZIP codes, which stay text
+fn has_leading_zero(value: &str) -> bool {
+    let digits = value.trim_start_matches(['+', '-']);
+    digits.len() > 1 && digits.starts_with('0') && digits.as_bytes()[1].is_ascii_digit()
+}
+
+fn parse_integer(value: &str) -> Option<i64> {
+    if has_leading_zero(value) {
+        return None;
+    }
+    value.parse().ok()
+}
+
+fn parse_float(value: &str) -> Option<f64> {
+    // f64's parser also takes "inf" and "NaN", which are rarely numbers in a CSV
+    let numeric = value
+        .chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, '+' | '-' | '.' | 'e' | 'E'));
+    if !numeric || has_leading_zero(value) {
+        return None;
+    }
+    value.parse().ok()
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "yes" => Some(true),
+        "false" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+/// Calendar date read from `YYYY-MM-DD`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Date {
+    year: i32,
+    month: u32,
+    day: u32,
 }
 
-impl CsvRow {
-    fn new(fields: Vec<String>) -> Self {
-        CsvRow { fields }
+impl Date {
+    fn parse(value: &str) -> Option<Date> {
+        let mut parts = value.split('-');
+        let (year, month, day) = (parts.next()?, parts.next()?, parts.next()?);
+        if parts.next().is_some() || year.len() != 4 || month.len() != 2 || day.len() != 2 {
+            return None;
+        }
+        let date = Date {
+            year: year.parse().ok()?,
+            month: month.parse().ok()?,
+            day: day.parse().ok()?,
+        };
+        let valid = (1..=12).contains(&date.month)
+            && date.day >= 1
+            && date.day <= days_in_month(date.year, date.month);
+        valid.then_some(date)
+    }
+
+    /// Days since 1970-01-01, so spans between dates are a subtraction
+    fn days_since_epoch(&self) -> i64 {
+        // Howard Hinnant's days_from_civil
+        let year = i64::from(self.year) - i64::from(self.month <= 2);
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let month = i64::from(self.month);
+        let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5
+            + i64::from(self.day)
+            - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146_097 + day_of_era - 719_468
     }
+}
 
-    fn get(&self, index: usize) -> Option<&String> {
-        self.fields.get(index)
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
     }
+}
 
-    fn len(&self) -> usize {
-        self.fields.len()
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
     }
 }
 
-/// CSV Data structure
+/// A column's values stored as their inferred type; `None` is an empty cell
+#[derive(Debug, Clone, PartialEq)]
+enum TypedColumn {
+    Integer(Vec<Option<i64>>),
+    Float(Vec<Option<f64>>),
+    Date(Vec<Option<Date>>),
+    Boolean(Vec<Option<bool>>),
+    String(Vec<Option<String>>),
+}
+
+impl TypedColumn {
+    /// Convert raw values into a column of `column_type`, which must fit them all
+    fn parse(column_type: ColumnType, values: &[&str]) -> Self {
+        fn cells<T>(values: &[&str], parse: impl Fn(&str) -> Option<T>) -> Vec<Option<T>> {
+            values
+                .iter()
+                .map(|v| Some(v.trim()).filter(|v| !v.is_empty()).and_then(&parse))
+                .collect()
+        }
+
+        match column_type {
+            ColumnType::Integer => TypedColumn::Integer(cells(values, parse_integer)),
+            ColumnType::Float => TypedColumn::Float(cells(values, parse_float)),
+            ColumnType::Date => TypedColumn::Date(cells(values, Date::parse)),
+            ColumnType::Boolean => TypedColumn::Boolean(cells(values, parse_bool)),
+            ColumnType::String => TypedColumn::String(cells(values, |v| Some(v.to_string()))),
+        }
+    }
+
+    fn column_type(&self) -> ColumnType {
+        match self {
+            TypedColumn::Integer(_) => ColumnType::Integer,
+            TypedColumn::Float(_) => ColumnType::Float,
+            TypedColumn::Date(_) => ColumnType::Date,
+            TypedColumn::Boolean(_) => ColumnType::Boolean,
+            TypedColumn::String(_) => ColumnType::String,
+        }
+    }
+
+    /// Cell at `index` as text, empty when missing
+    fn display_at(&self, index: usize) -> String {
+        fn show<T: ToString>(cells: &[Option<T>], index: usize) -> String {
+            cells
+                .get(index)
+                .and_then(Option::as_ref)
+                .map(T::to_string)
+                .unwrap_or_default()
+        }
+
+        match self {
+            TypedColumn::Integer(cells) => show(cells, index),
+            TypedColumn::Float(cells) => show(cells, index),
+            TypedColumn::Date(cells) => show(cells, index),
+            TypedColumn::Boolean(cells) => show(cells, index),
+            TypedColumn::String(cells) => show(cells, index),
+        }
+    }
+
+    /// Integer and float values as `f64`, skipping empty cells; `None` for
+    /// non-numeric columns
+    fn numeric_values(&self) -> Option<Vec<f64>> {
+        match self {
+            TypedColumn::Integer(cells) => {
+                Some(cells.iter().flatten().map(|&v| v as f64).collect())
+            }
+            TypedColumn::Float(cells) => Some(cells.iter().flatten().copied().collect()),
+            _ => None,
+        }
+    }
+
+    /// Cell at `index` as an owned value
+    fn value_at(&self, index: usize) -> Value {
+        match self {
+            TypedColumn::Integer(cells) => cells.get(index).copied().flatten().map(Value::Integer),
+            TypedColumn::Float(cells) => cells.get(index).copied().flatten().map(Value::Float),
+            TypedColumn::Date(cells) => cells.get(index).copied().flatten().map(Value::Date),
+            TypedColumn::Boolean(cells) => cells.get(index).copied().flatten().map(Value::Boolean),
+            TypedColumn::String(cells) => cells.get(index).cloned().flatten().map(Value::String),
+        }
+        .unwrap_or(Value::Null)
+    }
+
+    /// Column made of the cells at `indices`, in that order
+    fn take(&self, indices: &[usize]) -> TypedColumn {
+        fn pick<T: Clone>(cells: &[Option<T>], indices: &[usize]) -> Vec<Option<T>> {
+            indices.iter().map(|&i| cells.get(i).cloned().flatten()).collect()
+        }
+
+        match self {
+            TypedColumn::Integer(cells) => TypedColumn::Integer(pick(cells, indices)),
+            TypedColumn::Float(cells) => TypedColumn::Float(pick(cells, indices)),
+            TypedColumn::Date(cells) => TypedColumn::Date(pick(cells, indices)),
+            TypedColumn::Boolean(cells) => TypedColumn::Boolean(pick(cells, indices)),
+            TypedColumn::String(cells) => TypedColumn::String(pick(cells, indices)),
+        }
+    }
+
+    /// Build a column from values, typed as the narrowest type holding them all
+    fn from_values(values: Vec<Value>) -> TypedColumn {
+        let column_type = values
+            .iter()
+            .filter_map(Value::column_type)
+            .reduce(ColumnType::unify)
+            .unwrap_or(ColumnType::String);
+
+        match column_type {
+            ColumnType::Integer => TypedColumn::Integer(values.iter().map(Value::as_i64).collect()),
+            ColumnType::Float => TypedColumn::Float(values.iter().map(Value::as_f64).collect()),
+            ColumnType::Date => TypedColumn::Date(values.iter().map(Value::as_date).collect()),
+            ColumnType::Boolean => {
+                TypedColumn::Boolean(values.iter().map(Value::as_bool).collect())
+            }
+            ColumnType::String => TypedColumn::String(
+                values
+                    .into_iter()
+                    .map(|value| match value {
+                        Value::Null => None,
+                        Value::String(s) => Some(s),
+                        other => Some(other.to_string()),
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+// ============================================================================
+// CSV STRUCTURES
+// ============================================================================
+
+/// CSV data stored column by column, each column in its inferred type
 #[derive(Debug)]
 struct CsvData {
     headers: Vec<String>,
-    rows: Vec<CsvRow>,
+    columns: Vec<TypedColumn>,
+    row_count: usize,
 }
 
 impl CsvData {
     fn new() -> Self {
         CsvData {
             headers: Vec::new(),
-            rows: Vec::new(),
+            columns: Vec::new(),
+            row_count: 0,
         }
     }
 
-    fn with_headers(headers: Vec<String>) -> Self {
+    /// Run schema inference over raw records and store each column typed.
+    /// Short records leave empty cells, and fields past the last header get
+    /// a generated `column_N` name.
+    fn from_records(mut headers: Vec<String>, records: &[Vec<String>]) -> Self {
+        let width = records.iter().map(Vec::len).max().unwrap_or(0).max(headers.len());
+        for i in headers.len()..width {
+            headers.push(format!("column_{}", i + 1));
+        }
+
+        let columns = (0..width)
+            .map(|i| {
+                let values: Vec<&str> = records
+                    .iter()
+                    .map(|record| record.get(i).map_or("", String::as_str))
+                    .collect();
+                TypedColumn::parse(ColumnType::infer(values.iter().copied()), &values)
+            })
+            .collect();
+
         CsvData {
             headers,
-            rows: Vec::new(),
+            columns,
+            row_count: records.len(),
         }
     }
 
-    fn add_row(&mut self, row: CsvRow) {
-        self.rows.push(row);
-    }
-
     fn row_count(&self) -> usize {
-        self.rows.len()
+        self.row_count
     }
 
     fn column_count(&self) -> usize {
         self.headers.len()
     }
 
+    /// Header names paired with their inferred types
+    fn schema(&self) -> Vec<(&str, ColumnType)> {
+        self.headers
+            .iter()
+            .zip(&self.columns)
+            .map(|(header, column)| (header.as_str(), column.column_type()))
+            .collect()
+    }
+
     /// Get column by name
-    fn get_column(&self, column_name: &str) -> Option<Vec<&String>> {
+    fn get_column(&self, column_name: &str) -> Option<&TypedColumn> {
         self.headers
             .iter()
             .position(|h| h == column_name)
-            .map(|idx| self.rows.iter().filter_map(|row| row.get(idx)).collect())
+            .and_then(|idx| self.columns.get(idx))
     }
 
     /// Get column by index
-    fn get_column_by_index(&self, index: usize) -> Vec<&String> {
-        self.rows.iter().filter_map(|row| row.get(index)).collect()
+    fn get_column_by_index(&self, index: usize) -> Option<&TypedColumn> {
+        self.columns.get(index)
+    }
+
+    /// Row `index` with every cell rendered as text
+    fn row(&self, index: usize) -> Vec<String> {
+        self.columns.iter().map(|column| column.display_at(index)).collect()
+    }
+}
+
+// ============================================================================
+// PIPELINE
+// ============================================================================
+
+/// Single cell value, as seen by pipeline predicates and transforms
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Null,
+    Integer(i64),
+    Float(f64),
+    Date(Date),
+    Boolean(bool),
+    String(String),
+}
+
+impl Value {
+    fn column_type(&self) -> Option<ColumnType> {
+        match self {
+            Value::Null => None,
+            Value::Integer(_) => Some(ColumnType::Integer),
+            Value::Float(_) => Some(ColumnType::Float),
+            Value::Date(_) => Some(ColumnType::Date),
+            Value::Boolean(_) => Some(ColumnType::Boolean),
+            Value::String(_) => Some(ColumnType::String),
+        }
+    }
+
+    fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Integer(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Integers convert too, so predicates need not care which numeric type
+    /// a column was inferred as
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Integer(v) => Some(*v as f64),
+            Value::Float(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    fn as_date(&self) -> Option<Date> {
+        match self {
+            Value::Date(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Boolean(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Order two non-empty values; numbers compare across integer and
+    /// float, and mismatched types fall back to comparing their text
+    fn compare(&self, other: &Value) -> Ordering {
+        match (self, other) {
+            (Value::Date(a), Value::Date(b)) => a.cmp(b),
+            (Value::Boolean(a), Value::Boolean(b)) => a.cmp(b),
+            (Value::String(a), Value::String(b)) => a.cmp(b),
+            (a, b) => match (a.as_f64(), b.as_f64()) {
+                (Some(a), Some(b)) => a.total_cmp(&b),
+                _ => a.to_string().cmp(&b.to_string()),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Null => Ok(()),
+            Value::Integer(v) => write!(f, "{}", v),
+            Value::Float(v) => write!(f, "{}", v),
+            Value::Date(v) => write!(f, "{}", v),
+            Value::Boolean(v) => write!(f, "{}", v),
+            Value::String(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+/// Row handed to filter predicates
+struct Row<'a> {
+    columns: &'a [(String, Cow<'a, TypedColumn>)],
+    index: usize,
+}
+
+impl Row<'_> {
+    /// Value of the named column, `Value::Null` if there is no such column
+    fn get(&self, column_name: &str) -> Value {
+        self.columns
+            .iter()
+            .find(|(header, _)| header == column_name)
+            .map_or(Value::Null, |(_, column)| column.value_at(self.index))
+    }
+}
+
+enum Step<'a> {
+    Filter(Box<dyn Fn(&Row) -> bool + 'a>),
+    SortBy { column: String, descending: bool },
+    Select(Vec<String>),
+    MapColumn { column: String, transform: Box<dyn Fn(Value) -> Value + 'a> },
+}
+
+/// Chain of operations over a `CsvData`. The chainable methods only record
+/// steps; nothing is filtered, sorted, or copied until `collect` runs them.
+struct Pipeline<'a> {
+    source: &'a CsvData,
+    steps: Vec<Step<'a>>,
+}
+
+impl<'a> Pipeline<'a> {
+    /// Keep rows for which `predicate` returns true
+    fn filter(mut self, predicate: impl Fn(&Row) -> bool + 'a) -> Self {
+        self.steps.push(Step::Filter(Box::new(predicate)));
+        self
+    }
+
+    /// Stable sort on a column; empty cells always sort last
+    fn sort_by(mut self, column: &str, descending: bool) -> Self {
+        self.steps.push(Step::SortBy {
+            column: column.to_string(),
+            descending,
+        });
+        self
+    }
+
+    /// Keep only the named columns, in the given order
+    fn select(mut self, columns: &[&str]) -> Self {
+        self.steps
+            .push(Step::Select(columns.iter().map(|c| c.to_string()).collect()));
+        self
+    }
+
+    /// Replace a column's values with `transform` applied to each; the
+    /// column's type is re-inferred from the results
+    fn map_column(mut self, column: &str, transform: impl Fn(Value) -> Value + 'a) -> Self {
+        self.steps.push(Step::MapColumn {
+            column: column.to_string(),
+            transform: Box::new(transform),
+        });
+        self
+    }
+
+    /// Run the recorded steps and build the resulting data
+    fn collect(self) -> Result<CsvData, ProcessorError> {
+        fn position(
+            columns: &[(String, Cow<TypedColumn>)],
+            name: &str,
+        ) -> Result<usize, ProcessorError> {
+            columns
+                .iter()
+                .position(|(header, _)| header == name)
+                .ok_or_else(|| ProcessorError::ValidationError(format!("Unknown column: {}", name)))
+        }
+
+        let source = self.source;
+        let mut rows: Vec<usize> = (0..source.row_count()).collect();
+        let mut columns: Vec<(String, Cow<TypedColumn>)> = source
+            .headers
+            .iter()
+            .cloned()
+            .zip(source.columns.iter().map(Cow::Borrowed))
+            .collect();
+
+        for step in self.steps {
+            match step {
+                Step::Filter(predicate) => {
+                    rows.retain(|&index| predicate(&Row { columns: &columns, index }));
+                }
+                Step::SortBy { column, descending } => {
+                    let column = &columns[position(&columns, &column)?].1;
+                    rows.sort_by(|&a, &b| {
+                        match (column.value_at(a), column.value_at(b)) {
+                            (Value::Null, Value::Null) => Ordering::Equal,
+                            (Value::Null, _) => Ordering::Greater,
+                            (_, Value::Null) => Ordering::Less,
+                            (a, b) if descending => b.compare(&a),
+                            (a, b) => a.compare(&b),
+                        }
+                    });
+                }
+                Step::Select(names) => {
+                    columns = names
+                        .iter()
+                        .map(|name| Ok(columns[position(&columns, name)?].clone()))
+                        .collect::<Result<_, ProcessorError>>()?;
+                }
+                Step::MapColumn { column, transform } => {
+                    let idx = position(&columns, &column)?;
+                    let mut values = vec![Value::Null; source.row_count()];
+                    for &index in &rows {
+                        values[index] = transform(columns[idx].1.value_at(index));
+                    }
+                    columns[idx].1 = Cow::Owned(TypedColumn::from_values(values));
+                }
+            }
+        }
+
+        Ok(CsvData {
+            headers: columns.iter().map(|(header, _)| header.clone()).collect(),
+            columns: columns.iter().map(|(_, column)| column.take(&rows)).collect(),
+            row_count: rows.len(),
+        })
+    }
+}
+
+impl CsvData {
+    /// Start a lazy pipeline of filters, sorts, selections, and column
+    /// transforms over this data
+    fn pipeline(&self) -> Pipeline<'_> {
+        Pipeline {
+            source: self,
+            steps: Vec::new(),
+        }
     }
 }
 
@@ -142,23 +692,21 @@ impl CsvParser {
             return Ok(CsvData::new());
         }
 
-        let mut csv_data = if self.has_headers {
-            let headers = self.parse_line(lines[0]);
-            CsvData::with_headers(headers)
+        let headers = if self.has_headers {
+            self.parse_line(lines[0])
         } else {
-            CsvData::new()
+            Vec::new()
         };
 
         let start_idx = if self.has_headers { 1 } else { 0 };
-        
-        for line in lines.iter().skip(start_idx) {
-            if !line.trim().is_empty() {
-                let fields = self.parse_line(line);
-                csv_data.add_row(CsvRow::new(fields));
-            }
-        }
+        let records: Vec<Vec<String>> = lines
+            .iter()
+            .skip(start_idx)
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| self.parse_line(line))
+            .collect();
 
-        Ok(csv_data)
+        Ok(CsvData::from_records(headers, &records))
     }
 
     /// Parse CSV from file
@@ -169,27 +717,26 @@ impl CsvParser {
         let reader = BufReader::new(file);
         let mut lines = reader.lines();
 
-        let mut csv_data = if self.has_headers {
+        let headers = if self.has_headers {
             if let Some(Ok(first_line)) = lines.next() {
-                let headers = self.parse_line(&first_line);
-                CsvData::with_headers(headers)
+                self.parse_line(&first_line)
             } else {
                 return Ok(CsvData::new());
             }
         } else {
-            CsvData::new()
+            Vec::new()
         };
 
+        let mut records = Vec::new();
         for line_result in lines {
             if let Ok(line) = line_result {
                 if !line.trim().is_empty() {
-                    let fields = self.parse_line(&line);
-                    csv_data.add_row(CsvRow::new(fields));
+                    records.push(self.parse_line(&line));
                 }
             }
         }
 
-        Ok(csv_data)
+        Ok(CsvData::from_records(headers, &records))
     }
 
     fn parse_line(&self, line: &str) -> Vec<String> {
@@ -200,6 +747,11 @@ impl CsvParser {
 
         while let Some(ch) = chars.next() {
             match ch {
+                // A doubled quote inside a quoted field is a literal quote
+                '"' if in_quotes && chars.peek() == Some(&'"') => {
+                    chars.next();
+                    current_field.push('"');
+                }
                 '"' => {
                     in_quotes = !in_quotes;
                 }
@@ -231,6 +783,11 @@ struct Statistics {
     std_dev: f64,
     min: f64,
     max: f64,
+    /// `(percent, value)` pairs, e.g. `(95.0, ...)` for p95
+    percentiles: Vec<(f64, f64)>,
+    /// Most frequent value, smallest first on ties; `None` when nothing repeats
+    mode: Option<f64>,
+    skewness: f64,
 }
 
 impl fmt::Display for Statistics {
@@ -243,6 +800,14 @@ impl fmt::Display for Statistics {
         writeln!(f, "  Std Dev:  {:.2}", self.std_dev)?;
         writeln!(f, "  Min:      {:.2}", self.min)?;
         writeln!(f, "  Max:      {:.2}", self.max)?;
+        match self.mode {
+            Some(mode) => writeln!(f, "  Mode:     {:.2}", mode)?,
+            None => writeln!(f, "  Mode:     none")?,
+        }
+        writeln!(f, "  Skewness: {:.2}", self.skewness)?;
+        for (percent, value) in &self.percentiles {
+            writeln!(f, "  {:<9} {:.2}", format!("P{}:", percent), value)?;
+        }
         Ok(())
     }
 }
@@ -250,8 +815,18 @@ impl fmt::Display for Statistics {
 struct StatisticsCalculator;
 
 impl StatisticsCalculator {
+    const DEFAULT_PERCENTILES: [f64; 4] = [25.0, 50.0, 75.0, 95.0];
+
     /// Calculate statistics for a numeric column
     fn calculate(values: &[f64]) -> Result<Statistics, ProcessorError> {
+        Self::calculate_with_percentiles(values, &Self::DEFAULT_PERCENTILES)
+    }
+
+    /// Calculate statistics, reporting the given percentiles (0 to 100)
+    fn calculate_with_percentiles(
+        values: &[f64],
+        percents: &[f64],
+    ) -> Result<Statistics, ProcessorError> {
         if values.is_empty() {
             return Err(ProcessorError::ValidationError("No values to calculate".to_string()));
         }
@@ -276,6 +851,28 @@ impl StatisticsCalculator {
         let min = sorted[0];
         let max = sorted[count - 1];
 
+        if let Some(p) = percents.iter().find(|p| !(0.0..=100.0).contains(*p)) {
+            return Err(ProcessorError::ValidationError(format!("Percentile out of range: {}", p)));
+        }
+        let percentiles = percents.iter().map(|&p| (p, Self::percentile(&sorted, p))).collect();
+
+        // Sorted input puts equal values side by side, so runs give counts
+        let mut mode = None;
+        let mut best = 1;
+        for run in sorted.chunk_by(|a, b| a == b) {
+            if run.len() > best {
+                best = run.len();
+                mode = Some(run[0]);
+            }
+        }
+
+        // Population skewness; zero spread means a symmetric distribution
+        let skewness = if std_dev > 0.0 {
+            values.iter().map(|x| ((x - mean) / std_dev).powi(3)).sum::<f64>() / count as f64
+        } else {
+            0.0
+        };
+
         Ok(Statistics {
             count,
             sum,
@@ -284,15 +881,218 @@ impl StatisticsCalculator {
             std_dev,
             min,
             max,
+            percentiles,
+            mode,
+            skewness,
         })
     }
 
-    /// Parse string values to floats
-    fn parse_numeric_column(values: &[&String]) -> Vec<f64> {
-        values
+    /// Percentile of sorted values, interpolating linearly between ranks
+    fn percentile(sorted: &[f64], percent: f64) -> f64 {
+        let rank = percent / 100.0 * (sorted.len() - 1) as f64;
+        let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+        sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+    }
+
+    /// Split values into `bins` equal-width buckets between min and max
+    fn histogram(values: &[f64], bins: usize) -> Histogram {
+        if values.is_empty() {
+            return Histogram { bins: Vec::new() };
+        }
+        let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        // All-equal values get a single bucket rather than zero-width ones
+        let bins = if max > min { bins.max(1) } else { 1 };
+        let width = (max - min) / bins as f64;
+
+        let mut counts = vec![0; bins];
+        for &value in values {
+            let bin = if width > 0.0 { ((value - min) / width) as usize } else { 0 };
+            // The maximum lands exactly on the last edge
+            counts[bin.min(bins - 1)] += 1;
+        }
+
+        Histogram {
+            bins: counts
+                .into_iter()
+                .enumerate()
+                .map(|(i, count)| (min + width * i as f64, min + width * (i + 1) as f64, count))
+                .collect(),
+        }
+    }
+
+    /// Pearson correlation between every pair of numeric columns. Each pair
+    /// uses only the rows where both cells are present.
+    fn correlation_matrix(csv_data: &CsvData) -> CorrelationMatrix {
+        let numeric: Vec<(&String, &TypedColumn)> = csv_data
+            .headers
             .iter()
-            .filter_map(|v| v.parse::<f64>().ok())
-            .collect()
+            .zip(&csv_data.columns)
+            .filter(|(_, column)| column.numeric_values().is_some())
+            .collect();
+
+        let values = numeric
+            .iter()
+            .map(|(_, a)| {
+                numeric
+                    .iter()
+                    .map(|(_, b)| {
+                        let pairs: Vec<(f64, f64)> = (0..csv_data.row_count())
+                            .filter_map(|i| {
+                                Some((a.value_at(i).as_f64()?, b.value_at(i).as_f64()?))
+                            })
+                            .collect();
+                        Self::pearson(&pairs)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        CorrelationMatrix {
+            columns: numeric.into_iter().map(|(header, _)| header.clone()).collect(),
+            values,
+        }
+    }
+
+    /// `None` with fewer than two pairs or when either side is constant
+    fn pearson(pairs: &[(f64, f64)]) -> Option<f64> {
+        if pairs.len() < 2 {
+            return None;
+        }
+        let n = pairs.len() as f64;
+        let mean_x = pairs.iter().map(|p| p.0).sum::<f64>() / n;
+        let mean_y = pairs.iter().map(|p| p.1).sum::<f64>() / n;
+        let (mut covariance, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+        for &(x, y) in pairs {
+            covariance += (x - mean_x) * (y - mean_y);
+            var_x += (x - mean_x).powi(2);
+            var_y += (y - mean_y).powi(2);
+        }
+        (var_x > 0.0 && var_y > 0.0).then(|| covariance / (var_x * var_y).sqrt())
+    }
+}
+
+/// Equal-width buckets as `(from, to, count)`
+#[derive(Debug)]
+struct Histogram {
+    bins: Vec<(f64, f64, usize)>,
+}
+
+impl fmt::Display for Histogram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const BAR_WIDTH: usize = 30;
+        let largest = self.bins.iter().map(|bin| bin.2).max().unwrap_or(0).max(1);
+
+        writeln!(f, "Histogram:")?;
+        for &(from, to, count) in &self.bins {
+            let bar = "#".repeat(count * BAR_WIDTH / largest);
+            writeln!(
+                f,
+                "  {:>10.2} - {:>10.2} | {:<width$} {}",
+                from,
+                to,
+                bar,
+                count,
+                width = BAR_WIDTH
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Pairwise Pearson coefficients; `None` where a pair has no defined value
+#[derive(Debug)]
+struct CorrelationMatrix {
+    columns: Vec<String>,
+    values: Vec<Vec<Option<f64>>>,
+}
+
+impl fmt::Display for CorrelationMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let width = self.columns.iter().map(String::len).max().unwrap_or(0).max(6) + 2;
+
+        writeln!(f, "Correlation Matrix (Pearson):")?;
+        write!(f, "  {:width$}", "", width = width)?;
+        for column in &self.columns {
+            write!(f, "{:>width$}", column, width = width)?;
+        }
+        writeln!(f)?;
+        for (column, row) in self.columns.iter().zip(&self.values) {
+            write!(f, "  {:width$}", column, width = width)?;
+            for value in row {
+                match value {
+                    Some(r) => write!(f, "{:>width$.2}", r, width = width)?,
+                    None => write!(f, "{:>width$}", "n/a", width = width)?,
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Range and per-month counts of a date column
+#[derive(Debug)]
+struct DateSummary {
+    count: usize,
+    earliest: Date,
+    latest: Date,
+    per_month: BTreeMap<(i32, u32), usize>,
+}
+
+impl DateSummary {
+    fn calculate(dates: &[Date]) -> Result<DateSummary, ProcessorError> {
+        let (Some(&earliest), Some(&latest)) = (dates.iter().min(), dates.iter().max()) else {
+            return Err(ProcessorError::ValidationError("No dates to summarize".to_string()));
+        };
+
+        let mut per_month = BTreeMap::new();
+        for date in dates {
+            *per_month.entry((date.year, date.month)).or_insert(0) += 1;
+        }
+
+        Ok(DateSummary {
+            count: dates.len(),
+            earliest,
+            latest,
+            per_month,
+        })
+    }
+
+    fn span_days(&self) -> i64 {
+        self.latest.days_since_epoch() - self.earliest.days_since_epoch()
+    }
+
+    /// Per-month counts rolled up to years
+    fn per_year(&self) -> BTreeMap<i32, usize> {
+        let mut per_year = BTreeMap::new();
+        for (&(year, _), &count) in &self.per_month {
+            *per_year.entry(year).or_insert(0) += count;
+        }
+        per_year
+    }
+}
+
+impl fmt::Display for DateSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Dates:")?;
+        writeln!(f, "  Count:    {}", self.count)?;
+        writeln!(f, "  Earliest: {}", self.earliest)?;
+        writeln!(f, "  Latest:   {}", self.latest)?;
+        writeln!(f, "  Span:     {} days", self.span_days())?;
+        writeln!(f, "  Per year:")?;
+        for (year, count) in self.per_year() {
+            writeln!(f, "    {}: {}", year, count)?;
+        }
+        // Ties go to the earlier month
+        let busiest = self
+            .per_month
+            .iter()
+            .max_by_key(|&(month, count)| (count, std::cmp::Reverse(month)));
+        if let Some(((year, month), count)) = busiest {
+            writeln!(f, "  Busiest month: {}-{:02} ({})", year, month, count)?;
+        }
+        Ok(())
     }
 }
 
@@ -312,8 +1112,8 @@ impl ReportGenerator {
         report.push_str(&format!("Total Columns: {}\n\n", csv_data.column_count()));
 
         report.push_str("Columns:\n");
-        for (i, header) in csv_data.headers.iter().enumerate() {
-            report.push_str(&format!("  {}. {}\n", i + 1, header));
+        for (i, (header, column_type)) in csv_data.schema().into_iter().enumerate() {
+            report.push_str(&format!("  {}. {} ({})\n", i + 1, header, column_type));
         }
 
         report.push_str("\n=== DATA PREVIEW (First 5 rows) ===\n\n");
@@ -325,8 +1125,8 @@ impl ReportGenerator {
         report.push_str("\n");
 
         // Print first 5 rows
-        for (i, row) in csv_data.rows.iter().take(5).enumerate() {
-            report.push_str(&row.fields.join(" | "));
+        for i in 0..csv_data.row_count().min(5) {
+            report.push_str(&csv_data.row(i).join(" | "));
             report.push_str("\n");
         }
 
@@ -340,20 +1140,47 @@ impl ReportGenerator {
         report.push_str("\n=== STATISTICS REPORT ===\n\n");
 
         for (i, header) in csv_data.headers.iter().enumerate() {
-            let column = csv_data.get_column_by_index(i);
-            let numeric_values = StatisticsCalculator::parse_numeric_column(&column);
-            
-            if !numeric_values.is_empty() {
+            if let Some(summary) = csv_data.get_column_by_index(i).and_then(Self::column_summary) {
                 report.push_str(&format!("Column: {}\n", header));
-                if let Ok(stats) = StatisticsCalculator::calculate(&numeric_values) {
-                    report.push_str(&format!("{}\n", stats));
-                }
+                report.push_str(&format!("{}\n", summary));
             }
         }
 
+        let correlations = StatisticsCalculator::correlation_matrix(csv_data);
+        if correlations.columns.len() > 1 {
+            report.push_str(&format!("{}\n", correlations));
+        }
+
         report
     }
 
+    /// Summary of one column suited to its type: statistics for numbers,
+    /// range and counts for dates, tallies for booleans. `None` for string
+    /// columns and columns with no values.
+    fn column_summary(column: &TypedColumn) -> Option<String> {
+        match column {
+            TypedColumn::Integer(_) | TypedColumn::Float(_) => {
+                let numeric_values = column.numeric_values().unwrap_or_default();
+                // Square-root rule, capped so small screens stay readable
+                let bins = ((numeric_values.len() as f64).sqrt().ceil() as usize).min(10);
+                let histogram = StatisticsCalculator::histogram(&numeric_values, bins);
+                StatisticsCalculator::calculate(&numeric_values)
+                    .ok()
+                    .map(|stats| format!("{}{}", stats, histogram))
+            }
+            TypedColumn::Date(cells) => {
+                let dates: Vec<Date> = cells.iter().flatten().copied().collect();
+                DateSummary::calculate(&dates).ok().map(|summary| summary.to_string())
+            }
+            TypedColumn::Boolean(cells) => {
+                let yes = cells.iter().flatten().filter(|&&v| v).count();
+                let no = cells.iter().flatten().count() - yes;
+                Some(format!("  true: {}, false: {}\n", yes, no))
+            }
+            TypedColumn::String(_) => None,
+        }
+    }
+
     /// Generate HTML report
     fn generate_html_report(csv_data: &CsvData) -> String {
         let mut html = String::new();
@@ -384,9 +1211,9 @@ impl ReportGenerator {
         html.push_str("    </tr>\n");
         
         // Rows (first 10)
-        for row in csv_data.rows.iter().take(10) {
+        for i in 0..csv_data.row_count().min(10) {
             html.push_str("    <tr>\n");
-            for field in &row.fields {
+            for field in csv_data.row(i) {
                 html.push_str(&format!("      <td>{}</td>\n", field));
             }
             html.push_str("    </tr>\n");
@@ -398,6 +1225,91 @@ impl ReportGenerator {
         html
     }
 
+    /// Cross-file summary: row counts, schema warnings measured against the
+    /// first file that parsed, and statistics over each numeric column's
+    /// values from every file
+    fn generate_merged_report(reports: &[FileReport]) -> String {
+        let mut report = String::new();
+        let parsed: Vec<(&Path, &CsvData)> = reports
+            .iter()
+            .filter_map(|r| r.outcome.as_ref().ok().map(|(data, _)| (r.path.as_path(), data)))
+            .collect();
+
+        report.push_str("\n=== MERGED REPORT ===\n\n");
+        report.push_str(&format!(
+            "Files: {} ({} failed)\n",
+            reports.len(),
+            reports.len() - parsed.len()
+        ));
+        let total_rows: usize = parsed.iter().map(|(_, data)| data.row_count()).sum();
+        report.push_str(&format!("Total Rows: {}\n\n", total_rows));
+
+        report.push_str("Per File:\n");
+        for file in reports {
+            match &file.outcome {
+                Ok((data, _)) => report.push_str(&format!(
+                    "  {}: {} rows, {} columns\n",
+                    file.path.display(),
+                    data.row_count(),
+                    data.column_count()
+                )),
+                Err(e) => report.push_str(&format!("  {}: failed: {}\n", file.path.display(), e)),
+            }
+        }
+
+        let Some(&(reference_path, reference)) = parsed.first() else {
+            return report;
+        };
+        let expected = reference.schema();
+        let mut warnings = Vec::new();
+        for &(path, data) in &parsed[1..] {
+            let schema = data.schema();
+            for &(header, expected_type) in &expected {
+                match schema.iter().find(|(h, _)| *h == header) {
+                    None => warnings.push(format!("{}: missing column {}", path.display(), header)),
+                    Some(&(_, column_type)) if column_type != expected_type => {
+                        warnings.push(format!(
+                            "{}: column {} is {}, {} has {}",
+                            path.display(),
+                            header,
+                            column_type,
+                            reference_path.display(),
+                            expected_type
+                        ))
+                    }
+                    Some(_) => {}
+                }
+            }
+            for (header, _) in &schema {
+                if !expected.iter().any(|(e, _)| e == header) {
+                    warnings.push(format!("{}: unexpected column {}", path.display(), header));
+                }
+            }
+        }
+
+        report.push_str("\nSchema:\n");
+        if warnings.is_empty() {
+            report.push_str("  consistent across all files\n");
+        }
+        for warning in &warnings {
+            report.push_str(&format!("  warning: {}\n", warning));
+        }
+
+        report.push_str("\nCombined Statistics:\n\n");
+        for (header, _) in &expected {
+            let values: Vec<f64> = parsed
+                .iter()
+                .filter_map(|(_, data)| data.get_column(header)?.numeric_values())
+                .flatten()
+                .collect();
+            if let Ok(stats) = StatisticsCalculator::calculate(&values) {
+                report.push_str(&format!("Column: {}\n{}\n", header, stats));
+            }
+        }
+
+        report
+    }
+
     /// Save report to file
     fn save_report<P: AsRef<Path>>(path: P, content: &str) -> Result<(), ProcessorError> {
         let mut file = File::create(path)
@@ -410,25 +1322,733 @@ impl ReportGenerator {
     }
 }
 
+// ============================================================================
+// MULTI-FILE PROCESSING
+// ============================================================================
+
+/// One input file's parsed data and statistics report, or why it failed
+struct FileReport {
+    path: PathBuf,
+    outcome: Result<(CsvData, String), ProcessorError>,
+}
+
+struct MultiFileProcessor {
+    parser: CsvParser,
+}
+
+impl MultiFileProcessor {
+    fn new(parser: CsvParser) -> Self {
+        MultiFileProcessor { parser }
+    }
+
+    /// Parse and summarize every file, concurrently with the `parallel`
+    /// feature. A failing file does not stop the others; reports come back
+    /// in input order either way.
+    fn process(&self, paths: &[PathBuf]) -> Vec<FileReport> {
+        let process_one = |path: &PathBuf| FileReport {
+            path: path.clone(),
+            outcome: self.parser.parse_file(path).map(|data| {
+                let statistics = ReportGenerator::generate_statistics_report(&data);
+                (data, statistics)
+            }),
+        };
+
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+            paths.par_iter().map(process_one).collect()
+        }
+        #[cfg(not(feature = "parallel"))]
+        {
+            paths.iter().map(process_one).collect()
+        }
+    }
+}
+
+// ============================================================================
+// VALIDATION
+// ============================================================================
+
+/// A check applied to every cell of one column
+enum Rule {
+    /// The cell must not be empty
+    Required,
+    /// Non-empty cells must match the pattern somewhere; anchor it with
+    /// `^...$` to match whole values
+    #[cfg(feature = "regex")]
+    Matches(regex::Regex),
+    /// Non-empty cells must be numbers within `min..=max`
+    Range { min: f64, max: f64 },
+    /// Non-empty cells must not repeat within the column
+    Unique,
+}
+
+/// One failed rule; `row` counts data rows from 1
+#[derive(Debug)]
+struct Violation {
+    row: usize,
+    column: String,
+    message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "row {}, {}: {}", self.row, self.column, self.message)
+    }
+}
+
+/// Violations found, with the rows split into those that passed every rule
+/// and those quarantined for failing any. Both datasets are re-inferred, so a
+/// column holding one stray "N/A" turns numeric again once it is cleaned.
+struct ValidationReport {
+    violations: Vec<Violation>,
+    clean: CsvData,
+    quarantined: CsvData,
+}
+
+impl ValidationReport {
+    fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// The violations as a dataset, ready for any `Exporter`
+    fn violations_data(&self) -> CsvData {
+        let records: Vec<Vec<String>> = self
+            .violations
+            .iter()
+            .map(|v| vec![v.row.to_string(), v.column.clone(), v.message.clone()])
+            .collect();
+        let headers = vec!["Row".to_string(), "Column".to_string(), "Violation".to_string()];
+        CsvData::from_records(headers, &records)
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Validation:")?;
+        writeln!(f, "  Clean rows:       {}", self.clean.row_count())?;
+        writeln!(f, "  Quarantined rows: {}", self.quarantined.row_count())?;
+        writeln!(f, "  Violations:       {}", self.violations.len())?;
+        for violation in &self.violations {
+            writeln!(f, "    {}", violation)?;
+        }
+        Ok(())
+    }
+}
+
+struct Validator {
+    rules: Vec<(String, Rule)>,
+}
+
+impl Validator {
+    fn new() -> Self {
+        Validator { rules: Vec::new() }
+    }
+
+    fn rule(mut self, column: &str, rule: Rule) -> Self {
+        self.rules.push((column.to_string(), rule));
+        self
+    }
+
+    /// Check every rule against `csv_data`. Rules naming a column the data
+    /// lacks are an error rather than silently passing.
+    fn validate(&self, csv_data: &CsvData) -> Result<ValidationReport, ProcessorError> {
+        let mut violations = Vec::new();
+
+        for (column_name, rule) in &self.rules {
+            let column = csv_data.get_column(column_name).ok_or_else(|| {
+                ProcessorError::ValidationError(format!("Unknown column: {}", column_name))
+            })?;
+            let mut seen: HashMap<String, usize> = HashMap::new();
+
+            for index in 0..csv_data.row_count() {
+                let value = column.value_at(index);
+                let message = match (rule, &value) {
+                    (Rule::Required, Value::Null) => Some("required value is missing".to_string()),
+                    (_, Value::Null) | (Rule::Required, _) => None,
+                    #[cfg(feature = "regex")]
+                    (Rule::Matches(pattern), value) => {
+                        let text = value.to_string();
+                        (!pattern.is_match(&text))
+                            .then(|| format!("{:?} does not match {}", text, pattern))
+                    }
+                    (Rule::Range { min, max }, value) => {
+                        // A column with stray text is inferred as strings, so
+                        // numbers may still arrive as text here
+                        let number = value
+                            .as_f64()
+                            .or_else(|| value.as_str().and_then(|s| s.parse().ok()));
+                        match number {
+                            Some(n) if n < *min || n > *max => {
+                                Some(format!("{} is outside {}..={}", n, min, max))
+                            }
+                            Some(_) => None,
+                            None => Some(format!("{:?} is not a number", value.to_string())),
+                        }
+                    }
+                    (Rule::Unique, value) => match seen.entry(value.to_string()) {
+                        Entry::Occupied(first) => {
+                            Some(format!("{:?} duplicates row {}", first.key(), first.get()))
+                        }
+                        Entry::Vacant(slot) => {
+                            slot.insert(index + 1);
+                            None
+                        }
+                    },
+                };
+
+                if let Some(message) = message {
+                    violations.push(Violation {
+                        row: index + 1,
+                        column: column_name.clone(),
+                        message,
+                    });
+                }
+            }
+        }
+
+        violations.sort_by_key(|v| v.row);
+        let rejected: HashSet<usize> = violations.iter().map(|v| v.row - 1).collect();
+        let (mut clean, mut quarantined) = (Vec::new(), Vec::new());
+        for index in 0..csv_data.row_count() {
+            let row = csv_data.row(index);
+            if rejected.contains(&index) {
+                quarantined.push(row);
+            } else {
+                clean.push(row);
+            }
+        }
+
+        Ok(ValidationReport {
+            violations,
+            clean: CsvData::from_records(csv_data.headers.clone(), &clean),
+            quarantined: CsvData::from_records(csv_data.headers.clone(), &quarantined),
+        })
+    }
+}
+
+// ============================================================================
+// EXPORTERS
+// ============================================================================
+
+/// Writes a dataset out in one file format
+trait Exporter {
+    /// Conventional file extension, without the dot
+    fn extension(&self) -> &'static str;
+
+    fn export(&self, csv_data: &CsvData, out: &mut dyn Write) -> Result<(), ProcessorError>;
+
+    fn export_to_file(&self, csv_data: &CsvData, path: &Path) -> Result<(), ProcessorError> {
+        let mut file = File::create(path)
+            .map_err(|e| ProcessorError::IoError(format!("Cannot create file: {}", e)))?;
+        self.export(csv_data, &mut file)
+    }
+}
+
+fn write_error(e: std::io::Error) -> ProcessorError {
+    ProcessorError::IoError(format!("Cannot write export: {}", e))
+}
+
+/// CSV that `CsvParser` reads back unchanged: fields holding the delimiter,
+/// quotes, line breaks, or edge whitespace are quoted, with quotes doubled
+struct CsvExporter {
+    delimiter: char,
+}
+
+impl CsvExporter {
+    fn new() -> Self {
+        CsvExporter { delimiter: ',' }
+    }
+
+    fn quote(&self, field: &str) -> String {
+        let needs_quotes = field.contains([self.delimiter, '"', '\n', '\r'])
+            || field.trim() != field;
+        if needs_quotes {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.to_string()
+        }
+    }
+
+    fn write_record(&self, out: &mut dyn Write, fields: &[String]) -> std::io::Result<()> {
+        let quoted: Vec<String> = fields.iter().map(|field| self.quote(field)).collect();
+        writeln!(out, "{}", quoted.join(&self.delimiter.to_string()))
+    }
+}
+
+impl Exporter for CsvExporter {
+    fn extension(&self) -> &'static str {
+        "csv"
+    }
+
+    fn export(&self, csv_data: &CsvData, out: &mut dyn Write) -> Result<(), ProcessorError> {
+        self.write_record(out, &csv_data.headers).map_err(write_error)?;
+        for i in 0..csv_data.row_count() {
+            self.write_record(out, &csv_data.row(i)).map_err(write_error)?;
+        }
+        Ok(())
+    }
+}
+
+/// One JSON object per row, keyed by header; numbers and booleans keep
+/// their type, dates become `YYYY-MM-DD` strings and empty cells `null`
+struct JsonLinesExporter;
+
+impl JsonLinesExporter {
+    fn json_value(value: &Value) -> String {
+        match value {
+            Value::Null => "null".to_string(),
+            Value::Float(v) if !v.is_finite() => "null".to_string(),
+            Value::Integer(_) | Value::Float(_) | Value::Boolean(_) => value.to_string(),
+            Value::Date(v) => json_string(&v.to_string()),
+            Value::String(v) => json_string(v),
+        }
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+impl Exporter for JsonLinesExporter {
+    fn extension(&self) -> &'static str {
+        "jsonl"
+    }
+
+    fn export(&self, csv_data: &CsvData, out: &mut dyn Write) -> Result<(), ProcessorError> {
+        for i in 0..csv_data.row_count() {
+            let fields: Vec<String> = csv_data
+                .headers
+                .iter()
+                .zip(&csv_data.columns)
+                .map(|(header, column)| {
+                    format!("{}:{}", json_string(header), Self::json_value(&column.value_at(i)))
+                })
+                .collect();
+            writeln!(out, "{{{}}}", fields.join(",")).map_err(write_error)?;
+        }
+        Ok(())
+    }
+}
+
+/// GitHub-flavoured Markdown table with numeric columns right-aligned
+struct MarkdownExporter;
+
+impl MarkdownExporter {
+    fn cell(text: &str) -> String {
+        text.replace('|', "\\|").replace("\r\n", "<br>").replace('\n', "<br>")
+    }
+}
+
+impl Exporter for MarkdownExporter {
+    fn extension(&self) -> &'static str {
+        "md"
+    }
+
+    fn export(&self, csv_data: &CsvData, out: &mut dyn Write) -> Result<(), ProcessorError> {
+        let headers: Vec<String> = csv_data.headers.iter().map(|h| Self::cell(h)).collect();
+        let alignments: Vec<&str> = csv_data
+            .columns
+            .iter()
+            .map(|column| match column.column_type() {
+                ColumnType::Integer | ColumnType::Float => "---:",
+                _ => "---",
+            })
+            .collect();
+
+        writeln!(out, "| {} |", headers.join(" | ")).map_err(write_error)?;
+        writeln!(out, "|{}|", alignments.join("|")).map_err(write_error)?;
+        for i in 0..csv_data.row_count() {
+            let cells: Vec<String> = csv_data.row(i).iter().map(|c| Self::cell(c)).collect();
+            writeln!(out, "| {} |", cells.join(" | ")).map_err(write_error)?;
+        }
+        Ok(())
+    }
+}
+
+/// Excel workbook with a bold header row and native number, boolean, and
+/// date cells
+#[cfg(feature = "xlsx")]
+struct XlsxExporter;
+
+#[cfg(feature = "xlsx")]
+impl Exporter for XlsxExporter {
+    fn extension(&self) -> &'static str {
+        "xlsx"
+    }
+
+    fn export(&self, csv_data: &CsvData, out: &mut dyn Write) -> Result<(), ProcessorError> {
+        use rust_xlsxwriter::{ExcelDateTime, Format, Workbook, XlsxError};
+
+        fn build(csv_data: &CsvData) -> Result<Vec<u8>, XlsxError> {
+            let mut workbook = Workbook::new();
+            let sheet = workbook.add_worksheet();
+            let bold = Format::new().set_bold();
+            let date_format = Format::new().set_num_format("yyyy-mm-dd");
+
+            for (col, header) in csv_data.headers.iter().enumerate() {
+                sheet.write_string_with_format(0, col as u16, header, &bold)?;
+            }
+            for (col, column) in csv_data.columns.iter().enumerate() {
+                let col = col as u16;
+                for i in 0..csv_data.row_count() {
+                    let row = i as u32 + 1;
+                    match column.value_at(i) {
+                        Value::Null => {}
+                        Value::Integer(v) => {
+                            sheet.write_number(row, col, v as f64)?;
+                        }
+                        Value::Float(v) => {
+                            sheet.write_number(row, col, v)?;
+                        }
+                        Value::Boolean(v) => {
+                            sheet.write_boolean(row, col, v)?;
+                        }
+                        Value::Date(d) => {
+                            let date =
+                                ExcelDateTime::from_ymd(d.year as u16, d.month as u8, d.day as u8)?;
+                            sheet.write_datetime_with_format(row, col, &date, &date_format)?;
+                        }
+                        Value::String(v) => {
+                            sheet.write_string(row, col, v)?;
+                        }
+                    }
+                }
+            }
+            sheet.autofit();
+            workbook.save_to_buffer()
+        }
+
+        let bytes = build(csv_data)
+            .map_err(|e| ProcessorError::IoError(format!("Cannot build workbook: {}", e)))?;
+        out.write_all(&bytes).map_err(write_error)
+    }
+}
+
+// ============================================================================
+// COMMAND LINE
+// ============================================================================
+
+#[cfg(feature = "cli")]
+mod cli {
+    use super::*;
+    use clap::{Args, Parser, Subcommand, ValueEnum};
+    use std::path::PathBuf;
+
+    /// Statistics, reports, and exports for CSV files
+    #[derive(Parser)]
+    #[command(name = "process", version)]
+    struct Cli {
+        #[command(flatten)]
+        input: InputOptions,
+
+        #[command(subcommand)]
+        command: Command,
+    }
+
+    #[derive(Args)]
+    struct InputOptions {
+        /// Field delimiter
+        #[arg(short, long, default_value_t = ',', global = true)]
+        delimiter: char,
+
+        /// Treat the first line as data rather than headers
+        #[arg(long, global = true)]
+        no_headers: bool,
+    }
+
+    #[derive(Subcommand)]
+    enum Command {
+        /// Print statistics for each input file
+        Stats {
+            /// Input files or glob patterns, e.g. 'data/*.csv'
+            #[arg(required = true)]
+            inputs: Vec<String>,
+
+            /// Only summarize this column; repeat for several
+            #[arg(short, long)]
+            column: Vec<String>,
+        },
+        /// Generate a report for one file
+        Report {
+            input: PathBuf,
+
+            #[arg(short, long, value_enum, default_value_t = ReportFormat::Text)]
+            format: ReportFormat,
+
+            /// Output file; prints to stdout when omitted
+            #[arg(short, long)]
+            output: Option<PathBuf>,
+        },
+        /// Write one file's data out in another format
+        Export {
+            input: PathBuf,
+
+            #[arg(short, long, value_enum)]
+            format: ExportFormat,
+
+            /// Output file; prints to stdout when omitted
+            #[arg(short, long)]
+            output: Option<PathBuf>,
+        },
+        /// Check one file against column rules and split out failing rows
+        Validate {
+            input: PathBuf,
+
+            /// Column that must not be empty; repeat for several
+            #[arg(long, value_name = "COLUMN")]
+            required: Vec<String>,
+
+            /// Column whose values must not repeat
+            #[arg(long, value_name = "COLUMN")]
+            unique: Vec<String>,
+
+            /// Numeric bounds for a column, e.g. Age=0..120
+            #[arg(long, value_name = "COLUMN=MIN..MAX")]
+            range: Vec<String>,
+
+            /// Pattern a column must match, e.g. 'Email=^[^@]+@[^@]+$'
+            #[cfg(feature = "regex")]
+            #[arg(long, value_name = "COLUMN=REGEX")]
+            matches: Vec<String>,
+
+            /// Write rows passing every rule to this CSV file
+            #[arg(long)]
+            clean: Option<PathBuf>,
+
+            /// Write rows failing any rule to this CSV file
+            #[arg(long)]
+            quarantine: Option<PathBuf>,
+        },
+    }
+
+    #[derive(Clone, Copy, ValueEnum)]
+    enum ReportFormat {
+        Text,
+        Html,
+    }
+
+    #[derive(Clone, Copy, ValueEnum)]
+    enum ExportFormat {
+        Csv,
+        Jsonl,
+        Markdown,
+        #[cfg(feature = "xlsx")]
+        Xlsx,
+    }
+
+    pub fn run() -> Result<(), ProcessorError> {
+        let cli = Cli::parse();
+        let parser = CsvParser::new()
+            .with_delimiter(cli.input.delimiter)
+            .with_headers(!cli.input.no_headers);
+
+        match cli.command {
+            Command::Stats { inputs, column } => {
+                let reports = MultiFileProcessor::new(parser).process(&expand_globs(&inputs)?);
+                for report in &reports {
+                    let (csv_data, statistics) = match &report.outcome {
+                        Ok(outcome) => outcome,
+                        Err(e) => {
+                            eprintln!("✗ {}: {}", report.path.display(), e);
+                            continue;
+                        }
+                    };
+                    println!("=== {} ({} rows) ===", report.path.display(), csv_data.row_count());
+                    if column.is_empty() {
+                        println!("{}", statistics);
+                        continue;
+                    }
+                    for name in &column {
+                        let summary = csv_data
+                            .get_column(name)
+                            .ok_or_else(|| {
+                                ProcessorError::ValidationError(format!("Unknown column: {}", name))
+                            })
+                            .and_then(|c| {
+                                ReportGenerator::column_summary(c).ok_or_else(|| {
+                                    ProcessorError::ValidationError(format!(
+                                        "No statistics for column {} ({})",
+                                        name,
+                                        c.column_type()
+                                    ))
+                                })
+                            })?;
+                        println!("Column: {}\n{}", name, summary);
+                    }
+                }
+
+                if reports.len() > 1 {
+                    println!("{}", ReportGenerator::generate_merged_report(&reports));
+                }
+                let failed = reports.iter().filter(|r| r.outcome.is_err()).count();
+                if failed > 0 {
+                    return Err(ProcessorError::IoError(format!(
+                        "{} of {} files failed",
+                        failed,
+                        reports.len()
+                    )));
+                }
+            }
+            Command::Report { input, format, output } => {
+                let csv_data = parser.parse_file(&input)?;
+                let report = match format {
+                    ReportFormat::Text => format!(
+                        "{}\n{}",
+                        ReportGenerator::generate_text_report(&csv_data),
+                        ReportGenerator::generate_statistics_report(&csv_data)
+                    ),
+                    ReportFormat::Html => ReportGenerator::generate_html_report(&csv_data),
+                };
+                match output {
+                    Some(path) => ReportGenerator::save_report(&path, &report)?,
+                    None => println!("{}", report),
+                }
+            }
+            Command::Export { input, format, output } => {
+                let csv_data = parser.parse_file(&input)?;
+                let exporter: Box<dyn Exporter> = match format {
+                    ExportFormat::Csv => Box::new(CsvExporter::new()),
+                    ExportFormat::Jsonl => Box::new(JsonLinesExporter),
+                    ExportFormat::Markdown => Box::new(MarkdownExporter),
+                    #[cfg(feature = "xlsx")]
+                    ExportFormat::Xlsx => Box::new(XlsxExporter),
+                };
+                match output {
+                    Some(path) => exporter.export_to_file(&csv_data, &path)?,
+                    None => exporter.export(&csv_data, &mut std::io::stdout().lock())?,
+                }
+            }
+            Command::Validate {
+                input,
+                required,
+                unique,
+                range,
+                #[cfg(feature = "regex")]
+                matches,
+                clean,
+                quarantine,
+            } => {
+                let csv_data = parser.parse_file(&input)?;
+                let mut validator = Validator::new();
+                for column in &required {
+                    validator = validator.rule(column, Rule::Required);
+                }
+                for column in &unique {
+                    validator = validator.rule(column, Rule::Unique);
+                }
+                for spec in &range {
+                    let (column, min, max) = parse_range(spec)?;
+                    validator = validator.rule(column, Rule::Range { min, max });
+                }
+                #[cfg(feature = "regex")]
+                for spec in &matches {
+                    let (column, pattern) = spec.split_once('=').ok_or_else(|| {
+                        ProcessorError::ParseError(format!("Expected COLUMN=REGEX, got {}", spec))
+                    })?;
+                    let pattern = regex::Regex::new(pattern)
+                        .map_err(|e| ProcessorError::ParseError(e.to_string()))?;
+                    validator = validator.rule(column, Rule::Matches(pattern));
+                }
+
+                let report = validator.validate(&csv_data)?;
+                print!("{}", report);
+                if let Some(path) = clean {
+                    CsvExporter::new().export_to_file(&report.clean, &path)?;
+                }
+                if let Some(path) = quarantine {
+                    CsvExporter::new().export_to_file(&report.quarantined, &path)?;
+                }
+                if !report.is_valid() {
+                    return Err(ProcessorError::ValidationError(format!(
+                        "{} rule violations",
+                        report.violations.len()
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Parse a `--range` flag's `COLUMN=MIN..MAX`
+    fn parse_range(spec: &str) -> Result<(&str, f64, f64), ProcessorError> {
+        let invalid =
+            || ProcessorError::ParseError(format!("Expected COLUMN=MIN..MAX, got {}", spec));
+        let (column, bounds) = spec.split_once('=').ok_or_else(invalid)?;
+        let (min, max) = bounds.split_once("..").ok_or_else(invalid)?;
+        Ok((
+            column,
+            min.parse().map_err(|_| invalid())?,
+            max.parse().map_err(|_| invalid())?,
+        ))
+    }
+
+    /// Expand glob patterns into paths. A plain path that matches nothing is
+    /// passed through so opening it reports the real error.
+    fn expand_globs(patterns: &[String]) -> Result<Vec<PathBuf>, ProcessorError> {
+        let mut paths = Vec::new();
+        for pattern in patterns {
+            let matches = glob::glob(pattern).map_err(|e| {
+                ProcessorError::ValidationError(format!("Bad pattern {}: {}", pattern, e))
+            })?;
+            let before = paths.len();
+            paths.extend(matches.filter_map(Result::ok));
+            if paths.len() == before {
+                if pattern.contains(['*', '?', '[']) {
+                    return Err(ProcessorError::IoError(format!("No files match {}", pattern)));
+                }
+                paths.push(PathBuf::from(pattern));
+            }
+        }
+        Ok(paths)
+    }
+}
+
 // ============================================================================
 // DEMO
 // ============================================================================
 
+#[cfg(not(feature = "cli"))]
 fn create_sample_csv() -> String {
-    r#"Name,Age,Salary,Department
-Alice,28,75000,Engineering
-Bob,35,85000,Engineering
-Charlie,42,95000,Management
-David,31,70000,Sales
-Eve,29,72000,Sales
-Frank,38,88000,Engineering
-Grace,33,78000,Marketing
-Henry,27,65000,Marketing
-Ivy,40,92000,Management
-Jack,36,80000,Sales"#
+    r#"Name,Age,Salary,Department,Hired,Remote
+Alice,28,75000,Engineering,2019-03-15,true
+Bob,35,85000,Engineering,2017-06-01,false
+Charlie,42,95000,Management,2012-09-10,false
+David,31,70000,Sales,2020-01-20,true
+Eve,29,72000,Sales,2021-03-01,yes
+Frank,38,88000,Engineering,2016-11-07,false
+Grace,33,78000,Marketing,2019-03-04,true
+Henry,27,65000,Marketing,2022-08-15,
+Ivy,40,92000,Management,2014-02-28,false
+Jack,36,80000,Sales,2019-06-11,true"#
         .to_string()
 }
 
+#[cfg(feature = "cli")]
+fn main() {
+    if let Err(e) = cli::run() {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Without the `cli` feature, run a demo over built-in sample data
+#[cfg(not(feature = "cli"))]
 fn main() {
     println!("=== CSV File Processor Demo ===\n");
 
@@ -442,7 +2062,11 @@ fn main() {
     let parser = CsvParser::new();
     match parser.parse_string(&csv_content) {
         Ok(csv_data) => {
-            println!("✓ Parsed {} rows with {} columns\n", csv_data.row_count(), csv_data.column_count());
+            println!("✓ Parsed {} rows with {} columns", csv_data.row_count(), csv_data.column_count());
+            for (header, column_type) in csv_data.schema() {
+                println!("  {}: {}", header, column_type);
+            }
+            println!();
 
             // Generate text report
             println!("2. Generating Text Report:");
@@ -455,10 +2079,11 @@ fn main() {
             println!("{}", stats_report);
 
             // Analyze specific column
-            println!("4. Analyzing Salary Column:");
+            println!("4. Analyzing Salary Column (P10/P90/P99):");
             if let Some(salary_column) = csv_data.get_column("Salary") {
-                let numeric_values = StatisticsCalculator::parse_numeric_column(&salary_column);
-                match StatisticsCalculator::calculate(&numeric_values) {
+                let numeric_values = salary_column.numeric_values().unwrap_or_default();
+                let percents = [10.0, 90.0, 99.0];
+                match StatisticsCalculator::calculate_with_percentiles(&numeric_values, &percents) {
                     Ok(stats) => println!("{}", stats),
                     Err(e) => println!("Error: {}", e),
                 }
@@ -466,19 +2091,63 @@ fn main() {
 
             // Department-wise analysis
             println!("5. Department-wise Employee Count:");
-            let dept_column = csv_data.get_column("Department").unwrap();
             let mut dept_counts: std::collections::HashMap<&String, usize> = std::collections::HashMap::new();
             
-            for dept in dept_column {
-                *dept_counts.entry(dept).or_insert(0) += 1;
+            if let Some(TypedColumn::String(dept_column)) = csv_data.get_column("Department") {
+                for dept in dept_column.iter().flatten() {
+                    *dept_counts.entry(dept).or_insert(0) += 1;
+                }
             }
             
             for (dept, count) in dept_counts {
                 println!("  {}: {} employees", dept, count);
             }
 
+            // Pipeline
+            println!("\n6. Engineering Staff Over 30 by Salary, With a 5% Raise:");
+            let engineers = csv_data
+                .pipeline()
+                .filter(|row| row.get("Department").as_str() == Some("Engineering"))
+                .filter(|row| row.get("Age").as_i64().is_some_and(|age| age > 30))
+                .sort_by("Salary", true)
+                .map_column("Salary", |salary| {
+                    salary.as_i64().map_or(Value::Null, |s| Value::Integer(s * 105 / 100))
+                })
+                .select(&["Name", "Age", "Salary"])
+                .collect();
+            match &engineers {
+                Ok(engineers) => print!("{}", ReportGenerator::generate_text_report(engineers)),
+                Err(e) => println!("Error: {}", e),
+            }
+
+            // Export the pipeline result
+            if let Ok(engineers) = &engineers {
+                println!("\n7. Exporting Engineering Staff:");
+                #[allow(unused_mut)]
+                let mut exporters: Vec<Box<dyn Exporter>> = vec![
+                    Box::new(CsvExporter::new()),
+                    Box::new(JsonLinesExporter),
+                    Box::new(MarkdownExporter),
+                ];
+                #[cfg(feature = "xlsx")]
+                exporters.push(Box::new(XlsxExporter));
+
+                for exporter in &exporters {
+                    let path = format!("/tmp/csv_export.{}", exporter.extension());
+                    match exporter.export_to_file(engineers, Path::new(&path)) {
+                        Ok(_) => println!("✓ Exported to {}", path),
+                        Err(e) => println!("✗ Error exporting {}: {}", path, e),
+                    }
+                }
+
+                let mut markdown = Vec::new();
+                if MarkdownExporter.export(engineers, &mut markdown).is_ok() {
+                    println!("\n{}", String::from_utf8_lossy(&markdown));
+                }
+            }
+
             // Generate HTML report
-            println!("\n6. Generating HTML Report:");
+            println!("\n8. Generating HTML Report:");
             let html_report = ReportGenerator::generate_html_report(&csv_data);
             
             let html_path = "/tmp/csv_report.html";
@@ -494,6 +2163,70 @@ fn main() {
                 Ok(_) => println!("✓ Text report saved to {}", text_path),
                 Err(e) => println!("✗ Error saving text report: {}", e),
             }
+
+            // Multi-file processing
+            println!("\n9. Processing Multiple Files:");
+            let second_csv = "Name,Age,Salary,Department,Hired\n\
+                              Kim,45,101000.50,Management,2015-04-01\n\
+                              Leo,26,61000.25,Sales,2023-02-13\n\
+                              Mia,34,83000.75,Engineering,2019-10-30\n";
+            let paths: Vec<PathBuf> = [
+                "/tmp/csv_sample_a.csv",
+                "/tmp/csv_sample_b.csv",
+                "/tmp/csv_missing.csv",
+            ]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+            let written = ReportGenerator::save_report(&paths[0], &csv_content)
+                .and_then(|_| ReportGenerator::save_report(&paths[1], second_csv));
+            match written {
+                Ok(_) => {
+                    let reports = MultiFileProcessor::new(CsvParser::new()).process(&paths);
+                    print!("{}", ReportGenerator::generate_merged_report(&reports));
+                }
+                Err(e) => println!("✗ Error writing sample files: {}", e),
+            }
+
+            // Validation
+            println!("\n10. Validating Messy Data:");
+            let messy_csv = "Name,Age,Email\n\
+                             Alice,28,alice@example.com\n\
+                             Bob,N/A,bob@example\n\
+                             ,35,carol@example.com\n\
+                             Dave,150,dave@example.com\n\
+                             Alice,30,alice.b@example.com\n\
+                             Erin,41,erin@example.com\n";
+            let validator = Validator::new()
+                .rule("Name", Rule::Required)
+                .rule("Name", Rule::Unique)
+                .rule("Age", Rule::Range { min: 0.0, max: 120.0 });
+            #[cfg(feature = "regex")]
+            let validator = validator.rule(
+                "Email",
+                Rule::Matches(regex::Regex::new(r"^[^@\s]+@[^@\s]+\.[a-z]+$").unwrap()),
+            );
+            let validated = CsvParser::new()
+                .parse_string(messy_csv)
+                .and_then(|messy| validator.validate(&messy));
+            match validated {
+                Ok(report) => {
+                    print!("{}", report);
+                    println!("  Clean schema:");
+                    for (header, column_type) in report.clean.schema() {
+                        println!("    {}: {}", header, column_type);
+                    }
+                    let errors_path = Path::new("/tmp/csv_violations.csv");
+                    if !report.is_valid() {
+                        let violations = report.violations_data();
+                        match CsvExporter::new().export_to_file(&violations, errors_path) {
+                            Ok(_) => println!("✓ Violations saved to {}", errors_path.display()),
+                            Err(e) => println!("✗ Error saving violations: {}", e),
+                        }
+                    }
+                }
+                Err(e) => println!("✗ Error validating: {}", e),
+            }
         }
         Err(e) => {
             println!("✗ Error parsing CSV: {}", e);
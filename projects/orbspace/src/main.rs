@@ -2,6 +2,10 @@ use rand::Rng;
 use std::io;
 use std::collections::HashMap;
 
+mod market;
+
+use market::{Market, MarketEvent, Portfolio};
+
 // Represents the player's current state
 #[derive(Debug)]
 enum State {
@@ -51,6 +55,8 @@ struct Game {
     star_systems: HashMap<String, Vec<Planet>>,
     missions: HashMap<String, Vec<Mission>>, // Missions per planet
     travel_weeks_left: u32, // Weeks remaining for inter-system travel
+    market: Market,
+    portfolio: Portfolio,
 }
 
 impl Game {
@@ -106,6 +112,8 @@ impl Game {
             star_systems,
             missions,
             travel_weeks_left: 0,
+            market: Market::new(),
+            portfolio: Portfolio::default(),
         }
     }
 
@@ -116,6 +124,7 @@ impl Game {
                 if self.funds < SPACE_COST {
                     println!("Cannot pay space costs of {} credits. Your starship is locked in the bay by government decree.", SPACE_COST);
                     self.state = State::Grounded;
+                    self.market.react(MarketEvent::PlayerGrounded);
                     true
                 } else {
                     self.funds -= SPACE_COST;
@@ -144,8 +153,11 @@ impl Game {
             .iter()
             .find(|p| p.description.contains(&self.current_planet))
             .unwrap();
-        let income = choose_activity(&planet.activities);
+        let (activity, income) = choose_activity(&planet.activities);
         self.funds += income;
+        if activity == "Trading" {
+            self.market.react(MarketEvent::TradeActivity(&self.current_star_system));
+        }
     }
 
     // Accept and attempt a mission
@@ -174,8 +186,10 @@ impl Game {
             if roll < success_chance {
                 println!("Mission successful! Earned {} credits.", mission.reward);
                 self.funds += mission.reward;
+                self.market.react(MarketEvent::MissionSucceeded(&self.current_star_system));
             } else {
                 println!("Mission failed. No reward.");
+                self.market.react(MarketEvent::MissionFailed(&self.current_star_system));
             }
         }
     }
@@ -244,9 +258,109 @@ impl Game {
         }
     }
 
+    // Show share prices and the player's positions
+    fn show_portfolio(&self) {
+        println!("Ticker  Corporation               Price  Dividend  Owned");
+        for corp in self.market.corporations() {
+            let owned = self.portfolio.holdings.get(&corp.ticker).map_or(0, |h| h.shares);
+            println!(
+                "{:<6}  {:<24}  {:>5}  {:>8}  {:>5}",
+                corp.ticker, corp.name, corp.price, corp.dividend_per_share, owned
+            );
+        }
+        for (ticker, holding) in &self.portfolio.holdings {
+            let price = self.market.get(ticker).map_or(0, |c| c.price);
+            let value = u64::from(price) * u64::from(holding.shares);
+            println!(
+                "  {}: {} shares worth {} credits (paid {}, {:+})",
+                ticker, holding.shares, value, holding.cost_basis, value as i64 - holding.cost_basis as i64
+            );
+        }
+        println!(
+            "Portfolio value: {} credits, margin loan: {} credits, equity: {} credits",
+            self.portfolio.market_value(&self.market),
+            self.portfolio.margin_loan,
+            self.portfolio.equity(&self.market)
+        );
+    }
+
+    // Buy and sell shares on the exchange
+    fn trade_stocks(&mut self) {
+        loop {
+            println!("\nStock exchange: 1. View portfolio  2. Buy  3. Buy on margin  4. Sell  0. Leave");
+            let choice = read_input_as_number();
+            let grounded = matches!(self.state, State::Grounded);
+            match choice {
+                1 => self.show_portfolio(),
+                2 | 3 => {
+                    println!("Enter ticker:");
+                    let ticker = read_input_as_string();
+                    println!("Enter number of shares:");
+                    let Ok(shares) = u32::try_from(read_input_as_number()) else {
+                        println!("No exchange can settle an order that large.");
+                        continue;
+                    };
+                    match self.market.buy(&mut self.portfolio, self.funds, &ticker, shares, choice == 3, grounded) {
+                        Ok(paid) => {
+                            self.funds -= paid;
+                            println!("Bought {} shares of {} for {} credits.", shares, ticker.to_uppercase(), paid);
+                        }
+                        Err(e) => println!("{}", e),
+                    }
+                }
+                4 => {
+                    println!("Enter ticker:");
+                    let ticker = read_input_as_string();
+                    println!("Enter number of shares:");
+                    let Ok(shares) = u32::try_from(read_input_as_number()) else {
+                        println!("No exchange can settle an order that large.");
+                        continue;
+                    };
+                    match self.market.sell(&mut self.portfolio, &ticker, shares) {
+                        Ok(received) => {
+                            self.funds += received;
+                            println!("Sold {} shares of {}, received {} credits.", shares, ticker.to_uppercase(), received);
+                        }
+                        Err(e) => println!("{}", e),
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+
+    // Pay dividends and margin interest, and enforce margin requirements
+    fn update_market(&mut self) {
+        let grounded = matches!(self.state, State::Grounded);
+        let report = self.market.weekly_update(&mut self.portfolio, grounded);
+        if report.dividends > 0 {
+            self.funds += report.dividends;
+            println!("Received {} credits in dividends.", report.dividends);
+        }
+        if report.interest > 0 {
+            println!("Margin interest of {} credits added to your loan.", report.interest);
+        }
+        if !report.liquidated.is_empty() {
+            let sold: Vec<String> = report.liquidated.iter().map(|(t, n)| format!("{} {}", n, t)).collect();
+            println!("MARGIN CALL! Your broker force-sold {} shares.", sold.join(", "));
+        }
+        if report.shortfall > 0 {
+            let paid = report.shortfall.min(self.funds);
+            self.funds -= paid;
+            println!("Your broker seized {} credits to cover the remaining margin debt.", paid);
+            // Whatever the seizure could not cover stays on the loan and keeps accruing interest
+            let unpaid = report.shortfall - paid;
+            if unpaid > 0 {
+                self.portfolio.margin_loan += unpaid;
+                println!("You still owe your broker {} credits.", unpaid);
+            }
+        }
+    }
+
     // Advance to the next week
     fn advance_week(&mut self) {
         self.week += 1;
+        self.update_market();
         if matches!(self.state, State::Traveling) {
             self.travel_weeks_left -= 1;
             if self.travel_weeks_left == 0 {
@@ -290,8 +404,8 @@ fn read_yes_no() -> bool {
     }
 }
 
-// Choose an activity and return earned income
-fn choose_activity(activities: &[Activity]) -> u32 {
+// Choose an activity and return its name with the earned income
+fn choose_activity(activities: &[Activity]) -> (&str, u32) {
     println!("Choose an activity:");
     for (i, activity) in activities.iter().enumerate() {
         println!(
@@ -309,7 +423,7 @@ fn choose_activity(activities: &[Activity]) -> u32 {
                 rand::thread_rng().gen_range(activity.min_income..=activity.max_income)
             };
             println!("Earned {} credits from {}.", income, activity.name);
-            return income;
+            return (&activity.name, income);
         }
         println!("Invalid choice, please try again.");
     }
@@ -336,6 +450,10 @@ fn main() {
                 game.accept_mission();
             }
         }
+        println!("Visit the stock exchange? (y/n)");
+        if read_yes_no() {
+            game.trade_stocks();
+        }
         game.check_license_renewal();
         println!("Continue to next week? (y/n)");
         if !read_yes_no() {
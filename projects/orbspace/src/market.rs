@@ -0,0 +1,377 @@
+use rand::Rng;
+use std::collections::HashMap;
+
+// Market constants
+const INITIAL_MARGIN_PCT: u32 = 50;          // Share of a margin purchase the player must pay up front
+const MAINTENANCE_MARGIN_PCT: u32 = 30;      // Minimum equity / market value before a margin call
+const GROUNDED_MAINTENANCE_PCT: u32 = 50;    // Lenders demand more equity from grounded pilots
+const MARGIN_INTEREST_PCT: u32 = 2;          // Weekly interest on the margin loan
+const PRICE_IMPACT_PER_SHARE: f64 = 0.002;   // Player trades move the price by 0.2% per share
+
+// Things that happen in the game and move share prices
+pub enum MarketEvent<'a> {
+    MissionSucceeded(&'a str), // Star system where the mission happened
+    MissionFailed(&'a str),
+    TradeActivity(&'a str),    // Player traded goods in a system
+    PlayerGrounded,            // Bad press for every listed corporation
+}
+
+// An in-universe corporation with tradable shares
+pub struct Corporation {
+    pub ticker: String,
+    pub name: String,
+    pub home_system: String,
+    pub price: u32,              // Credits per share
+    pub dividend_per_share: u32, // Paid weekly
+    volatility: f64,             // Max weekly random drift as a fraction of price
+}
+
+impl Corporation {
+    fn new(ticker: &str, name: &str, home_system: &str, price: u32, dividend_per_share: u32, volatility: f64) -> Self {
+        Corporation {
+            ticker: ticker.to_string(),
+            name: name.to_string(),
+            home_system: home_system.to_string(),
+            price,
+            dividend_per_share,
+            volatility,
+        }
+    }
+
+    // The price moved by a percentage, never dropping below 1 credit
+    fn impacted_price(&self, change: f64) -> u32 {
+        (self.price as f64 * (1.0 + change)).round().max(1.0) as u32
+    }
+
+    fn adjust_price(&mut self, change: f64) {
+        self.price = self.impacted_price(change);
+    }
+
+    // Player orders fill at the average of the price before and after their
+    // own impact, buys rounding up and sells down, so buying and selling
+    // straight back can never move the price in the player's favour
+    fn fill_price(&self, change: f64, round_up: bool) -> u32 {
+        let total = self.price as u64 + self.impacted_price(change) as u64;
+        (if round_up { total.div_ceil(2) } else { total / 2 }) as u32
+    }
+}
+
+// A position in one corporation
+#[derive(Default)]
+pub struct Holding {
+    pub shares: u32,
+    pub cost_basis: u32, // Total credits paid for the shares held
+}
+
+// The player's shares and borrowed funds
+#[derive(Default)]
+pub struct Portfolio {
+    pub holdings: HashMap<String, Holding>,
+    pub margin_loan: u32,
+}
+
+impl Portfolio {
+    // Current value of all shares at market prices
+    pub fn market_value(&self, market: &Market) -> u64 {
+        self.holdings
+            .iter()
+            .filter_map(|(ticker, holding)| market.get(ticker).map(|c| c.price as u64 * holding.shares as u64))
+            .sum()
+    }
+
+    // Market value minus what is owed to the lender
+    pub fn equity(&self, market: &Market) -> i64 {
+        self.market_value(market) as i64 - self.margin_loan as i64
+    }
+
+    fn remove_shares(&mut self, ticker: &str, shares: u32) {
+        if let Some(holding) = self.holdings.get_mut(ticker) {
+            let removed_basis = (holding.cost_basis as u64 * shares as u64 / holding.shares.max(1) as u64) as u32;
+            holding.shares -= shares;
+            holding.cost_basis -= removed_basis;
+            if holding.shares == 0 {
+                self.holdings.remove(ticker);
+            }
+        }
+    }
+}
+
+// Outcome of the weekly market update
+pub struct WeeklyReport {
+    pub dividends: u32,
+    pub interest: u32,
+    pub liquidated: Vec<(String, u32)>, // Shares force-sold by a margin call
+    pub shortfall: u32,                 // Loan left unpaid after liquidation, taken from funds
+}
+
+// The stock exchange for all listed corporations
+pub struct Market {
+    corporations: Vec<Corporation>,
+}
+
+impl Market {
+    pub fn new() -> Self {
+        Market {
+            corporations: vec![
+                Corporation::new("AMC", "Alpha Mining Consortium", "Alpha", 120, 3, 0.05),
+                Corporation::new("BSL", "Beta Shipping Lines", "Beta", 80, 2, 0.08),
+                Corporation::new("GBX", "Gamma Biotech", "Gamma", 200, 1, 0.15),
+                Corporation::new("DDW", "Delta Defense Works", "Delta", 150, 4, 0.06),
+            ],
+        }
+    }
+
+    pub fn corporations(&self) -> &[Corporation] {
+        &self.corporations
+    }
+
+    pub fn get(&self, ticker: &str) -> Option<&Corporation> {
+        self.corporations.iter().find(|c| c.ticker.eq_ignore_ascii_case(ticker))
+    }
+
+    fn get_mut(&mut self, ticker: &str) -> Option<&mut Corporation> {
+        self.corporations.iter_mut().find(|c| c.ticker.eq_ignore_ascii_case(ticker))
+    }
+
+    // Let game events ripple through share prices
+    pub fn react(&mut self, event: MarketEvent) {
+        for corp in &mut self.corporations {
+            let change = match &event {
+                MarketEvent::MissionSucceeded(system) if corp.home_system == *system => 0.06,
+                MarketEvent::MissionSucceeded(_) => 0.01,
+                MarketEvent::MissionFailed(system) if corp.home_system == *system => -0.05,
+                MarketEvent::MissionFailed(_) => 0.0,
+                MarketEvent::TradeActivity(system) if corp.home_system == *system => 0.02,
+                MarketEvent::TradeActivity(_) => 0.0,
+                MarketEvent::PlayerGrounded => -0.03,
+            };
+            if change != 0.0 {
+                corp.adjust_price(change);
+            }
+        }
+    }
+
+    // Buy shares, optionally borrowing part of the cost on margin.
+    // Returns the credits taken from the player's funds.
+    pub fn buy(
+        &mut self,
+        portfolio: &mut Portfolio,
+        funds: u32,
+        ticker: &str,
+        shares: u32,
+        on_margin: bool,
+        grounded: bool,
+    ) -> Result<u32, String> {
+        if shares == 0 {
+            return Err("You must buy at least one share.".to_string());
+        }
+        if on_margin && grounded {
+            return Err("Lenders will not extend margin to a grounded pilot.".to_string());
+        }
+        let corp = self.get_mut(ticker).ok_or_else(|| format!("Unknown ticker {}.", ticker))?;
+        let impact = PRICE_IMPACT_PER_SHARE * shares as f64;
+        let cost = corp.fill_price(impact, true).checked_mul(shares).ok_or_else(order_too_large)?;
+        let cash_needed = if on_margin {
+            (cost as u64 * INITIAL_MARGIN_PCT as u64).div_ceil(100) as u32
+        } else {
+            cost
+        };
+        if cash_needed > funds {
+            return Err(format!("That costs {} credits up front, but you only have {}.", cash_needed, funds));
+        }
+
+        // Check every total before changing anything, so a failed order leaves no trace
+        let ticker = corp.ticker.clone();
+        let held = portfolio.holdings.get(&ticker);
+        let new_shares = held.map_or(0, |h| h.shares).checked_add(shares);
+        let new_basis = held.map_or(0, |h| h.cost_basis).checked_add(cost);
+        let new_loan = portfolio.margin_loan.checked_add(cost - cash_needed);
+        let (Some(new_shares), Some(new_basis), Some(new_loan)) = (new_shares, new_basis, new_loan) else {
+            return Err(order_too_large());
+        };
+
+        corp.adjust_price(impact);
+        let holding = portfolio.holdings.entry(ticker).or_default();
+        holding.shares = new_shares;
+        holding.cost_basis = new_basis;
+        portfolio.margin_loan = new_loan;
+        Ok(cash_needed)
+    }
+
+    // Sell shares; proceeds first repay any margin loan.
+    // Returns the credits added to the player's funds.
+    pub fn sell(&mut self, portfolio: &mut Portfolio, ticker: &str, shares: u32) -> Result<u32, String> {
+        let corp = self.get_mut(ticker).ok_or_else(|| format!("Unknown ticker {}.", ticker))?;
+        let ticker = corp.ticker.clone();
+        let owned = portfolio.holdings.get(&ticker).map_or(0, |h| h.shares);
+        if shares == 0 || shares > owned {
+            return Err(format!("You own {} shares of {}.", owned, ticker));
+        }
+
+        let impact = -PRICE_IMPACT_PER_SHARE * shares as f64;
+        let proceeds = corp.fill_price(impact, false).checked_mul(shares).ok_or_else(order_too_large)?;
+        corp.adjust_price(impact);
+        portfolio.remove_shares(&ticker, shares);
+
+        let repayment = proceeds.min(portfolio.margin_loan);
+        portfolio.margin_loan -= repayment;
+        Ok(proceeds - repayment)
+    }
+
+    // Advance the market one week: random drift, dividends, interest, and margin calls
+    pub fn weekly_update(&mut self, portfolio: &mut Portfolio, grounded: bool) -> WeeklyReport {
+        let mut rng = rand::thread_rng();
+        for corp in &mut self.corporations {
+            let drift = rng.gen_range(-corp.volatility..=corp.volatility);
+            corp.adjust_price(drift);
+        }
+
+        let dividends = portfolio
+            .holdings
+            .iter()
+            .filter_map(|(ticker, holding)| self.get(ticker).map(|c| c.dividend_per_share.saturating_mul(holding.shares)))
+            .fold(0, u32::saturating_add);
+
+        let interest = (portfolio.margin_loan as u64 * MARGIN_INTEREST_PCT as u64).div_ceil(100) as u32;
+        portfolio.margin_loan = portfolio.margin_loan.saturating_add(interest);
+
+        let (liquidated, shortfall) = self.enforce_margin(portfolio, grounded);
+        WeeklyReport { dividends, interest, liquidated, shortfall }
+    }
+
+    // Force-sell shares until equity meets the maintenance requirement
+    fn enforce_margin(&mut self, portfolio: &mut Portfolio, grounded: bool) -> (Vec<(String, u32)>, u32) {
+        let mut liquidated = Vec::new();
+        if portfolio.margin_loan == 0 {
+            return (liquidated, 0);
+        }
+        let maintenance = if grounded { GROUNDED_MAINTENANCE_PCT } else { MAINTENANCE_MARGIN_PCT };
+
+        while portfolio.margin_loan > 0 && !meets_maintenance(portfolio, self, maintenance) {
+            // Sell one share of the most valuable position at a time
+            let target = portfolio
+                .holdings
+                .keys()
+                .filter_map(|t| self.get(t).map(|c| (t.clone(), c.price)))
+                .max_by_key(|(_, price)| *price);
+            let Some((ticker, _)) = target else { break };
+
+            // Forced sales are repaid to the lender in full, so no proceeds remain
+            let _ = self.sell(portfolio, &ticker, 1);
+            match liquidated.iter_mut().find(|(t, _)| *t == ticker) {
+                Some((_, count)) => *count += 1,
+                None => liquidated.push((ticker, 1)),
+            }
+        }
+
+        // Nothing left to sell: the remaining debt comes out of the player's funds
+        let shortfall = if portfolio.holdings.is_empty() { portfolio.margin_loan } else { 0 };
+        if shortfall > 0 {
+            portfolio.margin_loan = 0;
+        }
+        (liquidated, shortfall)
+    }
+}
+
+fn order_too_large() -> String {
+    "That order is too large for the exchange to settle.".to_string()
+}
+
+fn meets_maintenance(portfolio: &Portfolio, market: &Market, maintenance_pct: u32) -> bool {
+    let value = portfolio.market_value(market) as i64;
+    portfolio.equity(market) * 100 >= value * maintenance_pct as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_margin_purchase_borrows_half() {
+        let mut market = Market::new();
+        let mut portfolio = Portfolio::default();
+        let fill = market.get("AMC").unwrap().fill_price(PRICE_IMPACT_PER_SHARE * 10.0, true);
+
+        let paid = market.buy(&mut portfolio, 10_000, "AMC", 10, true, false).unwrap();
+        assert_eq!(paid, fill * 5);
+        assert_eq!(portfolio.margin_loan, fill * 5);
+        assert_eq!(portfolio.holdings["AMC"].shares, 10);
+    }
+
+    #[test]
+    fn test_grounded_pilot_cannot_use_margin() {
+        let mut market = Market::new();
+        let mut portfolio = Portfolio::default();
+        assert!(market.buy(&mut portfolio, 10_000, "AMC", 1, true, true).is_err());
+        assert!(market.buy(&mut portfolio, 10_000, "AMC", 1, false, true).is_ok());
+    }
+
+    #[test]
+    fn test_sale_repays_loan_first() {
+        let mut market = Market::new();
+        let mut portfolio = Portfolio::default();
+        market.buy(&mut portfolio, 10_000, "BSL", 10, true, false).unwrap();
+        let loan = portfolio.margin_loan;
+        let fill = market.get("BSL").unwrap().fill_price(-PRICE_IMPACT_PER_SHARE * 10.0, false);
+
+        let cash = market.sell(&mut portfolio, "BSL", 10).unwrap();
+        assert_eq!(portfolio.margin_loan, 0);
+        assert_eq!(cash, fill * 10 - loan);
+        assert!(portfolio.holdings.is_empty());
+    }
+
+    #[test]
+    fn test_price_crash_triggers_margin_call() {
+        let mut market = Market::new();
+        let mut portfolio = Portfolio::default();
+        market.buy(&mut portfolio, 10_000, "GBX", 20, true, false).unwrap();
+
+        market.get_mut("GBX").unwrap().price = 60;
+        let (liquidated, _) = market.enforce_margin(&mut portfolio, false);
+        assert!(!liquidated.is_empty());
+        assert!(portfolio.margin_loan == 0 || meets_maintenance(&portfolio, &market, MAINTENANCE_MARGIN_PCT));
+    }
+
+    #[test]
+    fn test_round_trip_never_profits() {
+        for ticker in ["AMC", "BSL", "GBX", "DDW"] {
+            for shares in [1, 2, 5, 10, 37, 100, 250, 400] {
+                let mut market = Market::new();
+                let mut portfolio = Portfolio::default();
+                let mut funds = 1_000_000;
+                funds -= market.buy(&mut portfolio, funds, ticker, shares, false, false).unwrap();
+                funds += market.sell(&mut portfolio, ticker, shares).unwrap();
+                assert!(funds <= 1_000_000, "{} x{} turned 1000000 into {}", ticker, shares, funds);
+            }
+
+            // Buying in small lots and dumping them at once does not pay either
+            let mut market = Market::new();
+            let mut portfolio = Portfolio::default();
+            let mut funds = 1_000_000;
+            for _ in 0..50 {
+                funds -= market.buy(&mut portfolio, funds, ticker, 4, false, false).unwrap();
+            }
+            funds += market.sell(&mut portfolio, ticker, 200).unwrap();
+            assert!(funds <= 1_000_000, "{} in lots turned 1000000 into {}", ticker, funds);
+        }
+    }
+
+    #[test]
+    fn test_oversized_order_rejected() {
+        let mut market = Market::new();
+        let mut portfolio = Portfolio::default();
+        let price = market.get("AMC").unwrap().price;
+
+        assert_eq!(market.buy(&mut portfolio, u32::MAX, "AMC", 1_000_000_000, true, false), Err(order_too_large()));
+        assert!(portfolio.holdings.is_empty());
+        assert_eq!(market.get("AMC").unwrap().price, price);
+    }
+
+    #[test]
+    fn test_events_move_home_system_prices() {
+        let mut market = Market::new();
+        let before = market.get("DDW").unwrap().price;
+        market.react(MarketEvent::MissionSucceeded("Delta"));
+        assert!(market.get("DDW").unwrap().price > before);
+    }
+}
@@ -0,0 +1,216 @@
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use std::rc::Rc;
+
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::{header, Method};
+use actix_web::{web, Error};
+use sha2::{Digest, Sha256};
+use tokio::sync::mpsc;
+
+use crate::db::Database;
+use crate::models::{DailyViews, PostViews};
+use crate::AppState;
+
+pub const DEFAULT_DAYS: u32 = 30;
+pub const MAX_DAYS: u32 = 365;
+
+/// Views waiting to be written. When the writer falls this far behind, new
+/// views are dropped rather than making requests wait.
+const QUEUE_SIZE: usize = 1024;
+
+#[derive(Debug, Clone)]
+pub struct PageView {
+    pub path: String,
+    pub referrer: Option<String>,
+    pub ip_hash: String,
+    pub created_at: String,
+}
+
+/// One post's views on one UTC day, as aggregated by the database.
+#[derive(Debug, Clone)]
+pub struct DailyPostViews {
+    pub post_id: String,
+    pub title: String,
+    pub slug: String,
+    pub day: String,
+    pub views: i64,
+}
+
+/// Handle to the background task that writes page views.
+#[derive(Clone)]
+pub struct Analytics {
+    sender: mpsc::Sender<PageView>,
+    salt: String,
+}
+
+impl Analytics {
+    /// Spawns the writer task on the current runtime. Client IPs are hashed
+    /// with `salt` so raw addresses are never stored.
+    pub fn start(db: Database, salt: String) -> Self {
+        let (sender, mut receiver) = mpsc::channel::<PageView>(QUEUE_SIZE);
+
+        actix_web::rt::spawn(async move {
+            while let Some(view) = receiver.recv().await {
+                if let Err(e) = db.record_page_view(&view).await {
+                    log::warn!("Failed to record page view of {}: {}", view.path, e);
+                }
+            }
+        });
+
+        Self { sender, salt }
+    }
+
+    pub fn hash_ip(&self, ip: &str) -> String {
+        hex::encode(Sha256::digest(format!("{}:{}", self.salt, ip).as_bytes()))
+    }
+
+    /// Queues a view without waiting for the database.
+    pub fn record(&self, view: PageView) {
+        if let Err(mpsc::error::TrySendError::Full(view)) = self.sender.try_send(view) {
+            log::debug!("Analytics queue full, dropping view of {}", view.path);
+        }
+    }
+}
+
+/// Clamps the requested report length to `1..=MAX_DAYS`.
+pub fn report_days(requested: Option<u32>) -> u32 {
+    requested.unwrap_or(DEFAULT_DAYS).clamp(1, MAX_DAYS)
+}
+
+/// Folds per-day rows into one entry per post, most viewed first.
+pub fn group_by_post(rows: Vec<DailyPostViews>) -> Vec<PostViews> {
+    let mut posts: Vec<PostViews> = Vec::new();
+
+    for row in rows {
+        let daily = DailyViews { day: row.day, views: row.views };
+        match posts.iter_mut().find(|p| p.post_id == row.post_id) {
+            Some(post) => {
+                post.total += daily.views;
+                post.daily.push(daily);
+            }
+            None => posts.push(PostViews {
+                post_id: row.post_id,
+                title: row.title,
+                slug: row.slug,
+                total: daily.views,
+                daily: vec![daily],
+            }),
+        }
+    }
+
+    for post in &mut posts {
+        post.daily.sort_by(|a, b| a.day.cmp(&b.day));
+    }
+    posts.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.slug.cmp(&b.slug)));
+    posts
+}
+
+/// Middleware that records every successful `GET` it wraps as a page view.
+/// The view is handed to the writer task after the response is produced.
+pub fn track_views() -> TrackViews {
+    TrackViews
+}
+
+pub struct TrackViews;
+
+impl<S, B> Transform<S, ServiceRequest> for TrackViews
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = TrackViewsMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(TrackViewsMiddleware { service: Rc::new(service) }))
+    }
+}
+
+pub struct TrackViewsMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for TrackViewsMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    actix_web::dev::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let state = req
+            .app_data::<web::Data<AppState>>()
+            .filter(|_| req.method() == Method::GET)
+            .cloned();
+        let view = state.map(|state| {
+            let ip = req.connection_info().realip_remote_addr().unwrap_or("unknown").to_string();
+            let view = PageView {
+                path: req.path().to_string(),
+                referrer: req
+                    .headers()
+                    .get(header::REFERER)
+                    .and_then(|v| v.to_str().ok())
+                    .map(str::to_string),
+                ip_hash: state.analytics.hash_ip(&ip),
+                created_at: chrono::Utc::now().to_rfc3339(),
+            };
+            (state, view)
+        });
+
+        let service = Rc::clone(&self.service);
+        Box::pin(async move {
+            let res = service.call(req).await?;
+            if let Some((state, view)) = view {
+                if res.status().is_success() {
+                    state.analytics.record(view);
+                }
+            }
+            Ok(res)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(post_id: &str, day: &str, views: i64) -> DailyPostViews {
+        DailyPostViews {
+            post_id: post_id.to_string(),
+            title: post_id.to_uppercase(),
+            slug: post_id.to_string(),
+            day: day.to_string(),
+            views,
+        }
+    }
+
+    #[test]
+    fn test_group_by_post() {
+        let posts = group_by_post(vec![
+            row("a", "2024-05-02", 1),
+            row("b", "2024-05-01", 4),
+            row("a", "2024-05-01", 2),
+        ]);
+
+        assert_eq!(posts.len(), 2);
+        assert_eq!(posts[0].slug, "b");
+        assert_eq!(posts[1].total, 3);
+        let days: Vec<_> = posts[1].daily.iter().map(|d| d.day.as_str()).collect();
+        assert_eq!(days, ["2024-05-01", "2024-05-02"]);
+    }
+
+    #[test]
+    fn test_report_days() {
+        assert_eq!(report_days(None), DEFAULT_DAYS);
+        assert_eq!(report_days(Some(0)), 1);
+        assert_eq!(report_days(Some(10_000)), MAX_DAYS);
+    }
+}
@@ -0,0 +1,190 @@
+use actix_web::HttpRequest;
+use serde::Serialize;
+
+use crate::db::Database;
+use crate::models::{AuditEntry, AuditQuery, Claims};
+
+pub const DEFAULT_PAGE_SIZE: u32 = 50;
+pub const MAX_PAGE_SIZE: u32 = 200;
+
+/// Recorded actions, stored as `<target>.<verb>` so the viewer can filter
+/// on a whole target type (`post`) or a single action (`post.delete`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    Login,
+    LoginFailed,
+    Logout,
+    LogoutAll,
+    TokenReuse,
+    UserCreate,
+    UserRoleChange,
+    PostCreate,
+    PostUpdate,
+    PostDelete,
+    CommentApprove,
+    CommentDelete,
+}
+
+impl AuditAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditAction::Login => "user.login",
+            AuditAction::LoginFailed => "user.login_failed",
+            AuditAction::Logout => "user.logout",
+            AuditAction::LogoutAll => "user.logout_all",
+            AuditAction::TokenReuse => "user.token_reuse",
+            AuditAction::UserCreate => "user.create",
+            AuditAction::UserRoleChange => "user.role",
+            AuditAction::PostCreate => "post.create",
+            AuditAction::PostUpdate => "post.update",
+            AuditAction::PostDelete => "post.delete",
+            AuditAction::CommentApprove => "comment.approve",
+            AuditAction::CommentDelete => "comment.delete",
+        }
+    }
+
+    fn target_type(self) -> &'static str {
+        self.as_str().split('.').next().unwrap_or_default()
+    }
+}
+
+/// One audit record under construction; see [`record`].
+pub struct AuditEvent {
+    action: AuditAction,
+    actor_id: Option<String>,
+    actor_name: String,
+    target_id: Option<String>,
+    before: Option<serde_json::Value>,
+    after: Option<serde_json::Value>,
+}
+
+impl AuditEvent {
+    pub fn new(action: AuditAction, actor_id: Option<&str>, actor_name: &str) -> Self {
+        Self {
+            action,
+            actor_id: actor_id.map(str::to_string),
+            actor_name: actor_name.to_string(),
+            target_id: None,
+            before: None,
+            after: None,
+        }
+    }
+
+    pub fn by(action: AuditAction, claims: &Claims) -> Self {
+        Self::new(action, Some(&claims.sub), &claims.username)
+    }
+
+    pub fn target(mut self, id: &str) -> Self {
+        self.target_id = Some(id.to_string());
+        self
+    }
+
+    pub fn before<T: Serialize>(mut self, snapshot: &T) -> Self {
+        self.before = serde_json::to_value(snapshot).ok();
+        self
+    }
+
+    pub fn after<T: Serialize>(mut self, snapshot: &T) -> Self {
+        self.after = serde_json::to_value(snapshot).ok();
+        self
+    }
+}
+
+/// Writes the event with the client IP. A failed write is logged rather
+/// than failing the request that triggered it.
+pub async fn record(db: &Database, req: &HttpRequest, event: AuditEvent) {
+    let entry = AuditEntry {
+        id: uuid::Uuid::new_v4().to_string(),
+        actor_id: event.actor_id,
+        actor_name: event.actor_name,
+        action: event.action.as_str().to_string(),
+        target_type: event.action.target_type().to_string(),
+        target_id: event.target_id,
+        ip: req.connection_info().realip_remote_addr().map(str::to_string),
+        before: event.before,
+        after: event.after,
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+
+    if let Err(e) = db.record_audit(&entry).await {
+        log::error!("Failed to record audit entry {}: {}", entry.action, e);
+    }
+}
+
+/// Normalised filters and paging for an audit query.
+#[derive(Debug, PartialEq, Eq)]
+pub struct AuditFilter {
+    pub actor: Option<String>,
+    pub action: Option<String>,
+    pub page: u32,
+    pub per_page: u32,
+}
+
+impl AuditFilter {
+    pub fn from_query(query: &AuditQuery) -> Self {
+        let non_empty = |value: &Option<String>| {
+            value.as_deref().map(str::trim).filter(|v| !v.is_empty()).map(str::to_string)
+        };
+
+        Self {
+            actor: non_empty(&query.actor),
+            action: non_empty(&query.action),
+            page: query.page.unwrap_or(1).max(1),
+            per_page: query.per_page.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE),
+        }
+    }
+
+    /// Rows to skip. Widened to u64 because `page` comes straight from the
+    /// query string and only `per_page` is clamped.
+    pub fn offset(&self) -> u64 {
+        u64::from(self.page - 1) * u64::from(self.per_page)
+    }
+
+    pub fn total_pages(&self, total: i64) -> u32 {
+        ((total.max(0) as u32).div_ceil(self.per_page)).max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_normalises_query() {
+        let filter = AuditFilter::from_query(&AuditQuery {
+            page: Some(0),
+            per_page: Some(1000),
+            actor: Some("  ".to_string()),
+            action: Some("post".to_string()),
+        });
+
+        assert_eq!(filter, AuditFilter {
+            actor: None,
+            action: Some("post".to_string()),
+            page: 1,
+            per_page: MAX_PAGE_SIZE,
+        });
+        assert_eq!(filter.offset(), 0);
+        assert_eq!(filter.total_pages(0), 1);
+        assert_eq!(filter.total_pages(401), 3);
+    }
+
+    #[test]
+    fn test_offset_of_huge_page_does_not_overflow() {
+        let filter = AuditFilter::from_query(&AuditQuery {
+            page: Some(4_000_000_000),
+            per_page: Some(100),
+            actor: None,
+            action: None,
+        });
+
+        assert_eq!(filter.offset(), 3_999_999_999 * 100);
+    }
+
+    #[test]
+    fn test_action_names_carry_target_type() {
+        assert_eq!(AuditAction::PostDelete.as_str(), "post.delete");
+        assert_eq!(AuditAction::PostDelete.target_type(), "post");
+        assert_eq!(AuditAction::LoginFailed.target_type(), "user");
+    }
+}
@@ -1,17 +1,84 @@
+use std::env;
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use std::rc::Rc;
+
+use actix_web::body::EitherBody;
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::{web, Error, HttpMessage, HttpRequest, HttpResponse};
 use jsonwebtoken::{encode, decode, Header, Validation, EncodingKey, DecodingKey};
-use actix_web::HttpRequest;
-use crate::models::Claims;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::models::{Claims, Role};
+use crate::AppState;
+
+/// Actions gated by role. Ownership rules (authors may only touch their
+/// own posts) are checked in the handlers on top of these.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    ManageUsers,
+    ViewAuditLog,
+    ViewAnalytics,
+    WritePosts,
+    EditAnyPost,
+    ViewDrafts,
+    ModerateComments,
+}
+
+impl Role {
+    pub fn can(self, permission: Permission) -> bool {
+        match self {
+            Role::Admin => true,
+            Role::Editor => !matches!(
+                permission,
+                Permission::ManageUsers | Permission::ViewAuditLog | Permission::ViewAnalytics
+            ),
+            Role::Author => permission == Permission::WritePosts,
+        }
+    }
+}
+
+/// Lifetimes for the two halves of a login: short-lived JWT access tokens,
+/// and long-lived refresh tokens that are stored server-side and can be
+/// revoked.
+#[derive(Debug, Clone)]
+pub struct TokenConfig {
+    pub access_ttl: chrono::Duration,
+    pub refresh_ttl: chrono::Duration,
+}
+
+impl TokenConfig {
+    pub fn from_env() -> Self {
+        let number = |key: &str, default: i64| {
+            env::var(key).ok().and_then(|s| s.parse().ok()).unwrap_or(default)
+        };
+
+        Self {
+            access_ttl: chrono::Duration::minutes(number("ACCESS_TOKEN_MINUTES", 15)),
+            refresh_ttl: chrono::Duration::days(number("REFRESH_TOKEN_DAYS", 30)),
+        }
+    }
+}
 
-pub fn create_token(user_id: &str, username: &str, is_admin: bool, secret: &str) -> Result<String, jsonwebtoken::errors::Error> {
+pub fn create_token(
+    user_id: &str,
+    username: &str,
+    role: Role,
+    session_id: &str,
+    ttl: chrono::Duration,
+    secret: &str,
+) -> Result<String, jsonwebtoken::errors::Error> {
     let expiration = chrono::Utc::now()
-        .checked_add_signed(chrono::Duration::hours(24))
+        .checked_add_signed(ttl)
         .expect("valid timestamp")
         .timestamp() as usize;
 
     let claims = Claims {
         sub: user_id.to_string(),
         username: username.to_string(),
-        is_admin,
+        role,
+        sid: session_id.to_string(),
         exp: expiration,
     };
 
@@ -32,20 +99,148 @@ pub fn verify_token(token: &str, secret: &str) -> Result<Claims, jsonwebtoken::e
     Ok(token_data.claims)
 }
 
+/// A new opaque refresh token: 244 random bits from two v4 UUIDs.
+pub fn generate_refresh_token() -> String {
+    format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple())
+}
+
+pub fn hash_refresh_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
 pub fn extract_claims(req: &HttpRequest, secret: &str) -> Option<Claims> {
     let auth_header = req.headers().get("Authorization")?;
     let auth_str = auth_header.to_str().ok()?;
-    
-    if !auth_str.starts_with("Bearer ") {
-        return None;
-    }
 
-    let token = &auth_str[7..];
+    let token = auth_str.strip_prefix("Bearer ")?;
     verify_token(token, secret).ok()
 }
 
-pub fn verify_admin(req: &HttpRequest, secret: &str) -> bool {
+/// For routes that are public but show more to privileged users.
+pub fn has_permission(req: &HttpRequest, secret: &str, permission: Permission) -> bool {
     extract_claims(req, secret)
-        .map(|claims| claims.is_admin)
+        .map(|claims| claims.role.can(permission))
         .unwrap_or(false)
 }
+
+/// Route middleware that rejects requests without a valid token (401) or
+/// whose role lacks `permission` (403). On success the decoded [`Claims`]
+/// are stored in the request extensions for `web::ReqData<Claims>`.
+pub fn require(permission: Permission) -> RequirePermission {
+    RequirePermission { permission: Some(permission) }
+}
+
+/// Like [`require`], but any valid token is enough.
+pub fn authenticated() -> RequirePermission {
+    RequirePermission { permission: None }
+}
+
+pub struct RequirePermission {
+    permission: Option<Permission>,
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequirePermission
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = RequirePermissionMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequirePermissionMiddleware {
+            service: Rc::new(service),
+            permission: self.permission,
+        }))
+    }
+}
+
+pub struct RequirePermissionMiddleware<S> {
+    service: Rc<S>,
+    permission: Option<Permission>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequirePermissionMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    actix_web::dev::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let claims = req
+            .app_data::<web::Data<AppState>>()
+            .and_then(|state| extract_claims(req.request(), &state.jwt_secret));
+
+        let denied = match &claims {
+            None => Some(HttpResponse::Unauthorized().json(json!({"error": "Authentication required"}))),
+            Some(claims) if self.permission.is_some_and(|p| !claims.role.can(p)) => {
+                Some(HttpResponse::Forbidden().json(json!({"error": "Insufficient permissions"})))
+            }
+            Some(_) => None,
+        };
+
+        if let Some(response) = denied {
+            return Box::pin(async move { Ok(req.into_response(response).map_into_right_body()) });
+        }
+
+        if let Some(claims) = claims {
+            req.extensions_mut().insert(claims);
+        }
+
+        let service = Rc::clone(&self.service);
+        Box::pin(async move {
+            let res = service.call(req).await?;
+            Ok(res.map_into_left_body())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_role_permissions() {
+        assert!(Role::Admin.can(Permission::ManageUsers));
+        assert!(!Role::Editor.can(Permission::ManageUsers));
+        assert!(!Role::Editor.can(Permission::ViewAuditLog));
+        assert!(!Role::Editor.can(Permission::ViewAnalytics));
+        assert!(Role::Editor.can(Permission::EditAnyPost));
+        assert!(Role::Editor.can(Permission::ModerateComments));
+        assert!(Role::Author.can(Permission::WritePosts));
+        assert!(!Role::Author.can(Permission::EditAnyPost));
+        assert!(!Role::Author.can(Permission::ViewDrafts));
+    }
+
+    #[test]
+    fn test_token_round_trips_role() {
+        let token = create_token("42", "ed", Role::Editor, "s1", chrono::Duration::minutes(5), "secret").unwrap();
+        let claims = verify_token(&token, "secret").unwrap();
+        assert_eq!(claims.sub, "42");
+        assert_eq!(claims.role, Role::Editor);
+        assert_eq!(claims.sid, "s1");
+        assert!(verify_token(&token, "other").is_err());
+
+        let expired = create_token("42", "ed", Role::Editor, "s1", chrono::Duration::minutes(-5), "secret").unwrap();
+        assert!(verify_token(&expired, "secret").is_err());
+    }
+
+    #[test]
+    fn test_refresh_tokens_are_random_and_hashed() {
+        let token = generate_refresh_token();
+        assert_eq!(token.len(), 64);
+        assert_ne!(token, generate_refresh_token());
+
+        let hash = hash_refresh_token(&token);
+        assert_eq!(hash, hash_refresh_token(&token));
+        assert_ne!(hash, token);
+    }
+}
@@ -1,69 +1,99 @@
-use sqlx::{SqlitePool, Row};
-use crate::models::{User, Post, Comment};
+use sqlx::any::AnyRow;
+use sqlx::migrate::Migrator;
+use sqlx::{AnyPool, Row};
+use crate::models::{User, Post, Comment, Role, AuditEntry, Session};
+use crate::analytics::{DailyPostViews, PageView};
+use crate::audit::AuditFilter;
 use std::error::Error;
 
+static SQLITE_MIGRATIONS: Migrator = sqlx::migrate!("./migrations/sqlite");
+static POSTGRES_MIGRATIONS: Migrator = sqlx::migrate!("./migrations/postgres");
+
+// The `Any` driver cannot decode SQLite's BOOLEAN columns, so boolean flags
+// are read back as integers on both backends.
+const POST_COLUMNS: &str = "id, title, slug, content, summary, author_id, \
+    CAST(published AS INTEGER) AS published, created_at, updated_at";
+const COMMENT_COLUMNS: &str = "id, post_id, author_name, author_email, content, \
+    CAST(approved AS INTEGER) AS approved, created_at";
+
+/// The database server behind `DATABASE_URL`. Queries are written once with
+/// `$1`-style placeholders, which both SQLite and Postgres accept; only the
+/// schema migrations differ.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Sqlite,
+    Postgres,
+}
+
+impl Backend {
+    pub fn from_url(database_url: &str) -> Option<Self> {
+        let scheme = database_url.split(':').next()?;
+        match scheme {
+            "sqlite" => Some(Backend::Sqlite),
+            "postgres" | "postgresql" => Some(Backend::Postgres),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Database {
-    pool: SqlitePool,
+    pool: AnyPool,
+    backend: Backend,
 }
 
 impl Database {
     pub async fn new(database_url: &str) -> Result<Self, Box<dyn Error>> {
-        let pool = SqlitePool::connect(database_url).await?;
-        Ok(Self { pool })
+        let backend = Backend::from_url(database_url)
+            .ok_or_else(|| format!("unsupported DATABASE_URL scheme: {}", database_url))?;
+        sqlx::any::install_default_drivers();
+        let pool = AnyPool::connect(database_url).await?;
+        Ok(Self { pool, backend })
+    }
+
+    pub fn backend(&self) -> Backend {
+        self.backend
     }
 
+    /// Brings the schema up to date with the migrations for this backend.
     pub async fn init(&self) -> Result<(), Box<dyn Error>> {
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS users (
-                id TEXT PRIMARY KEY,
-                username TEXT UNIQUE NOT NULL,
-                email TEXT UNIQUE NOT NULL,
-                password_hash TEXT NOT NULL,
-                is_admin BOOLEAN NOT NULL DEFAULT 0,
-                created_at TEXT NOT NULL
-            )
-            "#
-        )
-        .execute(&self.pool)
-        .await?;
+        match self.backend {
+            Backend::Sqlite => {
+                SQLITE_MIGRATIONS.run(&self.pool).await?;
+                self.migrate_user_roles().await?;
+            }
+            Backend::Postgres => POSTGRES_MIGRATIONS.run(&self.pool).await?,
+        }
+        Ok(())
+    }
 
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS posts (
-                id TEXT PRIMARY KEY,
-                title TEXT NOT NULL,
-                slug TEXT UNIQUE NOT NULL,
-                content TEXT NOT NULL,
-                summary TEXT NOT NULL,
-                author_id TEXT NOT NULL,
-                published BOOLEAN NOT NULL DEFAULT 0,
-                created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL,
-                FOREIGN KEY (author_id) REFERENCES users(id)
-            )
-            "#
-        )
-        .execute(&self.pool)
-        .await?;
+    /// Databases created before roles existed only have `is_admin`; add the
+    /// `role` column, carry existing admins over, and drop `is_admin`.
+    async fn migrate_user_roles(&self) -> Result<(), Box<dyn Error>> {
+        let columns: Vec<String> = sqlx::query("SELECT name FROM pragma_table_info('users')")
+            .fetch_all(&self.pool)
+            .await?
+            .iter()
+            .map(|row| row.get("name"))
+            .collect();
 
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS comments (
-                id TEXT PRIMARY KEY,
-                post_id TEXT NOT NULL,
-                author_name TEXT NOT NULL,
-                author_email TEXT NOT NULL,
-                content TEXT NOT NULL,
-                approved BOOLEAN NOT NULL DEFAULT 0,
-                created_at TEXT NOT NULL,
-                FOREIGN KEY (post_id) REFERENCES posts(id)
-            )
-            "#
-        )
-        .execute(&self.pool)
-        .await?;
+        if !columns.iter().any(|c| c == "role") {
+            sqlx::query("ALTER TABLE users ADD COLUMN role TEXT NOT NULL DEFAULT 'author'")
+                .execute(&self.pool)
+                .await?;
+            if columns.iter().any(|c| c == "is_admin") {
+                sqlx::query("UPDATE users SET role = 'admin' WHERE is_admin = 1")
+                    .execute(&self.pool)
+                    .await?;
+            }
+        }
+
+        // The `Any` driver cannot decode the old BOOLEAN column at all
+        if columns.iter().any(|c| c == "is_admin") {
+            sqlx::query("ALTER TABLE users DROP COLUMN is_admin")
+                .execute(&self.pool)
+                .await?;
+        }
 
         Ok(())
     }
@@ -71,63 +101,72 @@ impl Database {
     // User operations
     pub async fn create_user(&self, user: &User) -> Result<(), Box<dyn Error>> {
         sqlx::query(
-            "INSERT INTO users (id, username, email, password_hash, is_admin, created_at) VALUES (?, ?, ?, ?, ?, ?)"
+            "INSERT INTO users (id, username, email, password_hash, role, created_at) VALUES ($1, $2, $3, $4, $5, $6)"
         )
         .bind(&user.id)
         .bind(&user.username)
         .bind(&user.email)
         .bind(&user.password_hash)
-        .bind(user.is_admin)
+        .bind(user.role.as_str())
         .bind(&user.created_at)
         .execute(&self.pool)
         .await?;
         Ok(())
     }
 
+    pub async fn count_users(&self) -> Result<i64, Box<dyn Error>> {
+        let row = sqlx::query("SELECT COUNT(*) AS count FROM users")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(row.get("count"))
+    }
+
+    pub async fn get_all_users(&self) -> Result<Vec<User>, Box<dyn Error>> {
+        let rows = sqlx::query("SELECT * FROM users ORDER BY created_at ASC")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.iter().map(user_from_row).collect())
+    }
+
+    pub async fn get_user_by_id(&self, id: &str) -> Result<Option<User>, Box<dyn Error>> {
+        let row = sqlx::query("SELECT * FROM users WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.as_ref().map(user_from_row))
+    }
+
+    pub async fn update_user_role(&self, id: &str, role: Role) -> Result<(), Box<dyn Error>> {
+        sqlx::query("UPDATE users SET role = $1 WHERE id = $2")
+            .bind(role.as_str())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
     pub async fn get_user_by_username(&self, username: &str) -> Result<Option<User>, Box<dyn Error>> {
-        let row = sqlx::query("SELECT * FROM users WHERE username = ?")
+        let row = sqlx::query("SELECT * FROM users WHERE username = $1")
             .bind(username)
             .fetch_optional(&self.pool)
             .await?;
 
-        if let Some(row) = row {
-            Ok(Some(User {
-                id: row.get("id"),
-                username: row.get("username"),
-                email: row.get("email"),
-                password_hash: row.get("password_hash"),
-                is_admin: row.get("is_admin"),
-                created_at: row.get("created_at"),
-            }))
-        } else {
-            Ok(None)
-        }
+        Ok(row.as_ref().map(user_from_row))
     }
 
     pub async fn get_user_by_email(&self, email: &str) -> Result<Option<User>, Box<dyn Error>> {
-        let row = sqlx::query("SELECT * FROM users WHERE email = ?")
+        let row = sqlx::query("SELECT * FROM users WHERE email = $1")
             .bind(email)
             .fetch_optional(&self.pool)
             .await?;
 
-        if let Some(row) = row {
-            Ok(Some(User {
-                id: row.get("id"),
-                username: row.get("username"),
-                email: row.get("email"),
-                password_hash: row.get("password_hash"),
-                is_admin: row.get("is_admin"),
-                created_at: row.get("created_at"),
-            }))
-        } else {
-            Ok(None)
-        }
+        Ok(row.as_ref().map(user_from_row))
     }
 
     // Post operations
     pub async fn create_post(&self, post: &Post) -> Result<(), Box<dyn Error>> {
         sqlx::query(
-            "INSERT INTO posts (id, title, slug, content, summary, author_id, published, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+            "INSERT INTO posts (id, title, slug, content, summary, author_id, published, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"
         )
         .bind(&post.id)
         .bind(&post.title)
@@ -144,50 +183,32 @@ impl Database {
     }
 
     pub async fn get_post_by_slug(&self, slug: &str) -> Result<Option<Post>, Box<dyn Error>> {
-        let row = sqlx::query("SELECT * FROM posts WHERE slug = ?")
+        let row = sqlx::query(&format!("SELECT {} FROM posts WHERE slug = $1", POST_COLUMNS))
             .bind(slug)
             .fetch_optional(&self.pool)
             .await?;
 
-        if let Some(row) = row {
-            Ok(Some(Post {
-                id: row.get("id"),
-                title: row.get("title"),
-                slug: row.get("slug"),
-                content: row.get("content"),
-                summary: row.get("summary"),
-                author_id: row.get("author_id"),
-                published: row.get("published"),
-                created_at: row.get("created_at"),
-                updated_at: row.get("updated_at"),
-            }))
-        } else {
-            Ok(None)
-        }
+        Ok(row.as_ref().map(post_from_row))
+    }
+
+    pub async fn get_post_by_id(&self, id: &str) -> Result<Option<Post>, Box<dyn Error>> {
+        let row = sqlx::query(&format!("SELECT {} FROM posts WHERE id = $1", POST_COLUMNS))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.as_ref().map(post_from_row))
     }
 
     pub async fn get_all_posts(&self, published_only: bool) -> Result<Vec<Post>, Box<dyn Error>> {
-        let query = if published_only {
-            "SELECT * FROM posts WHERE published = 1 ORDER BY created_at DESC"
-        } else {
-            "SELECT * FROM posts ORDER BY created_at DESC"
-        };
+        let filter = if published_only { " WHERE published = TRUE" } else { "" };
+        let query = format!("SELECT {} FROM posts{} ORDER BY created_at DESC", POST_COLUMNS, filter);
 
-        let rows = sqlx::query(query).fetch_all(&self.pool).await?;
+        let rows = sqlx::query(&query).fetch_all(&self.pool).await?;
 
         let posts = rows
             .iter()
-            .map(|row| Post {
-                id: row.get("id"),
-                title: row.get("title"),
-                slug: row.get("slug"),
-                content: row.get("content"),
-                summary: row.get("summary"),
-                author_id: row.get("author_id"),
-                published: row.get("published"),
-                created_at: row.get("created_at"),
-                updated_at: row.get("updated_at"),
-            })
+            .map(post_from_row)
             .collect();
 
         Ok(posts)
@@ -195,7 +216,7 @@ impl Database {
 
     pub async fn update_post(&self, post: &Post) -> Result<(), Box<dyn Error>> {
         sqlx::query(
-            "UPDATE posts SET title = ?, content = ?, summary = ?, published = ?, updated_at = ? WHERE id = ?"
+            "UPDATE posts SET title = $1, content = $2, summary = $3, published = $4, updated_at = $5 WHERE id = $6"
         )
         .bind(&post.title)
         .bind(&post.content)
@@ -209,7 +230,7 @@ impl Database {
     }
 
     pub async fn delete_post(&self, id: &str) -> Result<(), Box<dyn Error>> {
-        sqlx::query("DELETE FROM posts WHERE id = ?")
+        sqlx::query("DELETE FROM posts WHERE id = $1")
             .bind(id)
             .execute(&self.pool)
             .await?;
@@ -219,7 +240,7 @@ impl Database {
     // Comment operations
     pub async fn create_comment(&self, comment: &Comment) -> Result<(), Box<dyn Error>> {
         sqlx::query(
-            "INSERT INTO comments (id, post_id, author_name, author_email, content, approved, created_at) VALUES (?, ?, ?, ?, ?, ?, ?)"
+            "INSERT INTO comments (id, post_id, author_name, author_email, content, approved, created_at) VALUES ($1, $2, $3, $4, $5, $6, $7)"
         )
         .bind(&comment.id)
         .bind(&comment.post_id)
@@ -234,35 +255,36 @@ impl Database {
     }
 
     pub async fn get_comments_by_post(&self, post_id: &str, approved_only: bool) -> Result<Vec<Comment>, Box<dyn Error>> {
-        let query = if approved_only {
-            "SELECT * FROM comments WHERE post_id = ? AND approved = 1 ORDER BY created_at ASC"
-        } else {
-            "SELECT * FROM comments WHERE post_id = ? ORDER BY created_at ASC"
-        };
+        let filter = if approved_only { " AND approved = TRUE" } else { "" };
+        let query = format!(
+            "SELECT {} FROM comments WHERE post_id = $1{} ORDER BY created_at ASC",
+            COMMENT_COLUMNS, filter
+        );
 
-        let rows = sqlx::query(query)
+        let rows = sqlx::query(&query)
             .bind(post_id)
             .fetch_all(&self.pool)
             .await?;
 
         let comments = rows
             .iter()
-            .map(|row| Comment {
-                id: row.get("id"),
-                post_id: row.get("post_id"),
-                author_name: row.get("author_name"),
-                author_email: row.get("author_email"),
-                content: row.get("content"),
-                approved: row.get("approved"),
-                created_at: row.get("created_at"),
-            })
+            .map(comment_from_row)
             .collect();
 
         Ok(comments)
     }
 
+    pub async fn get_comment_by_id(&self, id: &str) -> Result<Option<Comment>, Box<dyn Error>> {
+        let row = sqlx::query(&format!("SELECT {} FROM comments WHERE id = $1", COMMENT_COLUMNS))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.as_ref().map(comment_from_row))
+    }
+
     pub async fn approve_comment(&self, id: &str) -> Result<(), Box<dyn Error>> {
-        sqlx::query("UPDATE comments SET approved = 1 WHERE id = ?")
+        sqlx::query("UPDATE comments SET approved = TRUE WHERE id = $1")
             .bind(id)
             .execute(&self.pool)
             .await?;
@@ -270,10 +292,262 @@ impl Database {
     }
 
     pub async fn delete_comment(&self, id: &str) -> Result<(), Box<dyn Error>> {
-        sqlx::query("DELETE FROM comments WHERE id = ?")
+        sqlx::query("DELETE FROM comments WHERE id = $1")
             .bind(id)
             .execute(&self.pool)
             .await?;
         Ok(())
     }
+
+    // Session operations
+    pub async fn create_session(&self, session: &Session) -> Result<(), Box<dyn Error>> {
+        sqlx::query(
+            "INSERT INTO sessions (id, user_id, token_hash, created_at, expires_at, revoked_at, ip, user_agent) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"
+        )
+        .bind(&session.id)
+        .bind(&session.user_id)
+        .bind(&session.token_hash)
+        .bind(&session.created_at)
+        .bind(&session.expires_at)
+        .bind(&session.revoked_at)
+        .bind(&session.ip)
+        .bind(&session.user_agent)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get_session_by_token_hash(&self, token_hash: &str) -> Result<Option<Session>, Box<dyn Error>> {
+        let row = sqlx::query("SELECT * FROM sessions WHERE token_hash = $1")
+            .bind(token_hash)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.as_ref().map(session_from_row))
+    }
+
+    pub async fn get_active_sessions(&self, user_id: &str, now: &str) -> Result<Vec<Session>, Box<dyn Error>> {
+        let rows = sqlx::query(
+            "SELECT * FROM sessions WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > $2 ORDER BY created_at DESC"
+        )
+        .bind(user_id)
+        .bind(now)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.iter().map(session_from_row).collect())
+    }
+
+    /// Revokes one session; returns false if it was already revoked.
+    pub async fn revoke_session(&self, id: &str, now: &str) -> Result<bool, Box<dyn Error>> {
+        let result = sqlx::query("UPDATE sessions SET revoked_at = $1 WHERE id = $2 AND revoked_at IS NULL")
+            .bind(now)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn revoke_user_sessions(&self, user_id: &str, now: &str) -> Result<u64, Box<dyn Error>> {
+        let result = sqlx::query("UPDATE sessions SET revoked_at = $1 WHERE user_id = $2 AND revoked_at IS NULL")
+            .bind(now)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    pub async fn delete_expired_sessions(&self, now: &str) -> Result<(), Box<dyn Error>> {
+        sqlx::query("DELETE FROM sessions WHERE expires_at <= $1")
+            .bind(now)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    // Page views
+    pub async fn record_page_view(&self, view: &PageView) -> Result<(), Box<dyn Error>> {
+        sqlx::query(
+            "INSERT INTO page_views (id, path, referrer, ip_hash, created_at) VALUES ($1, $2, $3, $4, $5)"
+        )
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(&view.path)
+        .bind(&view.referrer)
+        .bind(&view.ip_hash)
+        .bind(&view.created_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Views of each post's page per UTC day, from `since` (`YYYY-MM-DD`) on.
+    pub async fn get_daily_post_views(&self, since: &str) -> Result<Vec<DailyPostViews>, Box<dyn Error>> {
+        let rows = sqlx::query(
+            "SELECT p.id AS post_id, p.title AS title, p.slug AS slug, SUBSTR(v.created_at, 1, 10) AS day, COUNT(*) AS views \
+             FROM page_views v JOIN posts p ON v.path = '/post/' || p.slug \
+             WHERE v.created_at >= $1 \
+             GROUP BY p.id, p.title, p.slug, SUBSTR(v.created_at, 1, 10) \
+             ORDER BY p.slug, day"
+        )
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| DailyPostViews {
+                post_id: row.get("post_id"),
+                title: row.get("title"),
+                slug: row.get("slug"),
+                day: row.get("day"),
+                views: row.get("views"),
+            })
+            .collect())
+    }
+
+    // Audit log
+    pub async fn record_audit(&self, entry: &AuditEntry) -> Result<(), Box<dyn Error>> {
+        sqlx::query(
+            "INSERT INTO audit_log (id, actor_id, actor_name, action, target_type, target_id, ip, before_snapshot, after_snapshot, created_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)"
+        )
+        .bind(&entry.id)
+        .bind(&entry.actor_id)
+        .bind(&entry.actor_name)
+        .bind(&entry.action)
+        .bind(&entry.target_type)
+        .bind(&entry.target_id)
+        .bind(&entry.ip)
+        .bind(entry.before.as_ref().map(|v| v.to_string()))
+        .bind(entry.after.as_ref().map(|v| v.to_string()))
+        .bind(&entry.created_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// One page of audit entries, newest first, plus the total number of
+    /// entries matching the filter. An action filter without a dot matches
+    /// every action on that target type (`post` matches `post.delete`).
+    pub async fn get_audit_entries(&self, filter: &AuditFilter) -> Result<(Vec<AuditEntry>, i64), Box<dyn Error>> {
+        let mut conditions = Vec::new();
+        if filter.actor.is_some() {
+            conditions.push(format!("actor_name = ${}", conditions.len() + 1));
+        }
+        if filter.action.is_some() {
+            let n = conditions.len() + 1;
+            conditions.push(format!("(action = ${} OR target_type = ${})", n, n));
+        }
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!(" WHERE {}", conditions.join(" AND "))
+        };
+
+        let count_sql = format!("SELECT COUNT(*) AS count FROM audit_log{}", where_clause);
+        let page_sql = format!(
+            "SELECT * FROM audit_log{} ORDER BY created_at DESC, id DESC LIMIT ${} OFFSET ${}",
+            where_clause,
+            conditions.len() + 1,
+            conditions.len() + 2
+        );
+
+        let mut count_query = sqlx::query(&count_sql);
+        let mut page_query = sqlx::query(&page_sql);
+        if let Some(actor) = &filter.actor {
+            count_query = count_query.bind(actor);
+            page_query = page_query.bind(actor);
+        }
+        if let Some(action) = &filter.action {
+            count_query = count_query.bind(action);
+            page_query = page_query.bind(action);
+        }
+
+        let total: i64 = count_query.fetch_one(&self.pool).await?.get("count");
+        let rows = page_query
+            .bind(filter.per_page as i64)
+            .bind(filter.offset() as i64)
+            .fetch_all(&self.pool)
+            .await?;
+
+        let snapshot = |value: Option<String>| value.and_then(|v| serde_json::from_str(&v).ok());
+        let entries = rows
+            .iter()
+            .map(|row| AuditEntry {
+                id: row.get("id"),
+                actor_id: row.get("actor_id"),
+                actor_name: row.get("actor_name"),
+                action: row.get("action"),
+                target_type: row.get("target_type"),
+                target_id: row.get("target_id"),
+                ip: row.get("ip"),
+                before: snapshot(row.get("before_snapshot")),
+                after: snapshot(row.get("after_snapshot")),
+                created_at: row.get("created_at"),
+            })
+            .collect();
+
+        Ok((entries, total))
+    }
+}
+
+fn user_from_row(row: &AnyRow) -> User {
+    let role: String = row.get("role");
+    User {
+        id: row.get("id"),
+        username: row.get("username"),
+        email: row.get("email"),
+        password_hash: row.get("password_hash"),
+        role: Role::parse(&role).unwrap_or(Role::Author),
+        created_at: row.get("created_at"),
+    }
+}
+
+fn post_from_row(row: &AnyRow) -> Post {
+    Post {
+        id: row.get("id"),
+        title: row.get("title"),
+        slug: row.get("slug"),
+        content: row.get("content"),
+        summary: row.get("summary"),
+        author_id: row.get("author_id"),
+        published: row.get::<i64, _>("published") != 0,
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+fn comment_from_row(row: &AnyRow) -> Comment {
+    Comment {
+        id: row.get("id"),
+        post_id: row.get("post_id"),
+        author_name: row.get("author_name"),
+        author_email: row.get("author_email"),
+        content: row.get("content"),
+        approved: row.get::<i64, _>("approved") != 0,
+        created_at: row.get("created_at"),
+    }
+}
+
+fn session_from_row(row: &AnyRow) -> Session {
+    Session {
+        id: row.get("id"),
+        user_id: row.get("user_id"),
+        token_hash: row.get("token_hash"),
+        created_at: row.get("created_at"),
+        expires_at: row.get("expires_at"),
+        revoked_at: row.get("revoked_at"),
+        ip: row.get("ip"),
+        user_agent: row.get("user_agent"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backend_from_url() {
+        assert_eq!(Backend::from_url("sqlite://blog.db"), Some(Backend::Sqlite));
+        assert_eq!(Backend::from_url("postgres://blog@localhost/blog"), Some(Backend::Postgres));
+        assert_eq!(Backend::from_url("postgresql://localhost/blog"), Some(Backend::Postgres));
+        assert_eq!(Backend::from_url("mysql://localhost/blog"), None);
+    }
 }
@@ -0,0 +1,242 @@
+use std::env;
+
+use chrono::{DateTime, FixedOffset};
+
+use crate::models::Post;
+
+#[derive(Debug, Clone)]
+pub struct SiteConfig {
+    pub title: String,
+    pub description: String,
+    pub base_url: String,
+    pub author: String,
+    pub cache_seconds: u32,
+}
+
+impl SiteConfig {
+    pub fn from_env() -> Self {
+        let base_url = env::var("SITE_URL").unwrap_or_else(|_| "http://127.0.0.1:8080".to_string());
+
+        Self {
+            title: env::var("SITE_TITLE").unwrap_or_else(|_| "Blog Engine".to_string()),
+            description: env::var("SITE_DESCRIPTION")
+                .unwrap_or_else(|_| "A blog built with Rust and Actix-web".to_string()),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            author: env::var("SITE_AUTHOR").unwrap_or_else(|_| "Blog Engine".to_string()),
+            cache_seconds: env::var("FEED_CACHE_SECONDS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(3600),
+        }
+    }
+
+    fn post_url(&self, post: &Post) -> String {
+        format!("{}/post/{}", self.base_url, post.slug)
+    }
+}
+
+/// Newest `updated_at` across the posts, used for feed timestamps and
+/// the Last-Modified header.
+pub fn last_updated(posts: &[Post]) -> Option<DateTime<FixedOffset>> {
+    posts.iter().filter_map(|post| parse_date(&post.updated_at)).max()
+}
+
+pub fn render_rss(site: &SiteConfig, posts: &[Post]) -> String {
+    let mut items = String::new();
+    for post in posts {
+        let url = site.post_url(post);
+        let pub_date = parse_date(&post.created_at)
+            .map(|date| date.to_rfc2822())
+            .unwrap_or_default();
+
+        items.push_str(&format!(
+            r#"    <item>
+      <title>{title}</title>
+      <link>{url}</link>
+      <guid isPermaLink="true">{url}</guid>
+      <description>{summary}</description>
+      <pubDate>{pub_date}</pubDate>
+    </item>
+"#,
+            title = escape_xml(&post.title),
+            url = escape_xml(&url),
+            summary = escape_xml(&post.summary),
+            pub_date = pub_date,
+        ));
+    }
+
+    let build_date = last_updated(posts)
+        .map(|date| format!("\n    <lastBuildDate>{}</lastBuildDate>", date.to_rfc2822()))
+        .unwrap_or_default();
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom">
+  <channel>
+    <title>{title}</title>
+    <link>{base_url}/</link>
+    <description>{description}</description>
+    <atom:link href="{base_url}/feed.xml" rel="self" type="application/rss+xml"/>{build_date}
+{items}  </channel>
+</rss>
+"#,
+        title = escape_xml(&site.title),
+        base_url = escape_xml(&site.base_url),
+        description = escape_xml(&site.description),
+        build_date = build_date,
+        items = items,
+    )
+}
+
+pub fn render_atom(site: &SiteConfig, posts: &[Post]) -> String {
+    let mut entries = String::new();
+    for post in posts {
+        let url = escape_xml(&site.post_url(post));
+        entries.push_str(&format!(
+            r#"  <entry>
+    <title>{title}</title>
+    <link href="{url}"/>
+    <id>{url}</id>
+    <published>{published}</published>
+    <updated>{updated}</updated>
+    <summary>{summary}</summary>
+  </entry>
+"#,
+            title = escape_xml(&post.title),
+            url = url,
+            published = escape_xml(&post.created_at),
+            updated = escape_xml(&post.updated_at),
+            summary = escape_xml(&post.summary),
+        ));
+    }
+
+    // Atom requires a feed-level <updated>, even for an empty blog
+    let updated = last_updated(posts)
+        .map(|date| date.to_rfc3339())
+        .unwrap_or_else(|| "1970-01-01T00:00:00+00:00".to_string());
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>{title}</title>
+  <subtitle>{description}</subtitle>
+  <link href="{base_url}/"/>
+  <link href="{base_url}/atom.xml" rel="self" type="application/atom+xml"/>
+  <id>{base_url}/</id>
+  <updated>{updated}</updated>
+  <author><name>{author}</name></author>
+{entries}</feed>
+"#,
+        title = escape_xml(&site.title),
+        description = escape_xml(&site.description),
+        base_url = escape_xml(&site.base_url),
+        updated = updated,
+        author = escape_xml(&site.author),
+        entries = entries,
+    )
+}
+
+pub fn render_sitemap(site: &SiteConfig, posts: &[Post]) -> String {
+    let mut urls = String::new();
+
+    let home_lastmod = last_updated(posts)
+        .map(|date| format!("\n    <lastmod>{}</lastmod>", date.format("%Y-%m-%d")))
+        .unwrap_or_default();
+    urls.push_str(&format!(
+        "  <url>\n    <loc>{}/</loc>{}\n  </url>\n",
+        escape_xml(&site.base_url),
+        home_lastmod
+    ));
+
+    for post in posts {
+        let lastmod = parse_date(&post.updated_at)
+            .map(|date| format!("\n    <lastmod>{}</lastmod>", date.format("%Y-%m-%d")))
+            .unwrap_or_default();
+        urls.push_str(&format!(
+            "  <url>\n    <loc>{}</loc>{}\n  </url>\n",
+            escape_xml(&site.post_url(post)),
+            lastmod
+        ));
+    }
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n{}</urlset>\n",
+        urls
+    )
+}
+
+fn parse_date(value: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(value).ok()
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn site() -> SiteConfig {
+        SiteConfig {
+            title: "Rust & Friends".to_string(),
+            description: "Notes".to_string(),
+            base_url: "https://blog.example.com".to_string(),
+            author: "Admin".to_string(),
+            cache_seconds: 60,
+        }
+    }
+
+    fn post(slug: &str, updated_at: &str) -> Post {
+        Post {
+            id: slug.to_string(),
+            title: format!("<{}>", slug),
+            slug: slug.to_string(),
+            content: "# Body".to_string(),
+            summary: "A summary".to_string(),
+            author_id: "1".to_string(),
+            published: true,
+            created_at: "2024-01-01T12:00:00+00:00".to_string(),
+            updated_at: updated_at.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_rss_escapes_and_links_posts() {
+        let rss = render_rss(&site(), &[post("first", "2024-01-02T08:00:00+00:00")]);
+        assert!(rss.contains("<title>Rust &amp; Friends</title>"));
+        assert!(rss.contains("<title>&lt;first&gt;</title>"));
+        assert!(rss.contains("<link>https://blog.example.com/post/first</link>"));
+        assert!(rss.contains("<pubDate>Mon, 1 Jan 2024 12:00:00 +0000</pubDate>"));
+    }
+
+    #[test]
+    fn test_atom_uses_newest_update() {
+        let posts = [
+            post("old", "2024-01-02T08:00:00+00:00"),
+            post("new", "2024-03-05T08:00:00+00:00"),
+        ];
+        let atom = render_atom(&site(), &posts);
+        assert!(atom.contains("<updated>2024-03-05T08:00:00+00:00</updated>"));
+        assert_eq!(atom.matches("<entry>").count(), 2);
+    }
+
+    #[test]
+    fn test_sitemap_lists_home_and_posts() {
+        let sitemap = render_sitemap(&site(), &[post("first", "2024-01-02T08:00:00+00:00")]);
+        assert!(sitemap.contains("<loc>https://blog.example.com/</loc>"));
+        assert!(sitemap.contains("<loc>https://blog.example.com/post/first</loc>"));
+        assert!(sitemap.contains("<lastmod>2024-01-02</lastmod>"));
+    }
+}
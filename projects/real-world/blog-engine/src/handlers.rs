@@ -1,35 +1,79 @@
-use actix_web::{web, HttpResponse, HttpRequest};
+use actix_web::{middleware, web, HttpResponse, HttpRequest};
 use actix_web::http::header;
 use serde_json::json;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use validator::Validate;
 
-use crate::{AppState, models::*, auth, utils};
+use crate::{AppState, models::*, analytics, audit, auth, feed, openapi, utils};
+use crate::audit::{AuditAction, AuditEvent};
+use crate::auth::Permission;
+use crate::themes::Templates;
 
 pub fn config(cfg: &mut web::ServiceConfig) {
-    cfg.service(
-        web::scope("/api")
-            .route("/register", web::post().to(register))
-            .route("/login", web::post().to(login))
-            .route("/posts", web::get().to(get_posts))
-            .route("/posts", web::post().to(create_post))
-            .route("/posts/{slug}", web::get().to(get_post))
-            .route("/posts/{id}", web::put().to(update_post))
-            .route("/posts/{id}", web::delete().to(delete_post))
-            .route("/posts/{slug}/comments", web::get().to(get_comments))
-            .route("/posts/{slug}/comments", web::post().to(create_comment))
-            .route("/comments/{id}/approve", web::post().to(approve_comment))
-            .route("/comments/{id}", web::delete().to(delete_comment))
-    )
-    .service(
-        web::scope("")
-            .route("/", web::get().to(index))
-            .route("/post/{slug}", web::get().to(view_post))
-            .route("/admin", web::get().to(admin_panel))
-    );
+    // Registered before the `/api` scope, which would otherwise claim it
+    cfg.route("/api/docs", web::get().to(openapi::docs))
+        .service(web::scope("/api/v1").configure(api_v1))
+        .service(
+            // Unversioned paths from before v1, kept for existing clients
+            web::scope("/api")
+                .wrap(
+                    middleware::DefaultHeaders::new()
+                        .add(("Deprecation", "true"))
+                        .add((header::LINK, "</api/v1>; rel=\"successor-version\"")),
+                )
+                .configure(api_v1),
+        )
+        .service(
+            web::scope("")
+                .wrap(analytics::track_views())
+                .route("/", web::get().to(index))
+                .route("/post/{slug}", web::get().to(view_post))
+                .route("/admin", web::get().to(admin_panel))
+                .route("/admin/audit", web::get().to(audit_viewer))
+                .route("/feed.xml", web::get().to(rss_feed))
+                .route("/atom.xml", web::get().to(atom_feed))
+                .route("/sitemap.xml", web::get().to(sitemap))
+        );
+}
+
+/// Version 1 of the JSON API. Every route here must also be listed in
+/// [`openapi::ApiDoc`].
+fn api_v1(cfg: &mut web::ServiceConfig) {
+    cfg.route("/register", web::post().to(register))
+        .route("/login", web::post().to(login))
+        .route("/auth/refresh", web::post().to(refresh))
+        .route("/auth/logout", web::post().to(logout).wrap(auth::authenticated()))
+        .route("/auth/logout-all", web::post().to(logout_all).wrap(auth::authenticated()))
+        .route("/auth/sessions", web::get().to(list_sessions).wrap(auth::authenticated()))
+        .route("/posts", web::get().to(get_posts))
+        .route("/posts", web::post().to(create_post).wrap(auth::require(Permission::WritePosts)))
+        .route("/posts/{slug}", web::get().to(get_post))
+        .route("/posts/{id}", web::put().to(update_post).wrap(auth::require(Permission::WritePosts)))
+        .route("/posts/{id}", web::delete().to(delete_post).wrap(auth::require(Permission::WritePosts)))
+        .route("/posts/{slug}/comments", web::get().to(get_comments))
+        .route("/posts/{slug}/comments", web::post().to(create_comment))
+        .route("/comments/{id}/approve", web::post().to(approve_comment).wrap(auth::require(Permission::ModerateComments)))
+        .route("/comments/{id}", web::delete().to(delete_comment).wrap(auth::require(Permission::ModerateComments)))
+        .route("/users", web::get().to(list_users).wrap(auth::require(Permission::ManageUsers)))
+        .route("/users/{id}/role", web::put().to(update_user_role).wrap(auth::require(Permission::ManageUsers)))
+        .route("/audit", web::get().to(get_audit_log).wrap(auth::require(Permission::ViewAuditLog)))
+        .route("/analytics/views", web::get().to(get_post_views).wrap(auth::require(Permission::ViewAnalytics)));
 }
 
+#[utoipa::path(
+    post,
+    path = "/register",
+    tag = "auth",
+    request_body = RegisterRequest,
+    responses(
+        (status = 201, description = "User created", body = MessageResponse),
+        (status = 400, description = "Invalid input or name/email taken", body = ErrorResponse),
+    )
+)]
 async fn register(
     state: web::Data<AppState>,
+    http_req: HttpRequest,
     req: web::Json<RegisterRequest>,
 ) -> HttpResponse {
     if let Err(e) = req.validate() {
@@ -49,46 +93,281 @@ async fn register(
         Err(_) => return HttpResponse::InternalServerError().json(json!({"error": "Failed to hash password"})),
     };
 
+    // The first account bootstraps the site as its admin
+    let role = match state.db.count_users().await {
+        Ok(0) => Role::Admin,
+        Ok(_) => Role::Author,
+        Err(_) => return HttpResponse::InternalServerError().json(json!({"error": "Database error"})),
+    };
+
     let user = User {
         id: uuid::Uuid::new_v4().to_string(),
         username: req.username.clone(),
         email: req.email.clone(),
         password_hash,
-        is_admin: false,
+        role,
         created_at: chrono::Utc::now().to_rfc3339(),
     };
 
     match state.db.create_user(&user).await {
-        Ok(_) => HttpResponse::Created().json(json!({"message": "User created successfully"})),
+        Ok(_) => {
+            let event = AuditEvent::new(AuditAction::UserCreate, Some(&user.id), &user.username)
+                .target(&user.id)
+                .after(&user);
+            audit::record(&state.db, &http_req, event).await;
+            HttpResponse::Created().json(json!({"message": "User created successfully"}))
+        }
         Err(_) => HttpResponse::InternalServerError().json(json!({"error": "Failed to create user"})),
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/login",
+    tag = "auth",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Access and refresh tokens", body = TokenResponse),
+        (status = 401, description = "Invalid credentials", body = ErrorResponse),
+    )
+)]
 async fn login(
     state: web::Data<AppState>,
+    http_req: HttpRequest,
     req: web::Json<LoginRequest>,
 ) -> HttpResponse {
     let user = match state.db.get_user_by_username(&req.username).await {
         Ok(Some(user)) => user,
-        Ok(None) => return HttpResponse::Unauthorized().json(json!({"error": "Invalid credentials"})),
+        Ok(None) => {
+            let event = AuditEvent::new(AuditAction::LoginFailed, None, &req.username);
+            audit::record(&state.db, &http_req, event).await;
+            return HttpResponse::Unauthorized().json(json!({"error": "Invalid credentials"}));
+        }
         Err(_) => return HttpResponse::InternalServerError().json(json!({"error": "Database error"})),
     };
 
     if !bcrypt::verify(&req.password, &user.password_hash).unwrap_or(false) {
+        let event = AuditEvent::new(AuditAction::LoginFailed, Some(&user.id), &user.username).target(&user.id);
+        audit::record(&state.db, &http_req, event).await;
         return HttpResponse::Unauthorized().json(json!({"error": "Invalid credentials"}));
     }
 
-    let token = match auth::create_token(&user.id, &user.username, user.is_admin, &state.jwt_secret) {
-        Ok(token) => token,
-        Err(_) => return HttpResponse::InternalServerError().json(json!({"error": "Failed to create token"})),
+    let now = chrono::Utc::now().to_rfc3339();
+    if let Err(e) = state.db.delete_expired_sessions(&now).await {
+        log::warn!("Failed to prune expired sessions: {}", e);
+    }
+
+    let tokens = match start_session(&state, &http_req, &user).await {
+        Ok(tokens) => tokens,
+        Err(response) => return response,
+    };
+
+    let event = AuditEvent::new(AuditAction::Login, Some(&user.id), &user.username).target(&user.id);
+    audit::record(&state.db, &http_req, event).await;
+
+    HttpResponse::Ok().json(tokens)
+}
+
+/// Opens a refresh-token session for `user` and mints the first access
+/// token for it. Returns the JSON body shared by login and refresh.
+async fn start_session(
+    state: &AppState,
+    req: &HttpRequest,
+    user: &User,
+) -> Result<TokenResponse, HttpResponse> {
+    let now = chrono::Utc::now();
+    let refresh_token = auth::generate_refresh_token();
+    let session = Session {
+        id: uuid::Uuid::new_v4().to_string(),
+        user_id: user.id.clone(),
+        token_hash: auth::hash_refresh_token(&refresh_token),
+        created_at: now.to_rfc3339(),
+        expires_at: (now + state.tokens.refresh_ttl).to_rfc3339(),
+        revoked_at: None,
+        ip: req.connection_info().realip_remote_addr().map(str::to_string),
+        user_agent: req
+            .headers()
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string),
     };
 
-    HttpResponse::Ok().json(json!({"token": token, "username": user.username, "is_admin": user.is_admin}))
+    if state.db.create_session(&session).await.is_err() {
+        return Err(HttpResponse::InternalServerError().json(json!({"error": "Failed to create session"})));
+    }
+
+    let token = auth::create_token(
+        &user.id,
+        &user.username,
+        user.role,
+        &session.id,
+        state.tokens.access_ttl,
+        &state.jwt_secret,
+    )
+    .map_err(|_| HttpResponse::InternalServerError().json(json!({"error": "Failed to create token"})))?;
+
+    Ok(TokenResponse {
+        token,
+        refresh_token,
+        expires_in: state.tokens.access_ttl.num_seconds(),
+        username: user.username.clone(),
+        role: user.role,
+    })
 }
 
+/// Swaps a refresh token for a new access token and a new refresh token.
+/// Presenting a token that was already rotated out means it has leaked, so
+/// every session of that user is revoked.
+#[utoipa::path(
+    post,
+    path = "/auth/refresh",
+    tag = "auth",
+    request_body = RefreshRequest,
+    responses(
+        (status = 200, description = "New access and refresh tokens", body = TokenResponse),
+        (status = 401, description = "Unknown, expired, or revoked refresh token", body = ErrorResponse),
+    )
+)]
+async fn refresh(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    refresh_req: web::Json<RefreshRequest>,
+) -> HttpResponse {
+    let invalid = || HttpResponse::Unauthorized().json(json!({"error": "Invalid refresh token"}));
+    let now = chrono::Utc::now().to_rfc3339();
+
+    let token_hash = auth::hash_refresh_token(&refresh_req.refresh_token);
+    let session = match state.db.get_session_by_token_hash(&token_hash).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return invalid(),
+        Err(_) => return HttpResponse::InternalServerError().json(json!({"error": "Database error"})),
+    };
+
+    let user = match state.db.get_user_by_id(&session.user_id).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return invalid(),
+        Err(_) => return HttpResponse::InternalServerError().json(json!({"error": "Database error"})),
+    };
+
+    if session.revoked_at.is_some() {
+        if let Err(e) = state.db.revoke_user_sessions(&user.id, &now).await {
+            log::error!("Failed to revoke sessions after token reuse: {}", e);
+        }
+        let event = AuditEvent::new(AuditAction::TokenReuse, Some(&user.id), &user.username).target(&session.id);
+        audit::record(&state.db, &req, event).await;
+        return invalid();
+    }
+
+    if session.expires_at <= now {
+        return invalid();
+    }
+
+    // Revoke first so two concurrent refreshes cannot both succeed
+    match state.db.revoke_session(&session.id, &now).await {
+        Ok(true) => {}
+        Ok(false) => return invalid(),
+        Err(_) => return HttpResponse::InternalServerError().json(json!({"error": "Database error"})),
+    }
+
+    match start_session(&state, &req, &user).await {
+        Ok(tokens) => HttpResponse::Ok().json(tokens),
+        Err(response) => response,
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/logout",
+    tag = "auth",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Current session revoked", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+    )
+)]
+async fn logout(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    claims: web::ReqData<Claims>,
+) -> HttpResponse {
+    let now = chrono::Utc::now().to_rfc3339();
+    if state.db.revoke_session(&claims.sid, &now).await.is_err() {
+        return HttpResponse::InternalServerError().json(json!({"error": "Failed to revoke session"}));
+    }
+
+    let event = AuditEvent::by(AuditAction::Logout, &claims).target(&claims.sid);
+    audit::record(&state.db, &req, event).await;
+    HttpResponse::Ok().json(json!({"message": "Logged out"}))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/logout-all",
+    tag = "auth",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Every session of the user revoked", body = RevokedResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+    )
+)]
+async fn logout_all(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    claims: web::ReqData<Claims>,
+) -> HttpResponse {
+    let now = chrono::Utc::now().to_rfc3339();
+    let revoked = match state.db.revoke_user_sessions(&claims.sub, &now).await {
+        Ok(revoked) => revoked,
+        Err(_) => return HttpResponse::InternalServerError().json(json!({"error": "Failed to revoke sessions"})),
+    };
+
+    let event = AuditEvent::by(AuditAction::LogoutAll, &claims).target(&claims.sub);
+    audit::record(&state.db, &req, event).await;
+    HttpResponse::Ok().json(RevokedResponse {
+        message: "Logged out everywhere".to_string(),
+        revoked,
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/sessions",
+    tag = "auth",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Active sessions of the user", body = [SessionInfo]),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+    )
+)]
+async fn list_sessions(state: web::Data<AppState>, claims: web::ReqData<Claims>) -> HttpResponse {
+    let now = chrono::Utc::now().to_rfc3339();
+    match state.db.get_active_sessions(&claims.sub, &now).await {
+        Ok(sessions) => {
+            let sessions: Vec<SessionInfo> = sessions
+                .into_iter()
+                .map(|session| SessionInfo {
+                    current: session.id == claims.sid,
+                    session,
+                })
+                .collect();
+            HttpResponse::Ok().json(sessions)
+        }
+        Err(_) => HttpResponse::InternalServerError().json(json!({"error": "Failed to fetch sessions"})),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/posts",
+    tag = "posts",
+    security((), ("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Published posts, plus drafts for editors and admins", body = [Post]),
+    )
+)]
 async fn get_posts(state: web::Data<AppState>, req: HttpRequest) -> HttpResponse {
-    let is_admin = auth::verify_admin(&req, &state.jwt_secret);
-    let posts = match state.db.get_all_posts(!is_admin).await {
+    let show_drafts = auth::has_permission(&req, &state.jwt_secret, Permission::ViewDrafts);
+    let posts = match state.db.get_all_posts(!show_drafts).await {
         Ok(posts) => posts,
         Err(_) => return HttpResponse::InternalServerError().json(json!({"error": "Failed to fetch posts"})),
     };
@@ -96,24 +375,28 @@ async fn get_posts(state: web::Data<AppState>, req: HttpRequest) -> HttpResponse
     HttpResponse::Ok().json(posts)
 }
 
+#[utoipa::path(
+    post,
+    path = "/posts",
+    tag = "posts",
+    security(("bearer_auth" = [])),
+    request_body = CreatePostRequest,
+    responses(
+        (status = 201, description = "Post created", body = Post),
+        (status = 400, description = "Invalid input or duplicate title", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+    )
+)]
 async fn create_post(
     state: web::Data<AppState>,
     req: HttpRequest,
+    claims: web::ReqData<Claims>,
     post_req: web::Json<CreatePostRequest>,
 ) -> HttpResponse {
-    if !auth::verify_admin(&req, &state.jwt_secret) {
-        return HttpResponse::Unauthorized().json(json!({"error": "Admin access required"}));
-    }
-
     if let Err(e) = post_req.validate() {
         return HttpResponse::BadRequest().json(json!({"error": e.to_string()}));
     }
 
-    let claims = match auth::extract_claims(&req, &state.jwt_secret) {
-        Some(claims) => claims,
-        None => return HttpResponse::Unauthorized().json(json!({"error": "Invalid token"})),
-    };
-
     let slug = utils::slugify(&post_req.title);
     
     if state.db.get_post_by_slug(&slug).await.unwrap().is_some() {
@@ -126,18 +409,32 @@ async fn create_post(
         slug,
         content: post_req.content.clone(),
         summary: post_req.summary.clone(),
-        author_id: claims.sub,
+        author_id: claims.sub.clone(),
         published: post_req.published,
         created_at: chrono::Utc::now().to_rfc3339(),
         updated_at: chrono::Utc::now().to_rfc3339(),
     };
 
     match state.db.create_post(&post).await {
-        Ok(_) => HttpResponse::Created().json(post),
+        Ok(_) => {
+            let event = AuditEvent::by(AuditAction::PostCreate, &claims).target(&post.id).after(&post);
+            audit::record(&state.db, &req, event).await;
+            HttpResponse::Created().json(post)
+        }
         Err(_) => HttpResponse::InternalServerError().json(json!({"error": "Failed to create post"})),
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/posts/{post}",
+    tag = "posts",
+    params(("post" = String, Path, description = "Post slug")),
+    responses(
+        (status = 200, description = "The post", body = Post),
+        (status = 404, description = "No such post", body = ErrorResponse),
+    )
+)]
 async fn get_post(state: web::Data<AppState>, slug: web::Path<String>) -> HttpResponse {
     match state.db.get_post_by_slug(&slug).await {
         Ok(Some(post)) => HttpResponse::Ok().json(post),
@@ -146,16 +443,26 @@ async fn get_post(state: web::Data<AppState>, slug: web::Path<String>) -> HttpRe
     }
 }
 
+#[utoipa::path(
+    put,
+    path = "/posts/{post}",
+    tag = "posts",
+    security(("bearer_auth" = [])),
+    params(("post" = String, Path, description = "Post slug")),
+    request_body = CreatePostRequest,
+    responses(
+        (status = 200, description = "Post updated", body = Post),
+        (status = 403, description = "Authors may only edit their own posts", body = ErrorResponse),
+        (status = 404, description = "No such post", body = ErrorResponse),
+    )
+)]
 async fn update_post(
     state: web::Data<AppState>,
     req: HttpRequest,
+    claims: web::ReqData<Claims>,
     id: web::Path<String>,
     post_req: web::Json<CreatePostRequest>,
 ) -> HttpResponse {
-    if !auth::verify_admin(&req, &state.jwt_secret) {
-        return HttpResponse::Unauthorized().json(json!({"error": "Admin access required"}));
-    }
-
     if let Err(e) = post_req.validate() {
         return HttpResponse::BadRequest().json(json!({"error": e.to_string()}));
     }
@@ -166,6 +473,11 @@ async fn update_post(
         Err(_) => return HttpResponse::InternalServerError().json(json!({"error": "Database error"})),
     };
 
+    if !can_edit(&claims, &post) {
+        return HttpResponse::Forbidden().json(json!({"error": "You can only edit your own posts"}));
+    }
+
+    let before = post.clone();
     post.title = post_req.title.clone();
     post.content = post_req.content.clone();
     post.summary = post_req.summary.clone();
@@ -173,40 +485,81 @@ async fn update_post(
     post.updated_at = chrono::Utc::now().to_rfc3339();
 
     match state.db.update_post(&post).await {
-        Ok(_) => HttpResponse::Ok().json(post),
+        Ok(_) => {
+            let event = AuditEvent::by(AuditAction::PostUpdate, &claims)
+                .target(&post.id)
+                .before(&before)
+                .after(&post);
+            audit::record(&state.db, &req, event).await;
+            HttpResponse::Ok().json(post)
+        }
         Err(_) => HttpResponse::InternalServerError().json(json!({"error": "Failed to update post"})),
     }
 }
 
+#[utoipa::path(
+    delete,
+    path = "/posts/{post}",
+    tag = "posts",
+    security(("bearer_auth" = [])),
+    params(("post" = String, Path, description = "Post id")),
+    responses(
+        (status = 200, description = "Post deleted", body = MessageResponse),
+        (status = 403, description = "Authors may only delete their own posts", body = ErrorResponse),
+        (status = 404, description = "No such post", body = ErrorResponse),
+    )
+)]
 async fn delete_post(
     state: web::Data<AppState>,
     req: HttpRequest,
+    claims: web::ReqData<Claims>,
     id: web::Path<String>,
 ) -> HttpResponse {
-    if !auth::verify_admin(&req, &state.jwt_secret) {
-        return HttpResponse::Unauthorized().json(json!({"error": "Admin access required"}));
+    let post = match state.db.get_post_by_id(&id).await {
+        Ok(Some(post)) => post,
+        Ok(None) => return HttpResponse::NotFound().json(json!({"error": "Post not found"})),
+        Err(_) => return HttpResponse::InternalServerError().json(json!({"error": "Database error"})),
+    };
+
+    if !can_edit(&claims, &post) {
+        return HttpResponse::Forbidden().json(json!({"error": "You can only delete your own posts"}));
     }
 
     match state.db.delete_post(&id).await {
-        Ok(_) => HttpResponse::Ok().json(json!({"message": "Post deleted"})),
+        Ok(_) => {
+            let event = AuditEvent::by(AuditAction::PostDelete, &claims).target(&post.id).before(&post);
+            audit::record(&state.db, &req, event).await;
+            HttpResponse::Ok().json(json!({"message": "Post deleted"}))
+        }
         Err(_) => HttpResponse::InternalServerError().json(json!({"error": "Failed to delete post"})),
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/posts/{slug}/comments",
+    tag = "comments",
+    security((), ("bearer_auth" = [])),
+    params(("slug" = String, Path, description = "Post slug")),
+    responses(
+        (status = 200, description = "Approved comments, plus pending ones for moderators", body = [Comment]),
+        (status = 404, description = "No such post", body = ErrorResponse),
+    )
+)]
 async fn get_comments(
     state: web::Data<AppState>,
     req: HttpRequest,
     slug: web::Path<String>,
 ) -> HttpResponse {
-    let is_admin = auth::verify_admin(&req, &state.jwt_secret);
-    
+    let show_pending = auth::has_permission(&req, &state.jwt_secret, Permission::ModerateComments);
+
     let post = match state.db.get_post_by_slug(&slug).await {
         Ok(Some(post)) => post,
         Ok(None) => return HttpResponse::NotFound().json(json!({"error": "Post not found"})),
         Err(_) => return HttpResponse::InternalServerError().json(json!({"error": "Database error"})),
     };
 
-    let comments = match state.db.get_comments_by_post(&post.id, !is_admin).await {
+    let comments = match state.db.get_comments_by_post(&post.id, !show_pending).await {
         Ok(comments) => comments,
         Err(_) => return HttpResponse::InternalServerError().json(json!({"error": "Failed to fetch comments"})),
     };
@@ -214,6 +567,18 @@ async fn get_comments(
     HttpResponse::Ok().json(comments)
 }
 
+#[utoipa::path(
+    post,
+    path = "/posts/{slug}/comments",
+    tag = "comments",
+    params(("slug" = String, Path, description = "Post slug")),
+    request_body = CreateCommentRequest,
+    responses(
+        (status = 201, description = "Comment submitted for approval", body = MessageResponse),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 404, description = "No such post", body = ErrorResponse),
+    )
+)]
 async fn create_comment(
     state: web::Data<AppState>,
     slug: web::Path<String>,
@@ -245,39 +610,208 @@ async fn create_comment(
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/comments/{id}/approve",
+    tag = "comments",
+    security(("bearer_auth" = [])),
+    params(("id" = String, Path, description = "Comment id")),
+    responses(
+        (status = 200, description = "Comment approved", body = MessageResponse),
+        (status = 403, description = "Editor or admin role required", body = ErrorResponse),
+        (status = 404, description = "No such comment", body = ErrorResponse),
+    )
+)]
 async fn approve_comment(
     state: web::Data<AppState>,
     req: HttpRequest,
+    claims: web::ReqData<Claims>,
     id: web::Path<String>,
 ) -> HttpResponse {
-    if !auth::verify_admin(&req, &state.jwt_secret) {
-        return HttpResponse::Unauthorized().json(json!({"error": "Admin access required"}));
-    }
+    let comment = match state.db.get_comment_by_id(&id).await {
+        Ok(Some(comment)) => comment,
+        Ok(None) => return HttpResponse::NotFound().json(json!({"error": "Comment not found"})),
+        Err(_) => return HttpResponse::InternalServerError().json(json!({"error": "Database error"})),
+    };
 
     match state.db.approve_comment(&id).await {
-        Ok(_) => HttpResponse::Ok().json(json!({"message": "Comment approved"})),
+        Ok(_) => {
+            let approved = Comment { approved: true, ..comment.clone() };
+            let event = AuditEvent::by(AuditAction::CommentApprove, &claims)
+                .target(&comment.id)
+                .before(&comment)
+                .after(&approved);
+            audit::record(&state.db, &req, event).await;
+            HttpResponse::Ok().json(json!({"message": "Comment approved"}))
+        }
         Err(_) => HttpResponse::InternalServerError().json(json!({"error": "Failed to approve comment"})),
     }
 }
 
+#[utoipa::path(
+    delete,
+    path = "/comments/{id}",
+    tag = "comments",
+    security(("bearer_auth" = [])),
+    params(("id" = String, Path, description = "Comment id")),
+    responses(
+        (status = 200, description = "Comment deleted", body = MessageResponse),
+        (status = 403, description = "Editor or admin role required", body = ErrorResponse),
+        (status = 404, description = "No such comment", body = ErrorResponse),
+    )
+)]
 async fn delete_comment(
     state: web::Data<AppState>,
     req: HttpRequest,
+    claims: web::ReqData<Claims>,
     id: web::Path<String>,
 ) -> HttpResponse {
-    if !auth::verify_admin(&req, &state.jwt_secret) {
-        return HttpResponse::Unauthorized().json(json!({"error": "Admin access required"}));
-    }
+    let comment = match state.db.get_comment_by_id(&id).await {
+        Ok(Some(comment)) => comment,
+        Ok(None) => return HttpResponse::NotFound().json(json!({"error": "Comment not found"})),
+        Err(_) => return HttpResponse::InternalServerError().json(json!({"error": "Database error"})),
+    };
 
     match state.db.delete_comment(&id).await {
-        Ok(_) => HttpResponse::Ok().json(json!({"message": "Comment deleted"})),
+        Ok(_) => {
+            let event = AuditEvent::by(AuditAction::CommentDelete, &claims).target(&comment.id).before(&comment);
+            audit::record(&state.db, &req, event).await;
+            HttpResponse::Ok().json(json!({"message": "Comment deleted"}))
+        }
         Err(_) => HttpResponse::InternalServerError().json(json!({"error": "Failed to delete comment"})),
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/users",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "All users", body = [User]),
+        (status = 403, description = "Admin role required", body = ErrorResponse),
+    )
+)]
+async fn list_users(state: web::Data<AppState>) -> HttpResponse {
+    match state.db.get_all_users().await {
+        Ok(users) => HttpResponse::Ok().json(users),
+        Err(_) => HttpResponse::InternalServerError().json(json!({"error": "Failed to fetch users"})),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/users/{id}/role",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    params(("id" = String, Path, description = "User id")),
+    request_body = UpdateRoleRequest,
+    responses(
+        (status = 200, description = "Role changed", body = User),
+        (status = 400, description = "Admins cannot change their own role", body = ErrorResponse),
+        (status = 403, description = "Admin role required", body = ErrorResponse),
+        (status = 404, description = "No such user", body = ErrorResponse),
+    )
+)]
+async fn update_user_role(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    claims: web::ReqData<Claims>,
+    id: web::Path<String>,
+    role_req: web::Json<UpdateRoleRequest>,
+) -> HttpResponse {
+    // Stops the last admin from locking everyone out of user management
+    if *id == claims.sub {
+        return HttpResponse::BadRequest().json(json!({"error": "You cannot change your own role"}));
+    }
+
+    let mut user = match state.db.get_user_by_id(&id).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::NotFound().json(json!({"error": "User not found"})),
+        Err(_) => return HttpResponse::InternalServerError().json(json!({"error": "Database error"})),
+    };
+
+    let before = user.clone();
+    user.role = role_req.role;
+    match state.db.update_user_role(&user.id, user.role).await {
+        Ok(_) => {
+            let event = AuditEvent::by(AuditAction::UserRoleChange, &claims)
+                .target(&user.id)
+                .before(&before)
+                .after(&user);
+            audit::record(&state.db, &req, event).await;
+            HttpResponse::Ok().json(user)
+        }
+        Err(_) => HttpResponse::InternalServerError().json(json!({"error": "Failed to update role"})),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/audit",
+    tag = "audit",
+    security(("bearer_auth" = [])),
+    params(AuditQuery),
+    responses(
+        (status = 200, description = "One page of audit entries, newest first", body = AuditPage),
+        (status = 403, description = "Admin role required", body = ErrorResponse),
+    )
+)]
+async fn get_audit_log(
+    state: web::Data<AppState>,
+    query: web::Query<AuditQuery>,
+) -> HttpResponse {
+    let filter = audit::AuditFilter::from_query(&query);
+    match state.db.get_audit_entries(&filter).await {
+        Ok((entries, total)) => HttpResponse::Ok().json(AuditPage {
+            entries,
+            page: filter.page,
+            per_page: filter.per_page,
+            total,
+            total_pages: filter.total_pages(total),
+        }),
+        Err(_) => HttpResponse::InternalServerError().json(json!({"error": "Failed to fetch audit log"})),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/analytics/views",
+    tag = "analytics",
+    security(("bearer_auth" = [])),
+    params(ViewsQuery),
+    responses(
+        (status = 200, description = "Daily page views per post, most viewed first", body = ViewsReport),
+        (status = 403, description = "Admin role required", body = ErrorResponse),
+    )
+)]
+async fn get_post_views(
+    state: web::Data<AppState>,
+    query: web::Query<ViewsQuery>,
+) -> HttpResponse {
+    let days = analytics::report_days(query.days);
+    let since = (chrono::Utc::now() - chrono::Duration::days(days as i64 - 1))
+        .format("%Y-%m-%d")
+        .to_string();
+
+    match state.db.get_daily_post_views(&since).await {
+        Ok(rows) => HttpResponse::Ok().json(ViewsReport {
+            since,
+            days,
+            posts: analytics::group_by_post(rows),
+        }),
+        Err(_) => HttpResponse::InternalServerError().json(json!({"error": "Failed to fetch page views"})),
+    }
+}
+
+/// Authors may only change their own posts; editors and admins any post.
+fn can_edit(claims: &Claims, post: &Post) -> bool {
+    post.author_id == claims.sub || claims.role.can(Permission::EditAnyPost)
+}
+
 async fn index(
     state: web::Data<AppState>,
-    tmpl: web::Data<tera::Tera>,
+    tmpl: web::Data<Templates>,
 ) -> HttpResponse {
     let posts = match state.db.get_all_posts(true).await {
         Ok(posts) => posts,
@@ -296,7 +830,7 @@ async fn index(
 
 async fn view_post(
     state: web::Data<AppState>,
-    tmpl: web::Data<tera::Tera>,
+    tmpl: web::Data<Templates>,
     slug: web::Path<String>,
 ) -> HttpResponse {
     let post = match state.db.get_post_by_slug(&slug).await {
@@ -305,10 +839,7 @@ async fn view_post(
         Err(_) => return HttpResponse::InternalServerError().body("Database error"),
     };
 
-    let comments = match state.db.get_comments_by_post(&post.id, true).await {
-        Ok(comments) => comments,
-        Err(_) => vec![],
-    };
+    let comments = state.db.get_comments_by_post(&post.id, true).await.unwrap_or_default();
 
     let html_content = utils::markdown_to_html(&post.content);
 
@@ -326,10 +857,10 @@ async fn view_post(
 
 async fn admin_panel(
     state: web::Data<AppState>,
-    tmpl: web::Data<tera::Tera>,
+    tmpl: web::Data<Templates>,
     req: HttpRequest,
 ) -> HttpResponse {
-    if !auth::verify_admin(&req, &state.jwt_secret) {
+    if !auth::has_permission(&req, &state.jwt_secret, Permission::ViewDrafts) {
         return HttpResponse::Unauthorized()
             .insert_header((header::LOCATION, "/"))
             .finish();
@@ -349,3 +880,112 @@ async fn admin_panel(
         Err(_) => HttpResponse::InternalServerError().body("Template error"),
     }
 }
+
+async fn audit_viewer(
+    state: web::Data<AppState>,
+    tmpl: web::Data<Templates>,
+    req: HttpRequest,
+    query: web::Query<AuditQuery>,
+) -> HttpResponse {
+    if !auth::has_permission(&req, &state.jwt_secret, Permission::ViewAuditLog) {
+        return HttpResponse::Unauthorized()
+            .insert_header((header::LOCATION, "/"))
+            .finish();
+    }
+
+    let filter = audit::AuditFilter::from_query(&query);
+    let (entries, total) = match state.db.get_audit_entries(&filter).await {
+        Ok(result) => result,
+        Err(_) => return HttpResponse::InternalServerError().body("Database error"),
+    };
+
+    let mut ctx = tera::Context::new();
+    ctx.insert("entries", &entries);
+    ctx.insert("total", &total);
+    ctx.insert("page", &filter.page);
+    ctx.insert("per_page", &filter.per_page);
+    ctx.insert("total_pages", &filter.total_pages(total));
+    ctx.insert("actor", &filter.actor.unwrap_or_default());
+    ctx.insert("action", &filter.action.unwrap_or_default());
+    ctx.insert("title", "Audit Log");
+
+    match tmpl.render("admin/audit.html", &ctx) {
+        Ok(body) => HttpResponse::Ok().content_type("text/html").body(body),
+        Err(_) => HttpResponse::InternalServerError().body("Template error"),
+    }
+}
+
+async fn rss_feed(state: web::Data<AppState>, req: HttpRequest) -> HttpResponse {
+    let posts = match state.db.get_all_posts(true).await {
+        Ok(posts) => posts,
+        Err(_) => return HttpResponse::InternalServerError().body("Database error"),
+    };
+
+    let body = feed::render_rss(&state.site, &posts);
+    xml_response(&req, &state.site, "application/rss+xml; charset=utf-8", &posts, body)
+}
+
+async fn atom_feed(state: web::Data<AppState>, req: HttpRequest) -> HttpResponse {
+    let posts = match state.db.get_all_posts(true).await {
+        Ok(posts) => posts,
+        Err(_) => return HttpResponse::InternalServerError().body("Database error"),
+    };
+
+    let body = feed::render_atom(&state.site, &posts);
+    xml_response(&req, &state.site, "application/atom+xml; charset=utf-8", &posts, body)
+}
+
+async fn sitemap(state: web::Data<AppState>, req: HttpRequest) -> HttpResponse {
+    let posts = match state.db.get_all_posts(true).await {
+        Ok(posts) => posts,
+        Err(_) => return HttpResponse::InternalServerError().body("Database error"),
+    };
+
+    let body = feed::render_sitemap(&state.site, &posts);
+    xml_response(&req, &state.site, "application/xml; charset=utf-8", &posts, body)
+}
+
+/// Serves generated XML with Cache-Control, Last-Modified, and an ETag,
+/// answering 304 Not Modified when the client's copy is still current.
+fn xml_response(
+    req: &HttpRequest,
+    site: &feed::SiteConfig,
+    content_type: &str,
+    posts: &[Post],
+    body: String,
+) -> HttpResponse {
+    let mut hasher = DefaultHasher::new();
+    body.hash(&mut hasher);
+    let etag = format!("\"{:x}\"", hasher.finish());
+    let cache_control = format!("public, max-age={}", site.cache_seconds);
+
+    let not_modified = req
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.split(',').any(|tag| tag.trim() == etag || tag.trim() == "*"))
+        .unwrap_or(false);
+
+    let mut response = if not_modified {
+        HttpResponse::NotModified()
+    } else {
+        HttpResponse::Ok()
+    };
+    response
+        .insert_header((header::ETAG, etag))
+        .insert_header((header::CACHE_CONTROL, cache_control));
+
+    if let Some(updated) = feed::last_updated(posts) {
+        let last_modified = updated
+            .with_timezone(&chrono::Utc)
+            .format("%a, %d %b %Y %H:%M:%S GMT")
+            .to_string();
+        response.insert_header((header::LAST_MODIFIED, last_modified));
+    }
+
+    if not_modified {
+        response.finish()
+    } else {
+        response.content_type(content_type).body(body)
+    }
+}
@@ -2,18 +2,31 @@ use actix_web::{web, App, HttpServer, middleware};
 use actix_files as fs;
 use dotenv::dotenv;
 use std::env;
+use std::sync::Arc;
 
+mod analytics;
 mod handlers;
 mod models;
 mod db;
+mod audit;
 mod auth;
+mod feed;
+mod themes;
+mod openapi;
 mod utils;
 
+use analytics::Analytics;
+use auth::TokenConfig;
 use db::Database;
+use feed::SiteConfig;
+use themes::{ThemeConfig, Templates};
 
 pub struct AppState {
     pub db: Database,
     pub jwt_secret: String,
+    pub tokens: TokenConfig,
+    pub analytics: Analytics,
+    pub site: SiteConfig,
 }
 
 #[actix_web::main]
@@ -31,25 +44,47 @@ async fn main() -> std::io::Result<()> {
     let db = Database::new(&database_url).await.expect("Failed to connect to database");
     db.init().await.expect("Failed to initialize database");
     
-    log::info!("Database initialized successfully");
+    log::info!("Database initialized successfully ({:?})", db.backend());
+
+    // The JWT secret doubles as the salt for hashed visitor IPs
+    let analytics = Analytics::start(db.clone(), jwt_secret.clone());
 
     let app_state = web::Data::new(AppState {
         db,
         jwt_secret,
+        tokens: TokenConfig::from_env(),
+        analytics,
+        site: SiteConfig::from_env(),
     });
 
+    let theme = ThemeConfig::from_env();
+    let theme_static = theme.dir().map(|dir| dir.join("static")).filter(|dir| dir.is_dir());
+    let templates = Arc::new(Templates::load(theme.clone()).expect("Failed to initialize Tera"));
+    log::info!("Using theme '{}'", theme.name);
+
+    let _watcher = if theme.reload {
+        log::info!("Watching templates for changes");
+        Some(templates.watch().expect("Failed to watch templates"))
+    } else {
+        None
+    };
+    let templates = web::Data::from(templates);
+
     log::info!("Server starting at http://{}:{}", host, port);
 
     HttpServer::new(move || {
-        let tera = tera::Tera::new("templates/**/*.html").expect("Failed to initialize Tera");
-        
-        App::new()
+        let mut app = App::new()
             .app_data(app_state.clone())
-            .app_data(web::Data::new(tera))
+            .app_data(templates.clone())
             .wrap(middleware::Logger::default())
             .wrap(middleware::Compress::default())
-            .service(fs::Files::new("/static", "static").show_files_listing())
-            .configure(handlers::config)
+            .service(fs::Files::new("/static", "static").show_files_listing());
+
+        if let Some(dir) = &theme_static {
+            app = app.service(fs::Files::new("/theme", dir));
+        }
+
+        app.configure(handlers::config)
     })
     .bind(format!("{}:{}", host, port))?
     .run()
@@ -1,17 +1,46 @@
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 use validator::Validate;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct User {
     pub id: String,
     pub username: String,
     pub email: String,
+    #[serde(skip_serializing)]
     pub password_hash: String,
-    pub is_admin: bool,
+    pub role: Role,
     pub created_at: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Admin,
+    Editor,
+    Author,
+}
+
+impl Role {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::Editor => "editor",
+            Role::Author => "author",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "admin" => Some(Role::Admin),
+            "editor" => Some(Role::Editor),
+            "author" => Some(Role::Author),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct Post {
     pub id: String,
     pub title: String,
@@ -24,7 +53,7 @@ pub struct Post {
     pub updated_at: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct Comment {
     pub id: String,
     pub post_id: String,
@@ -35,7 +64,32 @@ pub struct Comment {
     pub created_at: String,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Serialize, Clone, ToSchema)]
+pub struct AuditEntry {
+    pub id: String,
+    pub actor_id: Option<String>,
+    pub actor_name: String,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: Option<String>,
+    pub ip: Option<String>,
+    #[schema(value_type = Option<Object>)]
+    pub before: Option<serde_json::Value>,
+    #[schema(value_type = Option<Object>)]
+    pub after: Option<serde_json::Value>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditQuery {
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+    pub actor: Option<String>,
+    pub action: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct RegisterRequest {
     #[validate(length(min = 3, max = 50))]
     pub username: String,
@@ -45,13 +99,13 @@ pub struct RegisterRequest {
     pub password: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct LoginRequest {
     pub username: String,
     pub password: String,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct CreatePostRequest {
     #[validate(length(min = 1, max = 200))]
     pub title: String,
@@ -62,7 +116,7 @@ pub struct CreatePostRequest {
     pub published: bool,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct CreateCommentRequest {
     #[validate(length(min = 1, max = 100))]
     pub author_name: String,
@@ -72,10 +126,114 @@ pub struct CreateCommentRequest {
     pub content: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateRoleRequest {
+    pub role: Role,
+}
+
+/// A refresh-token session. Only a hash of the token is stored, so a leaked
+/// database cannot be replayed against `/api/auth/refresh`.
+#[derive(Debug, Serialize, Clone, ToSchema)]
+pub struct Session {
+    pub id: String,
+    pub user_id: String,
+    #[serde(skip_serializing)]
+    pub token_hash: String,
+    pub created_at: String,
+    pub expires_at: String,
+    pub revoked_at: Option<String>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Claims {
     pub sub: String,
     pub username: String,
-    pub is_admin: bool,
+    pub role: Role,
+    /// Session the access token was issued for, so logout can revoke it.
+    pub sid: String,
     pub exp: usize,
 }
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TokenResponse {
+    /// Short-lived JWT for the `Authorization: Bearer` header
+    pub token: String,
+    /// Single-use token for `/auth/refresh`
+    pub refresh_token: String,
+    /// Seconds until `token` expires
+    pub expires_in: i64,
+    pub username: String,
+    pub role: Role,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SessionInfo {
+    pub session: Session,
+    /// Whether this is the session of the token making the request
+    pub current: bool,
+}
+
+#[derive(Debug, Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ViewsQuery {
+    /// Number of days to report, counting today (default 30, at most 365)
+    pub days: Option<u32>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DailyViews {
+    /// UTC date, `YYYY-MM-DD`
+    pub day: String,
+    pub views: i64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PostViews {
+    pub post_id: String,
+    pub title: String,
+    pub slug: String,
+    pub total: i64,
+    /// Days without views are omitted
+    pub daily: Vec<DailyViews>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ViewsReport {
+    /// First day covered, `YYYY-MM-DD`
+    pub since: String,
+    pub days: u32,
+    /// Most viewed first
+    pub posts: Vec<PostViews>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AuditPage {
+    pub entries: Vec<AuditEntry>,
+    pub page: u32,
+    pub per_page: u32,
+    pub total: i64,
+    pub total_pages: u32,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RevokedResponse {
+    pub message: String,
+    pub revoked: u64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MessageResponse {
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorResponse {
+    pub error: String,
+}
@@ -0,0 +1,127 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use tera::{Context, Tera};
+
+pub const DEFAULT_THEME: &str = "default";
+const TEMPLATES_DIR: &str = "templates";
+const THEMES_DIR: &str = "themes";
+
+/// Which theme to render with, and whether to pick up template edits
+/// without a restart.
+#[derive(Debug, Clone)]
+pub struct ThemeConfig {
+    pub name: String,
+    pub reload: bool,
+}
+
+impl ThemeConfig {
+    pub fn from_env() -> Self {
+        let reload = env::var("TEMPLATE_RELOAD")
+            .map(|v| matches!(v.as_str(), "1" | "true" | "yes"))
+            .unwrap_or(false);
+
+        Self {
+            name: env::var("THEME").unwrap_or_else(|_| DEFAULT_THEME.to_string()),
+            reload,
+        }
+    }
+
+    /// `themes/<name>`, or `None` for the built-in templates.
+    pub fn dir(&self) -> Option<PathBuf> {
+        (self.name != DEFAULT_THEME).then(|| Path::new(THEMES_DIR).join(&self.name))
+    }
+}
+
+/// The Tera instance shared by all workers. A theme only has to provide
+/// the templates it changes; the rest fall back to `templates/`.
+pub struct Templates {
+    config: ThemeConfig,
+    tera: RwLock<Tera>,
+}
+
+impl Templates {
+    pub fn load(config: ThemeConfig) -> Result<Self, tera::Error> {
+        let tera = build(&config)?;
+        Ok(Self { config, tera: RwLock::new(tera) })
+    }
+
+    pub fn render(&self, name: &str, ctx: &Context) -> Result<String, tera::Error> {
+        self.tera.read().unwrap().render(name, ctx)
+    }
+
+    /// Rebuilds from disk. A template that fails to parse is logged and the
+    /// previous templates stay in use, so a typo never takes the site down.
+    pub fn reload(&self) {
+        match build(&self.config) {
+            Ok(tera) => {
+                *self.tera.write().unwrap() = tera;
+                log::info!("Templates reloaded");
+            }
+            Err(e) => log::error!("Template reload failed, keeping previous templates: {:?}", e),
+        }
+    }
+
+    /// Reloads whenever a file under `templates/` or the theme directory
+    /// changes. Reloading stops when the returned watcher is dropped.
+    pub fn watch(self: &Arc<Self>) -> notify::Result<RecommendedWatcher> {
+        let templates = Arc::clone(self);
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            match event {
+                Ok(event) if !event.kind.is_access() => templates.reload(),
+                Ok(_) => {}
+                Err(e) => log::warn!("Template watcher error: {}", e),
+            }
+        })?;
+
+        watcher.watch(Path::new(TEMPLATES_DIR), RecursiveMode::Recursive)?;
+        if let Some(dir) = self.config.dir() {
+            watcher.watch(&dir, RecursiveMode::Recursive)?;
+        }
+        Ok(watcher)
+    }
+}
+
+fn build(config: &ThemeConfig) -> Result<Tera, tera::Error> {
+    let base = Tera::new(&format!("{}/**/*.html", TEMPLATES_DIR))?;
+
+    let Some(dir) = config.dir() else {
+        return Ok(base);
+    };
+    if !dir.is_dir() {
+        return Err(tera::Error::msg(format!("theme directory {} does not exist", dir.display())));
+    }
+
+    let mut themed = Tera::new(&format!("{}/**/*.html", dir.display()))?;
+    themed.extend(&base)?;
+    Ok(themed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(name: &str) -> ThemeConfig {
+        ThemeConfig { name: name.to_string(), reload: false }
+    }
+
+    #[test]
+    fn test_theme_falls_back_to_default_templates() {
+        let templates = Templates::load(config("minimal")).unwrap();
+        let mut ctx = Context::new();
+        ctx.insert("title", "Home");
+        ctx.insert("posts", &Vec::<String>::new());
+
+        let html = templates.render("blog/index.html", &ctx).unwrap();
+        assert!(html.contains("masthead"));
+        assert!(html.contains("No posts yet"));
+    }
+
+    #[test]
+    fn test_unknown_theme_is_an_error() {
+        assert!(config(DEFAULT_THEME).dir().is_none());
+        assert!(Templates::load(config("no-such-theme")).is_err());
+    }
+}
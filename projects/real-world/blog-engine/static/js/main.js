@@ -12,8 +12,11 @@ function formatDate(dateString) {
 }
 
 // Auth helpers
-function setAuthToken(token) {
+function setAuthToken(token, refreshToken) {
     localStorage.setItem('auth_token', token);
+    if (refreshToken) {
+        localStorage.setItem('refresh_token', refreshToken);
+    }
 }
 
 function getAuthToken() {
@@ -22,10 +25,32 @@ function getAuthToken() {
 
 function removeAuthToken() {
     localStorage.removeItem('auth_token');
+    localStorage.removeItem('refresh_token');
+}
+
+// Access tokens are short-lived; trade the refresh token for a new pair.
+async function refreshAuthToken() {
+    const refreshToken = localStorage.getItem('refresh_token');
+    if (!refreshToken) return false;
+
+    const response = await fetch('/api/v1/auth/refresh', {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ refresh_token: refreshToken })
+    });
+
+    if (!response.ok) {
+        removeAuthToken();
+        return false;
+    }
+
+    const result = await response.json();
+    setAuthToken(result.token, result.refresh_token);
+    return true;
 }
 
 // API helpers
-async function apiRequest(url, options = {}) {
+async function apiRequest(url, options = {}, retry = true) {
     const token = getAuthToken();
     const headers = {
         'Content-Type': 'application/json',
@@ -41,5 +66,9 @@ async function apiRequest(url, options = {}) {
         headers
     });
 
+    if (response.status === 401 && retry && await refreshAuthToken()) {
+        return apiRequest(url, options, false);
+    }
+
     return response;
 }
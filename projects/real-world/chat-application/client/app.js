@@ -4,6 +4,7 @@ class ChatClient {
         this.username = null;
         this.currentRoom = null;
         this.connected = false;
+        this.unreadMentions = 0;
         
         this.initElements();
         this.attachEventListeners();
@@ -99,9 +100,35 @@ class ChatClient {
             case 'UserLeft':
                 this.addSystemMessage(`${data.username} left ${data.room}`);
                 break;
+            case 'RoomExport':
+                this.downloadFile(data.filename, data.mime_type, data.content);
+                this.addSystemMessage(`Exported #${data.room} to ${data.filename}`);
+                break;
+            case 'Mention':
+                // Mentions in the open room are already highlighted inline
+                if (data.room !== this.currentRoom) {
+                    this.addMentionMessage(data.from, data.room, data.content, data.timestamp);
+                }
+                this.setUnreadMentions(data.unread);
+                break;
+            case 'MentionCount':
+                this.setUnreadMentions(data.unread);
+                break;
         }
     }
 
+    setUnreadMentions(count) {
+        this.unreadMentions = count;
+        const badge = count > 0 ? ` 🔔 ${count}` : '';
+        this.usernameDisplay.textContent = `👤 ${this.username}${badge}`;
+    }
+
+    mentionsMe(content) {
+        if (!this.username) return false;
+        const escaped = this.username.replace(/[.*+?^${}()|[\]\\]/g, '\\$&');
+        return new RegExp(`(^|[^\\w-])@${escaped}(?![\\w-])`).test(content);
+    }
+
     setUsername(name) {
         this.username = name;
         this.usernameDisplay.textContent = `👤 ${name}`;
@@ -167,6 +194,30 @@ class ChatClient {
             case '/rooms':
                 this.send({ type: 'ListRooms' });
                 break;
+            case '/mentions':
+                this.send({ type: 'ClearMentions' });
+                this.addSystemMessage('Mentions marked as read');
+                break;
+            case '/admin':
+                if (parts[1]) {
+                    this.send({ type: 'AdminLogin', token: parts[1] });
+                } else {
+                    this.addSystemMessage('Usage: /admin <token>');
+                }
+                break;
+            case '/export': {
+                // A lone format argument means the current room
+                const formats = ['json', 'html'];
+                const args = parts.slice(1);
+                const format = args.find(arg => formats.includes(arg)) || 'json';
+                const room = args.find(arg => !formats.includes(arg)) || this.currentRoom;
+                if (!room) {
+                    this.addSystemMessage('Usage: /export [room] [json|html]');
+                    break;
+                }
+                this.send({ type: 'ExportRoom', room, format });
+                break;
+            }
             default:
                 this.addSystemMessage('Unknown command');
         }
@@ -178,9 +229,19 @@ class ChatClient {
         }
     }
 
+    downloadFile(filename, mimeType, content) {
+        const blob = new Blob([content], { type: mimeType });
+        const url = URL.createObjectURL(blob);
+        const link = document.createElement('a');
+        link.href = url;
+        link.download = filename;
+        link.click();
+        URL.revokeObjectURL(url);
+    }
+
     addMessage(username, content, timestamp) {
         const messageEl = document.createElement('div');
-        messageEl.className = 'message';
+        messageEl.className = this.mentionsMe(content) ? 'message mention' : 'message';
         messageEl.innerHTML = `
             <div class="message-header">
                 <span class="message-username">${this.escapeHtml(username)}</span>
@@ -206,6 +267,20 @@ class ChatClient {
         this.scrollToBottom();
     }
 
+    addMentionMessage(from, room, content, timestamp) {
+        const messageEl = document.createElement('div');
+        messageEl.className = 'message mention';
+        messageEl.innerHTML = `
+            <div class="message-header">
+                <span class="message-username">🔔 ${this.escapeHtml(from)} mentioned you in #${this.escapeHtml(room)}</span>
+                <span class="message-time">${this.formatTime(timestamp)}</span>
+            </div>
+            <div class="message-content">${this.escapeHtml(content)}</div>
+        `;
+        this.messagesDiv.appendChild(messageEl);
+        this.scrollToBottom();
+    }
+
     addSystemMessage(content) {
         const messageEl = document.createElement('div');
         messageEl.className = 'message system';
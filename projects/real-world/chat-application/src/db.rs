@@ -29,6 +29,17 @@ impl Database {
         .execute(&self.pool)
         .await?;
 
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS mention_counts (
+                username TEXT PRIMARY KEY,
+                unread INTEGER NOT NULL DEFAULT 0
+            )
+            "#
+        )
+        .execute(&self.pool)
+        .await?;
+
         sqlx::query(
             r#"
             CREATE TABLE IF NOT EXISTS rooms (
@@ -83,6 +94,56 @@ impl Database {
         Ok(messages)
     }
 
+    pub async fn get_room_history(&self, room: &str) -> Result<Vec<ChatMessage>, Box<dyn Error>> {
+        let rows = sqlx::query(
+            "SELECT * FROM messages WHERE room = ? AND is_private = 0 ORDER BY timestamp ASC"
+        )
+        .bind(room)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| ChatMessage {
+                id: row.get("id"),
+                room: row.get("room"),
+                username: row.get("username"),
+                content: row.get("content"),
+                timestamp: row.get("timestamp"),
+                is_private: row.get("is_private"),
+            })
+            .collect())
+    }
+
+    /// Bumps the user's unread mention count and returns the new total.
+    pub async fn increment_mentions(&self, username: &str) -> Result<i64, Box<dyn Error>> {
+        let row = sqlx::query(
+            "INSERT INTO mention_counts (username, unread) VALUES (?, 1)
+             ON CONFLICT(username) DO UPDATE SET unread = unread + 1
+             RETURNING unread"
+        )
+        .bind(username)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(row.get("unread"))
+    }
+
+    pub async fn get_unread_mentions(&self, username: &str) -> Result<i64, Box<dyn Error>> {
+        let row = sqlx::query("SELECT unread FROM mention_counts WHERE username = ?")
+            .bind(username)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.map(|row| row.get("unread")).unwrap_or(0))
+    }
+
+    pub async fn clear_mentions(&self, username: &str) -> Result<(), Box<dyn Error>> {
+        sqlx::query("UPDATE mention_counts SET unread = 0 WHERE username = ?")
+            .bind(username)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
     pub async fn create_room(&self, name: &str) -> Result<(), Box<dyn Error>> {
         let now = chrono::Utc::now().to_rfc3339();
         sqlx::query("INSERT OR IGNORE INTO rooms (name, created_at) VALUES (?, ?)")
@@ -101,3 +162,22 @@ impl Database {
         Ok(rows.iter().map(|row| row.get("name")).collect())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_mention_counts_persist_and_clear() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        db.init().await.unwrap();
+
+        assert_eq!(db.get_unread_mentions("alice").await.unwrap(), 0);
+        assert_eq!(db.increment_mentions("alice").await.unwrap(), 1);
+        assert_eq!(db.increment_mentions("alice").await.unwrap(), 2);
+        assert_eq!(db.get_unread_mentions("alice").await.unwrap(), 2);
+
+        db.clear_mentions("alice").await.unwrap();
+        assert_eq!(db.get_unread_mentions("alice").await.unwrap(), 0);
+    }
+}
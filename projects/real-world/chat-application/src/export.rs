@@ -0,0 +1,139 @@
+use serde::Serialize;
+
+use crate::models::{ChatMessage, ExportFormat};
+
+#[derive(Debug, Serialize)]
+pub struct RoomArchive {
+    pub room: String,
+    pub exported_at: String,
+    pub message_count: usize,
+    pub messages: Vec<ArchivedMessage>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArchivedMessage {
+    pub username: String,
+    pub content: String,
+    pub timestamp: String,
+}
+
+impl RoomArchive {
+    pub fn new(room: &str, messages: Vec<ChatMessage>) -> Self {
+        let messages: Vec<ArchivedMessage> = messages
+            .into_iter()
+            .map(|msg| ArchivedMessage {
+                username: msg.username,
+                content: msg.content,
+                timestamp: msg.timestamp,
+            })
+            .collect();
+
+        Self {
+            room: room.to_string(),
+            exported_at: chrono::Utc::now().to_rfc3339(),
+            message_count: messages.len(),
+            messages,
+        }
+    }
+
+    pub fn filename(&self, format: ExportFormat) -> String {
+        let date = self.exported_at.get(..10).unwrap_or("archive");
+        format!("{}-{}.{}", self.room, date, format.extension())
+    }
+
+    pub fn render(&self, format: ExportFormat) -> Result<String, serde_json::Error> {
+        match format {
+            ExportFormat::Json => serde_json::to_string_pretty(self),
+            ExportFormat::Html => Ok(self.to_html()),
+        }
+    }
+
+    fn to_html(&self) -> String {
+        let mut rows = String::new();
+        for msg in &self.messages {
+            rows.push_str(&format!(
+                "        <li><time>{}</time> <strong>{}</strong>: {}</li>\n",
+                escape_html(&msg.timestamp),
+                escape_html(&msg.username),
+                escape_html(&msg.content),
+            ));
+        }
+
+        format!(
+            r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <title>#{room} archive</title>
+    <style>
+        body {{ font-family: sans-serif; max-width: 800px; margin: 2rem auto; }}
+        ul {{ list-style: none; padding: 0; }}
+        li {{ padding: 0.25rem 0; border-bottom: 1px solid #eee; }}
+        time {{ color: #888; font-size: 0.85em; }}
+    </style>
+</head>
+<body>
+    <h1>#{room}</h1>
+    <p>{count} messages, exported {exported_at}</p>
+    <ul>
+{rows}    </ul>
+</body>
+</html>
+"#,
+            room = escape_html(&self.room),
+            count = self.message_count,
+            exported_at = escape_html(&self.exported_at),
+            rows = rows,
+        )
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_archive() -> RoomArchive {
+        RoomArchive::new(
+            "general",
+            vec![ChatMessage {
+                id: "1".to_string(),
+                room: "general".to_string(),
+                username: "alice".to_string(),
+                content: "<b>hi</b> & bye".to_string(),
+                timestamp: "2024-01-01T12:00:00+00:00".to_string(),
+                is_private: false,
+            }],
+        )
+    }
+
+    #[test]
+    fn test_json_export() {
+        let json = sample_archive().render(ExportFormat::Json).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["room"], "general");
+        assert_eq!(value["message_count"], 1);
+        assert_eq!(value["messages"][0]["username"], "alice");
+    }
+
+    #[test]
+    fn test_html_export_escapes_content() {
+        let html = sample_archive().render(ExportFormat::Html).unwrap();
+        assert!(html.contains("&lt;b&gt;hi&lt;/b&gt; &amp; bye"));
+        assert!(!html.contains("<b>hi</b>"));
+    }
+}
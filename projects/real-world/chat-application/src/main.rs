@@ -7,6 +7,8 @@ use std::sync::Arc;
 mod server;
 mod models;
 mod db;
+mod export;
+mod mentions;
 
 use server::ChatServer;
 use models::ClientMessage;
@@ -21,7 +23,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     
     log::info!("Database initialized");
 
-    let server = Arc::new(ChatServer::new(db));
+    let admin_token = std::env::var("CHAT_ADMIN_TOKEN").ok().filter(|t| !t.is_empty());
+    if admin_token.is_none() {
+        log::info!("CHAT_ADMIN_TOKEN is not set; admin commands are disabled");
+    }
+
+    let server = Arc::new(ChatServer::new(db, admin_token));
     let addr = "127.0.0.1:9001";
     let listener = TcpListener::bind(addr).await?;
 
@@ -52,6 +59,7 @@ async fn handle_connection(
     let client_id = uuid::Uuid::new_v4().to_string();
     let mut username: Option<String> = None;
     let mut current_room: Option<String> = None;
+    let mut is_admin = false;
 
     log::info!("Client {} connected", client_id);
 
@@ -126,6 +134,24 @@ async fn handle_connection(
                         let msg = format!("Available rooms: {}", rooms.join(", "));
                         server.send_system_message(&client_id, &msg).await;
                     }
+                    ClientMessage::AdminLogin { token } => {
+                        is_admin = server.is_admin_token(&token);
+                        let reply = if is_admin { "Admin commands unlocked" } else { "Invalid admin token" };
+                        server.send_system_message(&client_id, reply).await;
+                    }
+                    ClientMessage::ExportRoom { room, format } => {
+                        if !is_admin {
+                            server.send_system_message(&client_id, "Exporting rooms requires /admin").await;
+                            continue;
+                        }
+
+                        server.export_room(&client_id, &room, format).await;
+                    }
+                    ClientMessage::ClearMentions => {
+                        if let Some(ref user) = username {
+                            server.clear_mentions(&client_id, user).await;
+                        }
+                    }
                 }
             }
         } else if let Message::Close(_) = msg {
@@ -0,0 +1,55 @@
+/// Extracts `@username` mentions from a message, in order of first
+/// appearance and without duplicates. An `@` directly after a word
+/// character (as in `alice@example.com`) is not a mention.
+pub fn parse_mentions(content: &str) -> Vec<String> {
+    let mut mentions: Vec<String> = Vec::new();
+    let mut prev: Option<char> = None;
+    let mut chars = content.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        let starts_mention = c == '@' && !prev.is_some_and(is_name_char);
+        prev = Some(c);
+        if !starts_mention {
+            continue;
+        }
+
+        let start = i + c.len_utf8();
+        let mut end = start;
+        while let Some(&(j, next)) = chars.peek() {
+            if !is_name_char(next) {
+                break;
+            }
+            end = j + next.len_utf8();
+            prev = Some(next);
+            chars.next();
+        }
+
+        let name = &content[start..end];
+        if !name.is_empty() && !mentions.iter().any(|m| m == name) {
+            mentions.push(name.to_string());
+        }
+    }
+
+    mentions
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mentions() {
+        assert_eq!(parse_mentions("hey @alice, ask @bob_2 and @alice"), vec!["alice", "bob_2"]);
+        assert_eq!(parse_mentions("(@carol)"), vec!["carol"]);
+    }
+
+    #[test]
+    fn test_ignores_emails_and_bare_at() {
+        assert!(parse_mentions("mail alice@example.com").is_empty());
+        assert!(parse_mentions("meet @ noon").is_empty());
+    }
+}
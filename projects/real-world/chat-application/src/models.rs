@@ -8,6 +8,32 @@ pub enum ClientMessage {
     SendMessage { content: String },
     PrivateMessage { to: String, content: String },
     ListRooms,
+    AdminLogin { token: String },
+    ExportRoom { room: String, format: ExportFormat },
+    ClearMentions,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Json,
+    Html,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Html => "html",
+        }
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "application/json",
+            ExportFormat::Html => "text/html",
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -38,6 +64,22 @@ pub enum ServerMessage {
     RoomsList { 
         rooms: Vec<String> 
     },
+    RoomExport {
+        room: String,
+        filename: String,
+        mime_type: String,
+        content: String,
+    },
+    Mention {
+        from: String,
+        room: String,
+        content: String,
+        timestamp: String,
+        unread: i64,
+    },
+    MentionCount {
+        unread: i64,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
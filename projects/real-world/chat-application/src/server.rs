@@ -3,22 +3,27 @@ use tokio::sync::mpsc::UnboundedSender;
 use tokio_tungstenite::tungstenite::protocol::Message;
 
 use crate::db::Database;
-use crate::models::{ServerMessage, ChatMessage};
+use crate::export::RoomArchive;
+use crate::mentions::parse_mentions;
+use crate::models::{ServerMessage, ChatMessage, ExportFormat};
 
 pub struct ChatServer {
     clients: DashMap<String, UnboundedSender<Message>>,
     usernames: DashMap<String, String>,
     rooms: DashMap<String, Vec<String>>,
     db: Database,
+    // Unlocks admin commands such as /export; None disables them
+    admin_token: Option<String>,
 }
 
 impl ChatServer {
-    pub fn new(db: Database) -> Self {
+    pub fn new(db: Database, admin_token: Option<String>) -> Self {
         let server = Self {
             clients: DashMap::new(),
             usernames: DashMap::new(),
             rooms: DashMap::new(),
             db,
+            admin_token,
         };
 
         server.create_default_rooms();
@@ -53,12 +58,19 @@ impl ChatServer {
 
     pub async fn set_username(&self, client_id: &str, username: &str) {
         self.usernames.insert(client_id.to_string(), username.to_string());
+
+        // Restore mentions that arrived while the user was away
+        let unread = self.db.get_unread_mentions(username).await.unwrap_or_else(|e| {
+            log::error!("Failed to load mention count for {}: {}", username, e);
+            0
+        });
+        self.send_to_client(client_id, ServerMessage::MentionCount { unread }).await;
     }
 
     pub async fn join_room(&self, client_id: &str, room: &str) {
         self.rooms
             .entry(room.to_string())
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(client_id.to_string());
 
         let db = self.db.clone();
@@ -118,6 +130,49 @@ impl ChatServer {
         }
 
         self.broadcast_to_room(room, content, username).await;
+        self.notify_mentions(&message).await;
+    }
+
+    /// Sends a Mention event to every connection of each user named in the
+    /// message, whichever room they are in, and persists their unread count.
+    async fn notify_mentions(&self, message: &ChatMessage) {
+        for mentioned in parse_mentions(&message.content) {
+            if mentioned == message.username {
+                continue;
+            }
+
+            let unread = match self.db.increment_mentions(&mentioned).await {
+                Ok(unread) => unread,
+                Err(e) => {
+                    log::error!("Failed to record mention of {}: {}", mentioned, e);
+                    continue;
+                }
+            };
+
+            let client_ids: Vec<String> = self.usernames
+                .iter()
+                .filter(|entry| *entry.value() == mentioned)
+                .map(|entry| entry.key().clone())
+                .collect();
+
+            let msg = ServerMessage::Mention {
+                from: message.username.clone(),
+                room: message.room.clone(),
+                content: message.content.clone(),
+                timestamp: message.timestamp.clone(),
+                unread,
+            };
+            for client_id in client_ids {
+                self.send_to_client(&client_id, msg.clone()).await;
+            }
+        }
+    }
+
+    pub async fn clear_mentions(&self, client_id: &str, username: &str) {
+        if let Err(e) = self.db.clear_mentions(username).await {
+            log::error!("Failed to clear mentions for {}: {}", username, e);
+        }
+        self.send_to_client(client_id, ServerMessage::MentionCount { unread: 0 }).await;
     }
 
     pub async fn send_private_message(&self, from_id: &str, from_username: &str, to_username: &str, content: &str) {
@@ -153,6 +208,45 @@ impl ChatServer {
         }
     }
 
+    pub fn is_admin_token(&self, token: &str) -> bool {
+        self.admin_token
+            .as_deref()
+            .is_some_and(|expected| tokens_match(expected, token))
+    }
+
+    pub async fn export_room(&self, client_id: &str, room: &str, format: ExportFormat) {
+        let messages = match self.db.get_room_history(room).await {
+            Ok(messages) => Some(messages),
+            Err(e) => {
+                log::error!("Failed to load history for {}: {}", room, e);
+                None
+            }
+        };
+
+        let Some(messages) = messages else {
+            self.send_system_message(client_id, "Failed to export room").await;
+            return;
+        };
+
+        let archive = RoomArchive::new(room, messages);
+        match archive.render(format) {
+            Ok(content) => {
+                log::info!("Exported {} messages from {}", archive.message_count, room);
+                let msg = ServerMessage::RoomExport {
+                    room: room.to_string(),
+                    filename: archive.filename(format),
+                    mime_type: format.mime_type().to_string(),
+                    content,
+                };
+                self.send_to_client(client_id, msg).await;
+            }
+            Err(e) => {
+                log::error!("Failed to render export for {}: {}", room, e);
+                self.send_system_message(client_id, "Failed to export room").await;
+            }
+        }
+    }
+
     pub async fn send_system_message(&self, client_id: &str, content: &str) {
         let msg = ServerMessage::SystemMessage {
             content: content.to_string(),
@@ -172,3 +266,27 @@ impl ChatServer {
         self.rooms.iter().map(|entry| entry.key().clone()).collect()
     }
 }
+
+// Compares every byte regardless of where the first mismatch is, so response
+// timing does not reveal how much of a guessed token was right
+fn tokens_match(expected: &str, given: &str) -> bool {
+    expected.len() == given.len()
+        && expected
+            .bytes()
+            .zip(given.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens_match() {
+        assert!(tokens_match("s3cret", "s3cret"));
+        assert!(!tokens_match("s3cret", "s3creT"));
+        assert!(!tokens_match("s3cret", "s3cret!"));
+        assert!(!tokens_match("s3cret", ""));
+    }
+}
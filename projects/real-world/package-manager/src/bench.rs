@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::models::{Hooks, Manifest, PackageInfo, RegistryPackage, ResolvedPackage};
+use crate::registry::Registry;
+use crate::resolver;
+
+/// Shape of a generated dependency graph for `pkgmgr bench`.
+#[derive(Debug, Clone, Copy)]
+pub struct GraphSpec {
+    pub packages: usize,
+    pub versions: usize,
+    pub deps_per_version: usize,
+    pub seed: u64,
+}
+
+/// Generates a layered registry where `pkg-i` only depends on a few of the
+/// next packages, so many paths share dependencies. Each edge accepts a
+/// narrow, randomly placed window of versions, which makes the newest
+/// version of one parent disagree with the newest version of another.
+pub fn generate(spec: GraphSpec) -> (Manifest, Registry) {
+    let mut rng = spec.seed.max(1);
+    let mut next = move |bound: usize| {
+        rng ^= rng << 13;
+        rng ^= rng >> 7;
+        rng ^= rng << 17;
+        (rng % bound.max(1) as u64) as usize
+    };
+
+    let name = |i: usize| format!("pkg-{:04}", i);
+    let mut packages = Vec::with_capacity(spec.packages * spec.versions);
+
+    for i in 0..spec.packages {
+        for j in 0..spec.versions {
+            let mut dependencies = HashMap::new();
+            let downstream = (i + 1..spec.packages.min(i + 12)).count();
+            for _ in 0..spec.deps_per_version.min(downstream) {
+                let target = i + 1 + next(downstream);
+                let high = next(j + 1);
+                let low = high.saturating_sub(3);
+                dependencies.insert(name(target), format!(">=1.{}.0, <=1.{}.0", low, high));
+            }
+
+            packages.push(RegistryPackage {
+                name: name(i),
+                version: format!("1.{}.0", j),
+                authors: vec!["bench".to_string()],
+                description: None,
+                dependencies,
+            });
+        }
+    }
+
+    let roots = spec.packages.min(4);
+    let manifest = Manifest {
+        package: PackageInfo {
+            name: "bench-root".to_string(),
+            version: "0.1.0".to_string(),
+            authors: Vec::new(),
+            description: None,
+        },
+        dependencies: (0..roots).map(|i| (name(i), "*".to_string())).collect(),
+        hooks: Hooks::default(),
+    };
+
+    (manifest, Registry::from_packages("bench", packages))
+}
+
+/// Packages the resolution picked more than one version of.
+fn duplicate_versions(resolved: &[ResolvedPackage]) -> usize {
+    let mut versions: HashMap<&str, Vec<&semver::Version>> = HashMap::new();
+    for package in resolved {
+        let entry = versions.entry(package.name.as_str()).or_default();
+        if !entry.contains(&&package.version) {
+            entry.push(&package.version);
+        }
+    }
+    versions.values().filter(|v| v.len() > 1).count()
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!("{:.2}ms", duration.as_secs_f64() * 1000.0)
+}
+
+pub fn run(spec: GraphSpec) -> Result<()> {
+    println!(
+        "{} {} packages × {} versions ({} versions, seed {})",
+        "📊 Resolver benchmark:".cyan().bold(),
+        spec.packages,
+        spec.versions,
+        spec.packages * spec.versions,
+        spec.seed
+    );
+    println!();
+
+    let (manifest, registry) = generate(spec);
+
+    println!("  {:<9} {:>10} {:>9} {:>11}", "resolver", "time", "packages", "duplicated");
+
+    let start = Instant::now();
+    match resolver::resolve_dependencies(&manifest, &registry) {
+        Ok(resolved) => {
+            let duplicated = duplicate_versions(&resolved);
+            println!(
+                "  {:<9} {:>10} {:>9} {:>11}",
+                "default",
+                format_duration(start.elapsed()),
+                resolved.len(),
+                if duplicated == 0 { "0".green() } else { duplicated.to_string().yellow() }
+            );
+        }
+        Err(err) => println!("  {:<9} {:>10} {}", "default", format_duration(start.elapsed()), err.to_string().red()),
+    }
+
+    let start = Instant::now();
+    match resolver::resolve_sat_with_stats(&manifest, &registry) {
+        Ok((resolved, stats)) => {
+            println!(
+                "  {:<9} {:>10} {:>9} {:>11}",
+                "sat",
+                format_duration(start.elapsed()),
+                resolved.len(),
+                duplicate_versions(&resolved).to_string().green()
+            );
+            println!();
+            println!(
+                "  sat: {} decisions, {} conflicts, {} learned clauses, {} propagations",
+                stats.decisions, stats.conflicts, stats.learned, stats.propagations
+            );
+        }
+        Err(err) => println!("  {:<9} {:>10} {}", "sat", format_duration(start.elapsed()), err.to_string().red()),
+    }
+
+    println!();
+    println!(
+        "{}",
+        "`duplicated` counts packages the resolver selected at more than one version.".dimmed()
+    );
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 
 #[derive(Parser)]
 #[command(name = "pkgmgr")]
@@ -7,6 +7,23 @@ use clap::{Parser, Subcommand};
 pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
+
+    #[arg(long, global = true, help = "Resolve only from the local registry cache and installed packages")]
+    pub offline: bool,
+
+    #[arg(long, global = true, help = "Do not run [hooks] scripts from Package.toml")]
+    pub ignore_scripts: bool,
+
+    #[arg(long, global = true, value_enum, default_value_t = ResolverKind::Default, help = "Dependency resolution backend")]
+    pub resolver: ResolverKind,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ResolverKind {
+    /// Breadth-first walk taking the newest match for each requirement
+    Default,
+    /// SAT search for one version per package that satisfies every requirement
+    Sat,
 }
 
 #[derive(Subcommand)]
@@ -15,6 +32,9 @@ pub enum Commands {
     Install {
         #[arg(help = "Specific package to install")]
         package: Option<String>,
+
+        #[arg(long, help = "Fail instead of changing Package.lock")]
+        frozen: bool,
     },
     
     #[command(about = "Update dependencies")]
@@ -23,6 +43,24 @@ pub enum Commands {
     #[command(about = "Display dependency tree")]
     Tree,
     
+    #[command(about = "Print a reproducibility digest of the lockfile and installed packages")]
+    Fingerprint,
+    
+    #[command(about = "Compare resolvers on a generated dependency graph")]
+    Bench {
+        #[arg(long, default_value_t = 200, help = "Number of packages")]
+        packages: usize,
+
+        #[arg(long, default_value_t = 20, help = "Versions per package")]
+        versions: usize,
+
+        #[arg(long, default_value_t = 3, help = "Dependencies per version")]
+        deps: usize,
+
+        #[arg(long, default_value_t = 42, help = "Random seed")]
+        seed: u64,
+    },
+    
     #[command(about = "Initialize a new package")]
     Init {
         #[arg(help = "Package name")]
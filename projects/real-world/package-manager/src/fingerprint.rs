@@ -0,0 +1,41 @@
+use std::path::Path;
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use walkdir::WalkDir;
+
+/// Hashes the lockfile together with every file under the install directory
+/// into a single hex digest. Two machines that print the same digest have
+/// byte-identical dependency trees.
+pub fn compute_fingerprint(lockfile_path: &str, modules_path: &str) -> Result<String> {
+    let mut hasher = Sha256::new();
+
+    let lockfile = std::fs::read(lockfile_path)
+        .context(format!("Failed to read lockfile: {}", lockfile_path))?;
+    hash_entry(&mut hasher, "lockfile", &lockfile);
+
+    let modules_path = Path::new(modules_path);
+    if modules_path.exists() {
+        let walker = WalkDir::new(modules_path).sort_by_file_name();
+
+        for entry in walker {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+
+            let relative = entry.path().strip_prefix(modules_path)?;
+            let name = relative.to_string_lossy().replace('\\', "/");
+            let content = std::fs::read(entry.path())?;
+            hash_entry(&mut hasher, &name, &content);
+        }
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+fn hash_entry(hasher: &mut Sha256, name: &str, content: &[u8]) {
+    hasher.update((name.len() as u64).to_le_bytes());
+    hasher.update(name.as_bytes());
+    hasher.update((content.len() as u64).to_le_bytes());
+    hasher.update(content);
+}
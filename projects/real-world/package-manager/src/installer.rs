@@ -1,5 +1,6 @@
 use std::path::Path;
-use anyhow::{Context, Result};
+use std::process::Command;
+use anyhow::{Context, Result, anyhow};
 use colored::Colorize;
 
 use crate::models::ResolvedPackage;
@@ -96,3 +97,43 @@ pub fn verify_installation(packages: &[ResolvedPackage]) -> Result<bool> {
 
     Ok(true)
 }
+
+/// Runs a manifest hook script from a fresh scratch directory with a cleared
+/// environment apart from `PATH`, `HOME` and the `PKGMGR_*` paths. The script
+/// must live inside the project, but this is not a sandbox: it runs as the
+/// current user with full filesystem access.
+pub fn run_hook(name: &str, script: &str) -> Result<()> {
+    let project_dir = std::env::current_dir()?.canonicalize()?;
+    let script_path = project_dir
+        .join(script)
+        .canonicalize()
+        .context(format!("Hook script not found: {}", script))?;
+
+    if !script_path.starts_with(&project_dir) {
+        return Err(anyhow!("Hook script {} is outside the project directory", script));
+    }
+
+    // A new directory with a random name, removed again when `scratch` drops
+    let scratch = tempfile::Builder::new()
+        .prefix(&format!("pkgmgr-{}-", name))
+        .tempdir()
+        .context("Failed to create hook directory")?;
+
+    let status = Command::new("sh")
+        .arg(&script_path)
+        .current_dir(scratch.path())
+        .env_clear()
+        .env("PATH", std::env::var_os("PATH").unwrap_or_default())
+        .env("HOME", scratch.path())
+        .env("PKGMGR_HOOK", name)
+        .env("PKGMGR_PROJECT_DIR", &project_dir)
+        .env("PKGMGR_MODULES_DIR", project_dir.join("pkg_modules"))
+        .status()
+        .context(format!("Failed to run {} hook", name))?;
+
+    if !status.success() {
+        return Err(anyhow!("{} hook {} failed with {}", name, script, status));
+    }
+
+    Ok(())
+}
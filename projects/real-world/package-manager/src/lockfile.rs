@@ -1,51 +1,211 @@
 use serde::{Deserialize, Serialize};
-use anyhow::{Context, Result};
-use crate::models::ResolvedPackage;
+use anyhow::{Context, Result, anyhow};
+use sha2::{Sha256, Digest};
+use crate::models::{PackageSource, ResolvedPackage};
+use crate::registry::DEFAULT_REGISTRY;
+
+pub const LOCKFILE_VERSION: u32 = 2;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Lockfile {
-    pub version: String,
+    pub version: u32,
     pub packages: Vec<LockfilePackage>,
+    pub integrity: Integrity,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LockfilePackage {
     pub name: String,
     pub version: String,
-    pub dependencies: Vec<String>,
+    pub source: PackageSource,
     pub checksum: String,
+    /// Resolved dependencies as `"name version"` pairs.
+    pub dependencies: Vec<String>,
+}
+
+/// Digest over every package entry so hand edits or merge accidents in the
+/// lockfile are caught on read instead of silently installed.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Integrity {
+    pub algorithm: String,
+    pub digest: String,
+}
+
+/// The original format: a `"1.0"` version string and bare dependency names.
+#[derive(Debug, Deserialize)]
+struct LockfileV1 {
+    packages: Vec<LockfilePackageV1>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LockfilePackageV1 {
+    name: String,
+    version: String,
+    dependencies: Vec<String>,
+    checksum: String,
 }
 
 impl Lockfile {
     pub fn from_file(path: &str) -> Result<Self> {
         let content = std::fs::read_to_string(path)
             .context(format!("Failed to read lockfile: {}", path))?;
-        let lockfile: Lockfile = toml::from_str(&content)
+        Self::parse(&content)
+    }
+
+    /// Parses any known lockfile format, migrating older ones to the current
+    /// layout in memory. The file on disk is only rewritten on the next install.
+    pub fn parse(content: &str) -> Result<Self> {
+        let raw: toml::Value = toml::from_str(content)
             .context("Failed to parse lockfile")?;
-        Ok(lockfile)
+
+        match raw.get("version") {
+            Some(toml::Value::String(v)) if v == "1.0" => {
+                let v1: LockfileV1 = raw.try_into()
+                    .context("Failed to parse version 1 lockfile")?;
+                Ok(Self::migrate_v1(v1))
+            }
+            Some(toml::Value::Integer(v)) if *v == i64::from(LOCKFILE_VERSION) => {
+                let lockfile: Lockfile = raw.try_into()
+                    .context("Failed to parse lockfile")?;
+                lockfile.verify_integrity()?;
+                Ok(lockfile)
+            }
+            Some(other) => Err(anyhow!("Unsupported lockfile version: {}", other)),
+            None => Err(anyhow!("Lockfile is missing a format version")),
+        }
     }
-}
 
-pub fn generate_lockfile(packages: &[ResolvedPackage], path: &str) -> Result<()> {
-    let mut lockfile_packages = Vec::new();
+    fn migrate_v1(v1: LockfileV1) -> Self {
+        let versions: Vec<(String, String)> = v1.packages
+            .iter()
+            .map(|p| (p.name.clone(), p.version.clone()))
+            .collect();
 
-    for package in packages {
-        let checksum = calculate_checksum(&package.name, &package.version.to_string());
-        
-        lockfile_packages.push(LockfilePackage {
-            name: package.name.clone(),
-            version: package.version.to_string(),
-            dependencies: package.dependencies.clone(),
-            checksum,
-        });
+        let packages = v1.packages
+            .into_iter()
+            .map(|p| LockfilePackage {
+                dependencies: p.dependencies
+                    .iter()
+                    .map(|dep| match versions.iter().find(|(name, _)| name == dep) {
+                        Some((name, version)) => format!("{} {}", name, version),
+                        None => dep.clone(),
+                    })
+                    .collect(),
+                name: p.name,
+                version: p.version,
+                source: PackageSource::Registry(DEFAULT_REGISTRY.to_string()),
+                checksum: p.checksum,
+            })
+            .collect();
+
+        Self::with_packages(packages)
     }
 
-    lockfile_packages.sort_by(|a, b| a.name.cmp(&b.name));
+    pub fn from_packages(packages: &[ResolvedPackage]) -> Self {
+        let mut lockfile_packages = Vec::new();
 
-    let lockfile = Lockfile {
-        version: "1.0".to_string(),
-        packages: lockfile_packages,
-    };
+        for package in packages {
+            let checksum = calculate_checksum(&package.name, &package.version.to_string());
+            let mut dependencies: Vec<String> = package.dependencies
+                .iter()
+                .map(|dep| match packages.iter().find(|p| &p.name == dep) {
+                    Some(resolved) => format!("{} {}", resolved.name, resolved.version),
+                    None => dep.clone(),
+                })
+                .collect();
+            dependencies.sort();
+
+            lockfile_packages.push(LockfilePackage {
+                name: package.name.clone(),
+                version: package.version.to_string(),
+                source: package.source.clone(),
+                checksum,
+                dependencies,
+            });
+        }
+
+        Self::with_packages(lockfile_packages)
+    }
+
+    fn with_packages(mut packages: Vec<LockfilePackage>) -> Self {
+        packages.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let integrity = Integrity {
+            algorithm: "sha256".to_string(),
+            digest: packages_digest(&packages),
+        };
+
+        Lockfile {
+            version: LOCKFILE_VERSION,
+            packages,
+            integrity,
+        }
+    }
+
+    fn verify_integrity(&self) -> Result<()> {
+        if self.integrity.algorithm != "sha256" {
+            return Err(anyhow!("Unsupported integrity algorithm: {}", self.integrity.algorithm));
+        }
+
+        if self.integrity.digest != packages_digest(&self.packages) {
+            return Err(anyhow!(
+                "Lockfile integrity check failed; it was modified by hand. Run `pkgmgr update` to regenerate it"
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Describes every difference between this lockfile and `other`, one
+    /// line per added, removed, or changed package.
+    pub fn changes(&self, other: &Lockfile) -> Vec<String> {
+        let mut changes = Vec::new();
+
+        for old in &self.packages {
+            match other.packages.iter().find(|p| p.name == old.name) {
+                None => changes.push(format!("- {} v{}", old.name, old.version)),
+                Some(new) if new.version != old.version => {
+                    changes.push(format!("~ {} v{} -> v{}", old.name, old.version, new.version));
+                }
+                Some(new) if new.source != old.source => {
+                    changes.push(format!("~ {} v{} ({} -> {})", old.name, old.version, old.source, new.source));
+                }
+                Some(new) if !new.same_contents(old) => {
+                    changes.push(format!("~ {} v{} (dependencies or checksum)", old.name, old.version));
+                }
+                Some(_) => {}
+            }
+        }
+
+        for new in &other.packages {
+            if !self.packages.iter().any(|p| p.name == new.name) {
+                changes.push(format!("+ {} v{}", new.name, new.version));
+            }
+        }
+
+        changes
+    }
+}
+
+impl LockfilePackage {
+    pub fn dependency_names(&self) -> impl Iterator<Item = &str> {
+        self.dependencies
+            .iter()
+            .filter_map(|dep| dep.split_whitespace().next())
+    }
+
+    fn same_contents(&self, other: &LockfilePackage) -> bool {
+        let mut deps = self.dependencies.clone();
+        let mut other_deps = other.dependencies.clone();
+        deps.sort();
+        other_deps.sort();
+
+        self.version == other.version && self.checksum == other.checksum && deps == other_deps
+    }
+}
+
+pub fn generate_lockfile(packages: &[ResolvedPackage], path: &str) -> Result<()> {
+    let lockfile = Lockfile::from_packages(packages);
 
     let toml = toml::to_string_pretty(&lockfile)?;
     std::fs::write(path, toml)?;
@@ -53,37 +213,114 @@ pub fn generate_lockfile(packages: &[ResolvedPackage], path: &str) -> Result<()>
     Ok(())
 }
 
+/// Fails unless the lockfile at `path` already describes exactly `packages`.
+pub fn ensure_frozen(packages: &[ResolvedPackage], path: &str) -> Result<()> {
+    if !std::path::Path::new(path).exists() {
+        return Err(anyhow!("--frozen requires an existing {}", path));
+    }
+
+    let existing = Lockfile::from_file(path)?;
+    let changes = existing.changes(&Lockfile::from_packages(packages));
+
+    if !changes.is_empty() {
+        return Err(anyhow!(
+            "{} is out of date and --frozen forbids updating it:\n  {}",
+            path,
+            changes.join("\n  ")
+        ));
+    }
+
+    Ok(())
+}
+
 fn calculate_checksum(name: &str, version: &str) -> String {
-    use sha2::{Sha256, Digest};
-    
     let mut hasher = Sha256::new();
     hasher.update(name.as_bytes());
     hasher.update(version.as_bytes());
     let result = hasher.finalize();
-    
+
     format!("{:x}", result)
 }
 
-pub fn verify_lockfile(packages: &[ResolvedPackage], path: &str) -> Result<bool> {
-    if !std::path::Path::new(path).exists() {
-        return Ok(false);
+fn packages_digest(packages: &[LockfilePackage]) -> String {
+    let mut hasher = Sha256::new();
+
+    for package in packages {
+        hasher.update(package.name.as_bytes());
+        hasher.update(b"\0");
+        hasher.update(package.version.as_bytes());
+        hasher.update(b"\0");
+        hasher.update(package.source.to_string().as_bytes());
+        hasher.update(b"\0");
+        hasher.update(package.checksum.as_bytes());
+        for dep in &package.dependencies {
+            hasher.update(b"\0");
+            hasher.update(dep.as_bytes());
+        }
+        hasher.update(b"\n");
     }
 
-    let lockfile = Lockfile::from_file(path)?;
+    format!("{:x}", hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V1_LOCKFILE: &str = r#"
+version = "1.0"
+
+[[packages]]
+name = "actix-web"
+version = "4.4.0"
+dependencies = ["tokio", "serde"]
+checksum = "abc"
 
-    if lockfile.packages.len() != packages.len() {
-        return Ok(false);
+[[packages]]
+name = "serde"
+version = "1.0.195"
+dependencies = []
+checksum = "def"
+
+[[packages]]
+name = "tokio"
+version = "1.35.1"
+dependencies = []
+checksum = "123"
+"#;
+
+    #[test]
+    fn test_migrates_v1_lockfile() {
+        let lockfile = Lockfile::parse(V1_LOCKFILE).unwrap();
+
+        assert_eq!(lockfile.version, LOCKFILE_VERSION);
+        let actix = &lockfile.packages[0];
+        assert_eq!(actix.source, PackageSource::Registry(DEFAULT_REGISTRY.to_string()));
+        assert_eq!(actix.dependencies, vec!["tokio 1.35.1", "serde 1.0.195"]);
+        assert_eq!(actix.dependency_names().collect::<Vec<_>>(), vec!["tokio", "serde"]);
     }
 
-    for package in packages {
-        let found = lockfile.packages.iter().any(|lp| {
-            lp.name == package.name && lp.version == package.version.to_string()
-        });
+    #[test]
+    fn test_v2_round_trip_and_integrity() {
+        let migrated = Lockfile::parse(V1_LOCKFILE).unwrap();
+        let written = toml::to_string_pretty(&migrated).unwrap();
 
-        if !found {
-            return Ok(false);
-        }
+        let reread = Lockfile::parse(&written).unwrap();
+        assert!(migrated.changes(&reread).is_empty());
+
+        let tampered = written.replace("1.35.1", "1.36.0");
+        assert!(Lockfile::parse(&tampered).is_err());
     }
 
-    Ok(true)
+    #[test]
+    fn test_package_source_strings() {
+        let git: PackageSource = "git+https://example.com/repo.git#abc123".parse().unwrap();
+        assert_eq!(git, PackageSource::Git {
+            url: "https://example.com/repo.git".to_string(),
+            rev: "abc123".to_string(),
+        });
+        assert_eq!(git.to_string(), "git+https://example.com/repo.git#abc123");
+        assert_eq!("path+../local".parse::<PackageSource>().unwrap(), PackageSource::Path("../local".to_string()));
+        assert!("ftp+example.com".parse::<PackageSource>().is_err());
+    }
 }
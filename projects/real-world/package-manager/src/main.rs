@@ -7,22 +7,31 @@ mod registry;
 mod installer;
 mod lockfile;
 mod models;
+mod fingerprint;
+mod sat;
+mod bench;
 
-use cli::{Cli, Commands};
+use cli::{Cli, Commands, ResolverKind};
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Install { package } => {
-            install_command(package)?;
+        Commands::Install { package, frozen } => {
+            install_command(package, cli.offline, frozen, cli.ignore_scripts, cli.resolver)?;
         }
         Commands::Update => {
-            update_command()?;
+            update_command(cli.offline, cli.ignore_scripts, cli.resolver)?;
         }
         Commands::Tree => {
             tree_command()?;
         }
+        Commands::Fingerprint => {
+            fingerprint_command()?;
+        }
+        Commands::Bench { packages, versions, deps, seed } => {
+            bench::run(bench::GraphSpec { packages, versions, deps_per_version: deps, seed })?;
+        }
         Commands::Init { name } => {
             init_command(name)?;
         }
@@ -34,29 +43,78 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-fn install_command(_package: Option<String>) -> Result<()> {
+fn install_command(
+    _package: Option<String>,
+    offline: bool,
+    frozen: bool,
+    ignore_scripts: bool,
+    resolver: ResolverKind,
+) -> Result<()> {
     use colored::Colorize;
     
     println!("{}", "🔍 Reading manifest...".cyan());
     let manifest = models::Manifest::from_file("Package.toml")?;
     
-    println!("{}", "📦 Resolving dependencies...".cyan());
-    let registry = registry::Registry::new("registry-data")?;
-    let resolved = resolver::resolve_dependencies(&manifest, &registry)?;
+    let resolved = if offline {
+        println!("{}", "📦 Resolving dependencies (offline)...".cyan());
+        let registry = registry::Registry::offline(registry::CACHE_DIR, "pkg_modules")?;
+        match resolver {
+            ResolverKind::Default => resolver::resolve_offline(&manifest, &registry)?,
+            ResolverKind::Sat => resolver::resolve_sat(&manifest, &registry)?,
+        }
+    } else {
+        println!("{}", "📦 Resolving dependencies...".cyan());
+        let registry = registry::Registry::new(registry::DEFAULT_REGISTRY)?;
+        let resolved = match resolver {
+            ResolverKind::Default => resolver::resolve_dependencies(&manifest, &registry)?,
+            ResolverKind::Sat => resolver::resolve_sat(&manifest, &registry)?,
+        };
+        registry.cache_packages(&resolved, registry::CACHE_DIR)?;
+        resolved
+    };
     
     println!("{} {} packages to install", "✓".green(), resolved.len());
     
+    if frozen {
+        println!("{}", "🧊 Verifying lock file is unchanged...".cyan());
+        lockfile::ensure_frozen(&resolved, "Package.lock")?;
+    }
+    
+    run_hook("pre-install", manifest.hooks.pre_install.as_deref(), ignore_scripts)?;
+    
     println!("{}", "📥 Installing packages...".cyan());
     installer::install_packages(&resolved)?;
     
-    println!("{}", "🔒 Generating lock file...".cyan());
-    lockfile::generate_lockfile(&resolved, "Package.lock")?;
+    if frozen {
+        println!("{}", "🔒 Lock file is up to date".cyan());
+    } else {
+        println!("{}", "🔒 Generating lock file...".cyan());
+        lockfile::generate_lockfile(&resolved, "Package.lock")?;
+    }
+    
+    run_hook("post-install", manifest.hooks.post_install.as_deref(), ignore_scripts)?;
     
     println!("{}", "✨ Installation complete!".green().bold());
     Ok(())
 }
 
-fn update_command() -> Result<()> {
+fn run_hook(name: &str, script: Option<&str>, ignore_scripts: bool) -> Result<()> {
+    use colored::Colorize;
+    
+    let Some(script) = script else {
+        return Ok(());
+    };
+    
+    if ignore_scripts {
+        println!("{} Skipping {} hook ({}) because of --ignore-scripts", "⚠".yellow(), name, script);
+        return Ok(());
+    }
+    
+    println!("{} Running {} hook: {}", "🪝".cyan(), name, script);
+    installer::run_hook(name, script)
+}
+
+fn update_command(offline: bool, ignore_scripts: bool, resolver: ResolverKind) -> Result<()> {
     use colored::Colorize;
     
     println!("{}", "🔄 Updating dependencies...".cyan());
@@ -66,7 +124,7 @@ fn update_command() -> Result<()> {
         println!("{}", "🗑️  Removed old lock file".yellow());
     }
     
-    install_command(None)?;
+    install_command(None, offline, false, ignore_scripts, resolver)?;
     Ok(())
 }
 
@@ -86,6 +144,12 @@ fn tree_command() -> Result<()> {
     Ok(())
 }
 
+fn fingerprint_command() -> Result<()> {
+    let digest = fingerprint::compute_fingerprint("Package.lock", "pkg_modules")?;
+    println!("{}", digest);
+    Ok(())
+}
+
 fn init_command(name: String) -> Result<()> {
     use colored::Colorize;
     
@@ -99,6 +163,7 @@ fn init_command(name: String) -> Result<()> {
             description: Some("A new package".to_string()),
         },
         dependencies: std::collections::HashMap::new(),
+        hooks: models::Hooks::default(),
     };
     
     let toml = toml::to_string_pretty(&manifest)?;
@@ -117,7 +182,7 @@ fn registry_command(subcommand: cli::RegistryCommands) -> Result<()> {
             println!("{}", "📚 Available packages:".cyan().bold());
             println!();
             
-            let registry = registry::Registry::new("registry-data")?;
+            let registry = registry::Registry::new(registry::DEFAULT_REGISTRY)?;
             let packages = registry.list_packages()?;
             let package_count = packages.len();
             
@@ -133,7 +198,7 @@ fn registry_command(subcommand: cli::RegistryCommands) -> Result<()> {
             println!("{} Searching for: {}", "🔍".cyan(), query.bold());
             println!();
             
-            let registry = registry::Registry::new("registry-data")?;
+            let registry = registry::Registry::new(registry::DEFAULT_REGISTRY)?;
             let results = registry.search(&query)?;
             
             for (name, info) in results {
@@ -147,7 +212,7 @@ fn registry_command(subcommand: cli::RegistryCommands) -> Result<()> {
             println!("{} Package info: {}", "ℹ️".cyan(), package.bold());
             println!();
             
-            let registry = registry::Registry::new("registry-data")?;
+            let registry = registry::Registry::new(registry::DEFAULT_REGISTRY)?;
             let info = registry.get_package_info(&package)?;
             
             println!("  Name:        {}", info.name.bold());
@@ -1,18 +1,38 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use anyhow::{Context, Result};
+use std::fmt;
+use std::str::FromStr;
+use anyhow::{Context, Result, anyhow};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Manifest {
     pub package: PackageInfo,
     #[serde(default)]
     pub dependencies: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Hooks::is_empty")]
+    pub hooks: Hooks,
+}
+
+/// Scripts run around `pkgmgr install`, given as paths relative to the
+/// manifest (e.g. `post-install = "scripts/gen.sh"`).
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct Hooks {
+    pub pre_install: Option<String>,
+    pub post_install: Option<String>,
+}
+
+impl Hooks {
+    pub fn is_empty(&self) -> bool {
+        self.pre_install.is_none() && self.post_install.is_none()
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PackageInfo {
     pub name: String,
     pub version: String,
+    #[serde(default)]
     pub authors: Vec<String>,
     pub description: Option<String>,
 }
@@ -32,6 +52,63 @@ pub struct ResolvedPackage {
     pub name: String,
     pub version: semver::Version,
     pub dependencies: Vec<String>,
+    pub source: PackageSource,
+}
+
+/// Where a package came from, written to the lockfile in Cargo's
+/// `kind+location` style (`registry+registry-data`, `git+url#rev`, `path+dir`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum PackageSource {
+    Registry(String),
+    Git { url: String, rev: String },
+    Path(String),
+}
+
+impl fmt::Display for PackageSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PackageSource::Registry(location) => write!(f, "registry+{}", location),
+            PackageSource::Git { url, rev } => write!(f, "git+{}#{}", url, rev),
+            PackageSource::Path(path) => write!(f, "path+{}", path),
+        }
+    }
+}
+
+impl FromStr for PackageSource {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (kind, location) = s
+            .split_once('+')
+            .ok_or_else(|| anyhow!("Invalid package source: {}", s))?;
+
+        match kind {
+            "registry" => Ok(PackageSource::Registry(location.to_string())),
+            "git" => {
+                let (url, rev) = location
+                    .split_once('#')
+                    .ok_or_else(|| anyhow!("Git source is missing a revision: {}", s))?;
+                Ok(PackageSource::Git { url: url.to_string(), rev: rev.to_string() })
+            }
+            "path" => Ok(PackageSource::Path(location.to_string())),
+            _ => Err(anyhow!("Unknown package source kind: {}", kind)),
+        }
+    }
+}
+
+impl From<PackageSource> for String {
+    fn from(source: PackageSource) -> Self {
+        source.to_string()
+    }
+}
+
+impl TryFrom<String> for PackageSource {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
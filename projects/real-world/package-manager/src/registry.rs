@@ -1,10 +1,14 @@
 use std::collections::HashMap;
 use std::path::Path;
 use anyhow::{Context, Result, anyhow};
-use crate::models::RegistryPackage;
+use crate::models::{Manifest, PackageSource, RegistryPackage, ResolvedPackage};
+
+pub const CACHE_DIR: &str = ".pkgmgr-cache";
+pub const DEFAULT_REGISTRY: &str = "registry-data";
 
 pub struct Registry {
     path: String,
+    source: String,
     packages: HashMap<String, Vec<RegistryPackage>>,
 }
 
@@ -12,6 +16,7 @@ impl Registry {
     pub fn new(path: &str) -> Result<Self> {
         let mut registry = Self {
             path: path.to_string(),
+            source: path.to_string(),
             packages: HashMap::new(),
         };
         
@@ -19,6 +24,44 @@ impl Registry {
         Ok(registry)
     }
 
+    /// Builds a registry from data that is already on disk: the cache filled by
+    /// previous online installs plus whatever is installed in `modules_path`.
+    pub fn offline(cache_path: &str, modules_path: &str) -> Result<Self> {
+        let mut registry = Self {
+            path: cache_path.to_string(),
+            source: DEFAULT_REGISTRY.to_string(),
+            packages: HashMap::new(),
+        };
+
+        registry.load_packages()?;
+        registry.load_installed(modules_path)?;
+        registry.sort_versions();
+        Ok(registry)
+    }
+
+    /// Builds a registry from packages held in memory, e.g. the generated
+    /// graphs used by `pkgmgr bench`.
+    pub fn from_packages(source: &str, packages: Vec<RegistryPackage>) -> Self {
+        let mut registry = Self {
+            path: source.to_string(),
+            source: source.to_string(),
+            packages: HashMap::new(),
+        };
+
+        for package in packages {
+            registry.packages.entry(package.name.clone()).or_default().push(package);
+        }
+
+        registry.sort_versions();
+        registry
+    }
+
+    /// The registry that packages resolved from here are attributed to. An
+    /// offline registry reports the default registry its cache was filled from.
+    pub fn source(&self) -> PackageSource {
+        PackageSource::Registry(self.source.clone())
+    }
+
     fn load_packages(&mut self) -> Result<()> {
         let registry_path = Path::new(&self.path);
         
@@ -36,11 +79,49 @@ impl Registry {
                 
                 self.packages
                     .entry(package.name.clone())
-                    .or_insert_with(Vec::new)
+                    .or_default()
                     .push(package);
             }
         }
 
+        self.sort_versions();
+        Ok(())
+    }
+
+    fn load_installed(&mut self, modules_path: &str) -> Result<()> {
+        let modules_path = Path::new(modules_path);
+
+        if !modules_path.exists() {
+            return Ok(());
+        }
+
+        for entry in std::fs::read_dir(modules_path)? {
+            let manifest_path = entry?.path().join("Package.toml");
+
+            if !manifest_path.is_file() {
+                continue;
+            }
+
+            let manifest = Manifest::from_file(&manifest_path.to_string_lossy())?;
+            let versions = self.packages.entry(manifest.package.name.clone()).or_default();
+
+            if versions.iter().any(|p| p.version == manifest.package.version) {
+                continue;
+            }
+
+            versions.push(RegistryPackage {
+                name: manifest.package.name,
+                version: manifest.package.version,
+                authors: manifest.package.authors,
+                description: manifest.package.description,
+                dependencies: manifest.dependencies,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn sort_versions(&mut self) {
         for versions in self.packages.values_mut() {
             versions.sort_by(|a, b| {
                 let v_a = semver::Version::parse(&a.version).unwrap();
@@ -48,10 +129,33 @@ impl Registry {
                 v_b.cmp(&v_a)
             });
         }
+    }
+
+    /// Copies the registry entries of resolved packages into the local cache
+    /// so later `--offline` runs can resolve them without the network.
+    pub fn cache_packages(&self, packages: &[ResolvedPackage], cache_path: &str) -> Result<()> {
+        std::fs::create_dir_all(cache_path)?;
+
+        for package in packages {
+            let version = package.version.to_string();
+            let entry = self.packages
+                .get(&package.name)
+                .and_then(|versions| versions.iter().find(|p| p.version == version));
+
+            if let Some(entry) = entry {
+                let file = Path::new(cache_path).join(format!("{}-{}.toml", entry.name, entry.version));
+                std::fs::write(file, toml::to_string_pretty(entry)?)?;
+            }
+        }
 
         Ok(())
     }
 
+    /// All known versions of a package, newest first.
+    pub fn versions(&self, name: &str) -> &[RegistryPackage] {
+        self.packages.get(name).map(Vec::as_slice).unwrap_or_default()
+    }
+
     pub fn get_package(&self, name: &str, version_req: &str) -> Result<RegistryPackage> {
         let versions = self.packages
             .get(name)
@@ -1,15 +1,53 @@
 use std::collections::{HashMap, HashSet, VecDeque};
 use anyhow::{Context, Result, anyhow};
 use petgraph::graph::{DiGraph, NodeIndex};
-use petgraph::visit::Bfs;
 use colored::Colorize;
 
-use crate::models::{Manifest, ResolvedPackage};
+use crate::models::{Manifest, RegistryPackage, ResolvedPackage};
 use crate::registry::Registry;
 use crate::lockfile::Lockfile;
+use crate::sat::{Lit, Solver, Stats, Var};
 
 pub fn resolve_dependencies(manifest: &Manifest, registry: &Registry) -> Result<Vec<ResolvedPackage>> {
+    let (resolved, mut unresolved) = walk_dependencies(manifest, registry)?;
+
+    if !unresolved.is_empty() {
+        let (name, version_req, err) = unresolved.remove(0);
+        return Err(err.context(format!("Failed to resolve: {} {}", name, version_req)));
+    }
+
+    check_for_cycles(&resolved)?;
+    Ok(resolved)
+}
+
+/// Resolves against local data only. Rather than stopping at the first miss,
+/// the whole graph is walked so the error lists every package that would
+/// have to be fetched from the network.
+pub fn resolve_offline(manifest: &Manifest, registry: &Registry) -> Result<Vec<ResolvedPackage>> {
+    let (resolved, unresolved) = walk_dependencies(manifest, registry)?;
+
+    if !unresolved.is_empty() {
+        let mut missing: Vec<String> = unresolved
+            .iter()
+            .map(|(name, version_req, _)| format!("  - {} {}", name, version_req))
+            .collect();
+        missing.sort();
+
+        return Err(anyhow!(
+            "Cannot resolve offline; these packages are not cached or installed and would require network access:\n{}",
+            missing.join("\n")
+        ));
+    }
+
+    check_for_cycles(&resolved)?;
+    Ok(resolved)
+}
+
+type Unresolved = (String, String, anyhow::Error);
+
+fn walk_dependencies(manifest: &Manifest, registry: &Registry) -> Result<(Vec<ResolvedPackage>, Vec<Unresolved>)> {
     let mut resolved = Vec::new();
+    let mut unresolved = Vec::new();
     let mut visited = HashSet::new();
     let mut queue = VecDeque::new();
 
@@ -25,8 +63,13 @@ pub fn resolve_dependencies(manifest: &Manifest, registry: &Registry) -> Result<
         }
         visited.insert(key);
 
-        let package = registry.get_package(&name, &version_req)
-            .context(format!("Failed to resolve: {} {}", name, version_req))?;
+        let package = match registry.get_package(&name, &version_req) {
+            Ok(package) => package,
+            Err(err) => {
+                unresolved.push((name, version_req, err));
+                continue;
+            }
+        };
 
         let version = semver::Version::parse(&package.version)?;
         let mut deps = Vec::new();
@@ -35,43 +78,169 @@ pub fn resolve_dependencies(manifest: &Manifest, registry: &Registry) -> Result<
             deps.push(dep_name.clone());
             queue.push_back((dep_name.clone(), dep_version.clone()));
         }
+        deps.sort();
 
         resolved.push(ResolvedPackage {
             name: name.clone(),
             version,
             dependencies: deps,
+            source: registry.source(),
         });
     }
 
-    check_for_cycles(&resolved)?;
+    Ok((resolved, unresolved))
+}
+
+/// Resolves by encoding the registry as a SAT problem: one variable per
+/// candidate version, at most one version per package, and each selected
+/// version implying one of the versions its dependencies accept. Unlike the
+/// default walk, this picks a single version per package that satisfies
+/// every requirement at once, and fails only if no such set exists.
+pub fn resolve_sat(manifest: &Manifest, registry: &Registry) -> Result<Vec<ResolvedPackage>> {
+    let (resolved, _) = resolve_sat_with_stats(manifest, registry)?;
     Ok(resolved)
 }
 
-fn check_for_cycles(packages: &[ResolvedPackage]) -> Result<()> {
-    let mut graph = HashMap::new();
-    let mut all_nodes = HashSet::new();
+pub fn resolve_sat_with_stats<'a>(manifest: &'a Manifest, registry: &'a Registry) -> Result<(Vec<ResolvedPackage>, Stats)> {
+    let mut solver = Solver::new();
+    let mut candidates: HashMap<&str, Vec<(Var, &RegistryPackage, semver::Version)>> = HashMap::new();
+    let mut order: Vec<&str> = Vec::new();
+
+    let mut roots: Vec<(&String, &String)> = manifest.dependencies.iter().collect();
+    roots.sort();
+
+    // Every version of every package reachable from the manifest becomes a
+    // variable, newest first so requirements prefer newer releases.
+    let mut queue: VecDeque<&str> = roots.iter().map(|(name, _)| name.as_str()).collect();
+    while let Some(name) = queue.pop_front() {
+        if candidates.contains_key(name) {
+            continue;
+        }
+
+        let mut versions = Vec::new();
+        for package in registry.versions(name) {
+            versions.push((solver.new_var(), package, semver::Version::parse(&package.version)?));
+        }
+
+        for (_, package, _) in &versions {
+            for dep_name in package.dependencies.keys() {
+                queue.push_back(dep_name);
+            }
+        }
+
+        order.push(name);
+        candidates.insert(name, versions);
+    }
+
+    // Many versions share the same requirement string, so each distinct
+    // (package, requirement) pair is matched only once.
+    let mut matches: HashMap<(&str, &str), Vec<Lit>> = HashMap::new();
+    let mut matching = |name: &'a str, version_req: &'a str| -> Result<Vec<Lit>> {
+        if let Some(lits) = matches.get(&(name, version_req)) {
+            return Ok(lits.clone());
+        }
+        let req = semver::VersionReq::parse(version_req)
+            .context(format!("Invalid version requirement for {}: {}", name, version_req))?;
+        let mut lits = Vec::new();
+        for (var, _, version) in candidates.get(name).map(Vec::as_slice).unwrap_or_default() {
+            if req.matches(version) {
+                lits.push(Lit::pos(*var));
+            }
+        }
+        matches.insert((name, version_req), lits.clone());
+        Ok(lits)
+    };
+
+    for (name, version_req) in &roots {
+        let lits = matching(name, version_req)?;
+        if lits.is_empty() {
+            let err = registry.get_package(name, version_req).err()
+                .unwrap_or_else(|| anyhow!("No matching version found for {} {}", name, version_req));
+            return Err(err.context(format!("Failed to resolve: {} {}", name, version_req)));
+        }
+        solver.add_requirement(None, lits);
+    }
+
+    let mut requirements = Vec::new();
+    for name in &order {
+        let versions = &candidates[name];
+        let vars: Vec<Var> = versions.iter().map(|(var, _, _)| *var).collect();
+        solver.at_most_one(&vars);
+
+        for (var, package, _) in versions {
+            let mut deps: Vec<(&String, &String)> = package.dependencies.iter().collect();
+            deps.sort();
+            for (dep_name, dep_req) in deps {
+                requirements.push((*var, matching(dep_name, dep_req)?));
+            }
+        }
+    }
+    for (var, lits) in requirements {
+        solver.add_requirement(Some(Lit::pos(var)), lits);
+    }
+
+    let model = solver.solve().ok_or_else(|| {
+        let names: Vec<String> = roots.iter().map(|(name, req)| format!("{} {}", name, req)).collect();
+        anyhow!(
+            "No set of package versions satisfies all requirements (at most one version per package):\n  {}",
+            names.join("\n  ")
+        )
+    })?;
 
-    for pkg in packages {
-        all_nodes.insert(pkg.name.clone());
-        graph.insert(pkg.name.clone(), pkg.dependencies.clone());
+    let mut resolved = Vec::new();
+    for name in &order {
+        let selected = candidates[name].iter().find(|(var, _, _)| model[*var]);
+        if let Some((_, package, version)) = selected {
+            let mut deps: Vec<String> = package.dependencies.keys().cloned().collect();
+            deps.sort();
+            resolved.push(ResolvedPackage {
+                name: package.name.clone(),
+                version: version.clone(),
+                dependencies: deps,
+                source: registry.source(),
+            });
+        }
     }
 
-    for start in &all_nodes {
-        let mut visited = HashSet::new();
-        let mut stack = vec![start.clone()];
+    check_for_cycles(&resolved)?;
+    Ok((resolved, solver.stats))
+}
+
+fn check_for_cycles(packages: &[ResolvedPackage]) -> Result<()> {
+    let graph: HashMap<&str, &[String]> = packages
+        .iter()
+        .map(|pkg| (pkg.name.as_str(), pkg.dependencies.as_slice()))
+        .collect();
+
+    // Depth-first search that only reports a cycle when it reaches a package
+    // still on the current path; shared dependencies are fine.
+    let mut finished: HashSet<&str> = HashSet::new();
+    for &start in graph.keys() {
+        if finished.contains(start) {
+            continue;
+        }
+
+        let mut on_path: HashSet<&str> = HashSet::new();
+        let mut stack: Vec<(&str, usize)> = vec![(start, 0)];
+        on_path.insert(start);
 
-        while let Some(node) = stack.pop() {
-            if !visited.insert(node.clone()) {
-                return Err(anyhow!("Circular dependency detected involving: {}", node));
+        while let Some((node, next)) = stack.pop() {
+            let deps = graph[node];
+            if next == deps.len() {
+                on_path.remove(node);
+                finished.insert(node);
+                continue;
             }
+            stack.push((node, next + 1));
 
-            if let Some(deps) = graph.get(&node) {
-                for dep in deps {
-                    if all_nodes.contains(dep) {
-                        stack.push(dep.clone());
-                    }
-                }
+            let dep = deps[next].as_str();
+            if !graph.contains_key(dep) || finished.contains(dep) {
+                continue;
+            }
+            if !on_path.insert(dep) {
+                return Err(anyhow!("Circular dependency detected involving: {}", dep));
             }
+            stack.push((dep, 0));
         }
     }
 
@@ -90,7 +259,7 @@ pub fn build_dependency_graph(lockfile: &Lockfile) -> Result<DiGraph<String, ()>
 
     for pkg in &lockfile.packages {
         if let Some(&from_node) = nodes.get(&pkg.name) {
-            for dep in &pkg.dependencies {
+            for dep in pkg.dependency_names() {
                 if let Some(&to_node) = nodes.get(dep) {
                     graph.add_edge(from_node, to_node, ());
                 }
@@ -149,3 +318,87 @@ fn print_node(
         print_node(graph, child, depth + 1, visited);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Hooks, PackageInfo};
+
+    fn package(name: &str, version: &str, deps: &[(&str, &str)]) -> RegistryPackage {
+        RegistryPackage {
+            name: name.to_string(),
+            version: version.to_string(),
+            authors: Vec::new(),
+            description: None,
+            dependencies: deps.iter().map(|(n, r)| (n.to_string(), r.to_string())).collect(),
+        }
+    }
+
+    fn manifest(deps: &[(&str, &str)]) -> Manifest {
+        Manifest {
+            package: PackageInfo {
+                name: "app".to_string(),
+                version: "0.1.0".to_string(),
+                authors: Vec::new(),
+                description: None,
+            },
+            dependencies: deps.iter().map(|(n, r)| (n.to_string(), r.to_string())).collect(),
+            hooks: Hooks::default(),
+        }
+    }
+
+    #[test]
+    fn test_sat_backtracks_to_a_shared_version() {
+        // The newest `web` needs log 2.x, but `db` only works with log 1.x,
+        // so the older `web` is the only consistent choice.
+        let registry = Registry::from_packages("test", vec![
+            package("web", "2.0.0", &[("log", "^2")]),
+            package("web", "1.0.0", &[("log", "^1")]),
+            package("db", "1.0.0", &[("log", "^1")]),
+            package("log", "2.0.0", &[]),
+            package("log", "1.5.0", &[]),
+        ]);
+        let manifest = manifest(&[("web", "*"), ("db", "*")]);
+
+        let greedy = resolve_dependencies(&manifest, &registry).unwrap();
+        assert_eq!(greedy.iter().filter(|p| p.name == "log").count(), 2);
+
+        let resolved = resolve_sat(&manifest, &registry).unwrap();
+        let versions: HashMap<&str, String> = resolved
+            .iter()
+            .map(|p| (p.name.as_str(), p.version.to_string()))
+            .collect();
+        assert_eq!(versions.len(), 3);
+        assert_eq!(versions["web"], "1.0.0");
+        assert_eq!(versions["log"], "1.5.0");
+
+        let conflicting = Registry::from_packages("test", vec![
+            package("web", "2.0.0", &[("log", "^2")]),
+            package("db", "1.0.0", &[("log", "^1")]),
+            package("log", "2.0.0", &[]),
+            package("log", "1.5.0", &[]),
+        ]);
+        assert!(resolve_sat(&manifest, &conflicting).is_err());
+    }
+
+    #[test]
+    fn test_shared_dependencies_are_not_cycles() {
+        let resolved = |name: &str, deps: &[&str]| ResolvedPackage {
+            name: name.to_string(),
+            version: semver::Version::new(1, 0, 0),
+            dependencies: deps.iter().map(|d| d.to_string()).collect(),
+            source: crate::models::PackageSource::Registry("test".to_string()),
+        };
+
+        let diamond = [
+            resolved("a", &["b", "c"]),
+            resolved("b", &["d"]),
+            resolved("c", &["d"]),
+            resolved("d", &[]),
+        ];
+        assert!(check_for_cycles(&diamond).is_ok());
+
+        let cycle = [resolved("a", &["b"]), resolved("b", &["c"]), resolved("c", &["a"])];
+        assert!(check_for_cycles(&cycle).is_err());
+    }
+}
@@ -0,0 +1,456 @@
+//! A small CDCL SAT solver used by `--resolver=sat`.
+//!
+//! Clauses are propagated with two watched literals; conflicts are analysed
+//! to the first unique implication point, the learned clause is kept, and the
+//! search jumps back to the second-highest level in that clause instead of
+//! undoing one decision at a time. Branching is driven by *requirements*
+//! ("if `trigger` holds, one of `candidates` must"): the solver satisfies the
+//! first open requirement with its first unassigned candidate, so callers
+//! control preference order (the resolver lists newest versions first).
+
+pub type Var = usize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lit(usize);
+
+impl Lit {
+    pub fn pos(var: Var) -> Self {
+        Lit(var << 1)
+    }
+
+    pub fn neg(var: Var) -> Self {
+        Lit((var << 1) | 1)
+    }
+
+    pub fn var(self) -> Var {
+        self.0 >> 1
+    }
+
+    fn is_neg(self) -> bool {
+        self.0 & 1 == 1
+    }
+
+    fn negate(self) -> Self {
+        Lit(self.0 ^ 1)
+    }
+
+    fn index(self) -> usize {
+        self.0
+    }
+}
+
+struct Requirement {
+    candidates: Vec<Lit>,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Stats {
+    pub decisions: usize,
+    pub conflicts: usize,
+    pub propagations: usize,
+    pub learned: usize,
+}
+
+#[derive(Default)]
+pub struct Solver {
+    clauses: Vec<Vec<Lit>>,
+    watches: Vec<Vec<usize>>,
+    requirements: Vec<Requirement>,
+    unconditional: Vec<usize>,
+    triggered_by: Vec<Vec<usize>>,
+    units: Vec<Lit>,
+    values: Vec<Option<bool>>,
+    levels: Vec<usize>,
+    reasons: Vec<Option<usize>>,
+    trail: Vec<Lit>,
+    trail_lim: Vec<usize>,
+    queue_head: usize,
+    // Between backtracks the trail only grows, so requirements triggered
+    // before `branch_head` and variables before `free_head` stay settled.
+    branch_head: usize,
+    free_head: usize,
+    unsat: bool,
+    pub stats: Stats,
+}
+
+impl Solver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn new_var(&mut self) -> Var {
+        self.values.push(None);
+        self.levels.push(0);
+        self.reasons.push(None);
+        self.watches.push(Vec::new());
+        self.watches.push(Vec::new());
+        self.triggered_by.push(Vec::new());
+        self.triggered_by.push(Vec::new());
+        self.values.len() - 1
+    }
+
+    pub fn num_vars(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn add_clause(&mut self, mut lits: Vec<Lit>) {
+        lits.sort_by_key(|l| l.index());
+        lits.dedup();
+        if lits.windows(2).any(|w| w[0].var() == w[1].var()) {
+            return; // tautology
+        }
+
+        match lits.len() {
+            0 => self.unsat = true,
+            1 => self.units.push(lits[0]),
+            _ => {
+                self.attach(lits);
+            }
+        }
+    }
+
+    /// Adds `trigger -> (c1 | c2 | ...)` and registers it for branching.
+    /// With no trigger the requirement always applies.
+    pub fn add_requirement(&mut self, trigger: Option<Lit>, candidates: Vec<Lit>) {
+        let mut clause: Vec<Lit> = trigger.map(Lit::negate).into_iter().collect();
+        clause.extend(&candidates);
+        self.add_clause(clause);
+
+        let index = self.requirements.len();
+        match trigger {
+            Some(lit) => self.triggered_by[lit.index()].push(index),
+            None => self.unconditional.push(index),
+        }
+        self.requirements.push(Requirement { candidates });
+    }
+
+    /// At most one of `vars` is true: pairwise for short lists, otherwise
+    /// the sequential counter encoding, which stays linear in size.
+    pub fn at_most_one(&mut self, vars: &[Var]) {
+        if vars.len() <= 6 {
+            for (i, &a) in vars.iter().enumerate() {
+                for &b in &vars[i + 1..] {
+                    self.add_clause(vec![Lit::neg(a), Lit::neg(b)]);
+                }
+            }
+            return;
+        }
+
+        let counters: Vec<Var> = (0..vars.len() - 1).map(|_| self.new_var()).collect();
+        self.add_clause(vec![Lit::neg(vars[0]), Lit::pos(counters[0])]);
+        for i in 1..vars.len() - 1 {
+            self.add_clause(vec![Lit::neg(vars[i]), Lit::pos(counters[i])]);
+            self.add_clause(vec![Lit::neg(counters[i - 1]), Lit::pos(counters[i])]);
+            self.add_clause(vec![Lit::neg(vars[i]), Lit::neg(counters[i - 1])]);
+        }
+        let last = vars.len() - 1;
+        self.add_clause(vec![Lit::neg(vars[last]), Lit::neg(counters[last - 1])]);
+    }
+
+    /// Returns a satisfying assignment indexed by variable, or `None` if
+    /// the clauses are unsatisfiable.
+    pub fn solve(&mut self) -> Option<Vec<bool>> {
+        if self.unsat {
+            return None;
+        }
+
+        for unit in std::mem::take(&mut self.units) {
+            match self.value(unit) {
+                Some(true) => {}
+                Some(false) => return None,
+                None => self.enqueue(unit, None),
+            }
+        }
+
+        loop {
+            if let Some(conflict) = self.propagate() {
+                self.stats.conflicts += 1;
+                if self.trail_lim.is_empty() {
+                    return None;
+                }
+
+                let (learnt, level) = self.analyze(conflict);
+                self.backtrack(level);
+                let asserting = learnt[0];
+                if learnt.len() == 1 {
+                    self.enqueue(asserting, None);
+                } else {
+                    let index = self.attach(learnt);
+                    self.stats.learned += 1;
+                    self.enqueue(asserting, Some(index));
+                }
+            } else {
+                match self.pick_branch() {
+                    Some(lit) => {
+                        self.stats.decisions += 1;
+                        self.trail_lim.push(self.trail.len());
+                        self.enqueue(lit, None);
+                    }
+                    None => {
+                        return Some(self.values.iter().map(|v| v.unwrap_or(false)).collect());
+                    }
+                }
+            }
+        }
+    }
+
+    fn attach(&mut self, lits: Vec<Lit>) -> usize {
+        let index = self.clauses.len();
+        self.watches[lits[0].index()].push(index);
+        self.watches[lits[1].index()].push(index);
+        self.clauses.push(lits);
+        index
+    }
+
+    fn value(&self, lit: Lit) -> Option<bool> {
+        self.values[lit.var()].map(|v| v != lit.is_neg())
+    }
+
+    fn enqueue(&mut self, lit: Lit, reason: Option<usize>) {
+        let var = lit.var();
+        self.values[var] = Some(!lit.is_neg());
+        self.levels[var] = self.trail_lim.len();
+        self.reasons[var] = reason;
+        self.trail.push(lit);
+    }
+
+    /// Unit propagation; returns the index of a falsified clause on conflict.
+    fn propagate(&mut self) -> Option<usize> {
+        while self.queue_head < self.trail.len() {
+            let false_lit = self.trail[self.queue_head].negate();
+            self.queue_head += 1;
+            self.stats.propagations += 1;
+
+            let mut watching = std::mem::take(&mut self.watches[false_lit.index()]);
+            let mut i = 0;
+            while i < watching.len() {
+                let index = watching[i];
+                let clause = &mut self.clauses[index];
+                if clause[0] == false_lit {
+                    clause.swap(0, 1);
+                }
+
+                let first = clause[0];
+                if self.values[first.var()].map(|v| v != first.is_neg()) == Some(true) {
+                    i += 1;
+                    continue;
+                }
+
+                let replacement = (2..clause.len()).find(|&k| {
+                    let lit = clause[k];
+                    self.values[lit.var()].map(|v| v != lit.is_neg()) != Some(false)
+                });
+
+                if let Some(k) = replacement {
+                    clause.swap(1, k);
+                    let new_watch = clause[1];
+                    self.watches[new_watch.index()].push(index);
+                    watching.swap_remove(i);
+                    continue;
+                }
+
+                if self.value(first) == Some(false) {
+                    self.watches[false_lit.index()] = watching;
+                    self.queue_head = self.trail.len();
+                    return Some(index);
+                }
+
+                self.enqueue(first, Some(index));
+                i += 1;
+            }
+            self.watches[false_lit.index()] = watching;
+        }
+
+        None
+    }
+
+    /// First-UIP conflict analysis. Returns the learned clause with the
+    /// asserting literal first and the highest remaining level second,
+    /// plus the level to jump back to.
+    fn analyze(&mut self, conflict: usize) -> (Vec<Lit>, usize) {
+        let current_level = self.trail_lim.len();
+        let mut seen = vec![false; self.num_vars()];
+        let mut learnt = vec![Lit(0)];
+        let mut pending = 0;
+        let mut index = self.trail.len();
+        let mut clause = conflict;
+        let mut skip_first = false;
+
+        let uip = loop {
+            for (position, &lit) in self.clauses[clause].iter().enumerate() {
+                if skip_first && position == 0 {
+                    continue;
+                }
+                let var = lit.var();
+                if seen[var] || self.levels[var] == 0 {
+                    continue;
+                }
+                seen[var] = true;
+                if self.levels[var] == current_level {
+                    pending += 1;
+                } else {
+                    learnt.push(lit);
+                }
+            }
+
+            loop {
+                index -= 1;
+                if seen[self.trail[index].var()] {
+                    break;
+                }
+            }
+            let lit = self.trail[index];
+            pending -= 1;
+            if pending == 0 {
+                break lit;
+            }
+            clause = self.reasons[lit.var()].expect("implied literal has a reason");
+            skip_first = true;
+        };
+        learnt[0] = uip.negate();
+
+        let mut level = 0;
+        if learnt.len() > 1 {
+            let (max_at, _) = learnt
+                .iter()
+                .enumerate()
+                .skip(1)
+                .max_by_key(|(_, lit)| self.levels[lit.var()])
+                .unwrap();
+            learnt.swap(1, max_at);
+            level = self.levels[learnt[1].var()];
+        }
+
+        (learnt, level)
+    }
+
+    fn backtrack(&mut self, level: usize) {
+        if self.trail_lim.len() <= level {
+            return;
+        }
+        let keep = self.trail_lim[level];
+        for lit in self.trail.drain(keep..) {
+            self.values[lit.var()] = None;
+            self.reasons[lit.var()] = None;
+        }
+        self.trail_lim.truncate(level);
+        self.queue_head = self.trail.len();
+        self.branch_head = 0;
+        self.free_head = 0;
+    }
+
+    /// The first candidate of the first open requirement, or failing that
+    /// any unassigned variable set to false.
+    fn pick_branch(&mut self) -> Option<Lit> {
+        for i in 0..self.unconditional.len() {
+            if let Some(lit) = self.open_candidate(self.unconditional[i]) {
+                return Some(lit);
+            }
+        }
+
+        while self.branch_head < self.trail.len() {
+            let trigger = self.trail[self.branch_head];
+            for &requirement in &self.triggered_by[trigger.index()] {
+                if let Some(lit) = self.open_candidate(requirement) {
+                    return Some(lit);
+                }
+            }
+            self.branch_head += 1;
+        }
+
+        while self.free_head < self.values.len() {
+            if self.values[self.free_head].is_none() {
+                return Some(Lit::neg(self.free_head));
+            }
+            self.free_head += 1;
+        }
+        None
+    }
+
+    fn open_candidate(&self, requirement: usize) -> Option<Lit> {
+        let candidates = &self.requirements[requirement].candidates;
+        if candidates.iter().any(|&c| self.value(c) == Some(true)) {
+            return None;
+        }
+        candidates.iter().copied().find(|&c| self.value(c).is_none())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(clauses: &[Vec<Lit>], model: &[bool]) -> bool {
+        clauses.iter().all(|clause| {
+            clause.iter().any(|&lit| model[lit.var()] != lit.is_neg())
+        })
+    }
+
+    #[test]
+    fn test_requirements_prefer_first_candidate() {
+        let mut solver = Solver::new();
+        let vars: Vec<Var> = (0..8).map(|_| solver.new_var()).collect();
+        solver.at_most_one(&vars);
+        solver.add_requirement(None, vars.iter().map(|&v| Lit::pos(v)).collect());
+        // The first two are ruled out, so the third must be chosen.
+        solver.add_clause(vec![Lit::neg(vars[0])]);
+        solver.add_requirement(Some(Lit::pos(vars[1])), vec![]);
+
+        let model = solver.solve().unwrap();
+        let chosen: Vec<Var> = vars.iter().copied().filter(|&v| model[v]).collect();
+        assert_eq!(chosen, vec![vars[2]]);
+    }
+
+    #[test]
+    fn test_pigeonhole_is_unsat() {
+        // Four pigeons, three holes.
+        let mut solver = Solver::new();
+        let p: Vec<Vec<Var>> = (0..4).map(|_| (0..3).map(|_| solver.new_var()).collect()).collect();
+        for pigeon in &p {
+            solver.add_requirement(None, pigeon.iter().map(|&v| Lit::pos(v)).collect());
+        }
+        for hole in 0..3 {
+            let column: Vec<Var> = p.iter().map(|pigeon| pigeon[hole]).collect();
+            solver.at_most_one(&column);
+        }
+
+        assert!(solver.solve().is_none());
+        assert!(solver.stats.conflicts > 0);
+    }
+
+    #[test]
+    fn test_random_3sat_agrees_with_brute_force() {
+        let mut seed = 0x9e3779b97f4a7c15u64;
+        let mut next = || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed
+        };
+
+        for _ in 0..40 {
+            let mut solver = Solver::new();
+            let vars: Vec<Var> = (0..10).map(|_| solver.new_var()).collect();
+            let mut clauses = Vec::new();
+            for _ in 0..45 {
+                let clause: Vec<Lit> = (0..3)
+                    .map(|_| {
+                        let var = vars[(next() % 10) as usize];
+                        if next() % 2 == 0 { Lit::pos(var) } else { Lit::neg(var) }
+                    })
+                    .collect();
+                clauses.push(clause.clone());
+                solver.add_clause(clause);
+            }
+
+            let satisfiable = (0..1u32 << 10).any(|bits| {
+                let model: Vec<bool> = (0..10).map(|i| bits & (1 << i) != 0).collect();
+                check(&clauses, &model)
+            });
+            match solver.solve() {
+                Some(model) => assert!(check(&clauses, &model)),
+                None => assert!(!satisfiable),
+            }
+        }
+    }
+}